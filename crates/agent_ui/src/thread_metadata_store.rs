@@ -2396,6 +2396,7 @@ mod tests {
             Some(RemoteConnectionOptions::Wsl(WslConnectionOptions {
                 distro_name: "Ubuntu".to_string(),
                 user: Some("anth".to_string()),
+                env: Default::default(),
             }))
         );
     }
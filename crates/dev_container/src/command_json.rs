@@ -4,20 +4,30 @@ use async_trait::async_trait;
 use serde::Deserialize;
 use util::command::Command;
 
-use crate::devcontainer_api::DevContainerError;
+use crate::{devcontainer_api::DevContainerError, docker::DockerHost};
 
-pub(crate) struct DefaultCommandRunner;
+/// Runs commands on `host`, the way the docker CLI they call is reached.
+pub(crate) struct DefaultCommandRunner {
+    host: DockerHost,
+}
 
 impl DefaultCommandRunner {
-    pub(crate) fn new() -> Self {
-        Self
+    pub(crate) fn new(host: DockerHost) -> Self {
+        Self { host }
     }
 }
 
 #[async_trait]
 impl CommandRunner for DefaultCommandRunner {
     async fn run_command(&self, command: &mut Command) -> Result<Output, std::io::Error> {
-        command.output().await
+        if self.host == DockerHost::Local {
+            return command.output().await;
+        }
+        let mut host_command = self.host.command(&command.get_program().to_string_lossy());
+        for arg in command.get_args() {
+            host_command.arg(self.host.host_arg(&arg.to_string_lossy()));
+        }
+        host_command.output().await
     }
 }
 
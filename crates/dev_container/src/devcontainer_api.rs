@@ -6,20 +6,22 @@ use std::{
 };
 
 use futures::TryFutureExt;
-use gpui::{AsyncWindowContext, Entity};
+use gpui::{AsyncWindowContext, Entity, Task};
 use project::Worktree;
 use serde::Deserialize;
 use settings::{DevContainerConnection, infer_json_indent_size, replace_value_in_json_text};
-use util::rel_path::RelPath;
+use util::{rel_path::RelPath, shell::ShellKind};
 use walkdir::WalkDir;
 use workspace::Workspace;
-use worktree::Snapshot;
+use worktree::{ChildEntriesOptions, Snapshot, WorktreeId};
 
 use crate::{
     DevContainerContext, DevContainerFeature, DevContainerTemplate,
     devcontainer_json::DevContainer,
     devcontainer_manifest::{read_devcontainer_configuration, spawn_dev_container},
-    devcontainer_templates_repository, get_latest_oci_manifest, get_oci_token, ghcr_registry,
+    devcontainer_templates_repository,
+    docker::DockerHost,
+    get_latest_oci_manifest, get_oci_token, ghcr_registry,
     oci::download_oci_tarball,
 };
 
@@ -28,8 +30,27 @@ use crate::{
 pub struct DevContainerConfig {
     /// Display name for the configuration (subfolder name or "default")
     pub name: String,
-    /// Relative path to the devcontainer.json file from the project root
+    /// Relative path to the devcontainer.json file from the project root, or
+    /// from the package directory for a package's configuration
     pub config_path: PathBuf,
+    /// The package the configuration belongs to, when it was found outside
+    /// the project root
+    pub package: Option<DevContainerPackage>,
+}
+
+/// A directory with its own devcontainer configuration other than the project
+/// root, such as a package in a monorepo or the root of another worktree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DevContainerPackage {
+    pub worktree_id: WorktreeId,
+    /// The directory, relative to the worktree root
+    pub path: Arc<RelPath>,
+    /// The directory on disk, which is used as the workspace folder of
+    /// containers started from the package's configurations
+    pub abs_path: Arc<Path>,
+    /// The directory as shown in the picker, prefixed with the worktree's
+    /// name when the project has several
+    pub label: String,
 }
 
 impl DevContainerConfig {
@@ -37,6 +58,7 @@ impl DevContainerConfig {
         Self {
             name: "default".to_string(),
             config_path: PathBuf::from(".devcontainer/devcontainer.json"),
+            package: None,
         }
     }
 
@@ -44,6 +66,7 @@ impl DevContainerConfig {
         Self {
             name: "root".to_string(),
             config_path: PathBuf::from(".devcontainer.json"),
+            package: None,
         }
     }
 }
@@ -69,6 +92,12 @@ pub(crate) struct DevContainerApply {
 pub enum DevContainerError {
     CommandFailed(String),
     DockerNotAvailable,
+    /// Docker wasn't found on Windows, but a docker or podman daemon answers
+    /// in several running WSL distros, so the user has to pick one.
+    DockerInSeveralWslDistros(Vec<WslDockerDaemon>),
+    /// Docker wasn't found on Windows, nor in any of these running WSL
+    /// distros.
+    DockerNotFoundInWsl(Vec<String>),
     ContainerNotValid(String),
     DevContainerTemplateApplyFailed(String),
     DevContainerScriptsFailed,
@@ -84,6 +113,97 @@ pub enum DevContainerError {
     /// expects those labels to be unique per project, so Zed can't choose
     /// which one to connect to. The user must remove the duplicate(s).
     MultipleMatchingContainers(Vec<String>),
+    /// A failure after the environment checks passed, along with the container
+    /// CLI that was in use so it can be included in bug reports.
+    WithContainerCli(Box<DevContainerError>, ContainerCliInfo),
+}
+
+/// A docker or podman daemon that answers inside a running WSL distro.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WslDockerDaemon {
+    pub distro: String,
+    pub use_podman: bool,
+}
+
+impl WslDockerDaemon {
+    pub fn program(&self) -> &'static str {
+        if self.use_podman { "podman" } else { "docker" }
+    }
+}
+
+impl Display for WslDockerDaemon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} in {}", self.program(), self.distro)
+    }
+}
+
+/// The docker or podman CLI found by the environment checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerCliInfo {
+    pub program: &'static str,
+    /// Where `program` resolved to on `$PATH`, if it could be determined.
+    pub path: Option<PathBuf>,
+    /// The output of `program --version`.
+    pub version: String,
+}
+
+impl ContainerCliInfo {
+    /// The oldest version of `program` known to work with dev containers.
+    fn default_min_version(&self) -> &'static str {
+        if self.program == "podman" {
+            "4.0.0"
+        } else {
+            "20.10.0"
+        }
+    }
+
+    /// Why this CLI might not work with dev containers, if its version is
+    /// older than `min_version` or can't be told.
+    fn version_warning(&self, min_version: &str) -> Option<String> {
+        let program = self.program;
+        match (self.parsed_version(), parse_version(min_version)) {
+            (Some(version), Some(min)) if version < min => Some(format!(
+                "{program} {} is older than {min_version}, the oldest version known to work with dev containers",
+                version
+                    .iter()
+                    .map(u64::to_string)
+                    .collect::<Vec<_>>()
+                    .join(".")
+            )),
+            (None, _) => Some(format!(
+                "could not determine the {program} version from {:?}",
+                self.version
+            )),
+            _ => None,
+        }
+    }
+
+    /// Parses the first dotted version number out of the `--version` output,
+    /// e.g. `27.0.3` from `Docker version 27.0.3, build 7d4bcd8`.
+    fn parsed_version(&self) -> Option<Vec<u64>> {
+        self.version
+            .split_whitespace()
+            .map(|word| word.trim_end_matches(','))
+            .find(|word| word.contains('.') && word.starts_with(|c: char| c.is_ascii_digit()))
+            .and_then(parse_version)
+    }
+}
+
+impl Display for ContainerCliInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{} ({})", self.version, path.display()),
+            None => write!(f, "{} ({})", self.version, self.program),
+        }
+    }
+}
+
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    version
+        .split(['.', '-', '+'])
+        .take(3)
+        .map(|part| part.parse().ok())
+        .collect()
 }
 
 impl Display for DevContainerError {
@@ -94,6 +214,27 @@ impl Display for DevContainerError {
             match self {
                 DevContainerError::DockerNotAvailable =>
                     "docker CLI not found on $PATH".to_string(),
+                DevContainerError::DockerInSeveralWslDistros(daemons) => format!(
+                    "docker CLI not found on $PATH, but Zed found a container engine running in \
+                     several WSL distros ({}). Choose one of them to start the dev container \
+                     with.",
+                    daemons
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                DevContainerError::DockerNotFoundInWsl(distros) if distros.is_empty() =>
+                    "docker CLI not found on $PATH. Zed also looks for a docker or podman daemon \
+                     in running WSL distros, but none is running. Start the distro docker is \
+                     installed in and try again."
+                        .to_string(),
+                DevContainerError::DockerNotFoundInWsl(distros) => format!(
+                    "docker CLI not found on $PATH. Zed also looked for a docker or podman \
+                     daemon in the running WSL distros ({}) and found none. Start docker in \
+                     one of them, or start the distro it's installed in, and try again.",
+                    distros.join(", ")
+                ),
                 DevContainerError::ContainerNotValid(id) => format!(
                     "docker image {id} did not have expected configuration for a dev container"
                 ),
@@ -123,6 +264,8 @@ impl Display for DevContainerError {
                      `docker stop <id>` and `docker rm <id>`, then try again.",
                     ids.join(", ")
                 ),
+                DevContainerError::WithContainerCli(error, cli) =>
+                    format!("{error}\n\nContainer CLI: {cli}"),
             }
         )
     }
@@ -142,44 +285,140 @@ pub(crate) async fn read_default_devcontainer_configuration(
         })
 }
 
-/// Finds all available devcontainer configurations in the project.
-///
-/// See [`find_configs_in_snapshot`] for the locations that are scanned.
-pub fn find_devcontainer_configs(workspace: &Workspace, cx: &gpui::App) -> Vec<DevContainerConfig> {
-    let project = workspace.project().read(cx);
+/// How many directories below a worktree root discovery looks for packages
+/// with their own `.devcontainer` folder, such as `packages/api` at a depth
+/// of 2.
+const MAX_PACKAGE_DEPTH: usize = 3;
+
+/// The most configurations discovery returns, so that a large monorepo
+/// doesn't flood the picker.
+const MAX_DISCOVERED_CONFIGS: usize = 50;
 
-    let worktree = project
+/// Finds all available devcontainer configurations in the project's visible
+/// worktrees, including those of packages nested below their roots.
+///
+/// Walking the worktrees can take a while in large repositories, so it runs
+/// on the background executor. See [`discover_configs`] for what is scanned.
+pub fn find_devcontainer_configs(
+    workspace: &Workspace,
+    cx: &gpui::App,
+) -> Task<Vec<DevContainerConfig>> {
+    let snapshots = workspace
+        .project()
+        .read(cx)
         .visible_worktrees(cx)
-        .find_map(|tree| tree.read(cx).root_entry()?.is_dir().then_some(tree));
+        .filter(|tree| {
+            tree.read(cx)
+                .root_entry()
+                .is_some_and(|entry| entry.is_dir())
+        })
+        .map(|tree| tree.read(cx).snapshot())
+        .collect::<Vec<_>>();
 
-    let Some(worktree) = worktree else {
+    if snapshots.is_empty() {
         log::debug!("find_devcontainer_configs: No worktree found");
-        return Vec::new();
-    };
+        return Task::ready(Vec::new());
+    }
 
-    let worktree = worktree.read(cx);
-    find_configs_in_snapshot(worktree)
+    cx.background_spawn(async move { discover_configs(&snapshots) })
 }
 
-/// Scans a worktree snapshot for devcontainer configurations.
+/// Scans worktree snapshots for devcontainer configurations.
+///
+/// Every directory up to [`MAX_PACKAGE_DEPTH`] below each worktree root is
+/// scanned as described in [`find_configs_in_snapshot`], skipping the ones
+/// that are gitignored. Configurations at the root of the first worktree,
+/// the project root, come first, and any found outside it are tagged with
+/// the [`DevContainerPackage`] they belong to. At most
+/// [`MAX_DISCOVERED_CONFIGS`] are returned.
+pub(crate) fn discover_configs(snapshots: &[Snapshot]) -> Vec<DevContainerConfig> {
+    let show_worktree_names = snapshots.len() > 1;
+    let mut configs = Vec::new();
+
+    for (index, snapshot) in snapshots.iter().enumerate() {
+        let mut directories = vec![(RelPath::empty().into_arc(), 0)];
+        while let Some((directory, depth)) = directories.pop() {
+            let package = (index > 0 || !directory.is_empty()).then(|| DevContainerPackage {
+                worktree_id: snapshot.id(),
+                path: directory.clone(),
+                abs_path: snapshot.absolutize(&directory).into(),
+                label: package_label(snapshot, &directory, show_worktree_names),
+            });
+            for mut config in find_configs_in_directory(snapshot, &directory) {
+                if configs.len() == MAX_DISCOVERED_CONFIGS {
+                    log::warn!(
+                        "discover_configs: Stopped after finding {MAX_DISCOVERED_CONFIGS} configurations"
+                    );
+                    return configs;
+                }
+                config.package = package.clone();
+                configs.push(config);
+            }
+
+            if depth == MAX_PACKAGE_DEPTH {
+                continue;
+            }
+            let children = snapshot
+                .child_entries_with_options(
+                    &directory,
+                    ChildEntriesOptions {
+                        include_files: false,
+                        include_dirs: true,
+                        include_ignored: false,
+                    },
+                )
+                .filter(|entry| {
+                    !entry.is_external
+                        && entry.path.file_name() != Some(".devcontainer")
+                        && entry.path.file_name() != Some(".git")
+                })
+                .map(|entry| (entry.path.clone(), depth + 1))
+                .collect::<Vec<_>>();
+            // Popped in reverse, so siblings are scanned in path order.
+            directories.extend(children.into_iter().rev());
+        }
+    }
+
+    log::info!("discover_configs: Found {} configurations", configs.len());
+    configs
+}
+
+fn package_label(snapshot: &Snapshot, directory: &RelPath, show_worktree_name: bool) -> String {
+    let directory = directory.display(snapshot.path_style());
+    match (show_worktree_name, directory.is_empty()) {
+        (true, true) => snapshot.root_name_str().to_string(),
+        (true, false) => format!("{}/{directory}", snapshot.root_name_str()),
+        (false, _) => directory.into_owned(),
+    }
+}
+
+/// Scans the root of a worktree snapshot for devcontainer configurations.
+///
+/// See [`find_configs_in_directory`] for the locations that are scanned.
+pub fn find_configs_in_snapshot(snapshot: &Snapshot) -> Vec<DevContainerConfig> {
+    find_configs_in_directory(snapshot, RelPath::empty())
+}
+
+/// Scans a directory of a worktree snapshot for devcontainer configurations,
+/// whose paths are relative to that directory.
 ///
 /// Scans for configurations in these locations:
 /// 1. `.devcontainer/devcontainer.json` (the default location)
-/// 2. `.devcontainer.json` in the project root
+/// 2. `.devcontainer.json` in the directory
 /// 3. `.devcontainer/<subfolder>/devcontainer.json` (named configurations)
 ///
 /// All found configurations are returned so the user can pick between them.
-pub fn find_configs_in_snapshot(snapshot: &Snapshot) -> Vec<DevContainerConfig> {
+fn find_configs_in_directory(snapshot: &Snapshot, directory: &RelPath) -> Vec<DevContainerConfig> {
     let mut configs = Vec::new();
 
-    let devcontainer_dir_path = RelPath::unix(".devcontainer").expect("valid path");
+    let devcontainer_dir_path = directory.join(RelPath::unix(".devcontainer").expect("valid path"));
 
-    if let Some(devcontainer_entry) = snapshot.entry_for_path(devcontainer_dir_path) {
+    if let Some(devcontainer_entry) = snapshot.entry_for_path(&devcontainer_dir_path) {
         if devcontainer_entry.is_dir() {
             log::debug!("find_configs_in_snapshot: Scanning .devcontainer directory");
             let devcontainer_json_path =
-                RelPath::unix(".devcontainer/devcontainer.json").expect("valid path");
-            for entry in snapshot.child_entries(devcontainer_dir_path) {
+                devcontainer_dir_path.join(RelPath::unix("devcontainer.json").expect("valid path"));
+            for entry in snapshot.child_entries(&devcontainer_dir_path) {
                 log::debug!(
                     "find_configs_in_snapshot: Found entry: {:?}, is_file: {}, is_dir: {}",
                     entry.path.as_unix_str(),
@@ -187,7 +426,7 @@ pub fn find_configs_in_snapshot(snapshot: &Snapshot) -> Vec<DevContainerConfig>
                     entry.is_dir()
                 );
 
-                if entry.is_file() && entry.path.as_ref() == devcontainer_json_path {
+                if entry.is_file() && entry.path == devcontainer_json_path {
                     log::debug!("find_configs_in_snapshot: Found default devcontainer.json");
                     configs.push(DevContainerConfig::default_config());
                 } else if entry.is_dir() {
@@ -198,9 +437,12 @@ pub fn find_configs_in_snapshot(snapshot: &Snapshot) -> Vec<DevContainerConfig>
                         .unwrap_or_default();
 
                     let config_json_path =
-                        format!("{}/devcontainer.json", entry.path.as_unix_str());
+                        format!(".devcontainer/{subfolder_name}/devcontainer.json");
                     if let Ok(rel_config_path) = RelPath::unix(&config_json_path) {
-                        if snapshot.entry_for_path(rel_config_path).is_some() {
+                        if snapshot
+                            .entry_for_path(&directory.join(rel_config_path))
+                            .is_some()
+                        {
                             log::debug!(
                                 "find_configs_in_snapshot: Found config in subfolder: {}",
                                 subfolder_name
@@ -208,6 +450,7 @@ pub fn find_configs_in_snapshot(snapshot: &Snapshot) -> Vec<DevContainerConfig>
                             configs.push(DevContainerConfig {
                                 name: subfolder_name,
                                 config_path: PathBuf::from(&config_json_path),
+                                package: None,
                             });
                         } else {
                             log::debug!(
@@ -223,16 +466,16 @@ pub fn find_configs_in_snapshot(snapshot: &Snapshot) -> Vec<DevContainerConfig>
 
     // Always include `.devcontainer.json` so the user can pick it from the UI
     // even when `.devcontainer/devcontainer.json` also exists.
-    let root_config_path = RelPath::unix(".devcontainer.json").expect("valid path");
+    let root_config_path = directory.join(RelPath::unix(".devcontainer.json").expect("valid path"));
     if snapshot
-        .entry_for_path(root_config_path)
+        .entry_for_path(&root_config_path)
         .is_some_and(|entry| entry.is_file())
     {
         log::debug!("find_configs_in_snapshot: Found .devcontainer.json in project root");
         configs.push(DevContainerConfig::root_config());
     }
 
-    log::info!(
+    log::debug!(
         "find_configs_in_snapshot: Found {} configurations",
         configs.len()
     );
@@ -251,15 +494,35 @@ pub fn find_configs_in_snapshot(snapshot: &Snapshot) -> Vec<DevContainerConfig>
 }
 
 pub async fn start_dev_container_with_config(
-    context: DevContainerContext,
+    mut context: DevContainerContext,
     config: Option<DevContainerConfig>,
     environment: HashMap<String, String>,
 ) -> Result<(DevContainerConnection, String), DevContainerError> {
-    check_for_docker(context.use_podman).await?;
+    // A package's container is set up from, and saved as belonging to, the
+    // package's directory rather than the project root.
+    if let Some(package) = config.as_ref().and_then(|config| config.package.as_ref()) {
+        context.project_directory = package.abs_path.clone();
+    }
+    let cli = check_for_docker(&mut context).await?;
+
+    start_checked_dev_container(context, config, environment)
+        .await
+        .map_err(|error| DevContainerError::WithContainerCli(Box::new(error), cli))
+}
 
+async fn start_checked_dev_container(
+    context: DevContainerContext,
+    config: Option<DevContainerConfig>,
+    environment: HashMap<String, String>,
+) -> Result<(DevContainerConnection, String), DevContainerError> {
     let Some(actual_config) = config.clone() else {
         return Err(DevContainerError::NotInValidProject);
     };
+    let config_path = context.project_directory.join(&actual_config.config_path);
+    context.append_log_line(format!(
+        "Setting up the container from {}",
+        config_path.display()
+    ));
 
     match spawn_dev_container(
         &context,
@@ -277,6 +540,7 @@ pub async fn start_dev_container_with_config(
             remote_env,
             ..
         }) => {
+            context.append_log_line(format!("Container {container_id} is running"));
             let project_name =
                 match read_devcontainer_configuration(actual_config, &context, environment).await {
                     Ok(DevContainer {
@@ -289,9 +553,18 @@ pub async fn start_dev_container_with_config(
                 name: project_name,
                 container_id,
                 use_podman: context.use_podman,
+                wsl_distro: context.docker_host.wsl_distro().map(str::to_string),
                 remote_user,
                 extension_ids,
                 remote_env: remote_env.into_iter().collect(),
+                extra_env: Default::default(),
+                shell: None,
+                detected_shell: None,
+                tag: None,
+                host_project_root: Some(context.project_directory.to_string_lossy().into_owned()),
+                config_path: Some(config_path.to_string_lossy().into_owned()),
+                extra_exec_args: Vec::new(),
+                extra_cli_args: Vec::new(),
             };
 
             Ok((connection, remote_workspace_folder))
@@ -304,21 +577,240 @@ pub async fn start_dev_container_with_config(
     }
 }
 
-async fn check_for_docker(use_podman: bool) -> Result<(), DevContainerError> {
-    let mut command = if use_podman {
-        util::command::new_command("podman")
-    } else {
-        util::command::new_command("docker")
+/// A `devcontainer up` invocation that starts a saved dev container the way
+/// Zed started it, for sharing with people using the devcontainer CLI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DevContainerSetupCommand {
+    pub command: String,
+    /// What the connection didn't record, such as `"host project root"`. Each
+    /// is left in the command as a `<placeholder>` to fill in.
+    pub missing: Vec<&'static str>,
+}
+
+/// Builds the command that sets up `connection`, quoted for `shell_kind`.
+pub fn devcontainer_setup_command(
+    connection: &DevContainerConnection,
+    shell_kind: ShellKind,
+) -> DevContainerSetupCommand {
+    let mut missing = Vec::new();
+    let mut argument = |value: Option<&str>, placeholder: &'static str| match value
+        .filter(|value| !value.is_empty())
+        .and_then(|value| shell_kind.try_quote(value))
+    {
+        Some(quoted) => quoted.into_owned(),
+        None => {
+            missing.push(placeholder);
+            format!("<{placeholder}>")
+        }
     };
-    command.arg("--version");
 
-    match command.output().await {
-        Ok(_) => Ok(()),
-        Err(e) => {
-            log::error!("Unable to find docker in $PATH: {:?}", e);
-            Err(DevContainerError::DockerNotAvailable)
+    let mut command = format!(
+        "devcontainer up --workspace-folder {}",
+        argument(connection.host_project_root.as_deref(), "host project root")
+    );
+    command.push_str(" --config ");
+    command.push_str(&argument(
+        connection.config_path.as_deref(),
+        "devcontainer.json path",
+    ));
+    if connection.use_podman {
+        command.push_str(" --docker-path podman");
+    }
+    for extra_arg in &connection.extra_cli_args {
+        // Arguments meant for a shell can't be passed on as they are.
+        let extra_arg = Some(extra_arg.as_str())
+            .filter(|extra_arg| util::shell::find_shell_metacharacter(extra_arg).is_none());
+        command.push(' ');
+        command.push_str(&argument(extra_arg, "extra CLI argument"));
+    }
+
+    DevContainerSetupCommand { command, missing }
+}
+
+/// Finds the docker or podman CLI to use. On Windows, when it isn't on
+/// `$PATH`, switches `context` to the WSL distro whose daemon answers, or
+/// fails asking the user to pick when several do.
+async fn check_for_docker(
+    context: &mut DevContainerContext,
+) -> Result<ContainerCliInfo, DevContainerError> {
+    let (program, output) = loop {
+        let program = if context.use_podman {
+            "podman"
+        } else {
+            "docker"
+        };
+        let mut command = context.docker_host.command(program);
+        command.arg("--version");
+
+        match command.output().await {
+            Ok(output) if context.docker_host == DockerHost::Local => break (program, output),
+            Ok(output) if output.status.success() => break (program, output),
+            Ok(output) => log::error!(
+                "Unable to run {program} in {:?}: {}",
+                context.docker_host,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(e) => log::error!("Unable to find {program} in $PATH: {:?}", e),
+        }
+
+        if let DockerHost::Wsl(distro) = &context.docker_host {
+            return Err(DevContainerError::DockerNotFoundInWsl(vec![distro.clone()]));
+        }
+        if !cfg!(target_os = "windows") {
+            return Err(DevContainerError::DockerNotAvailable);
+        }
+        let daemon = find_docker_in_wsl().await?;
+        context.append_log_line(format!(
+            "{program} not found on $PATH, using {} in WSL distro {}",
+            daemon.program(),
+            daemon.distro
+        ));
+        context.use_podman = daemon.use_podman;
+        context.docker_host = DockerHost::Wsl(daemon.distro);
+    };
+
+    let cli = ContainerCliInfo {
+        program,
+        path: match context.docker_host {
+            DockerHost::Local => which::which(program).ok(),
+            DockerHost::Wsl(_) => None,
+        },
+        version: String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    };
+    context.append_log_line(format!("Container CLI: {cli}"));
+
+    let min_version = context
+        .min_cli_version
+        .as_deref()
+        .unwrap_or(cli.default_min_version());
+    if let Some(warning) = cli.version_warning(min_version) {
+        log::warn!("{warning}");
+        if let Some(build_log) = &context.build_log {
+            build_log.unbounded_send(format!("Warning: {warning}")).ok();
         }
     }
+
+    Ok(cli)
+}
+
+/// On Windows, docker is often installed only inside a WSL distro, where the
+/// Windows side can't reach it. Probes the running distros for a docker or
+/// podman daemon, leaving stopped ones alone rather than booting each of
+/// them, and returns the only one that answers.
+async fn find_docker_in_wsl() -> Result<WslDockerDaemon, DevContainerError> {
+    let output = match util::command::new_command("wsl")
+        .args(["-l", "--running", "-q"])
+        .output()
+        .await
+    {
+        Ok(output) if output.status.success() => output,
+        Ok(_) | Err(_) => return Err(DevContainerError::DockerNotAvailable),
+    };
+    let distros = parse_wsl_distro_list(&output.stdout);
+
+    let probes = distros.iter().map(|distro| async move {
+        for use_podman in [false, true] {
+            let daemon = WslDockerDaemon {
+                distro: distro.clone(),
+                use_podman,
+            };
+            let output = util::command::new_command("wsl")
+                .args(["-d", distro, "--", daemon.program(), "info"])
+                .output()
+                .await;
+            if output.is_ok_and(|output| output.status.success()) {
+                log::info!("Found a running {daemon}");
+                return Some(daemon);
+            }
+        }
+        None
+    });
+    let daemons = futures::future::join_all(probes)
+        .await
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    choose_wsl_docker_daemon(distros, daemons)
+}
+
+fn choose_wsl_docker_daemon(
+    running_distros: Vec<String>,
+    mut daemons: Vec<WslDockerDaemon>,
+) -> Result<WslDockerDaemon, DevContainerError> {
+    match daemons.len() {
+        0 => Err(DevContainerError::DockerNotFoundInWsl(running_distros)),
+        1 => Ok(daemons.remove(0)),
+        _ => Err(DevContainerError::DockerInSeveralWslDistros(daemons)),
+    }
+}
+
+/// Works out which runtime a container belongs to by asking docker and then
+/// podman on `host` whether they can see `container_id`. Returns whether it's
+/// a podman container, or `None` when neither runtime knows it.
+pub async fn detect_container_runtime(container_id: &str, host: &DockerHost) -> Option<bool> {
+    for use_podman in [false, true] {
+        let program = if use_podman { "podman" } else { "docker" };
+        let output = host
+            .command(program)
+            .args(["container", "inspect", "--format", "{{.Id}}", container_id])
+            .output()
+            .await;
+        match output {
+            Ok(output) if output.status.success() => {
+                log::info!("Found container {container_id} with {program}");
+                return Some(use_podman);
+            }
+            Ok(_) => {}
+            Err(error) => log::debug!("Unable to run {program}: {error:?}"),
+        }
+    }
+    None
+}
+
+/// Stops the container `container_id` with docker, or podman when
+/// `use_podman` is set, on `host`. Setting the container up again starts it.
+pub async fn stop_dev_container(
+    container_id: &str,
+    use_podman: bool,
+    host: &DockerHost,
+) -> Result<(), DevContainerError> {
+    let program = if use_podman { "podman" } else { "docker" };
+    let output = host
+        .command(program)
+        .args(["stop", container_id])
+        .output()
+        .await
+        .map_err(|error| {
+            log::error!("Unable to run {program}: {error:?}");
+            DevContainerError::CommandFailed(program.to_string())
+        })?;
+    if !output.status.success() {
+        log::error!(
+            "Failed to stop container {container_id}: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return Err(DevContainerError::CommandFailed(format!("{program} stop")));
+    }
+    Ok(())
+}
+
+/// Parses the output of `wsl -l --running -q`, which is UTF-16LE unless `WSL_UTF8` is set.
+fn parse_wsl_distro_list(stdout: &[u8]) -> Vec<String> {
+    let text = if stdout.len() >= 2 && stdout[1] == 0 {
+        let units = stdout
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect::<Vec<_>>();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(stdout).into_owned()
+    };
+    text.lines()
+        .map(|line| line.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}'))
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
 }
 
 pub(crate) async fn apply_devcontainer_template(
@@ -484,15 +976,19 @@ fn get_backup_project_name(remote_workspace_folder: &str, container_id: &str) ->
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
-    use crate::devcontainer_api::{DevContainerConfig, find_configs_in_snapshot};
+    use crate::devcontainer_api::{
+        ContainerCliInfo, DevContainerConfig, DevContainerError, WslDockerDaemon,
+        choose_wsl_docker_daemon, devcontainer_setup_command, discover_configs,
+        find_configs_in_snapshot, parse_version, parse_wsl_distro_list,
+    };
     use fs::FakeFs;
     use gpui::TestAppContext;
     use project::Project;
     use serde_json::json;
-    use settings::SettingsStore;
-    use util::path;
+    use settings::{DevContainerConnection, SettingsStore};
+    use util::{path, shell::ShellKind};
 
     fn init_test(cx: &mut TestAppContext) {
         cx.update(|cx| {
@@ -751,4 +1247,266 @@ mod tests {
         assert_eq!(configs.len(), 1);
         assert_eq!(configs[0], DevContainerConfig::root_config());
     }
+
+    #[gpui::test]
+    async fn test_discover_configs_in_packages(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/project"),
+            json!({
+                ".git": {},
+                ".gitignore": "vendor/\n",
+                ".devcontainer": {
+                    "devcontainer.json": "{}"
+                },
+                "packages": {
+                    "api": {
+                        ".devcontainer": {
+                            "devcontainer.json": "{}"
+                        }
+                    },
+                    "web": {
+                        ".devcontainer": {
+                            "node": {
+                                "devcontainer.json": "{}"
+                            }
+                        }
+                    }
+                },
+                "vendor": {
+                    "lib": {
+                        ".devcontainer": {
+                            "devcontainer.json": "{}"
+                        }
+                    }
+                },
+                "a": {
+                    "b": {
+                        "c": {
+                            "too-deep": {
+                                ".devcontainer": {
+                                    "devcontainer.json": "{}"
+                                }
+                            }
+                        }
+                    }
+                }
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs, [path!("/project").as_ref()], cx).await;
+        cx.run_until_parked();
+
+        let configs = project.read_with(cx, |project, cx| {
+            let snapshots = project
+                .visible_worktrees(cx)
+                .map(|worktree| worktree.read(cx).snapshot())
+                .collect::<Vec<_>>();
+            discover_configs(&snapshots)
+        });
+
+        let found = configs
+            .iter()
+            .map(|config| {
+                (
+                    config
+                        .package
+                        .as_ref()
+                        .map(|package| package.path.as_unix_str()),
+                    config.name.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (None, "default"),
+                (Some("packages/api"), "default"),
+                (Some("packages/web"), "node"),
+            ]
+        );
+        let package = configs[1].package.as_ref().expect("package config");
+        assert_eq!(
+            package.abs_path.as_ref(),
+            Path::new(path!("/project/packages/api"))
+        );
+        assert_eq!(
+            configs[2].config_path,
+            PathBuf::from(".devcontainer/node/devcontainer.json")
+        );
+    }
+
+    #[test]
+    fn test_container_cli_version_parsing() {
+        let docker = ContainerCliInfo {
+            program: "docker",
+            path: None,
+            version: "Docker version 27.0.3, build 7d4bcd8".to_string(),
+        };
+        assert_eq!(docker.parsed_version(), Some(vec![27, 0, 3]));
+        assert!(docker.parsed_version() >= parse_version(docker.default_min_version()));
+
+        let podman = ContainerCliInfo {
+            program: "podman",
+            path: None,
+            version: "podman version 3.4.4".to_string(),
+        };
+        assert_eq!(podman.parsed_version(), Some(vec![3, 4, 4]));
+        assert!(podman.parsed_version() < parse_version(podman.default_min_version()));
+        assert_eq!(
+            podman.version_warning("4.0.0").as_deref(),
+            Some(
+                "podman 3.4.4 is older than 4.0.0, the oldest version known to work with dev containers"
+            )
+        );
+        assert_eq!(podman.version_warning("3.0.0"), None);
+
+        let unknown = ContainerCliInfo {
+            program: "docker",
+            path: None,
+            version: "something unexpected".to_string(),
+        };
+        assert_eq!(unknown.parsed_version(), None);
+    }
+
+    #[test]
+    fn test_wsl_distro_list_parsing() {
+        let utf16 = "Ubuntu-22.04\r\ndocker-desktop\r\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parse_wsl_distro_list(&utf16),
+            vec!["Ubuntu-22.04".to_string(), "docker-desktop".to_string()]
+        );
+        assert_eq!(
+            parse_wsl_distro_list(b"Debian\n\n"),
+            vec!["Debian".to_string()]
+        );
+        assert!(parse_wsl_distro_list(b"").is_empty());
+    }
+
+    #[test]
+    fn test_choose_wsl_docker_daemon() {
+        let daemon = |distro: &str, use_podman| WslDockerDaemon {
+            distro: distro.to_string(),
+            use_podman,
+        };
+        let running = vec!["Ubuntu".to_string(), "Fedora".to_string()];
+
+        assert_eq!(
+            choose_wsl_docker_daemon(running.clone(), vec![daemon("Fedora", true)]),
+            Ok(daemon("Fedora", true))
+        );
+
+        let several = choose_wsl_docker_daemon(
+            running.clone(),
+            vec![daemon("Ubuntu", false), daemon("Fedora", true)],
+        );
+        assert_eq!(
+            several,
+            Err(DevContainerError::DockerInSeveralWslDistros(vec![
+                daemon("Ubuntu", false),
+                daemon("Fedora", true)
+            ]))
+        );
+        assert!(
+            several
+                .unwrap_err()
+                .to_string()
+                .contains("docker in Ubuntu, podman in Fedora")
+        );
+
+        let none = choose_wsl_docker_daemon(running, Vec::new()).unwrap_err();
+        assert!(
+            none.to_string()
+                .contains("running WSL distros (Ubuntu, Fedora)")
+        );
+        let nothing_running = choose_wsl_docker_daemon(Vec::new(), Vec::new()).unwrap_err();
+        assert!(nothing_running.to_string().contains("none is running"));
+    }
+
+    #[test]
+    fn test_devcontainer_setup_command() {
+        let connection = DevContainerConnection {
+            name: "api".to_string(),
+            host_project_root: Some("/home/me/my project".to_string()),
+            config_path: Some("/home/me/my project/.devcontainer/devcontainer.json".to_string()),
+            ..DevContainerConnection::default()
+        };
+        let setup = devcontainer_setup_command(&connection, ShellKind::Posix);
+        assert!(setup.missing.is_empty());
+        assert_eq!(
+            ShellKind::Posix.split(&setup.command),
+            Some(vec![
+                "devcontainer".to_string(),
+                "up".to_string(),
+                "--workspace-folder".to_string(),
+                "/home/me/my project".to_string(),
+                "--config".to_string(),
+                "/home/me/my project/.devcontainer/devcontainer.json".to_string(),
+            ])
+        );
+
+        let setup = devcontainer_setup_command(
+            &DevContainerConnection {
+                use_podman: true,
+                ..connection.clone()
+            },
+            ShellKind::Posix,
+        );
+        assert!(setup.command.ends_with(" --docker-path podman"));
+
+        let setup = devcontainer_setup_command(
+            &DevContainerConnection {
+                extra_cli_args: vec!["--build-no-cache".to_string(), "--id-label=a b".to_string()],
+                ..connection.clone()
+            },
+            ShellKind::Posix,
+        );
+        assert!(setup.missing.is_empty());
+        assert_eq!(
+            ShellKind::Posix.split(&setup.command).unwrap()[6..],
+            ["--build-no-cache", "--id-label=a b"]
+        );
+
+        // Arguments written for a shell are left for the user to fix.
+        let setup = devcontainer_setup_command(
+            &DevContainerConnection {
+                extra_cli_args: vec!["--build-no-cache; rm -rf /".to_string()],
+                ..connection.clone()
+            },
+            ShellKind::Posix,
+        );
+        assert!(setup.command.ends_with(" <extra CLI argument>"));
+        assert_eq!(setup.missing, ["extra CLI argument"]);
+
+        let setup = devcontainer_setup_command(&connection, ShellKind::PowerShell);
+        assert!(setup.command.contains(&format!(
+            "--workspace-folder {} ",
+            ShellKind::quote_powershell("/home/me/my project")
+        )));
+
+        // Whatever wasn't recorded is left as a placeholder rather than
+        // guessed.
+        let setup = devcontainer_setup_command(
+            &DevContainerConnection {
+                config_path: None,
+                host_project_root: Some(String::new()),
+                ..connection
+            },
+            ShellKind::Posix,
+        );
+        assert_eq!(
+            setup.command,
+            "devcontainer up --workspace-folder <host project root> \
+             --config <devcontainer.json path>"
+        );
+        assert_eq!(
+            setup.missing,
+            ["host project root", "devcontainer.json path"]
+        );
+    }
 }
@@ -933,10 +933,8 @@ RUN sed -i -E 's/((^|\s)PATH=)([^\$]*)$/\1\${{PATH:-\3}}/g' /etc/profile || true
             } else {
                 Some(HashMap::from([(
                     "dev_containers_feature_content_source".to_string(),
-                    features_build_info
-                        .features_content_dir
-                        .display()
-                        .to_string(),
+                    self.docker_client
+                        .host_path(&features_build_info.features_content_dir),
                 )]))
             };
 
@@ -957,10 +955,11 @@ RUN sed -i -E 's/((^|\s)PATH=)([^\$]*)$/\1\${{PATH:-\3}}/g' /etc/profile || true
                                     .as_ref()
                                     .and_then(|b| b.context.clone())
                                     .unwrap_or_else(|| {
-                                        features_build_info.empty_context_dir.display().to_string()
+                                        self.docker_client
+                                            .host_path(&features_build_info.empty_context_dir)
                                     }),
                             ),
-                            dockerfile: Some(dockerfile_path.display().to_string()),
+                            dockerfile: Some(self.docker_client.host_path(&dockerfile_path)),
                             target: Some("dev_containers_target_stage".to_string()),
                             args: Some(build_args),
                             additional_contexts,
@@ -1032,10 +1031,8 @@ RUN sed -i -E 's/((^|\s)PATH=)([^\$]*)$/\1\${{PATH:-\3}}/g' /etc/profile || true
                 } else {
                     Some(HashMap::from([(
                         "dev_containers_feature_content_source".to_string(),
-                        features_build_info
-                            .features_content_dir
-                            .display()
-                            .to_string(),
+                        self.docker_client
+                            .host_path(&features_build_info.features_content_dir),
                     )]))
                 };
 
@@ -1051,9 +1048,10 @@ RUN sed -i -E 's/((^|\s)PATH=)([^\$]*)$/\1\${{PATH:-\3}}/g' /etc/profile || true
                             labels: None,
                             build: Some(DockerComposeServiceBuild {
                                 context: Some(
-                                    features_build_info.empty_context_dir.display().to_string(),
+                                    self.docker_client
+                                        .host_path(&features_build_info.empty_context_dir),
                                 ),
-                                dockerfile: Some(dockerfile_path.display().to_string()),
+                                dockerfile: Some(self.docker_client.host_path(&dockerfile_path)),
                                 target: Some("dev_containers_target_stage".to_string()),
                                 args: Some(build_args),
                                 additional_contexts,
@@ -2291,15 +2289,15 @@ pub(crate) async fn read_devcontainer_configuration(
     environment: HashMap<String, String>,
 ) -> Result<DevContainer, DevContainerError> {
     let docker = if context.use_podman {
-        Docker::new("podman").await
+        Docker::new("podman", context.docker_host.clone()).await
     } else {
-        Docker::new("docker").await
+        Docker::new("docker", context.docker_host.clone()).await
     };
     let mut dev_container = DevContainerManifest::new(
         context,
         environment,
         Arc::new(docker),
-        Arc::new(DefaultCommandRunner::new()),
+        Arc::new(DefaultCommandRunner::new(context.docker_host.clone())),
         config,
         &context.project_directory.as_ref(),
     )
@@ -2315,15 +2313,15 @@ pub(crate) async fn spawn_dev_container(
     local_project_path: &Path,
 ) -> Result<DevContainerUp, DevContainerError> {
     let docker = if context.use_podman {
-        Docker::new("podman").await
+        Docker::new("podman", context.docker_host.clone()).await
     } else {
-        Docker::new("docker").await
+        Docker::new("docker", context.docker_host.clone()).await
     };
     let mut devcontainer_manifest = DevContainerManifest::new(
         context,
         environment,
         Arc::new(docker),
-        Arc::new(DefaultCommandRunner::new()),
+        Arc::new(DefaultCommandRunner::new(context.docker_host.clone())),
         config,
         local_project_path,
     )
@@ -2840,8 +2838,8 @@ mod test {
         },
         docker::{
             DockerClient, DockerComposeConfig, DockerComposeService, DockerComposeServiceBuild,
-            DockerComposeVolume, DockerConfigLabels, DockerInspectConfig, DockerInspectMount,
-            DockerPs,
+            DockerComposeVolume, DockerConfigLabels, DockerHost, DockerInspectConfig,
+            DockerInspectMount, DockerPs,
         },
         oci::TokenResponse,
     };
@@ -2950,9 +2948,12 @@ mod test {
         let context = DevContainerContext {
             project_directory: SanitizedPath::cast_arc(project_path),
             use_podman: false,
+            docker_host: DockerHost::Local,
+            min_cli_version: None,
             fs: fs.clone(),
             http_client: http_client.clone(),
             environment: project_environment.downgrade(),
+            build_log: None,
         };
 
         let test_dependencies = TestDependencies {
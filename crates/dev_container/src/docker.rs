@@ -1,4 +1,7 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 use async_trait::async_trait;
 use serde::{Deserialize, Deserializer, Serialize, de};
@@ -173,8 +176,93 @@ pub(crate) struct DockerComposeConfig {
     pub(crate) volumes: HashMap<String, DockerComposeVolume>,
 }
 
+/// Where the docker or podman daemon that runs dev containers lives.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DockerHost {
+    /// The CLI on `$PATH`, talking to its daemon directly.
+    #[default]
+    Local,
+    /// The CLI inside this WSL distro, run through `wsl -d <distro> --`, as
+    /// on Windows machines where docker is only installed in WSL.
+    Wsl(String),
+}
+
+impl DockerHost {
+    pub fn from_wsl_distro(wsl_distro: Option<String>) -> Self {
+        match wsl_distro {
+            Some(distro) => DockerHost::Wsl(distro),
+            None => DockerHost::Local,
+        }
+    }
+
+    pub fn wsl_distro(&self) -> Option<&str> {
+        match self {
+            DockerHost::Local => None,
+            DockerHost::Wsl(distro) => Some(distro),
+        }
+    }
+
+    /// A command that runs `program` on this host.
+    pub(crate) fn command(&self, program: &str) -> Command {
+        match self {
+            DockerHost::Local => Command::new(program),
+            DockerHost::Wsl(distro) => {
+                let mut command = Command::new("wsl");
+                command.args(["-d", distro, "--", program]);
+                command
+            }
+        }
+    }
+
+    /// `arg` with the Windows paths in it rewritten to where WSL mounts them,
+    /// so that the CLI in the distro can find the files Zed wrote.
+    pub(crate) fn host_arg(&self, arg: &str) -> String {
+        match self {
+            DockerHost::Local => arg.to_string(),
+            DockerHost::Wsl(_) => windows_paths_to_wsl(arg),
+        }
+    }
+}
+
+/// Rewrites every Windows drive path in `arg`, such as `C:\work\app` or
+/// `C:/work/app`, to its WSL mount, `/mnt/c/work/app`. A path starts the
+/// argument or follows a `=` or `,`, as in
+/// `type=bind,source=C:\work\app,target=/workspaces/app` or
+/// `label=devcontainer.local_folder=C:\work\app`, and runs up to the next `,`.
+fn windows_paths_to_wsl(arg: &str) -> String {
+    let mut result = String::with_capacity(arg.len());
+    for (index, part) in arg.split(',').enumerate() {
+        if index > 0 {
+            result.push(',');
+        }
+        let path_start = std::iter::once(0)
+            .chain(part.match_indices('=').map(|(index, _)| index + 1))
+            .find(|&start| is_windows_drive_path(&part[start..]));
+        match path_start {
+            Some(start) => {
+                let path = &part[start..];
+                result.push_str(&part[..start]);
+                result.push_str("/mnt/");
+                result.push_str(&path[..1].to_ascii_lowercase());
+                result.push_str(path[2..].replace('\\', "/").trim_end_matches('/'));
+            }
+            None => result.push_str(part),
+        }
+    }
+    result
+}
+
+fn is_windows_drive_path(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/')
+}
+
 pub(crate) struct Docker {
     docker_cli: String,
+    host: DockerHost,
     has_buildx: bool,
 }
 
@@ -185,11 +273,12 @@ impl DockerInspect {
 }
 
 impl Docker {
-    pub(crate) async fn new(docker_cli: &str) -> Self {
+    pub(crate) async fn new(docker_cli: &str, host: DockerHost) -> Self {
         let has_buildx = if docker_cli == "podman" {
             false
         } else {
-            let output = Command::new(docker_cli)
+            let output = host
+                .command(docker_cli)
                 .args(["buildx", "version"])
                 .output()
                 .await;
@@ -202,6 +291,7 @@ impl Docker {
         }
         Self {
             docker_cli: docker_cli.to_string(),
+            host,
             has_buildx,
         }
     }
@@ -210,8 +300,12 @@ impl Docker {
         self.docker_cli == "podman"
     }
 
+    fn command(&self) -> Command {
+        self.host.command(&self.docker_cli)
+    }
+
     async fn pull_image(&self, image: &String) -> Result<(), DevContainerError> {
-        let mut command = Command::new(&self.docker_cli);
+        let mut command = self.command();
         command.args(&["pull", "--", image]);
 
         let output = command.output().await.map_err(|e| {
@@ -228,7 +322,7 @@ impl Docker {
     }
 
     fn create_docker_query_containers(&self, filters: Vec<String>) -> Command {
-        let mut command = Command::new(&self.docker_cli);
+        let mut command = self.command();
         command.args(&["ps", "-a"]);
 
         for filter in filters {
@@ -240,16 +334,16 @@ impl Docker {
     }
 
     fn create_docker_inspect(&self, id: &str) -> Command {
-        let mut command = Command::new(&self.docker_cli);
+        let mut command = self.command();
         command.args(&["inspect", "--format={{json . }}", id]);
         command
     }
 
     fn create_docker_compose_config_command(&self, config_files: &Vec<PathBuf>) -> Command {
-        let mut command = Command::new(&self.docker_cli);
+        let mut command = self.command();
         command.arg("compose");
         for file_path in config_files {
-            command.args(&["-f", &file_path.display().to_string()]);
+            command.args(&["-f", &self.host_path(file_path)]);
         }
         command.args(&["config", "--format", "json"]);
         command
@@ -285,13 +379,16 @@ impl DockerClient for Docker {
         config_files: &Vec<PathBuf>,
         project_name: &str,
     ) -> Result<(), DevContainerError> {
-        let mut command = Command::new(&self.docker_cli);
+        let mut command = self.command();
         if !self.is_podman() {
             command.env("DOCKER_BUILDKIT", "1");
+            if self.host != DockerHost::Local {
+                command.env("WSLENV", "DOCKER_BUILDKIT");
+            }
         }
         command.args(&["compose", "--project-name", project_name]);
         for docker_compose_file in config_files {
-            command.args(&["-f", &docker_compose_file.display().to_string()]);
+            command.args(&["-f", &self.host_path(docker_compose_file)]);
         }
         command.arg("build");
 
@@ -318,7 +415,7 @@ impl DockerClient for Docker {
         env: &HashMap<String, String>,
         inner_command: Command,
     ) -> Result<(), DevContainerError> {
-        let mut command = Command::new(&self.docker_cli);
+        let mut command = self.command();
 
         command.args(&["exec", "-w", remote_folder, "-u", user]);
 
@@ -355,7 +452,7 @@ impl DockerClient for Docker {
         Ok(())
     }
     async fn start_container(&self, id: &str) -> Result<(), DevContainerError> {
-        let mut command = Command::new(&self.docker_cli);
+        let mut command = self.command();
 
         command.args(&["start", id]);
 
@@ -407,6 +504,10 @@ impl DockerClient for Docker {
         self.docker_cli.clone()
     }
 
+    fn host_path(&self, path: &Path) -> String {
+        self.host.host_arg(&path.display().to_string())
+    }
+
     fn supports_compose_buildkit(&self) -> bool {
         self.has_buildx
     }
@@ -468,6 +569,10 @@ pub(crate) trait DockerClient {
     /// This operates as an escape hatch for more custom uses of the docker API.
     /// See DevContainerManifest::create_docker_build as an example
     fn docker_cli(&self) -> String;
+    /// `path` as the CLI sees it, for paths written into files it reads.
+    fn host_path(&self, path: &Path) -> String {
+        path.display().to_string()
+    }
 }
 
 fn deserialize_labels<'de, D>(deserializer: D) -> Result<Option<HashMap<String, String>>, D::Error>
@@ -583,10 +688,40 @@ mod test {
         devcontainer_json::MountDefinition,
         docker::{
             Docker, DockerComposeConfig, DockerComposeService, DockerComposeServicePort,
-            DockerComposeVolume, DockerInspect, DockerPs, parse_find_process_output,
+            DockerComposeVolume, DockerHost, DockerInspect, DockerPs, parse_find_process_output,
         },
     };
 
+    #[test]
+    fn should_run_wsl_commands_through_the_distro() {
+        let host = DockerHost::Wsl("Ubuntu".to_string());
+        let command = host.command("podman");
+        assert_eq!(command.get_program(), OsStr::new("wsl"));
+        assert_eq!(
+            command.get_args().collect::<Vec<&OsStr>>(),
+            vec!["-d", "Ubuntu", "--", "podman"]
+        );
+
+        assert_eq!(
+            host.host_arg(r"C:\Users\me\app"),
+            "/mnt/c/Users/me/app".to_string()
+        );
+        assert_eq!(
+            host.host_arg(r"type=bind,source=D:/work/app/,target=/workspaces/app"),
+            "type=bind,source=/mnt/d/work/app,target=/workspaces/app".to_string()
+        );
+        assert_eq!(
+            host.host_arg(r"label=devcontainer.local_folder=C:\app"),
+            "label=devcontainer.local_folder=/mnt/c/app".to_string()
+        );
+        assert_eq!(host.host_arg("--sig-proxy=false"), "--sig-proxy=false");
+        assert_eq!(host.host_arg("C:"), "C:");
+        assert_eq!(
+            DockerHost::Local.host_arg(r"C:\Users\me\app"),
+            r"C:\Users\me\app".to_string()
+        );
+    }
+
     #[test]
     fn should_parse_simple_env_var() {
         let config = super::DockerInspectConfig {
@@ -669,6 +804,7 @@ mod test {
     fn should_create_docker_inspect_command() {
         let docker = Docker {
             docker_cli: "docker".to_string(),
+            host: DockerHost::Local,
             has_buildx: false,
         };
         let given_id = "given_docker_id";
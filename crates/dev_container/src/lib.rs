@@ -1,6 +1,7 @@
 use std::path::Path;
 
 use fs::Fs;
+use futures::channel::mpsc::UnboundedSender;
 use gpui::AppContext;
 use gpui::Entity;
 use gpui::Task;
@@ -53,16 +54,18 @@ mod oci;
 
 use devcontainer_api::read_default_devcontainer_configuration;
 
-use crate::devcontainer_api::DevContainerError;
+pub use crate::devcontainer_api::DevContainerError;
 use crate::devcontainer_api::apply_devcontainer_template;
 use crate::oci::get_deserializable_oci_blob;
 use crate::oci::get_latest_oci_manifest;
 use crate::oci::get_oci_token;
 
 pub use devcontainer_api::{
-    DevContainerConfig, find_configs_in_snapshot, find_devcontainer_configs,
-    start_dev_container_with_config,
+    DevContainerConfig, DevContainerPackage, DevContainerSetupCommand, WslDockerDaemon,
+    detect_container_runtime, devcontainer_setup_command, find_configs_in_snapshot,
+    find_devcontainer_configs, start_dev_container_with_config, stop_dev_container,
 };
+pub use docker::DockerHost;
 
 /// Converts a string to a safe environment variable name.
 ///
@@ -95,30 +98,52 @@ fn get_safe_id(input: &str) -> String {
     result
 }
 
+#[derive(Clone)]
 pub struct DevContainerContext {
     pub project_directory: Arc<Path>,
     pub use_podman: bool,
+    /// Where `use_podman`'s CLI runs. Starts out local, and is switched to a
+    /// WSL distro when docker is only found there.
+    pub docker_host: DockerHost,
+    /// Overrides the oldest docker or podman version considered known-good.
+    pub min_cli_version: Option<String>,
     pub fs: Arc<dyn Fs>,
     pub http_client: Arc<dyn HttpClient>,
     pub environment: WeakEntity<ProjectEnvironment>,
+    /// Receives what setting the container up reports along the way, such as
+    /// the tools the environment checks found, for showing as it happens.
+    pub build_log: Option<UnboundedSender<String>>,
 }
 
 impl DevContainerContext {
     pub fn from_workspace(workspace: &Workspace, cx: &App) -> Option<Self> {
         let project_directory = workspace.project().read(cx).active_project_directory(cx)?;
-        let use_podman = DevContainerSettings::get_global(cx).use_podman;
+        let settings = DevContainerSettings::get_global(cx);
+        let use_podman = settings.use_podman;
+        let min_cli_version = settings.min_cli_version.clone();
         let http_client = cx.http_client().clone();
         let fs = workspace.app_state().fs.clone();
         let environment = workspace.project().read(cx).environment().downgrade();
         Some(Self {
             project_directory,
             use_podman,
+            docker_host: DockerHost::Local,
+            min_cli_version,
             fs,
             http_client,
             environment,
+            build_log: None,
         })
     }
 
+    /// Adds `line` to the build log, and to Zed's log.
+    pub(crate) fn append_log_line(&self, line: String) {
+        log::info!("{line}");
+        if let Some(build_log) = &self.build_log {
+            build_log.unbounded_send(line).ok();
+        }
+    }
+
     pub async fn environment(&self, cx: &mut impl AppContext) -> HashMap<String, String> {
         let Ok(task) = self.environment.update(cx, |this, cx| {
             this.local_directory_environment(&Shell::System, self.project_directory.clone(), cx)
@@ -134,6 +159,7 @@ impl DevContainerContext {
 #[derive(RegisterSetting)]
 struct DevContainerSettings {
     use_podman: bool,
+    min_cli_version: Option<String>,
 }
 
 pub fn use_podman(cx: &App) -> bool {
@@ -144,6 +170,7 @@ impl Settings for DevContainerSettings {
     fn from_settings(content: &settings::SettingsContent) -> Self {
         Self {
             use_podman: content.remote.use_podman.unwrap_or(false),
+            min_cli_version: content.remote.dev_container_min_cli_version.clone(),
         }
     }
 }
@@ -260,6 +260,9 @@ pub trait Fs: Send + Sync {
     async fn atomic_write(&self, path: PathBuf, text: String) -> Result<()>;
     async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()>;
     async fn write(&self, path: &Path, content: &[u8]) -> Result<()>;
+    /// Appends `content` to the file at `path`, creating the file if it
+    /// doesn't exist. Its parent directory must exist.
+    async fn append(&self, path: &Path, content: &[u8]) -> Result<()>;
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf>;
     async fn is_file(&self, path: &Path) -> bool;
     async fn is_dir(&self, path: &Path) -> bool;
@@ -1059,6 +1062,21 @@ impl Fs for RealFs {
             .await
     }
 
+    async fn append(&self, path: &Path, content: &[u8]) -> Result<()> {
+        let path = path.to_owned();
+        let content = content.to_owned();
+        self.executor
+            .spawn(async move {
+                let mut file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)?;
+                file.write_all(&content)?;
+                Ok(())
+            })
+            .await
+    }
+
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
         let path = path.to_owned();
         self.executor
@@ -3089,6 +3107,19 @@ impl Fs for FakeFs {
         Ok(())
     }
 
+    async fn append(&self, path: &Path, content: &[u8]) -> Result<()> {
+        self.simulate_random_delay().await;
+        let path = normalize_path(path);
+        let mut new_content = if self.metadata(&path).await?.is_some() {
+            self.load_internal(&path).await?
+        } else {
+            Vec::new()
+        };
+        new_content.extend_from_slice(content);
+        self.write_file_internal(path, new_content, false)?;
+        Ok(())
+    }
+
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
         let path = normalize_path(path);
         self.simulate_random_delay().await;
@@ -3374,6 +3405,22 @@ pub async fn copy_recursive<'a>(
     Ok(())
 }
 
+/// Creates a new directory at `path`, failing if something already exists there.
+///
+/// Missing parent directories are only created when `create_parents` is set, like `mkdir -p`.
+pub async fn create_new_dir(fs: &dyn Fs, path: &Path, create_parents: bool) -> Result<()> {
+    if fs.metadata(path).await?.is_some() {
+        anyhow::bail!("{path:?} already exists");
+    }
+    if !create_parents
+        && let Some(parent) = path.parent()
+        && !fs.is_dir(parent).await
+    {
+        anyhow::bail!("parent directory {parent:?} does not exist");
+    }
+    fs.create_dir(path).await
+}
+
 /// Recursively reads all of the paths in the given directory.
 ///
 /// Returns a vector of tuples of (path, is_dir).
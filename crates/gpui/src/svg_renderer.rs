@@ -179,19 +179,38 @@ impl SvgRenderer {
             bytes,
             SvgSize::ScaleFactor(scale_factor * SMOOTH_SVG_SCALE_FACTOR),
         )
-        .map(|pixmap| {
-            let mut buffer =
-                image::ImageBuffer::from_raw(pixmap.width(), pixmap.height(), pixmap.take())
-                    .unwrap();
+        .map(Self::frame_from_pixmap)
+    }
 
-            for pixel in buffer.chunks_exact_mut(4) {
-                swap_rgba_pa_to_bgra(pixel);
-            }
+    /// Like `render_single_frame`, but first passes the size the SVG declares
+    /// to `check_size`, and only rasterizes it if that succeeds. Use this for
+    /// untrusted SVGs, which could otherwise declare an enormous canvas.
+    pub fn render_single_frame_checked(
+        &self,
+        bytes: &[u8],
+        scale_factor: f32,
+        check_size: impl FnOnce(Size<f32>) -> Result<()>,
+    ) -> Result<Arc<RenderImage>> {
+        let tree = usvg::Tree::from_data(bytes, &self.usvg_options)?;
+        check_size(Size::new(tree.size().width(), tree.size().height()))?;
+        let pixmap = Self::render_tree(
+            &tree,
+            SvgSize::ScaleFactor(scale_factor * SMOOTH_SVG_SCALE_FACTOR),
+        )?;
+        Ok(Self::frame_from_pixmap(pixmap))
+    }
+
+    fn frame_from_pixmap(pixmap: Pixmap) -> Arc<RenderImage> {
+        let mut buffer =
+            image::ImageBuffer::from_raw(pixmap.width(), pixmap.height(), pixmap.take()).unwrap();
+
+        for pixel in buffer.chunks_exact_mut(4) {
+            swap_rgba_pa_to_bgra(pixel);
+        }
 
-            let mut image = RenderImage::new(SmallVec::from_const([Frame::new(buffer)]));
-            image.scale_factor = SMOOTH_SVG_SCALE_FACTOR;
-            Arc::new(image)
-        })
+        let mut image = RenderImage::new(SmallVec::from_const([Frame::new(buffer)]));
+        image.scale_factor = SMOOTH_SVG_SCALE_FACTOR;
+        Arc::new(image)
     }
 
     pub(crate) fn render_alpha_mask(
@@ -229,6 +248,10 @@ impl SvgRenderer {
 
     fn render_pixmap(&self, bytes: &[u8], size: SvgSize) -> Result<Pixmap, usvg::Error> {
         let tree = usvg::Tree::from_data(bytes, &self.usvg_options)?;
+        Self::render_tree(&tree, size)
+    }
+
+    fn render_tree(tree: &usvg::Tree, size: SvgSize) -> Result<Pixmap, usvg::Error> {
         let svg_size = tree.size();
         let scale = match size {
             SvgSize::Size(size) => size.width.0 as f32 / svg_size.width(),
@@ -244,7 +267,7 @@ impl SvgRenderer {
 
         let transform = resvg::tiny_skia::Transform::from_scale(scale, scale);
 
-        resvg::render(&tree, transform, &mut pixmap.as_mut());
+        resvg::render(tree, transform, &mut pixmap.as_mut());
 
         Ok(pixmap)
     }
@@ -42,6 +42,10 @@ pub struct OpenPathDelegate {
     render_footer:
         Arc<dyn Fn(&mut Window, &mut Context<Picker<Self>>) -> Option<AnyElement> + 'static>,
     hidden_entries: bool,
+    directory_creation: bool,
+    parent_directory_missing: bool,
+    new_directory: Option<NewDirectory>,
+    create_directory: Task<()>,
 }
 
 impl OpenPathDelegate {
@@ -70,6 +74,10 @@ impl OpenPathDelegate {
             replace_prompt: Task::ready(()),
             render_footer: Arc::new(|_, _| None),
             hidden_entries: false,
+            directory_creation: false,
+            parent_directory_missing: false,
+            new_directory: None,
+            create_directory: Task::ready(()),
         }
     }
 
@@ -87,6 +95,13 @@ impl OpenPathDelegate {
         self.hidden_entries = true;
         self
     }
+
+    /// Offers to create the typed directory when it does not exist yet.
+    pub fn allow_directory_creation(mut self) -> Self {
+        self.directory_creation = true;
+        self
+    }
+
     fn get_entry(&self, selected_match_index: usize) -> Option<CandidateInfo> {
         match &self.directory_state {
             DirectoryState::List { entries, .. } => {
@@ -150,6 +165,80 @@ impl OpenPathDelegate {
         }
     }
 
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn new_directory_candidate(&self) -> Option<(String, Option<SharedString>)> {
+        self.new_directory
+            .as_ref()
+            .map(|new_directory| (new_directory.path.clone(), new_directory.error.clone()))
+    }
+
+    fn confirm_new_directory(&mut self, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let Some(new_directory) = self.new_directory.as_ref() else {
+            return;
+        };
+        let path = new_directory.path.clone();
+        let create_parents = new_directory.create_parents;
+        let confirmation = create_parents.then(|| {
+            window.prompt(
+                gpui::PromptLevel::Info,
+                &format!("Create {path} and its missing parent directories?"),
+                Some("Some of the parent directories of this path do not exist yet."),
+                &["Create Directories", "Cancel"],
+                cx,
+            )
+        });
+
+        self.should_dismiss = false;
+        self.create_directory = cx.spawn_in(window, async move |picker, cx| {
+            if let Some(confirmation) = confirmation
+                && confirmation.await.ok() != Some(0)
+            {
+                picker
+                    .update(cx, |picker, _| picker.delegate.should_dismiss = true)
+                    .ok();
+                return;
+            }
+
+            let Ok(create) = picker.update(cx, |picker, cx| {
+                picker
+                    .delegate
+                    .lister
+                    .create_directory(path.clone(), create_parents, cx)
+            }) else {
+                return;
+            };
+            let result = create.await;
+
+            picker
+                .update(cx, |picker, cx| {
+                    picker.delegate.should_dismiss = true;
+                    match result {
+                        Ok(()) => {
+                            let created_path = PathBuf::from(
+                                picker.delegate.lister.resolve_tilde(&path, cx).as_ref(),
+                            );
+                            if let Some(tx) = picker.delegate.tx.take() {
+                                tx.send(Some(vec![created_path])).ok();
+                            }
+                            cx.emit(gpui::DismissEvent);
+                        }
+                        Err(error) => {
+                            if let Some(new_directory) = picker
+                                .delegate
+                                .new_directory
+                                .as_mut()
+                                .filter(|new_directory| new_directory.path == path)
+                            {
+                                new_directory.error = Some(SharedString::from(error.to_string()));
+                            }
+                            cx.notify();
+                        }
+                    }
+                })
+                .ok();
+        });
+    }
+
     fn current_dir(&self) -> &'static str {
         match self.path_style {
             PathStyle::Posix => "./",
@@ -175,6 +264,14 @@ enum DirectoryState {
     },
 }
 
+/// A directory that does not exist yet, offered for creation in the List state.
+#[derive(Debug, Clone)]
+struct NewDirectory {
+    path: String,
+    create_parents: bool,
+    error: Option<SharedString>,
+}
+
 #[derive(Debug, Clone)]
 struct UserInput {
     file: StringMatchCandidate,
@@ -262,7 +359,7 @@ impl PickerDelegate for OpenPathDelegate {
         } else {
             0
         };
-        self.string_matches.len() + user_input
+        self.string_matches.len() + user_input + self.new_directory.iter().count()
     }
 
     fn selected_index(&self) -> usize {
@@ -322,6 +419,7 @@ impl PickerDelegate for OpenPathDelegate {
 
                 if this
                     .update(cx, |this, _| {
+                        this.delegate.parent_directory_missing = paths.is_err();
                         let new_state = match &this.delegate.directory_state {
                             DirectoryState::None { create: false }
                             | DirectoryState::List { .. } => match paths {
@@ -445,6 +543,7 @@ impl PickerDelegate for OpenPathDelegate {
 
                 this.update(cx, |this, cx| {
                     this.delegate.selected_index = 0;
+                    this.delegate.new_directory = None;
                     this.delegate.string_matches = new_entries
                         .iter()
                         .map(|m| StringMatch {
@@ -515,6 +614,16 @@ impl PickerDelegate for OpenPathDelegate {
 
             this.update(cx, |this, cx| {
                 this.delegate.selected_index = 0;
+                this.delegate.new_directory = (this.delegate.directory_creation
+                    && !is_create_state
+                    && suffix != "."
+                    && suffix != ".."
+                    && !new_entries.iter().any(|entry| entry.path.string == suffix))
+                .then(|| NewDirectory {
+                    path: format!("{dir}{suffix}"),
+                    create_parents: this.delegate.parent_directory_missing,
+                    error: None,
+                });
                 this.delegate.string_matches = matches.clone();
                 this.delegate.string_matches.sort_by_key(|m| {
                     (
@@ -609,6 +718,10 @@ impl PickerDelegate for OpenPathDelegate {
     }
 
     fn confirm(&mut self, _: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        if self.new_directory.is_some() && self.selected_index == self.string_matches.len() {
+            self.confirm_new_directory(window, cx);
+            return;
+        }
         let Some(candidate) = self.get_entry(self.selected_index) else {
             return;
         };
@@ -699,6 +812,12 @@ impl PickerDelegate for OpenPathDelegate {
         window: &mut Window,
         cx: &mut Context<Picker<Self>>,
     ) -> Option<Self::ListItem> {
+        if let Some(new_directory) = &self.new_directory
+            && ix == self.string_matches.len()
+        {
+            return Some(render_new_directory(ix, selected, new_directory));
+        }
+
         let settings = FileFinderSettings::get_global(cx);
         let candidate = self.get_entry(ix)?;
         let mut match_positions = match &self.directory_state {
@@ -866,7 +985,9 @@ impl PickerDelegate for OpenPathDelegate {
         let Some(m) = self.string_matches.first() else {
             return Vec::new();
         };
-        if m.string == self.current_dir() {
+        if self.new_directory.is_some() {
+            vec![self.string_matches.len() - 1]
+        } else if m.string == self.current_dir() {
             vec![0]
         } else {
             Vec::new()
@@ -874,6 +995,31 @@ impl PickerDelegate for OpenPathDelegate {
     }
 }
 
+fn render_new_directory(ix: usize, selected: bool, new_directory: &NewDirectory) -> ListItem {
+    ListItem::new(ix)
+        .spacing(ListItemSpacing::Sparse)
+        .start_slot(Icon::new(IconName::Plus).color(Color::Muted))
+        .inset(true)
+        .toggle_state(selected)
+        .child(
+            v_flex()
+                .child(Label::new(format!(
+                    "Create '{}' and open",
+                    new_directory.path
+                )))
+                .when(new_directory.create_parents, |this| {
+                    this.child(
+                        Label::new("Missing parent directories will also be created")
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                })
+                .when_some(new_directory.error.clone(), |this, error| {
+                    this.child(Label::new(error).size(LabelSize::Small).color(Color::Error))
+                }),
+        )
+}
+
 fn path_candidates(
     parent_path_is_root: bool,
     mut children: Vec<DirectoryItem>,
@@ -1,8 +1,8 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
-use gpui::{AppContext, Entity, TestAppContext, VisualTestContext};
+use gpui::{AppContext, Entity, SharedString, TestAppContext, VisualTestContext};
 use picker::{Picker, PickerDelegate};
-use project::Project;
+use project::{Fs as _, Project};
 use serde_json::json;
 use ui::rems;
 use util::path;
@@ -407,6 +407,71 @@ async fn test_open_path_prompt_with_show_hidden(cx: &mut TestAppContext) {
     assert_eq!(collect_match_candidates(&picker, cx), vec![".hidden"]);
 }
 
+#[gpui::test]
+async fn test_open_path_prompt_creates_missing_directory(cx: &mut TestAppContext) {
+    let app_state = init_test(cx);
+    app_state
+        .fs
+        .as_fake()
+        .insert_tree(
+            path!("/root"),
+            json!({
+                "file": "",
+                "project": {},
+            }),
+        )
+        .await;
+
+    let project = Project::test(app_state.fs.clone(), [path!("/root").as_ref()], cx).await;
+    let (tx, mut rx) = futures::channel::oneshot::channel();
+    let lister = project::DirectoryLister::Project(project.clone());
+    let (multi_workspace, cx) =
+        cx.add_window_view(|window, cx| MultiWorkspace::test_new(project, window, cx));
+    let workspace = multi_workspace.read_with(cx, |mw, _| mw.workspace().clone());
+    let picker = workspace.update_in(cx, |_, window, cx| {
+        let delegate = OpenPathDelegate::new(tx, lister, false, cx).allow_directory_creation();
+        cx.new(|cx| Picker::uniform_list(delegate, window, cx).modal(false))
+    });
+
+    insert_query(path!("/root/project"), &picker, cx).await;
+    assert_eq!(new_directory_candidate(&picker, cx), None);
+
+    insert_query(path!("/root/file/nested"), &picker, cx).await;
+    assert_eq!(
+        new_directory_candidate(&picker, cx),
+        Some((path!("/root/file/nested").to_string(), None))
+    );
+    confirm_new_directory(&picker, cx);
+    cx.simulate_prompt_answer("Create Directories");
+    cx.run_until_parked();
+    let (path, error) = new_directory_candidate(&picker, cx).unwrap();
+    assert_eq!(path, path!("/root/file/nested"));
+    assert!(error.is_some(), "creation failures are shown in the picker");
+    assert!(rx.try_recv().unwrap().is_none());
+
+    insert_query(path!("/root/proj"), &picker, cx).await;
+    assert_eq!(
+        new_directory_candidate(&picker, cx),
+        Some((path!("/root/proj").to_string(), None))
+    );
+    assert_eq!(collect_match_candidates(&picker, cx), vec!["project"]);
+
+    insert_query(path!("/root/new/nested"), &picker, cx).await;
+    confirm_new_directory(&picker, cx);
+    cx.simulate_prompt_answer("Create Directories");
+    cx.run_until_parked();
+    assert!(
+        app_state
+            .fs
+            .is_dir(path!("/root/new/nested").as_ref())
+            .await
+    );
+    assert_eq!(
+        rx.try_recv().unwrap(),
+        Some(Some(vec![PathBuf::from(path!("/root/new/nested"))]))
+    );
+}
+
 fn init_test(cx: &mut TestAppContext) -> Arc<AppState> {
     cx.update(|cx| {
         let state = AppState::test(cx);
@@ -476,6 +541,22 @@ fn confirm_completion(
     })
 }
 
+fn new_directory_candidate(
+    picker: &Entity<Picker<OpenPathDelegate>>,
+    cx: &mut VisualTestContext,
+) -> Option<(String, Option<SharedString>)> {
+    picker.update(cx, |f, _| f.delegate.new_directory_candidate())
+}
+
+fn confirm_new_directory(picker: &Entity<Picker<OpenPathDelegate>>, cx: &mut VisualTestContext) {
+    picker.update_in(cx, |f, window, cx| {
+        let new_directory_index = f.delegate.match_count() - 1;
+        f.delegate
+            .set_selected_index(new_directory_index, window, cx);
+        f.delegate.confirm(false, window, cx);
+    });
+}
+
 fn collect_match_candidates(
     picker: &Entity<Picker<OpenPathDelegate>>,
     cx: &mut VisualTestContext,
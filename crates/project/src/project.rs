@@ -1033,6 +1033,27 @@ impl DirectoryLister {
         }
     }
 
+    pub fn create_directory(
+        &self,
+        path: String,
+        create_parents: bool,
+        cx: &mut App,
+    ) -> Task<Result<()>> {
+        match self {
+            DirectoryLister::Project(project) => project.update(cx, |project, cx| {
+                project.create_directory(path, create_parents, cx)
+            }),
+            DirectoryLister::Local(_, fs) => {
+                let fs = fs.clone();
+                cx.background_spawn(async move {
+                    let expanded = shellexpand::tilde(&path);
+                    fs::create_new_dir(fs.as_ref(), Path::new(expanded.as_ref()), create_parents)
+                        .await
+                })
+            }
+        }
+    }
+
     pub fn path_style(&self, cx: &App) -> PathStyle {
         match self {
             Self::Local(project, ..) | Self::Project(project, ..) => {
@@ -4796,6 +4817,38 @@ impl Project {
         }
     }
 
+    /// Creates a new directory at `path`, on the remote host for remote projects.
+    ///
+    /// Missing parent directories are only created when `create_parents` is set.
+    pub fn create_directory(
+        &self,
+        path: String,
+        create_parents: bool,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        if self.is_local() {
+            DirectoryLister::Local(cx.entity(), self.fs.clone()).create_directory(
+                path,
+                create_parents,
+                cx,
+            )
+        } else if let Some(session) = self.remote_client.as_ref() {
+            let request = proto::CreateRemoteDirectory {
+                dev_server_id: REMOTE_SERVER_PROJECT_ID,
+                path,
+                create_parents,
+            };
+
+            let response = session.read(cx).proto_client().request(request);
+            cx.background_spawn(async move {
+                response.await?;
+                Ok(())
+            })
+        } else {
+            Task::ready(Err(anyhow!("cannot create directory in remote project")))
+        }
+    }
+
     pub fn create_worktree(
         &mut self,
         abs_path: impl AsRef<Path>,
@@ -58,6 +58,7 @@ messages!(
     (CreateChannel, Foreground),
     (CreateChannelResponse, Foreground),
     (CreateProjectEntry, Foreground),
+    (CreateRemoteDirectory, Background),
     (CreateRoom, Foreground),
     (CreateRoomResponse, Foreground),
     (DeclineCall, Foreground),
@@ -423,6 +424,7 @@ request_messages!(
     (GetTypeDefinition, GetTypeDefinitionResponse),
     (LinkedEditingRange, LinkedEditingRangeResponse),
     (ListRemoteDirectory, ListRemoteDirectoryResponse),
+    (CreateRemoteDirectory, Ack),
     (GetUsers, UsersResponse),
     (IncomingCall, Ack),
     (InlayHints, InlayHintsResponse),
@@ -3,11 +3,9 @@ use project::project_settings::ProjectSettings;
 use remote::RemoteConnectionOptions;
 use settings::Settings;
 use ui::{ElevationIndex, Modal, ModalFooter, ModalHeader, Section, prelude::*};
-use workspace::{
-    ModalView, MultiWorkspace, OpenOptions, Workspace, notifications::DetachAndPromptErr,
-};
+use workspace::{ModalView, Workspace};
 
-use crate::open_remote_project;
+use crate::remote_servers::reconnect_to_remote_project;
 
 enum Host {
     CollabGuestProject,
@@ -92,50 +90,13 @@ impl DisconnectedOverlay {
         self.finished = true;
         cx.emit(DismissEvent);
 
-        if let Host::RemoteServerProject(remote_connection_options, _) = &self.host {
-            self.reconnect_to_remote_project(remote_connection_options.clone(), window, cx);
+        if let Host::RemoteServerProject(remote_connection_options, _) = &self.host
+            && let Some(workspace) = self.workspace.upgrade()
+        {
+            reconnect_to_remote_project(remote_connection_options.clone(), workspace, window, cx);
         }
     }
 
-    fn reconnect_to_remote_project(
-        &self,
-        connection_options: RemoteConnectionOptions,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) {
-        let Some(workspace) = self.workspace.upgrade() else {
-            return;
-        };
-
-        let Some(window_handle) = window.window_handle().downcast::<MultiWorkspace>() else {
-            return;
-        };
-
-        let app_state = workspace.read(cx).app_state().clone();
-        let paths = workspace
-            .read(cx)
-            .root_paths(cx)
-            .iter()
-            .map(|path| path.to_path_buf())
-            .collect();
-
-        cx.spawn_in(window, async move |_, cx| {
-            open_remote_project(
-                connection_options,
-                paths,
-                app_state,
-                OpenOptions {
-                    requesting_window: Some(window_handle),
-                    ..Default::default()
-                },
-                cx,
-            )
-            .await?;
-            Ok(())
-        })
-        .detach_and_prompt_err("Failed to reconnect", window, cx, |_, _, _| None);
-    }
-
     fn cancel(&mut self, _: &menu::Cancel, _: &mut Window, cx: &mut Context<Self>) {
         self.finished = true;
         cx.emit(DismissEvent)
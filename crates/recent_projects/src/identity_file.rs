@@ -0,0 +1,228 @@
+use std::path::{Path, PathBuf};
+
+use util::paths::home_dir;
+
+/// Why ssh can't use an identity file that a connection passes to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum IdentityFileProblem {
+    Missing(PathBuf),
+    Unreadable {
+        path: PathBuf,
+        error: String,
+    },
+    /// Other users can access the file, so ssh refuses to use it. Only
+    /// checked on Unix.
+    TooOpen {
+        path: PathBuf,
+        mode: u32,
+    },
+}
+
+impl IdentityFileProblem {
+    /// The identity file, with `~` expanded.
+    pub(crate) fn path(&self) -> &Path {
+        match self {
+            Self::Missing(path) | Self::Unreadable { path, .. } | Self::TooOpen { path, .. } => {
+                path
+            }
+        }
+    }
+
+    pub(crate) fn message(&self) -> String {
+        match self {
+            Self::Missing(path) => {
+                format!("The identity file {} doesn't exist.", path.display())
+            }
+            Self::Unreadable { path, error } => {
+                format!(
+                    "The identity file {} can't be read: {error}",
+                    path.display()
+                )
+            }
+            Self::TooOpen { path, mode } => format!(
+                "The identity file {} can be accessed by other users (permissions {mode:04o}), so ssh will refuse to use it.",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// The identity files that ssh arguments pass with `-i` or
+/// `-o IdentityFile=`, with `~` expanded. Paths with ssh's `%` tokens, and
+/// `none`, are left out since they can't be checked as they are.
+pub(crate) fn identity_files(args: &[String]) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let path = if arg == "-i" {
+            args.next().cloned()
+        } else if let Some(path) = arg.strip_prefix("-i") {
+            Some(path.to_string())
+        } else if arg == "-o" {
+            args.next().and_then(|option| identity_file_option(option))
+        } else if let Some(option) = arg.strip_prefix("-o") {
+            identity_file_option(option)
+        } else {
+            None
+        };
+        if let Some(path) = path
+            && !path.contains('%')
+            && !path.eq_ignore_ascii_case("none")
+        {
+            paths.push(expand_tilde(&path));
+        }
+    }
+    paths
+}
+
+/// The value of an `IdentityFile` option, written as `IdentityFile=path` or
+/// `IdentityFile path`.
+fn identity_file_option(option: &str) -> Option<String> {
+    let (key, value) = option
+        .split_once('=')
+        .or_else(|| option.split_once(char::is_whitespace))?;
+    key.trim()
+        .eq_ignore_ascii_case("IdentityFile")
+        .then(|| value.trim().to_string())
+}
+
+fn expand_tilde(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home_dir().join(rest),
+        None if path == "~" => home_dir().clone(),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Checks that ssh can use the identity file at `path`.
+pub(crate) fn check_identity_file(path: &Path) -> Option<IdentityFileProblem> {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            return Some(IdentityFileProblem::Missing(path.to_path_buf()));
+        }
+        Err(error) => {
+            return Some(IdentityFileProblem::Unreadable {
+                path: path.to_path_buf(),
+                error: error.to_string(),
+            });
+        }
+    };
+    if !metadata.is_file() {
+        return Some(IdentityFileProblem::Unreadable {
+            path: path.to_path_buf(),
+            error: "it isn't a file".to_string(),
+        });
+    }
+    if let Err(error) = std::fs::File::open(path) {
+        return Some(IdentityFileProblem::Unreadable {
+            path: path.to_path_buf(),
+            error: error.to_string(),
+        });
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let mode = metadata.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            return Some(IdentityFileProblem::TooOpen {
+                path: path.to_path_buf(),
+                mode,
+            });
+        }
+    }
+    None
+}
+
+/// The first problem with the identity files `args` pass to ssh.
+pub(crate) fn check_identity_files(args: &[String]) -> Option<IdentityFileProblem> {
+    identity_files(args)
+        .iter()
+        .find_map(|path| check_identity_file(path))
+}
+
+/// Makes the identity file at `path` accessible only to its owner, as ssh
+/// requires. Permissions are only checked on Unix, so elsewhere this fails.
+pub(crate) fn restrict_identity_file_permissions(path: &Path) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Err(std::io::Error::other(
+            "identity file permissions are only restricted on Unix",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_identity_files() {
+        assert_eq!(
+            identity_files(&args(&[
+                "-i",
+                "~/.ssh/work",
+                "-i/keys/attached",
+                "-o",
+                "IdentityFile=/keys/option",
+                "-oidentityfile /keys/lowercase",
+                "-o",
+                "IdentitiesOnly=yes",
+                "-J",
+                "bastion",
+            ])),
+            [
+                home_dir().join(".ssh/work"),
+                PathBuf::from("/keys/attached"),
+                PathBuf::from("/keys/option"),
+                PathBuf::from("/keys/lowercase"),
+            ]
+        );
+        assert!(identity_files(&args(&["-i", "~/.ssh/%h", "-o", "IdentityFile=none"])).is_empty());
+    }
+
+    #[test]
+    fn test_check_identity_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing");
+        assert_eq!(
+            check_identity_file(&missing),
+            Some(IdentityFileProblem::Missing(missing))
+        );
+        assert!(matches!(
+            check_identity_file(dir.path()),
+            Some(IdentityFileProblem::Unreadable { .. })
+        ));
+
+        let key = dir.path().join("id_ed25519");
+        std::fs::write(&key, "key").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+
+            std::fs::set_permissions(&key, std::fs::Permissions::from_mode(0o644)).unwrap();
+            assert_eq!(
+                check_identity_file(&key),
+                Some(IdentityFileProblem::TooOpen {
+                    path: key.clone(),
+                    mode: 0o644,
+                })
+            );
+            restrict_identity_file_permissions(&key).unwrap();
+        }
+        assert_eq!(check_identity_file(&key), None);
+    }
+}
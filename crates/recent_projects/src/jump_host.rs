@@ -0,0 +1,295 @@
+//! Tracks whether the bastions that saved SSH servers jump through can be
+//! reached, so that the servers behind one that's down, such as when off the
+//! VPN, are flagged up front rather than each timing out on connect.
+//!
+//! Each bastion is probed with a TCP connection to its SSH port, once however
+//! many servers depend on it, and at most once per [`PROBE_INTERVAL`] while
+//! the remote projects modal is open.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    pin::pin,
+    time::{Duration, Instant},
+};
+
+use futures::FutureExt as _;
+use gpui::{BackgroundExecutor, Global};
+use remote::parse_jump_hosts;
+use util::command::Stdio;
+
+/// How long a bastion's probe result is trusted before it's probed again.
+pub(crate) const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long a probe waits for the bastion to accept a connection.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The first hop of a server's jump host chain, the one this machine connects
+/// to directly.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct Bastion {
+    pub username: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl fmt::Display for Bastion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.port {
+            Some(port) => write!(f, "{}:{port}", self.host),
+            None => f.write_str(&self.host),
+        }
+    }
+}
+
+/// The bastion a server whose ssh arguments are `args` connects through: the
+/// first hop of its `-J` or `-o ProxyJump` chain, or else of the `ProxyJump`
+/// its host sets in the SSH config.
+pub(crate) fn connection_bastion(
+    args: &[String],
+    ssh_config_proxy_jump: Option<&str>,
+) -> Option<Bastion> {
+    let chain = jump_chain_from_args(args).or(ssh_config_proxy_jump)?;
+    let hop = parse_jump_hosts(chain.trim()).ok()?.into_iter().next()?;
+    Some(Bastion {
+        username: hop.username,
+        host: hop.host,
+        port: hop.port,
+    })
+}
+
+/// The jump host chain set in `args`. Like ssh, the first one set is used.
+fn jump_chain_from_args(args: &[String]) -> Option<&str> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "-J" {
+            return args.next().map(String::as_str);
+        }
+        if let Some(chain) = arg.strip_prefix("-J") {
+            return Some(chain);
+        }
+        let option = if arg == "-o" {
+            args.next().map(String::as_str)
+        } else {
+            arg.strip_prefix("-o")
+        };
+        if let Some((key, value)) = option.and_then(|option| option.split_once(['=', ' ']))
+            && key.trim().eq_ignore_ascii_case("proxyjump")
+        {
+            return Some(value.trim());
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BastionStatus {
+    Reachable,
+    Unreachable,
+}
+
+/// The last probe result of each bastion, shared by every remote projects
+/// modal so that reopening one doesn't probe again.
+#[derive(Debug, Default)]
+pub(crate) struct BastionProbes {
+    results: HashMap<Bastion, (BastionStatus, Instant)>,
+    in_flight: BTreeSet<Bastion>,
+}
+
+impl Global for BastionProbes {}
+
+impl BastionProbes {
+    /// What the last probe of `bastion` found, if it has been probed.
+    pub fn status(&self, bastion: &Bastion) -> Option<BastionStatus> {
+        self.results.get(bastion).map(|(status, _)| *status)
+    }
+
+    /// The bastions among `bastions` that are due a probe at `now`, which are
+    /// marked as being probed until [`BastionProbes::finish`] is called.
+    pub fn start_due(&mut self, bastions: BTreeSet<Bastion>, now: Instant) -> Vec<Bastion> {
+        let due = bastions
+            .into_iter()
+            .filter(|bastion| {
+                !self.in_flight.contains(bastion)
+                    && self.results.get(bastion).is_none_or(|(_, probed_at)| {
+                        now.saturating_duration_since(*probed_at) >= PROBE_INTERVAL
+                    })
+            })
+            .collect::<Vec<_>>();
+        self.in_flight.extend(due.iter().cloned());
+        due
+    }
+
+    pub fn finish(&mut self, bastion: Bastion, status: BastionStatus, now: Instant) {
+        self.in_flight.remove(&bastion);
+        self.results.insert(bastion, (status, now));
+    }
+}
+
+/// Whether `bastion` accepts a TCP connection on its SSH port. The address
+/// is looked up with `ssh -G` first, so that names defined in the SSH config
+/// resolve as they do for ssh. A bastion that hasn't accepted the connection
+/// within [`PROBE_TIMEOUT`] is unreachable.
+pub(crate) async fn probe_bastion(
+    bastion: &Bastion,
+    executor: BackgroundExecutor,
+) -> BastionStatus {
+    let (host, port) = resolve_bastion(bastion).await;
+    let mut connect = pin!(smol::net::TcpStream::connect((host.as_str(), port)).fuse());
+    let mut timeout = executor.timer(PROBE_TIMEOUT).fuse();
+    let reachable = futures::select_biased! {
+        stream = connect => stream.is_ok(),
+        _ = timeout => false,
+    };
+    if reachable {
+        BastionStatus::Reachable
+    } else {
+        BastionStatus::Unreachable
+    }
+}
+
+async fn resolve_bastion(bastion: &Bastion) -> (String, u16) {
+    let destination = match &bastion.username {
+        Some(username) => format!("{username}@{}", bastion.host),
+        None => bastion.host.clone(),
+    };
+    let mut command = util::command::new_command("ssh");
+    if let Some(port) = bastion.port {
+        command.arg("-p").arg(port.to_string());
+    }
+    let resolved = match command
+        .arg("-G")
+        .arg(&destination)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .await
+    {
+        Ok(output) if output.status.success() => {
+            parse_resolved_address(&String::from_utf8_lossy(&output.stdout))
+        }
+        Ok(_) => (None, None),
+        Err(error) => {
+            log::debug!("failed to run ssh -G for {destination}: {error}");
+            (None, None)
+        }
+    };
+    (
+        resolved.0.unwrap_or_else(|| bastion.host.clone()),
+        resolved.1.or(bastion.port).unwrap_or(22),
+    )
+}
+
+/// The `hostname` and `port` in the output of `ssh -G`.
+fn parse_resolved_address(output: &str) -> (Option<String>, Option<u16>) {
+    let mut hostname = None;
+    let mut port = None;
+    for line in output.lines() {
+        if let Some(value) = line.strip_prefix("hostname ") {
+            hostname = Some(value.trim().to_string());
+        } else if let Some(value) = line.strip_prefix("port ") {
+            port = value.trim().parse().ok();
+        }
+    }
+    (hostname, port)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bastion(host: &str) -> Bastion {
+        Bastion {
+            username: None,
+            host: host.to_string(),
+            port: None,
+        }
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_connection_bastion() {
+        assert_eq!(
+            connection_bastion(
+                &args(&["-A", "-J", "jump@bastion.example.com:2222,inner"]),
+                None
+            ),
+            Some(Bastion {
+                username: Some("jump".to_string()),
+                host: "bastion.example.com".to_string(),
+                port: Some(2222),
+            })
+        );
+        assert_eq!(
+            connection_bastion(&args(&["-Jbastion.example.com"]), None),
+            Some(bastion("bastion.example.com"))
+        );
+        assert_eq!(
+            connection_bastion(&args(&["-o", "ProxyJump=bastion.example.com"]), None),
+            Some(bastion("bastion.example.com"))
+        );
+
+        // The arguments take precedence over the SSH config, as they do for ssh.
+        assert_eq!(
+            connection_bastion(&args(&["-J", "arg-bastion"]), Some("config-bastion")),
+            Some(bastion("arg-bastion"))
+        );
+        assert_eq!(
+            connection_bastion(&[], Some("config-bastion")),
+            Some(bastion("config-bastion"))
+        );
+        assert_eq!(connection_bastion(&args(&["-J", "none"]), None), None);
+        assert_eq!(connection_bastion(&args(&["-p", "2222"]), None), None);
+    }
+
+    #[gpui::test]
+    fn test_probes_are_shared_and_rate_limited(cx: &mut gpui::TestAppContext) {
+        let mut probes = BastionProbes::default();
+        let start = cx.executor().now();
+        let bastions = BTreeSet::from([bastion("a"), bastion("b")]);
+
+        assert_eq!(
+            probes.start_due(bastions.clone(), start),
+            [bastion("a"), bastion("b")]
+        );
+        // A bastion isn't probed again while its probe is running.
+        assert!(probes.start_due(bastions.clone(), start).is_empty());
+
+        probes.finish(bastion("a"), BastionStatus::Unreachable, start);
+        probes.finish(bastion("b"), BastionStatus::Reachable, start);
+        assert_eq!(
+            probes.status(&bastion("a")),
+            Some(BastionStatus::Unreachable)
+        );
+        assert!(
+            probes
+                .start_due(bastions.clone(), start + PROBE_INTERVAL / 2)
+                .is_empty()
+        );
+
+        // Once the interval has passed, a bastion that came back is seen as
+        // reachable again.
+        let later = start + PROBE_INTERVAL;
+        assert_eq!(
+            probes.start_due(bastions, later),
+            [bastion("a"), bastion("b")]
+        );
+        probes.finish(bastion("a"), BastionStatus::Reachable, later);
+        assert_eq!(probes.status(&bastion("a")), Some(BastionStatus::Reachable));
+        assert_eq!(probes.status(&bastion("c")), None);
+    }
+
+    #[test]
+    fn test_parse_resolved_address() {
+        let output = "user jump\nhostname 10.0.0.5\nport 2222\nproxyjump none\n";
+        assert_eq!(
+            parse_resolved_address(output),
+            (Some("10.0.0.5".to_string()), Some(2222))
+        );
+        assert_eq!(parse_resolved_address(""), (None, None));
+    }
+}
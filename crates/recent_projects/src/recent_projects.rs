@@ -1,8 +1,14 @@
 mod dev_container_suggest;
 pub mod disconnected_overlay;
+mod identity_file;
+mod jump_host;
+pub mod remote_connection_indicator;
 mod remote_connections;
+pub mod remote_health;
 mod remote_servers;
+mod settings_changes;
 pub mod sidebar_recent_projects;
+mod ssh_agent;
 mod ssh_config;
 
 use std::{
@@ -39,7 +45,7 @@ use settings::{Settings, WorktreeId};
 use ui_input::ErasedEditor;
 use workspace::ProjectGroupKey;
 
-use dev_container::{DevContainerContext, find_devcontainer_configs};
+use dev_container::find_devcontainer_configs;
 use ui::{
     ButtonLike, ContextMenu, Divider, HighlightedLabel, KeyBinding, ListItem, ListItemSpacing,
     ListSubHeader, PopoverMenu, PopoverMenuHandle, TintColor, Tooltip, prelude::*,
@@ -314,10 +320,17 @@ pub fn init(cx: &mut App) {
                 if let Some(util::paths::WslPath { distro, path }) = wsl_path {
                     use remote::WslConnectionOptions;
 
-                    let connection_options = RemoteConnectionOptions::Wsl(WslConnectionOptions {
+                    let mut options = WslConnectionOptions {
                         distro_name: distro.to_string(),
                         user: None,
-                    });
+                        env: Default::default(),
+                    };
+                    cx.update(|_, cx| {
+                        RemoteSettings::get_global(cx)
+                            .fill_wsl_connection_options_from_settings(&mut options)
+                    })
+                    .log_err();
+                    let connection_options = RemoteConnectionOptions::Wsl(options);
 
                     let requesting_window = match create_new_window {
                         false => window_handle,
@@ -373,10 +386,12 @@ pub fn init(cx: &mut App) {
 
     #[cfg(target_os = "windows")]
     cx.on_action(|open_wsl: &remote::OpenWslPath, cx| {
-        let open_wsl = open_wsl.clone();
+        let mut open_wsl = open_wsl.clone();
         with_active_or_new_workspace(cx, move |workspace, window, cx| {
             let fs = workspace.project().read(cx).fs().clone();
             add_wsl_distro(fs, &open_wsl.distro, cx);
+            RemoteSettings::get_global(cx)
+                .fill_wsl_connection_options_from_settings(&mut open_wsl.distro);
             let open_options = OpenOptions {
                 requesting_window: window.window_handle().downcast::<MultiWorkspace>(),
                 ..Default::default()
@@ -474,11 +489,15 @@ pub fn init(cx: &mut App) {
             let handle = cx.entity().downgrade();
             let fs = workspace.project().read(cx).fs().clone();
             workspace.toggle_modal(window, cx, |window, cx| {
-                RemoteServerProjects::new(create_new_window, fs, window, handle, cx)
+                RemoteServerProjects::new_with_defaults(create_new_window, fs, window, handle, cx)
             })
         });
     });
 
+    cx.on_action(|_: &remote_servers::NormalizeSettings, cx| {
+        with_active_or_new_workspace(cx, remote_servers::normalize_remote_settings);
+    });
+
     cx.observe_new(DisconnectedOverlay::register).detach();
 
     cx.on_action(|_: &OpenDevContainer, cx| {
@@ -500,19 +519,9 @@ pub fn init(cx: &mut App) {
 
             let fs = workspace.project().read(cx).fs().clone();
             let configs = find_devcontainer_configs(workspace, cx);
-            let app_state = workspace.app_state().clone();
-            let dev_container_context = DevContainerContext::from_workspace(workspace, cx);
             let handle = cx.entity().downgrade();
             workspace.toggle_modal(window, cx, |window, cx| {
-                RemoteServerProjects::new_dev_container(
-                    fs,
-                    configs,
-                    app_state,
-                    dev_container_context,
-                    window,
-                    handle,
-                    cx,
-                )
+                RemoteServerProjects::new_dev_container(fs, configs, window, handle, cx)
             });
         });
     });
@@ -574,6 +583,8 @@ pub fn add_wsl_distro(
                 distro_name,
                 user,
                 projects: BTreeSet::new(),
+                tag: None,
+                env: None,
             })
         }
     });
@@ -2073,10 +2084,8 @@ impl RecentProjectsDelegate {
                         requesting_window: replace_window,
                         ..Default::default()
                     };
-                    if let RemoteConnectionOptions::Ssh(connection) = &mut connection {
-                        RemoteSettings::get_global(cx)
-                            .fill_connection_options_from_settings(connection);
-                    };
+                    RemoteSettings::get_global(cx)
+                        .fill_remote_connection_options_from_settings(&mut connection);
                     let paths = candidate_workspace_paths.paths().to_vec();
                     cx.spawn_in(window, async move |_, cx| {
                         open_remote_project(connection.clone(), paths, app_state, open_options, cx)
@@ -0,0 +1,209 @@
+use gpui::{
+    Anchor, App, Context, Empty, Entity, EventEmitter, IntoElement, Render, Subscription, TaskExt,
+    WeakEntity, Window,
+};
+use project::Project;
+use remote::{ConnectionState, RemoteConnectionOptions};
+use settings::Settings;
+use ui::{ButtonLike, ContextMenu, IconWithIndicator, PopoverMenu, Tooltip, prelude::*};
+use workspace::{
+    HideStatusItem, StatusBarSettings, StatusItemView, ToolbarItemEvent, Workspace,
+    item::ItemHandle, notifications::DetachAndPromptErr,
+};
+
+use crate::remote_health::RemoteHealth;
+use crate::remote_servers::{
+    disconnect_dev_container_now, open_remote_servers_for, reconnect_to_remote_project,
+    stop_dev_container_now,
+};
+
+/// Status bar item showing the server a remote project is connected to.
+pub struct RemoteConnectionIndicator {
+    workspace: WeakEntity<Workspace>,
+    project: Entity<Project>,
+    _observe_remote_client: Option<Subscription>,
+}
+
+impl RemoteConnectionIndicator {
+    pub fn new(workspace: &Workspace, cx: &mut Context<Self>) -> Self {
+        let project = workspace.project().clone();
+        let observe_remote_client = project
+            .read(cx)
+            .remote_client()
+            .map(|remote_client| cx.observe(&remote_client, |_, _, cx| cx.notify()));
+        Self {
+            workspace: workspace.weak_handle(),
+            project,
+            _observe_remote_client: observe_remote_client,
+        }
+    }
+
+    fn build_menu(
+        &self,
+        connection_options: RemoteConnectionOptions,
+        state: ConnectionState,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Entity<ContextMenu> {
+        let workspace = self.workspace.clone();
+        let project = self.project.clone();
+        let dev_container = match &connection_options {
+            RemoteConnectionOptions::Docker(options) => Some(options.clone()),
+            _ => None,
+        };
+        ContextMenu::build(window, cx, move |menu, _, _| {
+            let menu = menu
+                .entry("Open Remote Servers", None, {
+                    let workspace = workspace.clone();
+                    let connection_options = connection_options.clone();
+                    move |window, cx| {
+                        if let Some(workspace) = workspace.upgrade() {
+                            open_remote_servers_for(
+                                connection_options.clone(),
+                                workspace,
+                                window,
+                                cx,
+                            );
+                        }
+                    }
+                })
+                .separator()
+                .entry("Reconnect", None, {
+                    let workspace = workspace.clone();
+                    move |window, cx| {
+                        if state == ConnectionState::Disconnected {
+                            if let Some(workspace) = workspace.upgrade() {
+                                reconnect_to_remote_project(
+                                    connection_options.clone(),
+                                    workspace,
+                                    window,
+                                    cx,
+                                );
+                            }
+                        } else if let Some(remote_client) = project.read(cx).remote_client() {
+                            // Dropping the connection makes the client reconnect
+                            // on its own, keeping the project's state.
+                            remote_client
+                                .update(cx, |remote_client, cx| remote_client.force_disconnect(cx))
+                                .detach_and_log_err(cx);
+                        }
+                    }
+                })
+                .entry("Disconnect", None, |window, _| {
+                    // A remote project can't outlive its connection, so
+                    // disconnecting closes the window it's open in.
+                    window.remove_window();
+                });
+            let Some(dev_container) = dev_container else {
+                return menu;
+            };
+            menu.separator()
+                .entry("Return to Host", None, {
+                    let workspace = workspace.clone();
+                    let dev_container = dev_container.clone();
+                    move |window, cx| {
+                        if let Some(workspace) = workspace.upgrade() {
+                            disconnect_dev_container_now(&dev_container, workspace, window, cx)
+                                .detach_and_prompt_err(
+                                    "Failed to return to the host",
+                                    window,
+                                    cx,
+                                    |_, _, _| None,
+                                );
+                        }
+                    }
+                })
+                .entry("Stop Dev Container", None, move |window, cx| {
+                    if let Some(workspace) = workspace.upgrade() {
+                        stop_dev_container_now(dev_container.clone(), workspace, window, cx);
+                    }
+                })
+        })
+    }
+}
+
+impl Render for RemoteConnectionIndicator {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if !StatusBarSettings::get_global(cx).remote_connection_button {
+            return Empty.into_any_element();
+        }
+
+        let project = self.project.read(cx);
+        let (Some(connection_options), Some(state)) = (
+            project.remote_connection_options(cx),
+            project.remote_connection_state(cx),
+        ) else {
+            return Empty.into_any_element();
+        };
+
+        let host = connection_options.display_name();
+        let (label, icon) = match &connection_options {
+            RemoteConnectionOptions::Ssh(options) => (
+                options.nickname.clone().unwrap_or_else(|| host.clone()),
+                IconName::Server,
+            ),
+            RemoteConnectionOptions::Wsl(_) => (host.clone(), IconName::Linux),
+            RemoteConnectionOptions::Docker(_) => (host.clone(), IconName::Box),
+            #[cfg(any(test, feature = "test-support"))]
+            RemoteConnectionOptions::Mock(_) => (host.clone(), IconName::Server),
+        };
+
+        let tooltip = match state {
+            ConnectionState::Connecting => format!("Connecting to {host}"),
+            ConnectionState::Connected => format!("Connected to {host}"),
+            ConnectionState::HeartbeatMissed | ConnectionState::Reconnecting => {
+                format!("Reconnecting to {host}")
+            }
+            ConnectionState::Disconnected => format!("Disconnected from {host}"),
+        };
+
+        let this = cx.weak_entity();
+        PopoverMenu::new("remote-connection-menu")
+            .menu(move |window, cx| {
+                this.update(cx, |this, cx| {
+                    this.build_menu(connection_options.clone(), state, window, cx)
+                })
+                .ok()
+            })
+            .anchor(Anchor::BottomLeft)
+            .trigger_with_tooltip(
+                ButtonLike::new("remote-connection-indicator").child(
+                    h_flex()
+                        .gap_1()
+                        .child(
+                            IconWithIndicator::new(
+                                Icon::new(icon).size(IconSize::Small),
+                                Some(RemoteHealth::from(state).indicator()),
+                            )
+                            .indicator_border_color(Some(
+                                cx.theme().colors().status_bar_background,
+                            )),
+                        )
+                        .child(Label::new(label).size(LabelSize::Small).truncate()),
+                ),
+                Tooltip::text(tooltip),
+            )
+            .into_any_element()
+    }
+}
+
+impl EventEmitter<ToolbarItemEvent> for RemoteConnectionIndicator {}
+
+impl StatusItemView for RemoteConnectionIndicator {
+    fn set_active_pane_item(
+        &mut self,
+        _active_pane_item: Option<&dyn ItemHandle>,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) {
+    }
+
+    fn hide_setting(&self, _: &App) -> Option<HideStatusItem> {
+        Some(HideStatusItem::new(|settings| {
+            settings
+                .status_bar
+                .get_or_insert_default()
+                .remote_connection_button = Some(false);
+        }))
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeSet,
     path::{Path, PathBuf},
     sync::Arc,
 };
@@ -8,19 +9,25 @@ use askpass::EncryptedPassword;
 use editor::Editor;
 use extension_host::ExtensionStore;
 use futures::{FutureExt as _, channel::oneshot, select};
-use gpui::{AppContext, AsyncApp, PromptLevel, WindowHandle};
+use gpui::{App, AppContext, AsyncApp, PromptLevel, SharedString, Task, WeakEntity, WindowHandle};
 
-use project::trusted_worktrees;
+use crate::jump_host::connection_bastion;
+use project::{Fs, trusted_worktrees};
 use remote::{
-    DockerConnectionOptions, Interactive, RemoteConnection, RemoteConnectionOptions,
-    SshConnectionOptions,
+    DockerConnectionOptions, IncompatibleServerVersion, Interactive, PortForward,
+    PortForwardStatus, RemoteConnection, RemoteConnectionOptions, RemotePlatform,
+    SshConnectionOptions, WslConnectionOptions,
 };
 pub use settings::SshConnection;
-use settings::{DevContainerConnection, ExtendingVec, RegisterSetting, Settings, WslConnection};
+use settings::{
+    DevContainerConnection, ExtendingVec, RegisterSetting, RemoteProject,
+    RemoteServersDefaultAction, RemoteServersDensity, RemoteServersSection, Settings,
+    WslConnection, update_settings_file,
+};
 use util::paths::PathWithPosition;
 use workspace::{
-    AppState, MultiWorkspace, OpenOptions, SerializedWorkspaceLocation, Workspace,
-    find_existing_workspace,
+    AppState, MultiWorkspace, OpenOptions, SerializedWorkspaceLocation, Toast, Workspace,
+    find_existing_workspace, notifications::NotificationId,
 };
 
 pub use remote_connection::{
@@ -32,8 +39,20 @@ pub use remote_connection::{
 pub struct RemoteSettings {
     pub ssh_connections: ExtendingVec<SshConnection>,
     pub wsl_connections: ExtendingVec<WslConnection>,
+    pub dev_container_connections: Vec<DevContainerConnection>,
     /// Whether to read ~/.ssh/config for ssh connection sources.
     pub read_ssh_config: bool,
+    /// Which section of the remote projects modal is focused when it opens.
+    pub default_section: RemoteServersSection,
+    /// What the remote projects modal does as soon as it opens.
+    pub default_action: RemoteServersDefaultAction,
+    /// How much room each server takes up in the remote projects modal.
+    pub density: RemoteServersDensity,
+    /// Whether the remote projects modal opens projects in a new window.
+    pub open_in_new_window: bool,
+    /// Whether the remote projects modal probes the jump hosts of saved SSH
+    /// servers.
+    pub check_jump_hosts: bool,
 }
 
 impl RemoteSettings {
@@ -47,19 +66,61 @@ impl RemoteSettings {
 
     pub fn fill_connection_options_from_settings(&self, options: &mut SshConnectionOptions) {
         for conn in self.ssh_connections() {
-            if conn.host == options.host.to_string()
-                && conn.username == options.username
-                && conn.port == options.port
-            {
+            if is_saved_connection_for(&conn, options) {
                 options.nickname = conn.nickname;
                 options.upload_binary_over_ssh = conn.upload_binary_over_ssh.unwrap_or_default();
                 options.args = Some(conn.args);
                 options.port_forwards = conn.port_forwards;
+                options.keepalive_interval = conn.keepalive_interval;
+                options.keepalive_count_max = conn.keepalive_count_max;
+                options.shell = conn.shell;
+                options.login_shell = conn.login_shell;
+                options.env = conn.env.unwrap_or_default();
                 break;
             }
         }
     }
 
+    pub fn fill_wsl_connection_options_from_settings(&self, options: &mut WslConnectionOptions) {
+        if let Some(conn) = self
+            .wsl_connections()
+            .find(|conn| conn.distro_name == options.distro_name && conn.user == options.user)
+        {
+            options.env = conn.env.unwrap_or_default();
+        }
+    }
+
+    /// Fills in what the saved entry for the server `options` connects to
+    /// configures, for connections restored without it, such as from a
+    /// recent project.
+    pub fn fill_remote_connection_options_from_settings(
+        &self,
+        options: &mut RemoteConnectionOptions,
+    ) {
+        match options {
+            RemoteConnectionOptions::Ssh(options) => {
+                self.fill_connection_options_from_settings(options)
+            }
+            RemoteConnectionOptions::Wsl(options) => {
+                self.fill_wsl_connection_options_from_settings(options)
+            }
+            RemoteConnectionOptions::Docker(options) => {
+                if let Some(conn) = self
+                    .dev_container_connections
+                    .iter()
+                    .find(|conn| conn.container_id == options.container_id)
+                {
+                    options.remote_user = conn.remote_user.clone();
+                    options.remote_env = conn.remote_env.clone();
+                    options.remote_env.extend(conn.extra_env.clone());
+                    options.shell = conn.shell.clone();
+                    options.detected_shell = conn.detected_shell.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+
     pub fn connection_options_for(
         &self,
         host: String,
@@ -77,6 +138,556 @@ impl RemoteSettings {
     }
 }
 
+/// Whether `connection` is the saved entry for the server `options` connects to.
+pub(crate) fn is_saved_connection_for(
+    connection: &SshConnection,
+    options: &SshConnectionOptions,
+) -> bool {
+    connection.host == options.host.to_string()
+        && connection.username == options.username
+        && connection.port == options.port
+}
+
+/// The text a saved SSH server is found by when searching the server list:
+/// its nickname, user, host, port, tag, the jump host it connects through,
+/// and the paths of its projects.
+pub(crate) fn ssh_connection_search_haystack(connection: &SshConnection) -> String {
+    let port = connection.port.map(|port| port.to_string());
+    let bastion = connection_bastion(&connection.args, None).map(|bastion| bastion.host);
+    search_haystack(
+        [
+            connection.nickname.as_deref(),
+            connection.username.as_deref(),
+            Some(connection.host.as_str()),
+            port.as_deref(),
+            connection.tag.as_deref(),
+            bastion.as_deref(),
+        ],
+        &connection.projects,
+    )
+}
+
+/// The text a saved WSL distro is found by when searching the server list:
+/// its name, user, tag, and the paths of its projects.
+pub(crate) fn wsl_connection_search_haystack(connection: &WslConnection) -> String {
+    search_haystack(
+        [
+            Some(connection.distro_name.as_str()),
+            connection.user.as_deref(),
+            connection.tag.as_deref(),
+        ],
+        &connection.projects,
+    )
+}
+
+/// The text a saved dev container is found by when searching the server list:
+/// its name, container id, tag, and the project folder it was started from.
+pub(crate) fn dev_container_connection_search_haystack(
+    connection: &DevContainerConnection,
+) -> String {
+    search_haystack(
+        [
+            Some(connection.name.as_str()),
+            Some(connection.container_id.as_str()),
+            connection.tag.as_deref(),
+            connection.host_project_root.as_deref(),
+        ],
+        &BTreeSet::new(),
+    )
+}
+
+fn search_haystack<'a>(
+    fields: impl IntoIterator<Item = Option<&'a str>>,
+    projects: &'a BTreeSet<RemoteProject>,
+) -> String {
+    fields
+        .into_iter()
+        .flatten()
+        .chain(
+            projects
+                .iter()
+                .flat_map(|project| project.paths.iter().map(String::as_str)),
+        )
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Whether every whitespace-separated term of `query` appears in `haystack`,
+/// ignoring case. An empty query matches everything.
+pub(crate) fn matches_search_query(haystack: &str, query: &str) -> bool {
+    let haystack = haystack.to_lowercase();
+    query
+        .split_whitespace()
+        .all(|term| haystack.contains(&term.to_lowercase()))
+}
+
+/// The platform recorded for a saved server, such as `linux-x86_64`, or
+/// `None` if Zed has never connected to it.
+pub fn ssh_platform_label(connection: &SshConnection) -> Option<SharedString> {
+    let os = connection.remote_os.as_ref()?;
+    let arch = connection.remote_arch.as_ref()?;
+    Some(format!("{os}-{arch}").into())
+}
+
+/// The remote server version recorded for a saved server, such as
+/// `server 0.201.3`, or `None` if Zed has never connected to it.
+pub fn ssh_server_version_label(connection: &SshConnection) -> Option<SharedString> {
+    let version = connection.remote_server_version.as_ref()?;
+    Some(format!("server {version}").into())
+}
+
+/// Records the platform and remote server version of a freshly connected
+/// server on its saved entry, replacing the values from any earlier
+/// connection. Servers that aren't saved are left alone, as is the recorded
+/// version when the connection doesn't know it.
+pub fn record_ssh_server_info(
+    options: &SshConnectionOptions,
+    platform: RemotePlatform,
+    server_version: Option<String>,
+    cx: &mut App,
+) {
+    let remote_os = platform.os.as_str();
+    let remote_arch = platform.arch.as_str();
+    let is_stale = RemoteSettings::get_global(cx)
+        .ssh_connections
+        .0
+        .iter()
+        .any(|connection| {
+            is_saved_connection_for(connection, options)
+                && (connection.remote_os.as_deref() != Some(remote_os)
+                    || connection.remote_arch.as_deref() != Some(remote_arch)
+                    || server_version.is_some()
+                        && connection.remote_server_version != server_version)
+        });
+    if !is_stale {
+        return;
+    }
+
+    let options = options.clone();
+    update_settings_file(<dyn Fs>::global(cx), cx, move |settings, _| {
+        for connection in settings.remote.ssh_connections.iter_mut().flatten() {
+            if is_saved_connection_for(connection, &options) {
+                connection.remote_os = Some(remote_os.to_string());
+                connection.remote_arch = Some(remote_arch.to_string());
+                if server_version.is_some() {
+                    connection.remote_server_version = server_version.clone();
+                }
+            }
+        }
+    });
+}
+
+/// Records the shell detected in a freshly connected dev container on its
+/// saved entry, so that the next connection doesn't probe for it again.
+/// Containers with a configured shell, or that aren't saved, are left alone.
+pub fn record_dev_container_shell(options: &DockerConnectionOptions, shell: String, cx: &mut App) {
+    if options.shell.is_some() || options.detected_shell.as_ref() == Some(&shell) {
+        return;
+    }
+    let is_saved = RemoteSettings::get_global(cx)
+        .dev_container_connections
+        .iter()
+        .any(|conn| conn.container_id == options.container_id);
+    if !is_saved {
+        return;
+    }
+
+    let container_id = options.container_id.clone();
+    update_settings_file(<dyn Fs>::global(cx), cx, move |settings, _| {
+        for conn in settings
+            .remote
+            .dev_container_connections
+            .iter_mut()
+            .flatten()
+        {
+            if conn.container_id == container_id && conn.shell.is_none() {
+                conn.detected_shell = Some(shell.clone());
+            }
+        }
+    });
+}
+
+/// Describes the port forwards that couldn't listen, like
+/// `8080→remote:80 failed: address in use`, or `None` if none failed.
+pub(crate) fn failed_port_forwards_message(port_forwards: &[PortForward]) -> Option<String> {
+    let failures = port_forwards
+        .iter()
+        .filter_map(|forward| match &forward.status {
+            PortForwardStatus::Failed(reason) => {
+                Some(format!("{} failed: {reason}", forward.label()))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if failures.is_empty() {
+        None
+    } else {
+        Some(failures.join(", "))
+    }
+}
+
+struct PortForwardsFailed;
+
+/// Shows a toast for the connection's port forwards that couldn't listen,
+/// offering to retry them.
+pub(crate) fn show_failed_port_forwards(
+    workspace: WeakEntity<Workspace>,
+    connection: Arc<dyn RemoteConnection>,
+    cx: &mut App,
+) {
+    let Some(message) = failed_port_forwards_message(&connection.port_forwards()) else {
+        return;
+    };
+    let toast = Toast::new(NotificationId::unique::<PortForwardsFailed>(), message).on_click(
+        "Retry Forwards",
+        {
+            let workspace = workspace.clone();
+            move |_, cx| retry_port_forwards(workspace.clone(), connection.clone(), cx)
+        },
+    );
+    workspace
+        .update(cx, |workspace, cx| workspace.show_toast(toast, cx))
+        .ok();
+}
+
+/// Tries the connection's failed port forwards again, showing a toast with
+/// the outcome.
+pub(crate) fn retry_port_forwards(
+    workspace: WeakEntity<Workspace>,
+    connection: Arc<dyn RemoteConnection>,
+    cx: &mut App,
+) {
+    cx.spawn(async move |cx| {
+        connection.retry_port_forwards().await?;
+        cx.update(|cx| {
+            if connection
+                .port_forwards()
+                .iter()
+                .any(PortForward::is_failed)
+            {
+                show_failed_port_forwards(workspace, connection, cx);
+            } else {
+                workspace
+                    .update(cx, |workspace, cx| {
+                        workspace.show_toast(
+                            Toast::new(
+                                NotificationId::unique::<PortForwardsFailed>(),
+                                "All port forwards are listening",
+                            )
+                            .autohide(),
+                            cx,
+                        )
+                    })
+                    .ok();
+            }
+        });
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Whether `error` says the server couldn't download the remote server binary
+/// itself, which uploading the binary over SSH works around.
+fn is_server_binary_download_failure(error: &anyhow::Error) -> bool {
+    let error = format!("{error:#}").to_lowercase();
+    // Zed downloading the binary locally failing isn't fixed by uploading it.
+    if error.contains("downloading server binary locally") {
+        return false;
+    }
+    error.contains("download")
+        && ["server binary", "remote development server", "curl", "wget"]
+            .iter()
+            .any(|needle| error.contains(needle))
+}
+
+/// A suggestion for connecting to `connection_options` after it failed with
+/// `error`, when the failure is one a server option works around.
+pub(crate) fn connection_error_hint(
+    connection_options: &RemoteConnectionOptions,
+    error: &anyhow::Error,
+) -> Option<&'static str> {
+    match connection_options {
+        RemoteConnectionOptions::Ssh(options)
+            if !options.upload_binary_over_ssh && is_server_binary_download_failure(error) =>
+        {
+            Some(
+                "The server could not download the Zed remote server. If it has no internet \
+                access, turn on \"Upload Server Binary Over SSH\" in the server's options.",
+            )
+        }
+        _ => None,
+    }
+}
+
+/// The error shown when connecting to `connection_options` failed, followed
+/// by a hint when there is one.
+pub(crate) fn connection_error_detail(
+    connection_options: &RemoteConnectionOptions,
+    error: &anyhow::Error,
+) -> String {
+    match connection_error_hint(connection_options, error) {
+        Some(hint) => format!("{error:#}\n\n{hint}"),
+        None => format!("{error:#}"),
+    }
+}
+
+/// What ssh reported when a server's host key no longer matches the one in
+/// `known_hosts`, as happens after the server is reinstalled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HostKeyChange {
+    /// The host as `known_hosts` names it, such as `example.com` or
+    /// `[example.com]:2222`.
+    known_host: String,
+    /// The fingerprint of the key the server sent.
+    fingerprint: Option<String>,
+    /// The `known_hosts` file holding the old key.
+    known_hosts_file: Option<PathBuf>,
+    /// The line of the old key in `known_hosts_file`.
+    known_hosts_line: Option<u32>,
+}
+
+impl HostKeyChange {
+    fn detail(&self) -> String {
+        let mut detail = format!(
+            "The host key of {} has changed since you last connected.\n",
+            self.known_host
+        );
+        if let Some(fingerprint) = &self.fingerprint {
+            detail.push_str(&format!("\nNew key: {fingerprint}"));
+        }
+        match (&self.known_hosts_file, self.known_hosts_line) {
+            (Some(file), Some(line)) => {
+                detail.push_str(&format!("\nOld key: {}, line {line}", file.display()))
+            }
+            (Some(file), None) => detail.push_str(&format!("\nOld key: {}", file.display())),
+            _ => {}
+        }
+        detail
+    }
+}
+
+/// The host key change `error` reports, if connecting to `connection_options`
+/// failed because the server's key differs from the one in `known_hosts`.
+/// Every other failure, including a key that was never seen, gives `None`.
+pub(crate) fn host_key_change(
+    connection_options: &RemoteConnectionOptions,
+    error: &anyhow::Error,
+) -> Option<HostKeyChange> {
+    let RemoteConnectionOptions::Ssh(options) = connection_options else {
+        return None;
+    };
+    let error = format!("{error:#}");
+    if !error
+        .to_lowercase()
+        .contains("remote host identification has changed")
+    {
+        return None;
+    }
+
+    let mut known_host = None;
+    let mut fingerprint = None;
+    let mut known_hosts_file = None;
+    let mut known_hosts_line = None;
+    let mut lines = error.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        if line.starts_with("The fingerprint for the") {
+            fingerprint = lines
+                .next()
+                .filter(|fingerprint| !fingerprint.is_empty())
+                // ssh ends the fingerprint with a period.
+                .map(|fingerprint| fingerprint.trim_end_matches('.').to_string());
+        } else if let Some(location) = line
+            .strip_prefix("Offending ")
+            .and_then(|line| line.split_once(" in "))
+            .map(|(_, location)| location)
+        {
+            match location.rsplit_once(':') {
+                Some((file, line)) if line.parse::<u32>().is_ok() => {
+                    known_hosts_file = Some(PathBuf::from(file));
+                    known_hosts_line = line.parse().ok();
+                }
+                _ => known_hosts_file = Some(PathBuf::from(location)),
+            }
+        } else if line.starts_with("ssh-keygen ") {
+            // ssh suggests the exact command, naming the host as it appears
+            // in `known_hosts`.
+            known_host = line
+                .rsplit_once(" -R ")
+                .map(|(_, host)| host.trim_matches(['\'', '"']).to_string());
+        } else if known_host.is_none()
+            && let Some(host) = line.strip_prefix("Host key for ").and_then(|line| {
+                line.strip_suffix(" has changed and you have requested strict checking.")
+            })
+        {
+            known_host = Some(host.to_string());
+        }
+    }
+
+    let known_host = known_host.unwrap_or_else(|| {
+        let host = options.host.to_string();
+        match options.port {
+            Some(port) if port != 22 => format!("[{host}]:{port}"),
+            _ => host,
+        }
+    });
+    Some(HostKeyChange {
+        known_host,
+        fingerprint,
+        known_hosts_file,
+        known_hosts_line,
+    })
+}
+
+/// Removes the old key of `change` from `known_hosts` with `ssh-keygen -R`,
+/// which also finds hashed entries.
+async fn forget_host_key(change: &HostKeyChange) -> Result<()> {
+    let mut command = util::command::new_command("ssh-keygen");
+    if let Some(file) = &change.known_hosts_file {
+        command.arg("-f").arg(file);
+    }
+    let output = command
+        .arg("-R")
+        .arg(&change.known_host)
+        .output()
+        .await
+        .context("running ssh-keygen")?;
+    anyhow::ensure!(
+        output.status.success(),
+        "ssh-keygen -R {} failed: {}",
+        change.known_host,
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(())
+}
+
+/// Offers to remove the old key of `change` after a connection failed because
+/// of it. The key is only removed once the user confirms a warning that the
+/// change may be an attack. Resolves to whether to connect again.
+async fn recover_from_host_key_change(
+    change: &HostKeyChange,
+    window: WindowHandle<MultiWorkspace>,
+    cx: &mut AsyncApp,
+) -> Result<bool> {
+    let response = window
+        .update(cx, |_, window, cx| {
+            window.prompt(
+                PromptLevel::Critical,
+                "Failed to connect over SSH",
+                Some(&change.detail()),
+                &["Remove Old Key and Retry", "Cancel"],
+                cx,
+            )
+        })?
+        .await;
+    if response != Ok(0) {
+        return Ok(false);
+    }
+
+    let warning = format!(
+        "Only continue if you know why the key of {} changed, for example because the server \
+        was reinstalled. Otherwise someone may be intercepting the connection, and would see \
+        everything sent to the server.",
+        change.known_host
+    );
+    let response = window
+        .update(cx, |_, window, cx| {
+            window.prompt(
+                PromptLevel::Warning,
+                "Remove the old host key?",
+                Some(&warning),
+                &["Remove Key and Retry", "Cancel"],
+                cx,
+            )
+        })?
+        .await;
+    if response != Ok(0) {
+        return Ok(false);
+    }
+
+    match forget_host_key(change).await {
+        Ok(()) => Ok(true),
+        Err(error) => {
+            log::error!("failed to remove host key: {error:#}");
+            window
+                .update(cx, |_, window, cx| {
+                    window.prompt(
+                        PromptLevel::Critical,
+                        "Failed to remove the old host key",
+                        Some(&format!("{error:#}")),
+                        &["Ok"],
+                        cx,
+                    )
+                })?
+                .await
+                .ok();
+            Ok(false)
+        }
+    }
+}
+
+/// Copies the extra arguments saved for a dev container started from the same
+/// `devcontainer.json` as `connection`, so they apply whenever it's started
+/// again, even as a new container.
+pub(crate) fn apply_saved_dev_container_args(connection: &mut DevContainerConnection, cx: &App) {
+    let saved = RemoteSettings::get_global(cx)
+        .dev_container_connections
+        .iter()
+        .find(|saved| {
+            saved.container_id == connection.container_id
+                || (saved.config_path.is_some()
+                    && saved.config_path == connection.config_path
+                    && saved.host_project_root == connection.host_project_root)
+        });
+    if let Some(saved) = saved {
+        connection.extra_exec_args = saved.extra_exec_args.clone();
+        connection.extra_cli_args = saved.extra_cli_args.clone();
+        connection.extra_env = saved.extra_env.clone();
+    }
+}
+
+/// Switches the saved dev container `container_id` to podman or docker, or,
+/// given `None`, to whichever of them can see the container, for containers
+/// that moved between runtimes. Resolves to whether podman was recorded.
+pub fn set_dev_container_runtime(
+    container_id: String,
+    use_podman: Option<bool>,
+    cx: &mut App,
+) -> Task<Result<bool>> {
+    let host = dev_container::DockerHost::from_wsl_distro(
+        RemoteSettings::get_global(cx)
+            .dev_container_connections
+            .iter()
+            .find(|connection| connection.container_id == container_id)
+            .and_then(|connection| connection.wsl_distro.clone()),
+    );
+    cx.spawn(async move |cx| {
+        let use_podman = match use_podman {
+            Some(use_podman) => use_podman,
+            None => dev_container::detect_container_runtime(&container_id, &host)
+                .await
+                .with_context(|| {
+                    format!("Neither docker nor podman can see container {container_id}")
+                })?,
+        };
+        cx.update(|cx| {
+            update_settings_file(<dyn Fs>::global(cx), cx, move |settings, _| {
+                for connection in settings
+                    .remote
+                    .dev_container_connections
+                    .iter_mut()
+                    .flatten()
+                {
+                    if connection.container_id == container_id {
+                        connection.use_podman = use_podman;
+                    }
+                }
+            });
+        });
+        Ok(use_podman)
+    })
+}
+
 #[derive(Clone, PartialEq)]
 pub enum Connection {
     Ssh(SshConnection),
@@ -90,13 +701,19 @@ impl From<Connection> for RemoteConnectionOptions {
             Connection::Ssh(conn) => RemoteConnectionOptions::Ssh(conn.into()),
             Connection::Wsl(conn) => RemoteConnectionOptions::Wsl(conn.into()),
             Connection::DevContainer(conn) => {
+                let mut remote_env = conn.remote_env;
+                remote_env.extend(conn.extra_env);
                 RemoteConnectionOptions::Docker(DockerConnectionOptions {
                     name: conn.name,
                     remote_user: conn.remote_user,
                     container_id: conn.container_id,
                     upload_binary_over_docker_exec: false,
                     use_podman: conn.use_podman,
-                    remote_env: conn.remote_env,
+                    wsl_distro: conn.wsl_distro,
+                    remote_env,
+                    shell: conn.shell,
+                    detected_shell: conn.detected_shell,
+                    extra_exec_args: conn.extra_exec_args,
                 })
             }
         }
@@ -121,7 +738,13 @@ impl Settings for RemoteSettings {
         Self {
             ssh_connections: remote.ssh_connections.clone().unwrap_or_default().into(),
             wsl_connections: remote.wsl_connections.clone().unwrap_or_default().into(),
+            dev_container_connections: remote.dev_container_connections.clone().unwrap_or_default(),
             read_ssh_config: remote.read_ssh_config.unwrap(),
+            default_section: remote.remote_servers_default_section.unwrap_or_default(),
+            default_action: remote.remote_servers_default_action.unwrap_or_default(),
+            density: remote.remote_servers_density.unwrap_or_default(),
+            open_in_new_window: remote.remote_servers_open_in_new_window.unwrap_or_default(),
+            check_jump_hosts: remote.remote_servers_check_jump_hosts.unwrap_or(true),
         }
     }
 }
@@ -302,7 +925,19 @@ pub async fn open_remote_project(
             result = connection.fuse() => result,
         };
         let remote_connection = match connection {
-            Ok(connection) => connection,
+            Ok(connection) => {
+                if let RemoteConnectionOptions::Ssh(options) = &connection_options
+                    && let Some(platform) = connection.platform()
+                {
+                    let server_version = connection.server_version();
+                    cx.update(|cx| record_ssh_server_info(options, platform, server_version, cx));
+                }
+                if let RemoteConnectionOptions::Docker(options) = &connection_options {
+                    let shell = connection.shell();
+                    cx.update(|cx| record_dev_container_shell(options, shell, cx));
+                }
+                connection
+            }
             Err(e) => {
                 initial_workspace.update(cx, |workspace, cx| {
                     if let Some(ui) = workspace.active_modal::<RemoteConnectionModal>(cx) {
@@ -310,29 +945,41 @@ pub async fn open_remote_project(
                     }
                 });
                 log::error!("Failed to open project: {e:#}");
-                let response = window
-                    .update(cx, |_, window, cx| {
-                        window.prompt(
-                            PromptLevel::Critical,
-                            match connection_options {
-                                RemoteConnectionOptions::Ssh(_) => "Failed to connect over SSH",
-                                RemoteConnectionOptions::Wsl(_) => "Failed to connect to WSL",
-                                RemoteConnectionOptions::Docker(_) => {
-                                    "Failed to connect to Dev Container"
-                                }
-                                #[cfg(any(test, feature = "test-support"))]
-                                RemoteConnectionOptions::Mock(_) => {
-                                    "Failed to connect to mock server"
-                                }
-                            },
-                            Some(&format!("{e:#}")),
-                            &["Retry", "Cancel"],
-                            cx,
-                        )
-                    })?
-                    .await;
-
-                if response == Ok(0) {
+                let retry = match host_key_change(&connection_options, &e) {
+                    Some(change) => recover_from_host_key_change(&change, window, cx).await?,
+                    // The user was already asked whether to update the remote
+                    // server, and chose not to.
+                    None if e.downcast_ref::<IncompatibleServerVersion>().is_some() => false,
+                    None => {
+                        window
+                            .update(cx, |_, window, cx| {
+                                window.prompt(
+                                    PromptLevel::Critical,
+                                    match connection_options {
+                                        RemoteConnectionOptions::Ssh(_) => {
+                                            "Failed to connect over SSH"
+                                        }
+                                        RemoteConnectionOptions::Wsl(_) => {
+                                            "Failed to connect to WSL"
+                                        }
+                                        RemoteConnectionOptions::Docker(_) => {
+                                            "Failed to connect to Dev Container"
+                                        }
+                                        #[cfg(any(test, feature = "test-support"))]
+                                        RemoteConnectionOptions::Mock(_) => {
+                                            "Failed to connect to mock server"
+                                        }
+                                    },
+                                    Some(&connection_error_detail(&connection_options, &e)),
+                                    &["Retry", "Cancel"],
+                                    cx,
+                                )
+                            })?
+                            .await
+                            == Ok(0)
+                    }
+                };
+                if retry {
                     continue;
                 }
 
@@ -348,6 +995,7 @@ pub async fn open_remote_project(
         let (paths, paths_with_positions) =
             determine_paths_with_positions(&remote_connection, paths.clone()).await;
 
+        let port_forwards_connection = remote_connection.clone();
         let opened_items = cx
             .update(|cx| {
                 workspace::open_remote_project_with_new_connection(
@@ -386,7 +1034,7 @@ pub async fn open_remote_project(
                                     "Failed to connect to mock server"
                                 }
                             },
-                            Some(&format!("{e:#}")),
+                            Some(&connection_error_detail(&connection_options, &e)),
                             &["Retry", "Cancel"],
                             cx,
                         )
@@ -414,6 +1062,12 @@ pub async fn open_remote_project(
 
             Ok(items) => {
                 navigate_to_positions(&window, items, &paths_with_positions, cx);
+                window
+                    .update(cx, |multi_workspace, _, cx| {
+                        let workspace = multi_workspace.workspace().downgrade();
+                        show_failed_port_forwards(workspace, port_forwards_connection, cx);
+                    })
+                    .ok();
             }
         }
 
@@ -521,12 +1175,218 @@ mod tests {
     use gpui::{AppContext, TestAppContext};
     use http_client::BlockedHttpClient;
     use node_runtime::NodeRuntime;
-    use remote::RemoteClient;
+    use remote::{RemoteArch, RemoteClient, RemoteOs};
     use remote_server::{HeadlessAppState, HeadlessProject};
     use serde_json::json;
+    use settings::SettingsStore;
+    use std::collections::BTreeMap;
     use util::path;
     use workspace::find_existing_workspace;
 
+    #[test]
+    fn test_saved_connection_search() {
+        let project = |path: &str| RemoteProject {
+            paths: vec![path.to_string()],
+        };
+        let billing = SshConnection {
+            host: "10.0.4.12".into(),
+            username: Some("deploy".into()),
+            port: Some(2222),
+            nickname: Some("Billing box".into()),
+            projects: [project("/srv/billing-api"), project("/home/deploy/scripts")].into(),
+            ..Default::default()
+        };
+        let staging = SshConnection {
+            host: "staging.example.com".into(),
+            tag: Some("staging".into()),
+            args: vec!["-J".into(), "bastion.example.com".into()],
+            projects: [project("/var/www/frontend")].into(),
+            ..Default::default()
+        };
+        let haystacks = [billing, staging]
+            .iter()
+            .map(ssh_connection_search_haystack)
+            .collect::<Vec<_>>();
+        let matching = |query: &str| {
+            haystacks
+                .iter()
+                .enumerate()
+                .filter(|(_, haystack)| matches_search_query(haystack, query))
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(matching("billing box"), [0]);
+        assert_eq!(matching("DEPLOY"), [0]);
+        assert_eq!(matching("2222"), [0]);
+        assert_eq!(matching("billing-api"), [0]);
+        assert_eq!(matching("front"), [1]);
+        assert_eq!(matching("staging www"), [1]);
+        assert_eq!(matching("bastion"), [1]);
+        assert_eq!(matching("staging billing"), Vec::<usize>::new());
+        assert_eq!(matching("  "), [0, 1]);
+
+        let wsl = WslConnection {
+            distro_name: "Ubuntu".into(),
+            projects: [project("/home/me/billing-api")].into(),
+            ..Default::default()
+        };
+        assert!(matches_search_query(
+            &wsl_connection_search_haystack(&wsl),
+            "ubuntu billing"
+        ));
+    }
+
+    #[gpui::test]
+    fn test_dev_container_extra_env_applies_on_connect(cx: &mut TestAppContext) {
+        init_test(cx);
+        let connection = DevContainerConnection {
+            container_id: "1111".to_string(),
+            remote_user: "vscode".to_string(),
+            remote_env: BTreeMap::from([
+                ("EDITOR".to_string(), "vim".to_string()),
+                ("RUST_LOG".to_string(), "info".to_string()),
+            ]),
+            extra_env: BTreeMap::from([("RUST_LOG".to_string(), "debug".to_string())]),
+            ..DevContainerConnection::default()
+        };
+        let expected_env = BTreeMap::from([
+            ("EDITOR".to_string(), "vim".to_string()),
+            ("RUST_LOG".to_string(), "debug".to_string()),
+        ]);
+
+        let RemoteConnectionOptions::Docker(options) =
+            Connection::DevContainer(connection.clone()).into()
+        else {
+            panic!("expected docker connection options");
+        };
+        assert_eq!(options.remote_user, "vscode");
+        assert_eq!(options.remote_env, expected_env);
+
+        // A connection restored without them, such as from a recent project,
+        // picks them up from the saved entry.
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.remote.dev_container_connections = Some(vec![connection]);
+                });
+            });
+        });
+        let mut options = RemoteConnectionOptions::Docker(DockerConnectionOptions {
+            container_id: "1111".to_string(),
+            remote_user: "root".to_string(),
+            ..DockerConnectionOptions::default()
+        });
+        cx.update(|cx| {
+            RemoteSettings::get_global(cx)
+                .fill_remote_connection_options_from_settings(&mut options)
+        });
+        let RemoteConnectionOptions::Docker(options) = options else {
+            unreachable!()
+        };
+        assert_eq!(options.remote_user, "vscode");
+        assert_eq!(options.remote_env, expected_env);
+    }
+
+    #[test]
+    fn test_connection_error_hint() {
+        let ssh = |upload_binary_over_ssh| {
+            RemoteConnectionOptions::Ssh(SshConnectionOptions {
+                host: "example.com".into(),
+                upload_binary_over_ssh,
+                ..Default::default()
+            })
+        };
+        let download_failure = anyhow::anyhow!("curl: (6) Could not resolve host")
+            .context("downloading remote development server failed");
+
+        assert!(connection_error_hint(&ssh(false), &download_failure).is_some());
+        assert!(
+            connection_error_detail(&ssh(false), &download_failure)
+                .ends_with("in the server's options.")
+        );
+        // Already uploading, so there's nothing to suggest.
+        assert!(connection_error_hint(&ssh(true), &download_failure).is_none());
+
+        let local_failure =
+            anyhow::anyhow!("request failed: 503").context("downloading server binary locally");
+        assert!(connection_error_hint(&ssh(false), &local_failure).is_none());
+        let auth_failure = anyhow::anyhow!("Permission denied (publickey)");
+        assert!(connection_error_hint(&ssh(false), &auth_failure).is_none());
+    }
+
+    #[test]
+    fn test_failed_port_forwards_message() {
+        let forward = |local_port, status| PortForward {
+            option: remote::SshPortForwardOption {
+                local_host: None,
+                local_port,
+                remote_host: None,
+                remote_port: 80,
+            },
+            status,
+        };
+        assert_eq!(
+            failed_port_forwards_message(&[
+                forward(3000, PortForwardStatus::Active),
+                forward(8080, PortForwardStatus::Failed("address in use".into())),
+                forward(9000, PortForwardStatus::Unverified),
+            ]),
+            Some("8080→remote:80 failed: address in use".to_string())
+        );
+        assert_eq!(
+            failed_port_forwards_message(&[forward(3000, PortForwardStatus::Active)]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_host_key_change() {
+        let ssh = RemoteConnectionOptions::Ssh(SshConnectionOptions {
+            host: "devbox".into(),
+            port: Some(2222),
+            ..Default::default()
+        });
+        let changed = anyhow::anyhow!(
+            "@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+@    WARNING: REMOTE HOST IDENTIFICATION HAS CHANGED!     @
+@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+IT IS POSSIBLE THAT SOMEONE IS DOING SOMETHING NASTY!
+The fingerprint for the ED25519 key sent by the remote host is
+SHA256:Cq9PZkW1ytnT0cUbAUvn5bX2C1C1yZ2FMtKqkqXx3Yc.
+Please contact your system administrator.
+Add correct host key in /home/me/.ssh/known_hosts to get rid of this message.
+Offending ED25519 key in /home/me/.ssh/known_hosts:7
+  remove with:
+  ssh-keygen -f '/home/me/.ssh/known_hosts' -R '[10.0.0.5]:2222'
+Host key for [10.0.0.5]:2222 has changed and you have requested strict checking.
+Host key verification failed."
+        )
+        .context("failed to connect");
+        assert_eq!(
+            host_key_change(&ssh, &changed),
+            Some(HostKeyChange {
+                known_host: "[10.0.0.5]:2222".to_string(),
+                fingerprint: Some("SHA256:Cq9PZkW1ytnT0cUbAUvn5bX2C1C1yZ2FMtKqkqXx3Yc".to_string()),
+                known_hosts_file: Some(PathBuf::from("/home/me/.ssh/known_hosts")),
+                known_hosts_line: Some(7),
+            })
+        );
+
+        // Without ssh's details, the host comes from the connection.
+        let terse = anyhow::anyhow!("WARNING: REMOTE HOST IDENTIFICATION HAS CHANGED!");
+        assert_eq!(
+            host_key_change(&ssh, &terse).map(|change| change.known_host),
+            Some("[devbox]:2222".to_string())
+        );
+
+        // Keys that were never seen and other failures offer no recovery.
+        let unknown = anyhow::anyhow!("Host key verification failed.");
+        assert_eq!(host_key_change(&ssh, &unknown), None);
+        let auth_failure = anyhow::anyhow!("Permission denied (publickey)");
+        assert_eq!(host_key_change(&ssh, &auth_failure), None);
+    }
+
     #[gpui::test]
     async fn test_open_remote_project_with_mock_connection(
         cx: &mut TestAppContext,
@@ -608,6 +1468,46 @@ mod tests {
             .unwrap();
     }
 
+    #[gpui::test]
+    async fn test_declined_server_update_shows_no_failure_prompt(cx: &mut TestAppContext) {
+        let app_state = init_test(cx);
+        cx.update(|cx| {
+            release_channel::init(semver::Version::new(0, 0, 0), cx);
+        });
+
+        let opts = RemoteClient::fake_incompatible_server(
+            cx,
+            IncompatibleServerVersion {
+                server_version: "0.1.0".into(),
+                client_version: "0.2.0".into(),
+            },
+        );
+        let paths = vec![PathBuf::from(path!("/project"))];
+        let open_options = workspace::OpenOptions::default();
+        let open = cx.spawn(async move |mut cx| {
+            open_remote_project(opts, paths, app_state, open_options, &mut cx).await
+        });
+        cx.run_until_parked();
+
+        assert_eq!(
+            cx.pending_prompt().map(|(message, _)| message).as_deref(),
+            Some("Incompatible Remote Server")
+        );
+        cx.simulate_prompt_answer("Cancel");
+        cx.run_until_parked();
+
+        assert!(
+            !cx.has_pending_prompt(),
+            "declining the update shouldn't be reported as a connection failure"
+        );
+        open.await.expect("declining the update isn't an error");
+        assert_eq!(
+            cx.update(|cx| cx.windows().len()),
+            0,
+            "the window opened for the connection should be closed"
+        );
+    }
+
     #[gpui::test]
     async fn test_reuse_existing_remote_workspace_window(
         cx: &mut TestAppContext,
@@ -886,6 +1786,142 @@ mod tests {
             .unwrap();
     }
 
+    #[gpui::test]
+    async fn test_record_ssh_server_info(cx: &mut TestAppContext) {
+        let app_state = init_test(cx);
+        let connections = json!([
+            { "host": "never-connected.example.com" },
+            {
+                "host": "moved.example.com",
+                "username": "deploy",
+                "remote_os": "linux",
+                "remote_arch": "x86_64",
+                "remote_server_version": "0.200.0",
+            },
+        ]);
+        let fs = app_state.fs.as_fake();
+        fs.insert_file(
+            paths::settings_file(),
+            json!({ "ssh_connections": connections })
+                .to_string()
+                .into_bytes(),
+        )
+        .await;
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.remote.ssh_connections = serde_json::from_value(connections).ok();
+                });
+            });
+        });
+        let saved = cx.update(|cx| {
+            RemoteSettings::get_global(cx)
+                .ssh_connections()
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(ssh_platform_label(&saved[0]), None);
+        assert_eq!(
+            ssh_platform_label(&saved[1]).as_deref(),
+            Some("linux-x86_64")
+        );
+
+        let options = |host: &str, username: Option<&str>| SshConnectionOptions {
+            host: host.into(),
+            username: username.map(ToString::to_string),
+            ..SshConnectionOptions::default()
+        };
+        let aarch64_linux = RemotePlatform {
+            os: RemoteOs::Linux,
+            arch: RemoteArch::Aarch64,
+        };
+        cx.update(|cx| {
+            record_ssh_server_info(
+                &options("never-connected.example.com", None),
+                RemotePlatform {
+                    os: RemoteOs::MacOs,
+                    arch: RemoteArch::Aarch64,
+                },
+                Some("0.201.3".to_string()),
+                cx,
+            );
+            // A connection that doesn't know the server version keeps the
+            // recorded one.
+            record_ssh_server_info(
+                &options("moved.example.com", Some("deploy")),
+                aarch64_linux,
+                None,
+                cx,
+            );
+            // Servers that aren't saved aren't added.
+            record_ssh_server_info(
+                &options("unsaved.example.com", None),
+                aarch64_linux,
+                None,
+                cx,
+            );
+        });
+        cx.run_until_parked();
+
+        let settings = fs.load(paths::settings_file()).await.unwrap();
+        let settings: serde_json::Value = serde_json::from_str(&settings).unwrap();
+        let connections = &settings["ssh_connections"];
+        assert_eq!(connections[0]["remote_os"], "macos");
+        assert_eq!(connections[0]["remote_arch"], "aarch64");
+        assert_eq!(connections[0]["remote_server_version"], "0.201.3");
+        assert_eq!(connections[1]["remote_os"], "linux");
+        assert_eq!(connections[1]["remote_arch"], "aarch64");
+        assert_eq!(connections[1]["remote_server_version"], "0.200.0");
+        assert_eq!(connections.as_array().map(Vec::len), Some(2));
+    }
+
+    #[gpui::test]
+    async fn test_record_dev_container_shell(cx: &mut TestAppContext) {
+        let app_state = init_test(cx);
+        let connections = json!([
+            { "name": "api", "container_id": "1111" },
+            { "name": "web", "container_id": "2222", "shell": "/bin/zsh" },
+        ]);
+        let fs = app_state.fs.as_fake();
+        fs.insert_file(
+            paths::settings_file(),
+            json!({ "dev_container_connections": connections })
+                .to_string()
+                .into_bytes(),
+        )
+        .await;
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.remote.dev_container_connections =
+                        serde_json::from_value(connections).ok();
+                });
+            });
+        });
+
+        let options = |container_id: &str, shell: Option<&str>| DockerConnectionOptions {
+            container_id: container_id.to_string(),
+            shell: shell.map(ToString::to_string),
+            ..DockerConnectionOptions::default()
+        };
+        cx.update(|cx| {
+            record_dev_container_shell(&options("1111", None), "/bin/bash".to_string(), cx);
+            record_dev_container_shell(
+                &options("2222", Some("/bin/zsh")),
+                "/bin/zsh".to_string(),
+                cx,
+            );
+            record_dev_container_shell(&options("3333", None), "/bin/sh".to_string(), cx);
+        });
+        cx.run_until_parked();
+
+        let settings = fs.load(paths::settings_file()).await.unwrap();
+        let settings: serde_json::Value = serde_json::from_str(&settings).unwrap();
+        let connections = &settings["dev_container_connections"];
+        assert_eq!(connections[0]["detected_shell"], "/bin/bash");
+        assert_eq!(connections[1].get("detected_shell"), None);
+        assert_eq!(connections.as_array().map(Vec::len), Some(2));
+    }
+
     fn init_test(cx: &mut TestAppContext) -> Arc<AppState> {
         cx.update(|cx| {
             let state = AppState::test(cx);
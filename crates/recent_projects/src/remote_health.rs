@@ -0,0 +1,116 @@
+use gpui::ElementId;
+use remote::{ConnectionState, PortForwardStatus};
+use ui::{Indicator, Tooltip, prelude::*};
+
+/// The health of a remote server, container, connection, or port forward.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteHealth {
+    /// Running, connected, or listening.
+    Up,
+    /// Starting, connecting, or reconnecting.
+    Pending,
+    /// Stopped, disconnected, or failed.
+    Down,
+    /// Not known, such as when it couldn't be checked.
+    Unknown,
+    /// It can't be reached at all, such as a container when Docker isn't
+    /// available.
+    Unavailable,
+}
+
+impl RemoteHealth {
+    /// Each state has its own shape, so they can be told apart without
+    /// relying on color.
+    pub fn icon(self) -> IconName {
+        match self {
+            Self::Up => IconName::Circle,
+            Self::Pending => IconName::ArrowCircle,
+            Self::Down => IconName::XCircle,
+            Self::Unknown => IconName::CircleHelp,
+            Self::Unavailable => IconName::Warning,
+        }
+    }
+
+    /// A color from the theme's status palette.
+    pub fn color(self) -> Color {
+        match self {
+            Self::Up => Color::Success,
+            Self::Pending => Color::Info,
+            Self::Down => Color::Error,
+            Self::Unknown => Color::Muted,
+            Self::Unavailable => Color::Warning,
+        }
+    }
+
+    /// A badge for the icon of what this is the health of, as used with
+    /// [`ui::IconWithIndicator`].
+    pub fn indicator(self) -> Indicator {
+        Indicator::icon(Icon::new(self.icon())).color(self.color())
+    }
+}
+
+impl From<ConnectionState> for RemoteHealth {
+    fn from(state: ConnectionState) -> Self {
+        match state {
+            ConnectionState::Connected => Self::Up,
+            ConnectionState::Connecting
+            | ConnectionState::HeartbeatMissed
+            | ConnectionState::Reconnecting => Self::Pending,
+            ConnectionState::Disconnected => Self::Down,
+        }
+    }
+}
+
+impl From<&PortForwardStatus> for RemoteHealth {
+    fn from(status: &PortForwardStatus) -> Self {
+        match status {
+            PortForwardStatus::Active => Self::Up,
+            PortForwardStatus::Failed(_) => Self::Down,
+            PortForwardStatus::Unverified => Self::Unknown,
+        }
+    }
+}
+
+/// An icon showing a [`RemoteHealth`], with a description of it as its
+/// tooltip.
+#[derive(IntoElement)]
+pub struct RemoteHealthIndicator {
+    id: ElementId,
+    health: RemoteHealth,
+    description: SharedString,
+    size: IconSize,
+}
+
+impl RemoteHealthIndicator {
+    pub fn new(
+        id: impl Into<ElementId>,
+        health: RemoteHealth,
+        description: impl Into<SharedString>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            health,
+            description: description.into(),
+            size: IconSize::Small,
+        }
+    }
+
+    pub fn size(mut self, size: IconSize) -> Self {
+        self.size = size;
+        self
+    }
+}
+
+impl RenderOnce for RemoteHealthIndicator {
+    fn render(self, _window: &mut Window, _cx: &mut App) -> impl IntoElement {
+        div()
+            .id(self.id)
+            .flex_none()
+            .child(
+                Icon::new(self.health.icon())
+                    .size(self.size)
+                    .color(self.health.color()),
+            )
+            .tooltip(Tooltip::text(self.description))
+    }
+}
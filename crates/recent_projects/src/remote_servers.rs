@@ -1,22 +1,46 @@
 use crate::{
+    identity_file::{
+        IdentityFileProblem, check_identity_files, identity_files,
+        restrict_identity_file_permissions,
+    },
+    jump_host::{
+        Bastion, BastionProbes, BastionStatus, PROBE_INTERVAL, connection_bastion, probe_bastion,
+    },
     remote_connections::{
         Connection, RemoteConnectionModal, RemoteConnectionPrompt, RemoteSettings, SshConnection,
-        SshConnectionHeader, connect, determine_paths_with_positions, open_remote_project,
+        SshConnectionHeader, apply_saved_dev_container_args, connect, connection_error_detail,
+        connection_error_hint, determine_paths_with_positions,
+        dev_container_connection_search_haystack, is_saved_connection_for, matches_search_query,
+        open_remote_project, record_ssh_server_info, set_dev_container_runtime,
+        ssh_connection_search_haystack, ssh_platform_label, ssh_server_version_label,
+        wsl_connection_search_haystack,
     },
-    ssh_config::parse_ssh_config_hosts,
+    remote_health::{RemoteHealth, RemoteHealthIndicator},
+    settings_changes::{
+        ChangeKind, ConnectionChange, ConnectionChangeGroup, RemoteSettingsChanges,
+        RemoteSettingsEdit,
+    },
+    ssh_agent::{SshAgentStatus, forwards_agent, set_agent_forwarding, ssh_agent_status},
+    ssh_config::{SshConfigHostGroup, group_ssh_config_hosts, load_ssh_config_hosts},
 };
+use db::kvp::KeyValueStore;
 use dev_container::{
-    DevContainerConfig, DevContainerContext, find_devcontainer_configs,
-    start_dev_container_with_config,
+    DevContainerConfig, DevContainerContext, DevContainerError, DockerHost, WslDockerDaemon,
+    devcontainer_setup_command, find_devcontainer_configs, start_dev_container_with_config,
 };
-use editor::Editor;
+use editor::{Editor, EditorEvent};
 
+use anyhow::Context as _;
 use extension_host::ExtensionStore;
-use futures::{FutureExt, StreamExt as _, channel::oneshot, future::Shared};
+use futures::{
+    AsyncBufReadExt as _, AsyncReadExt as _, FutureExt, StreamExt as _,
+    channel::{mpsc, oneshot},
+    future::Shared,
+};
 use gpui::{
-    Action, AnyElement, App, ClickEvent, ClipboardItem, Context, DismissEvent, Entity,
-    EventEmitter, FocusHandle, Focusable, PromptLevel, ScrollHandle, Subscription, Task, TaskExt,
-    WeakEntity, Window, canvas,
+    Action, AnyElement, App, AsyncWindowContext, ClickEvent, ClipboardItem, Context, DismissEvent,
+    Entity, EventEmitter, FocusHandle, Focusable, Global, PromptLevel, ScrollHandle, Subscription,
+    Task, TaskExt, WeakEntity, Window, WindowHandle, actions, canvas,
 };
 use log::{debug, info};
 use open_path_prompt::OpenPathDelegate;
@@ -24,60 +48,339 @@ use paths::{global_ssh_config_file, user_ssh_config_file};
 use picker::{Picker, PickerDelegate};
 use project::{Fs, Project};
 use remote::{
-    RemoteClient, RemoteConnectionOptions, SshConnectionOptions, WslConnectionOptions,
-    remote_client::ConnectionIdentifier,
+    DockerConnectionOptions, Interactive, PortForward, PortForwardStatus, RemoteClient,
+    RemoteConnection, RemoteConnectionOptions, RemoteOs, RemotePlatform, SshConnectFailure,
+    SshConnectionOptions, SshSettings, WslConnectionOptions, check_container_user,
+    looks_like_secret, remote_client::ConnectionIdentifier, remote_server_env,
+    same_remote_connection_identity, validate_env_var_name, validate_env_var_value,
+    validate_remote_shell,
 };
 use settings::{
-    RemoteProject, RemoteSettingsContent, Settings as _, SettingsStore, update_settings_file,
-    watch_config_file,
+    DevContainerConnection, RemoteProject, RemoteServersDefaultAction, RemoteServersDensity,
+    RemoteServersSection, RemoteSettingsContent, Settings as _, SettingsStore,
+    update_settings_file, watch_config_file,
 };
 use std::{
     borrow::Cow,
-    collections::BTreeSet,
-    path::PathBuf,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    path::{Path, PathBuf},
     rc::Rc,
     sync::{
         Arc,
         atomic::{self, AtomicUsize},
     },
+    time::{Duration, Instant},
 };
 
 use ui::{
-    CommonAnimationExt, IconButtonShape, KeyBinding, List, ListItem, ListSeparator, Modal,
-    ModalFooter, ModalHeader, Navigable, NavigableEntry, ScrollAxes, Scrollbars, Section, Tooltip,
-    WithScrollbar, prelude::*,
+    Checkbox, Chip, CommonAnimationExt, IconButtonShape, KeyBinding, List, ListItem, ListSeparator,
+    Modal, ModalFooter, ModalHeader, Navigable, NavigableEntry, ScrollAxes, Scrollbars, Section,
+    Switch, Tooltip, WithScrollbar, prelude::*,
 };
 use util::{
     ResultExt,
-    paths::{PathStyle, RemotePathBuf},
+    paths::{PathExt as _, PathStyle, RemotePathBuf},
     rel_path::RelPath,
+    shell::ShellKind,
 };
 use workspace::{
-    AppState, DismissDecision, ModalView, MultiWorkspace, OpenLog, OpenOptions, Toast, Workspace,
+    AppState, DismissDecision, ItemHandle, ModalView, MultiWorkspace, OpenLog, OpenMode,
+    OpenOptions, PathList, SerializedWorkspaceLocation, Toast, Workspace, WorkspaceDb,
+    WorkspaceMatching,
     notifications::{DetachAndPromptErr, NotificationId},
     open_remote_project_with_existing_connection,
 };
 
+actions!(
+    remote_servers,
+    [
+        /// Copies the address of the selected server in the remote servers
+        /// modal: `user@host:port` for SSH servers, the distro for WSL, and
+        /// the container id for dev containers.
+        CopyServerAddress,
+        /// Opens the options of the selected server in the remote servers
+        /// modal.
+        OpenServerOptions,
+        /// Rewrites the saved SSH servers, WSL distros, and dev containers in
+        /// the settings file in the shape Zed writes them, filling in fields
+        /// that older entries leave out. Saving a server otherwise only
+        /// rewrites that server's entry.
+        NormalizeSettings,
+    ]
+);
+
 pub struct RemoteServerProjects {
     mode: Mode,
     focus_handle: FocusHandle,
     workspace: WeakEntity<Workspace>,
     retained_connections: Vec<Entity<RemoteClient>>,
     ssh_config_updates: Task<()>,
-    ssh_config_servers: BTreeSet<SharedString>,
+    /// Hosts from the SSH config, grouped by the file that defines them.
+    ssh_config_servers: Vec<SshConfigHostGroup>,
+    /// Hosts from the SSH config that connect through `ProxyCommand` or
+    /// `ProxyJump`, which can be slow or prompt when connecting.
+    ssh_config_proxied_hosts: BTreeSet<SharedString>,
+    /// The `ProxyJump` chains hosts in the SSH config set, by host.
+    ssh_config_proxy_jumps: BTreeMap<String, String>,
+    /// Probes the bastions saved servers jump through, while the modal is
+    /// open.
+    _bastion_probes: Task<()>,
+    server_list_entries: ServerListEntries,
+    /// Remote workspaces from the workspace database, most recently opened
+    /// first.
+    remote_project_history: Vec<(RemoteConnectionOptions, Vec<PathBuf>)>,
+    /// Whether opening a project creates a new window instead of replacing
+    /// the current one. Secondary confirm does the opposite.
     create_new_window: bool,
+    /// Filters the server list by server details and project paths.
+    server_search_editor: Entity<Editor>,
+    /// The text of `server_search_editor`, which every rebuild of the server
+    /// list is filtered by.
+    server_search_query: String,
     dev_container_picker: Option<Entity<Picker<DevContainerPickerDelegate>>>,
+    /// Looks for dev container configurations in the project's worktrees.
+    _dev_container_discovery: Task<()>,
     _subscription: Subscription,
     allow_dismissal: bool,
 }
 
+/// How long "Test Connection" may take to connect and run a command, not
+/// counting time spent waiting on the user to answer a prompt.
+const CONNECTION_TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Explains when a server needs its binary uploaded over SSH.
+const UPLOAD_BINARY_OVER_SSH_DESCRIPTION: &str = "Servers normally download the Zed remote server themselves. Turn this on for servers \
+    without internet access.";
+
+/// Explains what forwarding the ssh-agent does.
+const FORWARD_AGENT_DESCRIPTION: &str = "Lets commands on the server, such as git, use the keys in \
+    your local ssh-agent. Passes -A to ssh.";
+
+/// The longest keepalive interval, in seconds, that can be entered.
+const MAX_KEEPALIVE_INTERVAL: u32 = 24 * 60 * 60;
+/// The most unanswered keepalive messages that can be entered.
+const MAX_KEEPALIVE_COUNT: u32 = 1000;
+
 struct CreateRemoteServer {
     address_editor: Entity<Editor>,
-    address_error: Option<SharedString>,
+    keepalive: KeepaliveEditors,
+    /// Whether the server binary is uploaded over SSH instead of downloaded by
+    /// the server.
+    upload_binary_over_ssh: bool,
+    /// Whether the connection forwards the local ssh-agent, with `-A`.
+    forward_agent: bool,
+    /// The saved server whose original command is being edited, which
+    /// connecting updates instead of adding another server.
+    replacing: Option<SshServerIndex>,
+    errors: CreateRemoteServerFormErrors,
     ssh_prompt: Option<Entity<RemoteConnectionPrompt>>,
+    connection_test: Option<ConnectionTest>,
     _creating: Option<Task<Option<()>>>,
 }
 
+/// Problems with the values entered in the create-server form, each shown
+/// below the field it belongs to.
+#[derive(Default)]
+struct CreateRemoteServerFormErrors {
+    address: Option<SharedString>,
+    keepalive: KeepaliveErrors,
+}
+
+#[derive(Default)]
+struct KeepaliveErrors {
+    interval: Option<SharedString>,
+    count_max: Option<SharedString>,
+}
+
+/// Editors for a connection's keepalive options. An empty editor leaves the
+/// option unset, so the `ssh_keepalive_*` settings apply.
+#[derive(Clone)]
+struct KeepaliveEditors {
+    interval_editor: Entity<Editor>,
+    count_max_editor: Entity<Editor>,
+}
+
+impl KeepaliveEditors {
+    fn new(
+        interval: Option<u32>,
+        count_max: Option<u32>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Self {
+        let defaults = SshSettings::get_global(cx).clone();
+        let mut new_editor = |value: Option<u32>, default: Option<u32>| {
+            cx.new(|cx| {
+                let mut editor = Editor::single_line(window, cx);
+                let placeholder = match default {
+                    Some(default) => format!("{default} (default)"),
+                    None => "From your ssh config".to_string(),
+                };
+                editor.set_placeholder_text(&placeholder, window, cx);
+                if let Some(value) = value {
+                    editor.set_text(value.to_string(), window, cx);
+                }
+                editor
+            })
+        };
+        Self {
+            interval_editor: new_editor(interval, defaults.keepalive_interval),
+            count_max_editor: new_editor(count_max, defaults.keepalive_count_max),
+        }
+    }
+
+    /// Returns the entered interval and count, or an error for each one that
+    /// isn't valid.
+    fn parse(&self, cx: &mut App) -> Result<(Option<u32>, Option<u32>), KeepaliveErrors> {
+        let interval = parse_keepalive_value(
+            &get_text(&self.interval_editor, cx),
+            MAX_KEEPALIVE_INTERVAL,
+            "Keepalive interval",
+        );
+        let count_max = parse_keepalive_value(
+            &get_text(&self.count_max_editor, cx),
+            MAX_KEEPALIVE_COUNT,
+            "Keepalive count",
+        );
+        match (interval, count_max) {
+            (Ok(interval), Ok(count_max)) => Ok((interval, count_max)),
+            (interval, count_max) => Err(KeepaliveErrors {
+                interval: interval.err(),
+                count_max: count_max.err(),
+            }),
+        }
+    }
+
+    fn set_read_only(&self, read_only: bool, cx: &mut App) {
+        for editor in [&self.interval_editor, &self.count_max_editor] {
+            editor.update(cx, |editor, _| editor.set_read_only(read_only));
+        }
+    }
+}
+
+/// Parses a keepalive option, where empty text means the option is unset.
+fn parse_keepalive_value(text: &str, max: u32, name: &str) -> Result<Option<u32>, SharedString> {
+    if text.is_empty() {
+        return Ok(None);
+    }
+    match text.parse::<u32>() {
+        Ok(value) if value <= max => Ok(Some(value)),
+        Ok(_) => Err(format!("{name} can be at most {max}.").into()),
+        Err(_) => Err(format!("{name} must be a whole number, or 0 to disable it.").into()),
+    }
+}
+
+/// The outcome of testing the connection entered in the create-server form.
+struct ConnectionTest {
+    /// The form input that was tested, so a result isn't shown for a
+    /// different server after the input changes.
+    input: String,
+    result: Result<(), ConnectionTestFailure>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConnectionTestFailure {
+    HostKey,
+    HostKeyChanged,
+    Authentication,
+    /// Authentication was rejected, and the ssh-agent, which was all ssh had
+    /// to authenticate with, has no keys loaded.
+    AgentHasNoKeys,
+    /// Authentication was rejected, and there was no ssh-agent for ssh to
+    /// authenticate with.
+    NoAgent,
+    Unreachable,
+    TimedOut,
+    RemoteCommand,
+    ServerBinaryDownload,
+    Other,
+}
+
+impl ConnectionTestFailure {
+    fn categorize(error: &anyhow::Error) -> Self {
+        let Some(failure) = error.downcast_ref::<SshConnectFailure>() else {
+            return Self::Other;
+        };
+        if !failure.is_ssh_error() {
+            return Self::Other;
+        }
+        // ssh exits with 255 for every failure of its own, so only its
+        // diagnostics tell them apart.
+        let error = failure.stderr.to_lowercase();
+        if error.contains("remote host identification has changed") {
+            Self::HostKeyChanged
+        } else if error.contains("host key verification failed") {
+            Self::HostKey
+        } else if error.contains("permission denied")
+            || error.contains("authentication")
+            || error.contains("incorrect password")
+        {
+            Self::Authentication
+        } else if error.contains("could not resolve hostname")
+            || error.contains("connection refused")
+            || error.contains("no route to host")
+            || error.contains("network is unreachable")
+            || error.contains("timed out")
+        {
+            Self::Unreachable
+        } else {
+            Self::Other
+        }
+    }
+
+    /// The outcome of running `true` over an open connection, from the exit
+    /// code of the ssh process that ran it.
+    fn from_check_status(exit_code: Option<i32>) -> Result<(), Self> {
+        match exit_code {
+            Some(0) => Ok(()),
+            // ssh's own failure, so the connection dropped before `true` ran.
+            Some(255) => {
+                log::error!("connection test lost the connection");
+                Err(Self::Unreachable)
+            }
+            exit_code => {
+                log::error!("connection test command exited with {exit_code:?}");
+                Err(Self::RemoteCommand)
+            }
+        }
+    }
+
+    /// Narrows down an authentication failure of a connection that had
+    /// nothing but the ssh-agent to authenticate with.
+    fn from_agent_status(status: SshAgentStatus) -> Self {
+        match status {
+            SshAgentStatus::NoIdentities => Self::AgentHasNoKeys,
+            SshAgentStatus::Unavailable => Self::NoAgent,
+            SshAgentStatus::HasIdentities | SshAgentStatus::Unknown => Self::Authentication,
+        }
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            Self::HostKey => "Connection failed: the host key could not be verified.",
+            Self::HostKeyChanged => {
+                "Connection failed: the host key has changed. Connect to the server to remove the old key."
+            }
+            Self::Authentication => "Connection failed: authentication was rejected.",
+            Self::AgentHasNoKeys => {
+                "Connection failed: authentication was rejected, and your ssh-agent has no keys loaded. Run `ssh-add` (or `ssh-add <path to key>`), then try again."
+            }
+            Self::NoAgent if cfg!(windows) => {
+                "Connection failed: authentication was rejected, and no ssh-agent is running. Start the \"OpenSSH Authentication Agent\" service or Pageant, run `ssh-add`, then try again."
+            }
+            Self::NoAgent => {
+                "Connection failed: authentication was rejected, and no ssh-agent is running. Run `eval \"$(ssh-agent -s)\" && ssh-add`, then try again."
+            }
+            Self::Unreachable => "Connection failed: the host could not be reached.",
+            Self::TimedOut => "Connection failed: the server didn't respond in time.",
+            Self::RemoteCommand => "Connected, but the server could not run a command.",
+            Self::ServerBinaryDownload => {
+                "Connected, but the server could not download the Zed remote server. Try uploading it over SSH."
+            }
+            Self::Other => "Connection failed. See the log for details.",
+        }
+    }
+}
+
 impl CreateRemoteServer {
     fn new(window: &mut Window, cx: &mut App) -> Self {
         let address_editor = cx.new(|cx| Editor::single_line(window, cx));
@@ -86,15 +389,131 @@ impl CreateRemoteServer {
         });
         Self {
             address_editor,
-            address_error: None,
+            keepalive: KeepaliveEditors::new(None, None, window, cx),
+            upload_binary_over_ssh: false,
+            forward_agent: false,
+            replacing: None,
+            errors: CreateRemoteServerFormErrors::default(),
             ssh_prompt: None,
+            connection_test: None,
             _creating: None,
         }
     }
+
+    /// A form for editing the command the saved server at `index` was added
+    /// with, filled in with its other options.
+    fn for_original_command(index: SshServerIndex, window: &mut Window, cx: &mut App) -> Self {
+        let mut state = Self::new(window, cx);
+        let Some(connection) = RemoteSettings::get_global(cx)
+            .ssh_connections()
+            .nth(index.0)
+        else {
+            return state;
+        };
+        state.replacing = Some(index);
+        state.keepalive = KeepaliveEditors::new(
+            connection.keepalive_interval,
+            connection.keepalive_count_max,
+            window,
+            cx,
+        );
+        state.upload_binary_over_ssh = connection.upload_binary_over_ssh.unwrap_or(false);
+        state.forward_agent = forwards_agent(&connection.args);
+        if let Some(command) = &connection.original_command {
+            state.set_command_text(command, window, cx);
+        }
+        state
+    }
+
+    /// Replaces the command in the address field, for editing before
+    /// connecting.
+    fn set_command_text(&self, command: &str, window: &mut Window, cx: &mut App) {
+        self.address_editor.update(cx, |editor, cx| {
+            editor.set_text(command, window, cx);
+            editor.move_to_end(&editor::actions::MoveToEnd, window, cx);
+        });
+    }
+}
+
+/// Clones a git repository onto a saved server and then opens it as a project.
+struct CloneRepository {
+    index: ServerIndex,
+    connection: RemoteConnectionOptions,
+    url_editor: Entity<Editor>,
+    destination_editor: Entity<Editor>,
+    error: Option<SharedString>,
+    ssh_prompt: Option<Entity<RemoteConnectionPrompt>>,
+    /// Lines printed by `git clone` so far, with progress updates collapsed
+    /// into the line they overwrite.
+    output: Vec<SharedString>,
+    /// The server running `git clone` and its process id there, used to stop
+    /// the clone when it's cancelled.
+    remote_git: Option<(Entity<RemoteClient>, u32)>,
+    /// Dropping this task kills the local end of the clone command. The
+    /// remote `git` isn't attached to a terminal, so it is stopped separately
+    /// through `remote_git`.
+    _cloning: Option<Task<Option<()>>>,
+}
+
+impl CloneRepository {
+    fn new(
+        index: ServerIndex,
+        connection: RemoteConnectionOptions,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Self {
+        let url_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("https://github.com/user/repository.git", window, cx);
+            editor
+        });
+        let destination_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text(
+                "Destination on the server (defaults to ~/<repository>)",
+                window,
+                cx,
+            );
+            editor
+        });
+        url_editor.update(cx, |this, cx| {
+            this.focus_handle(cx).focus(window, cx);
+        });
+        Self {
+            index,
+            connection,
+            url_editor,
+            destination_editor,
+            error: None,
+            ssh_prompt: None,
+            output: Vec::new(),
+            remote_git: None,
+            _cloning: None,
+        }
+    }
+
+    fn set_read_only(&self, read_only: bool, cx: &mut App) {
+        for editor in [&self.url_editor, &self.destination_editor] {
+            editor.update(cx, |editor, _| editor.set_read_only(read_only));
+        }
+    }
+}
+
+/// Returns the directory `git clone` would pick for `url`, under the
+/// server's home directory.
+fn default_clone_destination(url: &str) -> Option<String> {
+    let name = url
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()?
+        .trim_end_matches(".git");
+    (!name.is_empty()).then(|| format!("~/{name}"))
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 enum DevContainerCreationProgress {
+    /// Looking for configurations in the project's worktrees.
+    Discovering,
     SelectingConfig,
     Creating,
     Error(String),
@@ -105,6 +524,9 @@ struct CreateRemoteDevContainer {
     view_logs_entry: NavigableEntry,
     back_entry: NavigableEntry,
     progress: DevContainerCreationProgress,
+    /// What setting the container up has reported so far, kept when it fails
+    /// so the error can be read alongside it.
+    build_log: Vec<SharedString>,
 }
 
 impl CreateRemoteDevContainer {
@@ -115,6 +537,7 @@ impl CreateRemoteDevContainer {
             view_logs_entry,
             back_entry,
             progress,
+            build_log: Vec::new(),
         }
     }
 }
@@ -164,6 +587,7 @@ enum ProjectPickerData {
     Ssh {
         connection_string: SharedString,
         nickname: Option<SharedString>,
+        platform: Option<SharedString>,
     },
     Wsl {
         distro_name: SharedString,
@@ -179,6 +603,395 @@ struct ProjectPicker {
 struct EditNicknameState {
     index: SshServerIndex,
     editor: Entity<Editor>,
+    error: Option<SharedString>,
+}
+
+struct EditKeepaliveState {
+    index: SshServerIndex,
+    keepalive: KeepaliveEditors,
+    errors: KeepaliveErrors,
+}
+
+struct EditTagState {
+    target: TagTarget,
+    editor: Entity<Editor>,
+}
+
+/// The saved connection whose tag is being edited.
+#[derive(Clone, Copy)]
+enum TagTarget {
+    Server(ServerIndex),
+    /// A saved dev container, by its position in the settings.
+    DevContainer(usize),
+}
+
+/// The changes a bulk edit of the remote settings makes, shown for
+/// confirmation before the settings file is written.
+struct ConfirmSettingsChangesState {
+    title: SharedString,
+    changes: RemoteSettingsChanges,
+    edit: RemoteSettingsEdit,
+    /// For each group of changes, the row that lists the rest of them, until
+    /// it's used. Groups with few changes list all of them from the start.
+    show_more: Vec<Option<NavigableEntry>>,
+    confirm: NavigableEntry,
+    cancel: NavigableEntry,
+    scroll_handle: ScrollHandle,
+}
+
+/// How many changes of each kind of connection are listed before the rest
+/// are folded into an "and N more" row.
+const MAX_SETTINGS_CHANGES_SHOWN: usize = 5;
+
+impl ConfirmSettingsChangesState {
+    fn new(
+        title: SharedString,
+        changes: RemoteSettingsChanges,
+        edit: RemoteSettingsEdit,
+        cx: &App,
+    ) -> Self {
+        let scroll_handle = ScrollHandle::new();
+        let show_more = changes
+            .groups
+            .iter()
+            .map(|group| {
+                (group.changes.len() > MAX_SETTINGS_CHANGES_SHOWN)
+                    .then(|| NavigableEntry::new(&scroll_handle, cx))
+            })
+            .collect();
+        Self {
+            title,
+            changes,
+            edit,
+            show_more,
+            confirm: NavigableEntry::new(&scroll_handle, cx),
+            cancel: NavigableEntry::new(&scroll_handle, cx),
+            scroll_handle,
+        }
+    }
+}
+
+struct EditShellState {
+    index: SshServerIndex,
+    /// The shell program, or empty to use the `ssh_shell` setting.
+    editor: Entity<Editor>,
+    login_shell: bool,
+    error: Option<SharedString>,
+}
+
+impl EditShellState {
+    fn new(index: SshServerIndex, window: &mut Window, cx: &mut App) -> Self {
+        let defaults = SshSettings::get_global(cx).clone();
+        let connection = RemoteSettings::get_global(cx)
+            .ssh_connections()
+            .nth(index.0);
+        let shell = connection
+            .as_ref()
+            .and_then(|connection| connection.shell.clone());
+        let login_shell = connection
+            .as_ref()
+            .and_then(|connection| connection.login_shell)
+            .unwrap_or(defaults.login_shell);
+        let editor = cx.new(|cx| Editor::single_line(window, cx));
+        editor.update(cx, |editor, cx| {
+            let placeholder = match &defaults.shell {
+                Some(shell) => format!("{shell} (default)"),
+                None => "The server's login shell (default)".to_string(),
+            };
+            editor.set_placeholder_text(&placeholder, window, cx);
+            if let Some(shell) = shell {
+                editor.set_text(shell, window, cx);
+            }
+        });
+        editor.focus_handle(cx).focus(window, cx);
+        Self {
+            index,
+            editor,
+            login_shell,
+            error: None,
+        }
+    }
+}
+
+/// Describes the shell terminals and interactive commands start in on a saved
+/// server, such as "/bin/bash, as a login shell".
+fn effective_shell_description(connection: &SshConnection, defaults: &SshSettings) -> String {
+    let shell = connection
+        .shell
+        .as_deref()
+        .or(defaults.shell.as_deref())
+        .unwrap_or("Login shell");
+    if connection.login_shell.unwrap_or(defaults.login_shell) {
+        format!("{shell}, as a login shell")
+    } else {
+        format!("{shell}, not as a login shell")
+    }
+}
+
+/// A variable in the environment editor.
+struct EnvVarRow {
+    name: Entity<Editor>,
+    value: Entity<Editor>,
+}
+
+impl EnvVarRow {
+    fn new(name: &str, value: &str, window: &mut Window, cx: &mut App) -> Self {
+        let mut new_editor = |placeholder: &str, text: &str| {
+            cx.new(|cx| {
+                let mut editor = Editor::single_line(window, cx);
+                editor.set_placeholder_text(placeholder, window, cx);
+                editor.set_text(text, window, cx);
+                editor
+            })
+        };
+        Self {
+            name: new_editor("NAME", name),
+            value: new_editor("value", value),
+        }
+    }
+}
+
+/// Edits the environment variables the remote server process of a saved
+/// server is started with.
+struct EditEnvState {
+    index: ServerIndex,
+    /// The variables, then an empty row for adding one.
+    rows: Vec<EnvVarRow>,
+    error: Option<SharedString>,
+}
+
+impl EditEnvState {
+    fn new(index: ServerIndex, window: &mut Window, cx: &mut App) -> Self {
+        let mut rows = saved_server_env(index, cx)
+            .iter()
+            .map(|(name, value)| EnvVarRow::new(name, value, window, cx))
+            .collect::<Vec<_>>();
+        rows.push(EnvVarRow::new("", "", window, cx));
+        if let Some(row) = rows.last() {
+            row.name.focus_handle(cx).focus(window, cx);
+        }
+        Self {
+            index,
+            rows,
+            error: None,
+        }
+    }
+
+    /// The variables entered, or why they can't be saved.
+    fn parse(&self, cx: &mut App) -> Result<BTreeMap<String, String>, SharedString> {
+        parse_env_rows(&self.rows, cx)
+    }
+}
+
+/// The variables entered in `rows`, or why they can't be saved. Rows left
+/// empty are ignored.
+fn parse_env_rows(
+    rows: &[EnvVarRow],
+    cx: &mut App,
+) -> Result<BTreeMap<String, String>, SharedString> {
+    let mut env = BTreeMap::new();
+    for row in rows {
+        let name = get_text(&row.name, cx);
+        let value = row.value.read(cx).text(cx);
+        if name.is_empty() && value.is_empty() {
+            continue;
+        }
+        validate_env_var_name(&name)
+            .and_then(|()| validate_env_var_value(&value))
+            .map_err(|error| match name.as_str() {
+                "" => SharedString::from(error),
+                name => format!("{name}: {error}").into(),
+            })?;
+        if env.insert(name.clone(), value).is_some() {
+            return Err(format!("{name} is set more than once.").into());
+        }
+    }
+    Ok(env)
+}
+
+/// Edits the user a saved dev container is connected to as, and the
+/// variables set in it on top of those from its devcontainer.json.
+struct EditDevContainerUserState {
+    /// The container's position in the settings.
+    index: usize,
+    connection: DevContainerConnection,
+    user_editor: Entity<Editor>,
+    /// The extra variables, then an empty row for adding one.
+    env_rows: Vec<EnvVarRow>,
+    error: Option<SharedString>,
+    /// Checks that the user exists in the container, before saving.
+    _checking: Option<Task<()>>,
+}
+
+impl EditDevContainerUserState {
+    fn new(
+        index: usize,
+        connection: DevContainerConnection,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Self {
+        let user_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("The container's default user", window, cx);
+            editor.set_text(connection.remote_user.as_str(), window, cx);
+            editor
+        });
+        user_editor.focus_handle(cx).focus(window, cx);
+        let mut env_rows = connection
+            .extra_env
+            .iter()
+            .map(|(name, value)| EnvVarRow::new(name, value, window, cx))
+            .collect::<Vec<_>>();
+        env_rows.push(EnvVarRow::new("", "", window, cx));
+        Self {
+            index,
+            connection,
+            user_editor,
+            env_rows,
+            error: None,
+            _checking: None,
+        }
+    }
+}
+
+/// The names entered in `rows` that look like they hold secrets.
+fn env_secret_names(rows: &[EnvVarRow], cx: &mut App) -> Vec<String> {
+    rows.iter()
+        .map(|row| get_text(&row.name, cx))
+        .filter(|name| looks_like_secret(name))
+        .collect()
+}
+
+/// The environment variables a saved server's remote server process is
+/// started with.
+fn saved_server_env(index: ServerIndex, cx: &App) -> BTreeMap<String, String> {
+    let settings = RemoteSettings::get_global(cx);
+    match index {
+        ServerIndex::Ssh(index) => settings
+            .ssh_connections()
+            .nth(index.0)
+            .and_then(|connection| connection.env),
+        ServerIndex::Wsl(index) => settings
+            .wsl_connections()
+            .nth(index.0)
+            .and_then(|connection| connection.env),
+    }
+    .unwrap_or_default()
+}
+
+/// Whether a saved server was running Windows the last time Zed connected
+/// to it. The remote server isn't started with its environment variables
+/// there, since Windows hosts have no `env` to start it through.
+fn saved_server_is_windows(index: ServerIndex, cx: &App) -> bool {
+    match index {
+        ServerIndex::Ssh(index) => RemoteSettings::get_global(cx)
+            .ssh_connections()
+            .nth(index.0)
+            .is_some_and(|connection| {
+                connection.remote_os.as_deref() == Some(RemoteOs::Windows.as_str())
+            }),
+        ServerIndex::Wsl(_) => false,
+    }
+}
+
+/// Lists the variables a saved server's remote server process is started
+/// with, including those forwarded from Zed's own environment, by name only
+/// so values such as proxy credentials aren't shown.
+fn effective_env_description(index: ServerIndex, cx: &App) -> Option<String> {
+    if saved_server_is_windows(index, cx) {
+        return Some("Not set on Windows hosts".to_string());
+    }
+    let names = remote_server_env(&saved_server_env(index, cx))
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    (!names.is_empty()).then(|| names.join(", "))
+}
+
+/// The header shown above a saved server's settings.
+fn saved_server_header(index: ServerIndex, cx: &App) -> Option<SshConnectionHeader> {
+    let settings = RemoteSettings::get_global(cx);
+    match index {
+        ServerIndex::Ssh(index) => {
+            settings
+                .ssh_connections()
+                .nth(index.0)
+                .map(|connection| SshConnectionHeader {
+                    platform: ssh_platform_label(&connection),
+                    connection_string: connection.host.into(),
+                    paths: Default::default(),
+                    nickname: connection.nickname.map(|s| s.into()),
+                    is_wsl: false,
+                    is_devcontainer: false,
+                })
+        }
+        ServerIndex::Wsl(index) => {
+            settings
+                .wsl_connections()
+                .nth(index.0)
+                .map(|connection| SshConnectionHeader {
+                    connection_string: connection.distro_name.into(),
+                    paths: Default::default(),
+                    nickname: None,
+                    platform: None,
+                    is_wsl: true,
+                    is_devcontainer: false,
+                })
+        }
+    }
+}
+
+impl EditTagState {
+    fn new(target: TagTarget, window: &mut Window, cx: &mut App) -> Self {
+        let settings = RemoteSettings::get_global(cx);
+        let tag = match target {
+            TagTarget::Server(ServerIndex::Ssh(index)) => settings
+                .ssh_connections()
+                .nth(index.0)
+                .and_then(|connection| connection.tag),
+            TagTarget::Server(ServerIndex::Wsl(index)) => settings
+                .wsl_connections()
+                .nth(index.0)
+                .and_then(|connection| connection.tag),
+            TagTarget::DevContainer(index) => settings
+                .dev_container_connections
+                .get(index)
+                .and_then(|connection| connection.tag.clone()),
+        };
+        let editor = cx.new(|cx| Editor::single_line(window, cx));
+        editor.update(cx, |editor, cx| {
+            editor.set_placeholder_text("Tag this server, such as prod or staging", window, cx);
+            if let Some(tag) = tag {
+                editor.set_text(tag, window, cx);
+            }
+        });
+        editor.focus_handle(cx).focus(window, cx);
+        Self { target, editor }
+    }
+}
+
+impl EditKeepaliveState {
+    fn new(index: SshServerIndex, window: &mut Window, cx: &mut App) -> Self {
+        let connection = RemoteSettings::get_global(cx)
+            .ssh_connections()
+            .nth(index.0);
+        let keepalive = KeepaliveEditors::new(
+            connection
+                .as_ref()
+                .and_then(|connection| connection.keepalive_interval),
+            connection
+                .as_ref()
+                .and_then(|connection| connection.keepalive_count_max),
+            window,
+            cx,
+        );
+        keepalive.interval_editor.focus_handle(cx).focus(window, cx);
+        Self {
+            index,
+            keepalive,
+            errors: KeepaliveErrors::default(),
+        }
+    }
 }
 
 struct DevContainerPickerDelegate {
@@ -237,6 +1050,9 @@ impl PickerDelegate for DevContainerPickerDelegate {
             .iter()
             .filter(|c| {
                 c.name.to_lowercase().contains(&query_lower)
+                    || c.package
+                        .as_ref()
+                        .is_some_and(|package| package.label.to_lowercase().contains(&query_lower))
                     || c.config_path
                         .to_string_lossy()
                         .to_lowercase()
@@ -302,11 +1118,21 @@ impl PickerDelegate for DevContainerPickerDelegate {
                 .toggle_state(selected)
                 .start_slot(Icon::new(IconName::FileToml).color(Color::Muted))
                 .child(
-                    v_flex().child(Label::new(candidate.name.clone())).child(
-                        Label::new(config_path)
-                            .size(ui::LabelSize::Small)
-                            .color(Color::Muted),
-                    ),
+                    v_flex()
+                        .child(match &candidate.package {
+                            // Packages often share configuration names, such
+                            // as "default", so lead with where they live.
+                            Some(package) => h_flex()
+                                .gap_1()
+                                .child(Label::new(package.label.clone()))
+                                .child(Label::new(candidate.name.clone()).color(Color::Muted)),
+                            None => h_flex().child(Label::new(candidate.name.clone())),
+                        })
+                        .child(
+                            Label::new(config_path)
+                                .size(ui::LabelSize::Small)
+                                .color(Color::Muted),
+                        ),
                 )
                 .into_any_element(),
         )
@@ -351,13 +1177,28 @@ impl PickerDelegate for DevContainerPickerDelegate {
 }
 
 impl EditNicknameState {
-    fn new(index: SshServerIndex, window: &mut Window, cx: &mut App) -> Self {
+    fn new(
+        index: SshServerIndex,
+        window: &mut Window,
+        cx: &mut Context<RemoteServerProjects>,
+    ) -> Self {
         let this = Self {
             index,
             editor: cx.new(|cx| Editor::single_line(window, cx)),
+            error: None,
         };
-        let starting_text = RemoteSettings::get_global(cx)
-            .ssh_connections()
+        // The error is about the nickname as it was, so editing clears it.
+        cx.subscribe(&this.editor, |this, _, event: &EditorEvent, cx| {
+            if let EditorEvent::BufferEdited = event
+                && let Mode::EditNickname(state) = &mut this.mode
+                && state.error.take().is_some()
+            {
+                cx.notify();
+            }
+        })
+        .detach();
+        let starting_text = RemoteSettings::get_global(cx)
+            .ssh_connections()
             .nth(index.0)
             .and_then(|state| state.nickname)
             .filter(|text| !text.is_empty());
@@ -370,6 +1211,35 @@ impl EditNicknameState {
         this.editor.focus_handle(cx).focus(window, cx);
         this
     }
+
+    /// Returns an error if `nickname` is already used by a saved connection to
+    /// a different server, since the two would look the same in the list.
+    fn validate_nickname(&self, nickname: &str, cx: &App) -> Result<(), SharedString> {
+        let connections = RemoteSettings::get_global(cx)
+            .ssh_connections()
+            .collect::<Vec<_>>();
+        let Some(own) = connections.get(self.index.0) else {
+            return Ok(());
+        };
+        let conflict = connections.iter().enumerate().find(|(ix, connection)| {
+            *ix != self.index.0
+                && connection
+                    .nickname
+                    .as_deref()
+                    .is_some_and(|other| other.trim().eq_ignore_ascii_case(nickname))
+                && (connection.host != own.host
+                    || connection.username != own.username
+                    || connection.port != own.port)
+        });
+        match conflict {
+            Some((_, connection)) => Err(format!(
+                "\"{nickname}\" is already the nickname of {}",
+                connection.host
+            )
+            .into()),
+            None => Ok(()),
+        }
+    }
 }
 
 impl Focusable for ProjectPicker {
@@ -391,7 +1261,9 @@ impl ProjectPicker {
     ) -> Entity<Self> {
         let (tx, rx) = oneshot::channel();
         let lister = project::DirectoryLister::Project(project.clone());
-        let delegate = open_path_prompt::OpenPathDelegate::new(tx, lister, false, cx).show_hidden();
+        let delegate = open_path_prompt::OpenPathDelegate::new(tx, lister, false, cx)
+            .show_hidden()
+            .allow_directory_creation();
 
         let picker = cx.new(|cx| {
             let picker = Picker::uniform_list(delegate, window, cx)
@@ -401,10 +1273,24 @@ impl ProjectPicker {
             picker
         });
 
+        let remote_client = project.read(cx).remote_client();
+        let platform = remote_client
+            .as_ref()
+            .and_then(|client| client.read(cx).platform());
+        if let RemoteConnectionOptions::Ssh(connection) = &connection
+            && let Some(platform) = platform
+        {
+            let server_version = remote_client.and_then(|client| client.read(cx).server_version());
+            record_ssh_server_info(connection, platform, server_version, cx);
+        }
+
         let data = match &connection {
             RemoteConnectionOptions::Ssh(connection) => ProjectPickerData::Ssh {
                 connection_string: connection.connection_string().into(),
                 nickname: connection.nickname.clone().map(|nick| nick.into()),
+                platform: platform.map(|platform| {
+                    format!("{}-{}", platform.os.as_str(), platform.arch.as_str()).into()
+                }),
             },
             RemoteConnectionOptions::Wsl(connection) => ProjectPickerData::Wsl {
                 distro_name: connection.distro_name.clone().into(),
@@ -413,11 +1299,13 @@ impl ProjectPicker {
                 // Not implemented as a project picker at this time
                 connection_string: "".into(),
                 nickname: None,
+                platform: None,
             },
             #[cfg(any(test, feature = "test-support"))]
             RemoteConnectionOptions::Mock(options) => ProjectPickerData::Ssh {
                 connection_string: format!("mock-{}", options.id).into(),
                 nickname: None,
+                platform: None,
             },
         };
         let _path_task = cx
@@ -455,59 +1343,30 @@ impl ProjectPicker {
                         determine_paths_with_positions(&remote_connection, paths).await;
 
                     cx.update(|_, cx| {
-                        let fs = app_state.fs.clone();
-                        update_settings_file(fs, cx, {
-                            let paths = paths
+                        add_project_to_server(
+                            app_state.fs.clone(),
+                            index,
+                            paths
                                 .iter()
                                 .map(|path| path.to_string_lossy().into_owned())
-                                .collect();
-                            move |settings, _| match index {
-                                ServerIndex::Ssh(index) => {
-                                    if let Some(server) = settings
-                                        .remote
-                                        .ssh_connections
-                                        .as_mut()
-                                        .and_then(|connections| connections.get_mut(index.0))
-                                    {
-                                        server.projects.insert(RemoteProject { paths });
-                                    };
-                                }
-                                ServerIndex::Wsl(index) => {
-                                    if let Some(server) = settings
-                                        .remote
-                                        .wsl_connections
-                                        .as_mut()
-                                        .and_then(|connections| connections.get_mut(index.0))
-                                    {
-                                        server.projects.insert(RemoteProject { paths });
-                                    };
-                                }
-                            }
-                        });
+                                .collect(),
+                            cx,
+                        );
                     })
                     .log_err();
 
-                    let window = if create_new_window {
-                        let options = cx
-                            .update(|_, cx| (app_state.build_window_options)(None, cx))
-                            .log_err()?;
-                        cx.open_window(options, |window, cx| {
-                            let workspace = cx.new(|cx| {
-                                telemetry::event!("SSH Project Created");
-                                Workspace::new(None, project.clone(), app_state.clone(), window, cx)
-                            });
-                            cx.new(|cx| MultiWorkspace::new(workspace, window, cx))
-                        })
-                        .log_err()
-                    } else {
-                        cx.window_handle().downcast::<MultiWorkspace>()
-                    }?;
-
-                    let items = open_remote_project_with_existing_connection(
-                        connection, project, paths, app_state, window, None, None, cx,
+                    let create_new_window = this
+                        .read_with(cx, |this, _| this.create_new_window)
+                        .unwrap_or(create_new_window);
+                    let (window, items) = open_connected_project(
+                        create_new_window,
+                        connection,
+                        project,
+                        paths,
+                        app_state,
+                        cx,
                     )
-                    .await
-                    .log_err();
+                    .await?;
 
                     if let Some(items) = items {
                         for (item, path) in items.into_iter().zip(paths_with_positions) {
@@ -562,10 +1421,12 @@ impl gpui::Render for ProjectPicker {
                 ProjectPickerData::Ssh {
                     connection_string,
                     nickname,
+                    platform,
                 } => SshConnectionHeader {
                     connection_string: connection_string.clone(),
                     paths: Default::default(),
                     nickname: nickname.clone(),
+                    platform: platform.clone(),
                     is_wsl: false,
                     is_devcontainer: false,
                 }
@@ -574,6 +1435,7 @@ impl gpui::Render for ProjectPicker {
                     connection_string: distro_name.clone(),
                     paths: Default::default(),
                     nickname: None,
+                    platform: None,
                     is_wsl: true,
                     is_devcontainer: false,
                 }
@@ -626,25 +1488,52 @@ impl From<WslServerIndex> for ServerIndex {
 enum RemoteEntry {
     Project {
         open_folder: NavigableEntry,
+        clone_repository: NavigableEntry,
         projects: Vec<(NavigableEntry, RemoteProject)>,
         configure: NavigableEntry,
         connection: Connection,
         index: ServerIndex,
     },
+    /// A saved server shown on a single line in the compact density. Its
+    /// projects and actions only get entries once it's expanded.
+    CompactProject {
+        row: NavigableEntry,
+        project_count: usize,
+        connection: Connection,
+        index: ServerIndex,
+    },
     SshConfig {
         open_folder: NavigableEntry,
         host: SharedString,
+        /// The SSH config file that defines the host.
+        source: Arc<Path>,
     },
 }
 
 impl RemoteEntry {
     fn is_from_zed(&self) -> bool {
-        matches!(self, Self::Project { .. })
+        matches!(self, Self::Project { .. } | Self::CompactProject { .. })
+    }
+
+    /// The connection of a server saved in the settings.
+    fn saved_connection(&self) -> Option<&Connection> {
+        match self {
+            Self::Project { connection, .. } | Self::CompactProject { connection, .. } => {
+                Some(connection)
+            }
+            Self::SshConfig { .. } => None,
+        }
+    }
+
+    fn tag(&self) -> Option<&str> {
+        self.saved_connection().and_then(connection_tag)
     }
 
     fn connection(&self) -> Cow<'_, Connection> {
         match self {
-            Self::Project { connection, .. } => Cow::Borrowed(connection),
+            Self::Project { connection, .. } | Self::CompactProject { connection, .. } => {
+                Cow::Borrowed(connection)
+            }
             Self::SshConfig { host, .. } => Cow::Owned(
                 SshConnection {
                     host: host.to_string(),
@@ -656,107 +1545,956 @@ impl RemoteEntry {
     }
 }
 
+/// The address a server is copied as: `user@host:port` for SSH, with the
+/// parts that aren't set left out, the distro for WSL, and the container id
+/// for dev containers.
+fn server_address(options: &RemoteConnectionOptions) -> String {
+    match options {
+        RemoteConnectionOptions::Ssh(options) => options.connection_string(),
+        RemoteConnectionOptions::Wsl(options) => options.distro_name.clone(),
+        RemoteConnectionOptions::Docker(options) => options.container_id.clone(),
+        #[cfg(any(test, feature = "test-support"))]
+        RemoteConnectionOptions::Mock(options) => format!("mock-{}", options.id),
+    }
+}
+
+/// The settings that hold saved remote servers.
+const REMOTE_SERVER_SETTINGS: &[&[&str]] = &[
+    &["ssh_connections"],
+    &["wsl_connections"],
+    &["dev_container_connections"],
+];
+
+pub(crate) fn normalize_remote_settings(
+    workspace: &mut Workspace,
+    window: &mut Window,
+    cx: &mut Context<Workspace>,
+) {
+    struct RemoteSettingsNormalized;
+
+    let normalized = SettingsStore::global(cx)
+        .normalize_settings_file(workspace.app_state().fs.clone(), REMOTE_SERVER_SETTINGS);
+    cx.spawn_in(window, async move |workspace, cx| {
+        let message = match normalized.await {
+            Ok(Ok(())) => "Rewrote the saved remote servers in settings".to_string(),
+            Ok(Err(error)) => format!("Failed to rewrite the saved remote servers: {error}"),
+            Err(_) => return,
+        };
+        workspace
+            .update(cx, |workspace, cx| {
+                workspace.show_toast(
+                    Toast::new(
+                        NotificationId::unique::<RemoteSettingsNormalized>(),
+                        message,
+                    )
+                    .autohide(),
+                    cx,
+                );
+            })
+            .ok();
+    })
+    .detach();
+}
+
+fn copy_server_address(workspace: &WeakEntity<Workspace>, address: SharedString, cx: &mut App) {
+    struct ServerAddressCopiedToClipboard;
+
+    cx.write_to_clipboard(ClipboardItem::new_string(address.to_string()));
+    workspace
+        .update(cx, |workspace, cx| {
+            workspace.show_toast(
+                Toast::new(
+                    NotificationId::composite::<ServerAddressCopiedToClipboard>(address.clone()),
+                    format!("Copied server address ({address}) to clipboard"),
+                )
+                .autohide(),
+                cx,
+            );
+        })
+        .ok();
+}
+
+fn copy_original_command(workspace: &WeakEntity<Workspace>, command: SharedString, cx: &mut App) {
+    struct OriginalCommandCopiedToClipboard;
+
+    cx.write_to_clipboard(ClipboardItem::new_string(command.to_string()));
+    workspace
+        .update(cx, |workspace, cx| {
+            workspace.show_toast(
+                Toast::new(
+                    NotificationId::composite::<OriginalCommandCopiedToClipboard>(command),
+                    "Copied the server's original command to clipboard",
+                )
+                .autohide(),
+                cx,
+            );
+        })
+        .ok();
+}
+
+/// The command the saved server at `index` was added with, if it was added
+/// with one.
+fn original_command(index: SshServerIndex, cx: &App) -> Option<SharedString> {
+    RemoteSettings::get_global(cx)
+        .ssh_connections()
+        .nth(index.0)
+        .and_then(|connection| connection.original_command)
+        .map(SharedString::from)
+}
+
+/// The tag a saved connection is grouped under, if it has one.
+fn connection_tag(connection: &Connection) -> Option<&str> {
+    let tag = match connection {
+        Connection::Ssh(connection) => connection.tag.as_deref(),
+        Connection::Wsl(connection) => connection.tag.as_deref(),
+        Connection::DevContainer(connection) => connection.tag.as_deref(),
+    };
+    tag.map(str::trim).filter(|tag| !tag.is_empty())
+}
+
+/// A badge with a saved server's tag, colored for common environment names so
+/// that production servers stand out before they're clicked.
+fn render_server_tag(tag: &str) -> Chip {
+    let color = match tag.to_lowercase().as_str() {
+        "prod" | "production" | "live" => Color::Error,
+        "staging" | "stage" | "preprod" | "qa" | "test" => Color::Warning,
+        "dev" | "development" | "local" | "personal" => Color::Success,
+        _ => Color::Accent,
+    };
+    Chip::new(tag.to_string()).label_color(color)
+}
+
+/// A row listing one connection a bulk settings edit changes, with the old
+/// and new value of each modified field.
+fn render_connection_change(change: &ConnectionChange) -> impl IntoElement {
+    let (icon, color) = match &change.kind {
+        ChangeKind::Added => (IconName::Plus, Color::Created),
+        ChangeKind::Removed => (IconName::Trash, Color::Deleted),
+        ChangeKind::Modified(_) => (IconName::Pencil, Color::Modified),
+    };
+    let fields = match &change.kind {
+        ChangeKind::Modified(fields) => fields.as_slice(),
+        ChangeKind::Added | ChangeKind::Removed => &[],
+    };
+    v_flex()
+        .px_2()
+        .py_0p5()
+        .child(
+            h_flex()
+                .gap_2()
+                .child(Icon::new(icon).size(IconSize::Small).color(color))
+                .child(Label::new(change.label.clone()).truncate()),
+        )
+        .children(fields.iter().map(|field| {
+            div().pl_6().child(
+                Label::new(format!("{}: {} → {}", field.field, field.old, field.new))
+                    .size(LabelSize::Small)
+                    .color(Color::Muted)
+                    .truncate(),
+            )
+        }))
+}
+
+/// The header above the saved servers with one tag.
+#[derive(Clone)]
+struct ServerTagGroupHeader {
+    toggle: NavigableEntry,
+    /// `None` for the servers without a tag, listed last as "Other".
+    tag: Option<SharedString>,
+    server_count: usize,
+    collapsed: bool,
+}
+
+/// Tags whose saved servers are collapsed in the server list, with `None` for
+/// the untagged ones. Remembered until Zed quits.
+#[derive(Default)]
+struct CollapsedServerTags(HashSet<Option<SharedString>>);
+
+impl Global for CollapsedServerTags {}
+
+fn is_server_tag_collapsed(tag: Option<&SharedString>, cx: &App) -> bool {
+    cx.try_global::<CollapsedServerTags>()
+        .is_some_and(|collapsed| collapsed.0.contains(&tag.cloned()))
+}
+
+/// The server whose row in the default list has focus.
+enum SelectedServer {
+    Saved(ServerIndex, Connection),
+    DevContainer(DevContainerConnection),
+    /// A host that's only in the SSH config, so it has no saved settings.
+    SshConfigHost,
+}
+
+/// A row of the saved servers in the default list.
+enum SavedServerRow<'a> {
+    TagHeader(usize, &'a ServerTagGroupHeader),
+    Server(usize, &'a RemoteEntry),
+}
+
+/// A saved dev container in the server list.
+#[derive(Clone)]
+struct DevContainerEntry {
+    /// Copies the container's setup command.
+    row: NavigableEntry,
+    /// Shows or hides the container's details and management actions.
+    manage: NavigableEntry,
+    /// Edits the user the container is connected to as, and its extra
+    /// environment variables, when its management actions are shown.
+    edit_user: Option<NavigableEntry>,
+    /// Switches the container between docker and podman, when its
+    /// management actions are shown.
+    change_engine: Option<NavigableEntry>,
+    /// Edits the container's tag, when its management actions are shown.
+    edit_tag: Option<NavigableEntry>,
+    /// Removes the container from the saved ones, when its management
+    /// actions are shown.
+    remove: Option<NavigableEntry>,
+    /// The container's position in the settings.
+    index: usize,
+    connection: DevContainerConnection,
+}
+
+/// The header above the hosts from one SSH config file.
+#[derive(Clone)]
+struct SshConfigGroupHeader {
+    toggle: NavigableEntry,
+    source: Arc<Path>,
+    /// The hosts of the file that aren't saved yet and match the search.
+    hosts: Vec<SharedString>,
+    collapsed: bool,
+    /// How many of `hosts` aren't shown until the user asks for more.
+    hidden_host_count: usize,
+    /// Shows the next page of hosts, below them while some are hidden.
+    show_more: Option<NavigableEntry>,
+    /// Saves all of `hosts` at once, shown below them while expanded.
+    save_all: Option<NavigableEntry>,
+}
+
+/// SSH config files whose hosts are collapsed in the server list. Remembered
+/// until Zed quits.
+#[derive(Default)]
+struct CollapsedSshConfigSources(HashSet<Arc<Path>>);
+
+impl Global for CollapsedSshConfigSources {}
+
+fn is_ssh_config_source_collapsed(source: &Path, cx: &App) -> bool {
+    cx.try_global::<CollapsedSshConfigSources>()
+        .is_some_and(|collapsed| collapsed.0.contains(source))
+}
+
+/// How many reconnects to a server can fail in a row before reconnecting
+/// opens the remote projects modal instead.
+const MAX_RECONNECT_ATTEMPTS: u32 = 3;
+
+/// Reconnects that have failed in a row, by the options of the connection
+/// that was lost.
+#[derive(Default)]
+struct FailedReconnects(HashMap<RemoteConnectionOptions, FailedReconnect>);
+
+impl Global for FailedReconnects {}
+
+#[derive(Clone, Copy)]
+struct FailedReconnect {
+    count: u32,
+    last_failed_at: Instant,
+}
+
+impl FailedReconnect {
+    /// How long to wait before reconnecting again, doubling with each failure.
+    fn backoff(&self) -> Duration {
+        Duration::from_secs(1 << self.count.min(4))
+    }
+}
+
+/// Opens the remote servers modal with the saved server `connection_options`
+/// connects to selected.
+pub fn open_remote_servers_for(
+    connection_options: RemoteConnectionOptions,
+    workspace: Entity<Workspace>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    workspace.update(cx, |workspace, cx| {
+        let fs = workspace.app_state().fs.clone();
+        let handle = cx.entity().downgrade();
+        workspace.toggle_modal(window, cx, |window, cx| {
+            let modal = RemoteServerProjects::new(false, fs, window, handle, cx);
+            // Deferred like in `new_with_defaults`, since the modal takes
+            // focus once it's shown.
+            cx.defer_in(window, move |this, window, cx| {
+                this.select_saved_server(&connection_options, window, cx);
+            });
+            modal
+        });
+    });
+}
+
+/// Reconnects `workspace` to the server it lost its connection to, and
+/// reopens the same project paths in its window. The saved settings for the
+/// server are used to connect, so edits made since the workspace connected
+/// apply.
+///
+/// Failed reconnects are retried with a growing delay. After
+/// [`MAX_RECONNECT_ATTEMPTS`] failures in a row, this opens the remote
+/// projects modal with the server selected instead.
+pub fn reconnect_to_remote_project(
+    connection_options: RemoteConnectionOptions,
+    workspace: Entity<Workspace>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let failed = cx
+        .try_global::<FailedReconnects>()
+        .and_then(|failed| failed.0.get(&connection_options).copied());
+    if failed.is_some_and(|failed| failed.count >= MAX_RECONNECT_ATTEMPTS) {
+        cx.default_global::<FailedReconnects>()
+            .0
+            .remove(&connection_options);
+        open_remote_servers_for(connection_options, workspace, window, cx);
+        return;
+    }
+
+    let Some(window_handle) = window.window_handle().downcast::<MultiWorkspace>() else {
+        return;
+    };
+    let app_state = workspace.read(cx).app_state().clone();
+    let paths = workspace
+        .read(cx)
+        .root_paths(cx)
+        .iter()
+        .map(|path| path.to_path_buf())
+        .collect();
+    let mut saved_connection_options = connection_options.clone();
+    RemoteSettings::get_global(cx)
+        .fill_remote_connection_options_from_settings(&mut saved_connection_options);
+    let executor = cx.background_executor().clone();
+    let delay = failed.map(|failed| {
+        failed
+            .backoff()
+            .saturating_sub(executor.now().duration_since(failed.last_failed_at))
+    });
+
+    window
+        .spawn(cx, async move |cx| {
+            if let Some(delay) = delay {
+                executor.timer(delay).await;
+            }
+            let result = open_remote_project(
+                saved_connection_options,
+                paths,
+                app_state,
+                OpenOptions {
+                    requesting_window: Some(window_handle),
+                    ..Default::default()
+                },
+                cx,
+            )
+            .await;
+            // Failing to connect is prompted about by `open_remote_project`,
+            // which leaves the disconnected workspace active.
+            let reconnected = result.is_ok()
+                && window_handle
+                    .read_with(cx, |multi_workspace, cx| {
+                        !multi_workspace
+                            .workspace()
+                            .read(cx)
+                            .project()
+                            .read(cx)
+                            .is_disconnected(cx)
+                    })
+                    .unwrap_or(false);
+            cx.update(|_, cx| {
+                let failed_reconnects = &mut cx.default_global::<FailedReconnects>().0;
+                if reconnected {
+                    failed_reconnects.remove(&connection_options);
+                } else {
+                    let failed =
+                        failed_reconnects
+                            .entry(connection_options)
+                            .or_insert(FailedReconnect {
+                                count: 0,
+                                last_failed_at: executor.now(),
+                            });
+                    failed.count += 1;
+                    failed.last_failed_at = executor.now();
+                }
+            })?;
+            result.map(|_| ())
+        })
+        .detach_and_prompt_err("Failed to reconnect", window, cx, |_, _, _| None);
+}
+
+/// The project folder on the host that the saved dev container `options`
+/// connects to was started from.
+fn dev_container_host_project_root(options: &DockerConnectionOptions, cx: &App) -> Option<PathBuf> {
+    RemoteSettings::get_global(cx)
+        .dev_container_connections
+        .iter()
+        .find(|connection| connection.container_id == options.container_id)
+        .and_then(|connection| connection.host_project_root.as_deref())
+        .map(PathBuf::from)
+}
+
+/// Replaces `workspace`, which is connected to the dev container `options`,
+/// with the project folder on the host that the container was started from,
+/// leaving the container running. Resolves to whether the workspace was
+/// replaced, which the user can decline when asked to save their changes.
+pub fn disconnect_dev_container_now(
+    options: &DockerConnectionOptions,
+    workspace: Entity<Workspace>,
+    window: &mut Window,
+    cx: &mut App,
+) -> Task<anyhow::Result<bool>> {
+    let Some(host_project_root) = dev_container_host_project_root(options, cx) else {
+        return Task::ready(Err(anyhow::anyhow!(
+            "The project folder that {} was started from isn't known. Open it from the \
+             host instead.",
+            options.name
+        )));
+    };
+    let Some(multi_workspace) = window.root::<MultiWorkspace>().flatten() else {
+        return Task::ready(Ok(false));
+    };
+    multi_workspace.update(cx, |multi_workspace, cx| {
+        let container_workspace = workspace.clone();
+        multi_workspace.remove(
+            [workspace],
+            move |multi_workspace, window, cx| {
+                multi_workspace.find_or_create_local_workspace(
+                    PathList::new(&[host_project_root]),
+                    None,
+                    std::slice::from_ref(&container_workspace),
+                    None,
+                    OpenMode::Activate,
+                    window,
+                    cx,
+                )
+            },
+            window,
+            cx,
+        )
+    })
+}
+
+/// Stops the dev container `options` once the user confirms, after replacing
+/// `workspace` with the project folder on the host like
+/// [`disconnect_dev_container_now`] does.
+pub fn stop_dev_container_now(
+    options: DockerConnectionOptions,
+    workspace: Entity<Workspace>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let confirmation = window.prompt(
+        PromptLevel::Warning,
+        &format!("Stop dev container `{}`?", options.name),
+        Some("The project reopens on the host. Opening it in the container again restarts it."),
+        &["Stop", "Cancel"],
+        cx,
+    );
+    window
+        .spawn(cx, async move |cx| {
+            if confirmation.await.ok() != Some(0) {
+                return Ok(());
+            }
+            let replaced = cx
+                .update(|window, cx| disconnect_dev_container_now(&options, workspace, window, cx))?
+                .await?;
+            if !replaced {
+                return Ok(());
+            }
+            dev_container::stop_dev_container(
+                &options.container_id,
+                options.use_podman,
+                &DockerHost::from_wsl_distro(options.wsl_distro.clone()),
+            )
+            .await
+            .map_err(|error| anyhow::anyhow!("{error}"))
+        })
+        .detach_and_prompt_err("Failed to stop the dev container", window, cx, |_, _, _| {
+            None
+        });
+}
+
+/// How many hosts of an SSH config file the server list shows at first, and
+/// how many more each "Show More Hosts" row adds.
+const SSH_CONFIG_HOSTS_PAGE_SIZE: usize = 50;
+
+/// Identifies a saved server by what it connects to, rather than by its
+/// position in the settings, which changes as other servers are added or
+/// removed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum SavedServerKey {
+    Ssh {
+        host: String,
+        username: Option<String>,
+        port: Option<u16>,
+    },
+    Wsl {
+        distro_name: String,
+        user: Option<String>,
+    },
+    DevContainer(String),
+}
+
+impl SavedServerKey {
+    fn new(connection: &Connection) -> Self {
+        match connection {
+            Connection::Ssh(connection) => Self::Ssh {
+                host: connection.host.clone(),
+                username: connection.username.clone(),
+                port: connection.port,
+            },
+            Connection::Wsl(connection) => Self::Wsl {
+                distro_name: connection.distro_name.clone(),
+                user: connection.user.clone(),
+            },
+            Connection::DevContainer(connection) => {
+                Self::DevContainer(connection.container_id.clone())
+            }
+        }
+    }
+}
+
+/// Identifies a row of the server list across rebuilds of [`DefaultState`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum RemoteEntryKey {
+    AddNewServer,
+    AddNewDevContainer,
+    AddNewWsl,
+    ClearSearch,
+    /// A saved server shown on a single line in the compact density.
+    CompactServer(SavedServerKey),
+    OpenFolder(SavedServerKey),
+    CloneRepository(SavedServerKey),
+    Configure(SavedServerKey),
+    /// A project of a saved server, by its paths.
+    Project(SavedServerKey, Vec<String>),
+    TagGroup(Option<SharedString>),
+    SshConfigGroup(Arc<Path>),
+    SshConfigHost(Arc<Path>, SharedString),
+    ShowMoreSshConfigHosts(Arc<Path>),
+    SaveSshConfigHosts(Arc<Path>),
+    /// A saved dev container, by its container id.
+    DevContainer(String),
+    ManageDevContainer(String),
+    EditDevContainerUser(String),
+    ChangeDevContainerEngine(String),
+    EditDevContainerTag(String),
+    RemoveDevContainer(String),
+    RemoveMissingDevContainers,
+}
+
+/// The entries of the rows in the server list, kept by the modal so that
+/// rebuilding the list reuses the focus handles of the rows it still shows,
+/// and keeps its scroll position. Rows that aren't shown anymore, such as the
+/// hosts of a collapsed SSH config group or a removed server, drop theirs.
+/// The hosts of an SSH config file are shown a page at a time, so only the
+/// pages the user asked for have entries.
+#[derive(Default)]
+struct ServerListEntries {
+    scroll_handle: ScrollHandle,
+    entries: HashMap<RemoteEntryKey, NavigableEntry>,
+    /// The entries of the previous build that haven't been reused yet.
+    unused: HashMap<RemoteEntryKey, NavigableEntry>,
+    /// The container ids of the dev containers whose management actions are
+    /// shown.
+    managed_dev_containers: HashSet<String>,
+    /// How many hosts of each SSH config file are shown, for the files that
+    /// show more than [`SSH_CONFIG_HOSTS_PAGE_SIZE`].
+    shown_ssh_config_hosts: HashMap<Arc<Path>, usize>,
+}
+
+impl ServerListEntries {
+    fn start_rebuild(&mut self) {
+        self.unused = std::mem::take(&mut self.entries);
+    }
+
+    fn entry(&mut self, key: RemoteEntryKey, cx: &App) -> NavigableEntry {
+        // Saved servers that connect to the same place share a key, and only
+        // the first of them keeps its entry across rebuilds.
+        if self.entries.contains_key(&key) {
+            return NavigableEntry::new(&self.scroll_handle, cx);
+        }
+        let entry = self
+            .unused
+            .remove(&key)
+            .unwrap_or_else(|| NavigableEntry::new(&self.scroll_handle, cx));
+        self.entries.insert(key, entry.clone());
+        entry
+    }
+
+    fn finish_rebuild(&mut self) {
+        self.unused.clear();
+    }
+
+    fn shown_ssh_config_hosts(&self, source: &Path) -> usize {
+        self.shown_ssh_config_hosts
+            .get(source)
+            .copied()
+            .unwrap_or(SSH_CONFIG_HOSTS_PAGE_SIZE)
+    }
+
+    /// How many rows have entries, which is never more than the list shows.
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
 #[derive(Clone)]
 struct DefaultState {
     scroll_handle: ScrollHandle,
     add_new_server: NavigableEntry,
     add_new_devcontainer: NavigableEntry,
     add_new_wsl: NavigableEntry,
+    /// Shown in place of the server list when the search matches nothing.
+    clear_search: NavigableEntry,
+    /// Saved servers, followed by the hosts of expanded SSH config groups.
     servers: Vec<RemoteEntry>,
+    /// The groups saved servers are listed in by tag, or empty when none of
+    /// them has a tag.
+    tag_groups: Vec<ServerTagGroupHeader>,
+    ssh_config_groups: Vec<SshConfigGroupHeader>,
+    dev_containers: Vec<DevContainerEntry>,
+    /// Removes the saved dev containers whose project is gone, shown below
+    /// them when there are any.
+    remove_missing_dev_containers: Option<NavigableEntry>,
+    /// The SSH config hosts this state was built from.
+    ssh_config_servers: Vec<SshConfigHostGroup>,
+    density: RemoteServersDensity,
+    /// The search query the servers were filtered by.
+    search_query: String,
 }
 
 impl DefaultState {
-    fn new(ssh_config_servers: &BTreeSet<SharedString>, cx: &mut App) -> Self {
-        let handle = ScrollHandle::new();
-        let add_new_server = NavigableEntry::new(&handle, cx);
-        let add_new_devcontainer = NavigableEntry::new(&handle, cx);
-        let add_new_wsl = NavigableEntry::new(&handle, cx);
+    /// In the compact density, every saved server other than
+    /// `expanded_server` is shown on a single line.
+    ///
+    /// Only the rows that are shown get entries, taken from `list_entries`
+    /// when an earlier build already showed them, and only those matching
+    /// `search_query`.
+    fn new(
+        ssh_config_servers: &[SshConfigHostGroup],
+        expanded_server: Option<ServerIndex>,
+        search_query: &str,
+        list_entries: &mut ServerListEntries,
+        cx: &mut App,
+    ) -> Self {
+        list_entries.start_rebuild();
+        let add_new_server = list_entries.entry(RemoteEntryKey::AddNewServer, cx);
+        let add_new_devcontainer = list_entries.entry(RemoteEntryKey::AddNewDevContainer, cx);
+        let add_new_wsl = list_entries.entry(RemoteEntryKey::AddNewWsl, cx);
+        let clear_search = list_entries.entry(RemoteEntryKey::ClearSearch, cx);
 
         let ssh_settings = RemoteSettings::get_global(cx);
         let read_ssh_config = ssh_settings.read_ssh_config;
+        let density = ssh_settings.density;
+        let search_query = search_query.to_string();
+        let is_collapsed = move |index: ServerIndex| {
+            density == RemoteServersDensity::Compact && expanded_server != Some(index)
+        };
+        let server_entry = |index: ServerIndex,
+                            connection: Connection,
+                            projects: &[RemoteProject],
+                            list_entries: &mut ServerListEntries| {
+            let key = SavedServerKey::new(&connection);
+            if is_collapsed(index) {
+                return RemoteEntry::CompactProject {
+                    row: list_entries.entry(RemoteEntryKey::CompactServer(key), cx),
+                    project_count: projects.len(),
+                    connection,
+                    index,
+                };
+            }
+            let open_folder = list_entries.entry(RemoteEntryKey::OpenFolder(key.clone()), cx);
+            let clone_repository =
+                list_entries.entry(RemoteEntryKey::CloneRepository(key.clone()), cx);
+            let configure = list_entries.entry(RemoteEntryKey::Configure(key.clone()), cx);
+            let projects = projects
+                .iter()
+                .map(|project| {
+                    let project_key = RemoteEntryKey::Project(key.clone(), project.paths.clone());
+                    (list_entries.entry(project_key, cx), project.clone())
+                })
+                .collect();
+            RemoteEntry::Project {
+                open_folder,
+                clone_repository,
+                configure,
+                projects,
+                index,
+                connection,
+            }
+        };
 
-        let ssh_servers = ssh_settings
-            .ssh_connections()
-            .enumerate()
-            .map(|(index, connection)| {
-                let open_folder = NavigableEntry::new(&handle, cx);
-                let configure = NavigableEntry::new(&handle, cx);
-                let projects = connection
-                    .projects
-                    .iter()
-                    .map(|project| (NavigableEntry::new(&handle, cx), project.clone()))
-                    .collect();
-                RemoteEntry::Project {
-                    open_folder,
-                    configure,
-                    projects,
-                    index: ServerIndex::Ssh(SshServerIndex(index)),
-                    connection: connection.into(),
-                }
-            });
+        let mut servers = Vec::new();
+        for (index, connection) in ssh_settings.ssh_connections().enumerate() {
+            if matches_search_query(&ssh_connection_search_haystack(&connection), &search_query) {
+                let index = ServerIndex::Ssh(SshServerIndex(index));
+                let projects = connection.projects.iter().cloned().collect::<Vec<_>>();
+                servers.push(server_entry(
+                    index,
+                    connection.into(),
+                    &projects,
+                    list_entries,
+                ));
+            }
+        }
+        for (index, connection) in ssh_settings.wsl_connections().enumerate() {
+            if matches_search_query(&wsl_connection_search_haystack(&connection), &search_query) {
+                let index = ServerIndex::Wsl(WslServerIndex(index));
+                let projects = connection.projects.iter().cloned().collect::<Vec<_>>();
+                servers.push(server_entry(
+                    index,
+                    connection.into(),
+                    &projects,
+                    list_entries,
+                ));
+            }
+        }
 
-        let wsl_servers = ssh_settings
-            .wsl_connections()
+        let mut tags = servers
+            .iter()
+            .filter_map(|server| server.tag().map(SharedString::new))
+            .collect::<Vec<_>>();
+        tags.sort_by_key(|tag| tag.to_lowercase());
+        tags.dedup();
+        let tag_groups = if tags.is_empty() {
+            Vec::new()
+        } else {
+            tags.into_iter()
+                .map(Some)
+                .chain([None])
+                .filter_map(|tag| {
+                    let server_count = servers
+                        .iter()
+                        .filter(|server| server.tag() == tag.as_deref())
+                        .count();
+                    (server_count > 0).then(|| ServerTagGroupHeader {
+                        toggle: list_entries.entry(RemoteEntryKey::TagGroup(tag.clone()), cx),
+                        collapsed: is_server_tag_collapsed(tag.as_ref(), cx),
+                        tag,
+                        server_count,
+                    })
+                })
+                .collect()
+        };
+
+        let dev_containers = ssh_settings
+            .dev_container_connections
+            .clone()
+            .into_iter()
             .enumerate()
+            .filter(|(_, connection)| {
+                matches_search_query(
+                    &dev_container_connection_search_haystack(connection),
+                    &search_query,
+                )
+            })
             .map(|(index, connection)| {
-                let open_folder = NavigableEntry::new(&handle, cx);
-                let configure = NavigableEntry::new(&handle, cx);
-                let projects = connection
-                    .projects
-                    .iter()
-                    .map(|project| (NavigableEntry::new(&handle, cx), project.clone()))
-                    .collect();
-                RemoteEntry::Project {
-                    open_folder,
-                    configure,
-                    projects,
-                    index: ServerIndex::Wsl(WslServerIndex(index)),
-                    connection: connection.into(),
+                let id = &connection.container_id;
+                let is_managed = list_entries.managed_dev_containers.contains(id);
+                let mut managed_entry = |key: fn(String) -> RemoteEntryKey| {
+                    is_managed.then(|| list_entries.entry(key(id.clone()), cx))
+                };
+                DevContainerEntry {
+                    edit_user: managed_entry(RemoteEntryKey::EditDevContainerUser),
+                    change_engine: managed_entry(RemoteEntryKey::ChangeDevContainerEngine),
+                    edit_tag: managed_entry(RemoteEntryKey::EditDevContainerTag),
+                    remove: managed_entry(RemoteEntryKey::RemoveDevContainer),
+                    row: list_entries.entry(RemoteEntryKey::DevContainer(id.clone()), cx),
+                    manage: list_entries.entry(RemoteEntryKey::ManageDevContainer(id.clone()), cx),
+                    index,
+                    connection,
                 }
-            });
+            })
+            .collect::<Vec<_>>();
+        let remove_missing_dev_containers = (!dev_containers.is_empty())
+            .then(|| list_entries.entry(RemoteEntryKey::RemoveMissingDevContainers, cx));
 
-        let mut servers = ssh_servers.chain(wsl_servers).collect::<Vec<RemoteEntry>>();
+        let mut ssh_config_groups = Vec::new();
 
         if read_ssh_config {
-            let mut extra_servers_from_config = ssh_config_servers.clone();
-            for server in &servers {
-                if let RemoteEntry::Project {
-                    connection: Connection::Ssh(ssh_options),
-                    ..
-                } = server
-                {
-                    extra_servers_from_config.remove(&SharedString::new(ssh_options.host.clone()));
+            let saved_hosts = ssh_settings
+                .ssh_connections
+                .0
+                .iter()
+                .map(|connection| connection.host.as_str())
+                .collect::<HashSet<_>>();
+
+            for group in ssh_config_servers {
+                let hosts = group
+                    .hosts
+                    .iter()
+                    .filter(|entry| {
+                        !saved_hosts.contains(entry.host.as_str())
+                            && matches_search_query(&entry.host, &search_query)
+                    })
+                    .collect::<Vec<_>>();
+                if hosts.is_empty() {
+                    continue;
                 }
-            }
-            servers.extend(extra_servers_from_config.into_iter().map(|host| {
-                RemoteEntry::SshConfig {
-                    open_folder: NavigableEntry::new(&handle, cx),
-                    host,
+
+                let collapsed = is_ssh_config_source_collapsed(&group.source, cx);
+                let shown = list_entries.shown_ssh_config_hosts(&group.source);
+                let hidden_host_count = if collapsed {
+                    0
+                } else {
+                    hosts.len().saturating_sub(shown)
+                };
+                let key = RemoteEntryKey::SshConfigGroup(group.source.clone());
+                let show_more_key = RemoteEntryKey::ShowMoreSshConfigHosts(group.source.clone());
+                let save_all_key = RemoteEntryKey::SaveSshConfigHosts(group.source.clone());
+                ssh_config_groups.push(SshConfigGroupHeader {
+                    toggle: list_entries.entry(key, cx),
+                    source: group.source.clone(),
+                    hosts: hosts
+                        .iter()
+                        .map(|entry| SharedString::from(entry.host.clone()))
+                        .collect(),
+                    collapsed,
+                    hidden_host_count,
+                    show_more: (hidden_host_count > 0)
+                        .then(|| list_entries.entry(show_more_key, cx)),
+                    save_all: (!collapsed).then(|| list_entries.entry(save_all_key, cx)),
+                });
+                if !collapsed {
+                    servers.extend(hosts.into_iter().take(shown).map(|entry| {
+                        let host = SharedString::from(entry.host.clone());
+                        let key = RemoteEntryKey::SshConfigHost(group.source.clone(), host.clone());
+                        RemoteEntry::SshConfig {
+                            open_folder: list_entries.entry(key, cx),
+                            host,
+                            source: group.source.clone(),
+                        }
+                    }));
                 }
-            }));
+            }
         }
+        list_entries.finish_rebuild();
 
         Self {
-            scroll_handle: handle,
+            scroll_handle: list_entries.scroll_handle.clone(),
             add_new_server,
             add_new_devcontainer,
             add_new_wsl,
+            clear_search,
             servers,
+            tag_groups,
+            ssh_config_groups,
+            dev_containers,
+            remove_missing_dev_containers,
+            ssh_config_servers: ssh_config_servers.to_vec(),
+            density,
+            search_query,
         }
     }
-}
 
-#[derive(Clone)]
+    /// The server that the focused row belongs to, if any.
+    fn selected_server(&self, window: &Window, cx: &App) -> Option<SelectedServer> {
+        let is_focused = |entry: &NavigableEntry| entry.focus_handle.contains_focused(window, cx);
+        for server in &self.servers {
+            match server {
+                RemoteEntry::Project {
+                    open_folder,
+                    clone_repository,
+                    projects,
+                    configure,
+                    connection,
+                    index,
+                } => {
+                    let is_selected = [open_folder, clone_repository, configure]
+                        .into_iter()
+                        .chain(projects.iter().map(|(entry, _)| entry))
+                        .any(is_focused);
+                    if is_selected {
+                        return Some(SelectedServer::Saved(*index, connection.clone()));
+                    }
+                }
+                RemoteEntry::CompactProject {
+                    row,
+                    connection,
+                    index,
+                    ..
+                } => {
+                    if is_focused(row) {
+                        return Some(SelectedServer::Saved(*index, connection.clone()));
+                    }
+                }
+                RemoteEntry::SshConfig { open_folder, .. } => {
+                    if is_focused(open_folder) {
+                        return Some(SelectedServer::SshConfigHost);
+                    }
+                }
+            }
+        }
+        self.dev_containers
+            .iter()
+            .find(|container| {
+                [&container.row, &container.manage]
+                    .into_iter()
+                    .chain(&container.edit_user)
+                    .chain(&container.edit_tag)
+                    .chain(&container.remove)
+                    .any(is_focused)
+            })
+            .map(|container| SelectedServer::DevContainer(container.connection.clone()))
+    }
+
+    /// The saved servers in the order they're listed, under the headers of
+    /// their tag groups. Servers in collapsed groups are left out.
+    fn saved_server_rows(&self) -> Vec<SavedServerRow<'_>> {
+        let saved_servers = || {
+            self.servers
+                .iter()
+                .enumerate()
+                .filter(|(_, server)| server.is_from_zed())
+        };
+        if self.tag_groups.is_empty() {
+            return saved_servers()
+                .map(|(ix, server)| SavedServerRow::Server(ix, server))
+                .collect();
+        }
+
+        let mut rows = Vec::new();
+        for (group_ix, group) in self.tag_groups.iter().enumerate() {
+            rows.push(SavedServerRow::TagHeader(group_ix, group));
+            if !group.collapsed {
+                rows.extend(
+                    saved_servers()
+                        .filter(|(_, server)| server.tag() == group.tag.as_deref())
+                        .map(|(ix, server)| SavedServerRow::Server(ix, server)),
+                );
+            }
+        }
+        rows
+    }
+}
+
+/// Whether the server is connected, with a port forward that couldn't listen.
+fn has_failed_port_forwards(connection: &SshConnectionOptions, cx: &App) -> bool {
+    remote::active_connection(&RemoteConnectionOptions::Ssh(connection.clone()), cx).is_some_and(
+        |connection| {
+            connection
+                .port_forwards()
+                .iter()
+                .any(PortForward::is_failed)
+        },
+    )
+}
+
+#[derive(Clone)]
 enum ViewServerOptionsState {
     Ssh {
         connection: SshConnectionOptions,
         server_index: SshServerIndex,
-        entries: [NavigableEntry; 4],
+        entries: [NavigableEntry; 13],
     },
     Wsl {
         connection: WslConnectionOptions,
         server_index: WslServerIndex,
-        entries: [NavigableEntry; 2],
+        entries: [NavigableEntry; 4],
     },
 }
 
@@ -773,16 +2511,28 @@ enum Mode {
     Default(DefaultState),
     ViewServerOptions(ViewServerOptionsState),
     EditNickname(EditNicknameState),
+    EditKeepalive(EditKeepaliveState),
+    EditShell(EditShellState),
+    EditEnv(EditEnvState),
+    EditDevContainerUser(EditDevContainerUserState),
+    EditTag(EditTagState),
     ProjectPicker(Entity<ProjectPicker>),
     CreateRemoteServer(CreateRemoteServer),
     CreateRemoteDevContainer(CreateRemoteDevContainer),
+    CloneRepository(CloneRepository),
+    ConfirmSettingsChanges(ConfirmSettingsChangesState),
     #[cfg(target_os = "windows")]
     AddWslDistro(AddWslDistro),
 }
 
 impl Mode {
-    fn default_mode(ssh_config_servers: &BTreeSet<SharedString>, cx: &mut App) -> Self {
-        Self::Default(DefaultState::new(ssh_config_servers, cx))
+    /// The rows of the environment editor shown, and its error, if one is.
+    fn env_rows_mut(&mut self) -> Option<(&mut Vec<EnvVarRow>, &mut Option<SharedString>)> {
+        match self {
+            Mode::EditEnv(state) => Some((&mut state.rows, &mut state.error)),
+            Mode::EditDevContainerUser(state) => Some((&mut state.env_rows, &mut state.error)),
+            _ => None,
+        }
     }
 }
 
@@ -812,54 +2562,73 @@ impl RemoteServerProjects {
         workspace: WeakEntity<Workspace>,
         cx: &mut Context<Self>,
     ) -> Self {
-        Self::new_inner(
-            Mode::default_mode(&BTreeSet::new(), cx),
+        let mut server_list_entries = ServerListEntries::default();
+        let state = DefaultState::new(&[], None, "", &mut server_list_entries, cx);
+        let mut this = Self::new_inner(
+            Mode::Default(state),
             create_new_window,
             fs,
             window,
             workspace,
             cx,
-        )
+        );
+        this.server_list_entries = server_list_entries;
+        this
+    }
+
+    /// Creates a new RemoteServerProjects modal that focuses the user's preferred
+    /// section, or runs their default action, once it's shown.
+    pub fn new_with_defaults(
+        create_new_window: bool,
+        fs: Arc<dyn Fs>,
+        window: &mut Window,
+        workspace: WeakEntity<Workspace>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let this = Self::new(create_new_window, fs, window, workspace, cx);
+        // Deferred because the workspace is still being updated while the modal
+        // is constructed, and the modal takes focus once it's shown.
+        cx.defer_in(
+            window,
+            |this, window, cx| match RemoteSettings::get_global(cx).default_action {
+                RemoteServersDefaultAction::None => this.focus_default_section(window, cx),
+                RemoteServersDefaultAction::ConnectSshServer => {
+                    this.mode = Mode::CreateRemoteServer(CreateRemoteServer::new(window, cx));
+                    cx.notify();
+                }
+                RemoteServersDefaultAction::ConnectDevContainer => {
+                    if this.can_connect_dev_container(cx) {
+                        this.init_dev_container_mode(window, cx);
+                    } else {
+                        this.focus_default_section(window, cx);
+                    }
+                }
+            },
+        );
+        this
     }
 
     /// Creates a new RemoteServerProjects modal that opens directly in dev container creation mode.
     /// Used when suggesting dev container connection from toast notification.
     pub fn new_dev_container(
         fs: Arc<dyn Fs>,
-        configs: Vec<DevContainerConfig>,
-        app_state: Arc<AppState>,
-        dev_container_context: Option<DevContainerContext>,
+        configs: Task<Vec<DevContainerConfig>>,
         window: &mut Window,
         workspace: WeakEntity<Workspace>,
         cx: &mut Context<Self>,
     ) -> Self {
-        let initial_mode = if configs.len() > 1 {
-            DevContainerCreationProgress::SelectingConfig
-        } else {
-            DevContainerCreationProgress::Creating
-        };
-
         let mut this = Self::new_inner(
-            Mode::CreateRemoteDevContainer(CreateRemoteDevContainer::new(initial_mode, cx)),
+            Mode::CreateRemoteDevContainer(CreateRemoteDevContainer::new(
+                DevContainerCreationProgress::Discovering,
+                cx,
+            )),
             false,
             fs,
             window,
             workspace,
             cx,
         );
-
-        if configs.len() > 1 {
-            let delegate = DevContainerPickerDelegate::new(configs, cx.weak_entity());
-            this.dev_container_picker =
-                Some(cx.new(|cx| Picker::uniform_list(delegate, window, cx).modal(false)));
-        } else if let Some(context) = dev_container_context {
-            let config = configs.into_iter().next();
-            this.open_dev_container(config, app_state, context, window, cx);
-            this.view_in_progress_dev_container(window, cx);
-        } else {
-            log::error!("No active project directory for Dev Container");
-        }
-
+        this.discover_dev_container_configs(configs, window, cx);
         this
     }
 
@@ -871,7 +2640,7 @@ impl RemoteServerProjects {
         cx: &mut App,
     ) -> Entity<Self> {
         cx.new(|cx| {
-            let server = Self::new(create_new_window, fs, window, workspace, cx);
+            let server = Self::new_with_defaults(create_new_window, fs, window, workspace, cx);
             server.focus_handle(cx).focus(window, cx);
             server
         })
@@ -899,6 +2668,51 @@ impl RemoteServerProjects {
             ..Default::default()
         });
 
+        let server_search_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Search servers and projects…", window, cx);
+            editor
+        });
+        cx.subscribe_in(
+            &server_search_editor,
+            window,
+            |this, editor, event: &EditorEvent, _, cx| {
+                if let EditorEvent::BufferEdited = event {
+                    this.server_search_query = editor.read(cx).text(cx);
+                    cx.notify();
+                }
+            },
+        )
+        .detach();
+
+        let db = WorkspaceDb::global(cx);
+        cx.spawn({
+            let fs = fs.clone();
+            async move |this, cx| {
+                let mut workspaces = db
+                    .recent_project_workspaces(fs.as_ref())
+                    .await
+                    .log_err()
+                    .unwrap_or_default();
+                workspaces.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+                let history = workspaces
+                    .into_iter()
+                    .filter_map(|workspace| match workspace.location {
+                        SerializedWorkspaceLocation::Remote(options) => {
+                            Some((options, workspace.paths.paths().to_vec()))
+                        }
+                        SerializedWorkspaceLocation::Local => None,
+                    })
+                    .collect();
+                this.update(cx, |this, cx| {
+                    this.remote_project_history = history;
+                    cx.notify();
+                })
+                .ok();
+            }
+        })
+        .detach();
+
         let _subscription =
             cx.observe_global_in::<SettingsStore>(window, move |recent_projects, _, cx| {
                 let new_read_ssh_config = RemoteSettings::get_global(cx).read_ssh_config;
@@ -908,6 +2722,8 @@ impl RemoteServerProjects {
                         recent_projects.ssh_config_updates = spawn_ssh_config_watch(fs.clone(), cx);
                     } else {
                         recent_projects.ssh_config_servers.clear();
+                        recent_projects.ssh_config_proxied_hosts.clear();
+                        recent_projects.ssh_config_proxy_jumps.clear();
                         recent_projects.ssh_config_updates = Task::ready(());
                     }
                 }
@@ -919,14 +2735,52 @@ impl RemoteServerProjects {
             workspace,
             retained_connections: Vec::new(),
             ssh_config_updates,
-            ssh_config_servers: BTreeSet::new(),
-            create_new_window,
+            ssh_config_servers: Vec::new(),
+            ssh_config_proxied_hosts: BTreeSet::new(),
+            ssh_config_proxy_jumps: BTreeMap::new(),
+            _bastion_probes: Self::poll_bastions(cx),
+            server_list_entries: ServerListEntries::default(),
+            remote_project_history: Vec::new(),
+            create_new_window: create_new_window
+                || RemoteSettings::get_global(cx).open_in_new_window,
+            server_search_editor,
+            server_search_query: String::new(),
             dev_container_picker: None,
+            _dev_container_discovery: Task::ready(()),
             _subscription,
             allow_dismissal: true,
         }
     }
 
+    fn focus_default_section(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let section = match RemoteSettings::get_global(cx).default_section {
+            RemoteServersSection::LastUsed => last_used_section(cx),
+            section => section,
+        };
+        let can_connect_dev_container = self.can_connect_dev_container(cx);
+        let Mode::Default(state) = &self.mode else {
+            return;
+        };
+        let entry = match section {
+            RemoteServersSection::DevContainer if can_connect_dev_container => {
+                &state.add_new_devcontainer
+            }
+            RemoteServersSection::Wsl if cfg!(target_os = "windows") => &state.add_new_wsl,
+            _ => &state.add_new_server,
+        };
+        entry.focus_handle.focus(window, cx);
+        cx.notify();
+    }
+
+    fn can_connect_dev_container(&self, cx: &App) -> bool {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return false;
+        };
+        let project = workspace.read(cx).project().read(cx);
+        // We cannot currently connect a dev container from within a remote server due to the remote_server architecture
+        project.is_local() && project.visible_worktrees(cx).next().is_some()
+    }
+
     fn project_picker(
         create_new_window: bool,
         index: ServerIndex,
@@ -957,21 +2811,33 @@ impl RemoteServerProjects {
     fn create_ssh_server(
         &mut self,
         editor: Entity<Editor>,
+        keepalive: KeepaliveEditors,
+        upload_binary_over_ssh: bool,
+        forward_agent: bool,
+        replacing: Option<SshServerIndex>,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        let input = get_text(&editor, cx);
-        if input.is_empty() {
-            return;
-        }
-
-        let connection_options = match SshConnectionOptions::parse_command_line(&input) {
-            Ok(c) => c,
-            Err(e) => {
+        let original_command = quick_connect_command(&get_text(&editor, cx));
+        let connection_options = match build_ssh_connection_from_form(
+            &editor,
+            &keepalive,
+            upload_binary_over_ssh,
+            forward_agent,
+            cx,
+        ) {
+            None => return,
+            Some(Ok(c)) => c,
+            Some(Err(errors)) => {
                 self.mode = Mode::CreateRemoteServer(CreateRemoteServer {
                     address_editor: editor,
-                    address_error: Some(format!("could not parse: {:?}", e).into()),
+                    keepalive,
+                    upload_binary_over_ssh,
+                    forward_agent,
+                    replacing,
+                    errors,
                     ssh_prompt: None,
+                    connection_test: None,
                     _creating: None,
                 });
                 return;
@@ -988,25 +2854,37 @@ impl RemoteServerProjects {
             )
         });
 
+        let remote_connection_options = RemoteConnectionOptions::Ssh(connection_options.clone());
         let connection = connect(
             ConnectionIdentifier::setup(),
-            RemoteConnectionOptions::Ssh(connection_options.clone()),
+            remote_connection_options.clone(),
             ssh_prompt.clone(),
             window,
             cx,
         )
-        .prompt_err("Failed to connect", window, cx, |_, _, _| None);
+        .prompt_err("Failed to connect", window, cx, move |error, _, _| {
+            connection_error_hint(&remote_connection_options, error)
+                .map(|_| connection_error_detail(&remote_connection_options, error))
+        });
 
         let address_editor = editor.clone();
+        let form_keepalive = keepalive.clone();
         let creating = cx.spawn_in(window, async move |this, cx| {
             match connection.await {
                 Some(Some(client)) => this
                     .update_in(cx, |this, window, cx| {
                         info!("ssh server created");
                         telemetry::event!("SSH Server Created");
+                        let platform = client.read(cx).platform();
                         this.retained_connections.push(client);
-                        this.add_ssh_server(connection_options, cx);
-                        this.mode = Mode::default_mode(&this.ssh_config_servers, cx);
+                        this.save_ssh_server(
+                            connection_options,
+                            platform,
+                            original_command,
+                            replacing,
+                            cx,
+                        );
+                        this.mode = this.default_mode(cx);
                         this.focus_handle(cx).focus(window, cx);
                         cx.notify()
                     })
@@ -1016,10 +2894,16 @@ impl RemoteServerProjects {
                         address_editor.update(cx, |this, _| {
                             this.set_read_only(false);
                         });
+                        form_keepalive.set_read_only(false, cx);
                         this.mode = Mode::CreateRemoteServer(CreateRemoteServer {
                             address_editor,
-                            address_error: None,
+                            keepalive: form_keepalive,
+                            upload_binary_over_ssh,
+                            forward_agent,
+                            replacing,
+                            errors: CreateRemoteServerFormErrors::default(),
                             ssh_prompt: None,
+                            connection_test: None,
                             _creating: None,
                         });
                         cx.notify()
@@ -1032,14 +2916,157 @@ impl RemoteServerProjects {
         editor.update(cx, |this, _| {
             this.set_read_only(true);
         });
+        keepalive.set_read_only(true, cx);
         self.mode = Mode::CreateRemoteServer(CreateRemoteServer {
             address_editor: editor,
-            address_error: None,
+            keepalive,
+            upload_binary_over_ssh,
+            forward_agent,
+            replacing,
+            errors: CreateRemoteServerFormErrors::default(),
             ssh_prompt: Some(ssh_prompt),
+            connection_test: None,
             _creating: Some(creating),
         });
     }
 
+    /// Connects to the server entered in the create-server form and runs a
+    /// trivial command on it, without saving the server or opening a project.
+    fn test_ssh_connection(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Mode::CreateRemoteServer(state) = &self.mode else {
+            return;
+        };
+        if state._creating.is_some() {
+            return;
+        }
+
+        let address_editor = state.address_editor.clone();
+        let keepalive = state.keepalive.clone();
+        let upload_binary_over_ssh = state.upload_binary_over_ssh;
+        let forward_agent = state.forward_agent;
+        let replacing = state.replacing;
+        let input = get_text(&address_editor, cx);
+        let connection_options = match build_ssh_connection_from_form(
+            &address_editor,
+            &keepalive,
+            upload_binary_over_ssh,
+            forward_agent,
+            cx,
+        ) {
+            None => return,
+            Some(Ok(c)) => c,
+            Some(Err(errors)) => {
+                self.mode = Mode::CreateRemoteServer(CreateRemoteServer {
+                    address_editor,
+                    keepalive,
+                    upload_binary_over_ssh,
+                    forward_agent,
+                    replacing,
+                    errors,
+                    ssh_prompt: None,
+                    connection_test: None,
+                    _creating: None,
+                });
+                cx.notify();
+                return;
+            }
+        };
+        let ssh_prompt = cx.new(|cx| {
+            RemoteConnectionPrompt::new(
+                connection_options.connection_string(),
+                connection_options.nickname.clone(),
+                false,
+                false,
+                window,
+                cx,
+            )
+        });
+
+        let connection_options = RemoteConnectionOptions::Ssh(connection_options);
+        let connection = connect(
+            ConnectionIdentifier::setup(),
+            connection_options.clone(),
+            ssh_prompt.clone(),
+            window,
+            cx,
+        );
+
+        let editor = address_editor.clone();
+        let form_keepalive = keepalive.clone();
+        let prompt = ssh_prompt.downgrade();
+        let testing = cx.spawn_in(window, async move |this, cx| {
+            let mut timeout_cx = cx.clone();
+            let probe = async {
+                match connection.await {
+                    Ok(Some(client)) => Some(run_connection_check(client, cx).await),
+                    // Cancelled from the prompt, which already reset the form.
+                    Ok(None) => None,
+                    Err(error) => {
+                        log::error!("connection test failed: {error:#}");
+                        if connection_error_hint(&connection_options, &error).is_some() {
+                            Some(Err(ConnectionTestFailure::ServerBinaryDownload))
+                        } else {
+                            let failure = ConnectionTestFailure::categorize(&error);
+                            if failure == ConnectionTestFailure::Authentication
+                                && authenticates_only_with_agent(&connection_options)
+                            {
+                                Some(Err(ConnectionTestFailure::from_agent_status(
+                                    ssh_agent_status().await,
+                                )))
+                            } else {
+                                Some(Err(failure))
+                            }
+                        }
+                    }
+                }
+            };
+            let result = futures::select_biased! {
+                result = probe.fuse() => result?,
+                _ = connection_test_timeout(prompt, &mut timeout_cx).fuse() => {
+                    Err(ConnectionTestFailure::TimedOut)
+                }
+            };
+            this.update_in(cx, |this, window, cx| {
+                editor.update(cx, |editor, cx| {
+                    editor.set_read_only(false);
+                    editor.focus_handle(cx).focus(window, cx);
+                });
+                form_keepalive.set_read_only(false, cx);
+                this.mode = Mode::CreateRemoteServer(CreateRemoteServer {
+                    address_editor: editor,
+                    keepalive: form_keepalive,
+                    upload_binary_over_ssh,
+                    forward_agent,
+                    replacing,
+                    errors: CreateRemoteServerFormErrors::default(),
+                    ssh_prompt: None,
+                    connection_test: Some(ConnectionTest { input, result }),
+                    _creating: None,
+                });
+                cx.notify();
+            })
+            .log_err();
+            None
+        });
+
+        address_editor.update(cx, |this, _| {
+            this.set_read_only(true);
+        });
+        keepalive.set_read_only(true, cx);
+        self.mode = Mode::CreateRemoteServer(CreateRemoteServer {
+            address_editor,
+            keepalive,
+            upload_binary_over_ssh,
+            forward_agent,
+            replacing,
+            errors: CreateRemoteServerFormErrors::default(),
+            ssh_prompt: Some(ssh_prompt),
+            connection_test: None,
+            _creating: Some(testing),
+        });
+        cx.notify();
+    }
+
     #[cfg(target_os = "windows")]
     fn connect_wsl_distro(
         &mut self,
@@ -1051,6 +3078,7 @@ impl RemoteServerProjects {
         let connection_options = WslConnectionOptions {
             distro_name: distro,
             user: None,
+            env: Default::default(),
         };
 
         let prompt = cx.new(|cx| {
@@ -1089,7 +3117,7 @@ impl RemoteServerProjects {
                     };
 
                     crate::add_wsl_distro(fs, &connection_options, cx);
-                    this.mode = Mode::default_mode(&BTreeSet::new(), cx);
+                    this.mode = this.default_mode(cx);
                     this.focus_handle(cx).focus(window, cx);
                     cx.notify();
                 }),
@@ -1135,7 +3163,7 @@ impl RemoteServerProjects {
             }
             _ => {
                 log::error!("server index and connection options mismatch");
-                self.mode = Mode::default_mode(&BTreeSet::default(), cx);
+                self.mode = self.default_mode(cx);
                 return;
             }
         });
@@ -1159,11 +3187,43 @@ impl RemoteServerProjects {
         connection_options: RemoteConnectionOptions,
         window: &mut Window,
         cx: &mut Context<Self>,
+    ) {
+        self.check_bastion_before_connecting(
+            &connection_options.clone(),
+            window,
+            cx,
+            move |this, window, cx| {
+                this.check_identity_files_before_connecting(
+                    &connection_options.clone(),
+                    window,
+                    cx,
+                    move |this, window, cx| {
+                        this.create_checked_remote_project(index, connection_options, window, cx)
+                    },
+                )
+            },
+        );
+    }
+
+    fn create_checked_remote_project(
+        &mut self,
+        index: ServerIndex,
+        connection_options: RemoteConnectionOptions,
+        window: &mut Window,
+        cx: &mut Context<Self>,
     ) {
         let Some(workspace) = self.workspace.upgrade() else {
             return;
         };
 
+        remember_section(
+            match index {
+                ServerIndex::Ssh(_) => RemoteServersSection::Ssh,
+                ServerIndex::Wsl(_) => RemoteServersSection::Wsl,
+            },
+            cx,
+        );
+
         let create_new_window = self.create_new_window;
         workspace.update(cx, |_, cx| {
             cx.defer_in(window, move |workspace, window, cx| {
@@ -1184,7 +3244,13 @@ impl RemoteServerProjects {
                     window,
                     cx,
                 )
-                .prompt_err("Failed to connect", window, cx, |_, _, _| None);
+                .prompt_err("Failed to connect", window, cx, {
+                    let connection_options = connection_options.clone();
+                    move |error, _, _| {
+                        connection_error_hint(&connection_options, error)
+                            .map(|_| connection_error_detail(&connection_options, error))
+                    }
+                });
 
                 cx.spawn_in(window, async move |workspace, cx| {
                     let session = connect.await;
@@ -1257,52 +3323,336 @@ impl RemoteServerProjects {
         })
     }
 
-    fn confirm(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
-        match &self.mode {
-            Mode::Default(_) | Mode::ViewServerOptions(_) => {}
-            Mode::ProjectPicker(_) => {}
-            Mode::CreateRemoteServer(state) => {
-                if let Some(prompt) = state.ssh_prompt.as_ref() {
-                    prompt.update(cx, |prompt, cx| {
-                        prompt.confirm(window, cx);
-                    });
-                    return;
-                }
+    fn init_clone_repository_mode(
+        &mut self,
+        index: ServerIndex,
+        connection_options: RemoteConnectionOptions,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        remember_section(
+            match index {
+                ServerIndex::Ssh(_) => RemoteServersSection::Ssh,
+                ServerIndex::Wsl(_) => RemoteServersSection::Wsl,
+            },
+            cx,
+        );
+        self.mode =
+            Mode::CloneRepository(CloneRepository::new(index, connection_options, window, cx));
+        cx.notify();
+    }
 
-                self.create_ssh_server(state.address_editor.clone(), window, cx);
-            }
-            Mode::CreateRemoteDevContainer(_) => {}
-            Mode::EditNickname(state) => {
-                let text = Some(state.editor.read(cx).text(cx)).filter(|text| !text.is_empty());
-                let index = state.index;
-                self.update_settings_file(cx, move |setting, _| {
-                    if let Some(connections) = setting.ssh_connections.as_mut()
-                        && let Some(connection) = connections.get_mut(index.0)
-                    {
-                        connection.nickname = text;
-                    }
-                });
-                self.mode = Mode::default_mode(&self.ssh_config_servers, cx);
-                self.focus_handle.focus(window, cx);
-            }
-            #[cfg(target_os = "windows")]
-            Mode::AddWslDistro(state) => {
-                let delegate = &state.picker.read(cx).delegate;
-                let distro = delegate.selected_distro().unwrap();
-                self.connect_wsl_distro(state.picker.clone(), distro, window, cx);
-            }
+    /// Connects to the server, clones the repository entered in the clone
+    /// form, and opens it. The destination is only saved to the server's
+    /// projects once the clone succeeds.
+    fn clone_repository(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Mode::CloneRepository(state) = &mut self.mode else {
+            return;
+        };
+        if state._cloning.is_some() {
+            return;
         }
-    }
 
-    fn cancel(&mut self, _: &menu::Cancel, window: &mut Window, cx: &mut Context<Self>) {
-        match &self.mode {
-            Mode::Default(_) => cx.emit(DismissEvent),
-            Mode::CreateRemoteServer(state) if state.ssh_prompt.is_some() => {
-                let new_state = CreateRemoteServer::new(window, cx);
-                let old_prompt = state.address_editor.read(cx).text(cx);
-                new_state.address_editor.update(cx, |this, cx| {
-                    this.set_text(old_prompt, window, cx);
+        let url = get_text(&state.url_editor, cx);
+        if url.is_empty() {
+            state.error = Some("Enter the URL of a git repository.".into());
+            cx.notify();
+            return;
+        }
+        let Some(destination) = Some(get_text(&state.destination_editor, cx))
+            .filter(|destination| !destination.is_empty())
+            .or_else(|| default_clone_destination(&url))
+        else {
+            state.error = Some("Enter a destination directory on the server.".into());
+            cx.notify();
+            return;
+        };
+        let Some(app_state) = self
+            .workspace
+            .read_with(cx, |workspace, _| workspace.app_state().clone())
+            .log_err()
+        else {
+            return;
+        };
+
+        let index = state.index;
+        let connection_options = state.connection.clone();
+        let (connection_string, nickname, is_wsl) = match &connection_options {
+            RemoteConnectionOptions::Ssh(options) => {
+                (options.connection_string(), options.nickname.clone(), false)
+            }
+            options => (
+                options.display_name(),
+                None,
+                matches!(options, RemoteConnectionOptions::Wsl(_)),
+            ),
+        };
+        let ssh_prompt = cx.new(|cx| {
+            RemoteConnectionPrompt::new(connection_string, nickname, is_wsl, false, window, cx)
+        });
+        let connection = connect(
+            ConnectionIdentifier::setup(),
+            connection_options.clone(),
+            ssh_prompt.clone(),
+            window,
+            cx,
+        );
+
+        let create_new_window = self.create_new_window;
+        let cloning = cx.spawn_in(window, async move |this, cx| {
+            let result = run_git_clone(
+                this.clone(),
+                connection,
+                app_state.clone(),
+                url,
+                destination,
+                cx,
+            )
+            .await;
+            let (project, destination) = match result {
+                Ok(Some(cloned)) => cloned,
+                Ok(None) => {
+                    this.update(cx, |this, cx| this.reset_clone_form(None, cx))
+                        .log_err();
+                    return None;
+                }
+                Err(error) => {
+                    log::error!("failed to clone repository: {error:#}");
+                    this.update(cx, |this, cx| {
+                        this.reset_clone_form(Some(format!("{error:#}").into()), cx)
+                    })
+                    .log_err();
+                    return None;
+                }
+            };
+
+            telemetry::event!("Remote Repository Cloned");
+            cx.update(|_, cx| {
+                add_project_to_server(app_state.fs.clone(), index, vec![destination.clone()], cx);
+            })
+            .log_err();
+            open_connected_project(
+                create_new_window,
+                connection_options,
+                project,
+                vec![PathBuf::from(destination)],
+                app_state,
+                cx,
+            )
+            .await?;
+
+            this.update(cx, |_, cx| cx.emit(DismissEvent)).ok();
+            Some(())
+        });
+
+        state.set_read_only(true, cx);
+        state.error = None;
+        state.output.clear();
+        state.ssh_prompt = Some(ssh_prompt);
+        state._cloning = Some(cloning);
+        cx.notify();
+    }
+
+    fn reset_clone_form(&mut self, error: Option<SharedString>, cx: &mut Context<Self>) {
+        let Mode::CloneRepository(state) = &mut self.mode else {
+            return;
+        };
+        state.set_read_only(false, cx);
+        state.error = error;
+        state.ssh_prompt = None;
+        state.remote_git = None;
+        state._cloning = None;
+        cx.notify();
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        match &self.mode {
+            Mode::Default(_) | Mode::ViewServerOptions(_) => {}
+            Mode::ProjectPicker(_) | Mode::ConfirmSettingsChanges(_) => {}
+            Mode::CreateRemoteServer(state) => {
+                if let Some(prompt) = state.ssh_prompt.as_ref() {
+                    prompt.update(cx, |prompt, cx| {
+                        prompt.confirm(window, cx);
+                    });
+                    return;
+                }
+
+                self.create_ssh_server(
+                    state.address_editor.clone(),
+                    state.keepalive.clone(),
+                    state.upload_binary_over_ssh,
+                    state.forward_agent,
+                    state.replacing,
+                    window,
+                    cx,
+                );
+            }
+            Mode::CreateRemoteDevContainer(_) => {}
+            Mode::CloneRepository(state) => {
+                if let Some(prompt) = state.ssh_prompt.as_ref() {
+                    prompt.update(cx, |prompt, cx| {
+                        prompt.confirm(window, cx);
+                    });
+                    return;
+                }
+
+                self.clone_repository(window, cx);
+            }
+            Mode::EditNickname(state) => {
+                let text = state.editor.read(cx).text(cx).trim().to_string();
+                if let Err(error) = state.validate_nickname(&text, cx) {
+                    if let Mode::EditNickname(state) = &mut self.mode {
+                        state.error = Some(error);
+                    }
+                    cx.notify();
+                    return;
+                }
+                let text = Some(text).filter(|text| !text.is_empty());
+                let index = state.index;
+                self.update_settings_file(cx, move |setting, _| {
+                    if let Some(connections) = setting.ssh_connections.as_mut()
+                        && let Some(connection) = connections.get_mut(index.0)
+                    {
+                        connection.nickname = text;
+                    }
+                });
+                self.mode = self.default_mode(cx);
+                self.focus_handle.focus(window, cx);
+            }
+            Mode::EditKeepalive(state) => {
+                let (interval, count_max) = match state.keepalive.parse(cx) {
+                    Ok(values) => values,
+                    Err(errors) => {
+                        if let Mode::EditKeepalive(state) = &mut self.mode {
+                            state.errors = errors;
+                        }
+                        cx.notify();
+                        return;
+                    }
+                };
+                let index = state.index;
+                self.update_settings_file(cx, move |setting, _| {
+                    if let Some(connections) = setting.ssh_connections.as_mut()
+                        && let Some(connection) = connections.get_mut(index.0)
+                    {
+                        connection.keepalive_interval = interval;
+                        connection.keepalive_count_max = count_max;
+                    }
+                });
+                self.mode = self.default_mode(cx);
+                self.focus_handle.focus(window, cx);
+            }
+            Mode::EditShell(state) => {
+                let shell = state.editor.read(cx).text(cx).trim().to_string();
+                if !shell.is_empty()
+                    && let Err(error) = validate_remote_shell(&shell)
+                {
+                    if let Mode::EditShell(state) = &mut self.mode {
+                        state.error = Some(error.into());
+                    }
+                    cx.notify();
+                    return;
+                }
+                let shell = Some(shell).filter(|shell| !shell.is_empty());
+                // Matching the default leaves the option unset, so the server
+                // follows later changes to `ssh_login_shell`.
+                let login_shell = Some(state.login_shell)
+                    .filter(|login_shell| *login_shell != SshSettings::get_global(cx).login_shell);
+                let index = state.index;
+                self.update_settings_file(cx, move |setting, _| {
+                    if let Some(connections) = setting.ssh_connections.as_mut()
+                        && let Some(connection) = connections.get_mut(index.0)
+                    {
+                        connection.shell = shell;
+                        connection.login_shell = login_shell;
+                    }
+                });
+                self.mode = self.default_mode(cx);
+                self.focus_handle.focus(window, cx);
+            }
+            Mode::EditDevContainerUser(_) => self.save_dev_container_user(window, cx),
+            Mode::EditEnv(state) => {
+                let index = state.index;
+                let env = match state.parse(cx) {
+                    Ok(env) => Some(env).filter(|env| !env.is_empty()),
+                    Err(error) => {
+                        if let Mode::EditEnv(state) = &mut self.mode {
+                            state.error = Some(error);
+                        }
+                        cx.notify();
+                        return;
+                    }
+                };
+                self.update_settings_file(cx, move |setting, _| match index {
+                    ServerIndex::Ssh(index) => {
+                        if let Some(connections) = setting.ssh_connections.as_mut()
+                            && let Some(connection) = connections.get_mut(index.0)
+                        {
+                            connection.env = env;
+                        }
+                    }
+                    ServerIndex::Wsl(index) => {
+                        if let Some(connections) = setting.wsl_connections.as_mut()
+                            && let Some(connection) = connections.get_mut(index.0)
+                        {
+                            connection.env = env;
+                        }
+                    }
+                });
+                self.mode = self.default_mode(cx);
+                self.focus_handle.focus(window, cx);
+            }
+            Mode::EditTag(state) => {
+                let tag = Some(state.editor.read(cx).text(cx).trim().to_string())
+                    .filter(|tag| !tag.is_empty());
+                let target = state.target;
+                self.update_settings_file(cx, move |setting, _| match target {
+                    TagTarget::Server(ServerIndex::Ssh(index)) => {
+                        if let Some(connections) = setting.ssh_connections.as_mut()
+                            && let Some(connection) = connections.get_mut(index.0)
+                        {
+                            connection.tag = tag;
+                        }
+                    }
+                    TagTarget::Server(ServerIndex::Wsl(index)) => {
+                        if let Some(connections) = setting.wsl_connections.as_mut()
+                            && let Some(connection) = connections.get_mut(index.0)
+                        {
+                            connection.tag = tag;
+                        }
+                    }
+                    TagTarget::DevContainer(index) => {
+                        if let Some(connections) = setting.dev_container_connections.as_mut()
+                            && let Some(connection) = connections.get_mut(index)
+                        {
+                            connection.tag = tag;
+                        }
+                    }
+                });
+                self.mode = self.default_mode(cx);
+                self.focus_handle.focus(window, cx);
+            }
+            #[cfg(target_os = "windows")]
+            Mode::AddWslDistro(state) => {
+                let delegate = &state.picker.read(cx).delegate;
+                let distro = delegate.selected_distro().unwrap();
+                self.connect_wsl_distro(state.picker.clone(), distro, window, cx);
+            }
+        }
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, window: &mut Window, cx: &mut Context<Self>) {
+        match &self.mode {
+            Mode::Default(_) => cx.emit(DismissEvent),
+            Mode::CreateRemoteServer(state) if state.ssh_prompt.is_some() => {
+                let mut new_state = CreateRemoteServer::new(window, cx);
+                let old_prompt = state.address_editor.read(cx).text(cx);
+                new_state.address_editor.update(cx, |this, cx| {
+                    this.set_text(old_prompt, window, cx);
                 });
+                state.keepalive.set_read_only(false, cx);
+                new_state.keepalive = state.keepalive.clone();
+                new_state.replacing = state.replacing;
 
                 self.mode = Mode::CreateRemoteServer(new_state);
                 cx.notify();
@@ -1313,9 +3663,15 @@ impl RemoteServerProjects {
             }) => {
                 cx.emit(DismissEvent);
             }
+            Mode::CloneRepository(state) if state._cloning.is_some() => {
+                if let Some((client, pid)) = state.remote_git.clone() {
+                    stop_remote_process(client, pid, cx);
+                }
+                self.reset_clone_form(Some("Cancelled the clone.".into()), cx);
+            }
             _ => {
                 self.allow_dismissal = true;
-                self.mode = Mode::default_mode(&self.ssh_config_servers, cx);
+                self.mode = self.default_mode(cx);
                 self.focus_handle(cx).focus(window, cx);
                 cx.notify();
             }
@@ -1328,7 +3684,25 @@ impl RemoteServerProjects {
         remote_server: RemoteEntry,
         window: &mut Window,
         cx: &mut Context<Self>,
-    ) -> impl IntoElement {
+    ) -> AnyElement {
+        if let RemoteEntry::CompactProject {
+            row,
+            project_count,
+            connection,
+            index,
+        } = &remote_server
+        {
+            return self.render_compact_server(
+                ix,
+                row,
+                *project_count,
+                connection,
+                *index,
+                window,
+                cx,
+            );
+        }
+
         let connection = remote_server.connection().into_owned();
 
         let (main_label, aux_label, is_wsl) = match &connection {
@@ -1347,6 +3721,29 @@ impl RemoteServerProjects {
                 (dev_container_options.name.clone(), None, false)
             }
         };
+        let via_proxy = matches!(
+            &remote_server,
+            RemoteEntry::SshConfig { host, .. } if self.ssh_config_proxied_hosts.contains(host)
+        );
+        let unreachable_bastion = match (&remote_server, &connection) {
+            (RemoteEntry::SshConfig { .. }, _) => None,
+            (_, Connection::Ssh(connection)) => {
+                self.unreachable_bastion(&connection.args, &connection.host, cx)
+            }
+            _ => None,
+        };
+        let connect_target = match &remote_server {
+            RemoteEntry::Project {
+                index, projects, ..
+            } => Some((
+                *index,
+                projects
+                    .iter()
+                    .map(|(_, project)| project.clone())
+                    .collect::<Vec<_>>(),
+            )),
+            RemoteEntry::CompactProject { .. } | RemoteEntry::SshConfig { .. } => None,
+        };
         v_flex()
             .w_full()
             .child(ListSeparator)
@@ -1381,11 +3778,52 @@ impl RemoteServerProjects {
                         aux_label.map(|label| {
                             Label::new(label).size(LabelSize::Small).color(Color::Muted)
                         }),
-                    ),
+                    )
+                    .children(connection_tag(&connection).map(render_server_tag))
+                    .when(via_proxy, |this| {
+                        this.child(Chip::new("via proxy").tooltip(Tooltip::text(
+                            "This host connects through a ProxyCommand or ProxyJump",
+                        )))
+                    })
+                    .when_some(unreachable_bastion, |this, bastion| {
+                        this.child(
+                            Chip::new(format!("via {bastion} — unreachable"))
+                                .icon(IconName::Warning)
+                                .icon_color(Color::Warning)
+                                .label_color(Color::Warning)
+                                .tooltip(Tooltip::text(
+                                    "The jump host this server connects through can't be \
+                                    reached, so connecting will likely time out",
+                                )),
+                        )
+                    })
+                    .when_some(connect_target, |this, (index, projects)| {
+                        this.child(
+                            div().ml_auto().child(
+                                Button::new(("connect-server", ix), "Connect")
+                                    .label_size(LabelSize::Small)
+                                    .tooltip(Tooltip::text("Open the most recent project"))
+                                    .on_click(cx.listener({
+                                        let connection = connection.clone();
+                                        move |this, event: &ClickEvent, window, cx| {
+                                            this.connect_to_server(
+                                                index,
+                                                connection.clone(),
+                                                &projects,
+                                                event.modifiers().platform,
+                                                window,
+                                                cx,
+                                            );
+                                        }
+                                    })),
+                            ),
+                        )
+                    }),
             )
             .child(match &remote_server {
                 RemoteEntry::Project {
                     open_folder,
+                    clone_repository,
                     projects,
                     configure,
                     connection,
@@ -1442,6 +3880,48 @@ impl RemoteServerProjects {
                                         })),
                                 ),
                         )
+                        .child(
+                            h_flex()
+                                .id(("clone-repository-container", ix))
+                                .track_focus(&clone_repository.focus_handle)
+                                .anchor_scroll(clone_repository.scroll_anchor.clone())
+                                .on_action(cx.listener({
+                                    let connection = connection.clone();
+                                    move |this, _: &menu::Confirm, window, cx| {
+                                        this.init_clone_repository_mode(
+                                            index,
+                                            connection.clone().into(),
+                                            window,
+                                            cx,
+                                        );
+                                    }
+                                }))
+                                .child(
+                                    ListItem::new(("clone-repository", ix))
+                                        .toggle_state(
+                                            clone_repository
+                                                .focus_handle
+                                                .contains_focused(window, cx),
+                                        )
+                                        .inset(true)
+                                        .spacing(ui::ListItemSpacing::Sparse)
+                                        .start_slot(
+                                            Icon::new(IconName::GitBranch).color(Color::Muted),
+                                        )
+                                        .child(Label::new("Clone Repository"))
+                                        .on_click(cx.listener({
+                                            let connection = connection.clone();
+                                            move |this, _, window, cx| {
+                                                this.init_clone_repository_mode(
+                                                    index,
+                                                    connection.clone().into(),
+                                                    window,
+                                                    cx,
+                                                );
+                                            }
+                                        })),
+                                ),
+                        )
                         .child(
                             h_flex()
                                 .id(("server-options-container", ix))
@@ -1481,7 +3961,9 @@ impl RemoteServerProjects {
                                 ),
                         )
                 }
-                RemoteEntry::SshConfig { open_folder, host } => List::new().child(
+                RemoteEntry::SshConfig {
+                    open_folder, host, ..
+                } => List::new().child(
                     h_flex()
                         .id(("new-remote-project-container", ix))
                         .track_focus(&open_folder.focus_handle)
@@ -1520,1505 +4002,6449 @@ impl RemoteServerProjects {
                                 })),
                         ),
                 ),
+                // Compact servers are rendered by `render_compact_server`.
+                RemoteEntry::CompactProject { .. } => List::new(),
             })
+            .into_any_element()
     }
 
-    fn render_remote_project(
-        &mut self,
-        server_ix: ServerIndex,
-        server: RemoteEntry,
-        ix: usize,
-        (navigation, project): &(NavigableEntry, RemoteProject),
+    fn render_saved_dev_container(
+        &self,
+        container: &DevContainerEntry,
         window: &mut Window,
         cx: &mut Context<Self>,
-    ) -> impl IntoElement {
-        let create_new_window = self.create_new_window;
-        let is_from_zed = server.is_from_zed();
-        let element_id_base = SharedString::from(format!(
-            "remote-project-{}",
-            match server_ix {
-                ServerIndex::Ssh(index) => format!("ssh-{index}"),
-                ServerIndex::Wsl(index) => format!("wsl-{index}"),
-            }
-        ));
-        let container_element_id_base =
-            SharedString::from(format!("remote-project-container-{element_id_base}"));
-
-        let callback = Rc::new({
-            let project = project.clone();
-            move |remote_server_projects: &mut Self,
-                  secondary_confirm: bool,
-                  window: &mut Window,
-                  cx: &mut Context<Self>| {
-                let Some(app_state) = remote_server_projects
-                    .workspace
-                    .read_with(cx, |workspace, _| workspace.app_state().clone())
-                    .log_err()
-                else {
-                    return;
-                };
-                let project = project.clone();
-                let server = server.connection().into_owned();
-                cx.emit(DismissEvent);
-
-                let replace_window = match (create_new_window, secondary_confirm) {
-                    (true, false) | (false, true) => None,
-                    (true, true) | (false, false) => {
-                        window.window_handle().downcast::<MultiWorkspace>()
-                    }
-                };
-
-                cx.spawn_in(window, async move |_, cx| {
-                    let result = open_remote_project(
-                        server.into(),
-                        project.paths.into_iter().map(PathBuf::from).collect(),
-                        app_state,
-                        OpenOptions {
-                            requesting_window: replace_window,
-                            ..OpenOptions::default()
-                        },
-                        cx,
-                    )
-                    .await;
-                    if let Err(e) = result {
-                        log::error!("Failed to connect: {e:#}");
-                        cx.prompt(
-                            gpui::PromptLevel::Critical,
-                            "Failed to connect",
-                            Some(&e.to_string()),
-                            &["Ok"],
-                        )
-                        .await
-                        .ok();
-                    }
-                })
-                .detach();
-            }
-        });
-
-        div()
-            .id((container_element_id_base, ix))
-            .track_focus(&navigation.focus_handle)
-            .anchor_scroll(navigation.scroll_anchor.clone())
-            .on_action(cx.listener({
-                let callback = callback.clone();
-                move |this, _: &menu::Confirm, window, cx| {
-                    callback(this, false, window, cx);
-                }
-            }))
-            .on_action(cx.listener({
-                let callback = callback.clone();
-                move |this, _: &menu::SecondaryConfirm, window, cx| {
-                    callback(this, true, window, cx);
-                }
-            }))
+    ) -> AnyElement {
+        let ix = container.index;
+        let row = &container.row;
+        let connection = &container.connection;
+        v_flex()
+            .w_full()
+            .child(ListSeparator)
             .child(
-                ListItem::new((element_id_base, ix))
-                    .toggle_state(navigation.focus_handle.contains_focused(window, cx))
-                    .inset(true)
-                    .spacing(ui::ListItemSpacing::Sparse)
-                    .start_slot(
-                        Icon::new(IconName::Folder)
-                            .color(Color::Muted)
-                            .size(IconSize::Small),
-                    )
-                    .child(Label::new(project.paths.join(", ")).truncate_start())
-                    .on_click(cx.listener(move |this, e: &ClickEvent, window, cx| {
-                        let secondary_confirm = e.modifiers().platform;
-                        callback(this, secondary_confirm, window, cx)
+                div()
+                    .id(("saved-dev-container", ix))
+                    .track_focus(&row.focus_handle)
+                    .anchor_scroll(row.scroll_anchor.clone())
+                    .on_action(cx.listener({
+                        let connection = connection.clone();
+                        move |this, _: &menu::Confirm, _, cx| {
+                            this.copy_dev_container_setup_command(&connection, cx);
+                        }
                     }))
-                    .tooltip(Tooltip::text(project.paths.join("\n")))
-                    .when(is_from_zed, |server_list_item| {
-                        server_list_item
+                    .child(
+                        ListItem::new(("saved-dev-container-item", ix))
+                            .toggle_state(row.focus_handle.contains_focused(window, cx))
+                            .inset(true)
+                            .spacing(ui::ListItemSpacing::Sparse)
+                            .start_slot(Icon::new(IconName::Box).color(Color::Muted))
+                            .child(
+                                h_flex()
+                                    .gap_1()
+                                    .overflow_hidden()
+                                    .text_ellipsis()
+                                    .child(Label::new(connection.name.clone()))
+                                    .children(connection.tag.as_deref().map(render_server_tag)),
+                            )
                             .end_slot(
-                                div()
-                                    .mr_2()
-                                    .child({
-                                        let project = project.clone();
-                                        IconButton::new("remove-remote-project", IconName::Trash)
-                                            .icon_size(IconSize::Small)
-                                            .shape(IconButtonShape::Square)
-                                            .size(ButtonSize::Large)
-                                            .tooltip(Tooltip::text("Delete Remote Project"))
-                                            .on_click(cx.listener(move |this, _, _, cx| {
-                                                this.delete_remote_project(server_ix, &project, cx)
-                                            }))
-                                    })
-                                    .into_any_element(),
+                                h_flex()
+                                    .gap_1()
+                                    .child(
+                                        Icon::new(IconName::Copy)
+                                            .size(IconSize::Small)
+                                            .color(Color::Muted),
+                                    )
+                                    .child(
+                                        Label::new("Copy Setup Command")
+                                            .size(LabelSize::Small)
+                                            .color(Color::Muted),
+                                    ),
                             )
                             .show_end_slot_on_hover()
-                    }),
+                            .on_click(cx.listener({
+                                let connection = connection.clone();
+                                move |this, _, _, cx| {
+                                    this.copy_dev_container_setup_command(&connection, cx);
+                                }
+                            })),
+                    ),
             )
+            .child(self.render_dev_container_management(container, window, cx))
+            .into_any_element()
     }
 
-    fn update_settings_file(
-        &mut self,
+    /// The row that shows or hides a dev container's details and management
+    /// actions, followed by them when they're shown. Removing the container
+    /// comes last, apart from the rest.
+    fn render_dev_container_management(
+        &self,
+        container: &DevContainerEntry,
+        window: &mut Window,
         cx: &mut Context<Self>,
-        f: impl FnOnce(&mut RemoteSettingsContent, &App) + Send + Sync + 'static,
-    ) {
-        let Some(fs) = self
-            .workspace
-            .read_with(cx, |workspace, _| workspace.app_state().fs.clone())
-            .log_err()
-        else {
-            return;
-        };
-        update_settings_file(fs, cx, move |setting, cx| f(&mut setting.remote, cx));
-    }
+    ) -> impl IntoElement {
+        let ix = container.index;
+        let connection = &container.connection;
+        let container_id = connection.container_id.clone();
+        let is_expanded = container.remove.is_some();
+
+        let details = is_expanded.then(|| {
+            let engine = if connection.use_podman {
+                "Podman"
+            } else {
+                "Docker"
+            };
+            let detail = |label: &'static str, value: String| {
+                h_flex()
+                    .gap_1()
+                    .child(Label::new(label).size(LabelSize::Small).color(Color::Muted))
+                    .child(Label::new(value).size(LabelSize::Small).truncate())
+            };
+            v_flex()
+                .pl_8()
+                .pr_2()
+                .py_1()
+                .gap_0p5()
+                .child(detail("Container", connection.container_id.clone()))
+                .child(detail(
+                    "User",
+                    if connection.remote_user.is_empty() {
+                        "the container's default user".to_string()
+                    } else {
+                        connection.remote_user.clone()
+                    },
+                ))
+                .when(!connection.extra_env.is_empty(), |this| {
+                    this.child(detail(
+                        "Extra Environment",
+                        connection
+                            .extra_env
+                            .keys()
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    ))
+                })
+                .child(detail(
+                    "Engine",
+                    match &connection.wsl_distro {
+                        Some(distro) => format!("{engine} in WSL ({distro})"),
+                        None => engine.to_string(),
+                    },
+                ))
+                .children(
+                    connection
+                        .host_project_root
+                        .clone()
+                        .map(|root| detail("Project", root)),
+                )
+                .children(
+                    connection
+                        .config_path
+                        .clone()
+                        .map(|config_path| detail("Config", config_path)),
+                )
+        });
 
-    fn delete_ssh_server(&mut self, server: SshServerIndex, cx: &mut Context<Self>) {
-        self.update_settings_file(cx, move |setting, _| {
-            if let Some(connections) = setting.ssh_connections.as_mut()
-                && connections.get(server.0).is_some()
-            {
-                connections.remove(server.0);
-            }
+        let edit_user = container.edit_user.as_ref().map(|edit_user| {
+            let index = container.index;
+            div()
+                .id(("edit-dev-container-user", ix))
+                .track_focus(&edit_user.focus_handle)
+                .anchor_scroll(edit_user.scroll_anchor.clone())
+                .on_action(cx.listener(move |this, _: &menu::Confirm, window, cx| {
+                    this.edit_dev_container_user(index, window, cx);
+                }))
+                .child(
+                    ListItem::new(("edit-dev-container-user-item", ix))
+                        .toggle_state(edit_user.focus_handle.contains_focused(window, cx))
+                        .inset(true)
+                        .spacing(ui::ListItemSpacing::Sparse)
+                        .start_slot(Icon::new(IconName::Person).color(Color::Muted))
+                        .child(Label::new("Edit User and Environment"))
+                        .on_click(cx.listener(move |this, _, window, cx| {
+                            this.edit_dev_container_user(index, window, cx);
+                        })),
+                )
         });
-    }
 
-    fn delete_remote_project(
-        &mut self,
-        server: ServerIndex,
-        project: &RemoteProject,
-        cx: &mut Context<Self>,
-    ) {
-        match server {
-            ServerIndex::Ssh(server) => {
-                self.delete_ssh_project(server, project, cx);
-            }
-            ServerIndex::Wsl(server) => {
-                self.delete_wsl_project(server, project, cx);
-            }
-        }
-    }
+        let change_engine = container.change_engine.as_ref().map(|change_engine| {
+            let index = container.index;
+            div()
+                .id(("change-dev-container-engine", ix))
+                .track_focus(&change_engine.focus_handle)
+                .anchor_scroll(change_engine.scroll_anchor.clone())
+                .on_action(cx.listener(move |this, _: &menu::Confirm, window, cx| {
+                    this.change_dev_container_engine(index, window, cx);
+                }))
+                .child(
+                    ListItem::new(("change-dev-container-engine-item", ix))
+                        .toggle_state(change_engine.focus_handle.contains_focused(window, cx))
+                        .inset(true)
+                        .spacing(ui::ListItemSpacing::Sparse)
+                        .start_slot(Icon::new(IconName::Box).color(Color::Muted))
+                        .child(Label::new("Change Engine"))
+                        .on_click(cx.listener(move |this, _, window, cx| {
+                            this.change_dev_container_engine(index, window, cx);
+                        })),
+                )
+        });
 
-    fn delete_ssh_project(
-        &mut self,
-        server: SshServerIndex,
-        project: &RemoteProject,
-        cx: &mut Context<Self>,
-    ) {
-        let project = project.clone();
-        self.update_settings_file(cx, move |setting, _| {
-            if let Some(server) = setting
-                .ssh_connections
-                .as_mut()
-                .and_then(|connections| connections.get_mut(server.0))
-            {
-                server.projects.remove(&project);
-            }
+        let edit_tag = container.edit_tag.as_ref().map(|edit_tag| {
+            let target = TagTarget::DevContainer(container.index);
+            let label = if connection.tag.is_some() {
+                "Edit Tag"
+            } else {
+                "Add Tag"
+            };
+            div()
+                .id(("edit-dev-container-tag", ix))
+                .track_focus(&edit_tag.focus_handle)
+                .anchor_scroll(edit_tag.scroll_anchor.clone())
+                .on_action(cx.listener(move |this, _: &menu::Confirm, window, cx| {
+                    this.mode = Mode::EditTag(EditTagState::new(target, window, cx));
+                    cx.notify();
+                }))
+                .child(
+                    ListItem::new(("edit-dev-container-tag-item", ix))
+                        .toggle_state(edit_tag.focus_handle.contains_focused(window, cx))
+                        .inset(true)
+                        .spacing(ui::ListItemSpacing::Sparse)
+                        .start_slot(Icon::new(IconName::Hash).color(Color::Muted))
+                        .child(Label::new(label))
+                        .on_click(cx.listener(move |this, _, window, cx| {
+                            this.mode = Mode::EditTag(EditTagState::new(target, window, cx));
+                            cx.notify();
+                        })),
+                )
+        });
+
+        let remove = container.remove.as_ref().map(|remove| {
+            let index = container.index;
+            let name = SharedString::from(connection.name.clone());
+            div()
+                .id(("remove-dev-container", ix))
+                .track_focus(&remove.focus_handle)
+                .anchor_scroll(remove.scroll_anchor.clone())
+                .on_action(cx.listener({
+                    let name = name.clone();
+                    move |this, _: &menu::Confirm, window, cx| {
+                        this.remove_dev_container(index, name.clone(), window, cx);
+                    }
+                }))
+                .child(
+                    ListItem::new(("remove-dev-container-item", ix))
+                        .toggle_state(remove.focus_handle.contains_focused(window, cx))
+                        .inset(true)
+                        .spacing(ui::ListItemSpacing::Sparse)
+                        .start_slot(Icon::new(IconName::Trash).color(Color::Error))
+                        .child(Label::new("Remove Dev Container").color(Color::Error))
+                        .on_click(cx.listener(move |this, _, window, cx| {
+                            this.remove_dev_container(index, name.clone(), window, cx);
+                        })),
+                )
         });
+
+        v_flex()
+            .w_full()
+            .child(
+                div()
+                    .id(("manage-dev-container", ix))
+                    .track_focus(&container.manage.focus_handle)
+                    .anchor_scroll(container.manage.scroll_anchor.clone())
+                    .on_action(cx.listener({
+                        let container_id = container_id.clone();
+                        move |this, _: &menu::Confirm, window, cx| {
+                            this.toggle_dev_container_management(container_id.clone(), window, cx);
+                        }
+                    }))
+                    .child(
+                        ListItem::new(("manage-dev-container-item", ix))
+                            .toggle_state(
+                                container.manage.focus_handle.contains_focused(window, cx),
+                            )
+                            .inset(true)
+                            .spacing(ui::ListItemSpacing::Sparse)
+                            .start_slot(
+                                Icon::new(if is_expanded {
+                                    IconName::ChevronDown
+                                } else {
+                                    IconName::ChevronRight
+                                })
+                                .color(Color::Muted),
+                            )
+                            .child(Label::new("Manage Container…").color(Color::Muted))
+                            .on_click(cx.listener(move |this, _, window, cx| {
+                                this.toggle_dev_container_management(
+                                    container_id.clone(),
+                                    window,
+                                    cx,
+                                );
+                            })),
+                    ),
+            )
+            .children(details)
+            .children(edit_user)
+            .children(change_engine)
+            .children(edit_tag)
+            .when(remove.is_some(), |this| this.child(ListSeparator))
+            .children(remove)
     }
 
-    fn delete_wsl_project(
+    /// Shows or hides the details and management actions of the dev
+    /// container with `container_id`, keeping the row that toggled them
+    /// selected.
+    fn toggle_dev_container_management(
         &mut self,
-        server: WslServerIndex,
-        project: &RemoteProject,
+        container_id: String,
+        window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        let project = project.clone();
-        self.update_settings_file(cx, move |setting, _| {
-            if let Some(server) = setting
-                .wsl_connections
-                .as_mut()
-                .and_then(|connections| connections.get_mut(server.0))
-            {
-                server.projects.remove(&project);
-            }
-        });
-    }
+        let managed = &mut self.server_list_entries.managed_dev_containers;
+        if !managed.remove(&container_id) {
+            managed.insert(container_id.clone());
+        }
 
-    fn delete_wsl_distro(&mut self, server: WslServerIndex, cx: &mut Context<Self>) {
-        self.update_settings_file(cx, move |setting, _| {
-            if let Some(connections) = setting.wsl_connections.as_mut() {
-                connections.remove(server.0);
-            }
-        });
+        self.mode = self.default_mode(cx);
+        if let Mode::Default(state) = &self.mode
+            && let Some(container) = state
+                .dev_containers
+                .iter()
+                .find(|container| container.connection.container_id == container_id)
+        {
+            container.manage.focus_handle.focus(window, cx);
+        }
+        cx.notify();
     }
 
-    fn add_ssh_server(
+    fn edit_dev_container_user(
         &mut self,
-        connection_options: remote::SshConnectionOptions,
+        index: usize,
+        window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        self.update_settings_file(cx, move |setting, _| {
-            setting
-                .ssh_connections
-                .get_or_insert(Default::default())
-                .push(SshConnection {
-                    host: connection_options.host.to_string(),
-                    username: connection_options.username,
-                    port: connection_options.port,
-                    projects: BTreeSet::new(),
-                    nickname: None,
-                    args: connection_options.args.unwrap_or_default(),
-                    upload_binary_over_ssh: None,
-                    port_forwards: connection_options.port_forwards,
-                    connection_timeout: connection_options.connection_timeout,
-                })
-        });
+        let Some(connection) = RemoteSettings::get_global(cx)
+            .dev_container_connections
+            .get(index)
+            .cloned()
+        else {
+            return;
+        };
+        self.mode = Mode::EditDevContainerUser(EditDevContainerUserState::new(
+            index, connection, window, cx,
+        ));
+        cx.notify();
     }
 
-    fn edit_in_dev_container_json(
+    /// Asks whether docker or podman runs the dev container at `index`, or
+    /// detects whichever of them can see it, and records the answer, for
+    /// containers that moved between them after being saved.
+    fn change_dev_container_engine(
         &mut self,
-        config: Option<DevContainerConfig>,
+        index: usize,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        let Some(workspace) = self.workspace.upgrade() else {
-            cx.emit(DismissEvent);
-            cx.notify();
+        let Some(connection) = RemoteSettings::get_global(cx)
+            .dev_container_connections
+            .get(index)
+            .cloned()
+        else {
             return;
         };
+        let answer = window.prompt(
+            PromptLevel::Info,
+            &format!("Which engine runs dev container `{}`?", connection.name),
+            Some("Detecting asks docker and then podman whether they can see the container."),
+            &["Docker", "Podman", "Detect", "Cancel"],
+            cx,
+        );
+        cx.spawn_in(window, async move |this, cx| {
+            let use_podman = match answer.await? {
+                0 => Some(false),
+                1 => Some(true),
+                2 => None,
+                _ => return anyhow::Ok(()),
+            };
+            let container_id = connection.container_id;
+            this.update(cx, |_, cx| {
+                set_dev_container_runtime(container_id, use_podman, cx)
+            })?
+            .await?;
+            this.update(cx, |this, cx| {
+                this.mode = this.default_mode(cx);
+                cx.notify();
+            })?;
+            anyhow::Ok(())
+        })
+        .detach_and_prompt_err("Failed to change the engine", window, cx, |_, _, _| None);
+    }
 
-        let config_path = config
-            .map(|c| c.config_path)
-            .unwrap_or_else(|| PathBuf::from(".devcontainer/devcontainer.json"));
-
-        workspace.update(cx, |workspace, cx| {
-            let project = workspace.project().clone();
-
-            let worktree = project
-                .read(cx)
-                .visible_worktrees(cx)
-                .find_map(|tree| tree.read(cx).root_entry()?.is_dir().then_some(tree));
-
-            if let Some(worktree) = worktree {
-                let tree_id = worktree.read(cx).id();
-                let devcontainer_path =
-                    match RelPath::new(&config_path, util::paths::PathStyle::Posix) {
-                        Ok(path) => path.into_owned(),
-                        Err(error) => {
-                            log::error!(
-                                "Invalid devcontainer path: {} - {}",
-                                config_path.display(),
-                                error
-                            );
-                            return;
-                        }
-                    };
-                cx.spawn_in(window, async move |workspace, cx| {
-                    workspace
-                        .update_in(cx, |workspace, window, cx| {
-                            workspace.open_path(
-                                (tree_id, devcontainer_path),
-                                None,
-                                true,
-                                window,
-                                cx,
-                            )
-                        })?
-                        .await
-                })
-                .detach();
-            } else {
+    /// Saves the user and extra environment entered for a dev container,
+    /// once the user is found in the container.
+    fn save_dev_container_user(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Mode::EditDevContainerUser(state) = &self.mode else {
+            return;
+        };
+        if state._checking.is_some() {
+            return;
+        }
+        let env = match parse_env_rows(&state.env_rows, cx) {
+            Ok(env) => env,
+            Err(error) => {
+                if let Mode::EditDevContainerUser(state) = &mut self.mode {
+                    state.error = Some(error);
+                }
+                cx.notify();
                 return;
             }
+        };
+        let user = get_text(&state.user_editor, cx);
+        let index = state.index;
+        let options = DockerConnectionOptions {
+            container_id: state.connection.container_id.clone(),
+            use_podman: state.connection.use_podman,
+            wsl_distro: state.connection.wsl_distro.clone(),
+            extra_exec_args: state.connection.extra_exec_args.clone(),
+            ..DockerConnectionOptions::default()
+        };
+
+        let checking = cx.spawn_in(window, async move |this, cx| {
+            let result = if user.is_empty() {
+                Ok(())
+            } else {
+                check_container_user(&options, &user).await
+            };
+            this.update_in(cx, |this, window, cx| {
+                let Mode::EditDevContainerUser(state) = &mut this.mode else {
+                    return;
+                };
+                state._checking = None;
+                if let Err(error) = result {
+                    state.error = Some(format!("{error:#}").into());
+                    cx.notify();
+                    return;
+                }
+                let container_id = options.container_id;
+                this.update_settings_file(cx, move |setting, _| {
+                    if let Some(connections) = setting.dev_container_connections.as_mut()
+                        && let Some(connection) = connections.get_mut(index)
+                        && connection.container_id == container_id
+                    {
+                        connection.remote_user = user;
+                        connection.extra_env = env;
+                    }
+                });
+                this.mode = this.default_mode(cx);
+                this.focus_handle.focus(window, cx);
+                cx.notify();
+            })
+            .ok();
         });
-        cx.emit(DismissEvent);
+        if let Mode::EditDevContainerUser(state) = &mut self.mode {
+            state.error = None;
+            state._checking = Some(checking);
+        }
         cx.notify();
     }
 
-    fn init_dev_container_mode(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let configs = self
-            .workspace
-            .read_with(cx, |workspace, cx| find_devcontainer_configs(workspace, cx))
-            .unwrap_or_default();
-
-        if configs.len() > 1 {
-            let delegate = DevContainerPickerDelegate::new(configs, cx.weak_entity());
-            self.dev_container_picker =
-                Some(cx.new(|cx| Picker::uniform_list(delegate, window, cx).modal(false)));
+    fn remove_dev_container(
+        &mut self,
+        index: usize,
+        name: SharedString,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let confirmation = window.prompt(
+            PromptLevel::Warning,
+            &format!("Remove dev container `{name}`?"),
+            Some("The container itself is left as it is."),
+            &["Yes, remove it", "No, keep it"],
+            cx,
+        );
+        cx.spawn(async move |this, cx| {
+            if confirmation.await.ok() == Some(0) {
+                this.update(cx, |this, cx| {
+                    this.delete_dev_container(index, cx);
+                    this.mode = this.default_mode(cx);
+                    cx.notify();
+                })?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
 
-            let state =
-                CreateRemoteDevContainer::new(DevContainerCreationProgress::SelectingConfig, cx);
-            self.mode = Mode::CreateRemoteDevContainer(state);
-            cx.notify();
-        } else if let Some((app_state, context)) = self
-            .workspace
-            .read_with(cx, |workspace, cx| {
-                let app_state = workspace.app_state().clone();
-                let context = DevContainerContext::from_workspace(workspace, cx)?;
-                Some((app_state, context))
-            })
-            .ok()
-            .flatten()
-        {
-            let config = configs.into_iter().next();
-            self.open_dev_container(config, app_state, context, window, cx);
-            self.view_in_progress_dev_container(window, cx);
-        } else {
-            log::error!("No active project directory for Dev Container");
+    /// Shows what `edit` would change in the remote settings, and only writes
+    /// it to the settings file once the user confirms. Shows `unchanged` in a
+    /// toast instead when it wouldn't change anything.
+    fn confirm_settings_changes(
+        &mut self,
+        title: SharedString,
+        edit: RemoteSettingsEdit,
+        unchanged: &'static str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let changes = RemoteSettingsChanges::preview(&edit, cx);
+        if changes.is_empty() {
+            self.show_selection_hint(unchanged, cx);
+            return;
         }
+        let state = ConfirmSettingsChangesState::new(title, changes, edit, cx);
+        state.confirm.focus_handle.focus(window, cx);
+        self.mode = Mode::ConfirmSettingsChanges(state);
+        cx.notify();
     }
 
-    fn open_dev_container(
-        &self,
-        config: Option<DevContainerConfig>,
-        app_state: Arc<AppState>,
-        context: DevContainerContext,
+    fn apply_settings_changes(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Mode::ConfirmSettingsChanges(state) = &self.mode else {
+            return;
+        };
+        let edit = state.edit.clone();
+        self.update_settings_file(cx, move |settings, _| edit(settings));
+        self.mode = self.default_mode(cx);
+        self.focus_handle.focus(window, cx);
+        cx.notify();
+    }
+
+    fn show_all_settings_changes(
+        &mut self,
+        group_ix: usize,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        let replace_window = window.window_handle().downcast::<MultiWorkspace>();
-        let app_state = Arc::downgrade(&app_state);
+        let Mode::ConfirmSettingsChanges(state) = &mut self.mode else {
+            return;
+        };
+        if let Some(show_more) = state.show_more.get_mut(group_ix) {
+            *show_more = None;
+        }
+        state.confirm.focus_handle.focus(window, cx);
+        cx.notify();
+    }
 
-        cx.spawn_in(window, async move |entity, cx| {
-            let environment = context.environment(cx).await;
+    /// Saves the hosts of the SSH config file `source` that aren't saved
+    /// yet, once the user confirms.
+    fn save_ssh_config_hosts(
+        &mut self,
+        source: Arc<Path>,
+        hosts: Vec<SharedString>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let hosts = hosts
+            .into_iter()
+            .map(|host| host.to_string())
+            .collect::<Vec<_>>();
+        let edit: RemoteSettingsEdit = Arc::new(move |settings| {
+            let connections = settings.ssh_connections.get_or_insert_default();
+            for host in &hosts {
+                if !connections
+                    .iter()
+                    .any(|connection| connection.host == *host)
+                {
+                    connections.push(SshConnection {
+                        host: host.clone(),
+                        ..SshConnection::default()
+                    });
+                }
+            }
+        });
+        self.confirm_settings_changes(
+            format!("Save Hosts from {}", source.compact().to_string_lossy()).into(),
+            edit,
+            "Every host in this SSH config file is already saved.",
+            window,
+            cx,
+        );
+    }
 
-            let (dev_container_connection, starting_dir) =
-                match start_dev_container_with_config(context, config, environment).await {
-                    Ok((c, s)) => (c, s),
-                    Err(e) => {
-                        log::error!("Failed to start dev container: {:?}", e);
-                        cx.prompt(
-                            gpui::PromptLevel::Critical,
-                            "Failed to start Dev Container. See logs for details",
-                            Some(&format!("{e}")),
-                            &["Ok"],
-                        )
-                        .await
-                        .ok();
-                        entity
-                            .update_in(cx, |remote_server_projects, window, cx| {
-                                remote_server_projects.allow_dismissal = true;
-                                remote_server_projects.mode =
-                                    Mode::CreateRemoteDevContainer(CreateRemoteDevContainer::new(
-                                        DevContainerCreationProgress::Error(format!("{e}")),
-                                        cx,
-                                    ));
-                                remote_server_projects.focus_handle(cx).focus(window, cx);
-                            })
-                            .ok();
-                        return;
-                    }
+    /// Removes the saved dev containers whose project folder or
+    /// devcontainer.json no longer exists, once the user confirms. The
+    /// containers themselves are left as they are.
+    fn remove_missing_dev_containers(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(fs) = self
+            .workspace
+            .read_with(cx, |workspace, _| workspace.app_state().fs.clone())
+            .log_err()
+        else {
+            return;
+        };
+        let connections = RemoteSettings::get_global(cx)
+            .dev_container_connections
+            .clone();
+        cx.spawn_in(window, async move |this, cx| {
+            let mut missing = HashSet::default();
+            for connection in connections {
+                let project_exists = match &connection.host_project_root {
+                    Some(root) => fs.is_dir(Path::new(root)).await,
+                    None => true,
                 };
-            cx.update(|_, cx| {
-                ExtensionStore::global(cx).update(cx, |this, cx| {
-                    for extension in &dev_container_connection.extension_ids {
-                        log::info!("Installing extension {extension} from devcontainer");
-                        this.install_latest_extension(Arc::from(extension.clone()), cx);
+                let config_exists = match &connection.config_path {
+                    Some(config_path) => fs.is_file(Path::new(config_path)).await,
+                    None => true,
+                };
+                if !project_exists || !config_exists {
+                    missing.insert(connection.container_id);
+                }
+            }
+
+            this.update_in(cx, |this, window, cx| {
+                let edit: RemoteSettingsEdit = Arc::new(move |settings| {
+                    if let Some(connections) = settings.dev_container_connections.as_mut() {
+                        connections
+                            .retain(|connection| !missing.contains(&connection.container_id));
                     }
-                })
+                });
+                this.confirm_settings_changes(
+                    "Remove Missing Dev Containers".into(),
+                    edit,
+                    "The projects of all saved dev containers still exist.",
+                    window,
+                    cx,
+                );
             })
-            .log_err();
+        })
+        .detach_and_log_err(cx);
+    }
 
-            entity
-                .update(cx, |this, cx| {
-                    this.allow_dismissal = true;
-                    cx.emit(DismissEvent);
-                })
-                .log_err();
+    fn copy_selected_server_address(
+        &mut self,
+        _: &CopyServerAddress,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let options = match &self.mode {
+            Mode::Default(state) => match state.selected_server(window, cx) {
+                Some(SelectedServer::Saved(_, connection)) => connection.into(),
+                Some(SelectedServer::DevContainer(connection)) => {
+                    Connection::DevContainer(connection).into()
+                }
+                Some(SelectedServer::SshConfigHost) => {
+                    self.show_selection_hint(
+                        "Hosts from your SSH config don't have a saved address. Connect to the host to save it.",
+                        cx,
+                    );
+                    return;
+                }
+                None => {
+                    self.show_selection_hint("Select a server to copy its address.", cx);
+                    return;
+                }
+            },
+            Mode::ViewServerOptions(ViewServerOptionsState::Ssh { connection, .. }) => {
+                RemoteConnectionOptions::Ssh(connection.clone())
+            }
+            Mode::ViewServerOptions(ViewServerOptionsState::Wsl { connection, .. }) => {
+                RemoteConnectionOptions::Wsl(connection.clone())
+            }
+            _ => return,
+        };
+        copy_server_address(&self.workspace, server_address(&options).into(), cx);
+    }
 
-            let Some(app_state) = app_state.upgrade() else {
-                return;
-            };
-            let result = open_remote_project(
-                Connection::DevContainer(dev_container_connection).into(),
-                vec![starting_dir].into_iter().map(PathBuf::from).collect(),
-                app_state,
-                OpenOptions {
-                    requesting_window: replace_window,
-                    ..OpenOptions::default()
-                },
-                cx,
-            )
-            .await;
-            if let Err(e) = result {
-                log::error!("Failed to connect: {e:#}");
-                cx.prompt(
-                    gpui::PromptLevel::Critical,
-                    "Failed to connect",
-                    Some(&e.to_string()),
-                    &["Ok"],
-                )
-                .await
-                .ok();
+    fn open_selected_server_options(
+        &mut self,
+        _: &OpenServerOptions,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Mode::Default(state) = &self.mode else {
+            return;
+        };
+        match state.selected_server(window, cx) {
+            Some(SelectedServer::Saved(index, connection)) => {
+                self.view_server_options((index, connection.into()), window, cx);
             }
-        })
-        .detach();
+            Some(SelectedServer::DevContainer(_)) => {
+                self.show_selection_hint("Dev containers don't have server options.", cx);
+            }
+            Some(SelectedServer::SshConfigHost) => {
+                self.show_selection_hint(
+                    "Hosts from your SSH config don't have server options. Connect to the host to save it.",
+                    cx,
+                );
+            }
+            None => self.show_selection_hint("Select a server to open its options.", cx),
+        }
     }
 
-    fn render_create_dev_container(
+    /// Explains why a server action did nothing, in a toast that goes away
+    /// by itself.
+    fn show_selection_hint(&self, message: &'static str, cx: &mut Context<Self>) {
+        struct RemoteServerSelectionHint;
+
+        self.workspace
+            .update(cx, |workspace, cx| {
+                workspace.show_toast(
+                    Toast::new(
+                        NotificationId::unique::<RemoteServerSelectionHint>(),
+                        message,
+                    )
+                    .autohide(),
+                    cx,
+                );
+            })
+            .ok();
+    }
+
+    /// Copies the `devcontainer up` command that sets up `connection` again,
+    /// warning when parts of it weren't saved and are left as placeholders.
+    fn copy_dev_container_setup_command(
         &self,
-        state: &CreateRemoteDevContainer,
+        connection: &DevContainerConnection,
+        cx: &mut Context<Self>,
+    ) {
+        struct DevContainerSetupCommandCopied;
+
+        let setup = devcontainer_setup_command(connection, ShellKind::system());
+        cx.write_to_clipboard(ClipboardItem::new_string(setup.command));
+        let is_partial = !setup.missing.is_empty();
+        let message = if is_partial {
+            format!(
+                "Copied a partial setup command for {}. Fill in the placeholders for what wasn't saved: {}.",
+                connection.name,
+                setup.missing.join(" and ")
+            )
+        } else {
+            format!(
+                "Copied the setup command for {} to clipboard",
+                connection.name
+            )
+        };
+        self.workspace
+            .update(cx, |workspace, cx| {
+                let toast = Toast::new(
+                    NotificationId::composite::<DevContainerSetupCommandCopied>(
+                        connection.container_id.clone(),
+                    ),
+                    message,
+                );
+                // Partial commands stay until dismissed, so the warning isn't missed.
+                let toast = if is_partial { toast } else { toast.autohide() };
+                workspace.show_toast(toast, cx);
+            })
+            .ok();
+    }
+
+    fn render_compact_server(
+        &mut self,
+        ix: usize,
+        row: &NavigableEntry,
+        project_count: usize,
+        connection: &Connection,
+        index: ServerIndex,
         window: &mut Window,
         cx: &mut Context<Self>,
-    ) -> impl IntoElement {
-        match &state.progress {
-            DevContainerCreationProgress::Error(message) => {
-                let view = Navigable::new(
-                    div()
-                        .child(
-                            div().track_focus(&self.focus_handle(cx)).size_full().child(
-                                v_flex().py_1().child(
-                                    ListItem::new("Error")
-                                        .inset(true)
-                                        .selectable(false)
-                                        .spacing(ui::ListItemSpacing::Sparse)
-                                        .start_slot(
-                                            Icon::new(IconName::XCircle).color(Color::Error),
-                                        )
-                                        .child(Label::new("Error Creating Dev Container:"))
-                                        .child(Label::new(message).buffer_font(cx)),
-                                ),
-                            ),
-                        )
-                        .child(ListSeparator)
-                        .child(
-                            div()
-                                .id("devcontainer-see-log")
-                                .track_focus(&state.view_logs_entry.focus_handle)
-                                .on_action(cx.listener(|_, _: &menu::Confirm, window, cx| {
-                                    window.dispatch_action(Box::new(OpenLog), cx);
-                                    cx.emit(DismissEvent);
-                                    cx.notify();
-                                }))
-                                .child(
-                                    ListItem::new("li-devcontainer-see-log")
-                                        .toggle_state(
-                                            state
-                                                .view_logs_entry
-                                                .focus_handle
-                                                .contains_focused(window, cx),
-                                        )
-                                        .inset(true)
-                                        .spacing(ui::ListItemSpacing::Sparse)
-                                        .start_slot(Icon::new(IconName::File).color(Color::Muted))
-                                        .child(Label::new("Open Zed Log"))
-                                        .on_click(cx.listener(|_, _, window, cx| {
-                                            window.dispatch_action(Box::new(OpenLog), cx);
-                                            cx.emit(DismissEvent);
-                                            cx.notify();
-                                        })),
-                                ),
-                        )
-                        .child(
-                            div()
-                                .id("devcontainer-go-back")
-                                .track_focus(&state.back_entry.focus_handle)
-                                .on_action(cx.listener(|this, _: &menu::Confirm, window, cx| {
-                                    this.cancel(&menu::Cancel, window, cx);
-                                    cx.notify();
-                                }))
-                                .child(
-                                    ListItem::new("li-devcontainer-go-back")
-                                        .toggle_state(
-                                            state
-                                                .back_entry
-                                                .focus_handle
-                                                .contains_focused(window, cx),
-                                        )
-                                        .inset(true)
-                                        .spacing(ui::ListItemSpacing::Sparse)
-                                        .start_slot(Icon::new(IconName::Exit).color(Color::Muted))
-                                        .child(Label::new("Exit"))
-                                        .on_click(cx.listener(|this, _, window, cx| {
-                                            this.cancel(&menu::Cancel, window, cx);
-                                            cx.notify();
-                                        })),
-                                ),
-                        )
-                        .into_any_element(),
-                )
-                .entry(state.view_logs_entry.clone())
-                .entry(state.back_entry.clone());
-                view.render(window, cx).into_any_element()
-            }
-            DevContainerCreationProgress::SelectingConfig => {
-                self.render_config_selection(window, cx).into_any_element()
-            }
-            DevContainerCreationProgress::Creating => {
-                self.focus_handle(cx).focus(window, cx);
-                div()
-                    .track_focus(&self.focus_handle(cx))
-                    .size_full()
-                    .child(
-                        v_flex()
-                            .pb_1()
-                            .child(
-                                ModalHeader::new().child(
-                                    Headline::new("Dev Containers").size(HeadlineSize::XSmall),
-                                ),
-                            )
-                            .child(ListSeparator)
-                            .child(
-                                ListItem::new("creating")
-                                    .inset(true)
-                                    .spacing(ui::ListItemSpacing::Sparse)
-                                    .disabled(true)
-                                    .start_slot(
-                                        Icon::new(IconName::ArrowCircle)
-                                            .color(Color::Muted)
-                                            .with_rotate_animation(2),
-                                    )
-                                    .child(
-                                        h_flex()
-                                            .opacity(0.6)
-                                            .gap_1()
-                                            .child(Label::new("Creating Dev Container"))
-                                            .child(LoadingLabel::new("")),
-                                    ),
-                            ),
-                    )
-                    .into_any_element()
+    ) -> AnyElement {
+        let (icon, main_label, aux_label) = match connection {
+            Connection::Ssh(connection) => match connection.nickname.clone() {
+                Some(nickname) => (
+                    IconName::Server,
+                    nickname,
+                    Some(SharedString::from(format!("({})", connection.host))),
+                ),
+                None => (IconName::Server, connection.host.clone(), None),
+            },
+            Connection::Wsl(connection) => (IconName::Linux, connection.distro_name.clone(), None),
+            Connection::DevContainer(connection) => {
+                (IconName::Server, connection.name.clone(), None)
             }
-        }
-    }
-
-    fn render_config_selection(
-        &self,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) -> impl IntoElement {
-        let Some(picker) = &self.dev_container_picker else {
-            return div().into_any_element();
         };
-
-        let content = v_flex().pb_1().child(picker.clone().into_any_element());
-
-        picker.focus_handle(cx).focus(window, cx);
-
-        content.into_any_element()
-    }
-
-    fn render_create_remote_server(
-        &self,
-        state: &CreateRemoteServer,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) -> impl IntoElement {
-        let ssh_prompt = state.ssh_prompt.clone();
-
-        state.address_editor.update(cx, |editor, cx| {
-            if editor.text(cx).is_empty() {
-                editor.set_placeholder_text("ssh user@example -p 2222", window, cx);
-            }
-        });
-
-        let theme = cx.theme();
+        let project_count_label = if project_count == 1 {
+            "1 project".to_string()
+        } else {
+            format!("{project_count} projects")
+        };
 
         v_flex()
-            .track_focus(&self.focus_handle(cx))
-            .id("create-remote-server")
-            .overflow_hidden()
-            .size_full()
-            .flex_1()
+            .w_full()
+            .child(ListSeparator)
             .child(
                 div()
-                    .p_2()
-                    .border_b_1()
-                    .border_color(theme.colors().border_variant)
-                    .child(state.address_editor.clone()),
-            )
-            .child(
-                h_flex()
-                    .bg(theme.colors().editor_background)
-                    .rounded_b_sm()
-                    .w_full()
-                    .map(|this| {
-                        if let Some(ssh_prompt) = ssh_prompt {
-                            this.child(h_flex().w_full().child(ssh_prompt))
-                        } else if let Some(address_error) = &state.address_error {
-                            this.child(
-                                h_flex().p_2().w_full().gap_2().child(
-                                    Label::new(address_error.clone())
-                                        .size(LabelSize::Small)
-                                        .color(Color::Error),
-                                ),
-                            )
-                        } else {
-                            this.child(
+                    .id(("compact-server-container", ix))
+                    .track_focus(&row.focus_handle)
+                    .anchor_scroll(row.scroll_anchor.clone())
+                    .on_action(cx.listener(move |this, _: &menu::Confirm, window, cx| {
+                        this.expand_server(index, window, cx);
+                    }))
+                    .child(
+                        ListItem::new(("compact-server", ix))
+                            .toggle_state(row.focus_handle.contains_focused(window, cx))
+                            .inset(true)
+                            .spacing(ui::ListItemSpacing::Dense)
+                            .start_slot(Icon::new(icon).color(Color::Muted))
+                            .child(
                                 h_flex()
-                                    .p_2()
-                                    .w_full()
                                     .gap_1()
-                                    .child(
-                                        Label::new(
-                                            "Enter the command you use to SSH into this server.",
-                                        )
-                                        .color(Color::Muted)
-                                        .size(LabelSize::Small),
-                                    )
-                                    .child(
-                                        Button::new("learn-more", "Learn More")
-                                            .label_size(LabelSize::Small)
-                                            .end_icon(
-                                                Icon::new(IconName::ArrowUpRight)
-                                                    .size(IconSize::XSmall),
-                                            )
-                                            .on_click(|_, _, cx| {
-                                                cx.open_url(
-                                                    "https://zed.dev/docs/remote-development",
-                                                );
-                                            }),
-                                    ),
+                                    .overflow_hidden()
+                                    .text_ellipsis()
+                                    .child(Label::new(main_label))
+                                    .children(aux_label.map(|label| {
+                                        Label::new(label).size(LabelSize::Small).color(Color::Muted)
+                                    }))
+                                    .children(connection_tag(connection).map(render_server_tag)),
                             )
-                        }
-                    }),
+                            .end_slot(
+                                Label::new(project_count_label)
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            )
+                            .on_click(cx.listener(move |this, _, window, cx| {
+                                this.expand_server(index, window, cx);
+                            })),
+                    ),
             )
+            .into_any_element()
     }
 
-    #[cfg(target_os = "windows")]
-    fn render_add_wsl_distro(
-        &self,
-        state: &AddWslDistro,
+    /// Focuses the saved server `connection_options` connects to, expanding it
+    /// in the compact density. Falls back to the default section when the
+    /// server isn't saved.
+    fn select_saved_server(
+        &mut self,
+        connection_options: &RemoteConnectionOptions,
         window: &mut Window,
         cx: &mut Context<Self>,
-    ) -> impl IntoElement {
-        let connection_prompt = state.connection_prompt.clone();
+    ) {
+        let settings = RemoteSettings::get_global(cx);
+        let index = match connection_options {
+            RemoteConnectionOptions::Ssh(options) => settings
+                .ssh_connections()
+                .position(|connection| is_saved_connection_for(&connection, options))
+                .map(|index| ServerIndex::Ssh(SshServerIndex(index))),
+            RemoteConnectionOptions::Wsl(options) => settings
+                .wsl_connections()
+                .position(|connection| {
+                    connection.distro_name == options.distro_name && connection.user == options.user
+                })
+                .map(|index| ServerIndex::Wsl(WslServerIndex(index))),
+            _ => None,
+        };
+        match index {
+            Some(index) => self.expand_server(index, window, cx),
+            None => self.focus_default_section(window, cx),
+        }
+    }
 
-        state.picker.update(cx, |picker, cx| {
-            picker.focus_handle(cx).focus(window, cx);
-        });
+    /// The server list, reusing the entries of the rows it already showed.
+    fn default_mode(&mut self, cx: &mut App) -> Mode {
+        Mode::Default(DefaultState::new(
+            &self.ssh_config_servers,
+            None,
+            &self.server_search_query,
+            &mut self.server_list_entries,
+            cx,
+        ))
+    }
 
-        v_flex()
-            .id("add-wsl-distro")
-            .overflow_hidden()
-            .size_full()
-            .flex_1()
-            .map(|this| {
-                if let Some(connection_prompt) = connection_prompt {
-                    this.child(connection_prompt)
-                } else {
-                    this.child(state.picker.clone())
-                }
-            })
+    /// Shows the projects and actions of a server collapsed by the compact
+    /// density, keeping every other server on a single line.
+    fn expand_server(&mut self, index: ServerIndex, window: &mut Window, cx: &mut Context<Self>) {
+        let state = DefaultState::new(
+            &self.ssh_config_servers,
+            Some(index),
+            &self.server_search_query,
+            &mut self.server_list_entries,
+            cx,
+        );
+        let expanded = state.servers.iter().find_map(|server| match server {
+            RemoteEntry::Project {
+                index: server_index,
+                projects,
+                open_folder,
+                ..
+            } if *server_index == index => Some(
+                projects
+                    .first()
+                    .map(|(entry, _)| entry)
+                    .unwrap_or(open_folder)
+                    .focus_handle
+                    .clone(),
+            ),
+            _ => None,
+        });
+        self.mode = Mode::Default(state);
+        if let Some(focus_handle) = expanded {
+            focus_handle.focus(window, cx);
+        }
+        cx.notify();
     }
 
-    fn render_view_options(
+    fn render_ssh_config_group_header(
         &mut self,
-        options: ViewServerOptionsState,
+        group_ix: usize,
+        group: &SshConfigGroupHeader,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
-        let last_entry = options.entries().last().unwrap();
-
-        let mut view = Navigable::new(
+        let source = group.source.clone();
+        v_flex().w_full().child(ListSeparator).child(
             div()
-                .track_focus(&self.focus_handle(cx))
-                .size_full()
-                .child(match &options {
-                    ViewServerOptionsState::Ssh { connection, .. } => SshConnectionHeader {
-                        connection_string: connection.host.to_string().into(),
-                        paths: Default::default(),
-                        nickname: connection.nickname.clone().map(|s| s.into()),
-                        is_wsl: false,
-                        is_devcontainer: false,
-                    }
-                    .render(window, cx)
-                    .into_any_element(),
-                    ViewServerOptionsState::Wsl { connection, .. } => SshConnectionHeader {
-                        connection_string: connection.distro_name.clone().into(),
-                        paths: Default::default(),
-                        nickname: None,
-                        is_wsl: true,
-                        is_devcontainer: false,
+                .id(("ssh-config-group", group_ix))
+                .track_focus(&group.toggle.focus_handle)
+                .anchor_scroll(group.toggle.scroll_anchor.clone())
+                .on_action(cx.listener({
+                    let source = source.clone();
+                    move |this, _: &menu::Confirm, window, cx| {
+                        this.toggle_ssh_config_group(source.clone(), window, cx);
                     }
-                    .render(window, cx)
-                    .into_any_element(),
-                })
+                }))
                 .child(
-                    v_flex()
-                        .pb_1()
-                        .child(ListSeparator)
-                        .map(|this| match &options {
-                            ViewServerOptionsState::Ssh {
-                                connection,
-                                entries,
-                                server_index,
-                            } => this.child(self.render_edit_ssh(
-                                connection,
-                                *server_index,
-                                entries,
-                                window,
-                                cx,
-                            )),
-                            ViewServerOptionsState::Wsl {
-                                connection,
-                                entries,
-                                server_index,
-                            } => this.child(self.render_edit_wsl(
-                                connection,
-                                *server_index,
-                                entries,
-                                window,
-                                cx,
-                            )),
-                        })
-                        .child(ListSeparator)
-                        .child({
-                            div()
-                                .id("ssh-options-copy-server-address")
-                                .track_focus(&last_entry.focus_handle)
-                                .on_action(cx.listener(|this, _: &menu::Confirm, window, cx| {
-                                    this.mode = Mode::default_mode(&this.ssh_config_servers, cx);
-                                    cx.focus_self(window);
-                                    cx.notify();
-                                }))
+                    ListItem::new(("ssh-config-group-header", group_ix))
+                        .toggle_state(group.toggle.focus_handle.contains_focused(window, cx))
+                        .inset(true)
+                        .spacing(ui::ListItemSpacing::Sparse)
+                        .start_slot(
+                            Icon::new(if group.collapsed {
+                                IconName::ChevronRight
+                            } else {
+                                IconName::ChevronDown
+                            })
+                            .color(Color::Muted),
+                        )
+                        .child(
+                            h_flex()
+                                .gap_1()
                                 .child(
-                                    ListItem::new("go-back")
-                                        .toggle_state(
-                                            last_entry.focus_handle.contains_focused(window, cx),
-                                        )
-                                        .inset(true)
-                                        .spacing(ui::ListItemSpacing::Sparse)
-                                        .start_slot(
-                                            Icon::new(IconName::ArrowLeft).color(Color::Muted),
-                                        )
-                                        .child(Label::new("Go Back"))
-                                        .on_click(cx.listener(|this, _, window, cx| {
-                                            this.mode =
-                                                Mode::default_mode(&this.ssh_config_servers, cx);
-                                            cx.focus_self(window);
-                                            cx.notify()
-                                        })),
+                                    Label::new(source.compact().to_string_lossy().into_owned())
+                                        .size(LabelSize::Small)
+                                        .color(Color::Muted),
                                 )
-                        }),
-                )
-                .into_any_element(),
-        );
-
-        for entry in options.entries() {
-            view = view.entry(entry.clone());
-        }
+                                .child(
+                                    Label::new(format!("({})", group.hosts.len()))
+                                        .size(LabelSize::Small)
+                                        .color(Color::Muted),
+                                ),
+                        )
+                        .on_click(cx.listener(move |this, _, window, cx| {
+                            this.toggle_ssh_config_group(source.clone(), window, cx);
+                        })),
+                ),
+        )
+    }
 
-        view.render(window, cx).into_any_element()
+    fn render_show_more_ssh_config_hosts(
+        &mut self,
+        group_ix: usize,
+        group: &SshConfigGroupHeader,
+        show_more: &NavigableEntry,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let source = group.source.clone();
+        let count = group.hidden_host_count.min(SSH_CONFIG_HOSTS_PAGE_SIZE);
+        let label = if count == 1 {
+            "Show 1 More Host".to_string()
+        } else {
+            format!("Show {count} More Hosts")
+        };
+        div()
+            .id(("show-more-ssh-config-hosts", group_ix))
+            .track_focus(&show_more.focus_handle)
+            .anchor_scroll(show_more.scroll_anchor.clone())
+            .on_action(cx.listener({
+                let source = source.clone();
+                move |this, _: &menu::Confirm, window, cx| {
+                    this.show_more_ssh_config_hosts(source.clone(), window, cx);
+                }
+            }))
+            .child(
+                ListItem::new(("show-more-ssh-config-hosts-item", group_ix))
+                    .toggle_state(show_more.focus_handle.contains_focused(window, cx))
+                    .inset(true)
+                    .spacing(ui::ListItemSpacing::Sparse)
+                    .start_slot(Icon::new(IconName::ChevronDown).color(Color::Muted))
+                    .child(Label::new(label).color(Color::Muted))
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.show_more_ssh_config_hosts(source.clone(), window, cx);
+                    })),
+            )
     }
 
-    fn render_edit_wsl(
-        &self,
-        connection: &WslConnectionOptions,
-        index: WslServerIndex,
-        entries: &[NavigableEntry],
+    fn render_save_ssh_config_hosts(
+        &mut self,
+        group_ix: usize,
+        group: &SshConfigGroupHeader,
+        save_all: &NavigableEntry,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
-        let distro_name = SharedString::new(connection.distro_name.clone());
+        let source = group.source.clone();
+        let hosts = group.hosts.clone();
+        div()
+            .id(("save-ssh-config-hosts", group_ix))
+            .track_focus(&save_all.focus_handle)
+            .anchor_scroll(save_all.scroll_anchor.clone())
+            .on_action(cx.listener({
+                let source = source.clone();
+                let hosts = hosts.clone();
+                move |this, _: &menu::Confirm, window, cx| {
+                    this.save_ssh_config_hosts(source.clone(), hosts.clone(), window, cx);
+                }
+            }))
+            .child(
+                ListItem::new(("save-ssh-config-hosts-item", group_ix))
+                    .toggle_state(save_all.focus_handle.contains_focused(window, cx))
+                    .inset(true)
+                    .spacing(ui::ListItemSpacing::Sparse)
+                    .start_slot(Icon::new(IconName::Plus).color(Color::Muted))
+                    .child(Label::new("Save All Hosts…").color(Color::Muted))
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.save_ssh_config_hosts(source.clone(), hosts.clone(), window, cx);
+                    })),
+            )
+    }
 
-        v_flex().child({
-            fn remove_wsl_distro(
-                remote_servers: Entity<RemoteServerProjects>,
-                index: WslServerIndex,
-                distro_name: SharedString,
-                window: &mut Window,
-                cx: &mut App,
-            ) {
-                let prompt_message = format!("Remove WSL distro `{}`?", distro_name);
-
-                let confirmation = window.prompt(
-                    PromptLevel::Warning,
-                    &prompt_message,
-                    None,
-                    &["Yes, remove it", "No, keep it"],
-                    cx,
-                );
+    fn render_remove_missing_dev_containers(
+        &mut self,
+        remove_missing: &NavigableEntry,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        v_flex().w_full().child(ListSeparator).child(
+            div()
+                .id("remove-missing-dev-containers")
+                .track_focus(&remove_missing.focus_handle)
+                .anchor_scroll(remove_missing.scroll_anchor.clone())
+                .on_action(cx.listener(|this, _: &menu::Confirm, window, cx| {
+                    this.remove_missing_dev_containers(window, cx);
+                }))
+                .child(
+                    ListItem::new("remove-missing-dev-containers-item")
+                        .toggle_state(remove_missing.focus_handle.contains_focused(window, cx))
+                        .inset(true)
+                        .spacing(ui::ListItemSpacing::Sparse)
+                        .start_slot(Icon::new(IconName::Trash).color(Color::Muted))
+                        .child(Label::new("Remove Missing Dev Containers…").color(Color::Muted))
+                        .on_click(cx.listener(|this, _, window, cx| {
+                            this.remove_missing_dev_containers(window, cx);
+                        })),
+                ),
+        )
+    }
 
-                cx.spawn(async move |cx| {
-                    if confirmation.await.ok() == Some(0) {
-                        remote_servers.update(cx, |this, cx| {
-                            this.delete_wsl_distro(index, cx);
-                        });
-                        remote_servers.update(cx, |this, cx| {
-                            this.mode = Mode::default_mode(&this.ssh_config_servers, cx);
-                            cx.notify();
-                        });
-                    }
-                    anyhow::Ok(())
-                })
-                .detach_and_log_err(cx);
-            }
+    fn render_server_tag_header(
+        &mut self,
+        group_ix: usize,
+        group: &ServerTagGroupHeader,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let tag = group.tag.clone();
+        v_flex().w_full().child(ListSeparator).child(
             div()
-                .id("wsl-options-remove-distro")
-                .track_focus(&entries[0].focus_handle)
+                .id(("server-tag-group", group_ix))
+                .track_focus(&group.toggle.focus_handle)
+                .anchor_scroll(group.toggle.scroll_anchor.clone())
                 .on_action(cx.listener({
-                    let distro_name = distro_name.clone();
-                    move |_, _: &menu::Confirm, window, cx| {
-                        remove_wsl_distro(cx.entity(), index, distro_name.clone(), window, cx);
-                        cx.focus_self(window);
+                    let tag = tag.clone();
+                    move |this, _: &menu::Confirm, window, cx| {
+                        this.toggle_server_tag_group(tag.clone(), window, cx);
                     }
                 }))
                 .child(
-                    ListItem::new("remove-distro")
-                        .toggle_state(entries[0].focus_handle.contains_focused(window, cx))
+                    ListItem::new(("server-tag-group-header", group_ix))
+                        .toggle_state(group.toggle.focus_handle.contains_focused(window, cx))
                         .inset(true)
                         .spacing(ui::ListItemSpacing::Sparse)
-                        .start_slot(Icon::new(IconName::Trash).color(Color::Error))
-                        .child(Label::new("Remove Distro").color(Color::Error))
-                        .on_click(cx.listener(move |_, _, window, cx| {
-                            remove_wsl_distro(cx.entity(), index, distro_name.clone(), window, cx);
-                            cx.focus_self(window);
+                        .start_slot(
+                            Icon::new(if group.collapsed {
+                                IconName::ChevronRight
+                            } else {
+                                IconName::ChevronDown
+                            })
+                            .color(Color::Muted),
+                        )
+                        .child(
+                            h_flex()
+                                .gap_1()
+                                .map(|this| match &tag {
+                                    Some(tag) => this.child(render_server_tag(tag)),
+                                    None => this.child(
+                                        Label::new("Other")
+                                            .size(LabelSize::Small)
+                                            .color(Color::Muted),
+                                    ),
+                                })
+                                .child(
+                                    Label::new(format!("({})", group.server_count))
+                                        .size(LabelSize::Small)
+                                        .color(Color::Muted),
+                                ),
+                        )
+                        .on_click(cx.listener(move |this, _, window, cx| {
+                            this.toggle_server_tag_group(tag.clone(), window, cx);
                         })),
-                )
-        })
+                ),
+        )
     }
 
-    fn render_edit_ssh(
-        &self,
-        connection: &SshConnectionOptions,
-        index: SshServerIndex,
-        entries: &[NavigableEntry],
+    fn toggle_server_tag_group(
+        &mut self,
+        tag: Option<SharedString>,
         window: &mut Window,
         cx: &mut Context<Self>,
-    ) -> impl IntoElement {
-        let connection_string = SharedString::new(connection.host.to_string());
+    ) {
+        let collapsed = &mut cx.default_global::<CollapsedServerTags>().0;
+        if !collapsed.remove(&tag) {
+            collapsed.insert(tag.clone());
+        }
 
-        v_flex()
-            .child({
-                let label = if connection.nickname.is_some() {
-                    "Edit Nickname"
-                } else {
-                    "Add Nickname to Server"
-                };
-                div()
-                    .id("ssh-options-add-nickname")
-                    .track_focus(&entries[0].focus_handle)
-                    .on_action(cx.listener(move |this, _: &menu::Confirm, window, cx| {
-                        this.mode = Mode::EditNickname(EditNicknameState::new(index, window, cx));
-                        cx.notify();
-                    }))
-                    .child(
-                        ListItem::new("add-nickname")
-                            .toggle_state(entries[0].focus_handle.contains_focused(window, cx))
-                            .inset(true)
-                            .spacing(ui::ListItemSpacing::Sparse)
-                            .start_slot(Icon::new(IconName::Pencil).color(Color::Muted))
-                            .child(Label::new(label))
-                            .on_click(cx.listener(move |this, _, window, cx| {
-                                this.mode =
-                                    Mode::EditNickname(EditNicknameState::new(index, window, cx));
-                                cx.notify();
-                            })),
-                    )
-            })
-            .child({
-                let workspace = self.workspace.clone();
-                fn callback(
-                    workspace: WeakEntity<Workspace>,
-                    connection_string: SharedString,
-                    cx: &mut App,
-                ) {
-                    cx.write_to_clipboard(ClipboardItem::new_string(connection_string.to_string()));
-                    workspace
-                        .update(cx, |this, cx| {
-                            struct SshServerAddressCopiedToClipboard;
-                            let notification = format!(
-                                "Copied server address ({}) to clipboard",
-                                connection_string
-                            );
+        self.mode = self.default_mode(cx);
+        if let Mode::Default(state) = &self.mode
+            && let Some(group) = state.tag_groups.iter().find(|group| group.tag == tag)
+        {
+            group.toggle.focus_handle.focus(window, cx);
+        }
+        cx.notify();
+    }
 
-                            this.show_toast(
-                                Toast::new(
-                                    NotificationId::composite::<SshServerAddressCopiedToClipboard>(
-                                        connection_string.clone(),
-                                    ),
-                                    notification,
-                                )
-                                .autohide(),
-                                cx,
-                            );
-                        })
-                        .ok();
-                }
-                div()
-                    .id("ssh-options-copy-server-address")
-                    .track_focus(&entries[1].focus_handle)
-                    .on_action({
-                        let connection_string = connection_string.clone();
-                        let workspace = self.workspace.clone();
-                        move |_: &menu::Confirm, _, cx| {
-                            callback(workspace.clone(), connection_string.clone(), cx);
-                        }
-                    })
-                    .child(
-                        ListItem::new("copy-server-address")
-                            .toggle_state(entries[1].focus_handle.contains_focused(window, cx))
-                            .inset(true)
-                            .spacing(ui::ListItemSpacing::Sparse)
-                            .start_slot(Icon::new(IconName::Copy).color(Color::Muted))
-                            .child(Label::new("Copy Server Address"))
-                            .end_slot(Label::new(connection_string.clone()).color(Color::Muted))
-                            .show_end_slot_on_hover()
-                            .on_click({
-                                let connection_string = connection_string.clone();
-                                move |_, _, cx| {
-                                    callback(workspace.clone(), connection_string.clone(), cx);
-                                }
-                            }),
-                    )
-            })
-            .child({
-                fn remove_ssh_server(
-                    remote_servers: Entity<RemoteServerProjects>,
-                    index: SshServerIndex,
-                    connection_string: SharedString,
-                    window: &mut Window,
-                    cx: &mut App,
-                ) {
-                    let prompt_message = format!("Remove server `{}`?", connection_string);
+    fn toggle_ssh_config_group(
+        &mut self,
+        source: Arc<Path>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let collapsed = &mut cx.default_global::<CollapsedSshConfigSources>().0;
+        if !collapsed.remove(&source) {
+            collapsed.insert(source.clone());
+        }
 
-                    let confirmation = window.prompt(
-                        PromptLevel::Warning,
-                        &prompt_message,
-                        None,
-                        &["Yes, remove it", "No, keep it"],
-                        cx,
-                    );
+        self.mode = self.default_mode(cx);
+        if let Mode::Default(state) = &self.mode
+            && let Some(group) = state
+                .ssh_config_groups
+                .iter()
+                .find(|group| group.source == source)
+        {
+            group.toggle.focus_handle.focus(window, cx);
+        }
+        cx.notify();
+    }
 
-                    cx.spawn(async move |cx| {
-                        if confirmation.await.ok() == Some(0) {
-                            remote_servers.update(cx, |this, cx| {
-                                this.delete_ssh_server(index, cx);
-                            });
-                            remote_servers.update(cx, |this, cx| {
-                                this.mode = Mode::default_mode(&this.ssh_config_servers, cx);
-                                cx.notify();
-                            });
-                        }
-                        anyhow::Ok(())
-                    })
-                    .detach_and_log_err(cx);
-                }
-                div()
-                    .id("ssh-options-copy-server-address")
-                    .track_focus(&entries[2].focus_handle)
-                    .on_action(cx.listener({
-                        let connection_string = connection_string.clone();
-                        move |_, _: &menu::Confirm, window, cx| {
-                            remove_ssh_server(
-                                cx.entity(),
-                                index,
-                                connection_string.clone(),
-                                window,
-                                cx,
-                            );
-                            cx.focus_self(window);
-                        }
-                    }))
-                    .child(
-                        ListItem::new("remove-server")
-                            .toggle_state(entries[2].focus_handle.contains_focused(window, cx))
-                            .inset(true)
-                            .spacing(ui::ListItemSpacing::Sparse)
-                            .start_slot(Icon::new(IconName::Trash).color(Color::Error))
-                            .child(Label::new("Remove Server").color(Color::Error))
-                            .on_click(cx.listener(move |_, _, window, cx| {
-                                remove_ssh_server(
-                                    cx.entity(),
-                                    index,
-                                    connection_string.clone(),
-                                    window,
-                                    cx,
-                                );
-                                cx.focus_self(window);
-                            })),
-                    )
-            })
+    /// Shows the next page of the hosts from the SSH config file `source`,
+    /// keeping the focus on the row that shows more while there are more.
+    fn show_more_ssh_config_hosts(
+        &mut self,
+        source: Arc<Path>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let shown = self.server_list_entries.shown_ssh_config_hosts(&source);
+        self.server_list_entries
+            .shown_ssh_config_hosts
+            .insert(source.clone(), shown + SSH_CONFIG_HOSTS_PAGE_SIZE);
+
+        self.mode = self.default_mode(cx);
+        if let Mode::Default(state) = &self.mode
+            && let Some(group) = state
+                .ssh_config_groups
+                .iter()
+                .find(|group| group.source == source)
+        {
+            group
+                .show_more
+                .as_ref()
+                .unwrap_or(&group.toggle)
+                .focus_handle
+                .focus(window, cx);
+        }
+        cx.notify();
     }
 
-    fn render_edit_nickname(
-        &self,
-        state: &EditNicknameState,
+    fn render_remote_project(
+        &mut self,
+        server_ix: ServerIndex,
+        server: RemoteEntry,
+        ix: usize,
+        (navigation, project): &(NavigableEntry, RemoteProject),
         window: &mut Window,
         cx: &mut Context<Self>,
     ) -> impl IntoElement {
-        let Some(connection) = RemoteSettings::get_global(cx)
-            .ssh_connections()
-            .nth(state.index.0)
-        else {
-            return v_flex()
-                .id("ssh-edit-nickname")
-                .track_focus(&self.focus_handle(cx));
-        };
+        let is_from_zed = server.is_from_zed();
+        let element_id_base = SharedString::from(format!(
+            "remote-project-{}",
+            match server_ix {
+                ServerIndex::Ssh(index) => format!("ssh-{index}"),
+                ServerIndex::Wsl(index) => format!("wsl-{index}"),
+            }
+        ));
+        let container_element_id_base =
+            SharedString::from(format!("remote-project-container-{element_id_base}"));
 
-        let connection_string = connection.host.clone();
-        let nickname = connection.nickname.map(|s| s.into());
+        let callback = Rc::new({
+            let project = project.clone();
+            move |remote_server_projects: &mut Self,
+                  secondary_confirm: bool,
+                  window: &mut Window,
+                  cx: &mut Context<Self>| {
+                remote_server_projects.open_saved_project(
+                    server.connection().into_owned(),
+                    project.clone(),
+                    secondary_confirm,
+                    window,
+                    cx,
+                );
+            }
+        });
 
-        v_flex()
-            .id("ssh-edit-nickname")
-            .track_focus(&self.focus_handle(cx))
-            .child(
-                SshConnectionHeader {
-                    connection_string: connection_string.into(),
-                    paths: Default::default(),
-                    nickname,
-                    is_wsl: false,
-                    is_devcontainer: false,
+        div()
+            .id((container_element_id_base, ix))
+            .track_focus(&navigation.focus_handle)
+            .anchor_scroll(navigation.scroll_anchor.clone())
+            .on_action(cx.listener({
+                let callback = callback.clone();
+                move |this, _: &menu::Confirm, window, cx| {
+                    callback(this, false, window, cx);
                 }
-                .render(window, cx),
-            )
+            }))
+            .on_action(cx.listener({
+                let callback = callback.clone();
+                move |this, _: &menu::SecondaryConfirm, window, cx| {
+                    callback(this, true, window, cx);
+                }
+            }))
             .child(
-                h_flex()
-                    .p_2()
-                    .border_t_1()
-                    .border_color(cx.theme().colors().border_variant)
-                    .child(state.editor.clone()),
+                ListItem::new((element_id_base, ix))
+                    .toggle_state(navigation.focus_handle.contains_focused(window, cx))
+                    .inset(true)
+                    .spacing(ui::ListItemSpacing::Sparse)
+                    .start_slot(
+                        Icon::new(IconName::Folder)
+                            .color(Color::Muted)
+                            .size(IconSize::Small),
+                    )
+                    .child(Label::new(project.paths.join(", ")).truncate_start())
+                    .on_click(cx.listener(move |this, e: &ClickEvent, window, cx| {
+                        let secondary_confirm = e.modifiers().platform;
+                        callback(this, secondary_confirm, window, cx)
+                    }))
+                    .tooltip({
+                        let paths = project.paths.join("\n");
+                        let open_behavior = if self.create_new_window {
+                            "Opens in a new window; secondary confirm reuses this one"
+                        } else {
+                            "Opens in this window; secondary confirm opens a new one"
+                        };
+                        move |_, cx| {
+                            Tooltip::with_meta(
+                                paths.clone(),
+                                Some(&menu::SecondaryConfirm),
+                                open_behavior,
+                                cx,
+                            )
+                        }
+                    })
+                    .when(is_from_zed, |server_list_item| {
+                        server_list_item
+                            .end_slot(
+                                div()
+                                    .mr_2()
+                                    .child({
+                                        let project = project.clone();
+                                        IconButton::new("remove-remote-project", IconName::Trash)
+                                            .icon_size(IconSize::Small)
+                                            .shape(IconButtonShape::Square)
+                                            .size(ButtonSize::Large)
+                                            .tooltip(Tooltip::text("Delete Remote Project"))
+                                            .on_click(cx.listener(move |this, _, _, cx| {
+                                                this.delete_remote_project(server_ix, &project, cx)
+                                            }))
+                                    })
+                                    .into_any_element(),
+                            )
+                            .show_end_slot_on_hover()
+                    }),
             )
     }
 
-    fn render_default(
+    /// Opens a saved project, replacing the current window unless the modal
+    /// opens projects in new windows. Secondary confirm does the opposite.
+    ///
+    /// A project that's already open in a window is activated there instead,
+    /// since two windows on the same remote checkout fight over its language
+    /// servers. Secondary confirm opens it in a second window anyway.
+    fn open_saved_project(
         &mut self,
-        mut state: DefaultState,
+        connection: Connection,
+        project: RemoteProject,
+        secondary_confirm: bool,
         window: &mut Window,
         cx: &mut Context<Self>,
-    ) -> impl IntoElement {
-        let ssh_settings = RemoteSettings::get_global(cx);
-        let mut should_rebuild = false;
+    ) {
+        let connection_options = RemoteConnectionOptions::from(connection.clone());
+        self.check_bastion_before_connecting(
+            &connection_options.clone(),
+            window,
+            cx,
+            move |this, window, cx| {
+                this.check_identity_files_before_connecting(
+                    &connection_options,
+                    window,
+                    cx,
+                    move |this, window, cx| {
+                        this.open_checked_saved_project(
+                            connection,
+                            project,
+                            secondary_confirm,
+                            window,
+                            cx,
+                        )
+                    },
+                )
+            },
+        );
+    }
 
-        let ssh_connections_changed = ssh_settings.ssh_connections.0.iter().ne(state
-            .servers
-            .iter()
-            .filter_map(|server| match server {
-                RemoteEntry::Project {
-                    connection: Connection::Ssh(connection),
-                    ..
-                } => Some(connection),
-                _ => None,
-            }));
+    fn open_checked_saved_project(
+        &mut self,
+        connection: Connection,
+        project: RemoteProject,
+        secondary_confirm: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(app_state) = self
+            .workspace
+            .read_with(cx, |workspace, _| workspace.app_state().clone())
+            .log_err()
+        else {
+            return;
+        };
+        cx.emit(DismissEvent);
 
-        let wsl_connections_changed = ssh_settings.wsl_connections.0.iter().ne(state
-            .servers
-            .iter()
-            .filter_map(|server| match server {
-                RemoteEntry::Project {
-                    connection: Connection::Wsl(connection),
-                    ..
-                } => Some(connection),
-                _ => None,
-            }));
+        let connection_options = RemoteConnectionOptions::from(connection);
+        let paths = project
+            .paths
+            .into_iter()
+            .map(PathBuf::from)
+            .collect::<Vec<_>>();
+        let already_open =
+            find_open_remote_project(open_remote_workspaces(cx), &connection_options, &paths);
+        if let Some((existing_window, existing_workspace)) = already_open.clone()
+            && !secondary_confirm
+        {
+            cx.spawn_in(window, async move |_, cx| {
+                existing_window
+                    .update(cx, |multi_workspace, window, cx| {
+                        window.activate_window();
+                        multi_workspace.activate(existing_workspace, None, window, cx);
+                    })
+                    .log_err();
+            })
+            .detach();
+            return;
+        }
 
-        if ssh_connections_changed || wsl_connections_changed {
-            should_rebuild = true;
+        // Forcing a second window must not reuse the one that's already open.
+        let (replace_window, workspace_matching) = if already_open.is_some() {
+            (None, WorkspaceMatching::None)
+        } else {
+            let replace_window = match (self.create_new_window, secondary_confirm) {
+                (true, false) | (false, true) => None,
+                (true, true) | (false, false) => {
+                    window.window_handle().downcast::<MultiWorkspace>()
+                }
+            };
+            (replace_window, WorkspaceMatching::default())
         };
 
-        if !should_rebuild && ssh_settings.read_ssh_config {
-            let current_ssh_hosts: BTreeSet<SharedString> = state
-                .servers
-                .iter()
-                .filter_map(|server| match server {
-                    RemoteEntry::SshConfig { host, .. } => Some(host.clone()),
-                    _ => None,
-                })
-                .collect();
-            let mut expected_ssh_hosts = self.ssh_config_servers.clone();
-            for server in &state.servers {
-                if let RemoteEntry::Project {
-                    connection: Connection::Ssh(connection),
-                    ..
-                } = server
+        cx.spawn_in(window, async move |_, cx| {
+            let result = open_remote_project(
+                connection_options,
+                paths,
+                app_state,
+                OpenOptions {
+                    requesting_window: replace_window,
+                    workspace_matching,
+                    ..OpenOptions::default()
+                },
+                cx,
+            )
+            .await;
+            if let Err(e) = result {
+                log::error!("Failed to connect: {e:#}");
+                cx.prompt(
+                    gpui::PromptLevel::Critical,
+                    "Failed to connect",
+                    Some(&e.to_string()),
+                    &["Ok"],
+                )
+                .await
+                .ok();
+            }
+        })
+        .detach();
+    }
+
+    /// Probes the bastions saved servers jump through every
+    /// [`PROBE_INTERVAL`], so that a bastion coming back online clears the
+    /// flags on the servers behind it.
+    fn poll_bastions(cx: &mut Context<Self>) -> Task<()> {
+        cx.spawn(async move |this, cx| {
+            loop {
+                if this
+                    .update(cx, |this, cx| this.probe_due_bastions(cx))
+                    .is_err()
                 {
-                    expected_ssh_hosts.remove(connection.host.as_str());
+                    return;
                 }
+                cx.background_executor().timer(PROBE_INTERVAL).await;
             }
-            should_rebuild = current_ssh_hosts != expected_ssh_hosts;
-        }
+        })
+    }
 
-        if should_rebuild {
-            self.mode = Mode::default_mode(&self.ssh_config_servers, cx);
-            if let Mode::Default(new_state) = &self.mode {
-                state = new_state.clone();
-            }
+    /// Starts a probe of each bastion saved servers jump through that wasn't
+    /// probed recently. Servers that share a bastion share its probe.
+    fn probe_due_bastions(&mut self, cx: &mut Context<Self>) {
+        let settings = RemoteSettings::get_global(cx);
+        if !settings.check_jump_hosts {
+            return;
+        }
+        let bastions = settings
+            .ssh_connections()
+            .filter_map(|connection| self.saved_server_bastion(&connection.args, &connection.host))
+            .collect();
+        let now = cx.background_executor().now();
+        let due = cx
+            .default_global::<BastionProbes>()
+            .start_due(bastions, now);
+        for bastion in due {
+            // The probe is left to finish if the modal closes, so that the
+            // bastion isn't left marked as being probed.
+            cx.spawn(async move |this, cx| {
+                let status = cx
+                    .background_spawn({
+                        let bastion = bastion.clone();
+                        let executor = cx.background_executor().clone();
+                        async move { probe_bastion(&bastion, executor).await }
+                    })
+                    .await;
+                cx.update(|cx| {
+                    let now = cx.background_executor().now();
+                    cx.default_global::<BastionProbes>()
+                        .finish(bastion, status, now)
+                });
+                this.update(cx, |_, cx| cx.notify()).ok();
+            })
+            .detach();
         }
+    }
 
-        let connect_button = div()
-            .id("ssh-connect-new-server-container")
-            .track_focus(&state.add_new_server.focus_handle)
-            .anchor_scroll(state.add_new_server.scroll_anchor.clone())
-            .child(
-                ListItem::new("register-remote-server-button")
-                    .toggle_state(
-                        state
-                            .add_new_server
-                            .focus_handle
-                            .contains_focused(window, cx),
-                    )
-                    .inset(true)
-                    .spacing(ui::ListItemSpacing::Sparse)
-                    .start_slot(Icon::new(IconName::Plus).color(Color::Muted))
-                    .child(Label::new("Connect SSH Server"))
-                    .on_click(cx.listener(|this, _, window, cx| {
-                        let state = CreateRemoteServer::new(window, cx);
-                        this.mode = Mode::CreateRemoteServer(state);
+    /// The bastion a saved server connecting to `host` with `args` jumps
+    /// through, following the host's SSH config when its arguments don't say.
+    fn saved_server_bastion(&self, args: &[String], host: &str) -> Option<Bastion> {
+        connection_bastion(
+            args,
+            self.ssh_config_proxy_jumps.get(host).map(String::as_str),
+        )
+    }
 
-                        cx.notify();
-                    })),
-            )
-            .on_action(cx.listener(|this, _: &menu::Confirm, window, cx| {
-                let state = CreateRemoteServer::new(window, cx);
-                this.mode = Mode::CreateRemoteServer(state);
+    /// The bastion a saved server jumps through, if the last probe of it
+    /// found it unreachable.
+    fn unreachable_bastion(&self, args: &[String], host: &str, cx: &App) -> Option<Bastion> {
+        if !RemoteSettings::get_global(cx).check_jump_hosts {
+            return None;
+        }
+        let bastion = self.saved_server_bastion(args, host)?;
+        let status = cx.try_global::<BastionProbes>()?.status(&bastion);
+        (status == Some(BastionStatus::Unreachable)).then_some(bastion)
+    }
 
-                cx.notify();
-            }));
+    /// Runs `connect` unless the server jumps through a bastion the last
+    /// probe couldn't reach. Otherwise asks first, since ssh would only give
+    /// up after its connect timeout.
+    fn check_bastion_before_connecting(
+        &mut self,
+        connection_options: &RemoteConnectionOptions,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+        connect: impl FnOnce(&mut Self, &mut Window, &mut Context<Self>) + 'static,
+    ) {
+        let bastion = match connection_options {
+            RemoteConnectionOptions::Ssh(options) => self.unreachable_bastion(
+                options.args.as_deref().unwrap_or_default(),
+                &options.host.to_string(),
+                cx,
+            ),
+            _ => None,
+        };
+        let Some(bastion) = bastion else {
+            connect(self, window, cx);
+            return;
+        };
 
-        let connect_dev_container_button = div()
-            .id("connect-new-dev-container")
-            .track_focus(&state.add_new_devcontainer.focus_handle)
-            .anchor_scroll(state.add_new_devcontainer.scroll_anchor.clone())
-            .child(
-                ListItem::new("register-dev-container-button")
-                    .toggle_state(
-                        state
-                            .add_new_devcontainer
-                            .focus_handle
-                            .contains_focused(window, cx),
-                    )
-                    .inset(true)
-                    .spacing(ui::ListItemSpacing::Sparse)
-                    .start_slot(Icon::new(IconName::Plus).color(Color::Muted))
-                    .child(Label::new("Connect Dev Container"))
-                    .on_click(cx.listener(|this, _, window, cx| {
-                        this.init_dev_container_mode(window, cx);
-                    })),
-            )
-            .on_action(cx.listener(|this, _: &menu::Confirm, window, cx| {
-                this.init_dev_container_mode(window, cx);
-            }));
+        let answer = window.prompt(
+            PromptLevel::Warning,
+            &format!("{bastion} can't be reached"),
+            Some(
+                "This server connects through it, so connecting will likely time out. \
+                Check that you're on the network or VPN it's reachable from.",
+            ),
+            &["Connect Anyway", "Cancel"],
+            cx,
+        );
+        cx.spawn_in(window, async move |this, cx| {
+            if answer.await? == 0 {
+                this.update_in(cx, |this, window, cx| connect(this, window, cx))?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
 
-        #[cfg(target_os = "windows")]
-        let wsl_connect_button = div()
-            .id("wsl-connect-new-server")
-            .track_focus(&state.add_new_wsl.focus_handle)
-            .anchor_scroll(state.add_new_wsl.scroll_anchor.clone())
-            .child(
-                ListItem::new("wsl-add-new-server")
-                    .toggle_state(state.add_new_wsl.focus_handle.contains_focused(window, cx))
-                    .inset(true)
-                    .spacing(ui::ListItemSpacing::Sparse)
-                    .start_slot(Icon::new(IconName::Plus).color(Color::Muted))
-                    .child(Label::new("Add WSL Distro"))
-                    .on_click(cx.listener(|this, _, window, cx| {
-                        let state = AddWslDistro::new(window, cx);
-                        this.mode = Mode::AddWslDistro(state);
+    /// Runs `connect` once the identity files a saved SSH connection passes
+    /// to ssh are usable. Otherwise asks whether to fix the first problem,
+    /// since ssh would only fail with it after its connect timeout.
+    fn check_identity_files_before_connecting(
+        &mut self,
+        connection_options: &RemoteConnectionOptions,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+        connect: impl FnOnce(&mut Self, &mut Window, &mut Context<Self>) + 'static,
+    ) {
+        let args = match connection_options {
+            RemoteConnectionOptions::Ssh(options) => options.args.clone().unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        if identity_files(&args).is_empty() {
+            connect(self, window, cx);
+            return;
+        }
 
-                        cx.notify();
-                    })),
-            )
-            .on_action(cx.listener(|this, _: &menu::Confirm, window, cx| {
-                let state = AddWslDistro::new(window, cx);
-                this.mode = Mode::AddWslDistro(state);
+        let check = cx.background_spawn(async move { check_identity_files(&args) });
+        cx.spawn_in(window, async move |this, cx| {
+            let Some(problem) = check.await else {
+                this.update_in(cx, |this, window, cx| connect(this, window, cx))?;
+                return anyhow::Ok(());
+            };
 
-                cx.notify();
-            }));
+            let can_restrict = cfg!(unix) && matches!(problem, IdentityFileProblem::TooOpen { .. });
+            let (detail, answers): (&str, &[&str]) = if can_restrict {
+                (
+                    "Restrict the file to your user, or edit the path in your settings.",
+                    &["Restrict to 0600", "Edit Settings", "Cancel"],
+                )
+            } else {
+                (
+                    "Edit the path in your settings, or connect anyway.",
+                    &["Edit Settings", "Connect Anyway", "Cancel"],
+                )
+            };
+            let answer = this
+                .update_in(cx, |_, window, cx| {
+                    window.prompt(
+                        PromptLevel::Warning,
+                        &problem.message(),
+                        Some(detail),
+                        answers,
+                        cx,
+                    )
+                })?
+                .await?;
+
+            match (can_restrict, answer) {
+                (true, 0) => {
+                    // Only the file named in the prompt is changed.
+                    let path = problem.path().to_path_buf();
+                    cx.background_spawn({
+                        let path = path.clone();
+                        async move { restrict_identity_file_permissions(&path) }
+                    })
+                    .await
+                    .with_context(|| format!("restricting permissions of {}", path.display()))?;
+                    this.update_in(cx, |this, window, cx| connect(this, window, cx))?;
+                }
+                (true, 1) | (false, 0) => {
+                    this.update_in(cx, |_, window, cx| {
+                        window.dispatch_action(Box::new(zed_actions::OpenSettingsFile), cx);
+                        cx.emit(DismissEvent);
+                    })?;
+                }
+                (false, 1) => {
+                    this.update_in(cx, |this, window, cx| connect(this, window, cx))?;
+                }
+                _ => {}
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_prompt_err(
+            "Failed to fix the identity file",
+            window,
+            cx,
+            |_, _, _| None,
+        );
+    }
 
-        let has_open_project = self
-            .workspace
-            .upgrade()
-            .map(|workspace| {
-                workspace
-                    .read(cx)
-                    .project()
-                    .read(cx)
-                    .visible_worktrees(cx)
-                    .next()
-                    .is_some()
-            })
-            .unwrap_or(false);
+    /// Opens the project of a saved server that was opened most recently, or
+    /// its only project. Without either, shows the server's folder picker.
+    fn connect_to_server(
+        &mut self,
+        index: ServerIndex,
+        connection: Connection,
+        projects: &[RemoteProject],
+        secondary_confirm: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let options = RemoteConnectionOptions::from(connection.clone());
+        match most_recent_project(&self.remote_project_history, &options, projects) {
+            Some(project) => {
+                self.open_saved_project(connection, project, secondary_confirm, window, cx)
+            }
+            None => self.create_remote_project(index, options, window, cx),
+        }
+    }
 
-        // We cannot currently connect a dev container from within a remote server due to the remote_server architecture
-        let is_local = self
+    /// Flips whether projects open in a new window, and remembers the choice
+    /// as the default for the next time the modal opens.
+    fn toggle_create_new_window(&mut self, cx: &mut Context<Self>) {
+        self.create_new_window = !self.create_new_window;
+        let create_new_window = self.create_new_window;
+        self.update_settings_file(cx, move |settings, _| {
+            settings.remote_servers_open_in_new_window = Some(create_new_window);
+        });
+        cx.notify();
+    }
+
+    fn update_settings_file(
+        &mut self,
+        cx: &mut Context<Self>,
+        f: impl FnOnce(&mut RemoteSettingsContent, &App) + Send + Sync + 'static,
+    ) {
+        let Some(fs) = self
             .workspace
-            .upgrade()
-            .map(|workspace| workspace.read(cx).project().read(cx).is_local())
-            .unwrap_or(true);
+            .read_with(cx, |workspace, _| workspace.app_state().fs.clone())
+            .log_err()
+        else {
+            return;
+        };
+        update_settings_file(fs, cx, move |setting, cx| f(&mut setting.remote, cx));
+    }
 
-        let modal_section = v_flex()
-            .track_focus(&self.focus_handle(cx))
-            .id("ssh-server-list")
-            .overflow_y_scroll()
-            .track_scroll(&state.scroll_handle)
-            .size_full()
-            .child(connect_button)
-            .when(has_open_project && is_local, |this| {
-                this.child(connect_dev_container_button)
-            });
+    fn set_upload_binary_over_ssh(
+        &mut self,
+        index: SshServerIndex,
+        upload_binary_over_ssh: bool,
+        cx: &mut Context<Self>,
+    ) {
+        let Mode::ViewServerOptions(ViewServerOptionsState::Ssh { connection, .. }) =
+            &mut self.mode
+        else {
+            return;
+        };
+        connection.upload_binary_over_ssh = upload_binary_over_ssh;
 
-        #[cfg(target_os = "windows")]
-        let modal_section = modal_section.child(wsl_connect_button);
-        #[cfg(not(target_os = "windows"))]
-        let modal_section = modal_section;
+        // A retained connection would be reused by the next connect, so drop
+        // it for that to pick up the new transfer mode.
+        let host = RemoteConnectionOptions::Ssh(connection.clone());
+        self.retained_connections
+            .retain(|client| !is_same_remote_host(&client.read(cx).connection_options(), &host));
 
-        let mut modal_section = Navigable::new(
-            modal_section
-                .child(
-                    List::new()
-                        .empty_message(
-                            h_flex()
-                                .size_full()
-                                .p_2()
-                                .justify_center()
-                                .border_t_1()
-                                .border_color(cx.theme().colors().border_variant)
-                                .child(
-                                    Label::new("No remote servers registered yet.")
-                                        .color(Color::Muted),
-                                )
-                                .into_any_element(),
-                        )
-                        .children(state.servers.iter().enumerate().map(|(ix, connection)| {
-                            self.render_remote_connection(ix, connection.clone(), window, cx)
-                                .into_any_element()
-                        })),
-                )
-                .into_any_element(),
-        )
-        .entry(state.add_new_server.clone());
+        self.update_settings_file(cx, move |setting, _| {
+            if let Some(connections) = setting.ssh_connections.as_mut()
+                && let Some(connection) = connections.get_mut(index.0)
+            {
+                connection.upload_binary_over_ssh = Some(upload_binary_over_ssh);
+            }
+        });
+        cx.notify();
+    }
 
-        if has_open_project && is_local {
-            modal_section = modal_section.entry(state.add_new_devcontainer.clone());
-        }
+    fn set_forward_agent(
+        &mut self,
+        index: SshServerIndex,
+        forward_agent: bool,
+        cx: &mut Context<Self>,
+    ) {
+        let Mode::ViewServerOptions(ViewServerOptionsState::Ssh { connection, .. }) =
+            &mut self.mode
+        else {
+            return;
+        };
+        set_agent_forwarding(connection.args.get_or_insert_default(), forward_agent);
 
-        if cfg!(target_os = "windows") {
-            modal_section = modal_section.entry(state.add_new_wsl.clone());
-        }
+        // Forwarding is set up when connecting, so a retained connection has
+        // to be dropped for the next connect to pick up the change.
+        let host = RemoteConnectionOptions::Ssh(connection.clone());
+        self.retained_connections
+            .retain(|client| !is_same_remote_host(&client.read(cx).connection_options(), &host));
 
-        for server in &state.servers {
-            match server {
-                RemoteEntry::Project {
-                    open_folder,
-                    projects,
-                    configure,
-                    ..
-                } => {
-                    for (navigation_state, _) in projects {
-                        modal_section = modal_section.entry(navigation_state.clone());
-                    }
-                    modal_section = modal_section
-                        .entry(open_folder.clone())
-                        .entry(configure.clone());
-                }
-                RemoteEntry::SshConfig { open_folder, .. } => {
-                    modal_section = modal_section.entry(open_folder.clone());
-                }
+        self.update_settings_file(cx, move |setting, _| {
+            if let Some(connections) = setting.ssh_connections.as_mut()
+                && let Some(connection) = connections.get_mut(index.0)
+            {
+                set_agent_forwarding(&mut connection.args, forward_agent);
+                // The command no longer says how the server connects.
+                connection.original_command = None;
             }
-        }
-        let mut modal_section = modal_section.render(window, cx).into_any_element();
+        });
+        cx.notify();
+    }
 
-        let is_project_selected = state.servers.iter().any(|server| match server {
-            RemoteEntry::Project { projects, .. } => projects
-                .iter()
-                .any(|(entry, _)| entry.focus_handle.contains_focused(window, cx)),
-            RemoteEntry::SshConfig { .. } => false,
+    fn delete_ssh_server(&mut self, server: SshServerIndex, cx: &mut Context<Self>) {
+        self.update_settings_file(cx, move |setting, _| {
+            if let Some(connections) = setting.ssh_connections.as_mut()
+                && connections.get(server.0).is_some()
+            {
+                connections.remove(server.0);
+            }
         });
+    }
 
-        Modal::new("remote-projects", None)
-            .header(ModalHeader::new().headline("Remote Projects"))
-            .section(
-                Section::new().padded(false).child(
-                    v_flex()
-                        .min_h(rems(20.))
-                        .size_full()
-                        .relative()
-                        .child(ListSeparator)
-                        .child(
-                            canvas(
-                                |bounds, window, cx| {
-                                    modal_section.prepaint_as_root(
-                                        bounds.origin,
-                                        bounds.size.into(),
-                                        window,
-                                        cx,
-                                    );
-                                    modal_section
-                                },
-                                |_, mut modal_section, window, cx| {
-                                    modal_section.paint(window, cx);
-                                },
+    fn delete_remote_project(
+        &mut self,
+        server: ServerIndex,
+        project: &RemoteProject,
+        cx: &mut Context<Self>,
+    ) {
+        match server {
+            ServerIndex::Ssh(server) => {
+                self.delete_ssh_project(server, project, cx);
+            }
+            ServerIndex::Wsl(server) => {
+                self.delete_wsl_project(server, project, cx);
+            }
+        }
+    }
+
+    fn delete_ssh_project(
+        &mut self,
+        server: SshServerIndex,
+        project: &RemoteProject,
+        cx: &mut Context<Self>,
+    ) {
+        let project = project.clone();
+        self.update_settings_file(cx, move |setting, _| {
+            if let Some(server) = setting
+                .ssh_connections
+                .as_mut()
+                .and_then(|connections| connections.get_mut(server.0))
+            {
+                server.projects.remove(&project);
+            }
+        });
+    }
+
+    fn delete_wsl_project(
+        &mut self,
+        server: WslServerIndex,
+        project: &RemoteProject,
+        cx: &mut Context<Self>,
+    ) {
+        let project = project.clone();
+        self.update_settings_file(cx, move |setting, _| {
+            if let Some(server) = setting
+                .wsl_connections
+                .as_mut()
+                .and_then(|connections| connections.get_mut(server.0))
+            {
+                server.projects.remove(&project);
+            }
+        });
+    }
+
+    fn delete_dev_container(&mut self, index: usize, cx: &mut Context<Self>) {
+        self.update_settings_file(cx, move |setting, _| {
+            if let Some(connections) = setting.dev_container_connections.as_mut()
+                && index < connections.len()
+            {
+                connections.remove(index);
+            }
+        });
+    }
+
+    fn delete_wsl_distro(&mut self, server: WslServerIndex, cx: &mut Context<Self>) {
+        self.update_settings_file(cx, move |setting, _| {
+            if let Some(connections) = setting.wsl_connections.as_mut() {
+                connections.remove(server.0);
+            }
+        });
+    }
+
+    /// Saves a server, as a new server or in place of the one whose command
+    /// was edited in the create-server form.
+    fn save_ssh_server(
+        &mut self,
+        connection_options: remote::SshConnectionOptions,
+        platform: Option<RemotePlatform>,
+        original_command: Option<String>,
+        replacing: Option<SshServerIndex>,
+        cx: &mut Context<Self>,
+    ) {
+        self.update_settings_file(cx, move |setting, _| {
+            let connections = setting.ssh_connections.get_or_insert_default();
+            match replacing.filter(|index| index.0 < connections.len()) {
+                Some(index) => update_ssh_connection_from_form(
+                    &mut connections[index.0],
+                    connection_options,
+                    platform,
+                    original_command,
+                ),
+                None => {
+                    let mut connection = SshConnection::default();
+                    update_ssh_connection_from_form(
+                        &mut connection,
+                        connection_options,
+                        platform,
+                        original_command,
+                    );
+                    connections.push(connection);
+                }
+            }
+        });
+    }
+
+    fn edit_in_dev_container_json(
+        &mut self,
+        config: Option<DevContainerConfig>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            cx.emit(DismissEvent);
+            cx.notify();
+            return;
+        };
+
+        let (package, config_path) = match config {
+            Some(config) => (config.package, config.config_path),
+            None => (None, PathBuf::from(".devcontainer/devcontainer.json")),
+        };
+
+        workspace.update(cx, |workspace, cx| {
+            let project = workspace.project().clone();
+
+            let worktree = match &package {
+                Some(package) => project.read(cx).worktree_for_id(package.worktree_id, cx),
+                None => project
+                    .read(cx)
+                    .visible_worktrees(cx)
+                    .find_map(|tree| tree.read(cx).root_entry()?.is_dir().then_some(tree)),
+            };
+
+            if let Some(worktree) = worktree {
+                let tree_id = worktree.read(cx).id();
+                let devcontainer_path: Arc<RelPath> =
+                    match RelPath::new(&config_path, util::paths::PathStyle::Posix) {
+                        Ok(path) => match &package {
+                            Some(package) => package.path.join(&path),
+                            None => path.into_owned().into(),
+                        },
+                        Err(error) => {
+                            log::error!(
+                                "Invalid devcontainer path: {} - {}",
+                                config_path.display(),
+                                error
+                            );
+                            return;
+                        }
+                    };
+                cx.spawn_in(window, async move |workspace, cx| {
+                    workspace
+                        .update_in(cx, |workspace, window, cx| {
+                            workspace.open_path(
+                                (tree_id, devcontainer_path),
+                                None,
+                                true,
+                                window,
+                                cx,
                             )
-                            .size_full(),
-                        )
-                        .custom_scrollbars(
-                            Scrollbars::always_visible(ScrollAxes::Vertical)
-                                .tracked_scroll_handle(&state.scroll_handle),
-                            window,
-                            cx,
-                        ),
+                        })?
+                        .await
+                })
+                .detach();
+            } else {
+                return;
+            }
+        });
+        cx.emit(DismissEvent);
+        cx.notify();
+    }
+
+    fn clear_server_search(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.server_search_editor.update(cx, |editor, cx| {
+            editor.set_text("", window, cx);
+        });
+        self.server_search_editor.focus_handle(cx).focus(window, cx);
+    }
+
+    fn init_dev_container_mode(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        remember_section(RemoteServersSection::DevContainer, cx);
+        let configs = self
+            .workspace
+            .read_with(cx, |workspace, cx| find_devcontainer_configs(workspace, cx))
+            .unwrap_or_else(|_| Task::ready(Vec::new()));
+        self.discover_dev_container_configs(configs, window, cx);
+    }
+
+    /// Shows a spinner until `configs` have been discovered, then offers them
+    /// to pick from, or starts the only one found.
+    fn discover_dev_container_configs(
+        &mut self,
+        configs: Task<Vec<DevContainerConfig>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.mode = Mode::CreateRemoteDevContainer(CreateRemoteDevContainer::new(
+            DevContainerCreationProgress::Discovering,
+            cx,
+        ));
+        self._dev_container_discovery = cx.spawn_in(window, async move |this, cx| {
+            let configs = configs.await;
+            this.update_in(cx, |this, window, cx| {
+                // The user may have gone back while the project was scanned.
+                if matches!(
+                    &this.mode,
+                    Mode::CreateRemoteDevContainer(CreateRemoteDevContainer {
+                        progress: DevContainerCreationProgress::Discovering,
+                        ..
+                    })
+                ) {
+                    this.show_dev_container_configs(configs, window, cx);
+                }
+            })
+            .ok();
+        });
+        cx.notify();
+    }
+
+    fn show_dev_container_configs(
+        &mut self,
+        configs: Vec<DevContainerConfig>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if configs.len() > 1 {
+            let delegate = DevContainerPickerDelegate::new(configs, cx.weak_entity());
+            self.dev_container_picker =
+                Some(cx.new(|cx| Picker::uniform_list(delegate, window, cx).modal(false)));
+
+            let state =
+                CreateRemoteDevContainer::new(DevContainerCreationProgress::SelectingConfig, cx);
+            self.mode = Mode::CreateRemoteDevContainer(state);
+            cx.notify();
+        } else if let Some((app_state, context)) = self
+            .workspace
+            .read_with(cx, |workspace, cx| {
+                let app_state = workspace.app_state().clone();
+                let context = DevContainerContext::from_workspace(workspace, cx)?;
+                Some((app_state, context))
+            })
+            .ok()
+            .flatten()
+        {
+            let config = configs.into_iter().next();
+            self.open_dev_container(config, app_state, context, window, cx);
+            self.view_in_progress_dev_container(window, cx);
+        } else {
+            log::error!("No active project directory for Dev Container");
+            self.mode = Mode::CreateRemoteDevContainer(CreateRemoteDevContainer::new(
+                DevContainerCreationProgress::Error(
+                    "No active project directory for Dev Container".to_string(),
                 ),
+                cx,
+            ));
+            cx.notify();
+        }
+    }
+
+    fn open_dev_container(
+        &self,
+        config: Option<DevContainerConfig>,
+        app_state: Arc<AppState>,
+        mut context: DevContainerContext,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let replace_window = window.window_handle().downcast::<MultiWorkspace>();
+        let app_state = Arc::downgrade(&app_state);
+
+        let (build_log_tx, mut build_log_rx) = mpsc::unbounded();
+        context.build_log = Some(build_log_tx);
+        cx.spawn(async move |this, cx| {
+            while let Some(line) = build_log_rx.next().await {
+                this.update(cx, |this, cx| {
+                    if let Mode::CreateRemoteDevContainer(state) = &mut this.mode {
+                        state.build_log.push(line.into());
+                        cx.notify();
+                    }
+                })
+                .ok();
+            }
+        })
+        .detach();
+
+        cx.spawn_in(window, async move |entity, cx| {
+            let environment = context.environment(cx).await;
+
+            let mut result = start_dev_container_with_config(
+                context.clone(),
+                config.clone(),
+                environment.clone(),
             )
-            .footer(ModalFooter::new().end_slot({
-                let confirm_button = |label: SharedString| {
-                    Button::new("select", label)
-                        .key_binding(KeyBinding::for_action(&menu::Confirm, cx))
-                        .on_click(|_, window, cx| {
-                            window.dispatch_action(menu::Confirm.boxed_clone(), cx)
-                        })
-                };
+            .await;
+            if let Err(DevContainerError::DockerInSeveralWslDistros(daemons)) = &result {
+                let daemons = daemons.clone();
+                if let Some(daemon) = pick_wsl_docker_daemon(&daemons, cx).await {
+                    context.docker_host = DockerHost::Wsl(daemon.distro);
+                    context.use_podman = daemon.use_podman;
+                    result = start_dev_container_with_config(context, config, environment).await;
+                }
+            }
 
-                if is_project_selected {
-                    h_flex()
-                        .gap_1()
-                        .child(
-                            Button::new("open_new_window", "New Window")
-                                .key_binding(KeyBinding::for_action(&menu::SecondaryConfirm, cx))
-                                .on_click(|_, window, cx| {
-                                    window.dispatch_action(menu::SecondaryConfirm.boxed_clone(), cx)
-                                }),
-                        )
-                        .child(confirm_button("Open".into()))
+            let (mut dev_container_connection, starting_dir) = match result {
+                Ok((c, s)) => (c, s),
+                Err(e) => {
+                    log::error!("Failed to start dev container: {:?}", e);
+                    cx.prompt(
+                        gpui::PromptLevel::Critical,
+                        "Failed to start Dev Container. See logs for details",
+                        Some(&format!("{e}")),
+                        &["Ok"],
+                    )
+                    .await
+                    .ok();
+                    entity
+                        .update_in(cx, |remote_server_projects, window, cx| {
+                            remote_server_projects.allow_dismissal = true;
+                            let build_log = match &mut remote_server_projects.mode {
+                                Mode::CreateRemoteDevContainer(state) => {
+                                    std::mem::take(&mut state.build_log)
+                                }
+                                _ => Vec::new(),
+                            };
+                            let mut state = CreateRemoteDevContainer::new(
+                                DevContainerCreationProgress::Error(format!("{e}")),
+                                cx,
+                            );
+                            state.build_log = build_log;
+                            remote_server_projects.mode = Mode::CreateRemoteDevContainer(state);
+                            remote_server_projects.focus_handle(cx).focus(window, cx);
+                        })
+                        .ok();
+                    return;
+                }
+            };
+            cx.update(|_, cx| {
+                apply_saved_dev_container_args(&mut dev_container_connection, cx);
+                ExtensionStore::global(cx).update(cx, |this, cx| {
+                    for extension in &dev_container_connection.extension_ids {
+                        log::info!("Installing extension {extension} from devcontainer");
+                        this.install_latest_extension(Arc::from(extension.clone()), cx);
+                    }
+                })
+            })
+            .log_err();
+
+            entity
+                .update(cx, |this, cx| {
+                    this.allow_dismissal = true;
+                    cx.emit(DismissEvent);
+                })
+                .log_err();
+
+            let Some(app_state) = app_state.upgrade() else {
+                return;
+            };
+            let result = open_remote_project(
+                Connection::DevContainer(dev_container_connection).into(),
+                vec![starting_dir].into_iter().map(PathBuf::from).collect(),
+                app_state,
+                OpenOptions {
+                    requesting_window: replace_window,
+                    ..OpenOptions::default()
+                },
+                cx,
+            )
+            .await;
+            if let Err(e) = result {
+                log::error!("Failed to connect: {e:#}");
+                cx.prompt(
+                    gpui::PromptLevel::Critical,
+                    "Failed to connect",
+                    Some(&e.to_string()),
+                    &["Ok"],
+                )
+                .await
+                .ok();
+            }
+        })
+        .detach();
+    }
+
+    fn render_create_dev_container(
+        &self,
+        state: &CreateRemoteDevContainer,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        match &state.progress {
+            DevContainerCreationProgress::Error(message) => {
+                let view = Navigable::new(
+                    div()
+                        .child(
+                            div().track_focus(&self.focus_handle(cx)).size_full().child(
+                                v_flex().py_1().child(
+                                    ListItem::new("Error")
+                                        .inset(true)
+                                        .selectable(false)
+                                        .spacing(ui::ListItemSpacing::Sparse)
+                                        .start_slot(
+                                            Icon::new(IconName::XCircle).color(Color::Error),
+                                        )
+                                        .child(Label::new("Error Creating Dev Container:"))
+                                        .child(Label::new(message).buffer_font(cx)),
+                                ),
+                            ),
+                        )
+                        .child(render_build_log(&state.build_log, cx))
+                        .child(ListSeparator)
+                        .child(
+                            div()
+                                .id("devcontainer-see-log")
+                                .track_focus(&state.view_logs_entry.focus_handle)
+                                .on_action(cx.listener(|_, _: &menu::Confirm, window, cx| {
+                                    window.dispatch_action(Box::new(OpenLog), cx);
+                                    cx.emit(DismissEvent);
+                                    cx.notify();
+                                }))
+                                .child(
+                                    ListItem::new("li-devcontainer-see-log")
+                                        .toggle_state(
+                                            state
+                                                .view_logs_entry
+                                                .focus_handle
+                                                .contains_focused(window, cx),
+                                        )
+                                        .inset(true)
+                                        .spacing(ui::ListItemSpacing::Sparse)
+                                        .start_slot(Icon::new(IconName::File).color(Color::Muted))
+                                        .child(Label::new("Open Zed Log"))
+                                        .on_click(cx.listener(|_, _, window, cx| {
+                                            window.dispatch_action(Box::new(OpenLog), cx);
+                                            cx.emit(DismissEvent);
+                                            cx.notify();
+                                        })),
+                                ),
+                        )
+                        .child(
+                            div()
+                                .id("devcontainer-go-back")
+                                .track_focus(&state.back_entry.focus_handle)
+                                .on_action(cx.listener(|this, _: &menu::Confirm, window, cx| {
+                                    this.cancel(&menu::Cancel, window, cx);
+                                    cx.notify();
+                                }))
+                                .child(
+                                    ListItem::new("li-devcontainer-go-back")
+                                        .toggle_state(
+                                            state
+                                                .back_entry
+                                                .focus_handle
+                                                .contains_focused(window, cx),
+                                        )
+                                        .inset(true)
+                                        .spacing(ui::ListItemSpacing::Sparse)
+                                        .start_slot(Icon::new(IconName::Exit).color(Color::Muted))
+                                        .child(Label::new("Exit"))
+                                        .on_click(cx.listener(|this, _, window, cx| {
+                                            this.cancel(&menu::Cancel, window, cx);
+                                            cx.notify();
+                                        })),
+                                ),
+                        )
+                        .into_any_element(),
+                )
+                .entry(state.view_logs_entry.clone())
+                .entry(state.back_entry.clone());
+                view.render(window, cx).into_any_element()
+            }
+            DevContainerCreationProgress::SelectingConfig => {
+                self.render_config_selection(window, cx).into_any_element()
+            }
+            DevContainerCreationProgress::Discovering => self
+                .render_dev_container_progress(
+                    "Looking for Dev Container Configurations",
+                    &state.build_log,
+                    window,
+                    cx,
+                )
+                .into_any_element(),
+            DevContainerCreationProgress::Creating => self
+                .render_dev_container_progress(
+                    "Creating Dev Container",
+                    &state.build_log,
+                    window,
+                    cx,
+                )
+                .into_any_element(),
+        }
+    }
+
+    fn render_dev_container_progress(
+        &self,
+        label: &'static str,
+        build_log: &[SharedString],
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        self.focus_handle(cx).focus(window, cx);
+        div().track_focus(&self.focus_handle(cx)).size_full().child(
+            v_flex()
+                .pb_1()
+                .child(
+                    ModalHeader::new()
+                        .child(Headline::new("Dev Containers").size(HeadlineSize::XSmall)),
+                )
+                .child(ListSeparator)
+                .child(
+                    ListItem::new("dev-container-progress")
+                        .inset(true)
+                        .spacing(ui::ListItemSpacing::Sparse)
+                        .disabled(true)
+                        .start_slot(
+                            Icon::new(IconName::ArrowCircle)
+                                .color(Color::Muted)
+                                .with_rotate_animation(2),
+                        )
+                        .child(
+                            h_flex()
+                                .opacity(0.6)
+                                .gap_1()
+                                .child(Label::new(label))
+                                .child(LoadingLabel::new("")),
+                        ),
+                )
+                .child(render_build_log(build_log, cx)),
+        )
+    }
+
+    fn render_config_selection(
+        &self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let Some(picker) = &self.dev_container_picker else {
+            return div().into_any_element();
+        };
+
+        let content = v_flex().pb_1().child(picker.clone().into_any_element());
+
+        picker.focus_handle(cx).focus(window, cx);
+
+        content.into_any_element()
+    }
+
+    fn render_create_remote_server(
+        &self,
+        state: &CreateRemoteServer,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let ssh_prompt = state.ssh_prompt.clone();
+        let is_busy = state._creating.is_some();
+        let current_input = get_text(&state.address_editor, cx);
+        let connection_test = state
+            .connection_test
+            .as_ref()
+            .filter(|test| test.input == current_input)
+            .map(|test| test.result);
+
+        state.address_editor.update(cx, |editor, cx| {
+            if editor.text(cx).is_empty() {
+                editor.set_placeholder_text("ssh user@example -p 2222", window, cx);
+            }
+        });
+
+        let theme = cx.theme();
+
+        v_flex()
+            .track_focus(&self.focus_handle(cx))
+            .id("create-remote-server")
+            .overflow_hidden()
+            .size_full()
+            .flex_1()
+            .child(
+                div()
+                    .p_2()
+                    .border_b_1()
+                    .border_color(theme.colors().border_variant)
+                    .child(state.address_editor.clone()),
+            )
+            .child(
+                h_flex()
+                    .bg(theme.colors().editor_background)
+                    .rounded_b_sm()
+                    .w_full()
+                    .map(|this| {
+                        if let Some(ssh_prompt) = ssh_prompt {
+                            this.child(h_flex().w_full().child(ssh_prompt))
+                        } else if let Some(address_error) = &state.errors.address {
+                            this.child(
+                                h_flex().p_2().w_full().gap_2().child(
+                                    Label::new(address_error.clone())
+                                        .size(LabelSize::Small)
+                                        .color(Color::Error),
+                                ),
+                            )
+                        } else {
+                            this.child(
+                                h_flex()
+                                    .p_2()
+                                    .w_full()
+                                    .gap_1()
+                                    .child(
+                                        Label::new(if state.replacing.is_some() {
+                                            "Edit the command, then connect to update the saved server."
+                                        } else {
+                                            "Enter the command you use to SSH into this server."
+                                        })
+                                        .color(Color::Muted)
+                                        .size(LabelSize::Small),
+                                    )
+                                    .child(
+                                        Button::new("learn-more", "Learn More")
+                                            .label_size(LabelSize::Small)
+                                            .end_icon(
+                                                Icon::new(IconName::ArrowUpRight)
+                                                    .size(IconSize::XSmall),
+                                            )
+                                            .on_click(|_, _, cx| {
+                                                cx.open_url(
+                                                    "https://zed.dev/docs/remote-development",
+                                                );
+                                            }),
+                                    ),
+                            )
+                        }
+                    }),
+            )
+            .when(state.ssh_prompt.is_none(), |this| {
+                this.child(
+                    v_flex()
+                        .p_2()
+                        .gap_2()
+                        .border_b_1()
+                        .border_color(theme.colors().border_variant)
+                        .child(
+                            Label::new("Advanced")
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                        .child(render_keepalive_fields(
+                            &state.keepalive,
+                            &state.errors.keepalive,
+                            cx,
+                        ))
+                        .child(
+                            v_flex()
+                                .gap_0p5()
+                                .child(
+                                    Checkbox::new(
+                                        "upload-binary-over-ssh",
+                                        state.upload_binary_over_ssh.into(),
+                                    )
+                                    .label("Upload server binary over SSH")
+                                    .label_size(LabelSize::Small)
+                                    .disabled(is_busy)
+                                    .on_click(cx.listener(
+                                        |this, toggle_state: &ToggleState, _, cx| {
+                                            if let Mode::CreateRemoteServer(state) = &mut this.mode
+                                            {
+                                                state.upload_binary_over_ssh =
+                                                    toggle_state.selected();
+                                            }
+                                            cx.notify();
+                                        },
+                                    )),
+                                )
+                                .child(
+                                    Label::new(UPLOAD_BINARY_OVER_SSH_DESCRIPTION)
+                                        .size(LabelSize::XSmall)
+                                        .color(Color::Muted),
+                                ),
+                        )
+                        .child(
+                            v_flex()
+                                .gap_0p5()
+                                .child(
+                                    Checkbox::new("forward-agent", state.forward_agent.into())
+                                        .label("Forward ssh-agent")
+                                        .label_size(LabelSize::Small)
+                                        .disabled(is_busy)
+                                        .on_click(cx.listener(
+                                            |this, toggle_state: &ToggleState, _, cx| {
+                                                if let Mode::CreateRemoteServer(state) =
+                                                    &mut this.mode
+                                                {
+                                                    state.forward_agent = toggle_state.selected();
+                                                }
+                                                cx.notify();
+                                            },
+                                        )),
+                                )
+                                .child(
+                                    Label::new(FORWARD_AGENT_DESCRIPTION)
+                                        .size(LabelSize::XSmall)
+                                        .color(Color::Muted),
+                                ),
+                        ),
+                )
+                .child(
+                    h_flex()
+                        .p_2()
+                        .w_full()
+                        .gap_2()
+                        .child(div().flex_1().children(connection_test.map(|result| {
+                            match result {
+                                Ok(()) => Label::new("Connection succeeded.")
+                                    .size(LabelSize::Small)
+                                    .color(Color::Success),
+                                Err(failure) => Label::new(failure.message())
+                                    .size(LabelSize::Small)
+                                    .color(Color::Error),
+                            }
+                        })))
+                        .child(
+                            h_flex()
+                                .gap_1()
+                                .child(
+                                    Button::new("test-connection", "Test Connection")
+                                        .label_size(LabelSize::Small)
+                                        .disabled(is_busy || current_input.is_empty())
+                                        .on_click(cx.listener(|this, _, window, cx| {
+                                            this.test_ssh_connection(window, cx);
+                                        })),
+                                )
+                                .when(matches!(connection_test, Some(Ok(()))), |this| {
+                                    this.child(
+                                        Button::new("save-and-connect", "Save & Connect")
+                                            .label_size(LabelSize::Small)
+                                            .disabled(is_busy)
+                                            .on_click(cx.listener(|this, _, window, cx| {
+                                                if let Mode::CreateRemoteServer(state) = &this.mode
+                                                {
+                                                    this.create_ssh_server(
+                                                        state.address_editor.clone(),
+                                                        state.keepalive.clone(),
+                                                        state.upload_binary_over_ssh,
+                                                        state.forward_agent,
+                                                        state.replacing,
+                                                        window,
+                                                        cx,
+                                                    );
+                                                }
+                                            })),
+                                    )
+                                }),
+                        ),
+                )
+            })
+    }
+
+    #[cfg(target_os = "windows")]
+    fn render_add_wsl_distro(
+        &self,
+        state: &AddWslDistro,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let connection_prompt = state.connection_prompt.clone();
+
+        state.picker.update(cx, |picker, cx| {
+            picker.focus_handle(cx).focus(window, cx);
+        });
+
+        v_flex()
+            .id("add-wsl-distro")
+            .overflow_hidden()
+            .size_full()
+            .flex_1()
+            .map(|this| {
+                if let Some(connection_prompt) = connection_prompt {
+                    this.child(connection_prompt)
+                } else {
+                    this.child(state.picker.clone())
+                }
+            })
+    }
+
+    fn render_view_options(
+        &mut self,
+        options: ViewServerOptionsState,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let last_entry = options.entries().last().unwrap();
+
+        let mut view = Navigable::new(
+            div()
+                .track_focus(&self.focus_handle(cx))
+                .size_full()
+                .child(match &options {
+                    ViewServerOptionsState::Ssh {
+                        connection,
+                        server_index,
+                        ..
+                    } => SshConnectionHeader {
+                        connection_string: connection.host.to_string().into(),
+                        paths: Default::default(),
+                        nickname: connection.nickname.clone().map(|s| s.into()),
+                        platform: RemoteSettings::get_global(cx)
+                            .ssh_connections()
+                            .nth(server_index.0)
+                            .and_then(|connection| {
+                                let platform = ssh_platform_label(&connection);
+                                match ssh_server_version_label(&connection) {
+                                    Some(version) => Some(match platform {
+                                        Some(platform) => format!("{platform} · {version}").into(),
+                                        None => version,
+                                    }),
+                                    None => platform,
+                                }
+                            }),
+                        is_wsl: false,
+                        is_devcontainer: false,
+                    }
+                    .render(window, cx)
+                    .into_any_element(),
+                    ViewServerOptionsState::Wsl { connection, .. } => SshConnectionHeader {
+                        connection_string: connection.distro_name.clone().into(),
+                        paths: Default::default(),
+                        nickname: None,
+                        platform: None,
+                        is_wsl: true,
+                        is_devcontainer: false,
+                    }
+                    .render(window, cx)
+                    .into_any_element(),
+                })
+                .child(
+                    v_flex()
+                        .pb_1()
+                        .child(ListSeparator)
+                        .map(|this| match &options {
+                            ViewServerOptionsState::Ssh {
+                                connection,
+                                entries,
+                                server_index,
+                            } => this.child(self.render_edit_ssh(
+                                connection,
+                                *server_index,
+                                entries,
+                                window,
+                                cx,
+                            )),
+                            ViewServerOptionsState::Wsl {
+                                connection,
+                                entries,
+                                server_index,
+                            } => this.child(self.render_edit_wsl(
+                                connection,
+                                *server_index,
+                                entries,
+                                window,
+                                cx,
+                            )),
+                        })
+                        .child(ListSeparator)
+                        .child({
+                            div()
+                                .id("ssh-options-copy-server-address")
+                                .track_focus(&last_entry.focus_handle)
+                                .on_action(cx.listener(|this, _: &menu::Confirm, window, cx| {
+                                    this.mode = this.default_mode(cx);
+                                    cx.focus_self(window);
+                                    cx.notify();
+                                }))
+                                .child(
+                                    ListItem::new("go-back")
+                                        .toggle_state(
+                                            last_entry.focus_handle.contains_focused(window, cx),
+                                        )
+                                        .inset(true)
+                                        .spacing(ui::ListItemSpacing::Sparse)
+                                        .start_slot(
+                                            Icon::new(IconName::ArrowLeft).color(Color::Muted),
+                                        )
+                                        .child(Label::new("Go Back"))
+                                        .on_click(cx.listener(|this, _, window, cx| {
+                                            this.mode = this.default_mode(cx);
+                                            cx.focus_self(window);
+                                            cx.notify()
+                                        })),
+                                )
+                        }),
+                )
+                .into_any_element(),
+        );
+
+        // The entry for retrying port forwards is only shown when one failed,
+        // and those for the original command when the server has one.
+        let mut hidden_entries = Vec::new();
+        if let ViewServerOptionsState::Ssh {
+            connection,
+            server_index,
+            entries,
+        } = &options
+        {
+            if original_command(*server_index, cx).is_none() {
+                hidden_entries.extend(entries[8..10].iter().map(|entry| &entry.focus_handle));
+            }
+            if !has_failed_port_forwards(connection, cx) {
+                hidden_entries.push(&entries[11].focus_handle);
+            }
+        }
+        for entry in options.entries() {
+            if !hidden_entries.contains(&&entry.focus_handle) {
+                view = view.entry(entry.clone());
+            }
+        }
+
+        view.render(window, cx).into_any_element()
+    }
+
+    fn render_edit_wsl(
+        &self,
+        connection: &WslConnectionOptions,
+        index: WslServerIndex,
+        entries: &[NavigableEntry],
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let distro_name = SharedString::new(connection.distro_name.clone());
+
+        v_flex()
+            .child(self.render_edit_tag_option(index.into(), &entries[0], window, cx))
+            .child(self.render_edit_env_option(index.into(), &entries[1], window, cx))
+            .child({
+                fn remove_wsl_distro(
+                    remote_servers: Entity<RemoteServerProjects>,
+                    index: WslServerIndex,
+                    distro_name: SharedString,
+                    window: &mut Window,
+                    cx: &mut App,
+                ) {
+                    let prompt_message = format!("Remove WSL distro `{}`?", distro_name);
+
+                    let confirmation = window.prompt(
+                        PromptLevel::Warning,
+                        &prompt_message,
+                        None,
+                        &["Yes, remove it", "No, keep it"],
+                        cx,
+                    );
+
+                    cx.spawn(async move |cx| {
+                        if confirmation.await.ok() == Some(0) {
+                            remote_servers.update(cx, |this, cx| {
+                                this.delete_wsl_distro(index, cx);
+                            });
+                            remote_servers.update(cx, |this, cx| {
+                                this.mode = this.default_mode(cx);
+                                cx.notify();
+                            });
+                        }
+                        anyhow::Ok(())
+                    })
+                    .detach_and_log_err(cx);
+                }
+                div()
+                    .id("wsl-options-remove-distro")
+                    .track_focus(&entries[2].focus_handle)
+                    .on_action(cx.listener({
+                        let distro_name = distro_name.clone();
+                        move |_, _: &menu::Confirm, window, cx| {
+                            remove_wsl_distro(cx.entity(), index, distro_name.clone(), window, cx);
+                            cx.focus_self(window);
+                        }
+                    }))
+                    .child(
+                        ListItem::new("remove-distro")
+                            .toggle_state(entries[2].focus_handle.contains_focused(window, cx))
+                            .inset(true)
+                            .spacing(ui::ListItemSpacing::Sparse)
+                            .start_slot(Icon::new(IconName::Trash).color(Color::Error))
+                            .child(Label::new("Remove Distro").color(Color::Error))
+                            .on_click(cx.listener(move |_, _, window, cx| {
+                                remove_wsl_distro(
+                                    cx.entity(),
+                                    index,
+                                    distro_name.clone(),
+                                    window,
+                                    cx,
+                                );
+                                cx.focus_self(window);
+                            })),
+                    )
+            })
+    }
+
+    fn render_edit_tag_option(
+        &self,
+        index: ServerIndex,
+        entry: &NavigableEntry,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let settings = RemoteSettings::get_global(cx);
+        let has_tag = match index {
+            ServerIndex::Ssh(index) => settings
+                .ssh_connections()
+                .nth(index.0)
+                .is_some_and(|connection| connection.tag.is_some()),
+            ServerIndex::Wsl(index) => settings
+                .wsl_connections()
+                .nth(index.0)
+                .is_some_and(|connection| connection.tag.is_some()),
+        };
+        let label = if has_tag { "Edit Tag" } else { "Add Tag" };
+        div()
+            .id("server-options-edit-tag")
+            .track_focus(&entry.focus_handle)
+            .on_action(cx.listener(move |this, _: &menu::Confirm, window, cx| {
+                this.mode = Mode::EditTag(EditTagState::new(TagTarget::Server(index), window, cx));
+                cx.notify();
+            }))
+            .child(
+                ListItem::new("edit-tag")
+                    .toggle_state(entry.focus_handle.contains_focused(window, cx))
+                    .inset(true)
+                    .spacing(ui::ListItemSpacing::Sparse)
+                    .start_slot(Icon::new(IconName::Hash).color(Color::Muted))
+                    .child(Label::new(label))
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.mode =
+                            Mode::EditTag(EditTagState::new(TagTarget::Server(index), window, cx));
+                        cx.notify();
+                    })),
+            )
+    }
+
+    fn render_edit_env_option(
+        &self,
+        index: ServerIndex,
+        entry: &NavigableEntry,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let description =
+            effective_env_description(index, cx).unwrap_or_else(|| "No variables set".to_string());
+        div()
+            .id("server-options-edit-env")
+            .track_focus(&entry.focus_handle)
+            .on_action(cx.listener(move |this, _: &menu::Confirm, window, cx| {
+                this.mode = Mode::EditEnv(EditEnvState::new(index, window, cx));
+                cx.notify();
+            }))
+            .child(
+                ListItem::new("edit-env")
+                    .toggle_state(entry.focus_handle.contains_focused(window, cx))
+                    .inset(true)
+                    .spacing(ui::ListItemSpacing::Sparse)
+                    .start_slot(Icon::new(IconName::Settings).color(Color::Muted))
+                    .child(
+                        v_flex().child(Label::new("Edit Environment")).child(
+                            Label::new(description)
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        ),
+                    )
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.mode = Mode::EditEnv(EditEnvState::new(index, window, cx));
+                        cx.notify();
+                    })),
+            )
+    }
+
+    fn render_edit_shell_option(
+        &self,
+        index: SshServerIndex,
+        entry: &NavigableEntry,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let description = RemoteSettings::get_global(cx)
+            .ssh_connections()
+            .nth(index.0)
+            .map(|connection| {
+                effective_shell_description(&connection, SshSettings::get_global(cx))
+            });
+        div()
+            .id("ssh-options-edit-shell")
+            .track_focus(&entry.focus_handle)
+            .on_action(cx.listener(move |this, _: &menu::Confirm, window, cx| {
+                this.mode = Mode::EditShell(EditShellState::new(index, window, cx));
+                cx.notify();
+            }))
+            .child(
+                ListItem::new("edit-shell")
+                    .toggle_state(entry.focus_handle.contains_focused(window, cx))
+                    .inset(true)
+                    .spacing(ui::ListItemSpacing::Sparse)
+                    .start_slot(Icon::new(IconName::Terminal).color(Color::Muted))
+                    .child(
+                        v_flex()
+                            .child(Label::new("Edit Shell"))
+                            .children(description.map(|description| {
+                                Label::new(description)
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted)
+                            })),
+                    )
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.mode = Mode::EditShell(EditShellState::new(index, window, cx));
+                        cx.notify();
+                    })),
+            )
+    }
+
+    fn render_upload_binary_over_ssh_option(
+        &self,
+        connection: &SshConnectionOptions,
+        index: SshServerIndex,
+        entry: &NavigableEntry,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let upload_binary_over_ssh = connection.upload_binary_over_ssh;
+        div()
+            .id("ssh-options-upload-binary-over-ssh")
+            .track_focus(&entry.focus_handle)
+            .on_action(cx.listener(move |this, _: &menu::Confirm, _, cx| {
+                this.set_upload_binary_over_ssh(index, !upload_binary_over_ssh, cx);
+            }))
+            .child(
+                ListItem::new("upload-binary-over-ssh")
+                    .toggle_state(entry.focus_handle.contains_focused(window, cx))
+                    .inset(true)
+                    .spacing(ui::ListItemSpacing::Sparse)
+                    .start_slot(Icon::new(IconName::Download).color(Color::Muted))
+                    .child(
+                        v_flex()
+                            .child(Label::new("Upload Server Binary Over SSH"))
+                            .child(
+                                Label::new(UPLOAD_BINARY_OVER_SSH_DESCRIPTION)
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            ),
+                    )
+                    .end_slot(Switch::new(
+                        "upload-binary-over-ssh-switch",
+                        upload_binary_over_ssh.into(),
+                    ))
+                    .on_click(cx.listener(move |this, _, _, cx| {
+                        this.set_upload_binary_over_ssh(index, !upload_binary_over_ssh, cx);
+                    })),
+            )
+    }
+
+    fn render_forward_agent_option(
+        &self,
+        connection: &SshConnectionOptions,
+        index: SshServerIndex,
+        entry: &NavigableEntry,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let forward_agent = forwards_agent(connection.args.as_deref().unwrap_or_default());
+        div()
+            .id("ssh-options-forward-agent")
+            .track_focus(&entry.focus_handle)
+            .on_action(cx.listener(move |this, _: &menu::Confirm, _, cx| {
+                this.set_forward_agent(index, !forward_agent, cx);
+            }))
+            .child(
+                ListItem::new("forward-agent")
+                    .toggle_state(entry.focus_handle.contains_focused(window, cx))
+                    .inset(true)
+                    .spacing(ui::ListItemSpacing::Sparse)
+                    .start_slot(Icon::new(IconName::LockOutlined).color(Color::Muted))
+                    .child(
+                        v_flex().child(Label::new("Forward ssh-agent")).child(
+                            Label::new(FORWARD_AGENT_DESCRIPTION)
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        ),
+                    )
+                    .end_slot(Switch::new("forward-agent-switch", forward_agent.into()))
+                    .on_click(cx.listener(move |this, _, _, cx| {
+                        this.set_forward_agent(index, !forward_agent, cx);
+                    })),
+            )
+    }
+
+    fn render_edit_ssh(
+        &self,
+        connection: &SshConnectionOptions,
+        index: SshServerIndex,
+        entries: &[NavigableEntry],
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let connection_string = SharedString::new(connection.host.to_string());
+
+        v_flex()
+            .child({
+                let label = if connection.nickname.is_some() {
+                    "Edit Nickname"
+                } else {
+                    "Add Nickname to Server"
+                };
+                div()
+                    .id("ssh-options-add-nickname")
+                    .track_focus(&entries[0].focus_handle)
+                    .on_action(cx.listener(move |this, _: &menu::Confirm, window, cx| {
+                        this.mode = Mode::EditNickname(EditNicknameState::new(index, window, cx));
+                        cx.notify();
+                    }))
+                    .child(
+                        ListItem::new("add-nickname")
+                            .toggle_state(entries[0].focus_handle.contains_focused(window, cx))
+                            .inset(true)
+                            .spacing(ui::ListItemSpacing::Sparse)
+                            .start_slot(Icon::new(IconName::Pencil).color(Color::Muted))
+                            .child(Label::new(label))
+                            .on_click(cx.listener(move |this, _, window, cx| {
+                                this.mode =
+                                    Mode::EditNickname(EditNicknameState::new(index, window, cx));
+                                cx.notify();
+                            })),
+                    )
+            })
+            .child(
+                div()
+                    .id("ssh-options-edit-keepalive")
+                    .track_focus(&entries[1].focus_handle)
+                    .on_action(cx.listener(move |this, _: &menu::Confirm, window, cx| {
+                        this.mode = Mode::EditKeepalive(EditKeepaliveState::new(index, window, cx));
+                        cx.notify();
+                    }))
+                    .child(
+                        ListItem::new("edit-keepalive")
+                            .toggle_state(entries[1].focus_handle.contains_focused(window, cx))
+                            .inset(true)
+                            .spacing(ui::ListItemSpacing::Sparse)
+                            .start_slot(Icon::new(IconName::Clock).color(Color::Muted))
+                            .child(Label::new("Edit Keepalive"))
+                            .on_click(cx.listener(move |this, _, window, cx| {
+                                this.mode =
+                                    Mode::EditKeepalive(EditKeepaliveState::new(index, window, cx));
+                                cx.notify();
+                            })),
+                    ),
+            )
+            .child(self.render_edit_shell_option(index, &entries[2], window, cx))
+            .child(self.render_edit_env_option(index.into(), &entries[3], window, cx))
+            .child(self.render_upload_binary_over_ssh_option(
+                connection,
+                index,
+                &entries[4],
+                window,
+                cx,
+            ))
+            .child(self.render_forward_agent_option(connection, index, &entries[5], window, cx))
+            .child(self.render_edit_tag_option(index.into(), &entries[6], window, cx))
+            .child({
+                let server_address = SharedString::from(connection.connection_string());
+                let workspace = self.workspace.clone();
+                div()
+                    .id("ssh-options-copy-server-address")
+                    .track_focus(&entries[7].focus_handle)
+                    .on_action({
+                        let server_address = server_address.clone();
+                        let workspace = self.workspace.clone();
+                        move |_: &menu::Confirm, _, cx| {
+                            copy_server_address(&workspace, server_address.clone(), cx);
+                        }
+                    })
+                    .child(
+                        ListItem::new("copy-server-address")
+                            .toggle_state(entries[7].focus_handle.contains_focused(window, cx))
+                            .inset(true)
+                            .spacing(ui::ListItemSpacing::Sparse)
+                            .start_slot(Icon::new(IconName::Copy).color(Color::Muted))
+                            .child(Label::new("Copy Server Address"))
+                            .end_slot(
+                                h_flex()
+                                    .gap_1()
+                                    .child(Label::new(server_address.clone()).color(Color::Muted))
+                                    .child(KeyBinding::for_action_in(
+                                        &CopyServerAddress,
+                                        &self.focus_handle,
+                                        cx,
+                                    )),
+                            )
+                            .show_end_slot_on_hover()
+                            .on_click(move |_, _, cx| {
+                                copy_server_address(&workspace, server_address.clone(), cx);
+                            }),
+                    )
+            })
+            .children(self.render_original_command_options(index, &entries[8..10], window, cx))
+            .child({
+                fn remove_ssh_server(
+                    remote_servers: Entity<RemoteServerProjects>,
+                    index: SshServerIndex,
+                    connection_string: SharedString,
+                    window: &mut Window,
+                    cx: &mut App,
+                ) {
+                    let prompt_message = format!("Remove server `{}`?", connection_string);
+
+                    let confirmation = window.prompt(
+                        PromptLevel::Warning,
+                        &prompt_message,
+                        None,
+                        &["Yes, remove it", "No, keep it"],
+                        cx,
+                    );
+
+                    cx.spawn(async move |cx| {
+                        if confirmation.await.ok() == Some(0) {
+                            remote_servers.update(cx, |this, cx| {
+                                this.delete_ssh_server(index, cx);
+                            });
+                            remote_servers.update(cx, |this, cx| {
+                                this.mode = this.default_mode(cx);
+                                cx.notify();
+                            });
+                        }
+                        anyhow::Ok(())
+                    })
+                    .detach_and_log_err(cx);
+                }
+                div()
+                    .id("ssh-options-copy-server-address")
+                    .track_focus(&entries[10].focus_handle)
+                    .on_action(cx.listener({
+                        let connection_string = connection_string.clone();
+                        move |_, _: &menu::Confirm, window, cx| {
+                            remove_ssh_server(
+                                cx.entity(),
+                                index,
+                                connection_string.clone(),
+                                window,
+                                cx,
+                            );
+                            cx.focus_self(window);
+                        }
+                    }))
+                    .child(
+                        ListItem::new("remove-server")
+                            .toggle_state(entries[10].focus_handle.contains_focused(window, cx))
+                            .inset(true)
+                            .spacing(ui::ListItemSpacing::Sparse)
+                            .start_slot(Icon::new(IconName::Trash).color(Color::Error))
+                            .child(Label::new("Remove Server").color(Color::Error))
+                            .on_click(cx.listener(move |_, _, window, cx| {
+                                remove_ssh_server(
+                                    cx.entity(),
+                                    index,
+                                    connection_string.clone(),
+                                    window,
+                                    cx,
+                                );
+                                cx.focus_self(window);
+                            })),
+                    )
+            })
+            .children(self.render_port_forwards(connection, &entries[11], window, cx))
+    }
+
+    /// Entries for copying the command the server was added with, and for
+    /// editing it in the create-server form to connect with it again. Servers
+    /// added without a command have neither.
+    fn render_original_command_options(
+        &self,
+        index: SshServerIndex,
+        entries: &[NavigableEntry],
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<impl IntoElement> {
+        let command = original_command(index, cx)?;
+        let [copy_entry, edit_entry] = entries else {
+            return None;
+        };
+        let workspace = self.workspace.clone();
+        Some(
+            v_flex()
+                .child(
+                    div()
+                        .id("ssh-options-copy-original-command")
+                        .track_focus(&copy_entry.focus_handle)
+                        .on_action({
+                            let command = command.clone();
+                            let workspace = self.workspace.clone();
+                            move |_: &menu::Confirm, _, cx| {
+                                copy_original_command(&workspace, command.clone(), cx);
+                            }
+                        })
+                        .child(
+                            ListItem::new("copy-original-command")
+                                .toggle_state(copy_entry.focus_handle.contains_focused(window, cx))
+                                .inset(true)
+                                .spacing(ui::ListItemSpacing::Sparse)
+                                .start_slot(Icon::new(IconName::Copy).color(Color::Muted))
+                                .child(Label::new("Copy Original Command"))
+                                .end_slot(
+                                    Label::new(command.clone()).color(Color::Muted).truncate(),
+                                )
+                                .show_end_slot_on_hover()
+                                .on_click(move |_, _, cx| {
+                                    copy_original_command(&workspace, command.clone(), cx);
+                                }),
+                        ),
+                )
+                .child(
+                    div()
+                        .id("ssh-options-edit-original-command")
+                        .track_focus(&edit_entry.focus_handle)
+                        .on_action(cx.listener(move |this, _: &menu::Confirm, window, cx| {
+                            this.edit_original_command(index, window, cx);
+                        }))
+                        .child(
+                            ListItem::new("edit-original-command")
+                                .toggle_state(edit_entry.focus_handle.contains_focused(window, cx))
+                                .inset(true)
+                                .spacing(ui::ListItemSpacing::Sparse)
+                                .start_slot(Icon::new(IconName::Terminal).color(Color::Muted))
+                                .child(Label::new("Re-run Original Command"))
+                                .on_click(cx.listener(move |this, _, window, cx| {
+                                    this.edit_original_command(index, window, cx);
+                                })),
+                        ),
+                ),
+        )
+    }
+
+    /// Opens the create-server form with the command the server was added
+    /// with, to edit before connecting. Connecting updates the saved server.
+    fn edit_original_command(
+        &mut self,
+        index: SshServerIndex,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.mode =
+            Mode::CreateRemoteServer(CreateRemoteServer::for_original_command(index, window, cx));
+        cx.notify();
+    }
+
+    /// The status of each of the server's port forwards while it's connected,
+    /// with a way to retry the ones that failed.
+    fn render_port_forwards(
+        &self,
+        connection: &SshConnectionOptions,
+        retry_entry: &NavigableEntry,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<impl IntoElement> {
+        let remote_connection =
+            remote::active_connection(&RemoteConnectionOptions::Ssh(connection.clone()), cx)?;
+        let port_forwards = remote_connection.port_forwards();
+        if port_forwards.is_empty() {
+            return None;
+        }
+        let any_failed = port_forwards.iter().any(PortForward::is_failed);
+
+        Some(
+            v_flex()
+                .child(ListSeparator)
+                .children(port_forwards.into_iter().enumerate().map(|(ix, forward)| {
+                    let health = RemoteHealth::from(&forward.status);
+                    let status = match &forward.status {
+                        PortForwardStatus::Active => "Listening".into(),
+                        PortForwardStatus::Failed(reason) => {
+                            SharedString::from(format!("Failed: {reason}"))
+                        }
+                        PortForwardStatus::Unverified => "Not verified".into(),
+                    };
+                    ListItem::new(("port-forward", ix))
+                        .inset(true)
+                        .spacing(ui::ListItemSpacing::Sparse)
+                        .start_slot(RemoteHealthIndicator::new(
+                            ("port-forward-health", ix),
+                            health,
+                            status.clone(),
+                        ))
+                        .child(Label::new(forward.label()))
+                        .end_slot(
+                            Label::new(status)
+                                .size(LabelSize::Small)
+                                .color(health.color()),
+                        )
+                }))
+                .when(any_failed, |this| {
+                    this.child(
+                        div()
+                            .id("ssh-options-retry-port-forwards")
+                            .track_focus(&retry_entry.focus_handle)
+                            .on_action(cx.listener({
+                                let remote_connection = remote_connection.clone();
+                                move |this, _: &menu::Confirm, _, cx| {
+                                    this.retry_port_forwards(remote_connection.clone(), cx)
+                                }
+                            }))
+                            .child(
+                                ListItem::new("retry-port-forwards")
+                                    .toggle_state(
+                                        retry_entry.focus_handle.contains_focused(window, cx),
+                                    )
+                                    .inset(true)
+                                    .spacing(ui::ListItemSpacing::Sparse)
+                                    .start_slot(Icon::new(IconName::RotateCw).color(Color::Muted))
+                                    .child(Label::new("Retry Forwards"))
+                                    .on_click(cx.listener(move |this, _, _, cx| {
+                                        this.retry_port_forwards(remote_connection.clone(), cx)
+                                    })),
+                            ),
+                    )
+                }),
+        )
+    }
+
+    fn retry_port_forwards(
+        &mut self,
+        remote_connection: Arc<dyn RemoteConnection>,
+        cx: &mut Context<Self>,
+    ) {
+        cx.spawn(async move |this, cx| {
+            remote_connection.retry_port_forwards().await?;
+            this.update(cx, |_, cx| cx.notify())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn render_clone_repository(
+        &self,
+        state: &CloneRepository,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let (connection_string, nickname, is_wsl) = match &state.connection {
+            RemoteConnectionOptions::Ssh(options) => (
+                options.connection_string().into(),
+                options.nickname.clone().map(SharedString::from),
+                false,
+            ),
+            options => (
+                options.display_name().into(),
+                None,
+                matches!(options, RemoteConnectionOptions::Wsl(_)),
+            ),
+        };
+        let is_cloning = state._cloning.is_some();
+        let border_color = cx.theme().colors().border_variant;
+        let output_background = cx.theme().colors().editor_background;
+
+        v_flex()
+            .id("clone-repository")
+            .track_focus(&self.focus_handle(cx))
+            .child(
+                SshConnectionHeader {
+                    connection_string,
+                    paths: Default::default(),
+                    nickname,
+                    platform: None,
+                    is_wsl,
+                    is_devcontainer: false,
+                }
+                .render(window, cx),
+            )
+            .child(
+                v_flex()
+                    .p_2()
+                    .gap_2()
+                    .border_t_1()
+                    .border_color(border_color)
+                    .child(state.url_editor.clone())
+                    .child(state.destination_editor.clone()),
+            )
+            .map(|this| {
+                if let Some(ssh_prompt) = state.ssh_prompt.clone() {
+                    this.child(h_flex().w_full().child(ssh_prompt))
+                } else if let Some(error) = state.error.clone() {
+                    this.child(
+                        h_flex()
+                            .p_2()
+                            .w_full()
+                            .child(Label::new(error).size(LabelSize::Small).color(Color::Error)),
+                    )
+                } else if is_cloning {
+                    this.child(
+                        h_flex()
+                            .p_2()
+                            .gap_1()
+                            .child(
+                                Icon::new(IconName::ArrowCircle)
+                                    .size(IconSize::Small)
+                                    .color(Color::Muted)
+                                    .with_rotate_animation(2),
+                            )
+                            .child(
+                                Label::new("Cloning")
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            ),
+                    )
+                } else {
+                    this
+                }
+            })
+            .when(!state.output.is_empty(), |this| {
+                this.child(
+                    v_flex()
+                        .id("clone-repository-output")
+                        .max_h_40()
+                        .overflow_y_scroll()
+                        .p_2()
+                        .border_t_1()
+                        .border_color(border_color)
+                        .bg(output_background)
+                        .children(state.output.iter().map(|line| {
+                            Label::new(line.clone())
+                                .size(LabelSize::Small)
+                                .buffer_font(cx)
+                        })),
+                )
+            })
+            .child(
+                h_flex().p_2().w_full().justify_end().child(
+                    Button::new("clone-and-open", "Clone & Open")
+                        .label_size(LabelSize::Small)
+                        .disabled(is_cloning)
+                        .on_click(cx.listener(|this, _, window, cx| {
+                            this.clone_repository(window, cx);
+                        })),
+                ),
+            )
+    }
+
+    fn render_edit_nickname(
+        &self,
+        state: &EditNicknameState,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let Some(connection) = RemoteSettings::get_global(cx)
+            .ssh_connections()
+            .nth(state.index.0)
+        else {
+            return v_flex()
+                .id("ssh-edit-nickname")
+                .track_focus(&self.focus_handle(cx));
+        };
+
+        let platform = ssh_platform_label(&connection);
+        let connection_string = connection.host.clone();
+        let nickname = connection.nickname.map(|s| s.into());
+
+        v_flex()
+            .id("ssh-edit-nickname")
+            .track_focus(&self.focus_handle(cx))
+            .child(
+                SshConnectionHeader {
+                    connection_string: connection_string.into(),
+                    paths: Default::default(),
+                    nickname,
+                    platform,
+                    is_wsl: false,
+                    is_devcontainer: false,
+                }
+                .render(window, cx),
+            )
+            .child(
+                h_flex()
+                    .p_2()
+                    .border_t_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(state.editor.clone()),
+            )
+            .children(state.error.clone().map(|error| {
+                h_flex()
+                    .p_2()
+                    .w_full()
+                    .child(Label::new(error).size(LabelSize::Small).color(Color::Error))
+            }))
+    }
+
+    fn render_edit_shell(
+        &self,
+        state: &EditShellState,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let Some(connection) = RemoteSettings::get_global(cx)
+            .ssh_connections()
+            .nth(state.index.0)
+        else {
+            return v_flex()
+                .id("ssh-edit-shell")
+                .track_focus(&self.focus_handle(cx));
+        };
+        let platform = ssh_platform_label(&connection);
+
+        v_flex()
+            .id("ssh-edit-shell")
+            .track_focus(&self.focus_handle(cx))
+            .child(
+                SshConnectionHeader {
+                    connection_string: connection.host.into(),
+                    paths: Default::default(),
+                    nickname: connection.nickname.map(|s| s.into()),
+                    platform,
+                    is_wsl: false,
+                    is_devcontainer: false,
+                }
+                .render(window, cx),
+            )
+            .child(
+                v_flex()
+                    .p_2()
+                    .gap_2()
+                    .border_t_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(state.editor.clone())
+                    .children(
+                        state.error.clone().map(|error| {
+                            Label::new(error).size(LabelSize::Small).color(Color::Error)
+                        }),
+                    )
+                    .child(
+                        Switch::new("login-shell", state.login_shell.into())
+                            .label("Start as a login shell")
+                            .label_size(LabelSize::Small)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                if let Mode::EditShell(state) = &mut this.mode {
+                                    state.login_shell = !state.login_shell;
+                                    cx.notify();
+                                }
+                            })),
+                    ),
+            )
+    }
+
+    fn render_edit_env(
+        &self,
+        state: &EditEnvState,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        v_flex()
+            .id("edit-server-env")
+            .track_focus(&self.focus_handle(cx))
+            .children(saved_server_header(state.index, cx).map(|header| header.render(window, cx)))
+            .child(
+                v_flex()
+                    .p_2()
+                    .gap_1()
+                    .border_t_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .when(saved_server_is_windows(state.index, cx), |this| {
+                        this.child(
+                            Label::new(
+                                "This server runs Windows, where Zed can't set environment variables for the remote server yet.",
+                            )
+                            .size(LabelSize::Small)
+                            .color(Color::Warning),
+                        )
+                    })
+                    .child(self.render_env_rows(&state.rows, state.error.clone(), cx)),
+            )
+    }
+
+    fn render_edit_dev_container_user(
+        &self,
+        state: &EditDevContainerUserState,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let border_color = cx.theme().colors().border_variant;
+        v_flex()
+            .id("edit-dev-container-user")
+            .track_focus(&self.focus_handle(cx))
+            .child(
+                ModalHeader::new()
+                    .child(Headline::new(state.connection.name.clone()).size(HeadlineSize::XSmall)),
+            )
+            .child(
+                v_flex()
+                    .p_2()
+                    .gap_1()
+                    .border_t_1()
+                    .border_color(border_color)
+                    .child(Label::new("User").size(LabelSize::Small))
+                    .child(
+                        div()
+                            .px_1()
+                            .border_1()
+                            .border_color(border_color)
+                            .rounded_sm()
+                            .child(state.user_editor.clone()),
+                    )
+                    .child(
+                        Label::new(
+                            "Extra environment variables, set on top of those from devcontainer.json",
+                        )
+                        .size(LabelSize::Small),
+                    )
+                    .child(self.render_env_rows(&state.env_rows, state.error.clone(), cx))
+                    .when(state._checking.is_some(), |this| {
+                        this.child(
+                            Label::new("Checking that the user exists in the container…")
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                    })
+                    .child(
+                        Label::new("Changes take effect the next time Zed connects.")
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+            )
+    }
+
+    /// The rows of an environment editor, a button for adding one, the
+    /// editor's `error`, and a warning about the variables that look like
+    /// secrets.
+    fn render_env_rows(
+        &self,
+        rows: &[EnvVarRow],
+        error: Option<SharedString>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let secret_names = env_secret_names(rows, cx);
+        let rows = rows.iter().enumerate().map(|(ix, row)| {
+            h_flex()
+                .gap_1()
+                .child(
+                    div()
+                        .w_1_3()
+                        .px_1()
+                        .border_1()
+                        .border_color(cx.theme().colors().border_variant)
+                        .rounded_sm()
+                        .child(row.name.clone()),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .px_1()
+                        .border_1()
+                        .border_color(cx.theme().colors().border_variant)
+                        .rounded_sm()
+                        .child(row.value.clone()),
+                )
+                .child(
+                    IconButton::new(("remove-env-var", ix), IconName::Close)
+                        .icon_size(IconSize::Small)
+                        .shape(IconButtonShape::Square)
+                        .tooltip(Tooltip::text("Remove Variable"))
+                        .on_click(cx.listener(move |this, _, _, cx| {
+                            if let Some((rows, error)) = this.mode.env_rows_mut()
+                                && ix < rows.len()
+                            {
+                                rows.remove(ix);
+                                *error = None;
+                                cx.notify();
+                            }
+                        })),
+                )
+        });
+
+        v_flex()
+            .gap_1()
+            .children(rows)
+            .child(
+                h_flex().child(
+                    Button::new("add-env-var", "Add Variable")
+                        .label_size(LabelSize::Small)
+                        .start_icon(Icon::new(IconName::Plus).size(IconSize::Small))
+                        .on_click(cx.listener(|this, _, window, cx| {
+                            if let Some((rows, _)) = this.mode.env_rows_mut() {
+                                let row = EnvVarRow::new("", "", window, cx);
+                                row.name.focus_handle(cx).focus(window, cx);
+                                rows.push(row);
+                                cx.notify();
+                            }
+                        })),
+                ),
+            )
+            .children(
+                error.map(|error| Label::new(error).size(LabelSize::Small).color(Color::Error)),
+            )
+            .when(!secret_names.is_empty(), |this| {
+                this.child(
+                    Label::new(format!(
+                        "{} {} like a secret. Values here are saved in plain text in \
+                                 settings.json; keep passwords in the system keychain instead.",
+                        secret_names.join(", "),
+                        if secret_names.len() == 1 {
+                            "looks"
+                        } else {
+                            "look"
+                        },
+                    ))
+                    .size(LabelSize::Small)
+                    .color(Color::Warning),
+                )
+            })
+    }
+
+    fn render_edit_tag(
+        &self,
+        state: &EditTagState,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let header = match state.target {
+            TagTarget::Server(index) => saved_server_header(index, cx)
+                .map(|header| header.render(window, cx).into_any_element()),
+            TagTarget::DevContainer(index) => RemoteSettings::get_global(cx)
+                .dev_container_connections
+                .get(index)
+                .map(|connection| {
+                    ModalHeader::new()
+                        .child(Headline::new(connection.name.clone()).size(HeadlineSize::XSmall))
                         .into_any_element()
+                }),
+        };
+        v_flex()
+            .id("edit-server-tag")
+            .track_focus(&self.focus_handle(cx))
+            .children(header)
+            .child(
+                h_flex()
+                    .p_2()
+                    .border_t_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(state.editor.clone()),
+            )
+    }
+
+    fn render_edit_keepalive(
+        &self,
+        state: &EditKeepaliveState,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let Some(connection) = RemoteSettings::get_global(cx)
+            .ssh_connections()
+            .nth(state.index.0)
+        else {
+            return v_flex()
+                .id("ssh-edit-keepalive")
+                .track_focus(&self.focus_handle(cx));
+        };
+        let platform = ssh_platform_label(&connection);
+
+        v_flex()
+            .id("ssh-edit-keepalive")
+            .track_focus(&self.focus_handle(cx))
+            .child(
+                SshConnectionHeader {
+                    connection_string: connection.host.into(),
+                    paths: Default::default(),
+                    nickname: connection.nickname.map(|s| s.into()),
+                    platform,
+                    is_wsl: false,
+                    is_devcontainer: false,
+                }
+                .render(window, cx),
+            )
+            .child(
+                v_flex()
+                    .p_2()
+                    .border_t_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(render_keepalive_fields(&state.keepalive, &state.errors, cx)),
+            )
+    }
+
+    fn render_confirm_settings_changes(
+        &self,
+        state: &ConfirmSettingsChangesState,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let groups = state
+            .changes
+            .groups
+            .iter()
+            .zip(&state.show_more)
+            .enumerate()
+            .map(|(group_ix, (group, show_more))| {
+                self.render_settings_change_group(group_ix, group, show_more.as_ref(), window, cx)
+            })
+            .collect::<Vec<_>>();
+
+        let confirm = div()
+            .id("confirm-settings-changes")
+            .track_focus(&state.confirm.focus_handle)
+            .anchor_scroll(state.confirm.scroll_anchor.clone())
+            .on_action(cx.listener(|this, _: &menu::Confirm, window, cx| {
+                this.apply_settings_changes(window, cx);
+            }))
+            .child(
+                ListItem::new("confirm-settings-changes-item")
+                    .toggle_state(state.confirm.focus_handle.contains_focused(window, cx))
+                    .inset(true)
+                    .spacing(ui::ListItemSpacing::Sparse)
+                    .start_slot(Icon::new(IconName::Check).color(Color::Muted))
+                    .child(Label::new("Apply Changes"))
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.apply_settings_changes(window, cx);
+                    })),
+            );
+        let cancel = div()
+            .id("cancel-settings-changes")
+            .track_focus(&state.cancel.focus_handle)
+            .anchor_scroll(state.cancel.scroll_anchor.clone())
+            .on_action(cx.listener(|this, _: &menu::Confirm, window, cx| {
+                this.cancel(&menu::Cancel, window, cx);
+            }))
+            .child(
+                ListItem::new("cancel-settings-changes-item")
+                    .toggle_state(state.cancel.focus_handle.contains_focused(window, cx))
+                    .inset(true)
+                    .spacing(ui::ListItemSpacing::Sparse)
+                    .start_slot(Icon::new(IconName::Close).color(Color::Muted))
+                    .child(Label::new("Cancel"))
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.cancel(&menu::Cancel, window, cx);
+                    })),
+            );
+
+        let mut view = Navigable::new(
+            v_flex()
+                .id("confirm-settings-changes-view")
+                .track_focus(&self.focus_handle(cx))
+                .size_full()
+                .max_h(rems(30.))
+                .overflow_y_scroll()
+                .track_scroll(&state.scroll_handle)
+                .child(
+                    h_flex()
+                        .px_2()
+                        .py_1p5()
+                        .child(Label::new(state.title.clone())),
+                )
+                .children(groups)
+                .child(ListSeparator)
+                .child(confirm)
+                .child(cancel)
+                .into_any_element(),
+        );
+        for show_more in state.show_more.iter().flatten() {
+            view = view.entry(show_more.clone());
+        }
+        view.entry(state.confirm.clone())
+            .entry(state.cancel.clone())
+            .render(window, cx)
+            .into_any_element()
+    }
+
+    fn render_settings_change_group(
+        &self,
+        group_ix: usize,
+        group: &ConnectionChangeGroup,
+        show_more: Option<&NavigableEntry>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let shown = if show_more.is_some() {
+            MAX_SETTINGS_CHANGES_SHOWN
+        } else {
+            group.changes.len()
+        };
+        let hidden = group.changes.len() - shown;
+
+        let show_more = show_more.map(|show_more| {
+            div()
+                .id(("show-all-settings-changes", group_ix))
+                .track_focus(&show_more.focus_handle)
+                .anchor_scroll(show_more.scroll_anchor.clone())
+                .on_action(cx.listener(move |this, _: &menu::Confirm, window, cx| {
+                    this.show_all_settings_changes(group_ix, window, cx);
+                }))
+                .child(
+                    ListItem::new(("show-all-settings-changes-item", group_ix))
+                        .toggle_state(show_more.focus_handle.contains_focused(window, cx))
+                        .inset(true)
+                        .spacing(ui::ListItemSpacing::Sparse)
+                        .start_slot(Icon::new(IconName::Ellipsis).color(Color::Muted))
+                        .child(Label::new(format!("and {hidden} more…")).color(Color::Muted))
+                        .on_click(cx.listener(move |this, _, window, cx| {
+                            this.show_all_settings_changes(group_ix, window, cx);
+                        })),
+                )
+        });
+
+        v_flex()
+            .w_full()
+            .child(ListSeparator)
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .gap_1()
+                    .child(
+                        Label::new(group.kind.label())
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .child(
+                        Label::new(format!("({})", group.changes.len()))
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+            )
+            .children(
+                group
+                    .changes
+                    .iter()
+                    .take(shown)
+                    .map(render_connection_change),
+            )
+            .children(show_more)
+    }
+
+    fn render_default(
+        &mut self,
+        mut state: DefaultState,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let ssh_settings = RemoteSettings::get_global(cx);
+        let search_query = self.server_search_query.clone();
+        let mut should_rebuild = false;
+
+        let ssh_connections_changed = ssh_settings
+            .ssh_connections
+            .0
+            .iter()
+            .filter(|connection| {
+                matches_search_query(&ssh_connection_search_haystack(connection), &search_query)
+            })
+            .ne(state
+                .servers
+                .iter()
+                .filter_map(|server| match server.saved_connection() {
+                    Some(Connection::Ssh(connection)) => Some(connection),
+                    _ => None,
+                }));
+
+        let wsl_connections_changed = ssh_settings
+            .wsl_connections
+            .0
+            .iter()
+            .filter(|connection| {
+                matches_search_query(&wsl_connection_search_haystack(connection), &search_query)
+            })
+            .ne(state
+                .servers
+                .iter()
+                .filter_map(|server| match server.saved_connection() {
+                    Some(Connection::Wsl(connection)) => Some(connection),
+                    _ => None,
+                }));
+
+        let dev_containers_changed = ssh_settings
+            .dev_container_connections
+            .iter()
+            .filter(|connection| {
+                matches_search_query(
+                    &dev_container_connection_search_haystack(connection),
+                    &search_query,
+                )
+            })
+            .ne(state
+                .dev_containers
+                .iter()
+                .map(|container| &container.connection));
+
+        if ssh_connections_changed
+            || wsl_connections_changed
+            || dev_containers_changed
+            || state.density != ssh_settings.density
+            || state.search_query != search_query
+        {
+            should_rebuild = true;
+        };
+
+        if !should_rebuild && ssh_settings.read_ssh_config {
+            should_rebuild = state.ssh_config_servers != self.ssh_config_servers;
+        }
+
+        if should_rebuild {
+            self.mode = self.default_mode(cx);
+            if let Mode::Default(new_state) = &self.mode {
+                state = new_state.clone();
+            }
+        }
+
+        let connect_button = div()
+            .id("ssh-connect-new-server-container")
+            .track_focus(&state.add_new_server.focus_handle)
+            .anchor_scroll(state.add_new_server.scroll_anchor.clone())
+            .child(
+                ListItem::new("register-remote-server-button")
+                    .toggle_state(
+                        state
+                            .add_new_server
+                            .focus_handle
+                            .contains_focused(window, cx),
+                    )
+                    .inset(true)
+                    .spacing(ui::ListItemSpacing::Sparse)
+                    .start_slot(Icon::new(IconName::Plus).color(Color::Muted))
+                    .child(Label::new("Connect SSH Server"))
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        remember_section(RemoteServersSection::Ssh, cx);
+                        let state = CreateRemoteServer::new(window, cx);
+                        this.mode = Mode::CreateRemoteServer(state);
+
+                        cx.notify();
+                    })),
+            )
+            .on_action(cx.listener(|this, _: &menu::Confirm, window, cx| {
+                remember_section(RemoteServersSection::Ssh, cx);
+                let state = CreateRemoteServer::new(window, cx);
+                this.mode = Mode::CreateRemoteServer(state);
+
+                cx.notify();
+            }));
+
+        let connect_dev_container_button = div()
+            .id("connect-new-dev-container")
+            .track_focus(&state.add_new_devcontainer.focus_handle)
+            .anchor_scroll(state.add_new_devcontainer.scroll_anchor.clone())
+            .child(
+                ListItem::new("register-dev-container-button")
+                    .toggle_state(
+                        state
+                            .add_new_devcontainer
+                            .focus_handle
+                            .contains_focused(window, cx),
+                    )
+                    .inset(true)
+                    .spacing(ui::ListItemSpacing::Sparse)
+                    .start_slot(Icon::new(IconName::Plus).color(Color::Muted))
+                    .child(Label::new("Connect Dev Container"))
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.init_dev_container_mode(window, cx);
+                    })),
+            )
+            .on_action(cx.listener(|this, _: &menu::Confirm, window, cx| {
+                this.init_dev_container_mode(window, cx);
+            }));
+
+        #[cfg(target_os = "windows")]
+        let wsl_connect_button = div()
+            .id("wsl-connect-new-server")
+            .track_focus(&state.add_new_wsl.focus_handle)
+            .anchor_scroll(state.add_new_wsl.scroll_anchor.clone())
+            .child(
+                ListItem::new("wsl-add-new-server")
+                    .toggle_state(state.add_new_wsl.focus_handle.contains_focused(window, cx))
+                    .inset(true)
+                    .spacing(ui::ListItemSpacing::Sparse)
+                    .start_slot(Icon::new(IconName::Plus).color(Color::Muted))
+                    .child(Label::new("Add WSL Distro"))
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        let state = AddWslDistro::new(window, cx);
+                        this.mode = Mode::AddWslDistro(state);
+
+                        cx.notify();
+                    })),
+            )
+            .on_action(cx.listener(|this, _: &menu::Confirm, window, cx| {
+                remember_section(RemoteServersSection::Wsl, cx);
+                let state = AddWslDistro::new(window, cx);
+                this.mode = Mode::AddWslDistro(state);
+
+                cx.notify();
+            }));
+
+        let can_connect_dev_container = self.can_connect_dev_container(cx);
+        let has_entries = !state.servers.is_empty()
+            || !state.ssh_config_groups.is_empty()
+            || !state.dev_containers.is_empty();
+        let is_searching = !state.search_query.trim().is_empty();
+
+        let empty_state = v_flex()
+            .p_2()
+            .gap_1()
+            .border_t_1()
+            .border_color(cx.theme().colors().border_variant)
+            .map(|this| {
+                if is_searching {
+                    this.child(
+                        Label::new(format!("No matches for '{}'", state.search_query.trim()))
+                            .color(Color::Muted),
+                    )
+                    .child(
+                        div()
+                            .id("clear-server-search-container")
+                            .track_focus(&state.clear_search.focus_handle)
+                            .anchor_scroll(state.clear_search.scroll_anchor.clone())
+                            .child(
+                                ListItem::new("clear-server-search")
+                                    .toggle_state(
+                                        state
+                                            .clear_search
+                                            .focus_handle
+                                            .contains_focused(window, cx),
+                                    )
+                                    .inset(true)
+                                    .spacing(ui::ListItemSpacing::Sparse)
+                                    .start_slot(Icon::new(IconName::Close).color(Color::Muted))
+                                    .child(Label::new("Clear Search"))
+                                    .on_click(cx.listener(|this, _, window, cx| {
+                                        this.clear_server_search(window, cx);
+                                    })),
+                            )
+                            .on_action(cx.listener(|this, _: &menu::Confirm, window, cx| {
+                                this.clear_server_search(window, cx);
+                            })),
+                    )
                 } else {
-                    confirm_button("Select".into()).into_any_element()
+                    this.child(Label::new("No remote servers registered yet.").color(Color::Muted))
+                        .child(
+                            Label::new(
+                                "Connect an SSH server above to open projects on another machine.",
+                            )
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                        )
+                        .when(can_connect_dev_container, |this| {
+                            this.child(
+                                Label::new(
+                                    "Dev containers are created from this project's \
+                                     devcontainer.json, and need Docker and the devcontainer CLI.",
+                                )
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                            )
+                        })
+                        .child(
+                            Label::new(if cfg!(target_os = "windows") {
+                                "WSL distros you add are listed here too."
+                            } else {
+                                "WSL distros can only be added on Windows."
+                            })
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                        )
+                }
+            })
+            .into_any_element();
+
+        let modal_section = v_flex()
+            .track_focus(&self.focus_handle(cx))
+            .id("ssh-server-list")
+            .overflow_y_scroll()
+            .track_scroll(&state.scroll_handle)
+            .size_full()
+            .child(connect_button)
+            .when(can_connect_dev_container, |this| {
+                this.child(connect_dev_container_button)
+            });
+
+        #[cfg(target_os = "windows")]
+        let modal_section = modal_section.child(wsl_connect_button);
+        #[cfg(not(target_os = "windows"))]
+        let modal_section = modal_section;
+
+        let mut modal_section = Navigable::new(
+            modal_section
+                .child(
+                    List::new()
+                        .empty_message(empty_state)
+                        .children(state.saved_server_rows().into_iter().map(|row| {
+                            match row {
+                                SavedServerRow::TagHeader(group_ix, group) => self
+                                    .render_server_tag_header(group_ix, group, window, cx)
+                                    .into_any_element(),
+                                SavedServerRow::Server(ix, server) => {
+                                    self.render_remote_connection(ix, server.clone(), window, cx)
+                                }
+                            }
+                        }))
+                        .children(state.ssh_config_groups.iter().enumerate().flat_map(
+                            |(group_ix, group)| {
+                                let header = self
+                                    .render_ssh_config_group_header(group_ix, group, window, cx)
+                                    .into_any_element();
+                                let hosts = state
+                                    .servers
+                                    .iter()
+                                    .enumerate()
+                                    .filter(|(_, server)| {
+                                        matches!(
+                                            server,
+                                            RemoteEntry::SshConfig { source, .. }
+                                                if *source == group.source
+                                        )
+                                    })
+                                    .map(|(ix, connection)| {
+                                        self.render_remote_connection(
+                                            ix,
+                                            connection.clone(),
+                                            window,
+                                            cx,
+                                        )
+                                        .into_any_element()
+                                    })
+                                    .collect::<Vec<_>>();
+                                let show_more = group.show_more.as_ref().map(|show_more| {
+                                    self.render_show_more_ssh_config_hosts(
+                                        group_ix, group, show_more, window, cx,
+                                    )
+                                    .into_any_element()
+                                });
+                                let save_all = group.save_all.as_ref().map(|save_all| {
+                                    self.render_save_ssh_config_hosts(
+                                        group_ix, group, save_all, window, cx,
+                                    )
+                                    .into_any_element()
+                                });
+                                std::iter::once(header)
+                                    .chain(hosts)
+                                    .chain(show_more)
+                                    .chain(save_all)
+                            },
+                        ))
+                        .children(state.dev_containers.iter().map(|container| {
+                            self.render_saved_dev_container(container, window, cx)
+                        }))
+                        .children(state.remove_missing_dev_containers.as_ref().map(
+                            |remove_missing| {
+                                self.render_remove_missing_dev_containers(
+                                    remove_missing,
+                                    window,
+                                    cx,
+                                )
+                            },
+                        )),
+                )
+                .into_any_element(),
+        )
+        .entry(state.add_new_server.clone());
+
+        if can_connect_dev_container {
+            modal_section = modal_section.entry(state.add_new_devcontainer.clone());
+        }
+
+        if cfg!(target_os = "windows") {
+            modal_section = modal_section.entry(state.add_new_wsl.clone());
+        }
+
+        if is_searching && !has_entries {
+            modal_section = modal_section.entry(state.clear_search.clone());
+        }
+
+        for row in state.saved_server_rows() {
+            match row {
+                SavedServerRow::TagHeader(_, group) => {
+                    modal_section = modal_section.entry(group.toggle.clone());
+                }
+                SavedServerRow::Server(
+                    _,
+                    RemoteEntry::Project {
+                        open_folder,
+                        clone_repository,
+                        projects,
+                        configure,
+                        ..
+                    },
+                ) => {
+                    for (navigation_state, _) in projects {
+                        modal_section = modal_section.entry(navigation_state.clone());
+                    }
+                    modal_section = modal_section
+                        .entry(open_folder.clone())
+                        .entry(clone_repository.clone())
+                        .entry(configure.clone());
+                }
+                SavedServerRow::Server(_, RemoteEntry::CompactProject { row, .. }) => {
+                    modal_section = modal_section.entry(row.clone());
+                }
+                SavedServerRow::Server(_, RemoteEntry::SshConfig { .. }) => {}
+            }
+        }
+        for group in &state.ssh_config_groups {
+            modal_section = modal_section.entry(group.toggle.clone());
+            for server in &state.servers {
+                if let RemoteEntry::SshConfig {
+                    open_folder,
+                    source,
+                    ..
+                } = server
+                    && *source == group.source
+                {
+                    modal_section = modal_section.entry(open_folder.clone());
+                }
+            }
+            if let Some(show_more) = &group.show_more {
+                modal_section = modal_section.entry(show_more.clone());
+            }
+            if let Some(save_all) = &group.save_all {
+                modal_section = modal_section.entry(save_all.clone());
+            }
+        }
+        for container in &state.dev_containers {
+            modal_section = modal_section
+                .entry(container.row.clone())
+                .entry(container.manage.clone());
+            if let Some(edit_user) = &container.edit_user {
+                modal_section = modal_section.entry(edit_user.clone());
+            }
+            if let Some(edit_tag) = &container.edit_tag {
+                modal_section = modal_section.entry(edit_tag.clone());
+            }
+            if let Some(remove) = &container.remove {
+                modal_section = modal_section.entry(remove.clone());
+            }
+        }
+        if let Some(remove_missing) = &state.remove_missing_dev_containers {
+            modal_section = modal_section.entry(remove_missing.clone());
+        }
+        let mut modal_section = modal_section.render(window, cx).into_any_element();
+
+        let is_project_selected = state.servers.iter().any(|server| match server {
+            RemoteEntry::Project { projects, .. } => projects
+                .iter()
+                .any(|(entry, _)| entry.focus_handle.contains_focused(window, cx)),
+            RemoteEntry::CompactProject { .. } | RemoteEntry::SshConfig { .. } => false,
+        });
+
+        Modal::new("remote-projects", None)
+            .header(ModalHeader::new().headline("Remote Projects"))
+            .section(
+                Section::new().padded(false).child(
+                    v_flex()
+                        .min_h(rems(20.))
+                        .size_full()
+                        .relative()
+                        .child(
+                            h_flex()
+                                .px_2()
+                                .py_1p5()
+                                .gap_2()
+                                .child(
+                                    Icon::new(IconName::MagnifyingGlass)
+                                        .size(IconSize::Small)
+                                        .color(Color::Muted),
+                                )
+                                .child(self.server_search_editor.clone()),
+                        )
+                        .child(ListSeparator)
+                        .child(
+                            canvas(
+                                |bounds, window, cx| {
+                                    modal_section.prepaint_as_root(
+                                        bounds.origin,
+                                        bounds.size.into(),
+                                        window,
+                                        cx,
+                                    );
+                                    modal_section
+                                },
+                                |_, mut modal_section, window, cx| {
+                                    modal_section.paint(window, cx);
+                                },
+                            )
+                            .size_full(),
+                        )
+                        .custom_scrollbars(
+                            Scrollbars::always_visible(ScrollAxes::Vertical)
+                                .tracked_scroll_handle(&state.scroll_handle),
+                            window,
+                            cx,
+                        ),
+                ),
+            )
+            .footer(
+                ModalFooter::new()
+                    .start_slot(
+                        Switch::new("open-in-new-window", self.create_new_window.into())
+                            .label("Open in New Window")
+                            .label_size(LabelSize::Small)
+                            .on_click(
+                                cx.listener(|this, _, _, cx| this.toggle_create_new_window(cx)),
+                            ),
+                    )
+                    .end_slot({
+                        let confirm_button = |label: SharedString| {
+                            Button::new("select", label)
+                                .key_binding(KeyBinding::for_action(&menu::Confirm, cx))
+                                .on_click(|_, window, cx| {
+                                    window.dispatch_action(menu::Confirm.boxed_clone(), cx)
+                                })
+                        };
+
+                        if is_project_selected {
+                            h_flex()
+                                .gap_1()
+                                .child(
+                                    Button::new(
+                                        "open_new_window",
+                                        if self.create_new_window {
+                                            "This Window"
+                                        } else {
+                                            "New Window"
+                                        },
+                                    )
+                                    .key_binding(KeyBinding::for_action(
+                                        &menu::SecondaryConfirm,
+                                        cx,
+                                    ))
+                                    .on_click(
+                                        |_, window, cx| {
+                                            window.dispatch_action(
+                                                menu::SecondaryConfirm.boxed_clone(),
+                                                cx,
+                                            )
+                                        },
+                                    ),
+                                )
+                                .child(confirm_button("Open".into()))
+                                .into_any_element()
+                        } else {
+                            confirm_button("Select".into()).into_any_element()
+                        }
+                    }),
+            )
+            .into_any_element()
+    }
+
+    fn create_host_from_ssh_config(
+        &mut self,
+        ssh_config_host: &SharedString,
+        cx: &mut Context<'_, Self>,
+    ) -> SshServerIndex {
+        let new_ix = Arc::new(AtomicUsize::new(0));
+
+        let update_new_ix = new_ix.clone();
+        self.update_settings_file(cx, move |settings, _| {
+            update_new_ix.store(
+                settings
+                    .ssh_connections
+                    .as_ref()
+                    .map_or(0, |connections| connections.len()),
+                atomic::Ordering::Release,
+            );
+        });
+
+        self.save_ssh_server(
+            SshConnectionOptions {
+                host: ssh_config_host.to_string().into(),
+                ..SshConnectionOptions::default()
+            },
+            None,
+            None,
+            None,
+            cx,
+        );
+        self.mode = self.default_mode(cx);
+        SshServerIndex(new_ix.load(atomic::Ordering::Acquire))
+    }
+}
+
+fn spawn_ssh_config_watch(fs: Arc<dyn Fs>, cx: &Context<RemoteServerProjects>) -> Task<()> {
+    enum ConfigSource {
+        User(String),
+        Global(String),
+    }
+
+    let mut streams = Vec::new();
+    let mut tasks = Vec::new();
+
+    // Setup User Watcher
+    let user_path = user_ssh_config_file();
+    info!("SSH: Watching User Config at: {:?}", user_path);
+
+    // We clone 'fs' here because we might need it again for the global watcher.
+    let (user_s, user_t) =
+        watch_config_file(cx.background_executor(), fs.clone(), user_path.clone());
+    streams.push(user_s.map(ConfigSource::User).boxed());
+    tasks.push(user_t);
+
+    // Setup Global Watcher
+    if let Some(gp) = global_ssh_config_file() {
+        info!("SSH: Watching Global Config at: {:?}", gp);
+        let (global_s, global_t) =
+            watch_config_file(cx.background_executor(), fs.clone(), gp.to_path_buf());
+        streams.push(global_s.map(ConfigSource::Global).boxed());
+        tasks.push(global_t);
+    } else {
+        debug!("SSH: No Global Config defined.");
+    }
+
+    // Combine into a single stream so that only one is parsed at once.
+    let mut merged_stream = futures::stream::select_all(streams);
+
+    cx.spawn(async move |remote_server_projects, cx| {
+        let _tasks = tasks; // Keeps the background watchers alive
+        let mut global_hosts = Vec::new();
+        let mut user_hosts = Vec::new();
+
+        while let Some(event) = merged_stream.next().await {
+            // Config files can be large, so keep parsing off the main thread.
+            match event {
+                ConfigSource::Global(content) => {
+                    let fs = fs.clone();
+                    let Some(path) = global_ssh_config_file() else {
+                        continue;
+                    };
+                    global_hosts = cx
+                        .background_spawn(async move {
+                            load_ssh_config_hosts(fs.as_ref(), path, &content).await
+                        })
+                        .await;
+                }
+                ConfigSource::User(content) => {
+                    let fs = fs.clone();
+                    let path = user_path.clone();
+                    user_hosts = cx
+                        .background_spawn(async move {
+                            load_ssh_config_hosts(fs.as_ref(), &path, &content).await
+                        })
+                        .await;
+                }
+            }
+
+            // Sync to Model
+            if remote_server_projects
+                .update(cx, |project, cx| {
+                    // ssh reads the user's config before the system one, and
+                    // uses the first definition of each host.
+                    let groups = group_ssh_config_hosts(user_hosts.iter().chain(&global_hosts));
+                    project.ssh_config_proxied_hosts = groups
+                        .iter()
+                        .flat_map(|group| &group.hosts)
+                        .filter(|entry| entry.via_proxy)
+                        .map(|entry| SharedString::from(entry.host.clone()))
+                        .collect();
+                    project.ssh_config_proxy_jumps = groups
+                        .iter()
+                        .flat_map(|group| &group.hosts)
+                        .filter_map(|entry| Some((entry.host.clone(), entry.proxy_jump.clone()?)))
+                        .collect();
+                    project.ssh_config_servers = groups;
+                    cx.notify();
+                })
+                .is_err()
+            {
+                return;
+            }
+        }
+    })
+}
+
+const LAST_USED_SECTION_KEY: &str = "remote_servers_last_used_section";
+
+/// The saved project to open when connecting to a server: the one opened most
+/// recently according to `history`, or the server's only project.
+fn most_recent_project(
+    history: &[(RemoteConnectionOptions, Vec<PathBuf>)],
+    options: &RemoteConnectionOptions,
+    projects: &[RemoteProject],
+) -> Option<RemoteProject> {
+    history
+        .iter()
+        .filter(|(history_options, _)| is_same_remote_host(history_options, options))
+        .find_map(|(_, paths)| {
+            let mut paths = paths.clone();
+            paths.sort();
+            projects
+                .iter()
+                .find(|project| {
+                    let mut project_paths =
+                        project.paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+                    project_paths.sort();
+                    project_paths == paths
+                })
+                .cloned()
+        })
+        .or_else(|| match projects {
+            [project] => Some(project.clone()),
+            _ => None,
+        })
+}
+
+/// The remote workspaces open in any window, with their connection and the
+/// paths of their visible worktrees.
+fn open_remote_workspaces(
+    cx: &App,
+) -> Vec<(
+    (WindowHandle<MultiWorkspace>, Entity<Workspace>),
+    RemoteConnectionOptions,
+    Vec<PathBuf>,
+)> {
+    let mut open_workspaces = Vec::new();
+    for window in cx
+        .windows()
+        .into_iter()
+        .filter_map(|window| window.downcast::<MultiWorkspace>())
+    {
+        let Ok(multi_workspace) = window.read(cx) else {
+            continue;
+        };
+        for workspace in multi_workspace.workspaces() {
+            let Some(connection) = workspace
+                .read(cx)
+                .project()
+                .read(cx)
+                .remote_connection_options(cx)
+            else {
+                continue;
+            };
+            let paths = workspace
+                .read(cx)
+                .root_paths(cx)
+                .iter()
+                .map(|path| path.to_path_buf())
+                .collect();
+            open_workspaces.push(((window, workspace.clone()), connection, paths));
+        }
+    }
+    open_workspaces
+}
+
+/// Finds the workspace among `open_workspaces` that has exactly the project
+/// at `paths` on `connection` open. Connections match by identity, so
+/// differences like nicknames don't matter, and paths match once normalized,
+/// in any order.
+fn find_open_remote_project<W>(
+    open_workspaces: impl IntoIterator<Item = (W, RemoteConnectionOptions, Vec<PathBuf>)>,
+    connection: &RemoteConnectionOptions,
+    paths: &[PathBuf],
+) -> Option<W> {
+    let paths = normalized_remote_paths(paths);
+    open_workspaces
+        .into_iter()
+        .find(|(_, open_connection, open_paths)| {
+            same_remote_connection_identity(Some(open_connection), Some(connection))
+                && normalized_remote_paths(open_paths) == paths
+        })
+        .map(|(workspace, _, _)| workspace)
+}
+
+/// Remote paths as a set, without the repeated or trailing separators and `.`
+/// components that don't change the folder a path points to.
+fn normalized_remote_paths(paths: &[PathBuf]) -> BTreeSet<String> {
+    paths
+        .iter()
+        .map(|path| {
+            let path = path.to_string_lossy();
+            let components = path
+                .split(['/', '\\'])
+                .filter(|component| !component.is_empty() && *component != ".")
+                .collect::<Vec<_>>()
+                .join("/");
+            if path.starts_with(['/', '\\']) {
+                format!("/{components}")
+            } else {
+                components
+            }
+        })
+        .collect()
+}
+
+/// Whether two connections reach the same host as the same user, ignoring
+/// how the connection is made.
+fn is_same_remote_host(a: &RemoteConnectionOptions, b: &RemoteConnectionOptions) -> bool {
+    match (a, b) {
+        (RemoteConnectionOptions::Ssh(a), RemoteConnectionOptions::Ssh(b)) => {
+            a.host == b.host && a.username == b.username && a.port == b.port
+        }
+        (RemoteConnectionOptions::Wsl(a), RemoteConnectionOptions::Wsl(b)) => {
+            a.distro_name == b.distro_name && a.user == b.user
+        }
+        _ => false,
+    }
+}
+
+fn last_used_section(cx: &App) -> RemoteServersSection {
+    KeyValueStore::global(cx)
+        .read_kvp(LAST_USED_SECTION_KEY)
+        .ok()
+        .flatten()
+        .and_then(|section| serde_json::from_str(&section).ok())
+        .unwrap_or(RemoteServersSection::Ssh)
+}
+
+fn remember_section(section: RemoteServersSection, cx: &mut App) {
+    let Some(section) = serde_json::to_string(&section).log_err() else {
+        return;
+    };
+    let kvp = KeyValueStore::global(cx);
+    cx.background_spawn(async move {
+        kvp.write_kvp(LAST_USED_SECTION_KEY.to_string(), section)
+            .await
+            .log_err();
+    })
+    .detach();
+}
+
+/// The command to remember for a server entered as `input` in the
+/// create-server form. A bare host or an `ssh://` URL says nothing the saved
+/// connection doesn't, so only input with options is kept.
+fn quick_connect_command(input: &str) -> Option<String> {
+    let input = input.trim();
+    (!input.starts_with("ssh://") && input.contains(char::is_whitespace)).then(|| input.to_string())
+}
+
+/// Sets what the create-server form determines about a saved connection,
+/// leaving its projects, nickname, and tag as they are. The shell and the
+/// server's platform are only changed when they're known.
+fn update_ssh_connection_from_form(
+    connection: &mut SshConnection,
+    connection_options: SshConnectionOptions,
+    platform: Option<RemotePlatform>,
+    original_command: Option<String>,
+) {
+    connection.host = connection_options.host.to_string();
+    connection.username = connection_options.username;
+    connection.port = connection_options.port;
+    connection.args = connection_options.args.unwrap_or_default();
+    connection.upload_binary_over_ssh = connection_options.upload_binary_over_ssh.then_some(true);
+    connection.port_forwards = connection_options.port_forwards;
+    connection.connection_timeout = connection_options.connection_timeout;
+    connection.keepalive_interval = connection_options.keepalive_interval;
+    connection.keepalive_count_max = connection_options.keepalive_count_max;
+    if connection_options.shell.is_some() {
+        connection.shell = connection_options.shell;
+    }
+    if connection_options.login_shell.is_some() {
+        connection.login_shell = connection_options.login_shell;
+    }
+    if let Some(platform) = platform {
+        connection.remote_os = Some(platform.os.as_str().to_string());
+        connection.remote_arch = Some(platform.arch.as_str().to_string());
+    }
+    connection.original_command = original_command;
+}
+
+/// Parses the SSH command and options entered in the create-server form,
+/// returning `None` when no command has been entered.
+fn build_ssh_connection_from_form(
+    address_editor: &Entity<Editor>,
+    keepalive: &KeepaliveEditors,
+    upload_binary_over_ssh: bool,
+    forward_agent: bool,
+    cx: &mut App,
+) -> Option<Result<SshConnectionOptions, CreateRemoteServerFormErrors>> {
+    let input = get_text(address_editor, cx);
+    if input.is_empty() {
+        return None;
+    }
+    let connection_options = parse_ssh_address(&input);
+    let result = match (connection_options, keepalive.parse(cx)) {
+        (Ok(mut connection_options), Ok((interval, count_max))) => {
+            connection_options.keepalive_interval = interval;
+            connection_options.keepalive_count_max = count_max;
+            connection_options.upload_binary_over_ssh = upload_binary_over_ssh;
+            // An `-A` typed into the address is kept even with the box unchecked.
+            if forward_agent {
+                set_agent_forwarding(connection_options.args.get_or_insert_default(), true);
+            }
+            match check_identity_files(connection_options.args.as_deref().unwrap_or_default()) {
+                Some(problem) => Err(CreateRemoteServerFormErrors {
+                    address: Some(identity_file_form_error(&problem)),
+                    keepalive: KeepaliveErrors::default(),
+                }),
+                None => Ok(connection_options),
+            }
+        }
+        (connection_options, keepalive) => Err(CreateRemoteServerFormErrors {
+            address: connection_options.err(),
+            keepalive: keepalive.err().unwrap_or_default(),
+        }),
+    };
+    Some(result)
+}
+
+/// Whether ssh has nothing but the ssh-agent to authenticate to the server
+/// with: no password and no identity file in its arguments.
+fn authenticates_only_with_agent(connection_options: &RemoteConnectionOptions) -> bool {
+    match connection_options {
+        RemoteConnectionOptions::Ssh(options) => {
+            options.password.is_none()
+                && identity_files(options.args.as_deref().unwrap_or_default()).is_empty()
+        }
+        _ => false,
+    }
+}
+
+fn identity_file_form_error(problem: &IdentityFileProblem) -> SharedString {
+    match problem {
+        IdentityFileProblem::TooOpen { path, .. } => format!(
+            "{} Run `chmod 600 {}` to fix it.",
+            problem.message(),
+            path.display()
+        )
+        .into(),
+        IdentityFileProblem::Missing(_) | IdentityFileProblem::Unreadable { .. } => {
+            problem.message().into()
+        }
+    }
+}
+
+/// Parses the text entered in the create-server address field, which is
+/// often pasted from elsewhere: surrounding whitespace is ignored, `ssh://`
+/// URLs are accepted, and a trailing dot on the host is dropped.
+fn parse_ssh_address(input: &str) -> Result<SshConnectionOptions, SharedString> {
+    let mut lines = input.lines().map(str::trim).filter(|line| !line.is_empty());
+    let Some(line) = lines.next() else {
+        return Err("Enter an SSH command or host.".into());
+    };
+    if lines.next().is_some() {
+        return Err("The pasted text spans several lines. Enter a single SSH command.".into());
+    }
+
+    let command = match line.strip_prefix("ssh://") {
+        Some(url) => ssh_url_to_command(url)?,
+        None => line.to_string(),
+    };
+    validate_port_arguments(&command)?;
+
+    let mut connection_options = SshConnectionOptions::parse_command_line(&command)
+        .map_err(|error| SharedString::from(format!("could not parse: {error:#}")))?;
+    let host = connection_options.host.to_string();
+    let trimmed_host = host.trim_end_matches('.');
+    if trimmed_host.is_empty() {
+        return Err("Enter a host to connect to.".into());
+    }
+    if trimmed_host.contains(char::is_whitespace) {
+        return Err("The host can't contain spaces.".into());
+    }
+    if trimmed_host.len() != host.len() {
+        connection_options.host = trimmed_host.into();
+    }
+    if connection_options
+        .username
+        .as_ref()
+        .is_some_and(|username| username.is_empty() || username.contains(char::is_whitespace))
+    {
+        return Err("The username can't be empty or contain spaces.".into());
+    }
+    Ok(connection_options)
+}
+
+/// Turns the part of an `ssh://[user@]host[:port][/path]` URL after the
+/// scheme into the equivalent SSH command. The path is ignored.
+fn ssh_url_to_command(url: &str) -> Result<String, SharedString> {
+    let authority = url.split_once('/').map_or(url, |(authority, _)| authority);
+    let (username, host_and_port) = match authority.rsplit_once('@') {
+        Some((username, host_and_port)) => (Some(username), host_and_port),
+        None => (None, authority),
+    };
+    let (host, port) = if let Some(bracketed) = host_and_port.strip_prefix('[') {
+        match bracketed.split_once(']') {
+            Some((host, "")) => (host, None),
+            Some((host, port)) => match port.strip_prefix(':') {
+                Some(port) => (host, Some(port)),
+                None => return Err("The host in this URL is not valid.".into()),
+            },
+            None => return Err("The host in this URL is not valid.".into()),
+        }
+    } else {
+        match host_and_port.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (host_and_port, None),
+        }
+    };
+    if host.is_empty() {
+        return Err("Enter a host to connect to.".into());
+    }
+
+    let mut command = match username {
+        Some(username) => format!("{username}@{host}"),
+        None => host.to_string(),
+    };
+    if let Some(port) = port {
+        command.push_str(" -p ");
+        command.push_str(&parse_port(port)?.to_string());
+    }
+    Ok(command)
+}
+
+/// Checks the values given to `-p`, which the SSH command parser otherwise
+/// ignores when they aren't valid ports.
+fn validate_port_arguments(command: &str) -> Result<(), SharedString> {
+    let mut arguments = command.split_whitespace();
+    while let Some(argument) = arguments.next() {
+        if argument == "-p" {
+            parse_port(arguments.next().unwrap_or_default())?;
+        } else if let Some(port) = argument.strip_prefix("-p") {
+            parse_port(port)?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_port(port: &str) -> Result<u16, SharedString> {
+    match port.parse::<u16>() {
+        Ok(port) if port > 0 => Ok(port),
+        _ => Err(format!("The port must be a number from 1 to 65535, not {port:?}.").into()),
+    }
+}
+
+fn render_keepalive_fields(
+    keepalive: &KeepaliveEditors,
+    errors: &KeepaliveErrors,
+    cx: &App,
+) -> impl IntoElement {
+    v_flex()
+        .gap_2()
+        .child(render_keepalive_field(
+            "Keepalive interval in seconds",
+            &keepalive.interval_editor,
+            errors.interval.as_ref(),
+            cx,
+        ))
+        .child(render_keepalive_field(
+            "Unanswered keepalives before disconnecting",
+            &keepalive.count_max_editor,
+            errors.count_max.as_ref(),
+            cx,
+        ))
+}
+
+fn render_keepalive_field(
+    label: &'static str,
+    editor: &Entity<Editor>,
+    error: Option<&SharedString>,
+    cx: &App,
+) -> impl IntoElement {
+    v_flex()
+        .gap_1()
+        .child(
+            h_flex()
+                .gap_2()
+                .child(
+                    div()
+                        .flex_1()
+                        .child(Label::new(label).size(LabelSize::Small)),
+                )
+                .child(
+                    div()
+                        .w(rems(8.))
+                        .px_1()
+                        .border_1()
+                        .border_color(cx.theme().colors().border_variant)
+                        .rounded_sm()
+                        .child(editor.clone()),
+                ),
+        )
+        .children(error.map(|error| {
+            Label::new(error.clone())
+                .size(LabelSize::Small)
+                .color(Color::Error)
+        }))
+}
+
+/// The lines a dev container has logged while being set up, or nothing when
+/// there are none yet.
+fn render_build_log(build_log: &[SharedString], cx: &App) -> impl IntoElement {
+    div().when(!build_log.is_empty(), |this| {
+        this.child(
+            v_flex()
+                .id("dev-container-build-log")
+                .max_h_40()
+                .overflow_y_scroll()
+                .p_2()
+                .border_t_1()
+                .border_color(cx.theme().colors().border_variant)
+                .bg(cx.theme().colors().editor_background)
+                .children(build_log.iter().map(|line| {
+                    Label::new(line.clone())
+                        .size(LabelSize::Small)
+                        .buffer_font(cx)
+                })),
+        )
+    })
+}
+
+/// Asks which of `daemons` to start a dev container with, when docker isn't
+/// installed on Windows but runs in several WSL distros. Returns `None` when
+/// the user cancels.
+async fn pick_wsl_docker_daemon(
+    daemons: &[WslDockerDaemon],
+    cx: &mut AsyncWindowContext,
+) -> Option<WslDockerDaemon> {
+    let mut answers = daemons.iter().map(ToString::to_string).collect::<Vec<_>>();
+    answers.push("Cancel".to_string());
+    let answers = answers.iter().map(String::as_str).collect::<Vec<_>>();
+    let choice = cx
+        .prompt(
+            gpui::PromptLevel::Info,
+            "Docker is running in several WSL distros",
+            Some("Docker isn't installed on Windows. Choose the distro to use."),
+            &answers,
+        )
+        .await
+        .ok()?;
+    daemons.get(choice).cloned()
+}
+
+/// Runs `true` on a freshly connected server to check that it can execute
+/// commands.
+async fn run_connection_check(
+    client: Entity<RemoteClient>,
+    cx: &mut AsyncWindowContext,
+) -> Result<(), ConnectionTestFailure> {
+    let command = cx
+        .update(|_, cx| {
+            client.read(cx).build_command_with_options(
+                Some("true".to_string()),
+                &[],
+                &Default::default(),
+                None,
+                None,
+                Interactive::No,
+            )
+        })
+        .and_then(|command| command)
+        .map_err(|error| {
+            log::error!("connection test failed to build a command: {error:#}");
+            ConnectionTestFailure::RemoteCommand
+        })?;
+
+    let status = util::command::new_command(command.program)
+        .args(command.args)
+        .envs(command.env)
+        .stdin(util::command::Stdio::null())
+        .kill_on_drop(true)
+        .status()
+        .await
+        .map_err(|error| {
+            log::error!("connection test command failed to start: {error:#}");
+            ConnectionTestFailure::RemoteCommand
+        })?;
+    ConnectionTestFailure::from_check_status(status.code())
+}
+
+/// Resolves once [`CONNECTION_TEST_TIMEOUT`] has passed without `prompt`
+/// waiting on the user, so that typing a password or checking a host key
+/// doesn't count against the test.
+async fn connection_test_timeout(
+    prompt: WeakEntity<RemoteConnectionPrompt>,
+    cx: &mut AsyncWindowContext,
+) {
+    const TICK: Duration = Duration::from_millis(250);
+    let mut waited = Duration::ZERO;
+    while waited < CONNECTION_TEST_TIMEOUT {
+        cx.background_executor().timer(TICK).await;
+        let prompting = prompt
+            .read_with(cx, |prompt, _| prompt.is_prompting())
+            .unwrap_or(false);
+        if !prompting {
+            waited += TICK;
+        }
+    }
+}
+
+/// Waits for `connection`, then runs `git clone` on the server, streaming its
+/// output into the clone form. Returns the project and the absolute
+/// destination on success, or `None` if the connection was cancelled.
+async fn run_git_clone(
+    this: WeakEntity<RemoteServerProjects>,
+    connection: Task<anyhow::Result<Option<Entity<RemoteClient>>>>,
+    app_state: Arc<AppState>,
+    url: String,
+    destination: String,
+    cx: &mut AsyncWindowContext,
+) -> anyhow::Result<Option<(Entity<Project>, String)>> {
+    let Some(client) = connection.await? else {
+        return Ok(None);
+    };
+    this.update(cx, |this, cx| {
+        if let Mode::CloneRepository(state) = &mut this.mode {
+            state.ssh_prompt = None;
+        }
+        this.retained_connections.push(client.clone());
+        cx.notify();
+    })?;
+
+    let (path_style, project) = cx.update(|_, cx| {
+        (
+            client.read(cx).path_style(),
+            project::Project::remote(
+                client.clone(),
+                app_state.client.clone(),
+                app_state.node_runtime.clone(),
+                app_state.user_store.clone(),
+                app_state.languages.clone(),
+                app_state.fs.clone(),
+                true,
+                cx,
+            ),
+        )
+    })?;
+    let home_dir = project
+        .read_with(cx, |project, cx| project.resolve_abs_path("~", cx))
+        .await
+        .and_then(|path| path.into_abs_path())
+        .context("failed to resolve the home directory on the server")?;
+    let home_dir = home_dir.to_string_lossy().into_owned();
+    let destination = if destination == "~" {
+        home_dir
+    } else if let Some(relative) = destination.strip_prefix("~/") {
+        path_style
+            .join(&home_dir, relative)
+            .context("invalid destination directory")?
+    } else if path_style.is_absolute(&destination) {
+        destination
+    } else {
+        path_style
+            .join(&home_dir, &destination)
+            .context("invalid destination directory")?
+    };
+
+    let command = cx.update(|_, cx| {
+        let client = client.read(cx);
+        let mut program = "git".to_string();
+        let mut args = vec![
+            "clone".to_string(),
+            "--progress".to_string(),
+            "--".to_string(),
+            url,
+            destination.clone(),
+        ];
+        // On Unix servers, run git through a shell that first prints its
+        // process id, so that a cancelled clone can be stopped on the server.
+        if !path_style.is_windows() {
+            // Clone from a login shell when the server was given a shell of its
+            // own and uses it as one, so that a git set up by the server's
+            // profile is found.
+            let login_shell = match client.connection_options() {
+                RemoteConnectionOptions::Ssh(options) => {
+                    let defaults = SshSettings::get_global(cx);
+                    let has_own_shell = options.shell.is_some() || defaults.shell.is_some();
+                    let login_shell = options.login_shell.unwrap_or(defaults.login_shell);
+                    if has_own_shell && login_shell {
+                        client.shell()
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            };
+            let shell = login_shell.clone().unwrap_or_else(|| "sh".to_string());
+            let shell_kind = ShellKind::new(&shell, false);
+            let git_command = std::iter::once(&program)
+                .chain(&args)
+                .map(|arg| shell_kind.try_quote(arg).context("shell quoting"))
+                .collect::<anyhow::Result<Vec<_>>>()?
+                .join(" ");
+            let script = format!("echo $$ && exec {git_command} </dev/null >/dev/null");
+            args = if login_shell.is_some() {
+                vec!["-l".to_string(), "-c".to_string(), script]
+            } else {
+                vec!["-c".to_string(), script]
+            };
+            program = shell;
+        }
+        client.build_command_with_options(
+            Some(program),
+            &args,
+            // Fail instead of waiting for credentials nobody can enter.
+            &[("GIT_TERMINAL_PROMPT".to_string(), "0".to_string())]
+                .into_iter()
+                .collect(),
+            None,
+            None,
+            Interactive::No,
+        )
+    })??;
+    let mut child = util::command::new_command(command.program)
+        .args(command.args)
+        .envs(command.env)
+        .stdin(util::command::Stdio::null())
+        .stdout(util::command::Stdio::piped())
+        .stderr(util::command::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to start git clone")?;
+
+    // The shell's profile may print to stdout before the process id, and git's
+    // own stdout goes to /dev/null, so the first number is the one we want.
+    let mut stdout_lines = child
+        .stdout
+        .take()
+        .map(|stdout| futures::io::BufReader::new(stdout).lines());
+    if !path_style.is_windows()
+        && let Some(lines) = stdout_lines.as_mut()
+    {
+        while let Some(line) = lines.next().await {
+            if let Ok(pid) = line?.trim().parse::<u32>() {
+                this.update(cx, |this, _| {
+                    if let Mode::CloneRepository(state) = &mut this.mode {
+                        state.remote_git = Some((client.clone(), pid));
+                    }
+                })?;
+                break;
+            }
+        }
+    }
+
+    let mut progress = CloneProgress::default();
+    if let Some(mut stderr) = child.stderr.take() {
+        let mut buffer = [0; 4096];
+        loop {
+            let read = stderr.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            progress.push(&buffer[..read]);
+            let output = progress.lines().to_vec();
+            this.update(cx, |this, cx| {
+                if let Mode::CloneRepository(state) = &mut this.mode {
+                    state.output = output;
+                    cx.notify();
+                }
+            })?;
+        }
+        progress.finish();
+    }
+    drop(stdout_lines);
+
+    let status = child.status().await?;
+    this.update(cx, |this, _| {
+        if let Mode::CloneRepository(state) = &mut this.mode {
+            state.remote_git = None;
+        }
+    })?;
+    let last_line = progress
+        .lines()
+        .iter()
+        .rev()
+        .find(|line| !line.trim().is_empty())
+        .cloned();
+    if !status.success() {
+        // A shell reports a missing `git` with exit code 127.
+        if status.code() == Some(127) {
+            anyhow::bail!("git is not installed on the server");
+        }
+        match last_line {
+            Some(line) => anyhow::bail!("{line}"),
+            None => anyhow::bail!("git clone exited with {status}"),
+        }
+    }
+    Ok(Some((project, destination)))
+}
+
+/// Collects `git clone --progress` output into lines the way a terminal shows
+/// them: a line ended by `\r` is replaced by the one that follows it.
+#[derive(Default)]
+struct CloneProgress {
+    lines: Vec<SharedString>,
+    partial: Vec<u8>,
+    overwrite_last: bool,
+}
+
+impl CloneProgress {
+    fn push(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            match byte {
+                b'\n' if self.overwrite_last && self.partial.is_empty() => {
+                    self.overwrite_last = false;
+                }
+                b'\n' => {
+                    self.finish_line();
+                    self.overwrite_last = false;
+                }
+                b'\r' => {
+                    self.finish_line();
+                    self.overwrite_last = true;
+                }
+                byte => self.partial.push(byte),
+            }
+        }
+    }
+
+    /// Keeps output that wasn't terminated by a line ending.
+    fn finish(&mut self) {
+        if !self.partial.is_empty() {
+            self.finish_line();
+        }
+    }
+
+    fn finish_line(&mut self) {
+        let line = SharedString::from(String::from_utf8_lossy(&self.partial).into_owned());
+        self.partial.clear();
+        match self.lines.last_mut() {
+            Some(last) if self.overwrite_last => *last = line,
+            _ => self.lines.push(line),
+        }
+    }
+
+    fn lines(&self) -> &[SharedString] {
+        &self.lines
+    }
+}
+
+/// Sends SIGTERM to `pid` on the server, which makes `git` remove the partial
+/// clone and exit.
+fn stop_remote_process(client: Entity<RemoteClient>, pid: u32, cx: &mut App) {
+    let command = client.read(cx).build_command_with_options(
+        Some("kill".to_string()),
+        &[pid.to_string()],
+        &Default::default(),
+        None,
+        None,
+        Interactive::No,
+    );
+    cx.background_spawn(async move {
+        let command = command?;
+        util::command::new_command(command.program)
+            .args(command.args)
+            .envs(command.env)
+            .stdin(util::command::Stdio::null())
+            .stdout(util::command::Stdio::null())
+            .stderr(util::command::Stdio::null())
+            .status()
+            .await?;
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Records `paths` as a project of the server at `index` in the settings file.
+fn add_project_to_server(fs: Arc<dyn Fs>, index: ServerIndex, paths: Vec<String>, cx: &mut App) {
+    update_settings_file(fs, cx, move |settings, _| match index {
+        ServerIndex::Ssh(index) => {
+            if let Some(server) = settings
+                .remote
+                .ssh_connections
+                .as_mut()
+                .and_then(|connections| connections.get_mut(index.0))
+            {
+                server.projects.insert(RemoteProject { paths });
+            };
+        }
+        ServerIndex::Wsl(index) => {
+            if let Some(server) = settings
+                .remote
+                .wsl_connections
+                .as_mut()
+                .and_then(|connections| connections.get_mut(index.0))
+            {
+                server.projects.insert(RemoteProject { paths });
+            };
+        }
+    });
+}
+
+/// Opens `paths` in an already connected remote `project`, either in a new
+/// window or in the modal's window.
+async fn open_connected_project(
+    create_new_window: bool,
+    connection: RemoteConnectionOptions,
+    project: Entity<Project>,
+    paths: Vec<PathBuf>,
+    app_state: Arc<AppState>,
+    cx: &mut AsyncWindowContext,
+) -> Option<(
+    WindowHandle<MultiWorkspace>,
+    Option<Vec<Option<Box<dyn ItemHandle>>>>,
+)> {
+    let window = if create_new_window {
+        let options = cx
+            .update(|_, cx| (app_state.build_window_options)(None, cx))
+            .log_err()?;
+        cx.open_window(options, |window, cx| {
+            let workspace = cx.new(|cx| {
+                telemetry::event!("SSH Project Created");
+                Workspace::new(None, project.clone(), app_state.clone(), window, cx)
+            });
+            cx.new(|cx| MultiWorkspace::new(workspace, window, cx))
+        })
+        .log_err()
+    } else {
+        cx.window_handle().downcast::<MultiWorkspace>()
+    }?;
+
+    let items = open_remote_project_with_existing_connection(
+        connection, project, paths, app_state, window, None, None, cx,
+    )
+    .await
+    .log_err();
+    Some((window, items))
+}
+
+fn get_text(element: &Entity<Editor>, cx: &mut App) -> String {
+    element.read(cx).text(cx).trim().to_string()
+}
+
+impl ModalView for RemoteServerProjects {
+    fn on_before_dismiss(
+        &mut self,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) -> DismissDecision {
+        DismissDecision::Dismiss(self.allow_dismissal)
+    }
+}
+
+impl Focusable for RemoteServerProjects {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        match &self.mode {
+            Mode::ProjectPicker(picker) => picker.focus_handle(cx),
+            _ => self.focus_handle.clone(),
+        }
+    }
+}
+
+impl EventEmitter<DismissEvent> for RemoteServerProjects {}
+
+impl Render for RemoteServerProjects {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .elevation_3(cx)
+            .w(rems(34.))
+            .key_context("RemoteServerModal")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .on_action(cx.listener(Self::copy_selected_server_address))
+            .on_action(cx.listener(Self::open_selected_server_options))
+            .capture_any_mouse_down(cx.listener(|this, _, window, cx| {
+                this.focus_handle(cx).focus(window, cx);
+            }))
+            .on_mouse_down_out(cx.listener(|this, _, _, cx| {
+                if matches!(this.mode, Mode::Default(_)) {
+                    cx.emit(DismissEvent)
                 }
             }))
-            .into_any_element()
+            .child(match &self.mode {
+                Mode::Default(state) => self
+                    .render_default(state.clone(), window, cx)
+                    .into_any_element(),
+                Mode::ViewServerOptions(state) => self
+                    .render_view_options(state.clone(), window, cx)
+                    .into_any_element(),
+                Mode::ProjectPicker(element) => element.clone().into_any_element(),
+                Mode::CreateRemoteServer(state) => self
+                    .render_create_remote_server(state, window, cx)
+                    .into_any_element(),
+                Mode::CreateRemoteDevContainer(state) => self
+                    .render_create_dev_container(state, window, cx)
+                    .into_any_element(),
+                Mode::EditNickname(state) => self
+                    .render_edit_nickname(state, window, cx)
+                    .into_any_element(),
+                Mode::EditKeepalive(state) => self
+                    .render_edit_keepalive(state, window, cx)
+                    .into_any_element(),
+                Mode::EditShell(state) => {
+                    self.render_edit_shell(state, window, cx).into_any_element()
+                }
+                Mode::EditEnv(state) => self.render_edit_env(state, window, cx).into_any_element(),
+                Mode::EditDevContainerUser(state) => self
+                    .render_edit_dev_container_user(state, cx)
+                    .into_any_element(),
+                Mode::EditTag(state) => self.render_edit_tag(state, window, cx).into_any_element(),
+                Mode::CloneRepository(state) => self
+                    .render_clone_repository(state, window, cx)
+                    .into_any_element(),
+                Mode::ConfirmSettingsChanges(state) => self
+                    .render_confirm_settings_changes(state, window, cx)
+                    .into_any_element(),
+                #[cfg(target_os = "windows")]
+                Mode::AddWslDistro(state) => self
+                    .render_add_wsl_distro(state, window, cx)
+                    .into_any_element(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ssh_config::SshConfigEntry;
+    use gpui::VisualTestContext;
+    use util::path;
+
+    #[test]
+    fn test_most_recent_project() {
+        let ssh = |host: &str, username: Option<&str>| {
+            RemoteConnectionOptions::Ssh(SshConnectionOptions {
+                host: host.into(),
+                username: username.map(ToString::to_string),
+                ..SshConnectionOptions::default()
+            })
+        };
+        let project = |paths: &[&str]| RemoteProject {
+            paths: paths.iter().map(ToString::to_string).collect(),
+        };
+        let api = project(&["/srv/api"]);
+        let web = project(&["/srv/web", "/srv/shared"]);
+        let projects = [api.clone(), web.clone()];
+        let server = ssh("example.com", Some("deploy"));
+
+        // History is newest first, and skips other hosts and users as well
+        // as projects that are no longer saved.
+        let history = vec![
+            (
+                ssh("other.example.com", Some("deploy")),
+                vec!["/srv/api".into()],
+            ),
+            (ssh("example.com", Some("root")), vec!["/srv/api".into()]),
+            (server.clone(), vec!["/srv/removed".into()]),
+            (
+                server.clone(),
+                vec!["/srv/shared".into(), "/srv/web".into()],
+            ),
+            (server.clone(), vec!["/srv/api".into()]),
+        ];
+        assert_eq!(most_recent_project(&history, &server, &projects), Some(web));
+
+        // Without history, only a server with a single project has an
+        // obvious one to open.
+        assert_eq!(most_recent_project(&[], &server, &projects), None);
+        assert_eq!(most_recent_project(&[], &server, &[api.clone()]), Some(api));
+        assert_eq!(most_recent_project(&[], &server, &[]), None);
+    }
+
+    #[test]
+    fn test_clone_progress_collapses_carriage_returns() {
+        let mut progress = CloneProgress::default();
+        progress.push(b"Cloning into 'zed'...\nReceiving objects:  10% (1/10)\r");
+        assert_eq!(
+            progress.lines(),
+            ["Cloning into 'zed'...", "Receiving objects:  10% (1/10)"]
+        );
+
+        // Updates arrive split across reads and replace the progress line.
+        progress.push(b"Receiving obj");
+        progress.push(b"ects:  50% (5/10)\rReceiving objects: 100% (10/10), done.\n");
+        assert_eq!(
+            progress.lines(),
+            [
+                "Cloning into 'zed'...",
+                "Receiving objects: 100% (10/10), done."
+            ]
+        );
+
+        // A Windows line ending doesn't add an empty line.
+        progress.push(b"Resolving deltas: 100%\r\nfatal: unterminated");
+        progress.finish();
+        assert_eq!(
+            progress.lines(),
+            [
+                "Cloning into 'zed'...",
+                "Receiving objects: 100% (10/10), done.",
+                "Resolving deltas: 100%",
+                "fatal: unterminated"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_connection_test_failure_from_exit_status() {
+        let ssh_failure = |exit_code, stderr: &str| {
+            anyhow::Error::new(SshConnectFailure {
+                exit_code,
+                stderr: stderr.to_string(),
+            })
+            .context("Failed to connect to host")
+        };
+
+        assert_eq!(
+            ConnectionTestFailure::categorize(&ssh_failure(
+                Some(255),
+                "user@host: Permission denied (publickey)."
+            )),
+            ConnectionTestFailure::Authentication
+        );
+        assert_eq!(
+            ConnectionTestFailure::categorize(&ssh_failure(
+                Some(255),
+                "ssh: Could not resolve hostname nowhere: Name or service not known"
+            )),
+            ConnectionTestFailure::Unreachable
+        );
+        assert_eq!(
+            ConnectionTestFailure::categorize(&ssh_failure(
+                Some(255),
+                "Host key verification failed."
+            )),
+            ConnectionTestFailure::HostKey
+        );
+        // Not ssh's own failure, so its output isn't one of ssh's diagnostics.
+        assert_eq!(
+            ConnectionTestFailure::categorize(&ssh_failure(Some(1), "permission denied")),
+            ConnectionTestFailure::Other
+        );
+        assert_eq!(
+            ConnectionTestFailure::categorize(&anyhow::anyhow!("permission denied")),
+            ConnectionTestFailure::Other
+        );
+
+        assert_eq!(ConnectionTestFailure::from_check_status(Some(0)), Ok(()));
+        assert_eq!(
+            ConnectionTestFailure::from_check_status(Some(255)),
+            Err(ConnectionTestFailure::Unreachable)
+        );
+        assert_eq!(
+            ConnectionTestFailure::from_check_status(Some(127)),
+            Err(ConnectionTestFailure::RemoteCommand)
+        );
+        assert_eq!(
+            ConnectionTestFailure::from_check_status(None),
+            Err(ConnectionTestFailure::RemoteCommand)
+        );
+    }
+
+    #[gpui::test]
+    async fn test_edit_nickname_validation(cx: &mut gpui::TestAppContext) {
+        let app_state = cx.update(|cx| {
+            let state = AppState::test(cx);
+            crate::init(cx);
+            editor::init(cx);
+            state
+        });
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.remote.ssh_connections = Some(vec![
+                        SshConnection {
+                            host: "db-1".into(),
+                            nickname: Some("Database".into()),
+                            ..SshConnection::default()
+                        },
+                        SshConnection {
+                            host: "web-1".into(),
+                            ..SshConnection::default()
+                        },
+                        SshConnection {
+                            host: "db-1".into(),
+                            args: vec!["-A".into()],
+                            ..SshConnection::default()
+                        },
+                    ]);
+                });
+            });
+        });
+        let (modal, cx) = cx.add_window_view(|window, cx| {
+            RemoteServerProjects::new(
+                false,
+                app_state.fs.clone(),
+                window,
+                WeakEntity::new_invalid(),
+                cx,
+            )
+        });
+
+        modal.update_in(cx, |modal, window, cx| {
+            let web = EditNicknameState::new(SshServerIndex(1), window, cx);
+            assert_eq!(
+                web.validate_nickname("database", cx),
+                Err("\"database\" is already the nickname of db-1".into())
+            );
+            assert_eq!(web.validate_nickname("Web", cx), Ok(()));
+            // Another connection to the same server may share the nickname.
+            let same_server = EditNicknameState::new(SshServerIndex(2), window, cx);
+            assert_eq!(same_server.validate_nickname("Database", cx), Ok(()));
+
+            web.editor
+                .update(cx, |editor, cx| editor.set_text("database", window, cx));
+            modal.mode = Mode::EditNickname(web);
+            modal.confirm(&menu::Confirm, window, cx);
+        });
+        let error = |modal: &Entity<RemoteServerProjects>, cx: &mut VisualTestContext| {
+            modal.read_with(cx, |modal, _| match &modal.mode {
+                Mode::EditNickname(state) => state.error.clone(),
+                _ => panic!("expected to still be editing the nickname"),
+            })
+        };
+        assert!(error(&modal, cx).is_some());
+
+        modal.update_in(cx, |modal, window, cx| {
+            if let Mode::EditNickname(state) = &modal.mode {
+                state
+                    .editor
+                    .clone()
+                    .update(cx, |editor, cx| editor.insert(" 2", window, cx));
+            }
+        });
+        cx.run_until_parked();
+        assert_eq!(error(&modal, cx), None);
+    }
+
+    #[test]
+    fn test_quick_connect_command() {
+        assert_eq!(
+            quick_connect_command("  ssh -J bastion host -L 5432:db:5432\n").as_deref(),
+            Some("ssh -J bastion host -L 5432:db:5432")
+        );
+        assert_eq!(quick_connect_command("user@host"), None);
+        assert_eq!(quick_connect_command("ssh://user@host:2222"), None);
+    }
+
+    #[test]
+    fn test_update_ssh_connection_from_form() {
+        let mut connection = SshConnection {
+            host: "old-host".into(),
+            port: Some(2222),
+            args: vec!["-A".into()],
+            nickname: Some("Database".into()),
+            tag: Some("prod".into()),
+            shell: Some("/bin/zsh".into()),
+            remote_os: Some("linux".into()),
+            original_command: Some("ssh -A old-host -p 2222".into()),
+            ..SshConnection::default()
+        };
+        let command = "ssh -J bastion host -L 5432:db:5432";
+        let connection_options = parse_ssh_address(command).unwrap();
+        update_ssh_connection_from_form(
+            &mut connection,
+            connection_options,
+            None,
+            quick_connect_command(command),
+        );
+
+        assert_eq!(connection.host, "host");
+        assert_eq!(connection.port, None);
+        assert_eq!(connection.args, ["-J", "bastion"]);
+        assert_eq!(connection.port_forwards.as_ref().map(Vec::len), Some(1));
+        assert_eq!(connection.original_command.as_deref(), Some(command));
+        // What the command doesn't say is kept.
+        assert_eq!(connection.nickname.as_deref(), Some("Database"));
+        assert_eq!(connection.tag.as_deref(), Some("prod"));
+        assert_eq!(connection.shell.as_deref(), Some("/bin/zsh"));
+        assert_eq!(connection.remote_os.as_deref(), Some("linux"));
+
+        // Editing the command into a bare host leaves no command behind to
+        // contradict the saved fields.
+        update_ssh_connection_from_form(
+            &mut connection,
+            parse_ssh_address("other-host").unwrap(),
+            None,
+            quick_connect_command("other-host"),
+        );
+        assert_eq!(connection.host, "other-host");
+        assert_eq!(connection.original_command, None);
+    }
+
+    #[test]
+    fn test_parse_pasted_ssh_command() {
+        let options = parse_ssh_address("ssh user@example.com -p 2222\n").unwrap();
+        assert_eq!(options.host.to_string(), "example.com");
+        assert_eq!(options.username.as_deref(), Some("user"));
+        assert_eq!(options.port, Some(2222));
+
+        let options = parse_ssh_address("  \n  example.com  \n\n").unwrap();
+        assert_eq!(options.host.to_string(), "example.com");
+
+        assert!(parse_ssh_address("ssh user@example.com\nls -la").is_err());
+        assert!(parse_ssh_address("user@example.com -p 22 22").is_err());
+        assert!(parse_ssh_address("user@example.com -p port").is_err());
+        assert!(parse_ssh_address("user@example.com -p0").is_err());
+    }
+
+    #[test]
+    fn test_parse_ssh_url() {
+        let options = parse_ssh_address("ssh://user@example.com:2222/home/user").unwrap();
+        assert_eq!(options.host.to_string(), "example.com");
+        assert_eq!(options.username.as_deref(), Some("user"));
+        assert_eq!(options.port, Some(2222));
+
+        let options = parse_ssh_address("ssh://[2001:db8::1]:2200").unwrap();
+        assert_eq!(options.host.to_string(), "2001:db8::1");
+        assert_eq!(options.port, Some(2200));
+
+        let options = parse_ssh_address("ssh://example.com").unwrap();
+        assert_eq!(options.port, None);
+
+        assert!(parse_ssh_address("ssh://example.com:99999").is_err());
+        assert!(parse_ssh_address("ssh://user@").is_err());
+    }
+
+    fn ssh_connection(host: &str, nickname: Option<&str>) -> RemoteConnectionOptions {
+        RemoteConnectionOptions::Ssh(SshConnectionOptions {
+            host: host.into(),
+            username: Some("user".to_string()),
+            nickname: nickname.map(str::to_string),
+            ..SshConnectionOptions::default()
+        })
+    }
+
+    #[test]
+    fn test_find_open_remote_project() {
+        let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+        let open_workspaces = || {
+            vec![
+                (
+                    "first window",
+                    ssh_connection("example.com", Some("work")),
+                    paths(&["/home/user/api", "/home/user/web"]),
+                ),
+                (
+                    "second window",
+                    ssh_connection("other.com", None),
+                    paths(&["/home/user/api"]),
+                ),
+            ]
+        };
+
+        // A different nickname, path order, and trailing or repeated
+        // separators still find the open project.
+        assert_eq!(
+            find_open_remote_project(
+                open_workspaces(),
+                &ssh_connection("example.com", None),
+                &paths(&["/home/user//web/", "/home/user/./api"]),
+            ),
+            Some("first window")
+        );
+        assert_eq!(
+            find_open_remote_project(
+                open_workspaces(),
+                &ssh_connection("other.com", Some("other")),
+                &paths(&["/home/user/api"]),
+            ),
+            Some("second window")
+        );
+
+        // A subset of an open project's paths is a different project.
+        assert_eq!(
+            find_open_remote_project(
+                open_workspaces(),
+                &ssh_connection("example.com", None),
+                &paths(&["/home/user/api"]),
+            ),
+            None
+        );
+        // So is the same path on another server.
+        assert_eq!(
+            find_open_remote_project(
+                open_workspaces(),
+                &ssh_connection("third.com", None),
+                &paths(&["/home/user/api"]),
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_server_address() {
+        let ssh = |username: Option<&str>, port: Option<u16>| {
+            RemoteConnectionOptions::Ssh(SshConnectionOptions {
+                host: "example.com".into(),
+                username: username.map(str::to_string),
+                port,
+                ..SshConnectionOptions::default()
+            })
+        };
+        assert_eq!(server_address(&ssh(None, None)), "example.com");
+        assert_eq!(
+            server_address(&ssh(Some("user"), Some(2222))),
+            "user@example.com:2222"
+        );
+
+        let wsl = RemoteConnectionOptions::Wsl(WslConnectionOptions {
+            distro_name: "Ubuntu".to_string(),
+            user: Some("user".to_string()),
+            env: Default::default(),
+        });
+        assert_eq!(server_address(&wsl), "Ubuntu");
+    }
+
+    #[test]
+    fn test_parse_host_with_trailing_dot() {
+        let options = parse_ssh_address("user@example.com.\n").unwrap();
+        assert_eq!(options.host.to_string(), "example.com");
+        assert_eq!(options.username.as_deref(), Some("user"));
+
+        assert!(parse_ssh_address("user@.").is_err());
+        assert!(parse_ssh_address("'my host'").is_err());
+        assert!(parse_ssh_address("'a user'@example.com").is_err());
+    }
+
+    #[gpui::test]
+    fn test_server_list_entries_follow_shown_rows(cx: &mut gpui::TestAppContext) {
+        cx.update(|cx| {
+            AppState::test(cx);
+            crate::init(cx);
+            editor::init(cx);
+        });
+        let source: Arc<Path> = Arc::from(Path::new("/home/user/.ssh/config"));
+        let ssh_config_servers = vec![SshConfigHostGroup {
+            source: source.clone(),
+            hosts: (0..300)
+                .map(|ix| SshConfigEntry {
+                    host: format!("host-{ix}"),
+                    via_proxy: false,
+                    proxy_jump: None,
+                })
+                .collect(),
+        }];
+
+        cx.update(|cx| {
+            let mut list_entries = ServerListEntries::default();
+            let state = DefaultState::new(&ssh_config_servers, None, "", &mut list_entries, cx);
+            // The four actions, the group's header, its first page of hosts,
+            // and the rows that show more of them and save them all.
+            assert_eq!(state.servers.len(), SSH_CONFIG_HOSTS_PAGE_SIZE);
+            assert_eq!(list_entries.len(), SSH_CONFIG_HOSTS_PAGE_SIZE + 7);
+            assert_eq!(
+                state.ssh_config_groups[0].hidden_host_count,
+                300 - SSH_CONFIG_HOSTS_PAGE_SIZE
+            );
+
+            // Rebuilding reuses the entries instead of adding to them.
+            let rebuilt = DefaultState::new(&ssh_config_servers, None, "", &mut list_entries, cx);
+            assert_eq!(list_entries.len(), SSH_CONFIG_HOSTS_PAGE_SIZE + 7);
+            assert_eq!(
+                rebuilt.add_new_server.focus_handle,
+                state.add_new_server.focus_handle
+            );
+
+            // Showing more hosts only adds entries for the next page.
+            list_entries
+                .shown_ssh_config_hosts
+                .insert(source.clone(), 2 * SSH_CONFIG_HOSTS_PAGE_SIZE);
+            let more = DefaultState::new(&ssh_config_servers, None, "", &mut list_entries, cx);
+            assert_eq!(more.servers.len(), 2 * SSH_CONFIG_HOSTS_PAGE_SIZE);
+            assert_eq!(list_entries.len(), 2 * SSH_CONFIG_HOSTS_PAGE_SIZE + 7);
+            let RemoteEntry::SshConfig { open_folder, .. } = &more.servers[0] else {
+                panic!("expected an SSH config host");
+            };
+            let RemoteEntry::SshConfig {
+                open_folder: first_open_folder,
+                ..
+            } = &state.servers[0]
+            else {
+                panic!("expected an SSH config host");
+            };
+            assert_eq!(open_folder.focus_handle, first_open_folder.focus_handle);
+
+            // Searching looks past the hosts that are shown.
+            let searched =
+                DefaultState::new(&ssh_config_servers, None, "host-299", &mut list_entries, cx);
+            assert_eq!(searched.servers.len(), 1);
+            assert!(searched.ssh_config_groups[0].show_more.is_none());
+
+            // The hosts of a collapsed group drop theirs.
+            cx.default_global::<CollapsedSshConfigSources>()
+                .0
+                .insert(source.clone());
+            let collapsed = DefaultState::new(&ssh_config_servers, None, "", &mut list_entries, cx);
+            assert_eq!(list_entries.len(), 5);
+            assert!(collapsed.servers.is_empty());
+            assert_eq!(
+                collapsed.ssh_config_groups[0].toggle.focus_handle,
+                state.ssh_config_groups[0].toggle.focus_handle
+            );
+        });
     }
 
-    fn create_host_from_ssh_config(
-        &mut self,
-        ssh_config_host: &SharedString,
-        cx: &mut Context<'_, Self>,
-    ) -> SshServerIndex {
-        let new_ix = Arc::new(AtomicUsize::new(0));
+    #[gpui::test]
+    fn test_dev_container_management_entries(cx: &mut gpui::TestAppContext) {
+        cx.update(|cx| {
+            AppState::test(cx);
+            crate::init(cx);
+            editor::init(cx);
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.remote.dev_container_connections = Some(vec![
+                        DevContainerConnection {
+                            name: "api".to_string(),
+                            container_id: "1111".to_string(),
+                            ..DevContainerConnection::default()
+                        },
+                        DevContainerConnection {
+                            name: "web".to_string(),
+                            container_id: "2222".to_string(),
+                            ..DevContainerConnection::default()
+                        },
+                    ]);
+                });
+            });
+        });
 
-        let update_new_ix = new_ix.clone();
-        self.update_settings_file(cx, move |settings, _| {
-            update_new_ix.store(
-                settings
-                    .ssh_connections
-                    .as_ref()
-                    .map_or(0, |connections| connections.len()),
-                atomic::Ordering::Release,
+        cx.update(|cx| {
+            let mut list_entries = ServerListEntries::default();
+            let state = DefaultState::new(&[], None, "", &mut list_entries, cx);
+            assert!(
+                state
+                    .dev_containers
+                    .iter()
+                    .all(|container| container.remove.is_none())
+            );
+
+            // Only the managed container shows its actions, and the rows
+            // shown before keep their entries.
+            list_entries
+                .managed_dev_containers
+                .insert("2222".to_string());
+            let managed = DefaultState::new(&[], None, "", &mut list_entries, cx);
+            assert!(managed.dev_containers[0].remove.is_none());
+            assert!(managed.dev_containers[1].remove.is_some());
+            assert_eq!(
+                managed.dev_containers[1].manage.focus_handle,
+                state.dev_containers[1].manage.focus_handle
             );
         });
+    }
 
-        self.add_ssh_server(
-            SshConnectionOptions {
-                host: ssh_config_host.to_string().into(),
-                ..SshConnectionOptions::default()
-            },
-            cx,
-        );
-        self.mode = Mode::default_mode(&self.ssh_config_servers, cx);
-        SshServerIndex(new_ix.load(atomic::Ordering::Acquire))
+    #[gpui::test]
+    fn test_server_list_entries_follow_server_identity(cx: &mut gpui::TestAppContext) {
+        let set_servers = |hosts: &[&str], cx: &mut App| {
+            let servers = hosts
+                .iter()
+                .map(|host| SshConnection {
+                    host: host.to_string(),
+                    ..SshConnection::default()
+                })
+                .collect();
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.remote.ssh_connections = Some(servers);
+                });
+            });
+        };
+        let open_folder = |state: &DefaultState, ix: usize| match &state.servers[ix] {
+            RemoteEntry::Project { open_folder, .. } => open_folder.focus_handle.clone(),
+            _ => panic!("expected a saved server"),
+        };
+        cx.update(|cx| {
+            AppState::test(cx);
+            crate::init(cx);
+            editor::init(cx);
+            set_servers(&["alpha", "beta"], cx);
+
+            let mut list_entries = ServerListEntries::default();
+            let state = DefaultState::new(&[], None, "", &mut list_entries, cx);
+            let beta = open_folder(&state, 1);
+
+            // Removing the server before it moves the row up, along with its
+            // entry.
+            set_servers(&["beta"], cx);
+            let state = DefaultState::new(&[], None, "", &mut list_entries, cx);
+            assert_eq!(open_folder(&state, 0), beta);
+
+            // Servers that connect to the same host still get entries of
+            // their own.
+            set_servers(&["beta", "beta"], cx);
+            let state = DefaultState::new(&[], None, "", &mut list_entries, cx);
+            assert_eq!(open_folder(&state, 0), beta);
+            assert_ne!(open_folder(&state, 1), beta);
+        });
     }
-}
 
-fn spawn_ssh_config_watch(fs: Arc<dyn Fs>, cx: &Context<RemoteServerProjects>) -> Task<()> {
-    enum ConfigSource {
-        User(String),
-        Global(String),
+    #[gpui::test]
+    fn test_server_tag_groups(cx: &mut gpui::TestAppContext) {
+        cx.update(|cx| {
+            AppState::test(cx);
+            crate::init(cx);
+            editor::init(cx);
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    let server = |host: &str, tag: Option<&str>| SshConnection {
+                        host: host.into(),
+                        tag: tag.map(ToString::to_string),
+                        ..SshConnection::default()
+                    };
+                    settings.remote.ssh_connections = Some(vec![
+                        server("db-1", Some("prod")),
+                        server("sandbox", None),
+                        server("web-staging", Some("Staging")),
+                        server("web-1", Some(" prod ")),
+                        server("scratch", Some("  ")),
+                    ]);
+                });
+            });
+        });
+        let rows = |state: &DefaultState| {
+            state
+                .saved_server_rows()
+                .into_iter()
+                .map(|row| match row {
+                    SavedServerRow::TagHeader(_, group) => format!(
+                        "{} ({})",
+                        group.tag.as_deref().unwrap_or("Other"),
+                        group.server_count
+                    ),
+                    SavedServerRow::Server(_, server) => match &*server.connection() {
+                        Connection::Ssh(connection) => format!("  {}", connection.host),
+                        _ => panic!("expected an SSH server"),
+                    },
+                })
+                .collect::<Vec<_>>()
+        };
+
+        cx.update(|cx| {
+            let mut list_entries = ServerListEntries::default();
+            let state = DefaultState::new(&[], None, "", &mut list_entries, cx);
+            // Tags are trimmed, and blank ones count as untagged.
+            assert_eq!(
+                rows(&state),
+                [
+                    "prod (2)",
+                    "  db-1",
+                    "  web-1",
+                    "Staging (1)",
+                    "  web-staging",
+                    "Other (2)",
+                    "  sandbox",
+                    "  scratch",
+                ]
+            );
+            assert_eq!(
+                state.servers[0].saved_connection().and_then(connection_tag),
+                Some("prod")
+            );
+
+            // A collapsed group keeps its header and hides its servers.
+            cx.default_global::<CollapsedServerTags>()
+                .0
+                .insert(Some("prod".into()));
+            let collapsed = DefaultState::new(&[], None, "", &mut list_entries, cx);
+            assert_eq!(
+                rows(&collapsed),
+                [
+                    "prod (2)",
+                    "Staging (1)",
+                    "  web-staging",
+                    "Other (2)",
+                    "  sandbox",
+                    "  scratch",
+                ]
+            );
+        });
+
+        // Without any tags, the servers aren't grouped.
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.remote.ssh_connections = Some(vec![SshConnection {
+                        host: "db-1".into(),
+                        ..SshConnection::default()
+                    }]);
+                });
+            });
+            let mut list_entries = ServerListEntries::default();
+            let state = DefaultState::new(&[], None, "", &mut list_entries, cx);
+            assert!(state.tag_groups.is_empty());
+            assert_eq!(rows(&state), ["  db-1"]);
+        });
     }
 
-    let mut streams = Vec::new();
-    let mut tasks = Vec::new();
+    #[gpui::test]
+    async fn test_edit_dev_container_tag(cx: &mut gpui::TestAppContext) {
+        let app_state = cx.update(|cx| {
+            let state = AppState::test(cx);
+            crate::init(cx);
+            editor::init(cx);
+            state
+        });
+        let connections = serde_json::json!([
+            { "name": "api", "container_id": "1111" },
+            { "name": "web", "container_id": "2222", "tag": "dev" },
+        ]);
+        app_state
+            .fs
+            .as_fake()
+            .insert_file(
+                paths::settings_file(),
+                serde_json::json!({ "dev_container_connections": connections })
+                    .to_string()
+                    .into_bytes(),
+            )
+            .await;
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.remote.dev_container_connections =
+                        serde_json::from_value(connections).ok();
+                });
+            });
+        });
 
-    // Setup User Watcher
-    let user_path = user_ssh_config_file();
-    info!("SSH: Watching User Config at: {:?}", user_path);
+        let project = Project::test(app_state.fs.clone(), [], cx).await;
+        let window = cx.add_window(|window, cx| MultiWorkspace::test_new(project, window, cx));
+        let workspace = window
+            .read_with(cx, |multi_workspace, _| multi_workspace.workspace().clone())
+            .unwrap();
+        let cx = &mut VisualTestContext::from_window(window.into(), cx);
+        let modal = workspace.update_in(cx, |workspace, window, cx| {
+            let weak_workspace = workspace.weak_handle();
+            cx.new(|cx| {
+                RemoteServerProjects::new(false, app_state.fs.clone(), window, weak_workspace, cx)
+            })
+        });
 
-    // We clone 'fs' here because we might need it again for the global watcher.
-    let (user_s, user_t) = watch_config_file(cx.background_executor(), fs.clone(), user_path);
-    streams.push(user_s.map(ConfigSource::User).boxed());
-    tasks.push(user_t);
+        // The tag row is one of the container's management actions.
+        modal.update_in(cx, |modal, window, cx| {
+            modal.toggle_dev_container_management("2222".to_string(), window, cx);
+            let Mode::Default(state) = &modal.mode else {
+                panic!("expected the server list");
+            };
+            assert!(state.dev_containers[0].edit_tag.is_none());
+            assert!(state.dev_containers[1].edit_tag.is_some());
 
-    // Setup Global Watcher
-    if let Some(gp) = global_ssh_config_file() {
-        info!("SSH: Watching Global Config at: {:?}", gp);
-        let (global_s, global_t) =
-            watch_config_file(cx.background_executor(), fs, gp.to_path_buf());
-        streams.push(global_s.map(ConfigSource::Global).boxed());
-        tasks.push(global_t);
-    } else {
-        debug!("SSH: No Global Config defined.");
+            let web = EditTagState::new(TagTarget::DevContainer(1), window, cx);
+            assert_eq!(web.editor.read(cx).text(cx), "dev");
+
+            let api = EditTagState::new(TagTarget::DevContainer(0), window, cx);
+            api.editor
+                .update(cx, |editor, cx| editor.set_text(" staging ", window, cx));
+            modal.mode = Mode::EditTag(api);
+            modal.confirm(&menu::Confirm, window, cx);
+        });
+        cx.run_until_parked();
+
+        let settings = app_state.fs.load(paths::settings_file()).await.unwrap();
+        let settings: serde_json::Value = serde_json::from_str(&settings).unwrap();
+        let connections = &settings["dev_container_connections"];
+        assert_eq!(connections[0]["tag"], "staging");
+        assert_eq!(connections[1]["tag"], "dev");
     }
 
-    // Combine into a single stream so that only one is parsed at once.
-    let mut merged_stream = futures::stream::select_all(streams);
+    #[gpui::test]
+    async fn test_edit_server_env(cx: &mut gpui::TestAppContext) {
+        let app_state = cx.update(|cx| {
+            let state = AppState::test(cx);
+            crate::init(cx);
+            editor::init(cx);
+            state
+        });
+        let connections = serde_json::json!([
+            { "host": "build-1", "env": { "HTTP_PROXY": "http://proxy:3128" } },
+            { "host": "win-1", "remote_os": "windows", "env": { "HTTP_PROXY": "http://proxy:3128" } },
+        ]);
+        app_state
+            .fs
+            .as_fake()
+            .insert_file(
+                paths::settings_file(),
+                serde_json::json!({ "ssh_connections": connections })
+                    .to_string()
+                    .into_bytes(),
+            )
+            .await;
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.remote.ssh_connections = serde_json::from_value(connections).ok();
+                });
+            });
+        });
 
-    cx.spawn(async move |remote_server_projects, cx| {
-        let _tasks = tasks; // Keeps the background watchers alive
-        let mut global_hosts = BTreeSet::default();
-        let mut user_hosts = BTreeSet::default();
+        let project = Project::test(app_state.fs.clone(), [], cx).await;
+        let window = cx.add_window(|window, cx| MultiWorkspace::test_new(project, window, cx));
+        let workspace = window
+            .read_with(cx, |multi_workspace, _| multi_workspace.workspace().clone())
+            .unwrap();
+        let cx = &mut VisualTestContext::from_window(window.into(), cx);
+        let modal = workspace.update_in(cx, |workspace, window, cx| {
+            let weak_workspace = workspace.weak_handle();
+            cx.new(|cx| {
+                RemoteServerProjects::new(false, app_state.fs.clone(), window, weak_workspace, cx)
+            })
+        });
 
-        while let Some(event) = merged_stream.next().await {
-            match event {
-                ConfigSource::Global(content) => {
-                    global_hosts = parse_ssh_config_hosts(&content);
-                }
-                ConfigSource::User(content) => {
-                    user_hosts = parse_ssh_config_hosts(&content);
-                }
-            }
+        let build = ServerIndex::Ssh(SshServerIndex(0));
+        let windows = ServerIndex::Ssh(SshServerIndex(1));
+        modal.update_in(cx, |modal, window, cx| {
+            assert!(
+                effective_env_description(build, cx)
+                    .is_some_and(|names| names.split(", ").any(|name| name == "HTTP_PROXY"))
+            );
+            assert_eq!(
+                effective_env_description(windows, cx).as_deref(),
+                Some("Not set on Windows hosts")
+            );
 
-            // Sync to Model
-            if remote_server_projects
-                .update(cx, |project, cx| {
-                    project.ssh_config_servers = global_hosts
-                        .iter()
-                        .chain(user_hosts.iter())
-                        .map(SharedString::from)
-                        .collect();
-                    cx.notify();
-                })
-                .is_err()
-            {
-                return;
-            }
-        }
-    })
-}
+            // The saved variables, then an empty row for adding one.
+            let state = EditEnvState::new(build, window, cx);
+            assert_eq!(state.rows.len(), 2);
+            assert_eq!(
+                state.parse(cx),
+                Ok(BTreeMap::from_iter([(
+                    "HTTP_PROXY".to_string(),
+                    "http://proxy:3128".to_string()
+                )]))
+            );
 
-fn get_text(element: &Entity<Editor>, cx: &mut App) -> String {
-    element.read(cx).text(cx).trim().to_string()
-}
+            let set_row =
+                |row: &EnvVarRow, name: &str, value: &str, window: &mut Window, cx: &mut App| {
+                    row.name
+                        .update(cx, |editor, cx| editor.set_text(name, window, cx));
+                    row.value
+                        .update(cx, |editor, cx| editor.set_text(value, window, cx));
+                };
+            set_row(&state.rows[1], "BAD-NAME", "1", window, cx);
+            assert_eq!(
+                state.parse(cx),
+                Err(
+                    "BAD-NAME: The variable name can only contain letters, digits, and underscores."
+                        .into()
+                )
+            );
+            set_row(&state.rows[1], "", "a\nb", window, cx);
+            assert_eq!(
+                state.parse(cx),
+                Err("The variable name can't be empty.".into())
+            );
+            set_row(
+                &state.rows[1],
+                "HTTP_PROXY",
+                "http://other:3128",
+                window,
+                cx,
+            );
+            assert_eq!(
+                state.parse(cx),
+                Err("HTTP_PROXY is set more than once.".into())
+            );
 
-impl ModalView for RemoteServerProjects {
-    fn on_before_dismiss(
-        &mut self,
-        _window: &mut Window,
-        _cx: &mut Context<Self>,
-    ) -> DismissDecision {
-        DismissDecision::Dismiss(self.allow_dismissal)
+            // An invalid variable keeps the editor open with the error.
+            modal.mode = Mode::EditEnv(state);
+            modal.confirm(&menu::Confirm, window, cx);
+            let Mode::EditEnv(state) = &modal.mode else {
+                panic!("expected to still be editing the environment");
+            };
+            assert!(state.error.is_some());
+
+            set_row(&state.rows[0], "", "", window, cx);
+            set_row(
+                &state.rows[1],
+                "SSL_CERT_FILE",
+                "/etc/ssl/bundle.pem",
+                window,
+                cx,
+            );
+            modal.confirm(&menu::Confirm, window, cx);
+            assert!(!matches!(modal.mode, Mode::EditEnv(_)));
+        });
+        cx.run_until_parked();
+
+        let settings = app_state.fs.load(paths::settings_file()).await.unwrap();
+        let settings: serde_json::Value = serde_json::from_str(&settings).unwrap();
+        let connections = &settings["ssh_connections"];
+        assert_eq!(
+            connections[0]["env"],
+            serde_json::json!({ "SSL_CERT_FILE": "/etc/ssl/bundle.pem" })
+        );
+        assert_eq!(
+            connections[1]["env"],
+            serde_json::json!({ "HTTP_PROXY": "http://proxy:3128" })
+        );
     }
-}
 
-impl Focusable for RemoteServerProjects {
-    fn focus_handle(&self, cx: &App) -> FocusHandle {
-        match &self.mode {
-            Mode::ProjectPicker(picker) => picker.focus_handle(cx),
-            _ => self.focus_handle.clone(),
-        }
+    #[gpui::test]
+    async fn test_change_dev_container_engine(cx: &mut gpui::TestAppContext) {
+        let app_state = cx.update(|cx| {
+            let state = AppState::test(cx);
+            crate::init(cx);
+            editor::init(cx);
+            state
+        });
+        let connections = serde_json::json!([
+            { "name": "api", "container_id": "1111" },
+        ]);
+        app_state
+            .fs
+            .as_fake()
+            .insert_file(
+                paths::settings_file(),
+                serde_json::json!({ "dev_container_connections": connections })
+                    .to_string()
+                    .into_bytes(),
+            )
+            .await;
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.remote.dev_container_connections =
+                        serde_json::from_value(connections).ok();
+                });
+            });
+        });
+
+        let project = Project::test(app_state.fs.clone(), [], cx).await;
+        let window = cx.add_window(|window, cx| MultiWorkspace::test_new(project, window, cx));
+        let workspace = window
+            .read_with(cx, |multi_workspace, _| multi_workspace.workspace().clone())
+            .unwrap();
+        let cx = &mut VisualTestContext::from_window(window.into(), cx);
+        let modal = workspace.update_in(cx, |workspace, window, cx| {
+            let weak_workspace = workspace.weak_handle();
+            cx.new(|cx| {
+                RemoteServerProjects::new(false, app_state.fs.clone(), window, weak_workspace, cx)
+            })
+        });
+
+        // Changing the engine is one of the container's management actions.
+        modal.update_in(cx, |modal, window, cx| {
+            let Mode::Default(state) = &modal.mode else {
+                panic!("expected the server list");
+            };
+            assert!(state.dev_containers[0].change_engine.is_none());
+            modal.toggle_dev_container_management("1111".to_string(), window, cx);
+            let Mode::Default(state) = &modal.mode else {
+                panic!("expected the server list");
+            };
+            assert!(state.dev_containers[0].change_engine.is_some());
+            modal.change_dev_container_engine(0, window, cx);
+        });
+        cx.simulate_prompt_answer("Podman");
+        cx.run_until_parked();
+
+        let settings = app_state.fs.load(paths::settings_file()).await.unwrap();
+        let settings: serde_json::Value = serde_json::from_str(&settings).unwrap();
+        assert_eq!(settings["dev_container_connections"][0]["use_podman"], true);
     }
-}
 
-impl EventEmitter<DismissEvent> for RemoteServerProjects {}
+    #[gpui::test]
+    async fn test_return_to_host_from_dev_container(cx: &mut gpui::TestAppContext) {
+        let app_state = cx.update(|cx| {
+            let state = AppState::test(cx);
+            crate::init(cx);
+            editor::init(cx);
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.remote.dev_container_connections =
+                        serde_json::from_value(serde_json::json!([{
+                            "name": "api",
+                            "container_id": "1111",
+                            "host_project_root": path!("/host/api"),
+                        }]))
+                        .ok();
+                });
+            });
+            state
+        });
+        app_state
+            .fs
+            .as_fake()
+            .insert_tree(path!("/host/api"), serde_json::json!({ "main.py": "" }))
+            .await;
 
-impl Render for RemoteServerProjects {
-    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        div()
-            .elevation_3(cx)
-            .w(rems(34.))
-            .key_context("RemoteServerModal")
-            .on_action(cx.listener(Self::cancel))
-            .on_action(cx.listener(Self::confirm))
-            .capture_any_mouse_down(cx.listener(|this, _, window, cx| {
-                this.focus_handle(cx).focus(window, cx);
-            }))
-            .on_mouse_down_out(cx.listener(|this, _, _, cx| {
-                if matches!(this.mode, Mode::Default(_)) {
-                    cx.emit(DismissEvent)
-                }
-            }))
-            .child(match &self.mode {
-                Mode::Default(state) => self
-                    .render_default(state.clone(), window, cx)
-                    .into_any_element(),
-                Mode::ViewServerOptions(state) => self
-                    .render_view_options(state.clone(), window, cx)
-                    .into_any_element(),
-                Mode::ProjectPicker(element) => element.clone().into_any_element(),
-                Mode::CreateRemoteServer(state) => self
-                    .render_create_remote_server(state, window, cx)
-                    .into_any_element(),
-                Mode::CreateRemoteDevContainer(state) => self
-                    .render_create_dev_container(state, window, cx)
-                    .into_any_element(),
-                Mode::EditNickname(state) => self
-                    .render_edit_nickname(state, window, cx)
-                    .into_any_element(),
-                #[cfg(target_os = "windows")]
-                Mode::AddWslDistro(state) => self
-                    .render_add_wsl_distro(state, window, cx)
-                    .into_any_element(),
+        let project = Project::test(app_state.fs.clone(), [], cx).await;
+        let window = cx.add_window(|window, cx| MultiWorkspace::test_new(project, window, cx));
+        let container_workspace = window
+            .read_with(cx, |multi_workspace, _| multi_workspace.workspace().clone())
+            .unwrap();
+        let cx = &mut VisualTestContext::from_window(window.into(), cx);
+
+        // Containers saved without their project folder can't go back to it.
+        let unknown = DockerConnectionOptions {
+            name: "web".to_string(),
+            container_id: "2222".to_string(),
+            ..DockerConnectionOptions::default()
+        };
+        let result = cx
+            .update(|window, cx| {
+                disconnect_dev_container_now(&unknown, container_workspace.clone(), window, cx)
+            })
+            .await;
+        assert!(result.is_err());
+
+        let options = DockerConnectionOptions {
+            name: "api".to_string(),
+            container_id: "1111".to_string(),
+            ..DockerConnectionOptions::default()
+        };
+        let replaced = cx
+            .update(|window, cx| {
+                disconnect_dev_container_now(&options, container_workspace.clone(), window, cx)
+            })
+            .await
+            .unwrap();
+        assert!(replaced);
+        cx.run_until_parked();
+
+        window
+            .read_with(cx, |multi_workspace, cx| {
+                assert!(!multi_workspace.is_workspace_retained(&container_workspace));
+                let root_paths = multi_workspace.workspace().read(cx).root_paths(cx);
+                assert_eq!(
+                    root_paths
+                        .iter()
+                        .map(|path| path.to_path_buf())
+                        .collect::<Vec<_>>(),
+                    [PathBuf::from(path!("/host/api"))]
+                );
+            })
+            .unwrap();
+    }
+
+    #[gpui::test]
+    async fn test_server_search_is_per_modal(cx: &mut gpui::TestAppContext) {
+        let app_state = cx.update(|cx| {
+            let state = AppState::test(cx);
+            crate::init(cx);
+            editor::init(cx);
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.remote.dev_container_connections = Some(vec![
+                        DevContainerConnection {
+                            name: "api".to_string(),
+                            container_id: "1111".to_string(),
+                            ..DevContainerConnection::default()
+                        },
+                        DevContainerConnection {
+                            name: "web".to_string(),
+                            container_id: "2222".to_string(),
+                            ..DevContainerConnection::default()
+                        },
+                    ]);
+                });
+            });
+            state
+        });
+
+        let project = Project::test(app_state.fs.clone(), [], cx).await;
+        let window = cx.add_window(|window, cx| MultiWorkspace::test_new(project, window, cx));
+        let workspace = window
+            .read_with(cx, |multi_workspace, _| multi_workspace.workspace().clone())
+            .unwrap();
+        let cx = &mut VisualTestContext::from_window(window.into(), cx);
+        let [searched, other] = [(); 2].map(|_| {
+            workspace.update_in(cx, |workspace, window, cx| {
+                let weak_workspace = workspace.weak_handle();
+                cx.new(|cx| {
+                    RemoteServerProjects::new(
+                        false,
+                        app_state.fs.clone(),
+                        window,
+                        weak_workspace,
+                        cx,
+                    )
+                })
             })
+        });
+
+        searched.update_in(cx, |modal, window, cx| {
+            modal
+                .server_search_editor
+                .update(cx, |editor, cx| editor.set_text("web", window, cx));
+        });
+        cx.run_until_parked();
+
+        let dev_container_names = |modal: &Entity<RemoteServerProjects>,
+                                   cx: &mut VisualTestContext| {
+            modal.update(cx, |modal, cx| {
+                let Mode::Default(state) = modal.default_mode(cx) else {
+                    panic!("expected the server list");
+                };
+                state
+                    .dev_containers
+                    .iter()
+                    .map(|container| container.connection.name.clone())
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(dev_container_names(&searched, cx), ["web"]);
+        assert_eq!(dev_container_names(&other, cx), ["api", "web"]);
     }
 }
@@ -0,0 +1,353 @@
+//! What a bulk edit of the remote connection settings changes, such as saving
+//! every host of an SSH config file at once.
+//!
+//! An edit is applied to a copy of the user's settings first, and the saved
+//! connections before and after are compared, so the user can see exactly
+//! which connections are added, removed or modified before the settings
+//! file is written.
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    sync::Arc,
+};
+
+use gpui::App;
+use serde::Serialize;
+use serde_json::Value;
+use settings::{
+    DevContainerConnection, RemoteSettingsContent, SettingsStore, SshConnection, WslConnection,
+};
+
+/// An edit of the remote settings. It's applied once to preview what it
+/// changes, and again to the settings file once the changes are confirmed,
+/// so it has to make the same change both times.
+pub(crate) type RemoteSettingsEdit = Arc<dyn Fn(&mut RemoteSettingsContent) + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum ConnectionKind {
+    Ssh,
+    Wsl,
+    DevContainer,
+}
+
+impl ConnectionKind {
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Ssh => "SSH Servers",
+            Self::Wsl => "WSL Distros",
+            Self::DevContainer => "Dev Containers",
+        }
+    }
+}
+
+/// A field of a connection that an edit changes, with its values shown as
+/// they'd appear in the settings file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FieldChange {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ChangeKind {
+    Added,
+    Removed,
+    Modified(Vec<FieldChange>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ConnectionChange {
+    /// The connection's nickname or address.
+    pub label: String,
+    pub kind: ChangeKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ConnectionChangeGroup {
+    pub kind: ConnectionKind,
+    /// Additions first, then modifications, then removals, each in the order
+    /// the connections are saved in.
+    pub changes: Vec<ConnectionChange>,
+}
+
+/// The connections an edit adds, removes or modifies, grouped by the kind of
+/// connection. Groups without changes are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct RemoteSettingsChanges {
+    pub groups: Vec<ConnectionChangeGroup>,
+}
+
+impl RemoteSettingsChanges {
+    /// What applying `edit` to the user's settings would change.
+    pub(crate) fn preview(edit: &RemoteSettingsEdit, cx: &App) -> Self {
+        let old = cx
+            .global::<SettingsStore>()
+            .raw_user_settings()
+            .map(|settings| settings.content.remote.clone())
+            .unwrap_or_default();
+        let mut new = old.clone();
+        edit(&mut new);
+        Self::between(&old, &new)
+    }
+
+    pub(crate) fn between(old: &RemoteSettingsContent, new: &RemoteSettingsContent) -> Self {
+        let groups = [
+            (
+                ConnectionKind::Ssh,
+                diff_connections(
+                    old.ssh_connections.as_deref().unwrap_or_default(),
+                    new.ssh_connections.as_deref().unwrap_or_default(),
+                ),
+            ),
+            (
+                ConnectionKind::Wsl,
+                diff_connections(
+                    old.wsl_connections.as_deref().unwrap_or_default(),
+                    new.wsl_connections.as_deref().unwrap_or_default(),
+                ),
+            ),
+            (
+                ConnectionKind::DevContainer,
+                diff_connections(
+                    old.dev_container_connections.as_deref().unwrap_or_default(),
+                    new.dev_container_connections.as_deref().unwrap_or_default(),
+                ),
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, changes)| !changes.is_empty())
+        .map(|(kind, changes)| ConnectionChangeGroup { kind, changes })
+        .collect();
+        Self { groups }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
+
+/// A saved connection, as far as comparing settings is concerned.
+trait SavedConnection: Serialize + PartialEq {
+    /// What identifies the connection across edits, so that a connection
+    /// whose other fields change is modified rather than replaced.
+    fn key(&self) -> String;
+    fn label(&self) -> String;
+}
+
+impl SavedConnection for SshConnection {
+    fn key(&self) -> String {
+        let user = self.username.as_deref().unwrap_or_default();
+        let port = self.port.map(|port| port.to_string()).unwrap_or_default();
+        format!("{user}@{}:{port}", self.host)
+    }
+
+    fn label(&self) -> String {
+        let mut address = self.host.clone();
+        if let Some(username) = &self.username {
+            address = format!("{username}@{address}");
+        }
+        if let Some(port) = self.port {
+            address = format!("{address}:{port}");
+        }
+        match &self.nickname {
+            Some(nickname) => format!("{nickname} ({address})"),
+            None => address,
+        }
+    }
+}
+
+impl SavedConnection for WslConnection {
+    fn key(&self) -> String {
+        format!(
+            "{}@{}",
+            self.user.as_deref().unwrap_or_default(),
+            self.distro_name
+        )
+    }
+
+    fn label(&self) -> String {
+        match &self.user {
+            Some(user) => format!("{} ({user})", self.distro_name),
+            None => self.distro_name.clone(),
+        }
+    }
+}
+
+impl SavedConnection for DevContainerConnection {
+    fn key(&self) -> String {
+        self.container_id.clone()
+    }
+
+    fn label(&self) -> String {
+        self.name.clone()
+    }
+}
+
+fn diff_connections<C: SavedConnection>(old: &[C], new: &[C]) -> Vec<ConnectionChange> {
+    // Connections saved more than once are matched up in order.
+    let mut matched_old = HashSet::default();
+    let mut added = Vec::new();
+    let mut modified = Vec::new();
+    for connection in new {
+        let key = connection.key();
+        let counterpart = old
+            .iter()
+            .enumerate()
+            .find(|(ix, old)| !matched_old.contains(ix) && old.key() == key);
+        match counterpart {
+            Some((ix, old)) => {
+                matched_old.insert(ix);
+                if old != connection {
+                    modified.push(ConnectionChange {
+                        label: connection.label(),
+                        kind: ChangeKind::Modified(field_changes(old, connection)),
+                    });
+                }
+            }
+            None => added.push(ConnectionChange {
+                label: connection.label(),
+                kind: ChangeKind::Added,
+            }),
+        }
+    }
+    let removed = old
+        .iter()
+        .enumerate()
+        .filter(|(ix, _)| !matched_old.contains(ix))
+        .map(|(_, connection)| ConnectionChange {
+            label: connection.label(),
+            kind: ChangeKind::Removed,
+        });
+
+    added.into_iter().chain(modified).chain(removed).collect()
+}
+
+fn field_changes(old: &impl Serialize, new: &impl Serialize) -> Vec<FieldChange> {
+    let (Ok(Value::Object(old)), Ok(Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return Vec::new();
+    };
+    let fields = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+    fields
+        .into_iter()
+        .filter(|field| old.get(*field) != new.get(*field))
+        .map(|field| FieldChange {
+            field: field.clone(),
+            old: display_value(old.get(field)),
+            new: display_value(new.get(field)),
+        })
+        .collect()
+}
+
+fn display_value(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "unset".to_string(),
+        Some(Value::String(value)) => value.clone(),
+        Some(value) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ssh(host: &str) -> SshConnection {
+        SshConnection {
+            host: host.to_string(),
+            ..SshConnection::default()
+        }
+    }
+
+    fn dev_container(container_id: &str, name: &str) -> DevContainerConnection {
+        DevContainerConnection {
+            name: name.to_string(),
+            container_id: container_id.to_string(),
+            ..DevContainerConnection::default()
+        }
+    }
+
+    #[test]
+    fn test_changes_are_grouped_by_connection_kind() {
+        let old = RemoteSettingsContent {
+            ssh_connections: Some(vec![ssh("kept"), ssh("renamed"), ssh("removed")]),
+            dev_container_connections: Some(vec![dev_container("abc", "api")]),
+            ..RemoteSettingsContent::default()
+        };
+        let mut new = old.clone();
+        if let Some(connections) = new.ssh_connections.as_mut() {
+            connections[1].nickname = Some("Build Box".to_string());
+            connections.remove(2);
+            connections.push(ssh("added"));
+        }
+        new.dev_container_connections = None;
+
+        let changes = RemoteSettingsChanges::between(&old, &new);
+        assert_eq!(
+            changes.groups,
+            vec![
+                ConnectionChangeGroup {
+                    kind: ConnectionKind::Ssh,
+                    changes: vec![
+                        ConnectionChange {
+                            label: "added".to_string(),
+                            kind: ChangeKind::Added,
+                        },
+                        ConnectionChange {
+                            label: "Build Box (renamed)".to_string(),
+                            kind: ChangeKind::Modified(vec![FieldChange {
+                                field: "nickname".to_string(),
+                                old: "unset".to_string(),
+                                new: "Build Box".to_string(),
+                            }]),
+                        },
+                        ConnectionChange {
+                            label: "removed".to_string(),
+                            kind: ChangeKind::Removed,
+                        },
+                    ],
+                },
+                ConnectionChangeGroup {
+                    kind: ConnectionKind::DevContainer,
+                    changes: vec![ConnectionChange {
+                        label: "api".to_string(),
+                        kind: ChangeKind::Removed,
+                    }],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_connections_are_matched_by_address() {
+        let mut with_port = ssh("example.com");
+        with_port.port = Some(2222);
+        let old = RemoteSettingsContent {
+            ssh_connections: Some(vec![ssh("example.com"), ssh("example.com")]),
+            ..RemoteSettingsContent::default()
+        };
+        let new = RemoteSettingsContent {
+            ssh_connections: Some(vec![ssh("example.com"), with_port]),
+            ..RemoteSettingsContent::default()
+        };
+
+        // A different port is a different server, and only one of the
+        // duplicates was removed.
+        let changes = RemoteSettingsChanges::between(&old, &new);
+        let kinds = changes.groups[0]
+            .changes
+            .iter()
+            .map(|change| (change.label.as_str(), change.kind.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                ("example.com:2222", ChangeKind::Added),
+                ("example.com", ChangeKind::Removed),
+            ]
+        );
+
+        assert!(RemoteSettingsChanges::between(&old, &old).is_empty());
+    }
+}
@@ -9,7 +9,6 @@ use picker::{
     Picker, PickerDelegate,
     highlighted_match_with_paths::{HighlightedMatch, HighlightedMatchWithPaths},
 };
-use remote::RemoteConnectionOptions;
 use settings::Settings;
 use ui::{ButtonLike, KeyBinding, ListItem, ListItemSpacing, Tooltip, prelude::*};
 use ui_input::ErasedEditor;
@@ -269,10 +268,8 @@ impl PickerDelegate for SidebarRecentProjectsDelegate {
                         requesting_window: replace_window,
                         ..Default::default()
                     };
-                    if let RemoteConnectionOptions::Ssh(connection) = &mut connection {
-                        crate::RemoteSettings::get_global(cx)
-                            .fill_connection_options_from_settings(connection);
-                    };
+                    crate::RemoteSettings::get_global(cx)
+                        .fill_remote_connection_options_from_settings(&mut connection);
                     let paths = recent_workspace.paths.paths().to_vec();
                     cx.spawn_in(window, async move |_, cx| {
                         open_remote_project(connection.clone(), paths, app_state, open_options, cx)
@@ -0,0 +1,176 @@
+/// What the system's ssh-agent can offer ssh when it authenticates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SshAgentStatus {
+    /// No agent is running, or ssh can't reach it.
+    Unavailable,
+    /// The agent is running but has no keys loaded.
+    NoIdentities,
+    HasIdentities,
+    /// The agent couldn't be asked, such as Pageant, which `ssh-add` can't
+    /// query.
+    Unknown,
+}
+
+/// Asks the system's ssh-agent whether it has keys loaded, the way
+/// `ssh-add -l` does.
+pub(crate) async fn ssh_agent_status() -> SshAgentStatus {
+    // ssh only finds an agent on Unix through this variable. On Windows it
+    // also looks for the OpenSSH agent's named pipe.
+    if cfg!(not(windows)) && std::env::var_os("SSH_AUTH_SOCK").is_none_or(|sock| sock.is_empty()) {
+        return SshAgentStatus::Unavailable;
+    }
+
+    let status = match util::command::new_command("ssh-add")
+        .arg("-l")
+        .output()
+        .await
+    {
+        Ok(output) => status_from_ssh_add_exit_code(output.status.code()),
+        Err(error) => {
+            log::warn!("failed to run ssh-add: {error}");
+            SshAgentStatus::Unknown
+        }
+    };
+    if cfg!(windows) && status == SshAgentStatus::Unavailable && pageant_is_running().await {
+        return SshAgentStatus::Unknown;
+    }
+    status
+}
+
+/// `ssh-add -l` exits with 1 when the agent has no identities, and 2 when it
+/// can't reach an agent.
+fn status_from_ssh_add_exit_code(code: Option<i32>) -> SshAgentStatus {
+    match code {
+        Some(0) => SshAgentStatus::HasIdentities,
+        Some(1) => SshAgentStatus::NoIdentities,
+        Some(2) => SshAgentStatus::Unavailable,
+        _ => SshAgentStatus::Unknown,
+    }
+}
+
+async fn pageant_is_running() -> bool {
+    util::command::new_command("tasklist")
+        .args(["/FI", "IMAGENAME eq pageant.exe", "/NH"])
+        .output()
+        .await
+        .is_ok_and(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .to_lowercase()
+                .contains("pageant.exe")
+        })
+}
+
+/// Whether ssh arguments forward the agent, with `-A` or
+/// `-o ForwardAgent=yes`. The last of them wins, as `-a` turns forwarding
+/// back off.
+pub(crate) fn forwards_agent(args: &[String]) -> bool {
+    let mut forwards = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-A" => forwards = true,
+            "-a" => forwards = false,
+            "-o" => {
+                if let Some(value) = args.next().and_then(|option| forward_agent_option(option)) {
+                    forwards = value;
+                }
+            }
+            option => {
+                if let Some(value) = option.strip_prefix("-o").and_then(forward_agent_option) {
+                    forwards = value;
+                }
+            }
+        }
+    }
+    forwards
+}
+
+/// Turns agent forwarding on or off in ssh arguments. Whatever the arguments
+/// said about it before is replaced by a single `-A` when it's on.
+pub(crate) fn set_agent_forwarding(args: &mut Vec<String>, enabled: bool) {
+    let mut kept = Vec::with_capacity(args.len() + 1);
+    let mut old_args = std::mem::take(args).into_iter();
+    while let Some(arg) = old_args.next() {
+        match arg.as_str() {
+            "-A" | "-a" => {}
+            "-o" => match old_args.next() {
+                Some(option) if forward_agent_option(&option).is_some() => {}
+                Some(option) => kept.extend([arg, option]),
+                None => kept.push(arg),
+            },
+            option
+                if option
+                    .strip_prefix("-o")
+                    .and_then(forward_agent_option)
+                    .is_some() => {}
+            _ => kept.push(arg),
+        }
+    }
+    if enabled {
+        kept.push("-A".to_string());
+    }
+    *args = kept;
+}
+
+/// The value of a `ForwardAgent` option, written as `ForwardAgent=yes` or
+/// `ForwardAgent yes`. Values other than `yes` and `no`, such as a socket
+/// path, count as forwarding.
+fn forward_agent_option(option: &str) -> Option<bool> {
+    let (key, value) = option
+        .split_once('=')
+        .or_else(|| option.split_once(char::is_whitespace))?;
+    key.trim()
+        .eq_ignore_ascii_case("ForwardAgent")
+        .then(|| !value.trim().eq_ignore_ascii_case("no"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_forwards_agent() {
+        assert!(!forwards_agent(&args(&["-i", "~/.ssh/work"])));
+        assert!(forwards_agent(&args(&["-A", "-J", "bastion"])));
+        assert!(!forwards_agent(&args(&["-A", "-a"])));
+        assert!(forwards_agent(&args(&["-o", "ForwardAgent=yes"])));
+        assert!(forwards_agent(&args(&["-oforwardagent yes"])));
+        assert!(!forwards_agent(&args(&["-A", "-o", "ForwardAgent=no"])));
+    }
+
+    #[test]
+    fn test_set_agent_forwarding() {
+        let mut connection_args = args(&["-i", "~/.ssh/work", "-o", "ForwardAgent=no", "-J", "a"]);
+        set_agent_forwarding(&mut connection_args, true);
+        assert_eq!(
+            connection_args,
+            args(&["-i", "~/.ssh/work", "-J", "a", "-A"])
+        );
+        assert!(forwards_agent(&connection_args));
+
+        set_agent_forwarding(&mut connection_args, false);
+        assert_eq!(connection_args, args(&["-i", "~/.ssh/work", "-J", "a"]));
+        assert!(!forwards_agent(&connection_args));
+    }
+
+    #[test]
+    fn test_status_from_ssh_add_exit_code() {
+        assert_eq!(
+            status_from_ssh_add_exit_code(Some(0)),
+            SshAgentStatus::HasIdentities
+        );
+        assert_eq!(
+            status_from_ssh_add_exit_code(Some(1)),
+            SshAgentStatus::NoIdentities
+        );
+        assert_eq!(
+            status_from_ssh_add_exit_code(Some(2)),
+            SshAgentStatus::Unavailable
+        );
+        assert_eq!(status_from_ssh_add_exit_code(None), SshAgentStatus::Unknown);
+    }
+}
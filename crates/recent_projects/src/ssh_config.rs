@@ -1,4 +1,10 @@
-use std::collections::BTreeSet;
+use fs::Fs;
+use futures::{FutureExt as _, StreamExt as _, future::BoxFuture};
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 const FILTERED_GIT_PROVIDER_HOSTNAMES: &[&str] = &[
     "dev.azure.com",
@@ -14,32 +20,235 @@ const FILTERED_GIT_PROVIDER_HOSTNAMES: &[&str] = &[
     "git.sr.ht",
 ];
 
-pub fn parse_ssh_config_hosts(config: &str) -> BTreeSet<String> {
-    parse_host_blocks(config)
-        .into_iter()
-        .flat_map(HostBlock::non_git_provider_hosts)
-        .collect()
+/// A host from an SSH config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshConfigEntry {
+    pub host: String,
+    /// Whether the host connects through `ProxyCommand` or `ProxyJump`, which
+    /// may be slow or prompt, so it shouldn't be probed in the background.
+    pub via_proxy: bool,
+    /// The host's `ProxyJump` chain, as written, if it sets one.
+    pub proxy_jump: Option<String>,
+}
+
+/// The deepest chain of `Include` directives that is followed, matching ssh.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A host along with the config file that defines it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshConfigHost {
+    pub entry: SshConfigEntry,
+    pub source: Arc<Path>,
+}
+
+/// The hosts defined by one SSH config file, sorted by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshConfigHostGroup {
+    pub source: Arc<Path>,
+    pub hosts: Vec<SshConfigEntry>,
+}
+
+/// Reads the hosts from an SSH config file and the files it includes, in the
+/// order ssh reads them.
+///
+/// Relative `Include` paths are resolved against the directory of `path`, so
+/// `~/.ssh` for the user's config and `/etc/ssh` for the system one, as ssh
+/// does. Wildcards are only supported in the last component of an include.
+pub async fn load_ssh_config_hosts(fs: &dyn Fs, path: &Path, content: &str) -> Vec<SshConfigHost> {
+    let Some(base_dir) = path.parent() else {
+        return Vec::new();
+    };
+    let mut hosts = Vec::new();
+    collect_hosts(
+        fs,
+        content.to_owned(),
+        Arc::from(path),
+        base_dir,
+        0,
+        &mut hosts,
+    )
+    .await;
+    hosts
+}
+
+/// Groups hosts by the file that defines them, in the order ssh reads the
+/// files. A host defined more than once is only kept in the group of its
+/// first definition, which is the one ssh uses.
+pub fn group_ssh_config_hosts<'a>(
+    hosts: impl IntoIterator<Item = &'a SshConfigHost>,
+) -> Vec<SshConfigHostGroup> {
+    let mut seen = HashSet::new();
+    let mut groups: Vec<SshConfigHostGroup> = Vec::new();
+    for host in hosts {
+        if !seen.insert(host.entry.host.as_str()) {
+            continue;
+        }
+        match groups.iter_mut().find(|group| group.source == host.source) {
+            Some(group) => group.hosts.push(host.entry.clone()),
+            None => groups.push(SshConfigHostGroup {
+                source: host.source.clone(),
+                hosts: vec![host.entry.clone()],
+            }),
+        }
+    }
+    for group in &mut groups {
+        group.hosts.sort_by(|a, b| a.host.cmp(&b.host));
+    }
+    groups
+}
+
+fn collect_hosts<'a>(
+    fs: &'a dyn Fs,
+    content: String,
+    source: Arc<Path>,
+    base_dir: &'a Path,
+    depth: usize,
+    hosts: &'a mut Vec<SshConfigHost>,
+) -> BoxFuture<'a, ()> {
+    async move {
+        for item in parse_config_items(&content) {
+            match item {
+                ConfigItem::Hosts(block) => {
+                    hosts.extend(block.non_git_provider_entries().map(|entry| SshConfigHost {
+                        entry,
+                        source: source.clone(),
+                    }));
+                }
+                ConfigItem::Include(patterns) => {
+                    if depth >= MAX_INCLUDE_DEPTH {
+                        log::warn!(
+                            "SSH: not following includes in {source:?} nested more than {MAX_INCLUDE_DEPTH} deep"
+                        );
+                        continue;
+                    }
+                    for pattern in patterns {
+                        for path in resolve_include(fs, &pattern, base_dir).await {
+                            let Ok(content) = fs.load(&path).await else {
+                                log::debug!("SSH: skipping unreadable include {path:?}");
+                                continue;
+                            };
+                            collect_hosts(fs, content, Arc::from(path), base_dir, depth + 1, hosts)
+                                .await;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    .boxed()
+}
+
+/// The files an `Include` pattern refers to, in the order ssh reads them.
+async fn resolve_include(fs: &dyn Fs, pattern: &str, base_dir: &Path) -> Vec<PathBuf> {
+    let path = match pattern.strip_prefix("~/") {
+        Some(relative) => paths::home_dir().join(relative),
+        None => base_dir.join(pattern),
+    };
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return vec![path];
+    };
+    if !file_name.contains(['*', '?']) {
+        return vec![path];
+    }
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let Ok(mut entries) = fs.read_dir(dir).await else {
+        return Vec::new();
+    };
+
+    let mut paths = Vec::new();
+    while let Some(entry) = entries.next().await {
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let matches = entry
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| {
+                (file_name.starts_with('.') || !name.starts_with('.'))
+                    && matches_wildcard(file_name, name)
+            });
+        if matches {
+            paths.push(entry);
+        }
+    }
+    paths.sort();
+    paths
+}
+
+/// Whether `name` matches `pattern`, where `*` matches any run of characters
+/// and `?` matches a single character.
+fn matches_wildcard(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    let (mut pattern_ix, mut name_ix) = (0, 0);
+    let mut backtrack = None;
+    while name_ix < name.len() {
+        match pattern.get(pattern_ix) {
+            Some('*') => {
+                backtrack = Some((pattern_ix, name_ix));
+                pattern_ix += 1;
+            }
+            Some(character) if *character == '?' || *character == name[name_ix] => {
+                pattern_ix += 1;
+                name_ix += 1;
+            }
+            _ => match backtrack {
+                Some((star_ix, star_name_ix)) => {
+                    pattern_ix = star_ix + 1;
+                    name_ix = star_name_ix + 1;
+                    backtrack = Some((star_ix, star_name_ix + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[pattern_ix..]
+        .iter()
+        .all(|character| *character == '*')
+}
+
+enum ConfigItem {
+    Hosts(HostBlock),
+    /// The patterns of an `Include` directive.
+    Include(Vec<String>),
 }
 
 struct HostBlock {
     aliases: BTreeSet<String>,
     hostname: Option<String>,
+    via_proxy: bool,
+    proxy_jump: Option<String>,
 }
 
 impl HostBlock {
-    fn non_git_provider_hosts(self) -> impl Iterator<Item = String> {
+    fn non_git_provider_entries(self) -> impl Iterator<Item = SshConfigEntry> {
         let hostname = self.hostname;
+        let via_proxy = self.via_proxy;
+        let proxy_jump = self.proxy_jump;
         let hostname_ref = hostname.as_deref().map(is_git_provider_domain);
         self.aliases
             .into_iter()
             .filter(move |alias| !hostname_ref.unwrap_or_else(|| is_git_provider_domain(alias)))
+            .map(move |host| SshConfigEntry {
+                host,
+                via_proxy,
+                proxy_jump: proxy_jump.clone(),
+            })
     }
 }
 
-fn parse_host_blocks(config: &str) -> Vec<HostBlock> {
-    let mut blocks = Vec::new();
+/// Splits a config into its host blocks and includes, in the order ssh reads
+/// them. A block that contains an `Include` comes before the included hosts,
+/// since its aliases are declared first.
+fn parse_config_items(config: &str) -> Vec<ConfigItem> {
+    let mut items = Vec::new();
+    let mut block_start = 0;
     let mut aliases = BTreeSet::new();
     let mut hostname = None;
+    let mut via_proxy = false;
+    let mut proxy_jump = None;
     let mut needs_continuation = false;
 
     for line in config.lines() {
@@ -57,22 +266,56 @@ fn parse_host_blocks(config: &str) -> Vec<HostBlock> {
 
         if keyword.eq_ignore_ascii_case("host") {
             if !aliases.is_empty() {
-                blocks.push(HostBlock { aliases, hostname });
+                items.insert(
+                    block_start,
+                    ConfigItem::Hosts(HostBlock {
+                        aliases,
+                        hostname,
+                        via_proxy,
+                        proxy_jump,
+                    }),
+                );
                 aliases = BTreeSet::new();
                 hostname = None;
+                via_proxy = false;
+                proxy_jump = None;
             }
+            block_start = items.len();
             parse_hosts(value, &mut aliases);
             needs_continuation = line.trim_end().ends_with('\\');
         } else if keyword.eq_ignore_ascii_case("hostname") {
             hostname = value.split_whitespace().next().map(ToOwned::to_owned);
+        } else if keyword.eq_ignore_ascii_case("proxycommand")
+            || keyword.eq_ignore_ascii_case("proxyjump")
+        {
+            via_proxy = !value.trim().eq_ignore_ascii_case("none");
+            // Like ssh, the first value set for the host is the one used.
+            if keyword.eq_ignore_ascii_case("proxyjump") && proxy_jump.is_none() {
+                proxy_jump = Some(value.trim().to_owned());
+            }
+        } else if keyword.eq_ignore_ascii_case("include") {
+            let patterns = value
+                .split_whitespace()
+                .map(|pattern| pattern.trim_matches('"').to_owned())
+                .filter(|pattern| !pattern.is_empty())
+                .collect();
+            items.push(ConfigItem::Include(patterns));
         }
     }
 
     if !aliases.is_empty() {
-        blocks.push(HostBlock { aliases, hostname });
+        items.insert(
+            block_start,
+            ConfigItem::Hosts(HostBlock {
+                aliases,
+                hostname,
+                via_proxy,
+                proxy_jump,
+            }),
+        );
     }
 
-    blocks
+    items
 }
 
 fn parse_hosts(line: &str, hosts: &mut BTreeSet<String>) {
@@ -106,7 +349,29 @@ fn is_git_provider_domain(host: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use fs::FakeFs;
+    use gpui::TestAppContext;
     use indoc::indoc;
+    use serde_json::json;
+    use util::path;
+
+    fn parse_ssh_config_entries(config: &str) -> Vec<SshConfigEntry> {
+        parse_config_items(config)
+            .into_iter()
+            .filter_map(|item| match item {
+                ConfigItem::Hosts(block) => Some(block),
+                ConfigItem::Include(_) => None,
+            })
+            .flat_map(HostBlock::non_git_provider_entries)
+            .collect()
+    }
+
+    fn parse_ssh_config_hosts(config: &str) -> BTreeSet<String> {
+        parse_ssh_config_entries(config)
+            .into_iter()
+            .map(|entry| entry.host)
+            .collect()
+    }
 
     #[test]
     fn test_thank_you_bjorn3() {
@@ -202,6 +467,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn records_proxied_hosts() {
+        let hosts = indoc! {"
+            Host bastion
+              HostName bastion.example.com
+
+            Host internal
+              ProxyJump bastion
+
+            Host scripted
+              ProxyCommand /usr/local/bin/connect %h %p
+
+            Host direct
+              ProxyCommand none
+        "};
+
+        assert_eq!(
+            vec![
+                SshConfigEntry {
+                    host: "bastion".to_owned(),
+                    via_proxy: false,
+                    proxy_jump: None,
+                },
+                SshConfigEntry {
+                    host: "internal".to_owned(),
+                    via_proxy: true,
+                    proxy_jump: Some("bastion".to_owned()),
+                },
+                SshConfigEntry {
+                    host: "scripted".to_owned(),
+                    via_proxy: true,
+                    proxy_jump: None,
+                },
+                SshConfigEntry {
+                    host: "direct".to_owned(),
+                    via_proxy: false,
+                    proxy_jump: None,
+                },
+            ],
+            parse_ssh_config_entries(hosts)
+        );
+    }
+
     #[test]
     fn uses_hostname_before_host_filtering() {
         let hosts = indoc! {"
@@ -214,4 +522,81 @@ mod tests {
             parse_ssh_config_hosts(hosts)
         );
     }
+
+    #[test]
+    fn test_matches_wildcard() {
+        assert!(matches_wildcard("*", "work"));
+        assert!(matches_wildcard("*.conf", "work.conf"));
+        assert!(matches_wildcard("host-?", "host-1"));
+        assert!(matches_wildcard("a*b*c", "aXXbYYc"));
+        assert!(!matches_wildcard("*.conf", "work.config"));
+        assert!(!matches_wildcard("host-?", "host-10"));
+    }
+
+    #[gpui::test]
+    async fn test_load_hosts_from_includes(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/home/user/.ssh"),
+            json!({
+                "config": indoc! {"
+                    Host bastion
+                      HostName bastion.example.com
+                      Include config.d/*
+
+                    Host shared
+                      User main
+                "},
+                "config.d": {
+                    "personal": "Host shared laptop\n  User me\n",
+                    "work": "Host build\n  ProxyJump bastion\nHost bastion\n  User work\n",
+                    ".hidden": "Host hidden\n",
+                },
+            }),
+        )
+        .await;
+
+        let config_path = Path::new(path!("/home/user/.ssh/config"));
+        let content = fs.load(config_path).await.unwrap();
+        let hosts = load_ssh_config_hosts(fs.as_ref(), config_path, &content).await;
+
+        let config: Arc<Path> = Arc::from(config_path);
+        let personal: Arc<Path> = Arc::from(Path::new(path!("/home/user/.ssh/config.d/personal")));
+        let work: Arc<Path> = Arc::from(Path::new(path!("/home/user/.ssh/config.d/work")));
+        assert_eq!(
+            hosts
+                .iter()
+                .map(|host| (host.entry.host.as_str(), host.source.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("bastion", config.clone()),
+                ("laptop", personal.clone()),
+                ("shared", personal.clone()),
+                ("build", work.clone()),
+                ("bastion", work.clone()),
+                ("shared", config.clone()),
+            ]
+        );
+
+        let groups = group_ssh_config_hosts(&hosts);
+        assert_eq!(
+            groups
+                .iter()
+                .map(|group| (
+                    group.source.clone(),
+                    group
+                        .hosts
+                        .iter()
+                        .map(|entry| entry.host.as_str())
+                        .collect::<Vec<_>>()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (config, vec!["bastion"]),
+                (personal, vec!["laptop", "shared"]),
+                (work, vec!["build"]),
+            ]
+        );
+        assert!(groups[2].hosts[0].via_proxy);
+    }
 }
@@ -3,6 +3,7 @@ use std::{path::PathBuf, sync::Arc};
 use gpui::{AppContext, DismissEvent, Entity, EventEmitter, Focusable, Subscription, Task};
 use picker::Picker;
 use remote::{RemoteConnectionOptions, WslConnectionOptions};
+use settings::Settings;
 use ui::{
     App, Context, HighlightedLabel, Icon, IconName, InteractiveElement, ListItem, ParentElement,
     Render, Styled, StyledExt, Toggleable, Window, div, h_flex, rems, v_flex,
@@ -10,7 +11,7 @@ use ui::{
 use util::ResultExt as _;
 use workspace::{ModalView, MultiWorkspace};
 
-use crate::open_remote_project;
+use crate::{RemoteSettings, open_remote_project};
 
 #[derive(Clone, Debug)]
 pub struct WslDistroSelected {
@@ -234,10 +235,13 @@ impl WslOpenModal {
     ) {
         let app_state = workspace::AppState::global(cx);
 
-        let connection_options = RemoteConnectionOptions::Wsl(WslConnectionOptions {
+        let mut options = WslConnectionOptions {
             distro_name: distro.to_string(),
             user: None,
-        });
+            env: Default::default(),
+        };
+        RemoteSettings::get_global(cx).fill_wsl_connection_options_from_settings(&mut options);
+        let connection_options = RemoteConnectionOptions::Wsl(options);
 
         let replace_current_window = match self.create_new_window {
             true => secondary,
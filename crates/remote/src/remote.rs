@@ -3,6 +3,8 @@ pub mod protocol;
 pub mod proxy;
 pub mod remote_client;
 pub mod remote_identity;
+mod server_env;
+mod server_version;
 mod transport;
 
 #[cfg(target_os = "windows")]
@@ -10,13 +12,22 @@ pub use remote_client::OpenWslPath;
 pub use remote_client::{
     CommandTemplate, ConnectionIdentifier, ConnectionState, Interactive, RemoteArch, RemoteClient,
     RemoteClientDelegate, RemoteClientEvent, RemoteConnection, RemoteConnectionOptions, RemoteOs,
-    RemotePlatform, connect, has_active_connection,
+    RemotePlatform, active_connection, connect, has_active_connection,
 };
 pub use remote_identity::{
     RemoteConnectionIdentity, remote_connection_identity, same_remote_connection_identity,
 };
-pub use transport::docker::DockerConnectionOptions;
-pub use transport::ssh::{SshConnectionOptions, SshPortForwardOption};
+pub use server_env::{
+    looks_like_secret, remote_server_env, validate_env_var_name, validate_env_var_value,
+};
+pub use server_version::{
+    IncompatibleServerVersion, check_server_version, expected_server_version, parse_server_version,
+};
+pub use transport::docker::{DockerConnectionOptions, check_container_user};
+pub use transport::ssh::{
+    PortForward, PortForwardStatus, SshConnectFailure, SshConnectionOptions, SshJumpHost,
+    SshPortForwardOption, SshSettings, parse_jump_hosts, validate_remote_shell,
+};
 pub use transport::wsl::WslConnectionOptions;
 #[cfg(target_os = "windows")]
 pub use transport::wsl::wsl_path_to_windows_path;
@@ -1,12 +1,12 @@
 #[cfg(any(test, feature = "test-support"))]
 use crate::transport::mock::ConnectGuard;
 use crate::{
-    SshConnectionOptions,
+    IncompatibleServerVersion, SshConnectFailure, SshConnectionOptions,
     protocol::MessageId,
     proxy::ProxyLaunchError,
     transport::{
         docker::{DockerConnectionOptions, DockerExecConnection},
-        ssh::SshRemoteConnection,
+        ssh::{PortForward, SshRemoteConnection},
         wsl::{WslConnectionOptions, WslRemoteConnection},
     },
 };
@@ -144,6 +144,17 @@ pub trait RemoteClientDelegate: Send + Sync {
         cx: &mut AsyncApp,
     ) -> Task<Result<PathBuf>>;
     fn set_status(&self, status: Option<&str>, cx: &mut AsyncApp);
+    /// Asks whether to replace the remote server binary on the host, which
+    /// reported a version this client can't talk to. Resolves to `true` to
+    /// update it, and `false` to abort the connection. Declines by default,
+    /// for connections that can't ask.
+    fn confirm_server_update(
+        &self,
+        _mismatch: &IncompatibleServerVersion,
+        _cx: &mut AsyncApp,
+    ) -> Task<bool> {
+        Task::ready(false)
+    }
 }
 
 const MAX_MISSED_HEARTBEATS: usize = 5;
@@ -375,7 +386,38 @@ pub async fn connect(
         })
     })
     .await
-    .map_err(|e| e.cloned())
+    .map_err(|e| clone_connection_error(&e))
+}
+
+/// Clones an error shared by the [`ConnectionPool`]'s callers. Unlike
+/// [`ErrorExt::cloned`], which keeps nothing but the message, this keeps the
+/// failures that callers tell apart by type.
+fn clone_connection_error(error: &anyhow::Error) -> anyhow::Error {
+    cloned_keeping::<SshConnectFailure>(error)
+        .or_else(|| cloned_keeping::<IncompatibleServerVersion>(error))
+        .unwrap_or_else(|| error.cloned())
+}
+
+/// Clones `error` if it was caused by a `T`, rebuilding the contexts around
+/// the `T` from their messages.
+fn cloned_keeping<T>(error: &anyhow::Error) -> Option<anyhow::Error>
+where
+    T: std::error::Error + Clone + Send + Sync + 'static,
+{
+    let cause = error.downcast_ref::<T>()?.clone();
+    let contexts = error
+        .chain()
+        .take_while(|context| !context.is::<T>())
+        .map(|context| context.to_string())
+        .collect::<Vec<_>>();
+    Some(
+        contexts
+            .into_iter()
+            .rev()
+            .fold(anyhow::Error::new(cause), |error, context| {
+                error.context(context)
+            }),
+    )
 }
 
 /// Returns `true` if the global [`ConnectionPool`] already has a live
@@ -383,13 +425,21 @@ pub async fn connect(
 /// whether to show interactive UI (e.g., a password modal) before
 /// connecting.
 pub fn has_active_connection(opts: &RemoteConnectionOptions, cx: &App) -> bool {
-    cx.try_global::<ConnectionPool>().is_some_and(|pool| {
-        matches!(
-            pool.connections.get(opts),
-            Some(ConnectionPoolEntry::Connected(remote))
-                if remote.upgrade().is_some_and(|r| !r.has_been_killed())
-        )
-    })
+    active_connection(opts, cx).is_some()
+}
+
+/// The established connection for `opts`, if it's still alive.
+pub fn active_connection(
+    opts: &RemoteConnectionOptions,
+    cx: &App,
+) -> Option<Arc<dyn RemoteConnection>> {
+    let pool = cx.try_global::<ConnectionPool>()?;
+    match pool.connections.get(opts)? {
+        ConnectionPoolEntry::Connected(remote) => {
+            remote.upgrade().filter(|remote| !remote.has_been_killed())
+        }
+        ConnectionPoolEntry::Connecting(_) => None,
+    }
 }
 
 impl RemoteClient {
@@ -655,7 +705,7 @@ impl RemoteClient {
                         pool.connect(connection_options, delegate.clone(), cx)
                     })
                     .await
-                    .map_err(|error| error.cloned())?;
+                    .map_err(|error| clone_connection_error(&error))?;
 
                 let io_task = remote_connection.start_proxy(
                     unique_identifier,
@@ -1012,6 +1062,16 @@ impl RemoteClient {
         self.path_style
     }
 
+    /// The operating system and architecture of the connected machine.
+    pub fn platform(&self) -> Option<RemotePlatform> {
+        self.remote_connection()?.platform()
+    }
+
+    /// The version the remote server reported when connecting, if known.
+    pub fn server_version(&self) -> Option<String> {
+        self.remote_connection()?.server_version()
+    }
+
     /// Forcibly disconnects from the remote server by killing the underlying connection.
     /// This will trigger the reconnection logic if reconnection attempts remain.
     /// Useful for testing reconnection behavior in real environments.
@@ -1138,6 +1198,19 @@ impl RemoteClient {
         MockConnection::new_with_opts(mock_opts, client_cx, server_cx)
     }
 
+    /// Registers a mock host whose remote server reports a version this
+    /// client can't talk to, returning the options to connect to it with.
+    /// Connecting asks the delegate whether to update the server, and fails
+    /// with `mismatch` either way.
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn fake_incompatible_server(
+        client_cx: &mut gpui::TestAppContext,
+        mismatch: IncompatibleServerVersion,
+    ) -> RemoteConnectionOptions {
+        use crate::transport::mock::MockConnection;
+        MockConnection::new_incompatible(client_cx, mismatch).into()
+    }
+
     /// Creates a `RemoteClient` connected to a mock server.
     ///
     /// Call `fake_server` first to get the connection options, set up the
@@ -1247,15 +1320,26 @@ impl ConnectionPool {
                                 .map(|connection| Arc::new(connection) as Arc<dyn RemoteConnection>)
                         }
                         #[cfg(any(test, feature = "test-support"))]
-                        RemoteConnectionOptions::Mock(opts) => match cx.update(|cx| {
-                            cx.default_global::<crate::transport::mock::MockConnectionRegistry>()
-                                .take(&opts)
-                        }) {
-                            Some(connection) => Ok(connection.await as Arc<dyn RemoteConnection>),
-                            None => Err(anyhow!(
-                                "Mock connection not found. Call MockConnection::new() first."
-                            )),
-                        },
+                        RemoteConnectionOptions::Mock(opts) => {
+                            use crate::transport::mock::MockConnectionRegistry;
+                            let (mismatch, connection) = cx.update(|cx| {
+                                let registry = cx.default_global::<MockConnectionRegistry>();
+                                (registry.take_incompatible(&opts), registry.take(&opts))
+                            });
+                            match (mismatch, connection) {
+                                (Some(mismatch), _) => {
+                                    delegate.confirm_server_update(&mismatch, cx).await;
+                                    Err(anyhow::Error::new(mismatch)
+                                        .context("Failed to start the remote server"))
+                                }
+                                (None, Some(connection)) => {
+                                    Ok(connection.await as Arc<dyn RemoteConnection>)
+                                }
+                                (None, None) => Err(anyhow!(
+                                    "Mock connection not found. Call MockConnection::new() first."
+                                )),
+                            }
+                        }
                     };
 
                     cx.update_global(|pool: &mut Self, _| {
@@ -1345,6 +1429,26 @@ mod tests {
         assert_eq!(options.display_name(), "1.2.3.4");
     }
 
+    #[test]
+    fn test_cloned_connection_error_keeps_ssh_failure() {
+        let failure = SshConnectFailure {
+            exit_code: Some(255),
+            stderr: "Permission denied (publickey).".to_string(),
+        };
+        let error = anyhow::Error::new(failure.clone()).context("Failed to connect to host");
+
+        let cloned = clone_connection_error(&error);
+
+        assert_eq!(cloned.downcast_ref::<SshConnectFailure>(), Some(&failure));
+        assert_eq!(format!("{cloned:#}"), format!("{error:#}"));
+
+        let other = anyhow!("no route").context("Failed to connect to host");
+        assert_eq!(
+            format!("{:#}", clone_connection_error(&other)),
+            "Failed to connect to host: no route"
+        );
+    }
+
     #[gpui::test]
     async fn test_channel_client_request_stream_terminates_on_error(cx: &mut TestAppContext) {
         let (incoming_tx, incoming_rx) = mpsc::unbounded::<Envelope>();
@@ -1542,6 +1646,25 @@ pub trait RemoteConnection: Send + Sync {
         forwards: Vec<(u16, String, u16)>,
     ) -> Result<CommandTemplate>;
     fn connection_options(&self) -> RemoteConnectionOptions;
+    /// The operating system and architecture of the remote machine, if known.
+    fn platform(&self) -> Option<RemotePlatform> {
+        None
+    }
+    /// The version the remote server binary reported when connecting, as
+    /// `zed-remote-server version` prints it.
+    fn server_version(&self) -> Option<String> {
+        None
+    }
+    /// The port forwards from the connection's settings, and whether each one
+    /// is listening.
+    fn port_forwards(&self) -> Vec<PortForward> {
+        Vec::new()
+    }
+    /// Tries again to listen on the port forwards that failed, without
+    /// reconnecting.
+    async fn retry_port_forwards(&self) -> Result<()> {
+        Ok(())
+    }
     fn path_style(&self) -> PathStyle;
     fn shell(&self) -> String;
     fn default_system_shell(&self) -> String;
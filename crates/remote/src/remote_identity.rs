@@ -124,10 +124,12 @@ mod tests {
         let left = RemoteConnectionOptions::Wsl(WslConnectionOptions {
             distro_name: "Ubuntu".to_string(),
             user: Some("anth".to_string()),
+            env: Default::default(),
         });
         let right = RemoteConnectionOptions::Wsl(WslConnectionOptions {
             distro_name: "Ubuntu".to_string(),
             user: Some("root".to_string()),
+            env: Default::default(),
         });
 
         assert!(!same_remote_connection_identity(Some(&left), Some(&right),));
@@ -141,7 +143,11 @@ mod tests {
             remote_user: "anth".to_string(),
             upload_binary_over_docker_exec: true,
             use_podman: true,
+            wsl_distro: None,
             remote_env: BTreeMap::from([("FOO".to_string(), "BAR".to_string())]),
+            shell: Some("/bin/sh".to_string()),
+            detected_shell: Some("/bin/bash".to_string()),
+            extra_exec_args: vec!["--privileged".to_string()],
         });
         let right = RemoteConnectionOptions::Docker(DockerConnectionOptions {
             name: "zed-dev".to_string(),
@@ -149,7 +155,11 @@ mod tests {
             remote_user: "anth".to_string(),
             upload_binary_over_docker_exec: false,
             use_podman: false,
+            wsl_distro: None,
             remote_env: BTreeMap::new(),
+            shell: None,
+            detected_shell: None,
+            extra_exec_args: Vec::new(),
         });
 
         assert!(same_remote_connection_identity(Some(&left), Some(&right),));
@@ -160,6 +170,7 @@ mod tests {
         let remote = RemoteConnectionOptions::Wsl(WslConnectionOptions {
             distro_name: "Ubuntu".to_string(),
             user: Some("anth".to_string()),
+            env: Default::default(),
         });
 
         assert!(same_remote_connection_identity(None, None));
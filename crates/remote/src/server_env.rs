@@ -0,0 +1,136 @@
+//! Environment variables set for the remote server process, such as
+//! `HTTP_PROXY` for downloading language servers or `SSL_CERT_FILE` for a
+//! custom certificate bundle.
+//!
+//! The transports launch the server through `env KEY=VALUE ...`, passing
+//! each assignment as its own argument, so values are quoted like any other
+//! argument rather than pasted into the remote command line.
+
+use std::collections::BTreeMap;
+
+/// Variables from Zed's own environment that are passed on to the remote
+/// server, for debugging it.
+const FORWARDED_VARS: [&str; 3] = ["RUST_LOG", "RUST_BACKTRACE", "ZED_GENERATE_MINIDUMPS"];
+
+/// Parts of variable names that suggest the value is a secret.
+const SECRET_NAME_PARTS: [&str; 7] = [
+    "PASSWORD",
+    "PASSWD",
+    "SECRET",
+    "TOKEN",
+    "API_KEY",
+    "PRIVATE_KEY",
+    "CREDENTIAL",
+];
+
+/// Checks that `name` can be set with `env`: letters, digits, and
+/// underscores, not starting with a digit.
+pub fn validate_env_var_name(name: &str) -> Result<(), &'static str> {
+    let Some(first) = name.chars().next() else {
+        return Err("The variable name can't be empty.");
+    };
+    if first.is_ascii_digit() {
+        return Err("The variable name can't start with a digit.");
+    }
+    if !name
+        .chars()
+        .all(|character| character.is_ascii_alphanumeric() || character == '_')
+    {
+        return Err("The variable name can only contain letters, digits, and underscores.");
+    }
+    Ok(())
+}
+
+/// Checks that `value` can be passed as a single argument. Line breaks don't
+/// survive every remote shell.
+pub fn validate_env_var_value(value: &str) -> Result<(), &'static str> {
+    if value.contains(['\n', '\r', '\0']) {
+        return Err("The value can't contain line breaks.");
+    }
+    Ok(())
+}
+
+/// Whether `name` looks like it holds a secret, such as `GITHUB_TOKEN`. Such
+/// values are stored in the settings file in plain text.
+pub fn looks_like_secret(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    SECRET_NAME_PARTS.iter().any(|part| name.contains(part))
+}
+
+/// The variables the remote server process is started with: the debugging
+/// variables set for Zed itself, then those configured for the connection,
+/// which win over them. Configured variables that fail validation, which
+/// only a hand-edited settings file can have, are skipped.
+pub fn remote_server_env(configured: &BTreeMap<String, String>) -> Vec<(String, String)> {
+    let forwarded = FORWARDED_VARS
+        .iter()
+        .filter(|name| !configured.contains_key(**name))
+        .filter_map(|name| Some((name.to_string(), std::env::var(name).ok()?)));
+    let configured = configured
+        .iter()
+        .filter(|(name, value)| {
+            validate_env_var_name(name).is_ok() && validate_env_var_value(value).is_ok()
+        })
+        .map(|(name, value)| (name.clone(), value.clone()));
+    forwarded.chain(configured).collect()
+}
+
+/// The `env` arguments that set `env` for the program after them.
+pub(crate) fn env_assignments(env: &[(String, String)]) -> Vec<String> {
+    env.iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_env_var_name() {
+        assert!(validate_env_var_name("HTTP_PROXY").is_ok());
+        assert!(validate_env_var_name("_private2").is_ok());
+        assert!(validate_env_var_name("").is_err());
+        assert!(validate_env_var_name("2FA").is_err());
+        assert!(validate_env_var_name("MY-VAR").is_err());
+        assert!(validate_env_var_name("A=B").is_err());
+        assert!(validate_env_var_name("PATH ").is_err());
+    }
+
+    #[test]
+    fn test_looks_like_secret() {
+        assert!(looks_like_secret("GITHUB_TOKEN"));
+        assert!(looks_like_secret("db_password"));
+        assert!(looks_like_secret("AWS_SECRET_ACCESS_KEY"));
+        assert!(!looks_like_secret("HTTP_PROXY"));
+        assert!(!looks_like_secret("SSL_CERT_FILE"));
+    }
+
+    #[test]
+    fn test_remote_server_env_skips_invalid_variables() {
+        let configured = BTreeMap::from_iter([
+            ("HTTP_PROXY".to_string(), "http://proxy:3128".to_string()),
+            ("BAD-NAME".to_string(), "value".to_string()),
+            ("MULTILINE".to_string(), "a\nb".to_string()),
+            ("RUST_LOG".to_string(), "info".to_string()),
+        ]);
+        let env = remote_server_env(&configured);
+        let configured_env = env
+            .iter()
+            .filter(|(name, _)| configured.contains_key(name))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            configured_env,
+            [
+                &("HTTP_PROXY".to_string(), "http://proxy:3128".to_string()),
+                &("RUST_LOG".to_string(), "info".to_string()),
+            ]
+        );
+        // A configured variable is set once, over Zed's own.
+        assert_eq!(env.iter().filter(|(name, _)| name == "RUST_LOG").count(), 1);
+        assert_eq!(
+            env_assignments(&configured_env.into_iter().cloned().collect::<Vec<_>>()),
+            ["HTTP_PROXY=http://proxy:3128", "RUST_LOG=info"]
+        );
+    }
+}
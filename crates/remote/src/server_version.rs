@@ -0,0 +1,175 @@
+//! Checks that the remote server binary already on a host is one this client
+//! can talk to, so that a stale binary, such as one cached under the same
+//! name by an earlier nightly, is caught before the handshake rather than
+//! failing deep inside it.
+
+use std::fmt;
+
+use release_channel::ReleaseChannel;
+use semver::Version;
+
+/// The remote server on a host reported a version this client can't talk to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncompatibleServerVersion {
+    /// The version the server reported, as `zed-remote-server version`
+    /// prints it.
+    pub server_version: String,
+    /// The version this client needs, in the same form.
+    pub client_version: String,
+}
+
+impl fmt::Display for IncompatibleServerVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Remote server is version {}, this client needs {}",
+            self.server_version, self.client_version
+        )
+    }
+}
+
+impl std::error::Error for IncompatibleServerVersion {}
+
+/// The version a remote server compatible with this client reports, as
+/// `zed-remote-server version` prints it, or `None` when it can't be told:
+/// for dev builds, and nightlies that don't know their build and commit.
+pub fn expected_server_version(
+    release_channel: ReleaseChannel,
+    client_version: &Version,
+) -> Option<String> {
+    match release_channel {
+        ReleaseChannel::Stable | ReleaseChannel::Preview => Some(
+            Version::new(
+                client_version.major,
+                client_version.minor,
+                client_version.patch,
+            )
+            .to_string(),
+        ),
+        // Nightlies carry `nightly.<build id>.<commit>` as build metadata,
+        // and their server prints `<build id>+<commit>`.
+        ReleaseChannel::Nightly => {
+            let mut identifiers = client_version.build.as_str().split('.');
+            let (Some(_channel), Some(build_id), Some(commit), None) = (
+                identifiers.next(),
+                identifiers.next(),
+                identifiers.next(),
+                identifiers.next(),
+            ) else {
+                return None;
+            };
+            Some(format!("{build_id}+{commit}"))
+        }
+        ReleaseChannel::Dev => None,
+    }
+}
+
+/// Checks the output of `zed-remote-server version` on a host against what
+/// this client needs. Anything printed before the version, such as a login
+/// banner, is ignored.
+pub fn check_server_version(
+    output: &str,
+    release_channel: ReleaseChannel,
+    client_version: &Version,
+) -> Result<(), IncompatibleServerVersion> {
+    let Some(expected) = expected_server_version(release_channel, client_version) else {
+        return Ok(());
+    };
+    let server_version = parse_server_version(output).unwrap_or_default();
+    if server_version == expected {
+        return Ok(());
+    }
+    Err(IncompatibleServerVersion {
+        server_version: if server_version.is_empty() {
+            "unknown".to_string()
+        } else {
+            server_version.to_string()
+        },
+        client_version: expected,
+    })
+}
+
+/// The version in the output of `zed-remote-server version`: its last
+/// non-empty line.
+pub fn parse_server_version(output: &str) -> Option<&str> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .next_back()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_server_version() {
+        let client = Version::parse("0.201.3+stable.512.abc123").unwrap();
+        assert_eq!(
+            expected_server_version(ReleaseChannel::Stable, &client).as_deref(),
+            Some("0.201.3")
+        );
+        assert_eq!(
+            check_server_version("0.201.3\n", ReleaseChannel::Stable, &client),
+            Ok(())
+        );
+        // A login banner before the version doesn't count.
+        assert_eq!(
+            check_server_version(
+                "Welcome to the build box\r\n0.201.3\r\n",
+                ReleaseChannel::Stable,
+                &client
+            ),
+            Ok(())
+        );
+
+        let error = check_server_version("0.199.0\n", ReleaseChannel::Stable, &client).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Remote server is version 0.199.0, this client needs 0.201.3"
+        );
+        assert_eq!(
+            check_server_version("", ReleaseChannel::Stable, &client)
+                .unwrap_err()
+                .server_version,
+            "unknown"
+        );
+    }
+
+    #[test]
+    fn test_nightly_server_version() {
+        let client = Version::parse("0.202.0+nightly.1234.abc123").unwrap();
+        assert_eq!(
+            check_server_version("1234+abc123\n", ReleaseChannel::Nightly, &client),
+            Ok(())
+        );
+        assert_eq!(
+            check_server_version("1200+def456\n", ReleaseChannel::Nightly, &client),
+            Err(IncompatibleServerVersion {
+                server_version: "1200+def456".to_string(),
+                client_version: "1234+abc123".to_string(),
+            })
+        );
+
+        // Without both a build id and a commit there's nothing to compare.
+        let client = Version::parse("0.202.0+nightly.abc123").unwrap();
+        assert_eq!(
+            expected_server_version(ReleaseChannel::Nightly, &client),
+            None
+        );
+        assert_eq!(
+            check_server_version("anything", ReleaseChannel::Nightly, &client),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_dev_server_version_is_not_checked() {
+        let client = Version::parse("0.202.0+dev").unwrap();
+        assert_eq!(
+            check_server_version("dev", ReleaseChannel::Dev, &client),
+            Ok(())
+        );
+    }
+}
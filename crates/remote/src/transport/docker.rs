@@ -16,7 +16,7 @@ use util::ResultExt;
 use util::command::Stdio;
 use util::shell::ShellKind;
 use util::{
-    paths::{PathStyle, RemotePathBuf},
+    paths::{PathExt, PathStyle, RemotePathBuf},
     rel_path::RelPath,
 };
 
@@ -48,9 +48,103 @@ pub struct DockerConnectionOptions {
     pub remote_user: String,
     pub upload_binary_over_docker_exec: bool,
     pub use_podman: bool,
+    /// The WSL distro that the container CLI runs in, on Windows machines
+    /// where docker is only installed in WSL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wsl_distro: Option<String>,
     pub remote_env: BTreeMap<String, String>,
+    /// Shell to use instead of the one detected in the container.
+    pub shell: Option<String>,
+    /// The shell detected on an earlier connection, tried before probing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detected_shell: Option<String>,
+    /// Arguments added to every `docker exec` into the container, before the
+    /// container id.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_exec_args: Vec<String>,
 }
 
+impl DockerConnectionOptions {
+    /// The CLI that runs the container.
+    pub fn docker_cli(&self) -> &'static str {
+        if self.use_podman { "podman" } else { "docker" }
+    }
+
+    /// The program and leading arguments that run the container CLI, which
+    /// goes through `wsl -d <distro> --` when the CLI lives in WSL.
+    fn docker_invocation(&self) -> (String, Vec<String>) {
+        match &self.wsl_distro {
+            Some(distro) => (
+                "wsl".to_string(),
+                vec![
+                    "-d".to_string(),
+                    distro.clone(),
+                    "--".to_string(),
+                    self.docker_cli().to_string(),
+                ],
+            ),
+            None => (self.docker_cli().to_string(), Vec::new()),
+        }
+    }
+
+    /// A command that runs the container CLI.
+    pub fn docker_command(&self) -> util::command::Command {
+        let (program, args) = self.docker_invocation();
+        let mut command = util::command::new_command(program);
+        command.args(args);
+        command
+    }
+
+    /// `path` on this machine as the container CLI sees it, which for a CLI
+    /// in WSL is under the distro's `/mnt` mounts.
+    fn host_path(&self, path: &Path) -> Result<String> {
+        if self.wsl_distro.is_none() {
+            return Ok(path.display().to_string());
+        }
+        path.local_to_wsl()
+            .map(|path| path.display().to_string())
+            .with_context(|| format!("{} can't be reached from WSL", path.display()))
+    }
+}
+
+/// Fails with a clear error if `user` doesn't exist in the container that
+/// `options` connects to, instead of every later `exec -u` failing obscurely.
+pub async fn check_container_user(options: &DockerConnectionOptions, user: &str) -> Result<()> {
+    let container_id = &options.container_id;
+    let mut command = options.docker_command();
+    command.arg("exec").args(&options.extra_exec_args).args([
+        container_id.as_str(),
+        "id",
+        "-u",
+        user,
+    ]);
+    let output = command.output().await?;
+    log::debug!("{:?}: {:?}", command, output);
+    anyhow::ensure!(
+        output.status.success(),
+        "user `{user}` does not exist in container {container_id}: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(())
+}
+
+/// Fails if any of `args` contains a shell metacharacter. Extra arguments are
+/// passed to the container CLI as they are, never through a shell, so one
+/// written as if a shell would run it wouldn't do what it looks like.
+fn validate_extra_args(args: &[String]) -> Result<()> {
+    for arg in args {
+        if let Some(metacharacter) = util::shell::find_shell_metacharacter(arg) {
+            anyhow::bail!(
+                "extra argument `{arg}` contains `{metacharacter}`, but arguments are passed as they are rather than through a shell"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Shells probed, in order, when the container's login shell can't be found.
+const SHELL_PREFERENCES: &[&str] = &["bash", "zsh", "sh"];
+
 pub(crate) struct DockerExecConnection {
     proxy_process: Mutex<Option<u32>>,
     remote_dir_for_server: String,
@@ -83,6 +177,9 @@ impl DockerExecConnection {
                 AppCommitSha::try_global(cx),
             )
         });
+        validate_extra_args(&this.connection_options.extra_exec_args)
+            .context("invalid extra `exec` arguments")?;
+        this.check_remote_user().await?;
         let remote_platform = this.check_remote_platform().await?;
 
         this.path_style = match remote_platform.os {
@@ -93,8 +190,26 @@ impl DockerExecConnection {
         this.remote_platform = Some(remote_platform);
         log::info!("Remote platform discovered: {:?}", this.remote_platform);
 
-        this.shell = this.discover_shell().await;
-        log::info!("Remote shell discovered: {}", this.shell);
+        this.shell = match this.connection_options.shell.clone() {
+            Some(shell) => {
+                this.find_program(&shell).await.with_context(|| {
+                    format!("the configured shell `{shell}` was not found in the container")
+                })?;
+                log::info!("Using configured remote shell: {shell}");
+                shell
+            }
+            None => match this.connection_options.detected_shell.clone() {
+                Some(shell) if this.find_program(&shell).await.is_ok() => {
+                    log::info!("Using previously detected remote shell: {shell}");
+                    shell
+                }
+                _ => {
+                    let shell = this.discover_shell().await;
+                    log::info!("Remote shell discovered: {shell}");
+                    shell
+                }
+            },
+        };
 
         this.remote_dir_for_server = this.docker_user_home_dir().await?.trim().to_string();
 
@@ -114,15 +229,45 @@ impl DockerExecConnection {
     }
 
     fn docker_cli(&self) -> &str {
-        if self.connection_options.use_podman {
-            "podman"
-        } else {
-            "docker"
-        }
+        self.connection_options.docker_cli()
+    }
+
+    /// Returns the path of `program` in the container, as reported by
+    /// `command -v`, which unlike `which` is part of every POSIX shell.
+    async fn find_program(&self, program: &str) -> Result<String> {
+        let path = self
+            .run_docker_exec(
+                "sh",
+                None,
+                &Default::default(),
+                &["-c", "command -v \"$1\"", "sh", program],
+            )
+            .await?;
+        let path = path.trim();
+        anyhow::ensure!(!path.is_empty(), "`command -v {program}` printed nothing");
+        Ok(path.to_owned())
     }
 
     async fn discover_shell(&self) -> String {
+        if let Some(shell) = self.discover_login_shell().await {
+            if self.find_program(&shell).await.is_ok() {
+                return shell;
+            }
+            log::info!("Login shell {shell} is missing from the container, probing for a shell");
+        }
+
+        for shell in SHELL_PREFERENCES {
+            if let Ok(path) = self.find_program(shell).await {
+                return path;
+            }
+        }
+
         let default_shell = "sh";
+        log::info!("No known shell found, falling back to {default_shell}");
+        default_shell.to_owned()
+    }
+
+    async fn discover_login_shell(&self) -> Option<String> {
         match self
             .run_docker_exec("sh", None, &Default::default(), &["-c", "echo $SHELL"])
             .await
@@ -132,7 +277,7 @@ impl DockerExecConnection {
                     log::info!("$SHELL is not set, checking passwd for user");
                 }
                 shell => {
-                    return shell.to_owned();
+                    return Some(shell.to_owned());
                 }
             },
             Err(e) => {
@@ -151,17 +296,30 @@ impl DockerExecConnection {
         {
             Ok(shell) => match shell.trim() {
                 "" => {
-                    log::info!("No shell found in passwd, falling back to {default_shell}");
+                    log::info!("No shell found in passwd");
                 }
                 shell => {
-                    return shell.to_owned();
+                    return Some(shell.to_owned());
                 }
             },
             Err(e) => {
-                log::info!("Error getting shell from passwd: {e}. Falling back to {default_shell}");
+                log::info!("Error getting shell from passwd: {e}");
             }
         }
-        default_shell.to_owned()
+        None
+    }
+
+    async fn check_remote_user(&self) -> Result<()> {
+        let user = &self.connection_options.remote_user;
+        if user.is_empty() {
+            return Ok(());
+        }
+        check_container_user(&self.connection_options, user).await?;
+        log::info!(
+            "Connecting to container {} as {user}",
+            self.connection_options.container_id
+        );
+        Ok(())
     }
 
     async fn check_remote_platform(&self) -> Result<RemotePlatform> {
@@ -408,12 +566,11 @@ impl DockerExecConnection {
     }
 
     async fn upload_and_chown(
-        docker_cli: String,
         connection_options: DockerConnectionOptions,
         src_path: String,
         dst_path: String,
     ) -> Result<()> {
-        let mut command = util::command::new_command(&docker_cli);
+        let mut command = connection_options.docker_command();
         command.arg("cp");
         command.arg("-a");
         command.arg(&src_path);
@@ -432,8 +589,9 @@ impl DockerExecConnection {
             );
         }
 
-        let mut chown_command = util::command::new_command(&docker_cli);
+        let mut chown_command = connection_options.docker_command();
         chown_command.arg("exec");
+        chown_command.args(&connection_options.extra_exec_args);
         chown_command.arg(connection_options.container_id);
         chown_command.arg("chown");
         chown_command.arg(format!(
@@ -464,12 +622,11 @@ impl DockerExecConnection {
     ) -> Result<()> {
         log::debug!("uploading file {:?} to {:?}", src_path, dest_path);
 
-        let src_path_display = src_path.display().to_string();
+        let src_path_display = self.connection_options.host_path(src_path)?;
         let dest_path_str = dest_path.display(self.path_style());
         let full_server_path = format!("{}/{}", remote_dir_for_server, dest_path_str);
 
         Self::upload_and_chown(
-            self.docker_cli().to_string(),
             self.connection_options.clone(),
             src_path_display,
             full_server_path,
@@ -482,7 +639,7 @@ impl DockerExecConnection {
         subcommand: &str,
         args: &[impl AsRef<str>],
     ) -> Result<String> {
-        let mut command = util::command::new_command(self.docker_cli());
+        let mut command = self.connection_options.docker_command();
         command.arg(subcommand);
         for arg in args {
             command.arg(arg.as_ref());
@@ -522,6 +679,7 @@ impl DockerExecConnection {
             args.push(format!("{k}={v}"));
         }
 
+        args.extend(self.connection_options.extra_exec_args.iter().cloned());
         args.push(self.connection_options.container_id.clone());
         args.push(inner_program.to_string());
 
@@ -667,8 +825,9 @@ impl RemoteConnection for DockerExecConnection {
             "-w".to_string(),
             self.remote_dir_for_server.clone(),
             "-i".to_string(),
-            self.connection_options.container_id.to_string(),
         ]);
+        docker_args.extend(self.connection_options.extra_exec_args.iter().cloned());
+        docker_args.push(self.connection_options.container_id.to_string());
 
         let val = remote_binary_relpath
             .display(self.path_style())
@@ -680,7 +839,11 @@ impl RemoteConnection for DockerExecConnection {
         if reconnect {
             docker_args.push("--reconnect".to_string());
         }
-        let mut command = util::command::new_command(self.docker_cli());
+        log::info!(
+            "Starting remote server proxy: {} {docker_args:?}",
+            self.docker_cli()
+        );
+        let mut command = self.connection_options.docker_command();
         command
             .kill_on_drop(true)
             .stdin(Stdio::piped())
@@ -722,10 +885,12 @@ impl RemoteConnection for DockerExecConnection {
         cx: &App,
     ) -> Task<Result<()>> {
         let dest_path_str = dest_path.to_string();
-        let src_path_display = src_path.display().to_string();
+        let src_path_display = match self.connection_options.host_path(&src_path) {
+            Ok(src_path) => src_path,
+            Err(error) => return Task::ready(Err(error)),
+        };
 
         let upload_task = Self::upload_and_chown(
-            self.docker_cli().to_string(),
             self.connection_options.clone(),
             src_path_display,
             dest_path_str,
@@ -805,13 +970,17 @@ impl RemoteConnection for DockerExecConnection {
             Interactive::Yes => docker_args.push("-it".to_string()),
             Interactive::No => docker_args.push("-i".to_string()),
         }
+        docker_args.extend(self.connection_options.extra_exec_args.iter().cloned());
         docker_args.push(self.connection_options.container_id.to_string());
 
         docker_args.append(&mut inner_program);
 
+        let (program, mut args) = self.connection_options.docker_invocation();
+        args.append(&mut docker_args);
+
         Ok(CommandTemplate {
-            program: self.docker_cli().to_string(),
-            args: docker_args,
+            program,
+            args,
             // Docker-exec pipes in environment via the "-e" argument
             env: Default::default(),
         })
@@ -828,6 +997,10 @@ impl RemoteConnection for DockerExecConnection {
         RemoteConnectionOptions::Docker(self.connection_options.clone())
     }
 
+    fn platform(&self) -> Option<RemotePlatform> {
+        self.remote_platform
+    }
+
     fn path_style(&self) -> PathStyle {
         self.path_style.unwrap_or(PathStyle::Posix)
     }
@@ -29,6 +29,7 @@
 //! }
 //! ```
 
+use crate::IncompatibleServerVersion;
 use crate::remote_client::{
     ChannelClient, CommandTemplate, Interactive, RemoteClientDelegate, RemoteConnection,
     RemoteConnectionOptions,
@@ -99,6 +100,9 @@ unsafe impl Sync for SendableCx {}
 #[derive(Default)]
 pub struct MockConnectionRegistry {
     pending: HashMap<u64, (oneshot::Receiver<()>, Arc<MockRemoteConnection>)>,
+    /// Hosts whose remote server reports a version this client can't talk
+    /// to, and that fail to connect once the update is declined.
+    incompatible: HashMap<u64, IncompatibleServerVersion>,
 }
 
 impl Global for MockConnectionRegistry {}
@@ -115,6 +119,15 @@ impl MockConnectionRegistry {
             con
         })
     }
+
+    /// Called by `ConnectionPool::connect` to find out whether the host was
+    /// registered with an incompatible remote server.
+    pub fn take_incompatible(
+        &mut self,
+        opts: &MockConnectionOptions,
+    ) -> Option<IncompatibleServerVersion> {
+        self.incompatible.remove(&opts.id)
+    }
 }
 
 /// Helper for creating mock connection pairs in tests.
@@ -142,14 +155,37 @@ impl MockConnection {
         client_cx: &mut TestAppContext,
         server_cx: &mut TestAppContext,
     ) -> (MockConnectionOptions, AnyProtoClient, ConnectGuard) {
-        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
-        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
-        let opts = MockConnectionOptions { id };
+        let opts = Self::next_options();
         let (server_client, connect_guard) =
             Self::new_with_opts(opts.clone(), client_cx, server_cx);
         (opts, server_client, connect_guard)
     }
 
+    /// Registers a host whose remote server reports a version this client
+    /// can't talk to. Connecting to it asks the delegate whether to update
+    /// the server, as connecting over SSH does, and fails with `mismatch`
+    /// either way, since there's no server binary to replace.
+    pub(crate) fn new_incompatible(
+        client_cx: &mut TestAppContext,
+        mismatch: IncompatibleServerVersion,
+    ) -> MockConnectionOptions {
+        let opts = Self::next_options();
+        let id = opts.id;
+        client_cx.update(|cx| {
+            cx.default_global::<MockConnectionRegistry>()
+                .incompatible
+                .insert(id, mismatch);
+        });
+        opts
+    }
+
+    fn next_options() -> MockConnectionOptions {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        MockConnectionOptions {
+            id: NEXT_ID.fetch_add(1, Ordering::SeqCst),
+        }
+    }
+
     /// Creates a mock connection pair for existing `MockConnectionOptions`.
     ///
     /// This is useful when simulating reconnection: after a connection is torn
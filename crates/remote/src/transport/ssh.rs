@@ -1,6 +1,8 @@
 use crate::{
-    RemoteArch, RemoteClientDelegate, RemoteOs, RemotePlatform,
+    RemoteArch, RemoteClientDelegate, RemoteOs, RemotePlatform, check_server_version,
+    parse_server_version,
     remote_client::{CommandTemplate, Interactive, RemoteConnection, RemoteConnectionOptions},
+    server_env::env_assignments,
     transport::{parse_platform, parse_shell},
 };
 use anyhow::{Context as _, Result, anyhow};
@@ -18,19 +20,23 @@ use release_channel::{AppVersion, ReleaseChannel};
 use rpc::proto::Envelope;
 use semver::Version;
 pub use settings::SshPortForwardOption;
+use settings::{RegisterSetting, Settings};
+use sha2::{Digest as _, Sha256};
 use smol::fs;
 use std::{
+    collections::BTreeMap,
     net::IpAddr,
     path::{Path, PathBuf},
     sync::{
         Arc,
         atomic::{AtomicBool, Ordering},
     },
-    time::Instant,
+    time::{Duration, Instant},
 };
 use tempfile::TempDir;
 use util::command::{Child, Stdio};
 use util::{
+    ResultExt as _,
     paths::{PathStyle, RemotePathBuf},
     rel_path::RelPath,
     shell::ShellKind,
@@ -43,14 +49,86 @@ pub(crate) struct SshRemoteConnection {
     /// reused ControlMaster sessions start with `master_process` as `None`.
     killed: AtomicBool,
     remote_binary_path: Option<Arc<RelPath>>,
+    /// The version the remote server binary reported, as
+    /// `zed-remote-server version` prints it.
+    remote_server_version: Option<String>,
     ssh_platform: RemotePlatform,
     ssh_path_style: PathStyle,
+    /// The shell terminals and commands are started in: the connection's
+    /// `shell` if it sets one, or else the login shell.
     ssh_shell: String,
+    /// The kind of the login shell, which interprets every command sent over
+    /// SSH.
     ssh_shell_kind: ShellKind,
     ssh_default_system_shell: String,
+    port_forwards: Mutex<Vec<PortForward>>,
     _temp_dir: TempDir,
 }
 
+/// The ssh process that opens a connection exited before connecting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshConnectFailure {
+    /// ssh's exit code, which is 255 when ssh itself failed, rather than a
+    /// command it ran, or `None` when it was killed by a signal.
+    pub exit_code: Option<i32>,
+    /// What ssh printed to stderr.
+    pub stderr: String,
+}
+
+impl SshConnectFailure {
+    /// Whether ssh itself failed, as opposed to the remote command.
+    pub fn is_ssh_error(&self) -> bool {
+        self.exit_code == Some(255)
+    }
+}
+
+impl std::fmt::Display for SshConnectFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "failed to connect: {}", self.stderr)
+    }
+}
+
+impl std::error::Error for SshConnectFailure {}
+
+/// Whether one of a connection's port forwards is listening.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortForwardStatus {
+    /// ssh is listening on the local port.
+    Active,
+    /// ssh couldn't listen on the local port, for the given reason.
+    Failed(String),
+    /// ssh was asked to forward the port but can't report whether it did,
+    /// which is the case on Windows, where there's no control socket.
+    Unverified,
+}
+
+/// A port forward from the connection's settings, and whether it's listening.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortForward {
+    pub option: SshPortForwardOption,
+    pub status: PortForwardStatus,
+}
+
+impl PortForward {
+    /// A short description of the forward, like `8080→remote:80`, where
+    /// `remote` stands for the remote machine itself.
+    pub fn label(&self) -> String {
+        let local = match &self.option.local_host {
+            Some(host) => format!("{}:{}", bracket_ipv6(host), self.option.local_port),
+            None => self.option.local_port.to_string(),
+        };
+        let remote_host = match &self.option.remote_host {
+            Some(host) => bracket_ipv6(host),
+            None => "remote".to_string(),
+        };
+        format!("{local}→{remote_host}:{}", self.option.remote_port)
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self.status, PortForwardStatus::Failed(_))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum SshConnectionHost {
     IpAddr(IpAddr),
@@ -108,6 +186,45 @@ fn bracket_ipv6(host: &str) -> String {
     }
 }
 
+/// The `-L` argument for a port forward, without the flag.
+fn port_forward_spec(forward: &SshPortForwardOption) -> String {
+    let local_host = match &forward.local_host {
+        Some(host) => host,
+        None => "localhost",
+    };
+    let remote_host = match &forward.remote_host {
+        Some(host) => host,
+        None => "localhost",
+    };
+
+    format!(
+        "{}:{}:{}:{}",
+        bracket_ipv6(local_host),
+        forward.local_port,
+        bracket_ipv6(remote_host),
+        forward.remote_port
+    )
+}
+
+/// Why ssh couldn't listen on a forward's local port. ssh itself only
+/// reports that the forwarding failed, so this binds the port to find out
+/// whether something else is listening on it.
+#[cfg(not(windows))]
+async fn port_forward_failure_reason(forward: &SshPortForwardOption, ssh_error: &str) -> String {
+    let host = forward.local_host.as_deref().unwrap_or("localhost");
+    match smol::net::TcpListener::bind((host, forward.local_port)).await {
+        Err(error) if error.kind() == std::io::ErrorKind::AddrInUse => "address in use".into(),
+        Err(error) if error.kind() == std::io::ErrorKind::PermissionDenied => {
+            "permission denied".into()
+        }
+        Err(error) => error.to_string(),
+        Ok(_) => match ssh_error.trim().rsplit(": ").next() {
+            Some(message) if !message.is_empty() => message.to_string(),
+            _ => "ssh couldn't forward the port".into(),
+        },
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct SshConnectionOptions {
     pub host: SshConnectionHost,
@@ -117,11 +234,51 @@ pub struct SshConnectionOptions {
     pub args: Option<Vec<String>>,
     pub port_forwards: Option<Vec<SshPortForwardOption>>,
     pub connection_timeout: Option<u16>,
+    /// Seconds between keepalive messages, passed to ssh as
+    /// `ServerAliveInterval`. 0 disables them.
+    pub keepalive_interval: Option<u32>,
+    /// Unanswered keepalive messages before the connection is dropped, passed
+    /// to ssh as `ServerAliveCountMax`. 0 leaves ssh's own limit.
+    pub keepalive_count_max: Option<u32>,
+    /// The program terminals and interactive commands are started in, instead
+    /// of the login shell. Must pass [`validate_remote_shell`].
+    pub shell: Option<String>,
+    /// Whether terminals and interactive commands are started as login
+    /// shells, so profiles that set up `PATH` and module systems are read.
+    pub login_shell: Option<bool>,
+    /// Environment variables the remote server process is started with.
+    pub env: BTreeMap<String, String>,
 
     pub nickname: Option<String>,
     pub upload_binary_over_ssh: bool,
 }
 
+/// Defaults for SSH connections that don't configure their own keepalive
+/// and shell options.
+#[derive(Clone, Debug, RegisterSetting)]
+pub struct SshSettings {
+    /// `None` leaves the interval to the user's ssh config.
+    pub keepalive_interval: Option<u32>,
+    /// `None` leaves the count to the user's ssh config.
+    pub keepalive_count_max: Option<u32>,
+    pub binary_transfer_retries: u32,
+    pub shell: Option<String>,
+    pub login_shell: bool,
+}
+
+impl Settings for SshSettings {
+    fn from_settings(content: &settings::SettingsContent) -> Self {
+        let remote = &content.remote;
+        Self {
+            keepalive_interval: remote.ssh_keepalive_interval,
+            keepalive_count_max: remote.ssh_keepalive_count_max,
+            binary_transfer_retries: remote.ssh_binary_transfer_retries.unwrap(),
+            shell: remote.ssh_shell.clone(),
+            login_shell: remote.ssh_login_shell.unwrap(),
+        }
+    }
+}
+
 impl From<settings::SshConnection> for SshConnectionOptions {
     fn from(val: settings::SshConnection) -> Self {
         SshConnectionOptions {
@@ -134,10 +291,40 @@ impl From<settings::SshConnection> for SshConnectionOptions {
             upload_binary_over_ssh: val.upload_binary_over_ssh.unwrap_or_default(),
             port_forwards: val.port_forwards,
             connection_timeout: val.connection_timeout,
+            keepalive_interval: val.keepalive_interval,
+            keepalive_count_max: val.keepalive_count_max,
+            shell: val.shell,
+            login_shell: val.login_shell,
+            env: val.env.unwrap_or_default(),
         }
     }
 }
 
+/// Checks that `shell` names a single program, such as `bash` or
+/// `/usr/local/bin/zsh`. Arguments can't be included, since the shell is
+/// quoted as one word when commands are built.
+pub fn validate_remote_shell(shell: &str) -> Result<(), &'static str> {
+    if shell.is_empty() {
+        return Err("The shell can't be empty.");
+    }
+    if shell.chars().any(char::is_whitespace) {
+        return Err("Enter only the shell program, without arguments.");
+    }
+    if shell.starts_with('-') {
+        return Err("The shell can't start with a dash.");
+    }
+    if shell.starts_with('~') {
+        return Err("Enter the shell's full path, since ~ isn't expanded.");
+    }
+    if !shell
+        .chars()
+        .all(|character| character.is_ascii_alphanumeric() || "/._-+".contains(character))
+    {
+        return Err("The shell can only contain letters, digits, and / . _ - +.");
+    }
+    Ok(())
+}
+
 struct SshSocket {
     connection_options: SshConnectionOptions,
     #[cfg(not(windows))]
@@ -300,6 +487,34 @@ impl RemoteConnection for SshRemoteConnection {
         self.ssh_default_system_shell.clone()
     }
 
+    fn port_forwards(&self) -> Vec<PortForward> {
+        self.port_forwards.lock().clone()
+    }
+
+    #[cfg(not(windows))]
+    async fn retry_port_forwards(&self) -> Result<()> {
+        let failed = self
+            .port_forwards
+            .lock()
+            .iter()
+            .enumerate()
+            .filter(|(_, forward)| forward.is_failed())
+            .map(|(index, forward)| (index, forward.option.clone()))
+            .collect::<Vec<_>>();
+        for (index, option) in failed {
+            let status = self.socket.request_port_forward(&option).await;
+            if let Some(forward) = self.port_forwards.lock().get_mut(index) {
+                forward.status = status;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    async fn retry_port_forwards(&self) -> Result<()> {
+        anyhow::bail!("port forwards can't be retried without reconnecting on Windows")
+    }
+
     fn build_command(
         &self,
         input_program: Option<String>,
@@ -344,6 +559,10 @@ impl RemoteConnection for SshRemoteConnection {
                 *ssh_path_style,
                 ssh_shell,
                 *ssh_shell_kind,
+                socket.connection_options.login_shell.unwrap_or(true),
+                // Only a shell the connection asks for runs the commands;
+                // otherwise they're exec'd as they always were.
+                socket.connection_options.shell.as_deref() == Some(ssh_shell.as_str()),
                 socket.ssh_command_options(),
                 &socket.connection_options.ssh_destination(),
                 interactive,
@@ -443,16 +662,19 @@ impl RemoteConnection for SshRemoteConnection {
         delegate: Arc<dyn RemoteClientDelegate>,
         cx: &mut AsyncApp,
     ) -> Task<Result<i32>> {
-        const VARS: [&str; 3] = ["RUST_LOG", "RUST_BACKTRACE", "ZED_GENERATE_MINIDUMPS"];
         delegate.set_status(Some("Starting proxy"), cx);
 
         let Some(remote_binary_path) = self.remote_binary_path.clone() else {
             return Task::ready(Err(anyhow!("Remote binary path not set")));
         };
 
+        let env = crate::remote_server_env(&self.socket.connection_options.env);
         let mut ssh_command = if self.ssh_platform.os.is_windows() {
-            // TODO: Set the `VARS` environment variables, we do not have `env` on windows
+            // TODO: Set the environment variables, we do not have `env` on windows
             // so this needs a different approach
+            if !self.socket.connection_options.env.is_empty() {
+                log::warn!("Environment variables aren't set for remote servers on Windows");
+            }
             let mut proxy_args = vec![];
             proxy_args.push("proxy".to_owned());
             proxy_args.push("--identifier".to_owned());
@@ -468,12 +690,7 @@ impl RemoteConnection for SshRemoteConnection {
                 false,
             )
         } else {
-            let mut proxy_args = vec![];
-            for env_var in VARS {
-                if let Some(value) = std::env::var(env_var).ok() {
-                    proxy_args.push(format!("{env_var}={value}"));
-                }
-            }
+            let mut proxy_args = env_assignments(&env);
             proxy_args.push(remote_binary_path.display(self.path_style()).into_owned());
             proxy_args.push("proxy".to_owned());
             proxy_args.push("--identifier".to_owned());
@@ -506,6 +723,14 @@ impl RemoteConnection for SshRemoteConnection {
         )
     }
 
+    fn platform(&self) -> Option<RemotePlatform> {
+        Some(self.ssh_platform)
+    }
+
+    fn server_version(&self) -> Option<String> {
+        self.remote_server_version.clone()
+    }
+
     fn path_style(&self) -> PathStyle {
         self.ssh_path_style
     }
@@ -593,12 +818,16 @@ async fn find_existing_control_master(
 
 impl SshRemoteConnection {
     pub(crate) async fn new(
-        connection_options: SshConnectionOptions,
+        mut connection_options: SshConnectionOptions,
         delegate: Arc<dyn RemoteClientDelegate>,
         cx: &mut AsyncApp,
     ) -> Result<Self> {
         use askpass::AskPassResult;
 
+        let ssh_settings = cx.update(|cx| SshSettings::get_global(cx).clone());
+        connection_options.apply_keepalive_defaults(&ssh_settings);
+        connection_options.apply_shell_defaults(&ssh_settings);
+
         let destination = connection_options.ssh_destination();
 
         let temp_dir = tempfile::Builder::new()
@@ -633,9 +862,11 @@ impl SshRemoteConnection {
             // for establish the connection and keep it open, allowing other ssh
             // commands to reuse it via a control socket.
             let socket_path = temp_dir.path().join("ssh.sock");
+            // The port forwards are requested over the control socket once
+            // connected, so that each one's failure is reported.
             let mut master_process = MasterProcess::new(
                 askpass.script_path().as_ref(),
-                connection_options.additional_args(),
+                connection_options.connection_args(),
                 &socket_path,
                 &destination,
             )?;
@@ -661,16 +892,16 @@ impl SshRemoteConnection {
                 return Err(e.context("Failed to connect to host"));
             }
 
-            if master_process.as_mut().try_status()?.is_some() {
+            if let Some(status) = master_process.as_mut().try_status()? {
                 let mut output = Vec::new();
                 let mut stderr = master_process.as_mut().stderr.take().unwrap();
                 stderr.read_to_end(&mut output).await?;
 
-                let error_message = format!(
-                    "failed to connect: {}",
-                    String::from_utf8_lossy(&output).trim()
-                );
-                anyhow::bail!(error_message);
+                return Err(SshConnectFailure {
+                    exit_code: status.code(),
+                    stderr: String::from_utf8_lossy(&output).trim().to_string(),
+                }
+                .into());
             }
 
             let socket = SshSocket::new(connection_options, socket_path).await?;
@@ -718,16 +949,16 @@ impl SshRemoteConnection {
                 return Err(e.context("Failed to connect to host"));
             }
 
-            if master_process.as_mut().try_status()?.is_some() {
+            if let Some(status) = master_process.as_mut().try_status()? {
                 let mut output = Vec::new();
                 let mut stderr = master_process.as_mut().stderr.take().unwrap();
                 stderr.read_to_end(&mut output).await?;
 
-                let error_message = format!(
-                    "failed to connect: {}",
-                    String::from_utf8_lossy(&output).trim()
-                );
-                anyhow::bail!(error_message);
+                return Err(SshConnectFailure {
+                    exit_code: status.code(),
+                    stderr: String::from_utf8_lossy(&output).trim().to_string(),
+                }
+                .into());
             }
 
             let socket = SshSocket::new(
@@ -744,20 +975,34 @@ impl SshRemoteConnection {
             (socket, Some(master_process))
         };
 
+        let port_forwards = socket.request_port_forwards().await;
+
         let is_windows = socket.probe_is_windows().await;
         log::info!("Remote is windows: {}", is_windows);
 
-        let ssh_shell = socket.shell(is_windows).await;
-        log::info!("Remote shell discovered: {}", ssh_shell);
+        let login_shell = socket.shell(is_windows).await;
+        log::info!("Remote shell discovered: {}", login_shell);
 
-        let ssh_shell_kind = ShellKind::new(&ssh_shell, is_windows);
+        let ssh_shell_kind = ShellKind::new(&login_shell, is_windows);
         let ssh_platform = socket.platform(ssh_shell_kind, is_windows).await?;
         log::info!("Remote platform discovered: {:?}", ssh_platform);
 
         let (ssh_path_style, ssh_default_system_shell) = match ssh_platform.os {
-            RemoteOs::Windows => (PathStyle::Windows, ssh_shell.clone()),
+            RemoteOs::Windows => (PathStyle::Windows, login_shell.clone()),
             _ => (PathStyle::Posix, String::from("/bin/sh")),
         };
+        let ssh_shell = match &socket.connection_options.shell {
+            // Commands on Windows servers are always run by the login shell.
+            Some(_) if ssh_platform.os.is_windows() => login_shell,
+            Some(shell) => match validate_remote_shell(shell) {
+                Ok(()) => shell.clone(),
+                Err(error) => {
+                    log::warn!("Ignoring the shell {shell:?} set for this server: {error}");
+                    login_shell
+                }
+            },
+            None => login_shell,
+        };
 
         let mut this = Self {
             socket,
@@ -765,30 +1010,51 @@ impl SshRemoteConnection {
             killed: AtomicBool::new(false),
             _temp_dir: temp_dir,
             remote_binary_path: None,
+            remote_server_version: None,
             ssh_path_style,
             ssh_platform,
             ssh_shell,
             ssh_shell_kind,
             ssh_default_system_shell,
+            port_forwards: Mutex::new(port_forwards),
         };
 
         let (release_channel, version) =
             cx.update(|cx| (ReleaseChannel::global(cx), AppVersion::global(cx)));
-        this.remote_binary_path = Some(
-            this.ensure_server_binary(&delegate, release_channel, version, cx)
-                .await?,
-        );
+        let (remote_binary_path, remote_server_version) = this
+            .ensure_server_binary(&delegate, release_channel, version, cx)
+            .await?;
+        this.remote_server_version = match remote_server_version {
+            Some(version) => Some(version),
+            None => this.read_server_version(&remote_binary_path).await,
+        };
+        this.remote_binary_path = Some(remote_binary_path);
 
         Ok(this)
     }
 
+    /// The version the server binary at `path` reports, if it runs.
+    async fn read_server_version(&self, path: &RelPath) -> Option<String> {
+        let output = self
+            .socket
+            .run_command(
+                self.ssh_shell_kind,
+                &path.display(self.path_style()),
+                &["version"],
+                true,
+            )
+            .await
+            .ok()?;
+        parse_server_version(&output).map(str::to_string)
+    }
+
     async fn ensure_server_binary(
         &self,
         delegate: &Arc<dyn RemoteClientDelegate>,
         release_channel: ReleaseChannel,
         version: Version,
         cx: &mut AsyncApp,
-    ) -> Result<Arc<RelPath>> {
+    ) -> Result<(Arc<RelPath>, Option<String>)> {
         let version_str = match release_channel {
             ReleaseChannel::Dev => "build".to_string(),
             _ => version.to_string(),
@@ -806,7 +1072,7 @@ impl SshRemoteConnection {
         let dst_path =
             paths::remote_server_dir_relative().join(RelPath::unix(&binary_name).unwrap());
 
-        let binary_exists_on_server = self
+        let installed_version = self
             .socket
             .run_command(
                 self.ssh_shell_kind,
@@ -815,7 +1081,8 @@ impl SshRemoteConnection {
                 true,
             )
             .await
-            .is_ok();
+            .ok();
+        let binary_exists_on_server = installed_version.is_some();
 
         #[cfg(any(debug_assertions, feature = "build-remote-server-binary"))]
         if let Some(remote_server_path) = super::build_remote_server_from_source(
@@ -838,24 +1105,13 @@ impl SshRemoteConnection {
                 .await?;
             self.extract_server_binary(&dst_path, &tmp_path, delegate, cx)
                 .await?;
-            return Ok(dst_path);
-        }
-
-        if binary_exists_on_server {
-            return Ok(dst_path);
+            return Ok((dst_path, None));
         }
 
-        let wanted_version = cx.update(|cx| match release_channel {
-            ReleaseChannel::Nightly => Ok(None),
-            ReleaseChannel::Dev => {
-                anyhow::bail!(
-                    "ZED_BUILD_REMOTE_SERVER is not set and no remote server exists at ({:?})",
-                    dst_path
-                )
-            }
-            _ => Ok(Some(AppVersion::global(cx))),
-        })?;
-
+        // The download path is the same across attempts and connections from
+        // this process, so an interrupted transfer can be resumed. It includes
+        // the pid so that other Zed processes connecting to the same host
+        // don't write to it at the same time.
         let tmp_path_compressed = remote_server_dir_relative().join(
             RelPath::unix(&format!(
                 "{}-download-{}.{}",
@@ -869,6 +1125,39 @@ impl SshRemoteConnection {
             ))
             .unwrap(),
         );
+
+        if let Some(installed_version) = installed_version {
+            let Err(mismatch) = check_server_version(&installed_version, release_channel, &version)
+            else {
+                return Ok((
+                    dst_path,
+                    parse_server_version(&installed_version).map(str::to_string),
+                ));
+            };
+            log::warn!("{mismatch}");
+            if !delegate.confirm_server_update(&mismatch, cx).await {
+                return Err(mismatch.into());
+            }
+            // A download left from an earlier attempt would be of the same
+            // stale binary. The binary itself is only replaced once the new
+            // one is in place, by renaming it over the old one, so a failed
+            // update leaves the old binary working.
+            self.remove_remote_file(&tmp_path_compressed)
+                .await
+                .context("removing the stale server download")?;
+        }
+
+        let wanted_version = cx.update(|cx| match release_channel {
+            ReleaseChannel::Nightly => Ok(None),
+            ReleaseChannel::Dev => {
+                anyhow::bail!(
+                    "ZED_BUILD_REMOTE_SERVER is not set and no remote server exists at ({:?})",
+                    dst_path
+                )
+            }
+            _ => Ok(Some(AppVersion::global(cx))),
+        })?;
+
         if !self.socket.connection_options.upload_binary_over_ssh
             && let Some(url) = delegate
                 .get_download_url(
@@ -887,7 +1176,7 @@ impl SshRemoteConnection {
                     self.extract_server_binary(&dst_path, &tmp_path_compressed, delegate, cx)
                         .await
                         .context("extracting server binary")?;
-                    return Ok(dst_path);
+                    return Ok((dst_path, None));
                 }
                 Err(e) => {
                     log::error!(
@@ -912,7 +1201,7 @@ impl SshRemoteConnection {
         self.extract_server_binary(&dst_path, &tmp_path_compressed, delegate, cx)
             .await
             .context("extracting server binary")?;
-        Ok(dst_path)
+        Ok((dst_path, None))
     }
 
     async fn download_binary_on_server(
@@ -922,21 +1211,45 @@ impl SshRemoteConnection {
         delegate: &Arc<dyn RemoteClientDelegate>,
         cx: &mut AsyncApp,
     ) -> Result<()> {
-        if let Some(parent) = tmp_path.parent() {
-            let res = self
-                .socket
-                .run_command(
-                    self.ssh_shell_kind,
-                    "mkdir",
-                    &["-p", parent.display(self.path_style()).as_ref()],
-                    true,
-                )
-                .await;
-            if !self.ssh_platform.os.is_windows() {
-                // mkdir fails on windows if the path already exists ...
-                res?;
+        self.create_parent_dir(tmp_path).await?;
+
+        let retries = cx.update(|cx| SshSettings::get_global(cx).binary_transfer_retries);
+        let mut attempt = 0;
+        loop {
+            match self
+                .download_binary_on_server_attempt(url, tmp_path, delegate, cx)
+                .await
+            {
+                Ok(()) => return Ok(()),
+                Err(error) if attempt < retries => {
+                    attempt += 1;
+                    log::warn!(
+                        "downloading remote development server failed, retrying ({attempt}/{retries}): {error:#}"
+                    );
+                    cx.background_executor()
+                        .timer(binary_transfer_backoff(attempt))
+                        .await;
+                }
+                Err(error) => return Err(error),
             }
         }
+    }
+
+    /// Downloads the server binary on the host, resuming from a partial
+    /// download left by an earlier attempt.
+    async fn download_binary_on_server_attempt(
+        &self,
+        url: &str,
+        tmp_path: &RelPath,
+        delegate: &Arc<dyn RemoteClientDelegate>,
+        cx: &mut AsyncApp,
+    ) -> Result<()> {
+        if self.remote_file_size(tmp_path).await.is_some()
+            && self.verify_downloaded_archive(tmp_path).await.is_ok()
+        {
+            log::info!("remote development server was already downloaded to {tmp_path:?}");
+            return Ok(());
+        }
 
         delegate.set_status(Some("Downloading remote development server on host"), cx);
 
@@ -946,83 +1259,258 @@ impl SshRemoteConnection {
             .connection_timeout
             .unwrap_or(10)
             .to_string();
+        let tmp_path_str = tmp_path.display(self.path_style());
 
-        match self
-            .socket
+        let download = async {
+            match self
+                .socket
+                .run_command(
+                    self.ssh_shell_kind,
+                    "curl",
+                    &[
+                        "-f",
+                        "-L",
+                        "-C",
+                        "-",
+                        "--connect-timeout",
+                        &connection_timeout,
+                        url,
+                        "-o",
+                        &tmp_path_str,
+                    ],
+                    true,
+                )
+                .await
+            {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    if self
+                        .socket
+                        .run_command(self.ssh_shell_kind, "which", &["curl"], true)
+                        .await
+                        .is_ok()
+                    {
+                        return Err(e);
+                    }
+
+                    log::info!("curl is not available, trying wget");
+                    match self
+                        .socket
+                        .run_command(
+                            self.ssh_shell_kind,
+                            "wget",
+                            &[
+                                "-c",
+                                "--connect-timeout",
+                                &connection_timeout,
+                                "--tries",
+                                "1",
+                                url,
+                                "-O",
+                                &tmp_path_str,
+                            ],
+                            true,
+                        )
+                        .await
+                    {
+                        Ok(_) => Ok(()),
+                        Err(e) => {
+                            if self
+                                .socket
+                                .run_command(self.ssh_shell_kind, "which", &["wget"], true)
+                                .await
+                                .is_ok()
+                            {
+                                Err(e)
+                            } else {
+                                Err(anyhow!("Neither curl nor wget is available"))
+                            }
+                        }
+                    }
+                }
+            }
+        };
+        self.with_transfer_progress(
+            download,
+            tmp_path,
+            None,
+            "Downloading remote development server on host",
+            delegate,
+            cx,
+        )
+        .await?;
+
+        if let Err(error) = self.verify_downloaded_archive(tmp_path).await {
+            self.remove_remote_file(tmp_path).await.log_err();
+            return Err(error.context("downloaded remote development server is corrupted"));
+        }
+        Ok(())
+    }
+
+    /// Checks a downloaded archive against the CRC it carries, so a corrupted
+    /// download is discarded rather than resumed or extracted. Zip archives
+    /// on Windows hosts are read through instead: one cut short has no
+    /// central directory to open, and damaged entries fail to inflate.
+    async fn verify_downloaded_archive(&self, tmp_path: &RelPath) -> Result<()> {
+        let tmp_path = tmp_path.display(self.path_style());
+        if self.ssh_platform.os.is_windows() {
+            let shell_kind = ShellKind::Pwsh;
+            let tmp_path = shell_kind.try_quote(&tmp_path).context("shell quoting")?;
+            let script = format!(
+                "$ErrorActionPreference = 'Stop'; \
+                Add-Type -AssemblyName System.IO.Compression.FileSystem; \
+                $archive = [System.IO.Compression.ZipFile]::OpenRead((Resolve-Path {tmp_path}).Path); \
+                try {{ foreach ($entry in $archive.Entries) {{ $stream = $entry.Open(); \
+                try {{ $stream.CopyTo([System.IO.Stream]::Null) }} finally {{ $stream.Dispose() }} }} }} \
+                finally {{ $archive.Dispose() }}"
+            );
+            let args = shell_kind.args_for_shell(false, script);
+            self.socket
+                .run_command(self.ssh_shell_kind, "powershell", &args, true)
+                .await?;
+            return Ok(());
+        }
+        self.socket
             .run_command(
                 self.ssh_shell_kind,
-                "curl",
-                &[
-                    "-f",
-                    "-L",
-                    "--connect-timeout",
-                    &connection_timeout,
-                    url,
-                    "-o",
-                    &tmp_path.display(self.path_style()),
-                ],
+                "gzip",
+                &["-t", tmp_path.as_ref()],
                 true,
             )
+            .await?;
+        Ok(())
+    }
+
+    async fn upload_local_server_binary(
+        &self,
+        src_path: &Path,
+        tmp_path: &RelPath,
+        delegate: &Arc<dyn RemoteClientDelegate>,
+        cx: &mut AsyncApp,
+    ) -> Result<()> {
+        self.create_parent_dir(tmp_path).await?;
+
+        let src_stat = fs::metadata(&src_path)
             .await
-        {
-            Ok(_) => {}
-            Err(e) => {
-                if self
-                    .socket
-                    .run_command(self.ssh_shell_kind, "which", &["curl"], true)
-                    .await
-                    .is_ok()
-                {
-                    return Err(e);
-                }
+            .with_context(|| format!("failed to get metadata for {:?}", src_path))?;
+        let size = src_stat.len();
+        let checksum = cx
+            .background_spawn({
+                let src_path = src_path.to_path_buf();
+                async move { file_sha256(&src_path).await }
+            })
+            .await
+            .with_context(|| format!("failed to checksum {:?}", src_path))?;
 
-                log::info!("curl is not available, trying wget");
-                match self
-                    .socket
-                    .run_command(
-                        self.ssh_shell_kind,
-                        "wget",
-                        &[
-                            "--connect-timeout",
-                            &connection_timeout,
-                            "--tries",
-                            "1",
-                            url,
-                            "-O",
-                            &tmp_path.display(self.path_style()),
-                        ],
-                        true,
-                    )
-                    .await
-                {
-                    Ok(_) => {}
-                    Err(e) => {
-                        if self
-                            .socket
-                            .run_command(self.ssh_shell_kind, "which", &["wget"], true)
-                            .await
-                            .is_ok()
-                        {
-                            return Err(e);
-                        } else {
-                            anyhow::bail!("Neither curl nor wget is available");
-                        }
-                    }
+        // A partial upload is only resumed if it was of the same file.
+        let checksum_path = checksum_sidecar_path(tmp_path)?;
+        if self.read_remote_file(&checksum_path).await.as_deref() != Some(checksum.as_str()) {
+            self.remove_remote_file(tmp_path).await.log_err();
+            self.write_remote_file(&checksum_path, &checksum).await?;
+        }
+
+        let t0 = Instant::now();
+        log::info!(
+            "uploading remote development server to {:?} ({}kb)",
+            tmp_path,
+            size / 1024
+        );
+        let retries = cx.update(|cx| SshSettings::get_global(cx).binary_transfer_retries);
+        let mut attempt = 0;
+        loop {
+            match self
+                .upload_local_server_binary_attempt(
+                    src_path, tmp_path, size, &checksum, delegate, cx,
+                )
+                .await
+            {
+                Ok(()) => break,
+                Err(error) if attempt < retries => {
+                    attempt += 1;
+                    log::warn!(
+                        "uploading remote development server failed, retrying ({attempt}/{retries}): {error:#}"
+                    );
+                    cx.background_executor()
+                        .timer(binary_transfer_backoff(attempt))
+                        .await;
                 }
+                Err(error) => return Err(error.context("failed to upload server binary")),
             }
         }
-
+        self.remove_remote_file(&checksum_path).await.log_err();
+        log::info!("uploaded remote development server in {:?}", t0.elapsed());
         Ok(())
     }
 
-    async fn upload_local_server_binary(
+    /// Uploads the rest of the server binary after the bytes already on the
+    /// host, then checks the result against the local file's checksum.
+    async fn upload_local_server_binary_attempt(
         &self,
         src_path: &Path,
         tmp_path: &RelPath,
+        size: u64,
+        checksum: &str,
+        delegate: &Arc<dyn RemoteClientDelegate>,
+        cx: &mut AsyncApp,
+    ) -> Result<()> {
+        let uploaded = self.remote_file_size(tmp_path).await.unwrap_or(0);
+        if uploaded < size {
+            if uploaded > 0 {
+                log::info!("resuming upload of remote development server after {uploaded} bytes");
+            }
+            let status = "Uploading remote development server";
+            delegate.set_status(Some(status), cx);
+            self.with_transfer_progress(
+                self.upload_file(src_path, tmp_path, uploaded > 0),
+                tmp_path,
+                Some(size),
+                status,
+                delegate,
+                cx,
+            )
+            .await?;
+        }
+
+        let uploaded_checksum = self.remote_sha256(tmp_path).await?;
+        if uploaded_checksum != checksum {
+            self.remove_remote_file(tmp_path).await.log_err();
+            anyhow::bail!(
+                "uploaded remote development server has checksum {uploaded_checksum}, expected {checksum}"
+            );
+        }
+        Ok(())
+    }
+
+    /// Runs `transfer`, showing how many bytes of `path` are on the host so far
+    /// in the connection status.
+    async fn with_transfer_progress(
+        &self,
+        transfer: impl Future<Output = Result<()>>,
+        path: &RelPath,
+        total: Option<u64>,
+        status: &str,
         delegate: &Arc<dyn RemoteClientDelegate>,
         cx: &mut AsyncApp,
     ) -> Result<()> {
-        if let Some(parent) = tmp_path.parent() {
+        let mut transfer = std::pin::pin!(transfer.fuse());
+        loop {
+            let mut timer = cx
+                .background_executor()
+                .timer(BINARY_TRANSFER_PROGRESS_INTERVAL)
+                .fuse();
+            select_biased! {
+                result = transfer => return result,
+                _ = timer => {}
+            }
+            if let Some(transferred) = self.remote_file_size(path).await {
+                let progress = format_transfer_progress(transferred, total);
+                delegate.set_status(Some(format!("{status} ({progress})").as_str()), cx);
+            }
+        }
+    }
+
+    async fn create_parent_dir(&self, path: &RelPath) -> Result<()> {
+        if let Some(parent) = path.parent() {
             let res = self
                 .socket
                 .run_command(
@@ -1037,23 +1525,106 @@ impl SshRemoteConnection {
                 res?;
             }
         }
+        Ok(())
+    }
+
+    /// Runs a script with `sh` on POSIX hosts, or PowerShell on Windows hosts.
+    async fn run_script(&self, posix_script: String, windows_script: String) -> Result<String> {
+        if self.ssh_platform.os.is_windows() {
+            let args = ShellKind::Pwsh.args_for_shell(false, windows_script);
+            self.socket
+                .run_command(self.ssh_shell_kind, "powershell", &args, true)
+                .await
+        } else {
+            let args = ShellKind::Posix.args_for_shell(false, posix_script);
+            self.socket
+                .run_command(self.ssh_shell_kind, "sh", &args, true)
+                .await
+        }
+    }
+
+    fn quote_for_scripts(&self, path: &RelPath) -> Result<(String, String)> {
+        let path = path.display(self.path_style());
+        Ok((
+            ShellKind::Posix
+                .try_quote(&path)
+                .context("shell quoting")?
+                .into_owned(),
+            ShellKind::Pwsh
+                .try_quote(&path)
+                .context("shell quoting")?
+                .into_owned(),
+        ))
+    }
 
-        let src_stat = fs::metadata(&src_path)
-            .await
-            .with_context(|| format!("failed to get metadata for {:?}", src_path))?;
-        let size = src_stat.len();
+    /// The size of a file on the host, or `None` if it doesn't exist.
+    async fn remote_file_size(&self, path: &RelPath) -> Option<u64> {
+        let (posix_path, windows_path) = self.quote_for_scripts(path).log_err()?;
+        self.run_script(
+            format!("[ -f {posix_path} ] && wc -c < {posix_path}"),
+            format!("(Get-Item -LiteralPath {windows_path} -ErrorAction Stop).Length"),
+        )
+        .await
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+    }
 
-        let t0 = Instant::now();
-        delegate.set_status(Some("Uploading remote development server"), cx);
-        log::info!(
-            "uploading remote development server to {:?} ({}kb)",
-            tmp_path,
-            size / 1024
-        );
-        self.upload_file(src_path, tmp_path)
+    async fn remote_sha256(&self, path: &RelPath) -> Result<String> {
+        let (posix_path, windows_path) = self.quote_for_scripts(path)?;
+        let output = self
+            .run_script(
+                format!(
+                    "if command -v sha256sum >/dev/null 2>&1; then sha256sum {posix_path}; else shasum -a 256 {posix_path}; fi"
+                ),
+                format!(
+                    "(Get-FileHash -Algorithm SHA256 -LiteralPath {windows_path} -ErrorAction Stop).Hash.ToLower()"
+                ),
+            )
+            .await?;
+        output
+            .split_whitespace()
+            .next()
+            .map(str::to_string)
+            .context("no checksum in output")
+    }
+
+    async fn read_remote_file(&self, path: &RelPath) -> Option<String> {
+        let (posix_path, windows_path) = self.quote_for_scripts(path).log_err()?;
+        let contents = self
+            .run_script(
+                format!("cat {posix_path}"),
+                format!("Get-Content -Raw -LiteralPath {windows_path} -ErrorAction Stop"),
+            )
             .await
-            .context("failed to upload server binary")?;
-        log::info!("uploaded remote development server in {:?}", t0.elapsed());
+            .ok()?;
+        Some(contents.trim().to_string())
+    }
+
+    async fn write_remote_file(&self, path: &RelPath, contents: &str) -> Result<()> {
+        let (posix_path, windows_path) = self.quote_for_scripts(path)?;
+        let posix_contents = ShellKind::Posix
+            .try_quote(contents)
+            .context("shell quoting")?;
+        let windows_contents = ShellKind::Pwsh
+            .try_quote(contents)
+            .context("shell quoting")?;
+        self.run_script(
+            format!("printf '%s\\n' {posix_contents} > {posix_path}"),
+            format!("Set-Content -LiteralPath {windows_path} -Value {windows_contents}"),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn remove_remote_file(&self, path: &RelPath) -> Result<()> {
+        let (posix_path, windows_path) = self.quote_for_scripts(path)?;
+        self.run_script(
+            format!("rm -f {posix_path}"),
+            format!("Remove-Item -Force -LiteralPath {windows_path} -ErrorAction SilentlyContinue"),
+        )
+        .await?;
         Ok(())
     }
 
@@ -1184,7 +1755,10 @@ impl SshRemoteConnection {
         command
     }
 
-    async fn upload_file(&self, src_path: &Path, dest_path: &RelPath) -> Result<()> {
+    /// Uploads `src_path` to `dest_path`. With `resume`, SFTP appends what's
+    /// missing to the partial file already at `dest_path`; SCP can't resume,
+    /// so it uploads the whole file.
+    async fn upload_file(&self, src_path: &Path, dest_path: &RelPath, resume: bool) -> Result<()> {
         log::debug!("uploading file {:?} to {:?}", src_path, dest_path);
 
         let src_path_display = src_path.display().to_string();
@@ -1198,25 +1772,30 @@ impl SshRemoteConnection {
         // https://github.com/PowerShell/openssh-portable/blob/b8c08ef9da9450a94a9c5ef717d96a7bd83f3332/sshconnect2.c#L417
         if Self::is_sftp_available().await {
             log::debug!("using SFTP for file upload");
-            let mut command = self.build_sftp_command();
-            let sftp_batch = format!("put {src_path_display} {dest_path_str}\n");
-
-            let mut child = command.spawn()?;
-            if let Some(mut stdin) = child.stdin.take() {
-                use futures::AsyncWriteExt;
-                stdin.write_all(sftp_batch.as_bytes()).await?;
-                stdin.flush().await?;
-            }
+            // Servers without `reput` support, or a partial file that can't
+            // be appended to, get the whole file instead.
+            let puts: &[&str] = if resume { &["reput", "put"] } else { &["put"] };
+            for put in puts {
+                let mut command = self.build_sftp_command();
+                let sftp_batch = format!("{put} {src_path_display} {dest_path_str}\n");
+
+                let mut child = command.spawn()?;
+                if let Some(mut stdin) = child.stdin.take() {
+                    use futures::AsyncWriteExt;
+                    stdin.write_all(sftp_batch.as_bytes()).await?;
+                    stdin.flush().await?;
+                }
 
-            let output = child.output().await?;
-            if output.status.success() {
-                return Ok(());
-            }
+                let output = child.output().await?;
+                if output.status.success() {
+                    return Ok(());
+                }
 
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            log::debug!(
-                "failed to upload file via SFTP {src_path_display} -> {dest_path_str}: {stderr}"
-            );
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                log::debug!(
+                    "failed to upload file via SFTP {put} {src_path_display} -> {dest_path_str}: {stderr}"
+                );
+            }
         }
 
         log::debug!("using SCP for file upload");
@@ -1346,7 +1925,7 @@ impl SshSocket {
         include_port_forwards: bool,
     ) -> &'a mut util::command::Command {
         let args = if include_port_forwards {
-            self.connection_options.additional_args()
+            self.command_args()
         } else {
             self.connection_options.additional_args_for_scp()
         };
@@ -1368,12 +1947,74 @@ impl SshSocket {
         cmd
     }
 
+    /// The options for ssh invocations other than the master. On Unix these
+    /// go through the control socket, whose master holds the port forwards,
+    /// so the forwards are left out.
+    fn command_args(&self) -> Vec<String> {
+        if cfg!(windows) {
+            self.connection_options.additional_args()
+        } else {
+            self.connection_options.connection_args()
+        }
+    }
+
+    /// Sets up the connection's port forwards, reporting whether each one is
+    /// listening. On Windows the master was started with the forwards, and
+    /// there's no way to ask it whether they bound.
+    async fn request_port_forwards(&self) -> Vec<PortForward> {
+        let mut port_forwards = Vec::new();
+        for option in self.connection_options.port_forwards.iter().flatten() {
+            #[cfg(not(windows))]
+            let status = self.request_port_forward(option).await;
+            #[cfg(windows)]
+            let status = PortForwardStatus::Unverified;
+            port_forwards.push(PortForward {
+                option: option.clone(),
+                status,
+            });
+        }
+        port_forwards
+    }
+
+    /// Asks the control master to listen on a forward's local port. The
+    /// master only keeps the forwards it could set up, so this binds again
+    /// after a failure, and does nothing for a forward that's listening.
+    #[cfg(not(windows))]
+    async fn request_port_forward(&self, forward: &SshPortForwardOption) -> PortForwardStatus {
+        let output = util::command::new_command("ssh")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .arg("-o")
+            .arg(format!("ControlPath={}", self.socket_path.display()))
+            .args(["-O", "forward"])
+            .arg(format!("-L{}", port_forward_spec(forward)))
+            .arg(self.connection_options.ssh_destination())
+            .output()
+            .await;
+        let status = match output {
+            Ok(output) if output.status.success() => PortForwardStatus::Active,
+            Ok(output) => PortForwardStatus::Failed(
+                port_forward_failure_reason(forward, &String::from_utf8_lossy(&output.stderr))
+                    .await,
+            ),
+            Err(error) => PortForwardStatus::Failed(format!("couldn't run ssh: {error}")),
+        };
+        if let PortForwardStatus::Failed(reason) = &status {
+            log::warn!(
+                "Failed to forward port {}: {reason}",
+                port_forward_spec(forward)
+            );
+        }
+        status
+    }
+
     // Returns the SSH command-line options (without the destination) for building commands.
     // On Linux, this includes the ControlPath option to reuse the existing connection.
     // Note: The destination must be added separately after all options to ensure proper
     // SSH command structure: ssh [options] destination [command]
     fn ssh_command_options(&self) -> Vec<String> {
-        let arguments = self.connection_options.additional_args();
+        let arguments = self.command_args();
         #[cfg(not(windows))]
         let arguments = {
             let mut args = arguments;
@@ -1495,6 +2136,52 @@ impl SshSocket {
     }
 }
 
+/// How often the connection status is updated with a transfer's progress.
+const BINARY_TRANSFER_PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long to wait before retrying a failed transfer of the server binary.
+fn binary_transfer_backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1 << attempt.saturating_sub(1).min(4))
+}
+
+fn format_transfer_progress(transferred: u64, total: Option<u64>) -> String {
+    let transferred = util::size::format_file_size(transferred, true);
+    match total {
+        Some(total) => format!(
+            "{transferred} / {}",
+            util::size::format_file_size(total, true)
+        ),
+        None => transferred,
+    }
+}
+
+/// Where the checksum of the file being uploaded to `path` is kept, so a
+/// partial upload is only resumed with the same file.
+fn checksum_sidecar_path(path: &RelPath) -> Result<Arc<RelPath>> {
+    let file_name = path.file_name().context("path has no file name")?;
+    let sidecar = RelPath::unix(&format!("{file_name}.sha256"))?;
+    Ok(match path.parent() {
+        Some(parent) => parent.join(sidecar),
+        None => sidecar.into(),
+    })
+}
+
+/// The SHA-256 of the file at `path`, read a chunk at a time rather than
+/// holding the whole server binary in memory.
+async fn file_sha256(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
 fn parse_port_number(port_str: &str) -> Result<u16> {
     port_str
         .parse()
@@ -1569,6 +2256,88 @@ fn parse_port_forward_spec(spec: &str) -> Result<SshPortForwardOption> {
     }
 }
 
+/// One hop of a `-J` jump host chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshJumpHost {
+    pub username: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+/// Parses the value of `-J`, a comma-separated chain of `[user@]host[:port]`
+/// hops, or `none`. Errors name the hop that's invalid.
+pub fn parse_jump_hosts(value: &str) -> Result<Vec<SshJumpHost>> {
+    if value == "none" {
+        return Ok(Vec::new());
+    }
+    value
+        .split(',')
+        .enumerate()
+        .map(|(index, hop)| {
+            parse_jump_host(hop).with_context(|| {
+                if hop.is_empty() {
+                    format!("jump host {} is empty; check for a stray comma", index + 1)
+                } else {
+                    format!("invalid jump host {} ({hop:?})", index + 1)
+                }
+            })
+        })
+        .collect()
+}
+
+fn parse_jump_host(hop: &str) -> Result<SshJumpHost> {
+    anyhow::ensure!(!hop.is_empty(), "missing host");
+    anyhow::ensure!(
+        !hop.contains(char::is_whitespace),
+        "jump hosts can't contain spaces"
+    );
+    let mut rest = hop.strip_prefix("ssh://").unwrap_or(hop);
+
+    let mut username = None;
+    if let Some((user, host)) = rest.rsplit_once('@') {
+        anyhow::ensure!(!user.is_empty(), "missing user before '@'");
+        username = Some(user.to_string());
+        rest = host;
+    }
+
+    let mut port = None;
+    if let Some(bracketed) = rest.strip_prefix('[') {
+        let (host, after) = bracketed
+            .split_once(']')
+            .context("missing ']' after IPv6 address")?;
+        if !after.is_empty() {
+            let after = after
+                .strip_prefix(':')
+                .with_context(|| format!("unexpected {after:?} after ']'"))?;
+            port = Some(parse_jump_host_port(after)?);
+        }
+        rest = host;
+    } else if let Some((host, port_str)) = rest.rsplit_once(':')
+        && !host.contains(':')
+    {
+        port = Some(parse_jump_host_port(port_str)?);
+        rest = host;
+    }
+
+    anyhow::ensure!(!rest.is_empty(), "missing host");
+    anyhow::ensure!(
+        !rest.contains(['/', '@', '[', ']']),
+        "invalid host {rest:?}"
+    );
+    Ok(SshJumpHost {
+        username,
+        host: rest.to_string(),
+        port,
+    })
+}
+
+fn parse_jump_host_port(port: &str) -> Result<u16> {
+    match port.parse::<u16>() {
+        Ok(port) if port > 0 => Ok(port),
+        _ => anyhow::bail!("invalid port {port:?}"),
+    }
+}
+
 impl SshConnectionOptions {
     pub fn parse_command_line(input: &str) -> Result<Self> {
         let input = input.trim_start_matches("ssh ");
@@ -1624,6 +2393,19 @@ impl SshConnectionOptions {
                     anyhow::bail!("Missing port forward format");
                 }
             }
+            // The chain is passed to ssh exactly as entered once it's valid.
+            if let Some(attached) = arg.strip_prefix("-J") {
+                if attached.is_empty() {
+                    let value = tokens.next().context("missing jump host after -J")?;
+                    parse_jump_hosts(&value)?;
+                    args.push(arg);
+                    args.push(value);
+                } else {
+                    parse_jump_hosts(attached)?;
+                    args.push(arg);
+                }
+                continue;
+            }
 
             for a in ALLOWED_ARGS {
                 if arg == *a {
@@ -1686,9 +2468,30 @@ impl SshConnectionOptions {
             nickname: None,
             upload_binary_over_ssh: false,
             connection_timeout: None,
+            keepalive_interval: None,
+            keepalive_count_max: None,
+            shell: None,
+            login_shell: None,
+            env: BTreeMap::new(),
         })
     }
 
+    /// Fills in the shell options this connection doesn't set itself.
+    fn apply_shell_defaults(&mut self, settings: &SshSettings) {
+        if self.shell.is_none() {
+            self.shell = settings.shell.clone();
+        }
+        self.login_shell.get_or_insert(settings.login_shell);
+    }
+
+    /// Fills in the keepalive options this connection doesn't set itself from
+    /// the ones set in settings. Options set in neither place are left out of
+    /// the ssh arguments, so the user's ssh config applies.
+    fn apply_keepalive_defaults(&mut self, settings: &SshSettings) {
+        self.keepalive_interval = self.keepalive_interval.or(settings.keepalive_interval);
+        self.keepalive_count_max = self.keepalive_count_max.or(settings.keepalive_count_max);
+    }
+
     pub fn ssh_destination(&self) -> String {
         let mut result = String::default();
         if let Some(username) = &self.username {
@@ -1707,38 +2510,40 @@ impl SshConnectionOptions {
     }
 
     pub fn additional_args(&self) -> Vec<String> {
+        let mut args = self.connection_args();
+        if let Some(forwards) = &self.port_forwards {
+            args.extend(
+                forwards
+                    .iter()
+                    .map(|forward| format!("-L{}", port_forward_spec(forward))),
+            );
+        }
+        args
+    }
+
+    /// The arguments for ssh, without the port forwards.
+    fn connection_args(&self) -> Vec<String> {
         let mut args = self.additional_args_for_scp();
 
         if let Some(timeout) = self.connection_timeout {
             args.extend(["-o".to_string(), format!("ConnectTimeout={}", timeout)]);
         }
 
+        if let Some(interval) = self.keepalive_interval {
+            args.extend(["-o".to_string(), format!("ServerAliveInterval={interval}")]);
+        }
+        if let Some(count_max) = self.keepalive_count_max
+            && self.keepalive_interval != Some(0)
+            && count_max > 0
+        {
+            args.extend(["-o".to_string(), format!("ServerAliveCountMax={count_max}")]);
+        }
+
         if let Some(port) = self.port {
             args.push("-p".to_string());
             args.push(port.to_string());
         }
 
-        if let Some(forwards) = &self.port_forwards {
-            args.extend(forwards.iter().map(|pf| {
-                let local_host = match &pf.local_host {
-                    Some(host) => host,
-                    None => "localhost",
-                };
-                let remote_host = match &pf.remote_host {
-                    Some(host) => host,
-                    None => "localhost",
-                };
-
-                format!(
-                    "-L{}:{}:{}:{}",
-                    bracket_ipv6(local_host),
-                    pf.local_port,
-                    bracket_ipv6(remote_host),
-                    pf.remote_port
-                )
-            }));
-        }
-
         args
     }
 
@@ -1775,6 +2580,8 @@ fn build_command_posix(
     ssh_path_style: PathStyle,
     ssh_shell: &str,
     ssh_shell_kind: ShellKind,
+    login_shell: bool,
+    run_commands_in_shell: bool,
     ssh_options: Vec<String>,
     ssh_destination: &str,
     interactive: Interactive,
@@ -1835,19 +2642,42 @@ fn build_command_posix(
     }
 
     if let Some(input_program) = input_program {
-        write!(
-            exec,
-            "{}",
+        // Interactive commands are run by a shell the connection chose, as a
+        // login shell when asked to, so the command is quoted for that shell
+        // first and then quoted again for the login shell that runs it.
+        let run_in_login_shell =
+            run_commands_in_shell && login_shell && interactive == Interactive::Yes;
+        let command_shell_kind = if run_in_login_shell {
+            ShellKind::new(ssh_shell, false)
+        } else {
             ssh_shell_kind
-                .try_quote_prefix_aware(&input_program)
-                .context("shell quoting")?
-        )?;
+        };
+        let mut command = command_shell_kind
+            .try_quote_prefix_aware(&input_program)
+            .context("shell quoting")?
+            .into_owned();
         for arg in input_args {
-            let arg = ssh_shell_kind.try_quote(&arg).context("shell quoting")?;
-            write!(exec, " {}", &arg)?;
+            let arg = command_shell_kind.try_quote(arg).context("shell quoting")?;
+            write!(command, " {}", &arg)?;
         }
-    } else {
+        if run_in_login_shell {
+            write!(
+                exec,
+                "{} -l -c {}",
+                ssh_shell_kind
+                    .try_quote(ssh_shell)
+                    .context("shell quoting")?,
+                ssh_shell_kind
+                    .try_quote(&command)
+                    .context("shell quoting")?
+            )?;
+        } else {
+            exec.push_str(&command);
+        }
+    } else if login_shell {
         write!(exec, "{ssh_shell} -l")?;
+    } else {
+        write!(exec, "{ssh_shell}")?;
     };
 
     let mut args = Vec::new();
@@ -2008,6 +2838,8 @@ mod tests {
             PathStyle::Posix,
             "/bin/bash",
             ShellKind::Posix,
+            true,
+            false,
             vec!["-o".to_string(), "ControlMaster=auto".to_string()],
             "user@host",
             Interactive::No,
@@ -2028,6 +2860,8 @@ mod tests {
             PathStyle::Posix,
             "/bin/fish",
             ShellKind::Fish,
+            false,
+            false,
             vec!["-p".to_string(), "2222".to_string()],
             "user@host",
             Interactive::Yes,
@@ -2062,6 +2896,8 @@ mod tests {
             PathStyle::Posix,
             "/bin/fish",
             ShellKind::Fish,
+            true,
+            false,
             vec!["-p".to_string(), "2222".to_string()],
             "user@host",
             Interactive::Yes,
@@ -2086,6 +2922,90 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_build_command_in_login_shell() -> Result<()> {
+        let build = |program: Option<&str>, login_shell, run_commands_in_shell, interactive| {
+            build_command_posix(
+                program.map(ToString::to_string),
+                &["it's".to_string()],
+                &HashMap::default(),
+                None,
+                None,
+                HashMap::default(),
+                PathStyle::Posix,
+                "/usr/bin/fish",
+                ShellKind::Posix,
+                login_shell,
+                run_commands_in_shell,
+                vec![],
+                "user@host",
+                interactive,
+            )
+            .map(|command| command.args.last().cloned().unwrap_or_default())
+        };
+
+        // The command is quoted for fish, then quoted again for the POSIX
+        // login shell that starts fish.
+        let split = |command: &str| ShellKind::Posix.split(command).unwrap_or_default();
+        let exec = split(&build(Some("make"), true, true, Interactive::Yes)?);
+        let [.., shell, login, run, command] = exec.as_slice() else {
+            panic!("unexpected command {exec:?}");
+        };
+        assert_eq!([shell, login, run], ["/usr/bin/fish", "-l", "-c"]);
+        assert_eq!(ShellKind::Fish.split(command).unwrap(), ["make", "it's"]);
+
+        // Commands whose output Zed reads never start a login shell, and
+        // without a shell of the connection's own, commands are exec'd as
+        // they were before shells could be chosen.
+        for (login_shell, run_commands_in_shell, interactive) in [
+            (false, true, Interactive::Yes),
+            (true, true, Interactive::No),
+            (true, false, Interactive::Yes),
+        ] {
+            let exec = split(&build(
+                Some("make"),
+                login_shell,
+                run_commands_in_shell,
+                interactive,
+            )?);
+            assert_eq!(exec, ["cd", "&&", "exec", "env", "make", "it's"]);
+        }
+        assert_eq!(
+            build(None, true, false, Interactive::Yes)?,
+            "cd && exec env /usr/bin/fish -l"
+        );
+        assert_eq!(
+            build(None, false, true, Interactive::Yes)?,
+            "cd && exec env /usr/bin/fish"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_remote_shell() {
+        for shell in [
+            "bash",
+            "/bin/zsh",
+            "/opt/homebrew/bin/fish",
+            "/opt/bin/nu-0.9",
+        ] {
+            assert_eq!(validate_remote_shell(shell), Ok(()), "{shell}");
+        }
+        for shell in [
+            "",
+            "bash -l",
+            "/bin/sh\t",
+            "-bash",
+            "zsh;rm",
+            "$SHELL",
+            "'bash'",
+            "~/bin/nu",
+            "/home/~user/bin/zsh",
+        ] {
+            assert!(validate_remote_shell(shell).is_err(), "{shell:?}");
+        }
+    }
+
     #[test]
     fn test_build_command_quotes_env_assignment() -> Result<()> {
         let mut input_env = HashMap::default();
@@ -2101,6 +3021,8 @@ mod tests {
             PathStyle::Posix,
             "/bin/bash",
             ShellKind::Posix,
+            true,
+            false,
             vec![],
             "user@host",
             Interactive::No,
@@ -2195,6 +3117,78 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_jump_hosts() {
+        let hop = |username: Option<&str>, host: &str, port: Option<u16>| SshJumpHost {
+            username: username.map(str::to_string),
+            host: host.to_string(),
+            port,
+        };
+        let valid = [
+            ("bastion", vec![hop(None, "bastion", None)]),
+            (
+                "user@bastion:2222",
+                vec![hop(Some("user"), "bastion", Some(2222))],
+            ),
+            (
+                "user@bastion:2222,inner",
+                vec![
+                    hop(Some("user"), "bastion", Some(2222)),
+                    hop(None, "inner", None),
+                ],
+            ),
+            (
+                "a@one:22,two,ssh://b@three:2200",
+                vec![
+                    hop(Some("a"), "one", Some(22)),
+                    hop(None, "two", None),
+                    hop(Some("b"), "three", Some(2200)),
+                ],
+            ),
+            (
+                "admin@[2001:db8::1]:2222,fe80::1",
+                vec![
+                    hop(Some("admin"), "2001:db8::1", Some(2222)),
+                    hop(None, "fe80::1", None),
+                ],
+            ),
+            ("none", Vec::new()),
+        ];
+        for (value, expected) in valid {
+            assert_eq!(parse_jump_hosts(value).unwrap(), expected, "{value}");
+        }
+
+        let invalid = [
+            ("bastion,", "jump host 2 is empty"),
+            (",bastion", "jump host 1 is empty"),
+            ("one,,two", "jump host 2 is empty"),
+            ("bastion:ssh", "invalid jump host 1 (\"bastion:ssh\")"),
+            ("one,two:0", "invalid jump host 2 (\"two:0\")"),
+            ("@bastion", "invalid jump host 1"),
+            ("user@:22", "invalid jump host 1"),
+            ("[::1", "invalid jump host 1"),
+        ];
+        for (value, expected) in invalid {
+            let error = parse_jump_hosts(value).unwrap_err().to_string();
+            assert!(error.starts_with(expected), "{value}: {error}");
+        }
+
+        let options =
+            SshConnectionOptions::parse_command_line("ssh -J user@bastion:2222,inner host")
+                .unwrap();
+        assert_eq!(
+            options.args,
+            Some(vec![
+                "-J".to_string(),
+                "user@bastion:2222,inner".to_string()
+            ])
+        );
+        let options = SshConnectionOptions::parse_command_line("-Jone,two:2200 user@host").unwrap();
+        assert_eq!(options.args, Some(vec!["-Jone,two:2200".to_string()]));
+        let error = SshConnectionOptions::parse_command_line("-J bastion, host").unwrap_err();
+        assert!(format!("{error:#}").contains("jump host 2 is empty"));
+    }
+
     #[test]
     fn test_parse_port_forward_spec_ipv6() -> Result<()> {
         let pf = parse_port_forward_spec("[::1]:8080:[::1]:80")?;
@@ -2244,6 +3238,168 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_port_forward_label() {
+        let forward = |local_host: Option<&str>, remote_host: Option<&str>| PortForward {
+            option: SshPortForwardOption {
+                local_host: local_host.map(ToString::to_string),
+                local_port: 8080,
+                remote_host: remote_host.map(ToString::to_string),
+                remote_port: 80,
+            },
+            status: PortForwardStatus::Active,
+        };
+        assert_eq!(forward(None, None).label(), "8080→remote:80");
+        assert_eq!(
+            forward(Some("127.0.0.1"), Some("db.internal")).label(),
+            "127.0.0.1:8080→db.internal:80"
+        );
+        assert_eq!(forward(Some("::1"), None).label(), "[::1]:8080→remote:80");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_port_forward_failure_reason() {
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let taken_port = listener.local_addr().unwrap().port();
+        let forward = |local_port| SshPortForwardOption {
+            local_host: Some("127.0.0.1".to_string()),
+            local_port,
+            remote_host: None,
+            remote_port: 80,
+        };
+        let ssh_error = "mux_client_forward: forwarding request failed: Port forwarding failed\n";
+
+        assert_eq!(
+            smol::block_on(port_forward_failure_reason(&forward(taken_port), ssh_error)),
+            "address in use"
+        );
+
+        // When the port is free, ssh's own error is all there is to go on.
+        drop(listener);
+        assert_eq!(
+            smol::block_on(port_forward_failure_reason(&forward(taken_port), ssh_error)),
+            "Port forwarding failed"
+        );
+    }
+
+    #[test]
+    fn test_binary_transfer_helpers() -> Result<()> {
+        assert_eq!(
+            checksum_sidecar_path(RelPath::unix(
+                ".zed_server/zed-remote-server-stable-1.0.gz"
+            )?)?
+            .as_unix_str(),
+            ".zed_server/zed-remote-server-stable-1.0.gz.sha256"
+        );
+        assert_eq!(
+            checksum_sidecar_path(RelPath::unix("server.gz")?)?.as_unix_str(),
+            "server.gz.sha256"
+        );
+
+        assert_eq!(binary_transfer_backoff(1), Duration::from_secs(1));
+        assert_eq!(binary_transfer_backoff(3), Duration::from_secs(4));
+        assert_eq!(binary_transfer_backoff(10), Duration::from_secs(16));
+
+        assert_eq!(
+            format_transfer_progress(1_500_000, Some(30_000_000)),
+            format!(
+                "{} / {}",
+                util::size::format_file_size(1_500_000, true),
+                util::size::format_file_size(30_000_000, true)
+            )
+        );
+        assert_eq!(
+            format_transfer_progress(1_500_000, None),
+            util::size::format_file_size(1_500_000, true)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_sha256_reads_in_chunks() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("server.gz");
+        // Spans several reads, with a partial one at the end.
+        let contents = (0..200_000u32).map(|i| i as u8).collect::<Vec<_>>();
+        std::fs::write(&path, &contents)?;
+
+        assert_eq!(
+            smol::block_on(file_sha256(&path))?,
+            format!("{:x}", Sha256::digest(&contents))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_keepalive_args() {
+        let keepalive_args = |options: &SshConnectionOptions| {
+            options
+                .additional_args()
+                .into_iter()
+                .filter(|arg| arg.starts_with("ServerAlive"))
+                .collect::<Vec<_>>()
+        };
+        let defaults = SshSettings {
+            keepalive_interval: Some(30),
+            keepalive_count_max: Some(3),
+            binary_transfer_retries: 3,
+            shell: None,
+            login_shell: true,
+        };
+
+        let mut options = SshConnectionOptions {
+            host: "example.com".into(),
+            ..Default::default()
+        };
+        assert!(keepalive_args(&options).is_empty());
+
+        options.apply_keepalive_defaults(&defaults);
+        assert_eq!(
+            keepalive_args(&options),
+            ["ServerAliveInterval=30", "ServerAliveCountMax=3"]
+        );
+
+        let mut options = SshConnectionOptions {
+            host: "example.com".into(),
+            keepalive_interval: Some(0),
+            ..Default::default()
+        };
+        options.apply_keepalive_defaults(&defaults);
+        assert_eq!(keepalive_args(&options), ["ServerAliveInterval=0"]);
+
+        let mut options = SshConnectionOptions {
+            host: "example.com".into(),
+            keepalive_interval: Some(15),
+            keepalive_count_max: Some(0),
+            ..Default::default()
+        };
+        options.apply_keepalive_defaults(&defaults);
+        assert_eq!(keepalive_args(&options), ["ServerAliveInterval=15"]);
+
+        // Without settings, only what the connection sets is passed, so the
+        // rest comes from the user's ssh config.
+        let unset = SshSettings {
+            keepalive_interval: None,
+            keepalive_count_max: None,
+            ..defaults
+        };
+        let mut options = SshConnectionOptions {
+            host: "example.com".into(),
+            ..Default::default()
+        };
+        options.apply_keepalive_defaults(&unset);
+        assert!(keepalive_args(&options).is_empty());
+
+        let mut options = SshConnectionOptions {
+            host: "example.com".into(),
+            keepalive_count_max: Some(5),
+            ..Default::default()
+        };
+        options.apply_keepalive_defaults(&unset);
+        assert_eq!(keepalive_args(&options), ["ServerAliveCountMax=5"]);
+    }
+
     #[test]
     fn test_build_command_with_ipv6_port_forward() -> Result<()> {
         let command = build_command_posix(
@@ -2256,6 +3412,8 @@ mod tests {
             PathStyle::Posix,
             "/bin/bash",
             ShellKind::Posix,
+            true,
+            false,
             vec![],
             "user@host",
             Interactive::No,
@@ -1,6 +1,8 @@
 use crate::{
     RemoteArch, RemoteClientDelegate, RemoteOs, RemotePlatform,
     remote_client::{CommandTemplate, Interactive, RemoteConnection, RemoteConnectionOptions},
+    remote_server_env,
+    server_env::env_assignments,
     transport::{parse_platform, parse_shell},
 };
 use anyhow::{Context, Result, anyhow, bail};
@@ -13,6 +15,7 @@ use rpc::proto::Envelope;
 use semver::Version;
 use smol::fs;
 use std::{
+    collections::BTreeMap,
     ffi::OsStr,
     fmt::Write as _,
     path::{Path, PathBuf},
@@ -34,6 +37,9 @@ use util::{
 pub struct WslConnectionOptions {
     pub distro_name: String,
     pub user: Option<String>,
+    /// Environment variables the remote server process is started with.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
 }
 
 impl From<settings::WslConnection> for WslConnectionOptions {
@@ -41,6 +47,7 @@ impl From<settings::WslConnection> for WslConnectionOptions {
         WslConnectionOptions {
             distro_name: val.distro_name,
             user: val.user,
+            env: val.env.unwrap_or_default(),
         }
     }
 }
@@ -351,15 +358,10 @@ impl RemoteConnection for WslRemoteConnection {
             return Task::ready(Err(anyhow!("Remote binary path not set")));
         };
 
-        let mut proxy_args = vec![];
-        for env_var in ["RUST_LOG", "RUST_BACKTRACE", "ZED_GENERATE_MINIDUMPS"] {
-            if let Some(value) = std::env::var(env_var).ok() {
-                // We don't quote the value here as it seems excessive and may result in invalid envs for the
-                // proxy server. For example, `RUST_LOG='debug'` will result in a warning "invalid logging spec 'debug'', ignoring it"
-                // in the proxy server. Therefore, we pass the env vars as is.
-                proxy_args.push(format!("{}={}", env_var, value));
-            }
-        }
+        // We don't quote the values here as it seems excessive and may result in invalid envs for the
+        // proxy server. For example, `RUST_LOG='debug'` will result in a warning "invalid logging spec 'debug'', ignoring it"
+        // in the proxy server. Therefore, we pass the env vars as is; `--exec` runs `env` without a shell.
+        let mut proxy_args = env_assignments(&remote_server_env(&self.connection_options.env));
 
         proxy_args.push(remote_binary_path.display(PathStyle::Posix).into_owned());
         proxy_args.push("proxy".to_owned());
@@ -517,6 +519,10 @@ impl RemoteConnection for WslRemoteConnection {
         RemoteConnectionOptions::Wsl(self.connection_options.clone())
     }
 
+    fn platform(&self) -> Option<RemotePlatform> {
+        Some(self.platform)
+    }
+
     fn path_style(&self) -> PathStyle {
         PathStyle::Posix
     }
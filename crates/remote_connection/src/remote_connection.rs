@@ -6,11 +6,14 @@ use auto_update::AutoUpdater;
 use futures::{FutureExt as _, channel::oneshot, select};
 use gpui::{
     AnyWindowHandle, App, AsyncApp, DismissEvent, Entity, EventEmitter, Focusable, FontFeatures,
-    ParentElement as _, Render, SharedString, Task, TextStyleRefinement, WeakEntity,
+    ParentElement as _, PromptLevel, Render, SharedString, Task, TextStyleRefinement, WeakEntity,
 };
 use markdown::{Markdown, MarkdownElement, MarkdownStyle};
 use release_channel::ReleaseChannel;
-use remote::{ConnectionIdentifier, RemoteClient, RemoteConnectionOptions, RemotePlatform};
+use remote::{
+    ConnectionIdentifier, IncompatibleServerVersion, RemoteClient, RemoteConnectionOptions,
+    RemotePlatform,
+};
 use semver::Version;
 use settings::Settings;
 use theme_settings::ThemeSettings;
@@ -100,6 +103,11 @@ impl RemoteConnectionPrompt {
         cx.notify();
     }
 
+    /// Whether the user is being asked for a password or other input.
+    pub fn is_prompting(&self) -> bool {
+        self.prompt.is_some()
+    }
+
     pub fn set_status(&mut self, status: Option<String>, cx: &mut Context<Self>) {
         self.status_message = status.map(|s| s.into());
         cx.notify();
@@ -288,6 +296,8 @@ pub struct SshConnectionHeader {
     pub connection_string: SharedString,
     pub paths: Vec<PathBuf>,
     pub nickname: Option<SharedString>,
+    /// The server's platform, such as `linux-x86_64`, shown after its name.
+    pub platform: Option<SharedString>,
     pub is_wsl: bool,
     pub is_devcontainer: bool,
 }
@@ -337,6 +347,11 @@ impl RenderOnce for SshConnectionHeader {
                             Label::new(label).color(Color::Muted).size(LabelSize::Small)
                         }),
                     )
+                    .children(self.platform.map(|platform| {
+                        Label::new(platform)
+                            .color(Color::Muted)
+                            .size(LabelSize::Small)
+                    }))
                     .child(div().overflow_x_hidden().text_ellipsis().children(
                         self.paths.into_iter().map(|path| {
                             Label::new(path.to_string_lossy().into_owned())
@@ -372,6 +387,7 @@ impl Render for RemoteConnectionModal {
                     paths: self.paths.clone(),
                     connection_string,
                     nickname,
+                    platform: None,
                     is_wsl,
                     is_devcontainer,
                 }
@@ -472,6 +488,29 @@ impl remote::RemoteClientDelegate for RemoteClientDelegate {
         self.update_status(status, cx)
     }
 
+    fn confirm_server_update(
+        &self,
+        mismatch: &IncompatibleServerVersion,
+        cx: &mut AsyncApp,
+    ) -> Task<bool> {
+        let detail = format!(
+            "{mismatch}. Updating replaces the remote server binary on the host; \
+            if the update fails, the current one is kept."
+        );
+        let Ok(response) = self.window.update(cx, |_, window, cx| {
+            window.prompt(
+                PromptLevel::Warning,
+                "Incompatible Remote Server",
+                Some(&detail),
+                &["Update Remote Server", "Cancel"],
+                cx,
+            )
+        }) else {
+            return Task::ready(false);
+        };
+        cx.spawn(async move |_| response.await == Ok(0))
+    }
+
     fn download_server_binary_locally(
         &self,
         platform: RemotePlatform,
@@ -289,6 +289,7 @@ impl HeadlessProject {
         session.subscribe_to_entity(REMOTE_SERVER_PROJECT_ID, &context_server_store);
 
         session.add_request_handler(cx.weak_entity(), Self::handle_list_remote_directory);
+        session.add_request_handler(cx.weak_entity(), Self::handle_create_remote_directory);
         session.add_request_handler(cx.weak_entity(), Self::handle_get_path_metadata);
         session.add_request_handler(cx.weak_entity(), Self::handle_shutdown_remote_server);
         session.add_request_handler(cx.weak_entity(), Self::handle_ping);
@@ -1171,6 +1172,17 @@ impl HeadlessProject {
         })
     }
 
+    async fn handle_create_remote_directory(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::CreateRemoteDirectory>,
+        cx: AsyncApp,
+    ) -> Result<proto::Ack> {
+        let fs = cx.read_entity(&this, |this, _| this.fs.clone());
+        let expanded = PathBuf::from(shellexpand::tilde(&envelope.payload.path).to_string());
+        fs::create_new_dir(fs.as_ref(), &expanded, envelope.payload.create_parents).await?;
+        Ok(proto::Ack {})
+    }
+
     async fn handle_get_path_metadata(
         this: Entity<Self>,
         envelope: TypedEnvelope<proto::GetPathMetadata>,
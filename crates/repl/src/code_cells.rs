@@ -0,0 +1,401 @@
+//! Code cells in scripts, delimited by marker lines such as `# %%`.
+
+use std::ops::Range;
+
+use collections::HashMap;
+use gpui::App;
+use language::{BufferSnapshot, Language, Point};
+use settings::{ReplCellType, Settings as _};
+
+use crate::ReplSettings;
+
+/// A cell of a script, run as a single execution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeCell {
+    /// From the cell's marker line, or its first line when it has no marker,
+    /// to its last non-blank line.
+    pub range: Range<Point>,
+    /// Whether the cell starts with a marker line. Only the code before the
+    /// first marker has none.
+    pub has_marker: bool,
+    /// The type the tag on the cell's marker line gives it. Markdown and raw
+    /// cells aren't run.
+    pub cell_type: ReplCellType,
+}
+
+/// The lines that start a cell in a language, and the tags on them that give
+/// the cell a type other than code.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CellMarkers {
+    /// The prefixes of marker lines.
+    pub prefixes: Vec<String>,
+    /// The cell types of the tags in brackets after a marker, such as the
+    /// `markdown` in `# %% [markdown]`.
+    pub tags: HashMap<String, ReplCellType>,
+}
+
+impl CellMarkers {
+    /// The marker line `line`'s cell type, from the first of its bracketed
+    /// tags that has one. Jupytext puts tags after an optional title, as in
+    /// `# %% Intro [markdown]`.
+    fn cell_type(&self, line: &str) -> ReplCellType {
+        let Some(rest) = self
+            .prefixes
+            .iter()
+            .find_map(|prefix| line.strip_prefix(prefix.as_str()))
+        else {
+            return ReplCellType::Code;
+        };
+        rest.split('[')
+            .skip(1)
+            .filter_map(|tagged| tagged.split_once(']'))
+            .find_map(|(tag, _)| self.tags.get(tag.trim()).copied())
+            .unwrap_or_default()
+    }
+}
+
+/// The cell markers of `language`, from `repl.cell_markers` and
+/// `repl.cell_tags`. Languages that aren't listed start cells with their line
+/// comment followed by `%%`, and use jupytext's `markdown`, `md` and `raw`
+/// tags.
+pub fn cell_markers(language: &Language, cx: &App) -> CellMarkers {
+    let settings = ReplSettings::get_global(cx);
+    let language_name = language.name();
+    let prefixes = match settings.cell_markers.get(language_name.as_ref()) {
+        Some(markers) => markers.clone(),
+        None => language
+            .default_scope()
+            .line_comment_prefixes()
+            .iter()
+            .map(|comment_prefix| format!("{comment_prefix}%%"))
+            .collect(),
+    };
+    let tags = match settings.cell_tags.get(language_name.as_ref()) {
+        Some(tags) => tags.clone(),
+        None => default_cell_tags(),
+    };
+    CellMarkers { prefixes, tags }
+}
+
+fn default_cell_tags() -> HashMap<String, ReplCellType> {
+    HashMap::from_iter([
+        ("markdown".to_string(), ReplCellType::Markdown),
+        ("md".to_string(), ReplCellType::Markdown),
+        ("raw".to_string(), ReplCellType::Raw),
+    ])
+}
+
+/// Splits `buffer` into cells at lines that start with one of the marker
+/// prefixes. Returns no cells when there are no marker lines.
+///
+/// A run of marker lines starts a single cell, typed by the last of them, and
+/// lines that the syntax tree puts inside a string or a multi-line comment
+/// never start one.
+pub fn code_cells(buffer: &BufferSnapshot, markers: &CellMarkers) -> Vec<CodeCell> {
+    let max_row = buffer.max_point().row;
+    let mut marker_rows: Vec<(u32, ReplCellType)> = Vec::new();
+    let mut previous_marker_row = None;
+    for row in 0..=max_row {
+        let is_marker = markers
+            .prefixes
+            .iter()
+            .any(|marker| buffer.contains_str_at(Point::new(row, 0), marker))
+            && !is_inside_other_syntax(buffer, row);
+        if !is_marker {
+            continue;
+        }
+        let cell_type = markers.cell_type(&line_text(buffer, row));
+        let continues_run = previous_marker_row.is_some_and(|previous| previous + 1 == row);
+        match marker_rows.last_mut() {
+            Some((_, last_cell_type)) if continues_run => *last_cell_type = cell_type,
+            _ => marker_rows.push((row, cell_type)),
+        }
+        previous_marker_row = Some(row);
+    }
+    let Some((first_marker_row, _)) = marker_rows.first().copied() else {
+        return Vec::new();
+    };
+
+    let mut cells = Vec::new();
+    if let Some(range) = non_blank_range(buffer, 0, first_marker_row) {
+        cells.push(CodeCell {
+            range,
+            has_marker: false,
+            cell_type: ReplCellType::Code,
+        });
+    }
+    for (index, (start_row, cell_type)) in marker_rows.iter().copied().enumerate() {
+        let end_row = marker_rows
+            .get(index + 1)
+            .map_or(max_row + 1, |(row, _)| *row);
+        let mut last_row = end_row - 1;
+        while last_row > start_row && buffer.is_line_blank(last_row) {
+            last_row -= 1;
+        }
+        cells.push(CodeCell {
+            range: Point::new(start_row, 0)..Point::new(last_row, buffer.line_len(last_row)),
+            has_marker: true,
+            cell_type,
+        });
+    }
+    cells
+}
+
+/// The text of a markdown or raw `cell` without its marker lines, and with
+/// `comment_prefixes` taken off the start of each line, the way jupytext
+/// writes these cells in scripts. Code cells are returned as they're run.
+pub(crate) fn cell_source(
+    buffer: &BufferSnapshot,
+    cell: &CodeCell,
+    markers: &CellMarkers,
+    comment_prefixes: &[String],
+) -> String {
+    if cell.cell_type == ReplCellType::Code {
+        return buffer.text_for_range(cell.range.clone()).collect();
+    }
+    let mut rows = cell.range.start.row..cell.range.end.row + 1;
+    let first_content_row = rows.find(|row| {
+        let line = line_text(buffer, *row);
+        !markers
+            .prefixes
+            .iter()
+            .any(|prefix| line.starts_with(prefix.as_str()))
+    });
+    let Some(first_content_row) = first_content_row else {
+        return String::new();
+    };
+    (first_content_row..=cell.range.end.row)
+        .map(|row| {
+            let line = line_text(buffer, row);
+            // A blank line is often written as the bare comment, without the
+            // space most prefixes end with.
+            let uncommented = comment_prefixes
+                .iter()
+                .find_map(|prefix| {
+                    line.strip_prefix(prefix.as_str())
+                        .or_else(|| (line.trim_end() == prefix.trim_end()).then_some(""))
+                })
+                .unwrap_or(line.as_str());
+            uncommented.to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn line_text(buffer: &BufferSnapshot, row: u32) -> String {
+    buffer
+        .text_for_range(Point::new(row, 0)..Point::new(row, buffer.line_len(row)))
+        .collect()
+}
+
+/// The whole of `buffer` without its leading and trailing blank lines, for
+/// running a script that has no cell markers.
+pub(crate) fn whole_buffer_range(buffer: &BufferSnapshot) -> Option<Range<Point>> {
+    non_blank_range(buffer, 0, buffer.max_point().row + 1)
+}
+
+/// The rows from `start_row` up to `end_row` without the blank ones at either
+/// end, or `None` when they're all blank.
+fn non_blank_range(buffer: &BufferSnapshot, start_row: u32, end_row: u32) -> Option<Range<Point>> {
+    let first_row = (start_row..end_row).find(|row| !buffer.is_line_blank(*row))?;
+    let last_row = (first_row..end_row)
+        .rev()
+        .find(|row| !buffer.is_line_blank(*row))?;
+    Some(Point::new(first_row, 0)..Point::new(last_row, buffer.line_len(last_row)))
+}
+
+/// Whether the line at `row` is part of a string or of a comment that starts
+/// on an earlier line, according to the syntax tree.
+fn is_inside_other_syntax(buffer: &BufferSnapshot, row: u32) -> bool {
+    let line_start = buffer.point_to_offset(Point::new(row, 0));
+    let mut node = buffer.syntax_ancestor(line_start..line_start + 1);
+    while let Some(current) = node {
+        let kind = current.kind();
+        if kind.contains("string")
+            || (kind.contains("comment") && current.start_byte() < line_start)
+        {
+            return true;
+        }
+        node = current.parent();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::AppContext as _;
+    use indoc::indoc;
+    use language::{Buffer, LanguageConfig};
+    use std::sync::Arc;
+
+    fn cell_texts(buffer: &BufferSnapshot, cells: &[CodeCell]) -> Vec<String> {
+        cells
+            .iter()
+            .map(|cell| buffer.text_for_range(cell.range.clone()).collect())
+            .collect()
+    }
+
+    fn markers(prefixes: &[&str]) -> CellMarkers {
+        CellMarkers {
+            prefixes: prefixes.iter().map(|prefix| prefix.to_string()).collect(),
+            tags: default_cell_tags(),
+        }
+    }
+
+    #[gpui::test]
+    fn test_code_cells(cx: &mut App) {
+        let language = Arc::new(Language::new(
+            LanguageConfig {
+                name: "TestLang".into(),
+                line_comments: vec!["# ".into()],
+                ..Default::default()
+            },
+            None,
+        ));
+        let buffer = cx.new(|cx| {
+            Buffer::local(
+                indoc! {r#"
+                    import os
+
+                    # %%
+                    # %% [markdown]
+                    print(1)
+
+                    #%% second
+                    print(2)
+
+
+                "#},
+                cx,
+            )
+            .with_language(language, cx)
+        });
+        let snapshot = buffer.read(cx).snapshot();
+        let markers = markers(&["# %%", "#%%"]);
+
+        let cells = code_cells(&snapshot, &markers);
+        assert_eq!(
+            cells.iter().map(|cell| cell.has_marker).collect::<Vec<_>>(),
+            [false, true, true]
+        );
+        // A run of markers is typed by its last one.
+        assert_eq!(
+            cells.iter().map(|cell| cell.cell_type).collect::<Vec<_>>(),
+            [
+                ReplCellType::Code,
+                ReplCellType::Markdown,
+                ReplCellType::Code
+            ]
+        );
+        assert_eq!(
+            cell_texts(&snapshot, &cells),
+            [
+                "import os",
+                "# %%\n# %% [markdown]\nprint(1)",
+                "#%% second\nprint(2)",
+            ]
+        );
+
+        assert!(code_cells(&snapshot, &markers(&["// %%"])).is_empty());
+        assert_eq!(
+            whole_buffer_range(&snapshot).map(|range| range.start.row..range.end.row),
+            Some(0..7)
+        );
+    }
+
+    #[gpui::test]
+    fn test_markers_in_strings(cx: &mut App) {
+        let python = languages::language("python", tree_sitter_python::LANGUAGE.into());
+        let buffer = cx.new(|cx| {
+            Buffer::local(
+                indoc! {r#"
+                    notes = """
+                    # %% not a cell
+                    """
+                    # %%
+                    print(notes)
+                "#},
+                cx,
+            )
+            .with_language(python, cx)
+        });
+        let snapshot = buffer.read(cx).snapshot();
+
+        let cells = code_cells(&snapshot, &markers(&["# %%"]));
+        assert_eq!(
+            cell_texts(&snapshot, &cells),
+            [
+                "notes = \"\"\"\n# %% not a cell\n\"\"\"",
+                "# %%\nprint(notes)",
+            ]
+        );
+    }
+
+    #[gpui::test]
+    fn test_cell_types(cx: &mut App) {
+        let language = Arc::new(Language::new(
+            LanguageConfig {
+                name: "TestLang".into(),
+                line_comments: vec!["# ".into()],
+                ..Default::default()
+            },
+            None,
+        ));
+        let buffer = cx.new(|cx| {
+            Buffer::local(
+                indoc! {r#"
+                    # %% Intro [markdown]
+                    # # Title
+                    #
+                    # Some *text*.
+
+                    # %%
+                    print(1)
+
+                    # %% [raw]
+                    # raw text
+                    # %% [notes]
+                    print(2)
+                    # %% [slide]
+                    # shown
+                "#},
+                cx,
+            )
+            .with_language(language, cx)
+        });
+        let snapshot = buffer.read(cx).snapshot();
+        let mut markers = markers(&["# %%"]);
+        markers
+            .tags
+            .insert("slide".to_string(), ReplCellType::Markdown);
+
+        let cells = code_cells(&snapshot, &markers);
+        assert_eq!(
+            cells.iter().map(|cell| cell.cell_type).collect::<Vec<_>>(),
+            [
+                ReplCellType::Markdown,
+                ReplCellType::Code,
+                ReplCellType::Raw,
+                ReplCellType::Code,
+                ReplCellType::Markdown,
+            ]
+        );
+
+        let comment_prefixes = ["# ".to_string()];
+        let sources = cells
+            .iter()
+            .map(|cell| cell_source(&snapshot, cell, &markers, &comment_prefixes))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sources,
+            [
+                "# Title\n\nSome *text*.",
+                "# %%\nprint(1)",
+                "raw text",
+                "# %% [notes]\nprint(2)",
+                "shown",
+            ]
+        );
+    }
+}
@@ -16,10 +16,16 @@ pub enum KernelPickerEntry {
     Kernel {
         spec: KernelSpecification,
         is_recommended: bool,
+        /// Whether the `kernel_selection_rules` setting picks this kernel.
+        is_default: bool,
     },
 }
 
-fn build_grouped_entries(store: &ReplStore, worktree_id: WorktreeId) -> Vec<KernelPickerEntry> {
+fn build_grouped_entries(
+    store: &ReplStore,
+    worktree_id: Option<WorktreeId>,
+    cx: &App,
+) -> Vec<KernelPickerEntry> {
     let mut entries = Vec::new();
     let mut recommended_entry: Option<KernelPickerEntry> = None;
     let mut found_selected = false;
@@ -32,18 +38,21 @@ fn build_grouped_entries(store: &ReplStore, worktree_id: WorktreeId) -> Vec<Kern
 
     for spec in store.kernel_specifications_for_worktree(worktree_id) {
         let is_recommended = store.is_recommended_kernel(worktree_id, spec);
+        let is_default = store.is_default_kernel_by_rules(worktree_id, spec, cx);
         let is_selected = selected_kernel.map_or(false, |s| s == spec);
 
         if is_selected {
             recommended_entry = Some(KernelPickerEntry::Kernel {
                 spec: spec.clone(),
                 is_recommended: true,
+                is_default,
             });
             found_selected = true;
         } else if is_recommended && !found_selected {
             recommended_entry = Some(KernelPickerEntry::Kernel {
                 spec: spec.clone(),
                 is_recommended: true,
+                is_default,
             });
         }
 
@@ -52,24 +61,28 @@ fn build_grouped_entries(store: &ReplStore, worktree_id: WorktreeId) -> Vec<Kern
                 python_envs.push(KernelPickerEntry::Kernel {
                     spec: spec.clone(),
                     is_recommended,
+                    is_default,
                 });
             }
             KernelSpecification::Jupyter(_) => {
                 jupyter_kernels.push(KernelPickerEntry::Kernel {
                     spec: spec.clone(),
                     is_recommended,
+                    is_default,
                 });
             }
             KernelSpecification::JupyterServer(_) | KernelSpecification::SshRemote(_) => {
                 remote_kernels.push(KernelPickerEntry::Kernel {
                     spec: spec.clone(),
                     is_recommended,
+                    is_default,
                 });
             }
             KernelSpecification::WslRemote(_) => {
                 wsl_kernels.push(KernelPickerEntry::Kernel {
                     spec: spec.clone(),
                     is_recommended,
+                    is_default,
                 });
             }
         }
@@ -87,7 +100,7 @@ fn build_grouped_entries(store: &ReplStore, worktree_id: WorktreeId) -> Vec<Kern
         spec_b
             .has_ipykernel()
             .cmp(&spec_a.has_ipykernel())
-            .then_with(|| spec_a.name().cmp(&spec_b.name()))
+            .then_with(|| spec_a.display_name().cmp(&spec_b.display_name()))
     });
 
     // Recommended section
@@ -136,7 +149,8 @@ where
     trigger: T,
     tooltip: TT,
     info_text: Option<SharedString>,
-    worktree_id: WorktreeId,
+    /// The worktree of the edited buffer, or `None` when it isn't in one.
+    worktree_id: Option<WorktreeId>,
 }
 
 pub struct KernelPickerDelegate {
@@ -152,7 +166,12 @@ where
     T: PopoverTrigger + ButtonCommon,
     TT: Fn(&mut Window, &mut App) -> AnyView + 'static,
 {
-    pub fn new(on_select: OnSelect, worktree_id: WorktreeId, trigger: T, tooltip: TT) -> Self {
+    pub fn new(
+        on_select: OnSelect,
+        worktree_id: Option<WorktreeId>,
+        trigger: T,
+        tooltip: TT,
+    ) -> Self {
         KernelSelector {
             on_select,
             handle: None,
@@ -260,7 +279,9 @@ impl PickerDelegate for KernelPickerDelegate {
                         pending_header = Some(entry.clone());
                     }
                     KernelPickerEntry::Kernel { spec, .. } => {
-                        if spec.name().to_lowercase().contains(&query_lower) {
+                        if spec.name().to_lowercase().contains(&query_lower)
+                            || spec.display_name().to_lowercase().contains(&query_lower)
+                        {
                             if let Some(header) = pending_header.take() {
                                 filtered.push(header);
                             }
@@ -329,10 +350,15 @@ impl PickerDelegate for KernelPickerDelegate {
             KernelPickerEntry::Kernel {
                 spec,
                 is_recommended,
+                is_default,
             } => {
                 let is_currently_selected = self.selected_kernelspec.as_ref() == Some(spec);
                 let icon = spec.icon(cx);
                 let has_ipykernel = spec.has_ipykernel();
+                let kernel_info = ReplStore::global(cx)
+                    .read(cx)
+                    .cached_kernel_info(spec)
+                    .cloned();
 
                 let subtitle = match spec {
                     KernelSpecification::Jupyter(_) => None,
@@ -374,11 +400,26 @@ impl PickerDelegate for KernelPickerDelegate {
                                                         .flex_shrink()
                                                         .text_ellipsis()
                                                         .child(
-                                                            Label::new(spec.name())
+                                                            Label::new(spec.display_name())
                                                                 .weight(FontWeight::MEDIUM)
                                                                 .size(LabelSize::Default),
                                                         ),
                                                 )
+                                                .when_some(kernel_info, |flex, info| {
+                                                    flex.child(
+                                                        Label::new(info.label())
+                                                            .size(LabelSize::XSmall)
+                                                            .color(Color::Muted),
+                                                    )
+                                                    .when(info.is_stale, |flex| {
+                                                        flex.child(
+                                                            Label::new("cached")
+                                                                .size(LabelSize::XSmall)
+                                                                .color(Color::Placeholder)
+                                                                .italic(),
+                                                        )
+                                                    })
+                                                })
                                                 .when(*is_recommended, |flex| {
                                                     flex.child(
                                                         Label::new("Recommended")
@@ -386,6 +427,13 @@ impl PickerDelegate for KernelPickerDelegate {
                                                             .color(Color::Accent),
                                                     )
                                                 })
+                                                .when(*is_default, |flex| {
+                                                    flex.child(
+                                                        Label::new("Default")
+                                                            .size(LabelSize::XSmall)
+                                                            .color(Color::Muted),
+                                                    )
+                                                })
                                                 .when(!has_ipykernel, |flex| {
                                                     flex.child(
                                                         Label::new("ipykernel not installed")
@@ -453,7 +501,7 @@ where
         store.update(cx, |store, cx| store.ensure_kernelspecs(cx));
         let store = store.read(cx);
 
-        let all_entries = build_grouped_entries(store, self.worktree_id);
+        let all_entries = build_grouped_entries(store, self.worktree_id, cx);
         let selected_kernelspec = store.active_kernelspec(self.worktree_id, None, cx);
         let selected_index = all_entries
             .iter()
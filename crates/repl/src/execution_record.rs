@@ -0,0 +1,1791 @@
+//! Structured records of the executions a session has run.
+//!
+//! The session renders an execution's outputs as they stream in, but other
+//! features need to know what ran and what it produced after the fact. An
+//! [`ExecutionRecord`] is assembled from the same messages: the code, its
+//! execution count, when it started and finished according to the kernel, how
+//! it ended, and its outputs once `clear_output` and display updates have been
+//! applied. Its [`ExecutionTiming`] splits the time it took into waiting and
+//! running.
+//!
+//! Each [`RecordedOutput`] gives access to its raw data by MIME type, which
+//! is what copying and saving an output works from, rather than the text that
+//! happens to be rendered.
+//!
+//! Only the most recent [`MAX_EXECUTION_RECORDS`] executions are kept, fewer
+//! if their outputs in memory add up to more than
+//! [`MAX_EXECUTION_RECORD_BYTES`], and only the outputs of the newest of them
+//! are all kept in memory. The large outputs of older ones are spilled to
+//! disk in the background, and [`RecordedOutput::load`] reads them back.
+
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+use chrono::{DateTime, Utc};
+use gpui::{App, ClipboardItem, ImageFormat, Task};
+use project::Fs;
+use runtimelib::{
+    ExecutionState, JupyterMessage, JupyterMessageContent, MimeBundle, MimeType, ReplyStatus,
+    Stdio, media::datatable::TabularDataResource,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::output_spill::{
+    OutputSpillDirectory, SPILL_THRESHOLD_BYTES, SpilledOutput, output_spill_root,
+};
+use crate::outputs::{
+    image::{decode_base64, file_extension},
+    stream::{MAX_STREAM_OUTPUT_BYTES, floor_char_boundary, truncation_notice},
+    table::TableView,
+};
+
+/// How many executions a session keeps records of. The oldest are dropped
+/// first.
+pub const MAX_EXECUTION_RECORDS: usize = 200;
+
+/// How many bytes of outputs the records of a session keep in memory, beyond
+/// which the oldest records are dropped. Outputs spilled to disk don't count.
+pub const MAX_EXECUTION_RECORD_BYTES: usize = 64 * 1024 * 1024;
+
+/// How an execution ended, as far as the session knows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionRecordStatus {
+    /// The kernel hasn't replied yet.
+    Pending,
+    Ok,
+    /// The code raised an error. Its details are in the record's outputs.
+    Error,
+    /// The user interrupted the execution. The kernel reports this as an
+    /// error, whose details are in the record's outputs.
+    Interrupted,
+    /// The execution never ran to completion, because it was cancelled, the
+    /// kernel restarted or shut down, or the kernel aborted it.
+    Aborted,
+}
+
+/// How long an execution waited before running, and how long it ran.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionTiming {
+    /// From submitting the code to the kernel starting to run it, including
+    /// time spent behind other executions.
+    pub queued: Duration,
+    pub running: Duration,
+    /// Whether the durations were measured with Zed's clock as messages
+    /// arrived, because the kernel's messages lacked timestamps. These include
+    /// the time messages took to arrive.
+    pub approximate: bool,
+}
+
+impl ExecutionTiming {
+    /// A summary such as "ran in 2.3s (queued 37.9s)". Queueing too short to
+    /// show is left out.
+    pub fn summary(&self) -> String {
+        let prefix = if self.approximate { "~" } else { "" };
+        let mut summary = format!("ran in {prefix}{:.1}s", self.running.as_secs_f64());
+        if self.queued >= Duration::from_millis(100) {
+            summary.push_str(&format!(
+                " (queued {prefix}{:.1}s)",
+                self.queued.as_secs_f64()
+            ));
+        }
+        summary
+    }
+}
+
+/// An output of an execution, after any clears and display updates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RecordedOutput {
+    /// Stream text, with consecutive text on the same stream joined together.
+    Stream { name: String, text: String },
+    /// Rich data from `display_data` or `execute_result`.
+    Data {
+        data: MimeBundle,
+        display_id: Option<String>,
+        /// Set for the execution's result, as opposed to displayed data.
+        execution_count: Option<usize>,
+    },
+    Error {
+        ename: String,
+        evalue: String,
+        traceback: Vec<String>,
+    },
+    /// A large output of an older execution, written to disk to save memory.
+    /// Its data is only available once [`RecordedOutput::load`] reads it back.
+    #[serde(skip)]
+    Spilled(SpilledOutput),
+}
+
+/// The data of an image output, decoded from what the kernel sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputImage {
+    pub format: ImageFormat,
+    /// The image file's bytes. SVGs are their source.
+    pub bytes: Vec<u8>,
+}
+
+/// An output's binary data, ready to be saved to a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputFile {
+    /// A name such as `output-7.png`, from the execution count and the type
+    /// of the data.
+    pub suggested_name: String,
+    pub bytes: Vec<u8>,
+}
+
+impl OutputFile {
+    /// Writes the data to `path` exactly as the kernel sent it.
+    pub async fn save(&self, fs: &dyn Fs, path: &Path) -> Result<()> {
+        fs.write(path, &self.bytes)
+            .await
+            .with_context(|| format!("failed to save output to {}", path.display()))
+    }
+}
+
+impl RecordedOutput {
+    /// The output itself, read back from disk if it was spilled.
+    pub async fn load(&self, fs: &dyn Fs) -> Result<Cow<'_, RecordedOutput>> {
+        let RecordedOutput::Spilled(spilled) = self else {
+            return Ok(Cow::Borrowed(self));
+        };
+        let output = serde_json::from_slice(&spilled.load(fs).await?)
+            .with_context(|| format!("failed to read spilled output {:?}", spilled.path))?;
+        Ok(Cow::Owned(output))
+    }
+
+    /// The output's `text/plain` data, or the text of a stream.
+    pub fn plain_text(&self) -> Option<&str> {
+        match self {
+            RecordedOutput::Stream { text, .. } => Some(text),
+            _ => self.media().iter().find_map(|media| match media {
+                MimeType::Plain(text) => Some(text.as_str()),
+                _ => None,
+            }),
+        }
+    }
+
+    /// The output's `text/html` data.
+    pub fn html(&self) -> Option<&str> {
+        self.media().iter().find_map(|media| match media {
+            MimeType::Html(html) => Some(html.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The output's table, from its `application/vnd.dataresource+json` data.
+    pub fn table(&self) -> Option<&TabularDataResource> {
+        self.media().iter().find_map(|media| match media {
+            MimeType::DataTable(table) => Some::<&TabularDataResource>(table),
+            _ => None,
+        })
+    }
+
+    /// The output's PNG, JPEG or SVG image, decoded from base64 for the
+    /// raster formats.
+    pub fn image(&self) -> Result<Option<OutputImage>> {
+        let Some((format, data)) = self.image_data() else {
+            return Ok(None);
+        };
+        let bytes = match format {
+            ImageFormat::Svg => data.as_bytes().to_vec(),
+            _ => decode_base64(data)?,
+        };
+        Ok(Some(OutputImage { format, bytes }))
+    }
+
+    fn image_data(&self) -> Option<(ImageFormat, &str)> {
+        self.media().iter().find_map(|media| match media {
+            MimeType::Png(data) => Some((ImageFormat::Png, data.as_str())),
+            MimeType::Jpeg(data) => Some((ImageFormat::Jpeg, data.as_str())),
+            MimeType::Svg(data) => Some((ImageFormat::Svg, data.as_str())),
+            _ => None,
+        })
+    }
+
+    fn media(&self) -> &[MimeType] {
+        match self {
+            RecordedOutput::Data { data, .. } => &data.content,
+            RecordedOutput::Stream { .. }
+            | RecordedOutput::Error { .. }
+            | RecordedOutput::Spilled(_) => &[],
+        }
+    }
+}
+
+/// What's known about one execution, assembled from the kernel's messages.
+#[derive(Debug, Clone)]
+pub struct ExecutionRecord {
+    /// The id of the `execute_request`, which its messages are children of.
+    pub msg_id: String,
+    pub code: String,
+    /// The kernel's count for this execution. Counts start over when the
+    /// kernel restarts.
+    pub execution_count: Option<usize>,
+    /// How many times the kernel had restarted before this execution. Counts
+    /// are only unique among records with the same number of restarts.
+    pub restarts: usize,
+    pub submitted_at: DateTime<Utc>,
+    /// When the kernel went busy running the code.
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the kernel went idle again.
+    pub finished_at: Option<DateTime<Utc>>,
+    /// The kernel's timestamp on the `execute_input` it broadcast.
+    pub input_at: Option<DateTime<Utc>>,
+    /// The kernel's timestamp on its `execute_reply`.
+    pub replied_at: Option<DateTime<Utc>>,
+    pub status: ExecutionRecordStatus,
+    pub outputs: Vec<RecordedOutput>,
+    /// Whether stream text was dropped for going over
+    /// [`MAX_STREAM_OUTPUT_BYTES`].
+    pub truncated: bool,
+    /// The file the execution's stream output is also logged to, which keeps
+    /// all of it.
+    pub output_log_path: Option<PathBuf>,
+    /// Whether the error the execution raised was caused by the user
+    /// interrupting it.
+    interrupted: bool,
+    /// The index of the stream output that text was dropped from.
+    truncated_output: Option<usize>,
+    /// Whether the outputs too large to keep in memory were spilled to disk,
+    /// or are being written.
+    outputs_spilled: bool,
+    /// Bumped whenever the outputs change, so that a spill that finishes
+    /// after they did isn't applied.
+    outputs_version: usize,
+    /// Files of spilled outputs that were cleared or updated, for
+    /// [`ExecutionRecords`] to delete.
+    discarded_spills: Vec<SpilledOutput>,
+    /// A `clear_output(wait=True)` waiting for the next output.
+    clear_pending: bool,
+    stream_bytes: usize,
+    /// Roughly how many bytes the outputs kept in memory take up.
+    output_bytes: usize,
+    /// When the messages that started and ended the execution arrived,
+    /// according to Zed's clock.
+    received_start_at: Option<DateTime<Utc>>,
+    received_end_at: Option<DateTime<Utc>>,
+}
+
+impl ExecutionRecord {
+    pub fn new(request: &JupyterMessage, code: String) -> Self {
+        Self {
+            msg_id: request.header.msg_id.clone(),
+            code,
+            execution_count: None,
+            restarts: 0,
+            submitted_at: request.header.date,
+            started_at: None,
+            finished_at: None,
+            input_at: None,
+            replied_at: None,
+            status: ExecutionRecordStatus::Pending,
+            outputs: Vec::new(),
+            truncated: false,
+            output_log_path: None,
+            interrupted: false,
+            truncated_output: None,
+            outputs_spilled: false,
+            outputs_version: 0,
+            discarded_spills: Vec::new(),
+            clear_pending: false,
+            stream_bytes: 0,
+            output_bytes: 0,
+            received_start_at: None,
+            received_end_at: None,
+        }
+    }
+
+    /// Splits the execution's time into waiting and running, once the kernel
+    /// has replied or gone idle.
+    ///
+    /// The kernel's clock may not agree with Zed's, so the running time comes
+    /// from the kernel's own timestamps, from going busy (or broadcasting the
+    /// input) to replying (or going idle). The queued time is what remains of
+    /// the time Zed observed between submitting the code and that end.
+    pub fn timing(&self) -> Option<ExecutionTiming> {
+        let received_end_at = self.received_end_at?;
+        let total = duration_between(self.submitted_at, received_end_at);
+
+        let kernel_start = self.started_at.or(self.input_at).filter(has_timestamp);
+        let kernel_end = self.replied_at.or(self.finished_at).filter(has_timestamp);
+        if let (Some(kernel_start), Some(kernel_end)) = (kernel_start, kernel_end) {
+            let running = duration_between(kernel_start, kernel_end);
+            return Some(ExecutionTiming {
+                queued: total.saturating_sub(running),
+                running,
+                approximate: false,
+            });
+        }
+
+        let received_start_at = self.received_start_at.unwrap_or(received_end_at);
+        Some(ExecutionTiming {
+            queued: duration_between(self.submitted_at, received_start_at),
+            running: duration_between(received_start_at, received_end_at),
+            approximate: true,
+        })
+    }
+
+    /// Whether the kernel has both replied and gone idle, or the execution was
+    /// aborted.
+    pub fn is_complete(&self) -> bool {
+        match self.status {
+            ExecutionRecordStatus::Pending => false,
+            ExecutionRecordStatus::Aborted => true,
+            ExecutionRecordStatus::Ok
+            | ExecutionRecordStatus::Error
+            | ExecutionRecordStatus::Interrupted => self.finished_at.is_some(),
+        }
+    }
+
+    /// The execution's outputs, with any that were spilled to disk read back.
+    pub async fn load_outputs(&self, fs: &dyn Fs) -> Result<Vec<Cow<'_, RecordedOutput>>> {
+        let mut outputs = Vec::with_capacity(self.outputs.len());
+        for output in &self.outputs {
+            outputs.push(output.load(fs).await?);
+        }
+        Ok(outputs)
+    }
+
+    /// The output at `index` as plain text for the clipboard. Errors are
+    /// summarized by their name and value, and tables become markdown. Stream
+    /// text that was cut short ends with the same notice that's shown for it.
+    pub async fn copy_as_plain_text(
+        &self,
+        index: usize,
+        fs: &dyn Fs,
+    ) -> Result<Option<ClipboardItem>> {
+        let Some(output) = self.outputs.get(index) else {
+            return Ok(None);
+        };
+        let output = output.load(fs).await?;
+        let text = match output.as_ref() {
+            RecordedOutput::Error { ename, evalue, .. } => Some(format!("{ename}: {evalue}")),
+            output => output
+                .plain_text()
+                .map(ToString::to_string)
+                .or_else(|| output.table().map(TableView::create_clipboard_content)),
+        };
+        let Some(mut text) = text else {
+            return Ok(None);
+        };
+        if self.truncated_output == Some(index) {
+            text.push_str(&truncation_notice());
+        }
+        Ok(Some(ClipboardItem::new_string(text)))
+    }
+
+    /// The `text/html` data of the output at `index`, for the clipboard.
+    pub async fn copy_as_html(&self, index: usize, fs: &dyn Fs) -> Result<Option<ClipboardItem>> {
+        let Some(output) = self.outputs.get(index) else {
+            return Ok(None);
+        };
+        let output = output.load(fs).await?;
+        Ok(output
+            .html()
+            .map(|html| ClipboardItem::new_string(html.to_string())))
+    }
+
+    /// The binary data of the output at `index`, such as an image, to save to
+    /// a file. When the execution has several such outputs, the suggested
+    /// name says which one this is, as in `output-7-2.png`.
+    pub async fn output_file(&self, index: usize, fs: &dyn Fs) -> Result<Option<OutputFile>> {
+        if index >= self.outputs.len() {
+            return Ok(None);
+        }
+        let outputs = self.load_outputs(fs).await?;
+        let Some(image) = outputs[index].image()? else {
+            return Ok(None);
+        };
+
+        let mut suggested_name = match self.execution_count {
+            Some(execution_count) => format!("output-{execution_count}"),
+            None => "output".to_string(),
+        };
+        let has_binary_data = |output: &&Cow<RecordedOutput>| output.image_data().is_some();
+        if outputs.iter().filter(has_binary_data).count() > 1 {
+            let position = outputs[..index].iter().filter(has_binary_data).count() + 1;
+            suggested_name.push_str(&format!("-{position}"));
+        }
+        suggested_name.push('.');
+        suggested_name.push_str(file_extension(image.format));
+
+        Ok(Some(OutputFile {
+            suggested_name,
+            bytes: image.bytes,
+        }))
+    }
+
+    /// Updates the record with a message that's a child of its request,
+    /// which arrived at `received_at` by Zed's clock.
+    pub fn handle_message(&mut self, message: &JupyterMessage, received_at: DateTime<Utc>) {
+        match &message.content {
+            JupyterMessageContent::Status(status) => match status.execution_state {
+                ExecutionState::Busy => {
+                    self.started_at.get_or_insert(message.header.date);
+                    self.received_start_at.get_or_insert(received_at);
+                }
+                ExecutionState::Idle => {
+                    self.finished_at = Some(message.header.date);
+                    self.received_end_at.get_or_insert(received_at);
+                    // No more outputs are coming to replace the cleared ones.
+                    self.clear_pending = false;
+                }
+                _ => {}
+            },
+            JupyterMessageContent::ExecuteInput(input) => {
+                self.execution_count = Some(input.execution_count.0);
+                self.input_at.get_or_insert(message.header.date);
+                self.received_start_at.get_or_insert(received_at);
+            }
+            JupyterMessageContent::ExecuteReply(reply) => {
+                self.execution_count = Some(reply.execution_count.0);
+                self.replied_at = Some(message.header.date);
+                self.received_end_at = Some(received_at);
+                self.status = match reply.status {
+                    ReplyStatus::Ok => ExecutionRecordStatus::Ok,
+                    ReplyStatus::Error if self.interrupted => ExecutionRecordStatus::Interrupted,
+                    ReplyStatus::Error => ExecutionRecordStatus::Error,
+                    _ => ExecutionRecordStatus::Aborted,
+                };
+            }
+            JupyterMessageContent::StreamContent(stream) => {
+                let name = match stream.name {
+                    Stdio::Stdout => "stdout",
+                    Stdio::Stderr => "stderr",
+                };
+                self.push_stream(name, &stream.text);
+            }
+            JupyterMessageContent::DisplayData(display) => {
+                self.push_output(RecordedOutput::Data {
+                    data: display.data.clone(),
+                    display_id: display
+                        .transient
+                        .as_ref()
+                        .and_then(|transient| transient.display_id.clone()),
+                    execution_count: None,
+                });
+            }
+            JupyterMessageContent::ExecuteResult(result) => {
+                self.execution_count = Some(result.execution_count.0);
+                self.push_output(RecordedOutput::Data {
+                    data: result.data.clone(),
+                    display_id: result
+                        .transient
+                        .as_ref()
+                        .and_then(|transient| transient.display_id.clone()),
+                    execution_count: Some(result.execution_count.0),
+                });
+            }
+            JupyterMessageContent::ErrorOutput(error) => {
+                self.push_output(RecordedOutput::Error {
+                    ename: error.ename.clone(),
+                    evalue: error.evalue.clone(),
+                    traceback: error.traceback.clone(),
+                });
+            }
+            JupyterMessageContent::ClearOutput(options) => {
+                if options.wait {
+                    self.clear_pending = true;
+                } else {
+                    self.clear();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Replaces the data of outputs shown under `display_id`. Updates can
+    /// come from any execution, so the session applies them to every record.
+    pub fn update_display_data(&mut self, new_data: &MimeBundle, display_id: &str) {
+        let mut updated_any = false;
+        for output in &mut self.outputs {
+            match output {
+                RecordedOutput::Data {
+                    data,
+                    display_id: Some(output_display_id),
+                    ..
+                } if output_display_id == display_id => {
+                    *data = new_data.clone();
+                    updated_any = true;
+                }
+                // The new data is kept in memory until the record's outputs are
+                // spilled again.
+                RecordedOutput::Spilled(spilled)
+                    if spilled.display_id.as_deref() == Some(display_id) =>
+                {
+                    let updated = RecordedOutput::Data {
+                        data: new_data.clone(),
+                        display_id: spilled.display_id.take(),
+                        execution_count: spilled.execution_count,
+                    };
+                    if let RecordedOutput::Spilled(spilled) = std::mem::replace(output, updated) {
+                        self.discarded_spills.push(spilled);
+                    }
+                    self.outputs_spilled = false;
+                    updated_any = true;
+                }
+                _ => {}
+            }
+        }
+        if updated_any {
+            self.outputs_version += 1;
+            self.output_bytes = self
+                .outputs
+                .iter()
+                .filter(|output| !matches!(output, RecordedOutput::Spilled(_)))
+                .map(serialized_len)
+                .sum();
+        }
+    }
+
+    /// The outputs that aren't spilled yet, each with a path in `directory`
+    /// to spill it to if it turns out to be large enough.
+    fn outputs_to_spill(
+        &self,
+        directory: &mut OutputSpillDirectory,
+    ) -> Vec<(usize, RecordedOutput, PathBuf)> {
+        self.outputs
+            .iter()
+            .enumerate()
+            .filter(|(_, output)| !matches!(output, RecordedOutput::Spilled(_)))
+            .map(|(index, output)| (index, output.clone(), directory.next_path()))
+            .collect()
+    }
+
+    /// Puts the handles of outputs that were written to disk in their place.
+    fn finish_spill(&mut self, spilled_outputs: Vec<(usize, SpilledOutput)>) {
+        for (index, mut spilled) in spilled_outputs {
+            let Some(output) = self.outputs.get_mut(index) else {
+                continue;
+            };
+            match output {
+                RecordedOutput::Data {
+                    display_id,
+                    execution_count,
+                    ..
+                } => {
+                    spilled.display_id = display_id.clone();
+                    spilled.execution_count = *execution_count;
+                }
+                RecordedOutput::Stream { .. } | RecordedOutput::Error { .. } => {}
+                RecordedOutput::Spilled(_) => continue,
+            }
+            self.output_bytes = self.output_bytes.saturating_sub(spilled.size as usize);
+            *output = RecordedOutput::Spilled(spilled);
+        }
+    }
+
+    /// The outputs that were spilled, whose files the record no longer
+    /// needs once it's dropped.
+    fn spilled_outputs(&self) -> impl Iterator<Item = &SpilledOutput> {
+        self.outputs.iter().filter_map(|output| match output {
+            RecordedOutput::Spilled(spilled) => Some(spilled),
+            _ => None,
+        })
+    }
+
+    fn clear(&mut self) {
+        for output in self.outputs.drain(..) {
+            if let RecordedOutput::Spilled(spilled) = output {
+                self.discarded_spills.push(spilled);
+            }
+        }
+        self.outputs_version += 1;
+        self.truncated_output = None;
+        self.clear_pending = false;
+        self.stream_bytes = 0;
+        self.output_bytes = 0;
+    }
+
+    fn push_output(&mut self, output: RecordedOutput) {
+        if self.clear_pending {
+            self.clear();
+        }
+        self.output_bytes += serialized_len(&output);
+        self.outputs.push(output);
+        self.outputs_version += 1;
+    }
+
+    fn push_stream(&mut self, name: &str, text: &str) {
+        if self.clear_pending {
+            self.clear();
+        }
+        let remaining = MAX_STREAM_OUTPUT_BYTES.saturating_sub(self.stream_bytes);
+        let kept = &text[..floor_char_boundary(text, remaining)];
+        let cut = kept.len() < text.len();
+        if cut {
+            self.truncated = true;
+        }
+        if !kept.is_empty() {
+            self.outputs_version += 1;
+            self.stream_bytes += kept.len();
+            self.output_bytes += kept.len();
+            if let Some(RecordedOutput::Stream {
+                name: last_name,
+                text: last_text,
+            }) = self.outputs.last_mut()
+                && last_name == name
+            {
+                last_text.push_str(kept);
+            } else {
+                self.outputs.push(RecordedOutput::Stream {
+                    name: name.to_string(),
+                    text: kept.to_string(),
+                });
+            }
+        }
+        if cut && self.truncated_output.is_none() {
+            self.truncated_output = self
+                .outputs
+                .iter()
+                .rposition(|output| matches!(output, RecordedOutput::Stream { .. }));
+        }
+    }
+}
+
+/// Whether a kernel message carried a timestamp. Kernels that leave the date
+/// out of their headers end up with the Unix epoch.
+fn has_timestamp(date: &DateTime<Utc>) -> bool {
+    date.timestamp() > 0
+}
+
+/// How many bytes `output` takes up serialized, which is roughly what it takes
+/// up in memory.
+fn serialized_len(output: &RecordedOutput) -> usize {
+    struct ByteCounter(usize);
+
+    impl std::io::Write for ByteCounter {
+        fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+            self.0 += bytes.len();
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = ByteCounter(0);
+    if let Err(error) = serde_json::to_writer(&mut counter, output) {
+        log::error!("Failed to measure an output: {error}");
+    }
+    counter.0
+}
+
+/// The time from `start` to `end`, or zero if `end` comes first.
+fn duration_between(start: DateTime<Utc>, end: DateTime<Utc>) -> Duration {
+    (end - start).to_std().unwrap_or_default()
+}
+
+/// Outputs of an execution that were written to disk by
+/// [`ExecutionRecords::spill_older_outputs`], to be put in place of the ones
+/// in memory by [`ExecutionRecords::finish_spill`].
+#[derive(Debug)]
+pub struct PendingSpill {
+    msg_id: String,
+    outputs_version: usize,
+    spilled_outputs: Vec<(usize, SpilledOutput)>,
+}
+
+/// The records of a session's most recent executions, oldest first.
+#[derive(Debug, Default)]
+pub struct ExecutionRecords {
+    records: VecDeque<ExecutionRecord>,
+    restarts: usize,
+    /// Where the outputs of older executions are spilled, once any are.
+    spill_directory: Option<OutputSpillDirectory>,
+}
+
+impl ExecutionRecords {
+    /// Starts a record for the execution sent as `request`, dropping the
+    /// oldest records while there are too many of them, or their outputs in
+    /// memory take up more than [`MAX_EXECUTION_RECORD_BYTES`].
+    pub fn start(&mut self, request: &JupyterMessage, code: String) -> &mut ExecutionRecord {
+        while self.records.len() >= MAX_EXECUTION_RECORDS
+            || self.output_bytes() > MAX_EXECUTION_RECORD_BYTES
+        {
+            let Some(dropped) = self.records.pop_front() else {
+                break;
+            };
+            if let Some(directory) = &self.spill_directory {
+                for spilled in dropped.spilled_outputs().chain(&dropped.discarded_spills) {
+                    directory.remove(spilled);
+                }
+            }
+        }
+        let mut record = ExecutionRecord::new(request, code);
+        record.restarts = self.restarts;
+        self.records.push_back(record);
+        let index = self.records.len() - 1;
+        &mut self.records[index]
+    }
+
+    /// Spills the large outputs of all but the `kept_in_memory` most recent
+    /// executions to disk, leaving their code and everything else about them
+    /// in memory. Executions that haven't completed keep their outputs, since
+    /// more may be added to them.
+    ///
+    /// The outputs are written in the background, and stay in memory until
+    /// the returned task's result is passed to
+    /// [`ExecutionRecords::finish_spill`]. Outputs that can't be written stay
+    /// in memory. Returns `None` when there's nothing to spill.
+    pub fn spill_older_outputs(
+        &mut self,
+        kept_in_memory: usize,
+        fs: &Arc<dyn Fs>,
+        cx: &App,
+    ) -> Option<Task<Vec<PendingSpill>>> {
+        let older = self.records.len().saturating_sub(kept_in_memory);
+        let mut spills = Vec::new();
+        for record in self.records.iter_mut().take(older) {
+            if record.outputs_spilled || !record.is_complete() {
+                continue;
+            }
+            let directory = self.spill_directory.get_or_insert_with(|| {
+                OutputSpillDirectory::new(
+                    &output_spill_root(),
+                    fs.clone(),
+                    cx.background_executor().clone(),
+                )
+            });
+            // Not retried after a failure, which would likely fail again.
+            record.outputs_spilled = true;
+            spills.push((
+                record.msg_id.clone(),
+                record.outputs_version,
+                record.outputs_to_spill(directory),
+            ));
+        }
+        if spills.is_empty() {
+            return None;
+        }
+
+        let fs = self.spill_directory.as_ref()?.fs().clone();
+        Some(cx.background_spawn(async move {
+            let mut pending = Vec::with_capacity(spills.len());
+            for (msg_id, outputs_version, outputs) in spills {
+                let mut spilled_outputs = Vec::new();
+                for (index, output, path) in outputs {
+                    let spilled = async {
+                        let bytes = serde_json::to_vec(&output)?;
+                        if bytes.len() < SPILL_THRESHOLD_BYTES {
+                            return anyhow::Ok(None);
+                        }
+                        SpilledOutput::write(fs.as_ref(), path, &bytes)
+                            .await
+                            .map(Some)
+                    };
+                    match spilled.await {
+                        Ok(Some(spilled)) => spilled_outputs.push((index, spilled)),
+                        Ok(None) => {}
+                        Err(error) => {
+                            log::warn!(
+                                "Keeping the outputs of execution {msg_id} in memory: {error:#}"
+                            );
+                            break;
+                        }
+                    }
+                }
+                pending.push(PendingSpill {
+                    msg_id,
+                    outputs_version,
+                    spilled_outputs,
+                });
+            }
+            pending
+        }))
+    }
+
+    /// Puts the outputs written by [`ExecutionRecords::spill_older_outputs`]
+    /// in place of the ones in memory, returning the ids of the executions
+    /// any were spilled for. Outputs that changed while they were being
+    /// written are kept in memory, and spilled again later.
+    pub fn finish_spill(&mut self, pending: Vec<PendingSpill>) -> Vec<String> {
+        let mut spilled_executions = Vec::new();
+        for spill in pending {
+            let record = self
+                .records
+                .iter_mut()
+                .find(|record| record.msg_id == spill.msg_id);
+            match record {
+                Some(record) if record.outputs_version == spill.outputs_version => {
+                    if !spill.spilled_outputs.is_empty() {
+                        record.finish_spill(spill.spilled_outputs);
+                        spilled_executions.push(spill.msg_id);
+                    }
+                }
+                record => {
+                    if let Some(record) = record {
+                        record.outputs_spilled = false;
+                    }
+                    if let Some(directory) = &self.spill_directory {
+                        for (_, spilled) in &spill.spilled_outputs {
+                            directory.remove(spilled);
+                        }
+                    }
+                }
+            }
+        }
+        spilled_executions
+    }
+
+    /// Deletes the files of spilled outputs that were cleared or updated.
+    fn remove_discarded_spills(&mut self) {
+        for record in &mut self.records {
+            for spilled in record.discarded_spills.drain(..) {
+                if let Some(directory) = &self.spill_directory {
+                    directory.remove(&spilled);
+                }
+            }
+        }
+    }
+
+    /// How many bytes the outputs the records keep in memory take up.
+    fn output_bytes(&self) -> usize {
+        self.records.iter().map(|record| record.output_bytes).sum()
+    }
+
+    /// Marks where the kernel restarted, after which its execution counts
+    /// start over.
+    pub fn record_restart(&mut self) {
+        self.restarts += 1;
+    }
+
+    /// Updates the records with a message from the kernel that arrived at
+    /// `received_at`. Messages that aren't about a recorded execution are
+    /// ignored.
+    pub fn handle_message(&mut self, message: &JupyterMessage, received_at: DateTime<Utc>) {
+        if let JupyterMessageContent::UpdateDisplayData(update) = &message.content {
+            if let Some(display_id) = &update.transient.display_id {
+                for record in &mut self.records {
+                    record.update_display_data(&update.data, display_id);
+                }
+                self.remove_discarded_spills();
+            }
+            return;
+        }
+
+        let Some(parent_header) = &message.parent_header else {
+            return;
+        };
+        if let Some(record) = self.get_mut(&parent_header.msg_id) {
+            record.handle_message(message, received_at);
+        }
+        self.remove_discarded_spills();
+    }
+
+    /// Marks the execution aborted, unless the kernel already replied to it.
+    pub fn abort(&mut self, msg_id: &str) {
+        if let Some(record) = self.get_mut(msg_id)
+            && record.status == ExecutionRecordStatus::Pending
+        {
+            record.status = ExecutionRecordStatus::Aborted;
+        }
+    }
+
+    /// Marks the error of the execution sent as `msg_id` as caused by the
+    /// user interrupting it, whether or not the kernel has replied yet.
+    pub fn mark_interrupted(&mut self, msg_id: &str) {
+        if let Some(record) = self.get_mut(msg_id) {
+            record.interrupted = true;
+            if record.status == ExecutionRecordStatus::Error {
+                record.status = ExecutionRecordStatus::Interrupted;
+            }
+        }
+    }
+
+    /// Marks every execution the kernel hasn't replied to as aborted.
+    pub fn abort_pending(&mut self) {
+        for record in &mut self.records {
+            if record.status == ExecutionRecordStatus::Pending {
+                record.status = ExecutionRecordStatus::Aborted;
+            }
+        }
+    }
+
+    pub fn get(&self, msg_id: &str) -> Option<&ExecutionRecord> {
+        self.records.iter().find(|record| record.msg_id == msg_id)
+    }
+
+    fn get_mut(&mut self, msg_id: &str) -> Option<&mut ExecutionRecord> {
+        self.records
+            .iter_mut()
+            .find(|record| record.msg_id == msg_id)
+    }
+
+    /// The execution with the given count since the kernel last restarted.
+    /// Counts start over when the kernel restarts, so earlier executions may
+    /// share it.
+    pub fn for_execution_count(&self, execution_count: usize) -> Option<&ExecutionRecord> {
+        self.records
+            .iter()
+            .rev()
+            .take_while(|record| record.restarts == self.restarts)
+            .find(|record| record.execution_count == Some(execution_count))
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &ExecutionRecord> {
+        self.records.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use runtimelib::{
+        ClearOutput, DisplayData, ErrorOutput, ExecuteInput, ExecuteReply, ExecuteRequest,
+        ExecuteResult, Status, StreamContent, UpdateDisplayData,
+    };
+    use serde_json::json;
+
+    fn request(code: &str) -> JupyterMessage {
+        ExecuteRequest {
+            code: code.to_string(),
+            ..ExecuteRequest::default()
+        }
+        .into()
+    }
+
+    fn child(
+        content: impl Into<JupyterMessageContent>,
+        request: &JupyterMessage,
+    ) -> JupyterMessage {
+        JupyterMessage::new(content, Some(request))
+    }
+
+    fn request_code(request: &JupyterMessage) -> String {
+        match &request.content {
+            JupyterMessageContent::ExecuteRequest(request) => request.code.clone(),
+            _ => String::new(),
+        }
+    }
+
+    fn status(execution_state: ExecutionState) -> Status {
+        Status { execution_state }
+    }
+
+    fn stream(name: Stdio, text: &str) -> StreamContent {
+        StreamContent {
+            name,
+            text: text.to_string(),
+        }
+    }
+
+    fn display(text: &str, display_id: Option<&str>) -> DisplayData {
+        serde_json::from_value(json!({
+            "data": { "text/plain": text },
+            "metadata": {},
+            "transient": display_id.map(|display_id| json!({ "display_id": display_id })),
+        }))
+        .unwrap()
+    }
+
+    fn reply(status: &str, execution_count: usize) -> ExecuteReply {
+        serde_json::from_value(json!({
+            "status": status,
+            "execution_count": execution_count,
+        }))
+        .unwrap()
+    }
+
+    fn record_status(
+        records: &ExecutionRecords,
+        request: &JupyterMessage,
+    ) -> ExecutionRecordStatus {
+        records.get(&request.header.msg_id).unwrap().status
+    }
+
+    #[test]
+    fn test_successful_execution() {
+        let mut records = ExecutionRecords::default();
+        let request = request("x = 1\nx + 1");
+        records.start(&request, "x = 1\nx + 1".to_string());
+
+        let mut busy = child(status(ExecutionState::Busy), &request);
+        let started_at = request.header.date + chrono::Duration::milliseconds(5);
+        busy.header.date = started_at;
+        let mut idle = child(status(ExecutionState::Idle), &request);
+        let finished_at = started_at + chrono::Duration::seconds(2);
+        idle.header.date = finished_at;
+        let result: ExecuteResult = serde_json::from_value(json!({
+            "data": { "text/plain": "2" },
+            "metadata": {},
+            "execution_count": 7,
+        }))
+        .unwrap();
+
+        let messages = [
+            busy,
+            child(
+                serde_json::from_value::<ExecuteInput>(
+                    json!({ "code": "x = 1\nx + 1", "execution_count": 7 }),
+                )
+                .unwrap(),
+                &request,
+            ),
+            child(stream(Stdio::Stdout, "one "), &request),
+            child(stream(Stdio::Stdout, "two\n"), &request),
+            child(stream(Stdio::Stderr, "warning\n"), &request),
+            child(result, &request),
+            child(reply("ok", 7), &request),
+            idle,
+        ];
+        for message in &messages {
+            assert!(!records.get(&request.header.msg_id).unwrap().is_complete());
+            records.handle_message(message, Utc::now());
+        }
+
+        let record = records.get(&request.header.msg_id).unwrap();
+        assert!(record.is_complete());
+        assert_eq!(record.code, "x = 1\nx + 1");
+        assert_eq!(record.execution_count, Some(7));
+        assert_eq!(record.status, ExecutionRecordStatus::Ok);
+        assert_eq!(record.submitted_at, request.header.date);
+        assert_eq!(record.started_at, Some(started_at));
+        assert_eq!(record.finished_at, Some(finished_at));
+        assert!(!record.truncated);
+
+        let outputs: Vec<_> = record
+            .outputs
+            .iter()
+            .map(RecordedOutput::plain_text)
+            .collect();
+        assert_eq!(outputs, [Some("one two\n"), Some("warning\n"), Some("2")]);
+        assert!(matches!(
+            &record.outputs[1],
+            RecordedOutput::Stream { name, .. } if name == "stderr"
+        ));
+        assert!(matches!(
+            &record.outputs[2],
+            RecordedOutput::Data {
+                execution_count: Some(7),
+                ..
+            }
+        ));
+        assert_eq!(
+            records.for_execution_count(7).map(|record| &record.msg_id),
+            Some(&request.header.msg_id)
+        );
+    }
+
+    #[test]
+    fn test_timing_from_kernel_timestamps() {
+        let mut records = ExecutionRecords::default();
+        let request = request("train()");
+        records.start(&request, "train()".to_string());
+        let submitted_at = request.header.date;
+        // The kernel's clock runs an hour ahead of Zed's.
+        let kernel_clock = submitted_at + chrono::Duration::hours(1);
+
+        let at = |content: JupyterMessageContent, kernel_offset_ms: i64| {
+            let mut message = child(content, &request);
+            message.header.date = kernel_clock + chrono::Duration::milliseconds(kernel_offset_ms);
+            message
+        };
+        let local = |offset_ms: i64| submitted_at + chrono::Duration::milliseconds(offset_ms);
+
+        records.handle_message(
+            &at(status(ExecutionState::Busy).into(), 37_900),
+            local(37_950),
+        );
+        assert_eq!(records.get(&request.header.msg_id).unwrap().timing(), None);
+        records.handle_message(&at(reply("ok", 1).into(), 40_200), local(40_300));
+        records.handle_message(
+            &at(status(ExecutionState::Idle).into(), 40_210),
+            local(40_320),
+        );
+
+        let record = records.get(&request.header.msg_id).unwrap();
+        let timing = record.timing().unwrap();
+        assert!(!timing.approximate);
+        assert_eq!(timing.running, Duration::from_millis(2_300));
+        assert_eq!(timing.queued, Duration::from_millis(38_000));
+        assert_eq!(timing.summary(), "ran in 2.3s (queued 38.0s)");
+        assert_eq!(
+            record.replied_at,
+            Some(kernel_clock + chrono::Duration::milliseconds(40_200))
+        );
+    }
+
+    #[test]
+    fn test_timing_without_kernel_timestamps() {
+        let mut records = ExecutionRecords::default();
+        let request = request("x");
+        records.start(&request, "x".to_string());
+        let submitted_at = request.header.date;
+
+        let undated = |content: JupyterMessageContent| {
+            let mut message = child(content, &request);
+            message.header.date = DateTime::<Utc>::default();
+            message
+        };
+        let local = |offset_ms: i64| submitted_at + chrono::Duration::milliseconds(offset_ms);
+
+        records.handle_message(&undated(status(ExecutionState::Busy).into()), local(20));
+        records.handle_message(&undated(reply("ok", 1).into()), local(1_520));
+
+        let timing = records
+            .get(&request.header.msg_id)
+            .unwrap()
+            .timing()
+            .unwrap();
+        assert!(timing.approximate);
+        assert_eq!(timing.running, Duration::from_millis(1_500));
+        assert_eq!(timing.queued, Duration::from_millis(20));
+        assert_eq!(timing.summary(), "ran in ~1.5s");
+    }
+
+    #[test]
+    fn test_failed_execution() {
+        let mut records = ExecutionRecords::default();
+        let request = request("1 / 0");
+        records.start(&request, "1 / 0".to_string());
+
+        for message in [
+            child(status(ExecutionState::Busy), &request),
+            child(
+                ErrorOutput {
+                    ename: "ZeroDivisionError".to_string(),
+                    evalue: "division by zero".to_string(),
+                    traceback: vec!["Traceback".to_string(), "ZeroDivisionError".to_string()],
+                },
+                &request,
+            ),
+            child(reply("error", 3), &request),
+            child(status(ExecutionState::Idle), &request),
+        ] {
+            records.handle_message(&message, Utc::now());
+        }
+
+        let record = records.get(&request.header.msg_id).unwrap();
+        assert!(record.is_complete());
+        assert_eq!(record.status, ExecutionRecordStatus::Error);
+        assert_eq!(record.execution_count, Some(3));
+        let [
+            RecordedOutput::Error {
+                ename,
+                evalue,
+                traceback,
+            },
+        ] = record.outputs.as_slice()
+        else {
+            panic!("expected a single error output");
+        };
+        assert_eq!(ename, "ZeroDivisionError");
+        assert_eq!(evalue, "division by zero");
+        assert_eq!(traceback.len(), 2);
+    }
+
+    #[test]
+    fn test_aborted_execution() {
+        let mut records = ExecutionRecords::default();
+        let aborted_by_kernel = request("a");
+        let cancelled = request("b");
+        let finished = request("c");
+        records.start(&aborted_by_kernel, "a".to_string());
+        records.start(&cancelled, "b".to_string());
+        records.start(&finished, "c".to_string());
+
+        records.handle_message(&child(reply("aborted", 4), &aborted_by_kernel), Utc::now());
+        records.handle_message(&child(reply("ok", 5), &finished), Utc::now());
+        records.abort(&cancelled.header.msg_id);
+        // Aborting after the kernel replied keeps the reply's status.
+        records.abort(&finished.header.msg_id);
+
+        assert_eq!(
+            record_status(&records, &aborted_by_kernel),
+            ExecutionRecordStatus::Aborted
+        );
+        assert_eq!(
+            record_status(&records, &cancelled),
+            ExecutionRecordStatus::Aborted
+        );
+        assert_eq!(
+            record_status(&records, &finished),
+            ExecutionRecordStatus::Ok
+        );
+        assert!(records.get(&cancelled.header.msg_id).unwrap().is_complete());
+        assert!(
+            records
+                .get(&cancelled.header.msg_id)
+                .unwrap()
+                .started_at
+                .is_none()
+        );
+
+        let pending = request("d");
+        records.start(&pending, "d".to_string());
+        records.abort_pending();
+        assert_eq!(
+            record_status(&records, &pending),
+            ExecutionRecordStatus::Aborted
+        );
+        assert_eq!(
+            record_status(&records, &finished),
+            ExecutionRecordStatus::Ok
+        );
+    }
+
+    #[test]
+    fn test_clear_output() {
+        let mut records = ExecutionRecords::default();
+        let request = request("animate()");
+        records.start(&request, "animate()".to_string());
+
+        records.handle_message(
+            &child(stream(Stdio::Stdout, "frame 1\n"), &request),
+            Utc::now(),
+        );
+        records.handle_message(&child(ClearOutput { wait: false }, &request), Utc::now());
+        assert!(
+            records
+                .get(&request.header.msg_id)
+                .unwrap()
+                .outputs
+                .is_empty()
+        );
+
+        // A waiting clear keeps the outputs until the next one replaces them.
+        records.handle_message(
+            &child(stream(Stdio::Stdout, "frame 2\n"), &request),
+            Utc::now(),
+        );
+        records.handle_message(&child(ClearOutput { wait: true }, &request), Utc::now());
+        let outputs = &records.get(&request.header.msg_id).unwrap().outputs;
+        assert_eq!(
+            outputs
+                .iter()
+                .map(RecordedOutput::plain_text)
+                .collect::<Vec<_>>(),
+            [Some("frame 2\n")]
+        );
+
+        records.handle_message(&child(display("frame 3", None), &request), Utc::now());
+        let outputs = &records.get(&request.header.msg_id).unwrap().outputs;
+        assert_eq!(
+            outputs
+                .iter()
+                .map(RecordedOutput::plain_text)
+                .collect::<Vec<_>>(),
+            [Some("frame 3")]
+        );
+
+        // A waiting clear that nothing follows leaves the outputs in place.
+        records.handle_message(&child(ClearOutput { wait: true }, &request), Utc::now());
+        records.handle_message(&child(status(ExecutionState::Idle), &request), Utc::now());
+        records.handle_message(
+            &child(stream(Stdio::Stdout, "late\n"), &request),
+            Utc::now(),
+        );
+        let outputs = &records.get(&request.header.msg_id).unwrap().outputs;
+        assert_eq!(
+            outputs
+                .iter()
+                .map(RecordedOutput::plain_text)
+                .collect::<Vec<_>>(),
+            [Some("frame 3"), Some("late\n")]
+        );
+    }
+
+    #[test]
+    fn test_display_updates_across_executions() {
+        let mut records = ExecutionRecords::default();
+        let first = request("handle = display('a', display_id=True)");
+        let second = request("handle.update('b')");
+        records.start(&first, request_code(&first));
+        records.start(&second, request_code(&second));
+
+        records.handle_message(&child(display("a", Some("progress")), &first), Utc::now());
+        records.handle_message(&child(display("other", Some("other")), &first), Utc::now());
+        let update: UpdateDisplayData = serde_json::from_value(json!({
+            "data": { "text/plain": "b" },
+            "metadata": {},
+            "transient": { "display_id": "progress" },
+        }))
+        .unwrap();
+        records.handle_message(&child(update, &second), Utc::now());
+
+        let outputs = &records.get(&first.header.msg_id).unwrap().outputs;
+        assert_eq!(
+            outputs
+                .iter()
+                .map(RecordedOutput::plain_text)
+                .collect::<Vec<_>>(),
+            [Some("b"), Some("other")]
+        );
+        assert!(
+            records
+                .get(&second.header.msg_id)
+                .unwrap()
+                .outputs
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_stream_truncation() {
+        let mut records = ExecutionRecords::default();
+        let request = request("print_forever()");
+        records.start(&request, "print_forever()".to_string());
+
+        let chunk = "é".repeat(MAX_STREAM_OUTPUT_BYTES / 4);
+        for _ in 0..3 {
+            records.handle_message(&child(stream(Stdio::Stdout, &chunk), &request), Utc::now());
+        }
+        let record = records.get(&request.header.msg_id).unwrap();
+        assert!(record.truncated);
+        let [RecordedOutput::Stream { text, .. }] = record.outputs.as_slice() else {
+            panic!("expected a single stream output");
+        };
+        assert_eq!(text.len(), MAX_STREAM_OUTPUT_BYTES);
+
+        // Clearing makes room for more text, but the record stays truncated.
+        records.handle_message(&child(ClearOutput { wait: false }, &request), Utc::now());
+        records.handle_message(
+            &child(stream(Stdio::Stdout, "done\n"), &request),
+            Utc::now(),
+        );
+        let record = records.get(&request.header.msg_id).unwrap();
+        assert!(record.truncated);
+        assert_eq!(
+            record
+                .outputs
+                .iter()
+                .map(RecordedOutput::plain_text)
+                .collect::<Vec<_>>(),
+            [Some("done\n")]
+        );
+    }
+
+    #[test]
+    fn test_retention() {
+        let mut records = ExecutionRecords::default();
+        let requests: Vec<_> = (0..MAX_EXECUTION_RECORDS + 5)
+            .map(|ix| request(&format!("print({ix})")))
+            .collect();
+        for (ix, request) in requests.iter().enumerate() {
+            records.start(request, format!("print({ix})"));
+            records.handle_message(&child(reply("ok", ix + 1), request), Utc::now());
+        }
+
+        assert_eq!(records.iter().count(), MAX_EXECUTION_RECORDS);
+        assert!(records.get(&requests[4].header.msg_id).is_none());
+        assert!(records.for_execution_count(5).is_none());
+        assert_eq!(
+            records.iter().next().map(|record| record.code.as_str()),
+            Some("print(5)")
+        );
+        assert_eq!(
+            records
+                .for_execution_count(MAX_EXECUTION_RECORDS + 5)
+                .map(|record| &record.msg_id),
+            requests.last().map(|request| &request.header.msg_id)
+        );
+
+        // Messages for dropped executions are ignored.
+        records.handle_message(
+            &child(stream(Stdio::Stdout, "late\n"), &requests[0]),
+            Utc::now(),
+        );
+        assert_eq!(records.iter().count(), MAX_EXECUTION_RECORDS);
+    }
+
+    #[test]
+    fn test_retention_by_size() {
+        let mut records = ExecutionRecords::default();
+        let text = "x".repeat(MAX_STREAM_OUTPUT_BYTES);
+        let requests: Vec<_> = (0..30).map(|ix| request(&format!("dump({ix})"))).collect();
+        for (ix, request) in requests.iter().enumerate() {
+            records.start(request, format!("dump({ix})"));
+            records.handle_message(&child(stream(Stdio::Stdout, &text), request), Utc::now());
+            records.handle_message(&child(reply("ok", ix + 1), request), Utc::now());
+        }
+
+        // The oldest records are dropped to make room, but the newest one is
+        // kept whatever its size.
+        let kept = records.iter().count();
+        assert!(kept < requests.len(), "{kept} records kept");
+        assert!(
+            in_memory_bytes_total(&records) <= MAX_EXECUTION_RECORD_BYTES + text.len(),
+            "{} bytes of outputs in memory",
+            in_memory_bytes_total(&records)
+        );
+        assert!(records.get(&requests[0].header.msg_id).is_none());
+        assert_eq!(
+            records.iter().last().map(|record| &record.msg_id),
+            requests.last().map(|request| &request.header.msg_id)
+        );
+    }
+
+    fn in_memory_bytes_total(records: &ExecutionRecords) -> usize {
+        records
+            .iter()
+            .flat_map(|record| &record.outputs)
+            .map(|output| match output {
+                RecordedOutput::Stream { text, .. } => text.len(),
+                RecordedOutput::Spilled(_) => 0,
+                output => serde_json::to_vec(output).unwrap().len(),
+            })
+            .sum()
+    }
+
+    fn image_display(mime_type: &str, data: &str) -> DisplayData {
+        serde_json::from_value(json!({
+            "data": { mime_type: data, "text/plain": "<Figure>" },
+            "metadata": {},
+        }))
+        .unwrap()
+    }
+
+    #[gpui::test]
+    async fn test_output_file_names(cx: &mut gpui::TestAppContext) {
+        let fs = project::FakeFs::new(cx.executor());
+        let mut records = ExecutionRecords::default();
+        let plot = request("plot()");
+        records.start(&plot, "plot()".to_string());
+        for message in [
+            child(image_display("image/png", "iVBORw0KGgo="), &plot),
+            child(stream(Stdio::Stdout, "done\n"), &plot),
+            child(image_display("image/svg+xml", "<svg></svg>"), &plot),
+            child(reply("ok", 7), &plot),
+        ] {
+            records.handle_message(&message, Utc::now());
+        }
+
+        let record = records.get(&plot.header.msg_id).unwrap();
+        let mut names = Vec::new();
+        for index in 0..3 {
+            let file = record.output_file(index, fs.as_ref()).await.unwrap();
+            names.push(file.map(|file| file.suggested_name));
+        }
+        assert_eq!(
+            names,
+            [
+                Some("output-7-1.png".to_string()),
+                None,
+                Some("output-7-2.svg".to_string())
+            ]
+        );
+        assert_eq!(
+            record
+                .output_file(2, fs.as_ref())
+                .await
+                .unwrap()
+                .map(|file| file.bytes),
+            Some(b"<svg></svg>".to_vec())
+        );
+
+        let show = request("show()");
+        records.start(&show, "show()".to_string());
+        records.handle_message(
+            &child(image_display("image/jpeg", "/9j/4AAQ"), &show),
+            Utc::now(),
+        );
+        let record = records.get(&show.header.msg_id).unwrap();
+        assert_eq!(
+            record
+                .output_file(0, fs.as_ref())
+                .await
+                .unwrap()
+                .map(|file| file.suggested_name),
+            Some("output.jpg".to_string())
+        );
+    }
+
+    #[gpui::test]
+    async fn test_saving_image_output_writes_original_bytes(cx: &mut gpui::TestAppContext) {
+        use base64::Engine as _;
+
+        let original: Vec<u8> = b"\x89PNG\r\n\x1a\n"
+            .iter()
+            .copied()
+            .chain((0..=255).rev())
+            .collect();
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&original);
+        // Kernels wrap long base64 data across lines.
+        let (first, rest) = encoded.split_at(40);
+        let wrapped = format!("{first}\n{rest}\n");
+
+        let mut records = ExecutionRecords::default();
+        let request = request("plot()");
+        records.start(&request, "plot()".to_string());
+        records.handle_message(
+            &child(image_display("image/png", &wrapped), &request),
+            Utc::now(),
+        );
+        let record = records.get(&request.header.msg_id).unwrap();
+        let image = record.outputs[0].image().unwrap().unwrap();
+        assert_eq!(image.format, ImageFormat::Png);
+        assert_eq!(record.outputs[0].plain_text(), Some("<Figure>"));
+
+        let fs = project::FakeFs::new(cx.executor());
+        fs.insert_tree("/outputs", json!({})).await;
+        let file = record.output_file(0, fs.as_ref()).await.unwrap().unwrap();
+        let path = Path::new("/outputs").join(&file.suggested_name);
+        file.save(fs.as_ref(), &path).await.unwrap();
+        assert_eq!(fs.load_bytes(&path).await.unwrap(), original);
+    }
+
+    #[gpui::test]
+    async fn test_copying_truncated_stream_includes_notice(cx: &mut gpui::TestAppContext) {
+        let fs = project::FakeFs::new(cx.executor());
+        let mut records = ExecutionRecords::default();
+        let request = request("print_forever()");
+        records.start(&request, "print_forever()".to_string());
+        records.handle_message(
+            &child(image_display("text/html", "<b>start</b>"), &request),
+            Utc::now(),
+        );
+        let chunk = "x".repeat(MAX_STREAM_OUTPUT_BYTES / 2);
+        for _ in 0..3 {
+            records.handle_message(&child(stream(Stdio::Stdout, &chunk), &request), Utc::now());
+        }
+
+        let record = records.get(&request.header.msg_id).unwrap();
+        let copied = record
+            .copy_as_plain_text(1, fs.as_ref())
+            .await
+            .unwrap()
+            .and_then(|item| item.text());
+        let copied = copied.unwrap();
+        assert!(copied.starts_with(&chunk));
+        assert!(copied.ends_with(&truncation_notice()));
+
+        // Only the stream that was cut short gets the notice.
+        assert_eq!(
+            record
+                .copy_as_plain_text(0, fs.as_ref())
+                .await
+                .unwrap()
+                .and_then(|item| item.text()),
+            Some("<Figure>".to_string())
+        );
+        assert_eq!(
+            record
+                .copy_as_html(0, fs.as_ref())
+                .await
+                .unwrap()
+                .and_then(|item| item.text()),
+            Some("<b>start</b>".to_string())
+        );
+        assert!(record.copy_as_html(1, fs.as_ref()).await.unwrap().is_none());
+    }
+
+    /// The size of the outputs a record keeps in memory, as they'd be
+    /// spilled.
+    fn in_memory_bytes(record: &ExecutionRecord) -> usize {
+        record
+            .outputs
+            .iter()
+            .filter(|output| !matches!(output, RecordedOutput::Spilled(_)))
+            .map(|output| serde_json::to_vec(output).unwrap().len())
+            .sum()
+    }
+
+    /// Spills the outputs of all but the `kept_in_memory` most recent
+    /// executions, and waits for them to be written.
+    async fn spill(
+        records: &mut ExecutionRecords,
+        kept_in_memory: usize,
+        fs: &Arc<dyn Fs>,
+        cx: &mut gpui::TestAppContext,
+    ) -> Vec<String> {
+        let Some(spill) = cx.update(|cx| records.spill_older_outputs(kept_in_memory, fs, cx))
+        else {
+            return Vec::new();
+        };
+        records.finish_spill(spill.await)
+    }
+
+    async fn file_count(fs: &dyn Fs, path: &Path) -> usize {
+        use futures::StreamExt as _;
+        match fs.read_dir(path).await {
+            Ok(entries) => entries.count().await,
+            Err(_) => 0,
+        }
+    }
+
+    #[gpui::test]
+    async fn test_spilling_older_outputs(cx: &mut gpui::TestAppContext) {
+        use base64::Engine as _;
+
+        const KEPT_IN_MEMORY: usize = 5;
+        const EXECUTIONS: usize = 30;
+        let plot_bytes = |ix: usize| vec![ix as u8; 48 * 1024];
+        let plot = |ix: usize| -> DisplayData {
+            serde_json::from_value(json!({
+                "data": {
+                    "image/png": base64::engine::general_purpose::STANDARD.encode(plot_bytes(ix)),
+                    "text/plain": "<Figure>",
+                },
+                "metadata": {},
+                "transient": { "display_id": format!("plot-{ix}") },
+            }))
+            .unwrap()
+        };
+
+        let fs: Arc<dyn Fs> = project::FakeFs::new(cx.executor());
+        let mut records = ExecutionRecords::default();
+        records.spill_directory = Some(OutputSpillDirectory::new(
+            Path::new(util::path!("/tmp/zed-repl-outputs")),
+            fs.clone(),
+            cx.executor(),
+        ));
+        let spill_path = records
+            .spill_directory
+            .as_ref()
+            .unwrap()
+            .path()
+            .to_path_buf();
+
+        let requests: Vec<_> = (0..EXECUTIONS)
+            .map(|ix| request(&format!("plot({ix})")))
+            .collect();
+        for (ix, request) in requests.iter().enumerate() {
+            records.start(request, format!("plot({ix})"));
+            spill(&mut records, KEPT_IN_MEMORY, &fs, cx).await;
+            for message in [
+                child(status(ExecutionState::Busy), request),
+                child(stream(Stdio::Stdout, &format!("step {ix}\n")), request),
+                child(plot(ix), request),
+                child(reply("ok", ix + 1), request),
+                child(status(ExecutionState::Idle), request),
+            ] {
+                records.handle_message(&message, Utc::now());
+            }
+        }
+
+        // Only the most recent executions keep their plots in memory.
+        let plot_size = in_memory_bytes(records.iter().last().unwrap());
+        let total_size: usize = records.iter().map(in_memory_bytes).sum();
+        assert!(plot_size > SPILL_THRESHOLD_BYTES);
+        assert!(
+            total_size < (KEPT_IN_MEMORY + 1) * plot_size,
+            "{total_size} bytes of outputs in memory"
+        );
+        let spilled_files = file_count(fs.as_ref(), &spill_path).await;
+        assert_eq!(spilled_files, EXECUTIONS - KEPT_IN_MEMORY);
+
+        // Spilling only takes the heavy outputs, and nothing else about the
+        // execution.
+        let oldest = records.get(&requests[0].header.msg_id).unwrap();
+        assert_eq!(oldest.code, "plot(0)");
+        assert_eq!(oldest.execution_count, Some(1));
+        assert_eq!(oldest.status, ExecutionRecordStatus::Ok);
+        assert_eq!(oldest.outputs[0].plain_text(), Some("step 0\n"));
+        assert!(matches!(oldest.outputs[1], RecordedOutput::Spilled(_)));
+        assert_eq!(oldest.outputs[1].plain_text(), None);
+
+        // Spilled outputs are read back when they're needed.
+        let outputs = oldest.load_outputs(fs.as_ref()).await.unwrap();
+        assert_eq!(outputs[1].plain_text(), Some("<Figure>"));
+        assert_eq!(
+            oldest
+                .output_file(1, fs.as_ref())
+                .await
+                .unwrap()
+                .map(|file| file.bytes),
+            Some(plot_bytes(0))
+        );
+        assert_eq!(
+            oldest
+                .copy_as_plain_text(1, fs.as_ref())
+                .await
+                .unwrap()
+                .and_then(|item| item.text()),
+            Some("<Figure>".to_string())
+        );
+        let notebook = crate::notebook_export::notebook_from_records(
+            records.iter(),
+            &[],
+            json!({}),
+            None,
+            fs.as_ref(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            notebook["cells"][0]["outputs"][1]["data"]["image/png"],
+            base64::engine::general_purpose::STANDARD.encode(plot_bytes(0))
+        );
+
+        // Updating a spilled display brings it back into memory.
+        let update = |display_id: &str| -> UpdateDisplayData {
+            serde_json::from_value(json!({
+                "data": { "text/plain": "<Updated figure>" },
+                "metadata": {},
+                "transient": { "display_id": display_id },
+            }))
+            .unwrap()
+        };
+        records.handle_message(
+            &child(update("plot-0"), &requests[EXECUTIONS - 1]),
+            Utc::now(),
+        );
+        let oldest = records.get(&requests[0].header.msg_id).unwrap();
+        assert_eq!(oldest.outputs[1].plain_text(), Some("<Updated figure>"));
+        cx.run_until_parked();
+        assert_eq!(
+            file_count(fs.as_ref(), &spill_path).await,
+            spilled_files - 1
+        );
+
+        // Outputs that change while they're being written stay in memory,
+        // and their files are removed.
+        let last = request("plot()");
+        records.start(&last, "plot()".to_string());
+        let pending = cx
+            .update(|cx| records.spill_older_outputs(KEPT_IN_MEMORY, &fs, cx))
+            .unwrap();
+        let updated_id = format!("plot-{}", EXECUTIONS - KEPT_IN_MEMORY);
+        records.handle_message(&child(update(&updated_id), &last), Utc::now());
+        assert!(records.finish_spill(pending.await).is_empty());
+        let updated = records
+            .get(&requests[EXECUTIONS - KEPT_IN_MEMORY].header.msg_id)
+            .unwrap();
+        assert_eq!(updated.outputs[1].plain_text(), Some("<Updated figure>"));
+        cx.run_until_parked();
+        assert_eq!(
+            file_count(fs.as_ref(), &spill_path).await,
+            spilled_files - 1
+        );
+
+        // The spilled outputs go away with the session's records.
+        drop(records);
+        cx.run_until_parked();
+        assert!(!fs.is_dir(&spill_path).await);
+    }
+
+    #[test]
+    fn test_execution_count_after_restart() {
+        let mut records = ExecutionRecords::default();
+        let before_restart = request("a");
+        let after_restart = request("b");
+        records.start(&before_restart, "a".to_string());
+        records.handle_message(&child(reply("ok", 1), &before_restart), Utc::now());
+        records.record_restart();
+        // The count belongs to the kernel that restarted.
+        assert!(records.for_execution_count(1).is_none());
+
+        records.start(&after_restart, "b".to_string());
+        records.handle_message(&child(reply("ok", 1), &after_restart), Utc::now());
+
+        assert_eq!(
+            records
+                .for_execution_count(1)
+                .map(|record| record.code.as_str()),
+            Some("b")
+        );
+        assert_eq!(
+            records
+                .get(&before_restart.header.msg_id)
+                .map(|record| record.code.as_str()),
+            Some("a")
+        );
+    }
+}
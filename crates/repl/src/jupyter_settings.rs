@@ -2,11 +2,12 @@ use collections::HashMap;
 
 use editor::EditorSettings;
 use gpui::App;
-use settings::{RegisterSetting, Settings};
+use settings::{KernelSelectionRule, RegisterSetting, Settings};
 
 #[derive(Debug, Default, RegisterSetting)]
 pub struct JupyterSettings {
     pub kernel_selections: HashMap<String, String>,
+    pub kernel_selection_rules: HashMap<String, Vec<KernelSelectionRule>>,
 }
 
 impl JupyterSettings {
@@ -23,6 +24,7 @@ impl Settings for JupyterSettings {
         let jupyter = content.editor.jupyter.clone().unwrap();
         Self {
             kernel_selections: jupyter.kernel_selections.unwrap_or_default(),
+            kernel_selection_rules: jupyter.kernel_selection_rules.unwrap_or_default(),
         }
     }
 }
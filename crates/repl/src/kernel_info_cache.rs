@@ -0,0 +1,132 @@
+use std::path::PathBuf;
+
+use project::MTime;
+use runtimelib::KernelInfoReply;
+use serde::{Deserialize, Serialize};
+
+/// The key-value store key the cache is persisted under.
+pub(crate) const KERNEL_INFO_CACHE_KEY: &str = "repl_kernel_info_cache";
+
+/// What a kernel last reported about itself in its `kernel_info_reply`, kept
+/// so kernel pickers can show the language version before the kernel is
+/// launched again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedKernelInfo {
+    pub language: String,
+    pub language_version: String,
+    pub implementation: String,
+    pub implementation_version: String,
+    /// The first line of the kernel's banner.
+    pub banner: String,
+    /// The interpreter or kernelspec file whose changes make this info out of
+    /// date, for local kernels.
+    pub(crate) source_path: Option<PathBuf>,
+    /// When `source_path` was last modified as of caching.
+    pub(crate) source_mtime: Option<MTime>,
+    /// Whether `source_path` changed since this info was cached, so it may no
+    /// longer describe the kernel. It's refreshed on the kernel's next launch.
+    #[serde(skip)]
+    pub is_stale: bool,
+}
+
+impl CachedKernelInfo {
+    pub(crate) fn new(
+        reply: &KernelInfoReply,
+        source_path: Option<PathBuf>,
+        source_mtime: Option<MTime>,
+    ) -> Self {
+        Self {
+            language: reply.language_info.name.clone(),
+            language_version: reply.language_info.version.clone(),
+            implementation: reply.implementation.clone(),
+            implementation_version: reply.implementation_version.clone(),
+            banner: reply.banner.lines().next().unwrap_or_default().to_string(),
+            source_path,
+            source_mtime,
+            is_stale: false,
+        }
+    }
+
+    /// A summary such as "Python 3.11.6 (ipython 8.29.0)".
+    pub fn label(&self) -> String {
+        let mut language = self.language.chars();
+        let mut label = match language.next() {
+            Some(first) => first.to_uppercase().chain(language).collect::<String>(),
+            None => String::new(),
+        };
+        if !self.language_version.is_empty() {
+            label.push(' ');
+            label.push_str(&self.language_version);
+        }
+        if !self.implementation.is_empty() {
+            label.push_str(" (");
+            label.push_str(&self.implementation);
+            if !self.implementation_version.is_empty() {
+                label.push(' ');
+                label.push_str(&self.implementation_version);
+            }
+            label.push(')');
+        }
+        label
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn kernel_info_reply() -> KernelInfoReply {
+        serde_json::from_value(json!({
+            "status": "ok",
+            "protocol_version": "5.3",
+            "implementation": "ipython",
+            "implementation_version": "8.29.0",
+            "language_info": {
+                "name": "python",
+                "version": "3.11.6",
+                "mimetype": "text/x-python",
+                "file_extension": ".py",
+            },
+            "banner": "Python 3.11.6 (main, Oct  2 2023)\nType 'copyright' for more information",
+            "help_links": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cached_kernel_info_label() {
+        let info = CachedKernelInfo::new(&kernel_info_reply(), None, None);
+        assert_eq!(info.label(), "Python 3.11.6 (ipython 8.29.0)");
+        assert_eq!(info.banner, "Python 3.11.6 (main, Oct  2 2023)");
+
+        let bare = CachedKernelInfo {
+            language_version: String::new(),
+            implementation: String::new(),
+            ..info
+        };
+        assert_eq!(bare.label(), "Python");
+    }
+
+    #[test]
+    fn test_cached_kernel_info_roundtrip() {
+        let info = CachedKernelInfo {
+            is_stale: true,
+            ..CachedKernelInfo::new(
+                &kernel_info_reply(),
+                Some(PathBuf::from("/usr/bin/python3")),
+                Some(MTime::from_seconds_and_nanos(100, 0)),
+            )
+        };
+        let serialized = serde_json::to_string(&info).unwrap();
+        let deserialized = serde_json::from_str::<CachedKernelInfo>(&serialized).unwrap();
+        // Staleness is worked out again when the cache is loaded.
+        assert_eq!(
+            deserialized,
+            CachedKernelInfo {
+                is_stale: false,
+                ..info
+            }
+        );
+    }
+}
@@ -0,0 +1,294 @@
+//! Connection files for the native kernels Zed launches.
+//!
+//! A connection file holds the ports and signing key of a kernel's sockets.
+//! Ours live in a subdirectory of the Jupyter runtime directory, and carry a
+//! `zed` field recording who wrote them and which process they belong to, so
+//! files left behind by a crash can be swept on a later launch without
+//! touching anyone else's.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context as _, Result};
+use futures::StreamExt as _;
+use gpui::App;
+use jupyter_protocol::connection_info::ConnectionInfo;
+use project::{Fs, RemoveOptions};
+use runtimelib::dirs;
+use serde::{Deserialize, Serialize};
+
+/// How old a connection file must be before the startup sweep considers it,
+/// so a kernel whose process id hasn't been recorded yet is never swept.
+const STALE_CONNECTION_FILE_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// The directory Zed writes its kernels' connection files to.
+pub(crate) fn connection_files_dir() -> PathBuf {
+    dirs::runtime_dir().join("zed")
+}
+
+/// The `zed` field written into every connection file Zed creates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ConnectionFileOwner {
+    /// The Zed process that launched the kernel.
+    pub app_pid: u32,
+    /// The kernel process, once it has been spawned.
+    pub kernel_pid: Option<u32>,
+    /// When the file was written, in seconds since the Unix epoch.
+    pub created_at: u64,
+}
+
+impl ConnectionFileOwner {
+    pub fn new() -> Self {
+        Self {
+            app_pid: std::process::id(),
+            kernel_pid: None,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The process whose exit makes the file stale: the kernel's once it's
+    /// known, otherwise the Zed process that was launching it.
+    fn pid(&self) -> u32 {
+        self.kernel_pid.unwrap_or(self.app_pid)
+    }
+}
+
+#[derive(Deserialize)]
+struct OwnedConnectionFile {
+    zed: ConnectionFileOwner,
+}
+
+/// Writes `connection_info` to `path`, marked as owned by Zed.
+pub(crate) async fn write_connection_file(
+    fs: &dyn Fs,
+    path: &Path,
+    connection_info: &ConnectionInfo,
+    owner: ConnectionFileOwner,
+) -> Result<()> {
+    let mut content = serde_json::to_value(connection_info)?;
+    let object = content
+        .as_object_mut()
+        .context("connection info is not a JSON object")?;
+    object.insert("zed".into(), serde_json::to_value(owner)?);
+    fs.atomic_write(path.to_path_buf(), serde_json::to_string(&content)?)
+        .await
+}
+
+/// Sweeps Zed's connection file directory for files left behind by kernels
+/// that are no longer running.
+pub(crate) fn sweep_stale_connection_files_in_background(fs: Arc<dyn Fs>, cx: &App) {
+    cx.background_spawn(async move {
+        let removed = sweep_stale_connection_files(
+            fs.as_ref(),
+            &connection_files_dir(),
+            SystemTime::now(),
+            is_process_alive,
+        )
+        .await?;
+        if !removed.is_empty() {
+            log::info!("Removed {} stale kernel connection files", removed.len());
+        }
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Removes the connection files in `dir` that Zed wrote, whose process is no
+/// longer running, and that are older than [`STALE_CONNECTION_FILE_AGE`].
+/// Files without Zed's marker are left alone. Failures to remove a file are
+/// logged and don't stop the sweep.
+pub(crate) async fn sweep_stale_connection_files(
+    fs: &dyn Fs,
+    dir: &Path,
+    now: SystemTime,
+    is_alive: impl Fn(u32) -> bool,
+) -> Result<Vec<PathBuf>> {
+    if !fs.is_dir(dir).await {
+        return Ok(Vec::new());
+    }
+    let now = now
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+
+    let mut entries = fs.read_dir(dir).await?;
+    let mut removed = Vec::new();
+    while let Some(entry) = entries.next().await {
+        let path = match entry {
+            Ok(path) => path,
+            Err(error) => {
+                log::warn!("Failed to read kernel connection files in {dir:?}: {error:?}");
+                continue;
+            }
+        };
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+        let Ok(content) = fs.load(&path).await else {
+            continue;
+        };
+        let Ok(OwnedConnectionFile { zed: owner }) = serde_json::from_str(&content) else {
+            continue;
+        };
+        let age = now.saturating_sub(owner.created_at);
+        if age < STALE_CONNECTION_FILE_AGE.as_secs() || is_alive(owner.pid()) {
+            continue;
+        }
+        match fs
+            .remove_file(
+                &path,
+                RemoveOptions {
+                    recursive: false,
+                    ignore_if_not_exists: true,
+                },
+            )
+            .await
+        {
+            Ok(()) => removed.push(path),
+            Err(error) => {
+                log::warn!("Failed to remove stale kernel connection file {path:?}: {error:?}")
+            }
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(unix)]
+pub(crate) fn is_process_alive(pid: u32) -> bool {
+    let Ok(pid) = i32::try_from(pid) else {
+        return false;
+    };
+    // Signal 0 only checks that the process exists. EPERM means it does, but
+    // belongs to another user.
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(windows)]
+pub(crate) fn is_process_alive(pid: u32) -> bool {
+    use windows::Win32::Foundation::{CloseHandle, STILL_ACTIVE};
+    use windows::Win32::System::Threading::{
+        GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    let Ok(handle) = (unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }) else {
+        return false;
+    };
+    let mut exit_code = 0;
+    let is_alive = unsafe { GetExitCodeProcess(handle, &mut exit_code) }.is_ok()
+        && exit_code == STILL_ACTIVE.0 as u32;
+    unsafe { CloseHandle(handle) }.ok();
+    is_alive
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use jupyter_protocol::connection_info::Transport;
+    use project::FakeFs;
+    use serde_json::json;
+    use util::path;
+
+    fn connection_info() -> ConnectionInfo {
+        ConnectionInfo {
+            transport: Transport::TCP,
+            ip: "127.0.0.1".to_string(),
+            stdin_port: 1,
+            control_port: 2,
+            hb_port: 3,
+            shell_port: 4,
+            iopub_port: 5,
+            signature_scheme: "hmac-sha256".to_string(),
+            key: "key".to_string(),
+            kernel_name: Some("zed-python3".to_string()),
+        }
+    }
+
+    #[gpui::test]
+    async fn test_sweep_stale_connection_files(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let dir = PathBuf::from(path!("/runtime/zed"));
+        fs.insert_tree(
+            &dir,
+            json!({
+                "not-ours.json": r#"{"ip": "127.0.0.1", "key": "key"}"#,
+                "notes.txt": "",
+            }),
+        )
+        .await;
+
+        let now = UNIX_EPOCH + Duration::from_secs(100_000);
+        let old = 100_000 - STALE_CONNECTION_FILE_AGE.as_secs() - 1;
+        let write = |name: &str, kernel_pid: Option<u32>, created_at: u64| {
+            let fs = fs.clone();
+            let path = dir.join(name);
+            async move {
+                let owner = ConnectionFileOwner {
+                    app_pid: 1,
+                    kernel_pid,
+                    created_at,
+                };
+                write_connection_file(fs.as_ref(), &path, &connection_info(), owner)
+                    .await
+                    .unwrap();
+            }
+        };
+        write("dead-kernel.json", Some(10), old).await;
+        write("live-kernel.json", Some(20), old).await;
+        write("recent-dead-kernel.json", Some(10), 100_000 - 5).await;
+        write("dead-app.json", None, old).await;
+
+        let written = fs.load(&dir.join("dead-kernel.json")).await.unwrap();
+        let written: serde_json::Value = serde_json::from_str(&written).unwrap();
+        assert_eq!(written["shell_port"], 4);
+        assert_eq!(written["zed"]["kernel_pid"], 10);
+
+        let mut removed = sweep_stale_connection_files(fs.as_ref(), &dir, now, |pid| pid == 20)
+            .await
+            .unwrap();
+        removed.sort();
+        assert_eq!(
+            removed,
+            [dir.join("dead-app.json"), dir.join("dead-kernel.json")]
+        );
+
+        let mut remaining = fs
+            .read_dir(&dir)
+            .await
+            .unwrap()
+            .map(|path| path.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        remaining.sort();
+        assert_eq!(
+            remaining,
+            [
+                dir.join("live-kernel.json"),
+                dir.join("not-ours.json"),
+                dir.join("notes.txt"),
+                dir.join("recent-dead-kernel.json"),
+            ]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_sweep_missing_directory(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let removed = sweep_stale_connection_files(
+            fs.as_ref(),
+            Path::new(path!("/runtime/zed")),
+            SystemTime::now(),
+            |_| false,
+        )
+        .await
+        .unwrap();
+        assert!(removed.is_empty());
+    }
+}
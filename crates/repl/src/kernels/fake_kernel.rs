@@ -0,0 +1,451 @@
+use std::{collections::VecDeque, path::PathBuf, sync::Arc, time::Duration};
+
+use futures::{FutureExt, StreamExt, channel::mpsc};
+use gpui::{App, AsyncWindowContext, Entity, Task, WeakEntity, Window};
+use parking_lot::Mutex;
+use runtimelib::{
+    ErrorOutput, ExecuteReply, ExecutionState, InterruptReply, JupyterMessage,
+    JupyterMessageContent, KernelInfoReply, ShutdownReply, Status,
+};
+use util::ResultExt as _;
+
+use super::{KernelResourceLimits, KernelRuntimeInfo, KernelSession, RunningKernel, route_message};
+
+/// A step a [`FakeKernel`] plays in answer to an execute request.
+#[derive(Debug, Clone)]
+pub enum FakeKernelStep {
+    /// Sends a message to the session as a child of the execute request.
+    Reply(JupyterMessageContent),
+    /// Waits before playing the next step. Requests are still handled while
+    /// waiting, so an interrupt can cut the execution short.
+    Delay(Duration),
+    /// Stops answering and reports the kernel as errored, like a kernel whose
+    /// process died.
+    Die,
+    /// Stops answering and reports the kernel as having died of running into
+    /// its memory limit of this many bytes.
+    ExceedMemoryLimit(u64),
+}
+
+impl FakeKernelStep {
+    pub fn status(execution_state: ExecutionState) -> Self {
+        Self::Reply(JupyterMessageContent::Status(Status { execution_state }))
+    }
+
+    /// An `error` output, as the code raising `ename` broadcasts.
+    pub fn error(ename: &str, evalue: &str) -> Self {
+        Self::Reply(JupyterMessageContent::ErrorOutput(ErrorOutput {
+            ename: ename.to_string(),
+            evalue: evalue.to_string(),
+            traceback: vec![format!("{ename}: {evalue}")],
+        }))
+    }
+
+    /// An `execute_reply` with the given status, such as `"ok"`, or `"error"`
+    /// for an execution cut short by an interrupt.
+    pub fn execute_reply(status: &str) -> Option<Self> {
+        let reply = if status == "ok" {
+            serde_json::json!({ "status": status, "execution_count": 1 })
+        } else {
+            serde_json::json!({
+                "status": status,
+                "execution_count": 1,
+                "ename": "KeyboardInterrupt",
+                "evalue": "",
+                "traceback": [],
+            })
+        };
+        serde_json::from_value::<ExecuteReply>(reply)
+            .log_err()
+            .map(|reply| Self::Reply(JupyterMessageContent::ExecuteReply(reply)))
+    }
+}
+
+/// How a [`FakeKernel`] answers the requests it receives.
+#[derive(Debug, Clone)]
+pub struct FakeKernelBehavior {
+    /// The steps played for every execute request, one request at a time.
+    pub on_execute: Vec<FakeKernelStep>,
+    /// Whether interrupt requests are ignored and never answered, like a kernel
+    /// stuck in native code.
+    pub ignore_interrupts: bool,
+    /// Whether shutdown requests are ignored and never answered, like a kernel
+    /// that hangs on exit. Either way the kernel stops handling requests.
+    pub ignore_shutdowns: bool,
+}
+
+impl FakeKernelBehavior {
+    /// Answers every execute request with `steps` between a busy status, and
+    /// an `execute_reply` followed by an idle status, as a real kernel does.
+    pub fn replying_with(steps: impl IntoIterator<Item = FakeKernelStep>) -> Self {
+        let on_execute = std::iter::once(FakeKernelStep::status(ExecutionState::Busy))
+            .chain(steps)
+            .chain(FakeKernelStep::execute_reply("ok"))
+            .chain(std::iter::once(FakeKernelStep::status(
+                ExecutionState::Idle,
+            )))
+            .collect();
+        Self {
+            on_execute,
+            ignore_interrupts: false,
+            ignore_shutdowns: false,
+        }
+    }
+}
+
+impl Default for FakeKernelBehavior {
+    fn default() -> Self {
+        Self::replying_with([])
+    }
+}
+
+/// The messages a [`FakeKernel`] received on its request and stdin channels,
+/// in the order they arrived.
+#[derive(Debug, Clone, Default)]
+pub struct FakeKernelMessages(Arc<Mutex<Vec<JupyterMessage>>>);
+
+impl FakeKernelMessages {
+    pub fn all(&self) -> Vec<JupyterMessage> {
+        self.0.lock().clone()
+    }
+
+    pub fn take(&self) -> Vec<JupyterMessage> {
+        std::mem::take(&mut *self.0.lock())
+    }
+
+    fn push(&self, message: JupyterMessage) {
+        self.0.lock().push(message);
+    }
+}
+
+/// An in-memory [`RunningKernel`] that answers requests from a script, for
+/// testing sessions without spawning a Jupyter kernel. Its messages are routed
+/// through [`route_message`] like a real kernel's, so status messages
+/// drive its execution state the same way.
+#[derive(Debug)]
+pub struct FakeKernel {
+    request_tx: mpsc::Sender<JupyterMessage>,
+    stdin_tx: mpsc::Sender<JupyterMessage>,
+    working_directory: PathBuf,
+    execution_state: ExecutionState,
+    kernel_info: Option<KernelInfoReply>,
+    received: FakeKernelMessages,
+    owned: Option<bool>,
+    resource_limits: Option<KernelResourceLimits>,
+}
+
+impl FakeKernel {
+    pub fn new<S: KernelSession + 'static>(
+        session: &Entity<S>,
+        behavior: FakeKernelBehavior,
+        working_directory: PathBuf,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Self {
+        let (request_tx, request_rx) = mpsc::channel(100);
+        let (stdin_tx, stdin_rx) = mpsc::channel(100);
+        let received = FakeKernelMessages::default();
+
+        let mut script = FakeKernelScript {
+            session: session.downgrade(),
+            behavior,
+            received: received.clone(),
+            pending: VecDeque::new(),
+            current: None,
+        };
+        window
+            .spawn(cx, async move |cx| {
+                script.run(request_rx, stdin_rx, cx).await;
+            })
+            .detach();
+
+        Self {
+            request_tx,
+            stdin_tx,
+            working_directory,
+            execution_state: ExecutionState::Idle,
+            kernel_info: None,
+            received,
+            owned: None,
+            resource_limits: None,
+        }
+    }
+
+    /// Reports the kernel as started with `resource_limits`, as a kernel
+    /// spawned with them does.
+    pub fn with_resource_limits(mut self, resource_limits: KernelResourceLimits) -> Self {
+        self.resource_limits = Some(resource_limits);
+        self
+    }
+
+    /// A kernel that plays no script and answers nothing. Its requests go to
+    /// the returned receiver, for tests that answer them by hand.
+    pub fn unscripted(working_directory: PathBuf) -> (Self, mpsc::Receiver<JupyterMessage>) {
+        let (request_tx, request_rx) = mpsc::channel(100);
+        let (stdin_tx, _stdin_rx) = mpsc::channel(100);
+        let kernel = Self {
+            request_tx,
+            stdin_tx,
+            working_directory,
+            execution_state: ExecutionState::Idle,
+            kernel_info: None,
+            received: FakeKernelMessages::default(),
+            owned: None,
+            resource_limits: None,
+        };
+        (kernel, request_rx)
+    }
+
+    pub fn received(&self) -> FakeKernelMessages {
+        self.received.clone()
+    }
+}
+
+impl RunningKernel for FakeKernel {
+    fn request_tx(&self) -> mpsc::Sender<JupyterMessage> {
+        self.request_tx.clone()
+    }
+
+    fn stdin_tx(&self) -> mpsc::Sender<JupyterMessage> {
+        self.stdin_tx.clone()
+    }
+
+    fn working_directory(&self) -> &PathBuf {
+        &self.working_directory
+    }
+
+    fn set_working_directory(&mut self, working_directory: PathBuf) {
+        self.working_directory = working_directory;
+    }
+
+    fn runtime_info(&self) -> KernelRuntimeInfo {
+        KernelRuntimeInfo {
+            working_directory: self.working_directory.clone(),
+            resource_limits: self.resource_limits,
+            ..KernelRuntimeInfo::default()
+        }
+    }
+
+    fn execution_state(&self) -> &ExecutionState {
+        &self.execution_state
+    }
+
+    fn set_execution_state(&mut self, state: ExecutionState) {
+        self.execution_state = state;
+    }
+
+    fn kernel_info(&self) -> Option<&KernelInfoReply> {
+        self.kernel_info.as_ref()
+    }
+
+    fn set_kernel_info(&mut self, info: KernelInfoReply) {
+        self.kernel_info = Some(info);
+    }
+
+    fn force_shutdown(&mut self, _window: &mut Window, _cx: &mut App) -> Task<anyhow::Result<()>> {
+        Task::ready(Ok(()))
+    }
+
+    fn kill(&mut self) {
+        self.request_tx.close_channel();
+        self.stdin_tx.close_channel();
+    }
+
+    fn is_owned(&self) -> Option<bool> {
+        self.owned
+    }
+
+    fn set_owned(&mut self, owned: bool) {
+        self.owned = Some(owned);
+    }
+}
+
+struct FakeKernelScript<S> {
+    session: WeakEntity<S>,
+    behavior: FakeKernelBehavior,
+    received: FakeKernelMessages,
+    /// The steps left to play, with the execute request each answers.
+    pending: VecDeque<(JupyterMessage, FakeKernelStep)>,
+    /// The execute request whose steps are being played.
+    current: Option<JupyterMessage>,
+}
+
+impl<S: KernelSession + 'static> FakeKernelScript<S> {
+    async fn run(
+        &mut self,
+        mut request_rx: mpsc::Receiver<JupyterMessage>,
+        mut stdin_rx: mpsc::Receiver<JupyterMessage>,
+        cx: &mut AsyncWindowContext,
+    ) {
+        loop {
+            let mut delay = match self.pending.pop_front() {
+                Some((parent, step)) => {
+                    self.current = Some(parent.clone());
+                    match step {
+                        FakeKernelStep::Reply(content) => {
+                            self.send(content, &parent, cx);
+                            continue;
+                        }
+                        FakeKernelStep::Die => {
+                            self.session
+                                .update(cx, |session, cx| {
+                                    session.kernel_errored("Kernel died".to_string(), cx);
+                                })
+                                .ok();
+                            return;
+                        }
+                        FakeKernelStep::ExceedMemoryLimit(limit) => {
+                            self.session
+                                .update(cx, |session, cx| {
+                                    session.kernel_exceeded_memory_limit(
+                                        limit,
+                                        "Kernel ran out of memory".to_string(),
+                                        cx,
+                                    );
+                                })
+                                .ok();
+                            return;
+                        }
+                        FakeKernelStep::Delay(duration) => cx
+                            .background_executor()
+                            .timer(duration)
+                            .boxed_local()
+                            .fuse(),
+                    }
+                }
+                None => {
+                    self.current = None;
+                    futures::future::pending().boxed_local().fuse()
+                }
+            };
+
+            let waiting_on = self.current_id();
+            loop {
+                futures::select_biased! {
+                    _ = delay => break,
+                    request = request_rx.next() => match request {
+                        Some(request) => {
+                            if !self.handle_request(request, cx) {
+                                return;
+                            }
+                            // Stop waiting when an interrupt cut the current
+                            // execution short, or when an idle kernel got work.
+                            let interrupted = self.current_id() != waiting_on;
+                            let has_work = waiting_on.is_none() && !self.pending.is_empty();
+                            if interrupted || has_work {
+                                break;
+                            }
+                        }
+                        None => return,
+                    },
+                    message = stdin_rx.next() => match message {
+                        Some(message) => self.received.push(message),
+                        None => return,
+                    },
+                }
+            }
+        }
+    }
+
+    fn current_id(&self) -> Option<String> {
+        self.current
+            .as_ref()
+            .map(|current| current.header.msg_id.clone())
+    }
+
+    /// Returns whether the kernel keeps running.
+    fn handle_request(&mut self, request: JupyterMessage, cx: &mut AsyncWindowContext) -> bool {
+        self.received.push(request.clone());
+        match &request.content {
+            JupyterMessageContent::ExecuteRequest(execute) => {
+                // Zed's own silent executions, such as listing variables, are
+                // answered right away so they don't play the script.
+                let steps = if execute.silent {
+                    FakeKernelBehavior::default().on_execute
+                } else {
+                    self.behavior.on_execute.clone()
+                };
+                self.pending
+                    .extend(steps.into_iter().map(|step| (request.clone(), step)));
+            }
+            JupyterMessageContent::InterruptRequest(_) if !self.behavior.ignore_interrupts => {
+                if let Some(current) = self.current.take() {
+                    self.pending
+                        .retain(|(parent, _)| parent.header.msg_id != current.header.msg_id);
+                    // Like IPython, the interrupted code raises an error.
+                    if let FakeKernelStep::Reply(error) =
+                        FakeKernelStep::error("KeyboardInterrupt", "")
+                    {
+                        self.send(error, &current, cx);
+                    }
+                    if let Some(FakeKernelStep::Reply(reply)) =
+                        FakeKernelStep::execute_reply("error")
+                    {
+                        self.send(reply, &current, cx);
+                    }
+                    self.send(
+                        JupyterMessageContent::Status(Status {
+                            execution_state: ExecutionState::Idle,
+                        }),
+                        &current,
+                        cx,
+                    );
+                }
+                if let Some(reply) =
+                    serde_json::from_value::<InterruptReply>(serde_json::json!({ "status": "ok" }))
+                        .log_err()
+                {
+                    self.send(JupyterMessageContent::InterruptReply(reply), &request, cx);
+                }
+            }
+            JupyterMessageContent::KernelInfoRequest(_) => {
+                if let Some(session) = self.session.upgrade()
+                    && let Some(reply) = fake_kernel_info().log_err()
+                {
+                    route_message(&session, &reply.as_child_of(&request), cx);
+                }
+            }
+            JupyterMessageContent::ShutdownRequest(shutdown) => {
+                if !self.behavior.ignore_shutdowns
+                    && let Some(reply) = serde_json::from_value::<ShutdownReply>(
+                        serde_json::json!({ "status": "ok", "restart": shutdown.restart }),
+                    )
+                    .log_err()
+                {
+                    self.send(JupyterMessageContent::ShutdownReply(reply), &request, cx);
+                }
+                return false;
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn send(
+        &self,
+        content: JupyterMessageContent,
+        parent: &JupyterMessage,
+        cx: &mut AsyncWindowContext,
+    ) {
+        let Some(session) = self.session.upgrade() else {
+            return;
+        };
+        let message = JupyterMessage::new(content, Some(parent));
+        route_message(&session, &message, cx);
+    }
+}
+
+/// The `kernel_info_reply` of a Python kernel, which a [`FakeKernel`] sends
+/// when asked for its info.
+fn fake_kernel_info() -> serde_json::Result<KernelInfoReply> {
+    serde_json::from_value(serde_json::json!({
+        "status": "ok",
+        "protocol_version": "5.3",
+        "implementation": "ipython",
+        "implementation_version": "8.29.0",
+        "language_info": { "name": "python", "version": "3.11.6" },
+        "banner": "",
+        "help_links": [],
+        "debugger": false,
+    }))
+}
@@ -1,16 +1,31 @@
+mod connection_file;
 mod native_kernel;
-use std::{fmt::Debug, future::Future, path::PathBuf};
+pub(crate) use connection_file::*;
+use std::{
+    fmt::Debug,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use futures::{channel::mpsc, future::Shared};
 use gpui::{App, Entity, Task, Window};
-use language::LanguageName;
+use language::{LanguageName, Toolchain, ToolchainScope};
 use log;
 pub use native_kernel::*;
+use parking_lot::Mutex;
 
+mod protocol_version;
 mod remote_kernels;
+mod resource_limits;
+mod resource_usage;
 use project::{Project, ProjectPath, Toolchains, WorktreeId};
+pub use protocol_version::*;
 use remote::RemoteConnectionOptions;
 pub use remote_kernels::*;
+pub use resource_limits::*;
+pub use resource_usage::*;
 
 mod ssh_kernel;
 pub use ssh_kernel::*;
@@ -18,7 +33,12 @@ pub use ssh_kernel::*;
 mod wsl_kernel;
 pub use wsl_kernel::*;
 
-use std::collections::HashMap;
+#[cfg(any(test, feature = "test-support"))]
+mod fake_kernel;
+#[cfg(any(test, feature = "test-support"))]
+pub use fake_kernel::*;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use anyhow::Result;
 use futures::{FutureExt, StreamExt};
@@ -28,8 +48,11 @@ use runtimelib::{
     ClientControlConnection, ClientIoPubConnection, ClientShellConnection, ClientStdinConnection,
     ExecutionState, JupyterMessage, KernelInfoReply,
 };
+use settings::{KernelSelectionRule, Settings as _};
 use ui::{Icon, IconName, SharedString};
-use util::rel_path::RelPath;
+use util::{paths::PathExt as _, rel_path::RelPath};
+
+use crate::{outputs::HtmlTrust, repl_settings::ReplSettings};
 
 pub(crate) const VENV_DIR_NAMES: &[&str] = &[".venv", "venv", ".env", "env"];
 
@@ -121,13 +144,7 @@ pub fn start_kernel_tasks<S: KernelSession + 'static>(
                     msg = stdin.read().fuse() => ("stdin", msg),
                 };
                 match result {
-                    Ok(message) => {
-                        session
-                            .update_in(cx, |session, window, cx| {
-                                session.route(&message, window, cx);
-                            })
-                            .ok();
-                    }
+                    Ok(message) => route_message(&session, &message, cx),
                     Err(
                         ref err @ (runtimelib::RuntimeError::ParseError { .. }
                         | runtimelib::RuntimeError::SerdeError(_)),
@@ -203,8 +220,48 @@ pub fn start_kernel_tasks<S: KernelSession + 'static>(
 }
 
 pub trait KernelSession: Sized {
-    fn route(&mut self, message: &JupyterMessage, window: &mut Window, cx: &mut Context<Self>);
+    /// Updates the session for a message from its kernel: its execution
+    /// state, replies, and finished executions. This needs no window, so
+    /// sessions keep up with their kernel while nothing shows them.
+    fn handle_message(&mut self, message: &JupyterMessage, cx: &mut Context<Self>);
+
+    /// Shows the outputs of the messages handled since the last call.
+    fn present_messages(&mut self, window: &mut Window, cx: &mut Context<Self>);
+
+    /// Handles `message` and shows it right away.
+    fn route(&mut self, message: &JupyterMessage, window: &mut Window, cx: &mut Context<Self>) {
+        self.handle_message(message, cx);
+        self.present_messages(window, cx);
+    }
+
     fn kernel_errored(&mut self, error_message: String, cx: &mut Context<Self>);
+
+    /// Called instead of [`KernelSession::kernel_errored`] when the kernel
+    /// died of running into its memory limit of `limit` bytes.
+    fn kernel_exceeded_memory_limit(
+        &mut self,
+        _limit: u64,
+        error_message: String,
+        cx: &mut Context<Self>,
+    ) {
+        self.kernel_errored(error_message, cx);
+    }
+}
+
+/// Hands a message from the kernel to `session`, then shows it if the
+/// session's window is still open. Without one, the session still handles the
+/// message and shows it once a window is available again.
+pub fn route_message<S: KernelSession + 'static>(
+    session: &Entity<S>,
+    message: &JupyterMessage,
+    cx: &mut AsyncWindowContext,
+) {
+    session.update(cx, |session, cx| session.handle_message(message, cx));
+    session
+        .update_in(cx, |session, window, cx| {
+            session.present_messages(window, cx)
+        })
+        .ok();
 }
 
 #[derive(Debug, Clone)]
@@ -215,6 +272,10 @@ pub struct PythonEnvKernelSpecification {
     pub has_ipykernel: bool,
     /// Display label for the environment type: "venv", "Conda", "Pyenv", etc.
     pub environment_kind: Option<String>,
+    /// The name shown for the environment, such as "myproj .venv (Python
+    /// 3.12, uv)". `name` stays the toolchain's name, which identifies the
+    /// kernel in caches and settings.
+    pub display_name: String,
 }
 
 impl PartialEq for PythonEnvKernelSpecification {
@@ -256,6 +317,10 @@ pub struct SshRemoteKernelSpecification {
     pub name: String,
     pub path: SharedString,
     pub kernelspec: JupyterKernelspec,
+    /// The name shown for the kernel, which for a Python environment names
+    /// it like a local one. `name` identifies the kernel in caches and
+    /// settings.
+    pub display_name: String,
 }
 
 #[derive(Debug, Clone)]
@@ -263,6 +328,9 @@ pub struct WslKernelSpecification {
     pub name: String,
     pub kernelspec: JupyterKernelspec,
     pub distro: String,
+    /// The name shown for the kernel, which for a Python environment names
+    /// it like a local one.
+    pub display_name: String,
 }
 
 impl PartialEq for SshRemoteKernelSpecification {
@@ -306,6 +374,17 @@ impl KernelSpecification {
         }
     }
 
+    /// The name shown in menus. Only differs from `name` for Python
+    /// environments, whose toolchain names are often just "venv".
+    pub fn display_name(&self) -> SharedString {
+        match self {
+            Self::PythonEnv(spec) => spec.display_name.clone().into(),
+            Self::SshRemote(spec) => spec.display_name.clone().into(),
+            Self::WslRemote(spec) => spec.display_name.clone().into(),
+            _ => self.name(),
+        }
+    }
+
     pub fn type_name(&self) -> SharedString {
         match self {
             Self::Jupyter(_) => "Jupyter".into(),
@@ -340,6 +419,66 @@ impl KernelSpecification {
         })
     }
 
+    pub fn kernelspec(&self) -> &JupyterKernelspec {
+        match self {
+            Self::Jupyter(spec) => &spec.kernelspec,
+            Self::PythonEnv(spec) => &spec.kernelspec,
+            Self::JupyterServer(spec) => &spec.kernelspec,
+            Self::SshRemote(spec) => &spec.kernelspec,
+            Self::WslRemote(spec) => &spec.kernelspec,
+        }
+    }
+
+    /// Identifies the kernel across restarts of Zed, for caching what it
+    /// reports about itself.
+    pub fn cache_key(&self) -> String {
+        let kind = match self {
+            Self::Jupyter(_) => "jupyter",
+            Self::PythonEnv(_) => "python-env",
+            Self::JupyterServer(_) => "jupyter-server",
+            Self::SshRemote(_) => "ssh",
+            Self::WslRemote(_) => "wsl",
+        };
+        format!("{kind}:{}:{}", self.path(), self.name())
+    }
+
+    /// Where the kernel runs, as listed in the `trusted_html_sources`
+    /// setting: `local`, `ssh`, `wsl`, or the URL of its Jupyter server.
+    pub fn html_trust_source(&self) -> String {
+        match self {
+            Self::Jupyter(_) | Self::PythonEnv(_) => "local".to_string(),
+            Self::JupyterServer(spec) => spec.url.clone(),
+            Self::SshRemote(_) => "ssh".to_string(),
+            Self::WslRemote(_) => "wsl".to_string(),
+        }
+    }
+
+    /// Whether the kernel's HTML outputs are shown as it sends them, rather
+    /// than sanitized first.
+    pub fn html_trust(&self, cx: &App) -> HtmlTrust {
+        let is_trusted = ReplSettings::get_global(cx)
+            .trusted_html_sources
+            .get(&self.html_trust_source())
+            .copied()
+            .unwrap_or(false);
+        if is_trusted {
+            HtmlTrust::Trusted
+        } else {
+            HtmlTrust::Untrusted
+        }
+    }
+
+    /// The local file whose changes mean the kernel may now be a different
+    /// version: the interpreter of a Python environment, or the `kernel.json`
+    /// of a Jupyter kernelspec.
+    pub fn local_source_path(&self) -> Option<PathBuf> {
+        match self {
+            Self::Jupyter(spec) => Some(spec.path.join("kernel.json")),
+            Self::PythonEnv(spec) => Some(spec.path.clone()),
+            Self::JupyterServer(_) | Self::SshRemote(_) | Self::WslRemote(_) => None,
+        }
+    }
+
     pub fn has_ipykernel(&self) -> bool {
         match self {
             Self::Jupyter(_) | Self::JupyterServer(_) | Self::SshRemote(_) | Self::WslRemote(_) => {
@@ -378,6 +517,52 @@ impl KernelSpecification {
     }
 }
 
+/// Picks the default kernel from `specs` using `rules`. Rules are tried in
+/// order, and each one picks the first kernel it matches that can run.
+/// Relative path prefixes are resolved against `worktree_root`.
+pub fn select_kernel_by_rules<'a>(
+    specs: &[&'a KernelSpecification],
+    rules: &[KernelSelectionRule],
+    worktree_root: Option<&Path>,
+) -> Option<&'a KernelSpecification> {
+    rules.iter().find_map(|rule| {
+        let matches: Box<dyn Fn(&KernelSpecification) -> bool + '_> = match rule {
+            KernelSelectionRule::Name(pattern) => {
+                let matcher = globset::GlobBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map(|glob| glob.compile_matcher());
+                match matcher {
+                    Ok(matcher) => Box::new(move |spec| matcher.is_match(spec.name().as_str())),
+                    Err(error) => {
+                        log::warn!("invalid kernel name pattern {pattern:?}: {error}");
+                        return None;
+                    }
+                }
+            }
+            KernelSelectionRule::EnvironmentKind(kind) => Box::new(move |spec| {
+                matches!(spec, KernelSpecification::PythonEnv(_))
+                    && spec
+                        .environment_kind_label()
+                        .is_some_and(|label| label.eq_ignore_ascii_case(kind))
+            }),
+            KernelSelectionRule::PathPrefix(prefix) => {
+                let prefix = Path::new(prefix);
+                let prefix = if prefix.is_absolute() {
+                    prefix.to_path_buf()
+                } else {
+                    worktree_root?.join(prefix)
+                };
+                Box::new(move |spec| Path::new(spec.path().as_str()).starts_with(&prefix))
+            }
+        };
+        specs
+            .iter()
+            .copied()
+            .find(|spec| spec.has_ipykernel() && matches(spec))
+    })
+}
+
 fn extract_environment_kind(toolchain_json: &serde_json::Value) -> Option<String> {
     let kind_str = toolchain_json.get("kind")?.as_str()?;
     let label = match kind_str {
@@ -405,9 +590,113 @@ fn extract_environment_kind(toolchain_json: &serde_json::Value) -> Option<String
     Some(label.to_string())
 }
 
+/// The `major.minor` Python version of a toolchain, when its discovery
+/// reported one.
+fn extract_python_version(toolchain_json: &serde_json::Value) -> Option<String> {
+    let version = toolchain_json.get("version")?.as_str()?;
+    let mut parts = version.split('.');
+    let major = parts.next()?;
+    let minor = parts.next()?;
+    Some(format!("{major}.{minor}"))
+}
+
+/// The directory of a toolchain's environment, when its discovery reported
+/// one.
+fn extract_environment_prefix(toolchain_json: &serde_json::Value) -> Option<PathBuf> {
+    toolchain_json.get("prefix")?.as_str().map(PathBuf::from)
+}
+
+/// The longest environment location shown before it's cut from the front,
+/// keeping the end that tells environments apart.
+const MAX_ENVIRONMENT_LOCATION_CHARS: usize = 48;
+
+/// Names a Python environment by where it is and what it runs, such as
+/// "myproj .venv (Python 3.12, uv)" for one inside the worktree `myproj`, or
+/// "~/miniconda3/envs/ml (Python 3.11, Conda)" for one outside it.
+fn python_env_display_name(
+    interpreter: &Path,
+    prefix: Option<&Path>,
+    worktree_root: Option<&Path>,
+    python_version: Option<&str>,
+    environment_kind: Option<&str>,
+) -> String {
+    // Global interpreters share their prefix, such as `/usr`, so they're
+    // named by the interpreter instead.
+    let is_global = environment_kind.is_some_and(|kind| kind.starts_with("global"));
+    let environment_dir = if is_global {
+        None
+    } else {
+        prefix.map(Path::to_path_buf).or_else(|| {
+            let bin_dir = interpreter.parent()?;
+            match bin_dir.file_name()?.to_str()? {
+                "bin" | "Scripts" => bin_dir.parent().map(Path::to_path_buf),
+                _ => Some(bin_dir.to_path_buf()),
+            }
+        })
+    };
+    let environment_dir = environment_dir.as_deref().unwrap_or(interpreter);
+
+    let worktree_relative = worktree_root.and_then(|root| {
+        let relative = environment_dir.strip_prefix(root).ok()?;
+        let worktree_name = root.file_name()?.to_string_lossy();
+        Some(if relative.as_os_str().is_empty() {
+            worktree_name.into_owned()
+        } else {
+            format!("{worktree_name} {}", relative.display())
+        })
+    });
+    let location = worktree_relative
+        .unwrap_or_else(|| environment_dir.compact().to_string_lossy().into_owned());
+    let location = util::truncate_and_remove_front(&location, MAX_ENVIRONMENT_LOCATION_CHARS);
+
+    let details = python_version
+        .map(|version| format!("Python {version}"))
+        .into_iter()
+        .chain(environment_kind.map(str::to_string))
+        .collect::<Vec<_>>();
+    if details.is_empty() {
+        location
+    } else {
+        format!("{location} ({})", details.join(", "))
+    }
+}
+
+/// Combines toolchains from several sources, keeping only the first toolchain
+/// for each interpreter, since user toolchains are often discovered as well.
+fn dedupe_toolchains(toolchains: impl IntoIterator<Item = Toolchain>) -> Vec<Toolchain> {
+    let mut seen_paths = HashSet::new();
+    toolchains
+        .into_iter()
+        .filter(|toolchain| seen_paths.insert(toolchain.path.clone()))
+        .collect()
+}
+
+/// Whether `path` is an interpreter that can still be launched. Environments
+/// are often deleted after being added or discovered.
+async fn is_executable_interpreter(path: &Path) -> bool {
+    let Ok(metadata) = smol::fs::metadata(path).await else {
+        return false;
+    };
+    if !metadata.is_file() {
+        return false;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+        metadata.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
+    {
+        true
+    }
+}
+
+/// Kernels for the Python toolchains available in `worktree_id`. Without a
+/// worktree, such as for untitled buffers, the toolchains the user added for
+/// every project are used.
 pub fn python_env_kernel_specifications(
     project: &Entity<Project>,
-    worktree_id: WorktreeId,
+    worktree_id: Option<WorktreeId>,
     cx: &mut App,
 ) -> impl Future<Output = Result<Vec<KernelSpecification>>> + use<> {
     let python_language = LanguageName::new_static("Python");
@@ -423,40 +712,57 @@ pub fn python_env_kernel_specifications(
             }
         });
 
-    let toolchains = project.read(cx).available_toolchains(
-        ProjectPath {
-            worktree_id,
-            path: RelPath::empty().into(),
-        },
-        python_language,
-        cx,
-    );
-    #[allow(unused)]
-    let worktree_root_path: Option<std::sync::Arc<std::path::Path>> = project
-        .read(cx)
-        .worktree_for_id(worktree_id, cx)
+    let toolchains = match worktree_id {
+        Some(worktree_id) => {
+            let toolchains = project.read(cx).available_toolchains(
+                ProjectPath {
+                    worktree_id,
+                    path: RelPath::empty().into(),
+                },
+                python_language,
+                cx,
+            );
+            cx.background_spawn(async move {
+                let Toolchains {
+                    toolchains,
+                    root_path: _,
+                    user_toolchains,
+                } = toolchains.await?;
+                Some(
+                    user_toolchains
+                        .into_values()
+                        .flatten()
+                        .chain(toolchains.toolchains)
+                        .collect::<Vec<_>>(),
+                )
+            })
+        }
+        None => Task::ready(project.read(cx).user_toolchains(cx).map(|user_toolchains| {
+            user_toolchains
+                .into_iter()
+                .filter(|(scope, _)| *scope == ToolchainScope::Global)
+                .flat_map(|(_, toolchains)| toolchains)
+                .filter(|toolchain| toolchain.language_name == python_language)
+                .collect()
+        })),
+    };
+    let worktree_root_path: Option<std::sync::Arc<std::path::Path>> = worktree_id
+        .and_then(|worktree_id| project.read(cx).worktree_for_id(worktree_id, cx))
         .map(|w| w.read(cx).abs_path());
 
     let background_executor = cx.background_executor().clone();
 
     async move {
-        let (toolchains, user_toolchains) = if let Some(Toolchains {
-            toolchains,
-            root_path: _,
-            user_toolchains,
-        }) = toolchains.await
-        {
-            (toolchains, user_toolchains)
-        } else {
+        let Some(toolchains) = toolchains.await else {
             return Ok(Vec::new());
         };
 
-        let kernelspecs = user_toolchains
-            .into_values()
-            .flatten()
-            .chain(toolchains.toolchains)
+        let toolchains = dedupe_toolchains(toolchains);
+        let kernelspecs = toolchains
+            .into_iter()
             .map(|toolchain| {
                 let wsl_distro = wsl_distro.clone();
+                let worktree_root_path = worktree_root_path.clone();
                 background_executor.spawn(async move {
                     // For remote projects, we assume python is available assuming toolchain is reported.
                     // We can skip the `ipykernel` check or run it remotely.
@@ -464,6 +770,16 @@ pub fn python_env_kernel_specifications(
                     // `new_smol_command` runs locally. We need to run remotely if `is_remote`.
 
                     if is_remote {
+                        // The interpreter can't be probed from here, so the
+                        // version is only known when discovery reported it.
+                        let interpreter = toolchain.path.to_string();
+                        let display_name = python_env_display_name(
+                            Path::new(&interpreter),
+                            extract_environment_prefix(&toolchain.as_json).as_deref(),
+                            worktree_root_path.as_deref(),
+                            extract_python_version(&toolchain.as_json).as_deref(),
+                            extract_environment_kind(&toolchain.as_json).as_deref(),
+                        );
                         let default_kernelspec = JupyterKernelspec {
                             argv: vec![
                                 toolchain.path.to_string(),
@@ -488,6 +804,7 @@ pub fn python_env_kernel_specifications(
                                 name: toolchain.name.to_string(),
                                 kernelspec: default_kernelspec,
                                 distro,
+                                display_name,
                             }));
                         }
 
@@ -500,19 +817,47 @@ pub fn python_env_kernel_specifications(
                                 name: format!("Remote {}", toolchain.name),
                                 path: toolchain.path.clone(),
                                 kernelspec: default_kernelspec,
+                                display_name: format!("Remote {display_name}"),
                             },
                         ));
                     }
 
                     let python_path = toolchain.path.to_string();
+                    if !is_executable_interpreter(Path::new(&python_path)).await {
+                        log::warn!(
+                            "skipping toolchain {}: {} is missing or not executable",
+                            toolchain.name,
+                            python_path
+                        );
+                        return None;
+                    }
                     let environment_kind = extract_environment_kind(&toolchain.as_json);
 
-                    let has_ipykernel = util::command::new_command(&python_path)
-                        .args(&["-c", "import ipykernel"])
+                    // Prints the version before the import, so it's known
+                    // even when ipykernel is missing.
+                    let probe = util::command::new_command(&python_path)
+                        .args(&[
+                            "-c",
+                            "import sys; print('%d.%d' % sys.version_info[:2]); import ipykernel",
+                        ])
                         .output()
                         .await
-                        .map(|output| output.status.success())
-                        .unwrap_or(false);
+                        .ok();
+                    let has_ipykernel = probe
+                        .as_ref()
+                        .is_some_and(|output| output.status.success());
+                    let python_version = extract_python_version(&toolchain.as_json).or_else(|| {
+                        let stdout = String::from_utf8_lossy(&probe.as_ref()?.stdout).into_owned();
+                        Some(stdout.lines().next()?.trim().to_string())
+                            .filter(|version| !version.is_empty())
+                    });
+                    let display_name = python_env_display_name(
+                        Path::new(&python_path),
+                        extract_environment_prefix(&toolchain.as_json).as_deref(),
+                        worktree_root_path.as_deref(),
+                        python_version.as_deref(),
+                        environment_kind.as_deref(),
+                    );
 
                     let mut env = HashMap::new();
                     if let Some(python_bin_dir) = PathBuf::from(&python_path).parent() {
@@ -561,6 +906,7 @@ pub fn python_env_kernel_specifications(
                         kernelspec,
                         has_ipykernel,
                         environment_kind,
+                        display_name,
                     }))
                 })
             });
@@ -647,9 +993,10 @@ pub fn python_env_kernel_specifications(
 
                             kernel_specs.push(KernelSpecification::WslRemote(
                                 WslKernelSpecification {
-                                    name: display_name,
+                                    name: display_name.clone(),
                                     kernelspec: default_kernelspec,
                                     distro: distro.to_string(),
+                                    display_name,
                                 },
                             ));
                         }
@@ -662,16 +1009,184 @@ pub fn python_env_kernel_specifications(
     }
 }
 
+/// What's known about the process behind a running kernel, for the status bar
+/// and for debugging which environment code runs in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KernelRuntimeInfo {
+    pub working_directory: PathBuf,
+    /// The kernel's process id. Only known for kernels Zed spawns locally.
+    pub pid: Option<u32>,
+    /// The program running the kernel, such as a Python interpreter.
+    pub interpreter: Option<String>,
+    /// The kernel's id on the server that runs it.
+    pub kernel_id: Option<String>,
+    /// The Jupyter server the kernel runs on.
+    pub server_url: Option<String>,
+    /// Whether Zed started the kernel on its server and shuts it down there
+    /// when the session closes, rather than having attached to it. `None` for
+    /// kernels that don't run on a Jupyter server.
+    pub owned: Option<bool>,
+    /// The allowlisted environment variables as seen by the kernel, or `None`
+    /// until they have been fetched from it. Unset variables map to `None`.
+    pub environment: Option<BTreeMap<String, Option<String>>>,
+    /// Whether the kernel supports the Jupyter debug protocol, or `None` until
+    /// it has sent its kernel info.
+    pub debug_support: Option<KernelDebugSupport>,
+    /// The Jupyter protocol version the kernel speaks, or `None` until it has
+    /// sent its kernel info.
+    pub protocol_version: Option<ProtocolVersion>,
+    /// The resource limits the kernel was started with. Only known for
+    /// kernels Zed spawns locally.
+    pub resource_limits: Option<KernelResourceLimits>,
+}
+
+/// Whether a kernel implements the Debug Adapter Protocol tunneled over
+/// Jupyter messages, as ipykernel 6 and later do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KernelDebugSupport {
+    Unsupported,
+    /// `info` is the body of the kernel's reply to `debugInfo`, or `None`
+    /// until it has answered.
+    Supported {
+        info: Option<serde_json::Value>,
+    },
+}
+
+/// How long each phase of a kernel launch took, for finding where a slow
+/// startup spends its time. Phases the kernel doesn't go through, or that the
+/// launch didn't reach, are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KernelStartupTiming {
+    /// Writing the connection file and spawning the kernel process, for
+    /// kernels Zed starts itself, locally, over SSH or in WSL.
+    pub process_spawn: Option<Duration>,
+    /// Creating the kernel through the Jupyter server's HTTP API.
+    pub kernel_creation: Option<Duration>,
+    /// Connecting to the kernel's channels, including the SSH tunnel to
+    /// them.
+    pub channel_connect: Option<Duration>,
+    /// From sending the first `kernel_info_request` to its reply.
+    pub first_kernel_info: Option<Duration>,
+}
+
+impl KernelStartupTiming {
+    /// The phases that were reached, in launch order, with their names.
+    pub fn phases(&self) -> Vec<(&'static str, Duration)> {
+        [
+            ("Process spawn", self.process_spawn),
+            ("Kernel creation", self.kernel_creation),
+            ("Channel connect", self.channel_connect),
+            ("First kernel info", self.first_kernel_info),
+        ]
+        .into_iter()
+        .filter_map(|(name, duration)| Some((name, duration?)))
+        .collect()
+    }
+
+    pub fn total(&self) -> Duration {
+        self.phases()
+            .into_iter()
+            .map(|(_, duration)| duration)
+            .sum()
+    }
+}
+
+/// Records [`KernelStartupTiming`] from the task launching a kernel, so the
+/// phases that finished are still known when the launch fails.
+#[derive(Debug, Clone, Default)]
+pub struct KernelStartupTimer(Arc<Mutex<KernelStartupTiming>>);
+
+impl KernelStartupTimer {
+    /// Records the time since `started_at` as the duration of `phase`.
+    pub fn record(
+        &self,
+        phase: impl FnOnce(&mut KernelStartupTiming) -> &mut Option<Duration>,
+        started_at: Instant,
+    ) {
+        *phase(&mut self.0.lock()) = Some(started_at.elapsed());
+    }
+
+    pub fn timing(&self) -> KernelStartupTiming {
+        self.0.lock().clone()
+    }
+}
+
 pub trait RunningKernel: Send + Debug {
     fn request_tx(&self) -> mpsc::Sender<JupyterMessage>;
     fn stdin_tx(&self) -> mpsc::Sender<JupyterMessage>;
     fn working_directory(&self) -> &PathBuf;
+    /// Records the directory the kernel moved into after it started. Only
+    /// kernels whose directory Zed can't know up front need to track this.
+    fn set_working_directory(&mut self, _working_directory: PathBuf) {}
+    /// What's known about the kernel without asking it. The environment is
+    /// fetched separately by [`crate::Session::runtime_info`].
+    fn runtime_info(&self) -> KernelRuntimeInfo;
     fn execution_state(&self) -> &ExecutionState;
     fn set_execution_state(&mut self, state: ExecutionState);
     fn kernel_info(&self) -> Option<&KernelInfoReply>;
     fn set_kernel_info(&mut self, info: KernelInfoReply);
     fn force_shutdown(&mut self, window: &mut Window, cx: &mut App) -> Task<anyhow::Result<()>>;
     fn kill(&mut self);
+    /// What the kernel process printed outside of the Jupyter protocol, for
+    /// kernels Zed launched itself.
+    fn diagnostics_log(&self) -> Option<String> {
+        None
+    }
+    /// Whether the connection to the kernel dropped and is being re-established.
+    fn is_reconnecting(&self) -> bool {
+        false
+    }
+    /// How the kernel is interrupted. Kernels Zed doesn't spawn itself can
+    /// only be sent a message.
+    fn interrupt_mode(&self) -> InterruptMode {
+        InterruptMode::Message
+    }
+    /// Interrupts the kernel's process, for [`InterruptMode::Signal`].
+    fn signal_interrupt(&mut self) -> Result<()> {
+        anyhow::bail!("this kernel can't be interrupted with a signal")
+    }
+    /// Whether Zed owns the kernel on the server that runs it. Owned kernels
+    /// are deleted from the server when their session closes, while kernels
+    /// Zed attached to are left running. `None` for kernels that don't run on
+    /// a Jupyter server.
+    fn is_owned(&self) -> Option<bool> {
+        None
+    }
+    /// Takes or releases ownership of the kernel, for kernels that run on a
+    /// Jupyter server.
+    fn set_owned(&mut self, _owned: bool) {}
+    /// Deletes the kernel from the server that runs it if Zed owns it there.
+    fn delete_if_owned(&self, _cx: &App) -> Option<Task<Result<()>>> {
+        None
+    }
+}
+
+/// How a kernel is interrupted, from the `interrupt_mode` of its kernelspec.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InterruptMode {
+    /// Signals the kernel's process: `SIGINT` to its process group, or
+    /// `CTRL_BREAK` on Windows. Kernelspecs that don't say use this.
+    #[default]
+    Signal,
+    /// Sends an `interrupt_request` on the control channel, for kernels that
+    /// ignore signals.
+    Message,
+}
+
+impl InterruptMode {
+    pub fn from_kernelspec(kernelspec: &JupyterKernelspec) -> Self {
+        match kernelspec.interrupt_mode.as_deref() {
+            Some("message") => Self::Message,
+            _ => Self::Signal,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Signal => "Interrupts by signaling the kernel process",
+            Self::Message => "Interrupts by sending the kernel an interrupt_request",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -682,7 +1197,10 @@ pub enum KernelStatus {
     Error,
     ShuttingDown,
     Shutdown,
+    /// Shut down after being idle. Starts again the next time code runs.
+    Culled,
     Restarting,
+    Reconnecting,
 }
 
 impl KernelStatus {
@@ -700,7 +1218,9 @@ impl ToString for KernelStatus {
             KernelStatus::Error => "Error".to_string(),
             KernelStatus::ShuttingDown => "Shutting Down".to_string(),
             KernelStatus::Shutdown => "Shutdown".to_string(),
+            KernelStatus::Culled => "Culled (Idle)".to_string(),
             KernelStatus::Restarting => "Restarting".to_string(),
+            KernelStatus::Reconnecting => "Reconnecting".to_string(),
         }
     }
 }
@@ -709,15 +1229,21 @@ impl ToString for KernelStatus {
 pub enum Kernel {
     RunningKernel(Box<dyn RunningKernel>),
     StartingKernel(Shared<Task<()>>),
-    ErroredLaunch(String),
+    /// The kernel failed, with the timing of its launch when the launch is
+    /// what failed.
+    ErroredLaunch(String, Option<KernelStartupTiming>),
     ShuttingDown,
     Shutdown,
+    /// The kernel was shut down after being idle, and is started again with
+    /// the same specification the next time code runs.
+    Culled,
     Restarting,
 }
 
 impl From<&Kernel> for KernelStatus {
     fn from(kernel: &Kernel) -> Self {
         match kernel {
+            Kernel::RunningKernel(kernel) if kernel.is_reconnecting() => KernelStatus::Reconnecting,
             Kernel::RunningKernel(kernel) => match kernel.execution_state() {
                 ExecutionState::Idle => KernelStatus::Idle,
                 ExecutionState::Busy => KernelStatus::Busy,
@@ -730,9 +1256,10 @@ impl From<&Kernel> for KernelStatus {
                 ExecutionState::Other(_) => KernelStatus::Error,
             },
             Kernel::StartingKernel(_) => KernelStatus::Starting,
-            Kernel::ErroredLaunch(_) => KernelStatus::Error,
+            Kernel::ErroredLaunch(..) => KernelStatus::Error,
             Kernel::ShuttingDown => KernelStatus::ShuttingDown,
             Kernel::Shutdown => KernelStatus::Shutdown,
+            Kernel::Culled => KernelStatus::Culled,
             Kernel::Restarting => KernelStatus::Restarting,
         }
     }
@@ -755,13 +1282,281 @@ impl Kernel {
         }
     }
 
+    /// What the running kernel reported about itself, once it has.
+    pub fn kernel_info(&self) -> Option<&KernelInfoReply> {
+        match self {
+            Kernel::RunningKernel(running_kernel) => running_kernel.kernel_info(),
+            _ => None,
+        }
+    }
+
     pub fn is_shutting_down(&self) -> bool {
         match self {
             Kernel::Restarting | Kernel::ShuttingDown => true,
             Kernel::RunningKernel(_)
             | Kernel::StartingKernel(_)
-            | Kernel::ErroredLaunch(_)
-            | Kernel::Shutdown => false,
+            | Kernel::ErroredLaunch(..)
+            | Kernel::Shutdown
+            | Kernel::Culled => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kernelspec(display_name: &str) -> JupyterKernelspec {
+        JupyterKernelspec {
+            argv: Vec::new(),
+            display_name: display_name.to_string(),
+            language: "python".to_string(),
+            interrupt_mode: None,
+            metadata: None,
+            env: None,
         }
     }
+
+    fn jupyter(name: &str, path: &str) -> KernelSpecification {
+        KernelSpecification::Jupyter(LocalKernelSpecification {
+            name: name.to_string(),
+            path: PathBuf::from(path),
+            kernelspec: kernelspec(name),
+        })
+    }
+
+    fn python_env(name: &str, path: &str, kind: &str, has_ipykernel: bool) -> KernelSpecification {
+        KernelSpecification::PythonEnv(PythonEnvKernelSpecification {
+            name: name.to_string(),
+            path: PathBuf::from(path),
+            kernelspec: kernelspec(name),
+            has_ipykernel,
+            environment_kind: Some(kind.to_string()),
+            display_name: name.to_string(),
+        })
+    }
+
+    fn jupyter_server(name: &str) -> KernelSpecification {
+        KernelSpecification::JupyterServer(RemoteKernelSpecification {
+            name: name.to_string(),
+            url: "http://localhost:8888".to_string(),
+            token: Default::default(),
+            kernelspec: kernelspec(name),
+        })
+    }
+
+    fn select<'a>(
+        specs: &'a [KernelSpecification],
+        rules: &[KernelSelectionRule],
+    ) -> Option<&'a KernelSpecification> {
+        let specs = specs.iter().collect::<Vec<_>>();
+        select_kernel_by_rules(&specs, rules, Some(Path::new("/project")))
+    }
+
+    #[test]
+    fn test_select_kernel_by_rules() {
+        let specs = [
+            jupyter("python3", "/usr/share/jupyter/kernels/python3"),
+            python_env("conda-base", "/opt/conda/bin/python", "Conda", true),
+            python_env("project-venv", "/project/.venv/bin/python", "venv", true),
+            python_env("broken-uv", "/project/.uv/bin/python", "uv", false),
+            jupyter_server("remote-python"),
+        ];
+
+        assert_eq!(select(&specs, &[]), None);
+        assert_eq!(
+            select(&specs, &[KernelSelectionRule::Name("REMOTE-*".into())]),
+            Some(&specs[4])
+        );
+        assert_eq!(
+            select(
+                &specs,
+                &[KernelSelectionRule::EnvironmentKind("conda".into())]
+            ),
+            Some(&specs[1])
+        );
+        assert_eq!(
+            select(&specs, &[KernelSelectionRule::PathPrefix(".venv/".into())]),
+            Some(&specs[2])
+        );
+        assert_eq!(
+            select(
+                &specs,
+                &[KernelSelectionRule::PathPrefix("/usr/share/jupyter".into())]
+            ),
+            Some(&specs[0])
+        );
+
+        // Kernels that can't run are skipped, and later rules are tried in order.
+        assert_eq!(
+            select(
+                &specs,
+                &[
+                    KernelSelectionRule::EnvironmentKind("uv".into()),
+                    KernelSelectionRule::Name("[invalid".into()),
+                    KernelSelectionRule::Name("python*".into()),
+                    KernelSelectionRule::EnvironmentKind("venv".into()),
+                ]
+            ),
+            Some(&specs[0])
+        );
+
+        // Relative prefixes need a worktree to resolve against.
+        let spec_refs = specs.iter().collect::<Vec<_>>();
+        assert_eq!(
+            select_kernel_by_rules(
+                &spec_refs,
+                &[KernelSelectionRule::PathPrefix(".venv/".into())],
+                None
+            ),
+            None
+        );
+    }
+
+    fn toolchain(name: &str, path: &Path) -> Toolchain {
+        Toolchain {
+            name: name.to_string().into(),
+            path: path.to_string_lossy().into_owned().into(),
+            language_name: LanguageName::new_static("Python"),
+            as_json: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_deleted_toolchains_are_skipped() {
+        let tree = util::test::TempTree::new(serde_json::json!({
+            ".venv": {
+                "bin": {
+                    "python": "#!/bin/sh\n",
+                },
+            },
+        }));
+        let venv_python = tree.path().join(".venv/bin/python");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            std::fs::set_permissions(&venv_python, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let missing_python = tree.path().join("missing/bin/python");
+
+        // The user toolchain is discovered too, and is only listed once.
+        let toolchains = dedupe_toolchains([
+            toolchain("user venv", &venv_python),
+            toolchain("discovered venv", &venv_python),
+            toolchain("missing", &missing_python),
+        ]);
+        assert_eq!(
+            toolchains
+                .iter()
+                .map(|toolchain| toolchain.name.as_ref())
+                .collect::<Vec<_>>(),
+            ["user venv", "missing"]
+        );
+
+        smol::block_on(async {
+            assert!(is_executable_interpreter(&venv_python).await);
+            assert!(!is_executable_interpreter(&missing_python).await);
+            assert!(!is_executable_interpreter(&tree.path().join(".venv/bin")).await);
+
+            // The environment is removed after it was discovered.
+            std::fs::remove_dir_all(tree.path().join(".venv")).unwrap();
+            assert!(!is_executable_interpreter(&venv_python).await);
+        });
+    }
+
+    #[test]
+    fn test_kernel_startup_timing() {
+        let timing = KernelStartupTiming {
+            process_spawn: Some(Duration::from_millis(1700)),
+            channel_connect: Some(Duration::from_millis(300)),
+            ..KernelStartupTiming::default()
+        };
+        assert_eq!(
+            timing.phases(),
+            [
+                ("Process spawn", Duration::from_millis(1700)),
+                ("Channel connect", Duration::from_millis(300)),
+            ]
+        );
+        assert_eq!(timing.total(), Duration::from_secs(2));
+
+        // Phases recorded from the launch task are visible to its clones.
+        let timer = KernelStartupTimer::default();
+        timer
+            .clone()
+            .record(|timing| &mut timing.kernel_creation, Instant::now());
+        assert!(timer.timing().kernel_creation.is_some());
+        assert_eq!(timer.timing().process_spawn, None);
+    }
+
+    #[test]
+    fn test_python_env_display_name() {
+        let worktree_root = Path::new("/work/myproj");
+        assert_eq!(
+            python_env_display_name(
+                Path::new("/work/myproj/.venv/bin/python"),
+                None,
+                Some(worktree_root),
+                Some("3.12"),
+                Some("uv"),
+            ),
+            "myproj .venv (Python 3.12, uv)"
+        );
+        assert_eq!(
+            python_env_display_name(
+                Path::new("/opt/conda/envs/ml/bin/python"),
+                Some(Path::new("/opt/conda/envs/ml")),
+                Some(worktree_root),
+                None,
+                Some("Conda"),
+            ),
+            "/opt/conda/envs/ml (Conda)"
+        );
+        assert_eq!(
+            python_env_display_name(
+                Path::new("/usr/bin/python3"),
+                Some(Path::new("/usr")),
+                None,
+                Some("3.12"),
+                Some("global"),
+            ),
+            "/usr/bin/python3 (Python 3.12, global)"
+        );
+
+        let long_name = python_env_display_name(
+            Path::new(
+                "/srv/shared/environments/team/data-science/projects/forecasting/.venv/bin/python",
+            ),
+            None,
+            None,
+            Some("3.11"),
+            None,
+        );
+        assert!(
+            long_name.starts_with('…') && long_name.ends_with("forecasting/.venv (Python 3.11)"),
+            "{long_name}"
+        );
+
+        assert_eq!(
+            extract_python_version(&serde_json::json!({ "version": "3.12.4.final.0" })),
+            Some("3.12".to_string())
+        );
+        assert_eq!(
+            extract_python_version(&serde_json::json!({ "version": null })),
+            None
+        );
+    }
+
+    #[test]
+    fn test_jupyter_server_token_is_not_debug_formatted() {
+        let spec = KernelSpecification::JupyterServer(RemoteKernelSpecification {
+            name: "python3".to_string(),
+            url: "http://localhost:8888".to_string(),
+            token: "s3cret-token".to_string().into(),
+            kernelspec: kernelspec("python3"),
+        });
+        let formatted = format!("{spec:?} {spec:#?}");
+        assert!(!formatted.contains("s3cret-token"), "{formatted}");
+        assert!(formatted.contains("[redacted]"));
+    }
 }
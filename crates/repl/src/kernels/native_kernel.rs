@@ -1,28 +1,40 @@
 use anyhow::{Context as _, Result};
 use futures::{
-    AsyncBufReadExt as _, StreamExt as _,
-    channel::mpsc::{self},
-    io::BufReader,
+    AsyncRead, AsyncReadExt as _, FutureExt as _, Stream, StreamExt as _,
+    channel::{
+        mpsc::{self},
+        oneshot,
+    },
 };
 use gpui::{App, Entity, EntityId, Task, Window};
 use jupyter_protocol::{
     ExecutionState, JupyterKernelspec, JupyterMessage, KernelInfoReply,
     connection_info::{ConnectionInfo, Transport},
 };
+use parking_lot::Mutex;
 use project::Fs;
 use runtimelib::dirs;
 use smol::net::TcpListener;
 use std::{
+    collections::VecDeque,
     env,
     fmt::Debug,
     net::{IpAddr, Ipv4Addr, SocketAddr},
     path::PathBuf,
     sync::Arc,
+    time::{Duration, Instant},
 };
 
+use util::ResultExt as _;
 use uuid::Uuid;
 
-use super::{KernelSession, RunningKernel, start_kernel_tasks};
+use crate::outputs::stream::LossyUtf8Decoder;
+
+use super::{
+    ConnectionFileOwner, InterruptMode, KernelResourceLimits, KernelRuntimeInfo, KernelSession,
+    KernelStartupTimer, RunningKernel, connection_files_dir, format_memory_limit,
+    start_kernel_tasks, write_connection_file,
+};
 
 #[derive(Debug, Clone)]
 pub struct LocalKernelSpecification {
@@ -90,9 +102,140 @@ async fn peek_ports(ip: IpAddr) -> Result<[u16; 5]> {
     Ok(ports)
 }
 
+/// How much of the kernel process's own output to keep, in bytes.
+const DIAGNOSTICS_LOG_CAPACITY: usize = 256 * 1024;
+/// How many lines of the kernel process's output to add to the error when it
+/// exits abnormally.
+const DIAGNOSTICS_LOG_ERROR_LINES: usize = 20;
+/// How long to wait for the rest of the kernel process's output once it has
+/// exited.
+const DIAGNOSTICS_LOG_DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// The longest line of the kernel process's output kept whole, in bytes.
+/// Longer lines are split so that one enormous line is never buffered.
+const DIAGNOSTICS_LINE_LENGTH: usize = 16 * 1024;
+/// How much of the kernel process's output to read at a time, in bytes.
+const PIPE_CHUNK_LENGTH: usize = 8 * 1024;
+
+/// Reads lines from one of the kernel process's pipes. Bytes that aren't
+/// UTF-8 are replaced rather than failing the read, and lines longer than
+/// [`DIAGNOSTICS_LINE_LENGTH`] come back in segments.
+fn read_pipe_lines(pipe: impl AsyncRead + Unpin) -> impl Stream<Item = String> {
+    struct PipeLines<R> {
+        pipe: R,
+        decoder: LossyUtf8Decoder,
+        line: String,
+        lines: VecDeque<String>,
+        finished: bool,
+    }
+
+    let state = PipeLines {
+        pipe,
+        decoder: LossyUtf8Decoder::default(),
+        line: String::new(),
+        lines: VecDeque::new(),
+        finished: false,
+    };
+    futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(line) = state.lines.pop_front() {
+                return Some((line, state));
+            }
+            if state.finished {
+                return None;
+            }
+
+            let mut chunk = [0; PIPE_CHUNK_LENGTH];
+            let text = match state.pipe.read(&mut chunk).await {
+                Ok(0) => {
+                    state.finished = true;
+                    state.decoder.finish()
+                }
+                Ok(len) => state.decoder.decode(&chunk[..len]),
+                Err(err) => {
+                    log::warn!("kernel: failed to read output: {err}");
+                    state.finished = true;
+                    state.decoder.finish()
+                }
+            };
+
+            for (ix, segment) in text.split('\n').enumerate() {
+                if ix > 0 {
+                    let mut line = std::mem::take(&mut state.line);
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                    state.lines.push_back(line);
+                }
+                state.line.push_str(segment);
+                if state.line.len() >= DIAGNOSTICS_LINE_LENGTH {
+                    state.lines.push_back(std::mem::take(&mut state.line));
+                }
+            }
+            if state.finished && !state.line.is_empty() {
+                state.lines.push_back(std::mem::take(&mut state.line));
+            }
+        }
+    })
+}
+
+/// What the kernel process printed to its stdout and stderr outside of the
+/// Jupyter protocol, such as warnings from native libraries or a crash's
+/// backtrace. Only the most recent [`DIAGNOSTICS_LOG_CAPACITY`] bytes are kept.
+#[derive(Debug, Default)]
+struct DiagnosticsLog {
+    lines: VecDeque<String>,
+    len: usize,
+}
+
+impl DiagnosticsLog {
+    fn push(&mut self, mut line: String) {
+        if line.len() > DIAGNOSTICS_LOG_CAPACITY {
+            let mut start = line.len() - DIAGNOSTICS_LOG_CAPACITY;
+            while !line.is_char_boundary(start) {
+                start += 1;
+            }
+            line.drain(..start);
+        }
+
+        self.len += line.len();
+        self.lines.push_back(line);
+        while self.len > DIAGNOSTICS_LOG_CAPACITY {
+            let Some(oldest) = self.lines.pop_front() else {
+                break;
+            };
+            self.len -= oldest.len();
+        }
+    }
+
+    fn text(&self) -> String {
+        self.lines
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn tail(&self, line_count: usize) -> String {
+        let skip = self.lines.len().saturating_sub(line_count);
+        self.lines
+            .iter()
+            .skip(skip)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
 pub struct NativeRunningKernel {
     pub process: util::process::Child,
+    pid: u32,
+    interrupt_mode: InterruptMode,
+    interpreter: Option<String>,
     connection_path: PathBuf,
+    resource_limits: KernelResourceLimits,
+    diagnostics_log: Arc<Mutex<DiagnosticsLog>>,
+    _diagnostics_task: Option<Task<()>>,
     _process_status_task: Option<Task<()>>,
     pub working_directory: PathBuf,
     pub request_tx: mpsc::Sender<JupyterMessage>,
@@ -117,10 +260,13 @@ impl NativeRunningKernel {
         fs: Arc<dyn Fs>,
         // todo: convert to weak view
         session: Entity<S>,
+        startup_timer: KernelStartupTimer,
+        resource_limits: KernelResourceLimits,
         window: &mut Window,
         cx: &mut App,
     ) -> Task<Result<Box<dyn RunningKernel>>> {
         window.spawn(cx, async move |cx| {
+            let spawn_started_at = Instant::now();
             let ip = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
             let ports = peek_ports(ip).await?;
 
@@ -137,16 +283,30 @@ impl NativeRunningKernel {
                 kernel_name: Some(format!("zed-{}", kernel_specification.name)),
             };
 
-            let runtime_dir = dirs::runtime_dir();
-            fs.create_dir(&runtime_dir)
-                .await
-                .with_context(|| format!("Failed to create jupyter runtime dir {runtime_dir:?}"))?;
-            let connection_path = runtime_dir.join(format!("kernel-zed-{entity_id}.json"));
-            let content = serde_json::to_string(&connection_info)?;
-            fs.atomic_write(connection_path.clone(), content).await?;
+            let connection_files_dir = connection_files_dir();
+            fs.create_dir(&connection_files_dir).await.with_context(|| {
+                format!("Failed to create kernel connection file dir {connection_files_dir:?}")
+            })?;
+            let connection_path = connection_files_dir
+                .join(format!("kernel-{}-{entity_id}.json", std::process::id()));
+            let mut owner = ConnectionFileOwner::new();
+            write_connection_file(fs.as_ref(), &connection_path, &connection_info, owner).await?;
 
             let mut cmd = kernel_specification.command(&connection_path)?;
+            let interpreter = kernel_specification.kernelspec.argv.first().cloned();
+            let interrupt_mode = InterruptMode::from_kernelspec(&kernel_specification.kernelspec);
             cmd.current_dir(&working_directory);
+            // A process group of its own, so CTRL_BREAK only reaches the kernel.
+            #[cfg(windows)]
+            {
+                use std::os::windows::process::CommandExt as _;
+                use windows::Win32::System::Threading::{
+                    CREATE_NEW_PROCESS_GROUP, CREATE_NO_WINDOW,
+                };
+                cmd.creation_flags(CREATE_NO_WINDOW.0 | CREATE_NEW_PROCESS_GROUP.0);
+            }
+            #[cfg(unix)]
+            resource_limits.apply_to_command(&mut cmd);
 
             let mut process = util::process::Child::spawn(
                 cmd,
@@ -154,7 +314,25 @@ impl NativeRunningKernel {
                 std::process::Stdio::piped(),
                 std::process::Stdio::piped(),
             )?;
+            let pid = process.id();
+            // Windows limits processes once they run, by putting them in a job.
+            #[cfg(windows)]
+            let job = match resource_limits.apply_to_process(pid) {
+                Ok(job) => job,
+                Err(error) => {
+                    process.kill().ok();
+                    return Err(error);
+                }
+            };
+            // Recorded so a sweep after a crash can tell whether the kernel
+            // is still running.
+            owner.kernel_pid = Some(pid);
+            write_connection_file(fs.as_ref(), &connection_path, &connection_info, owner)
+                .await
+                .log_err();
+            startup_timer.record(|timing| &mut timing.process_spawn, spawn_started_at);
 
+            let connect_started_at = Instant::now();
             let session_id = Uuid::new_v4().to_string();
 
             let iopub_socket =
@@ -176,6 +354,7 @@ impl NativeRunningKernel {
                 peer_identity,
             )
             .await?;
+            startup_timer.record(|timing| &mut timing.channel_connect, connect_started_at);
 
             let (request_tx, stdin_tx) = start_kernel_tasks(
                 session.clone(),
@@ -189,65 +368,112 @@ impl NativeRunningKernel {
             let stderr = process.stderr.take();
             let stdout = process.stdout.take();
 
-            cx.spawn(async move |_cx| {
-                use futures::future::Either;
-
-                let stderr_lines = match stderr {
-                    Some(s) => Either::Left(
-                        BufReader::new(s)
-                            .lines()
-                            .map(|line| (log::Level::Error, line)),
-                    ),
-                    None => Either::Right(futures::stream::empty()),
-                };
-                let stdout_lines = match stdout {
-                    Some(s) => Either::Left(
-                        BufReader::new(s)
-                            .lines()
-                            .map(|line| (log::Level::Info, line)),
-                    ),
-                    None => Either::Right(futures::stream::empty()),
-                };
-                let mut lines = futures::stream::select(stderr_lines, stdout_lines);
-                while let Some((level, Ok(line))) = lines.next().await {
-                    log::log!(level, "kernel: {}", line);
+            // Drain the pipes on the background executor so a kernel printing a
+            // lot never blocks on a full pipe.
+            let diagnostics_log = Arc::new(Mutex::new(DiagnosticsLog::default()));
+            let (drained_tx, drained_rx) = oneshot::channel::<()>();
+            let diagnostics_task = cx.background_spawn({
+                let diagnostics_log = diagnostics_log.clone();
+                async move {
+                    use futures::future::Either;
+
+                    let stderr_lines = match stderr {
+                        Some(s) => Either::Left(
+                            read_pipe_lines(s).map(|line| (log::Level::Error, line)),
+                        ),
+                        None => Either::Right(futures::stream::empty()),
+                    };
+                    let stdout_lines = match stdout {
+                        Some(s) => Either::Left(
+                            read_pipe_lines(s).map(|line| (log::Level::Info, line)),
+                        ),
+                        None => Either::Right(futures::stream::empty()),
+                    };
+                    let mut lines = futures::stream::select(stderr_lines, stdout_lines);
+                    while let Some((level, line)) = lines.next().await {
+                        log::log!(level, "kernel: {}", line);
+                        diagnostics_log.lock().push(line);
+                    }
+                    drained_tx.send(()).ok();
                 }
-            })
-            .detach();
+            });
 
             let status = process.status();
 
-            let process_status_task = cx.spawn(async move |cx| {
-                let error_message = match status.await {
-                    Ok(status) => {
-                        if status.success() {
-                            log::info!("kernel process exited successfully");
-                            return;
+            let process_status_task = cx.spawn({
+                let diagnostics_log = diagnostics_log.clone();
+                async move |cx| {
+                    let error_message = match status.await {
+                        Ok(status) => {
+                            if status.success() {
+                                log::info!("kernel process exited successfully");
+                                return;
+                            }
+
+                            format!("kernel process exited with status: {:?}", status)
                         }
+                        Err(err) => {
+                            format!("kernel process exited with error: {:?}", err)
+                        }
+                    };
 
-                        format!("kernel process exited with status: {:?}", status)
-                    }
-                    Err(err) => {
-                        format!("kernel process exited with error: {:?}", err)
-                    }
-                };
-
-                log::error!("{}", error_message);
+                    log::error!("{}", error_message);
 
-                session.update(cx, |session, cx| {
-                    session.kernel_errored(error_message, cx);
+                    // A crashing kernel's last words may still be in the pipes.
+                    futures::select_biased! {
+                        _ = drained_rx.fuse() => {}
+                        _ = cx.background_executor().timer(DIAGNOSTICS_LOG_DRAIN_TIMEOUT).fuse() => {}
+                    }
+                    let tail = diagnostics_log.lock().tail(DIAGNOSTICS_LOG_ERROR_LINES);
+                    // Only job objects keep track of the memory a process
+                    // tree has used, so on Unix the kernel's last output is
+                    // the only sign it ran out.
+                    #[cfg(windows)]
+                    let peak_memory = job.as_ref().and_then(|job| job.peak_memory());
+                    #[cfg(not(windows))]
+                    let peak_memory = None;
+                    let exceeded_limit = resource_limits
+                        .memory
+                        .filter(|_| resource_limits.hit_memory_limit(&tail, peak_memory));
+                    let error_message = match exceeded_limit {
+                        Some(limit) => format!(
+                            "kernel exceeded its {} memory limit",
+                            format_memory_limit(limit)
+                        ),
+                        None => error_message,
+                    };
+                    let error_message = if tail.is_empty() {
+                        error_message
+                    } else {
+                        format!("{error_message}\n\n{tail}")
+                    };
+
+                    session.update(cx, |session, cx| {
+                        match exceeded_limit {
+                            Some(limit) => {
+                                session.kernel_exceeded_memory_limit(limit, error_message, cx)
+                            }
+                            None => session.kernel_errored(error_message, cx),
+                        }
 
-                    cx.notify();
-                });
+                        cx.notify();
+                    });
+                }
             });
 
             anyhow::Ok(Box::new(Self {
                 process,
+                pid,
+                interrupt_mode,
+                interpreter,
                 request_tx,
                 stdin_tx,
                 working_directory,
+                diagnostics_log,
+                _diagnostics_task: Some(diagnostics_task),
                 _process_status_task: Some(process_status_task),
                 connection_path,
+                resource_limits,
                 execution_state: ExecutionState::Idle,
                 kernel_info: None,
             }) as Box<dyn RunningKernel>)
@@ -268,6 +494,16 @@ impl RunningKernel for NativeRunningKernel {
         &self.working_directory
     }
 
+    fn runtime_info(&self) -> KernelRuntimeInfo {
+        KernelRuntimeInfo {
+            working_directory: self.working_directory.clone(),
+            pid: Some(self.pid),
+            interpreter: self.interpreter.clone(),
+            resource_limits: Some(self.resource_limits),
+            ..KernelRuntimeInfo::default()
+        }
+    }
+
     fn execution_state(&self) -> &ExecutionState {
         &self.execution_state
     }
@@ -289,8 +525,38 @@ impl RunningKernel for NativeRunningKernel {
         Task::ready(Ok(()))
     }
 
+    fn diagnostics_log(&self) -> Option<String> {
+        Some(self.diagnostics_log.lock().text())
+    }
+
+    fn interrupt_mode(&self) -> InterruptMode {
+        self.interrupt_mode
+    }
+
+    #[cfg(unix)]
+    fn signal_interrupt(&mut self) -> Result<()> {
+        // The kernel leads its own process group, which is signaled as a whole
+        // like Jupyter does, so code it runs in subprocesses stops too.
+        let result = unsafe { libc::killpg(self.pid as i32, libc::SIGINT) };
+        anyhow::ensure!(
+            result == 0,
+            "failed to send SIGINT to kernel process {}: {}",
+            self.pid,
+            std::io::Error::last_os_error()
+        );
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn signal_interrupt(&mut self) -> Result<()> {
+        use windows::Win32::System::Console::{CTRL_BREAK_EVENT, GenerateConsoleCtrlEvent};
+        unsafe { GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, self.pid) }
+            .with_context(|| format!("failed to send CTRL_BREAK to kernel process {}", self.pid))
+    }
+
     fn kill(&mut self) {
         self._process_status_task.take();
+        self._diagnostics_task.take();
         self.request_tx.close_channel();
         self.stdin_tx.close_channel();
         self.process.kill().ok();
@@ -299,7 +565,14 @@ impl RunningKernel for NativeRunningKernel {
 
 impl Drop for NativeRunningKernel {
     fn drop(&mut self) {
-        std::fs::remove_file(&self.connection_path).ok();
+        if let Err(error) = std::fs::remove_file(&self.connection_path)
+            && error.kind() != std::io::ErrorKind::NotFound
+        {
+            log::warn!(
+                "Failed to remove kernel connection file {:?}: {error}",
+                self.connection_path
+            );
+        }
         self.kill();
     }
 }
@@ -453,4 +726,48 @@ mod test {
             vec!["deno", "python"]
         );
     }
+
+    #[test]
+    fn test_diagnostics_log_keeps_most_recent_output() {
+        let mut log = DiagnosticsLog::default();
+        let line = "x".repeat(DIAGNOSTICS_LOG_CAPACITY / 4);
+        for _ in 0..4 {
+            log.push(line.clone());
+        }
+        log.push("last".to_string());
+
+        assert_eq!(log.lines.len(), 4);
+        assert!(log.len <= DIAGNOSTICS_LOG_CAPACITY);
+        assert_eq!(log.tail(1), "last");
+        assert_eq!(log.tail(2), format!("{line}\nlast"));
+
+        log.push(format!("é{}", "y".repeat(DIAGNOSTICS_LOG_CAPACITY)));
+        assert_eq!(log.lines.len(), 1);
+        assert_eq!(log.text(), "y".repeat(DIAGNOSTICS_LOG_CAPACITY));
+    }
+
+    #[test]
+    fn test_read_pipe_lines() {
+        let mut output = b"first\r\nbinary \xff\xfe\n".to_vec();
+        output.extend(std::iter::repeat_n(b'z', DIAGNOSTICS_LINE_LENGTH * 3));
+        output.extend_from_slice(b"\nlast \xe2\x9c");
+
+        let lines = futures::executor::block_on(
+            read_pipe_lines(futures::io::Cursor::new(output)).collect::<Vec<_>>(),
+        );
+        assert_eq!(lines[0], "first");
+        assert_eq!(lines[1], "binary \u{FFFD}\u{FFFD}");
+        let long_segments = &lines[2..lines.len() - 1];
+        assert!(long_segments.len() >= 3);
+        assert!(
+            long_segments
+                .iter()
+                .all(|segment| segment.len() < DIAGNOSTICS_LINE_LENGTH + PIPE_CHUNK_LENGTH)
+        );
+        assert_eq!(
+            long_segments.concat(),
+            "z".repeat(DIAGNOSTICS_LINE_LENGTH * 3)
+        );
+        assert_eq!(lines.last().map(String::as_str), Some("last \u{FFFD}"));
+    }
 }
@@ -0,0 +1,115 @@
+use std::fmt;
+
+/// The version of the Jupyter messaging protocol a kernel speaks, from the
+/// `protocol_version` of its kernel info.
+///
+/// Requests that rely on behavior newer kernels added are checked against it
+/// with [`ProtocolVersion::supports`] and downgraded for older kernels, which
+/// would otherwise ignore or misread them without saying so.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ProtocolVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+/// Behavior that only kernels speaking a recent enough protocol support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolFeature {
+    /// Aborting the kernel's own queue when an `execute_request` with
+    /// `stop_on_error` fails.
+    StopOnError,
+    /// `debug_request`s, sent over the control channel.
+    DebugRequests,
+}
+
+impl ProtocolFeature {
+    /// The oldest protocol version with the feature.
+    pub fn minimum_version(self) -> ProtocolVersion {
+        match self {
+            Self::StopOnError => ProtocolVersion { major: 5, minor: 2 },
+            Self::DebugRequests => ProtocolVersion { major: 5, minor: 3 },
+        }
+    }
+}
+
+impl ProtocolVersion {
+    /// The oldest version Zed talks to, assumed for kernels whose version
+    /// can't be read.
+    pub const LOWEST: Self = Self { major: 5, minor: 0 };
+
+    /// Parses a `major.minor` version, ignoring any patch component.
+    pub fn parse(version: &str) -> Option<Self> {
+        let mut components = version.trim().split('.');
+        let major = components.next()?.parse().ok()?;
+        let minor = match components.next() {
+            Some(minor) => minor.parse().ok()?,
+            None => 0,
+        };
+        if components.any(|patch| patch.parse::<u32>().is_err()) {
+            return None;
+        }
+        Some(Self { major, minor })
+    }
+
+    /// The version a kernel reported, or [`ProtocolVersion::LOWEST`] with a
+    /// warning when it reported something that isn't a version.
+    pub fn from_kernel_info(version: &str) -> Self {
+        Self::parse(version).unwrap_or_else(|| {
+            log::warn!(
+                "kernel reported an unreadable protocol version {version:?}, assuming {}",
+                Self::LOWEST
+            );
+            Self::LOWEST
+        })
+    }
+
+    pub fn supports(self, feature: ProtocolFeature) -> bool {
+        self >= feature.minimum_version()
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_5_0_kernel() {
+        let version = ProtocolVersion::from_kernel_info("5.0");
+        assert_eq!(version, ProtocolVersion { major: 5, minor: 0 });
+        assert!(!version.supports(ProtocolFeature::StopOnError));
+        assert!(!version.supports(ProtocolFeature::DebugRequests));
+    }
+
+    #[test]
+    fn test_protocol_5_3_kernel() {
+        let version = ProtocolVersion::from_kernel_info("5.3");
+        assert_eq!(version, ProtocolVersion { major: 5, minor: 3 });
+        assert!(version.supports(ProtocolFeature::StopOnError));
+        assert!(version.supports(ProtocolFeature::DebugRequests));
+        assert_eq!(version.to_string(), "5.3");
+
+        assert_eq!(
+            ProtocolVersion::parse("5.3.1"),
+            Some(ProtocolVersion { major: 5, minor: 3 })
+        );
+        assert!(ProtocolVersion::parse("5.10").unwrap() > version);
+    }
+
+    #[test]
+    fn test_unreadable_protocol_version() {
+        for version in ["", "five", "5.x", "5.3-beta", "v5.3"] {
+            assert_eq!(ProtocolVersion::parse(version), None, "{version:?}");
+            assert_eq!(
+                ProtocolVersion::from_kernel_info(version),
+                ProtocolVersion::LOWEST
+            );
+        }
+        assert!(!ProtocolVersion::LOWEST.supports(ProtocolFeature::StopOnError));
+    }
+}
@@ -1,7 +1,10 @@
-use futures::{SinkExt as _, channel::mpsc};
-use gpui::{App, AppContext as _, Entity, Task, Window};
-use http_client::{AsyncBody, HttpClient, Request};
-use jupyter_protocol::{ExecutionState, JupyterKernelspec, JupyterMessage, KernelInfoReply};
+use futures::{FutureExt as _, SinkExt as _, channel::mpsc};
+use gpui::{App, AppContext as _, BackgroundExecutor, Entity, Task, Window};
+use http_client::{AsyncBody, HttpClient, Request, StatusCode};
+use jupyter_protocol::{
+    ErrorOutput, ExecutionState, JupyterKernelspec, JupyterMessage, KernelInfoReply,
+    KernelInfoRequest, Status,
+};
 
 use async_tungstenite::tokio::connect_async;
 use async_tungstenite::tungstenite::{client::IntoClientRequest, http::HeaderValue};
@@ -9,19 +12,42 @@ use async_tungstenite::tungstenite::{client::IntoClientRequest, http::HeaderValu
 use futures::StreamExt;
 use smol::io::AsyncReadExt as _;
 
-use super::{KernelSession, RunningKernel};
-use anyhow::Result;
+use super::{KernelRuntimeInfo, KernelSession, KernelStartupTimer, RunningKernel, route_message};
+use crate::redacted::{Redacted, redact_error};
+use crate::repl_settings::ReplSettings;
+use anyhow::{Context as _, Result};
 use jupyter_websocket_client::{
     JupyterWebSocket, JupyterWebSocketReader, JupyterWebSocketWriter, KernelLaunchRequest,
     KernelSpecsResponse, ProtocolMode, RemoteServer,
 };
-use std::{fmt::Debug, sync::Arc};
+use settings::Settings as _;
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+/// Messages sent while the websocket is down are held until it comes back,
+/// up to this many. Anything beyond that is rejected.
+const MAX_QUEUED_MESSAGES: usize = 32;
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+const QUEUE_FULL_MESSAGE: &str =
+    "The connection to the kernel was lost and too many messages are waiting to be sent.";
+
+pub const REMOTE_KERNEL_GONE_MESSAGE: &str =
+    "The kernel no longer exists on the server. Restart to start a new one.";
 
 #[derive(Debug, Clone)]
 pub struct RemoteKernelSpecification {
     pub name: String,
     pub url: String,
-    pub token: String,
+    pub token: Redacted<String>,
     pub kernelspec: JupyterKernelspec,
 }
 
@@ -95,7 +121,7 @@ pub async fn list_remote_kernelspecs(
         .map(|(name, spec)| RemoteKernelSpecification {
             name,
             url: remote_server.base_url.clone(),
-            token: remote_server.token.clone(),
+            token: Redacted::new(remote_server.token.clone()),
             kernelspec: spec.spec,
         })
         .collect::<Vec<RemoteKernelSpecification>>();
@@ -104,6 +130,154 @@ pub async fn list_remote_kernelspecs(
     Ok(remote_kernelspecs)
 }
 
+/// Deletes kernel `kernel_id` from `remote_server`, which shuts it down.
+async fn delete_remote_kernel(
+    remote_server: &RemoteServer,
+    http_client: Arc<dyn HttpClient>,
+    kernel_id: &str,
+) -> Result<()> {
+    let request = Request::builder()
+        .method("DELETE")
+        .uri(remote_server.api_url(&format!("/kernels/{kernel_id}")))
+        .header("Authorization", format!("token {}", remote_server.token))
+        .body(AsyncBody::default())?;
+
+    let response = http_client.send(request).await?;
+
+    anyhow::ensure!(
+        response.status().is_success(),
+        "Failed to shutdown kernel: {}",
+        response.status()
+    );
+    Ok(())
+}
+
+async fn connect_kernel_channels(
+    remote_server: &RemoteServer,
+    kernel_id: &str,
+) -> Result<(JupyterWebSocketWriter, JupyterWebSocketReader)> {
+    let ws_url = format!(
+        "{}/api/kernels/{}/channels?token={}",
+        remote_server.base_url.replace("http", "ws"),
+        kernel_id,
+        remote_server.token
+    );
+
+    let mut req: Request<()> = ws_url.into_client_request()?;
+    let headers = req.headers_mut();
+
+    headers.insert(
+        "User-Agent",
+        HeaderValue::from_str(&format!(
+            "Zed/{} ({}; {})",
+            "repl",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        ))?,
+    );
+
+    let response = connect_async(req).await;
+
+    let (ws_stream, _response) = response?;
+
+    let kernel_socket = JupyterWebSocket {
+        inner: ws_stream,
+        protocol_mode: ProtocolMode::Json,
+    };
+    Ok(kernel_socket.split())
+}
+
+async fn remote_kernel_exists(
+    remote_server: &RemoteServer,
+    http_client: Arc<dyn HttpClient>,
+    kernel_id: &str,
+) -> Result<bool> {
+    let request = Request::builder()
+        .method("GET")
+        .uri(&remote_server.api_url(&format!("/kernels/{kernel_id}")))
+        .header("Authorization", format!("token {}", remote_server.token))
+        .body(AsyncBody::default())?;
+
+    let response = http_client.send(request).await?;
+    match response.status() {
+        StatusCode::NOT_FOUND => Ok(false),
+        status if status.is_success() => Ok(true),
+        status => anyhow::bail!("Failed to look up kernel: {status}"),
+    }
+}
+
+/// The outcome of one attempt to reconnect to a remote kernel.
+enum ReconnectAttempt<C> {
+    Connected(C),
+    /// The server no longer has the kernel, so there's nothing to reconnect to.
+    Gone,
+    Failed(anyhow::Error),
+}
+
+/// Retries `attempt` with exponential backoff until it connects, the kernel
+/// is gone, or `timeout` has passed. Requests sent while waiting are queued,
+/// up to `MAX_QUEUED_MESSAGES`, and passed to `reject` beyond that. Returns
+/// `None` once nothing can send requests anymore.
+async fn reconnect_with_backoff<C, F>(
+    executor: &BackgroundExecutor,
+    timeout: Duration,
+    request_rx: &mut mpsc::Receiver<JupyterMessage>,
+    queued: &mut VecDeque<JupyterMessage>,
+    mut reject: impl FnMut(&JupyterMessage),
+    mut attempt: impl FnMut() -> F,
+) -> Option<Result<C>>
+where
+    F: Future<Output = ReconnectAttempt<C>>,
+{
+    let deadline = executor.now() + timeout;
+    let mut delay = INITIAL_RECONNECT_DELAY;
+    loop {
+        let mut backoff = executor.timer(delay).fuse();
+        loop {
+            futures::select_biased! {
+                _ = backoff => break,
+                message = request_rx.next() => match message {
+                    Some(message) if queued.len() < MAX_QUEUED_MESSAGES => {
+                        queued.push_back(message);
+                    }
+                    Some(message) => reject(&message),
+                    None => return None,
+                },
+            }
+        }
+
+        match attempt().await {
+            ReconnectAttempt::Connected(connection) => return Some(Ok(connection)),
+            ReconnectAttempt::Gone => {
+                return Some(Err(anyhow::anyhow!(REMOTE_KERNEL_GONE_MESSAGE)));
+            }
+            ReconnectAttempt::Failed(err) => {
+                if executor.now() >= deadline {
+                    return Some(Err(err).context("Lost the connection to the remote kernel"));
+                }
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            }
+        }
+    }
+}
+
+/// The replies a kernel would send for a message it refused, so whatever is
+/// waiting on `message` sees an error and finishes instead of hanging.
+fn rejection_replies(message: &JupyterMessage, reason: &str) -> [JupyterMessage; 2] {
+    [
+        ErrorOutput {
+            ename: "ConnectionError".to_string(),
+            evalue: reason.to_string(),
+            traceback: vec![reason.to_string()],
+        }
+        .as_child_of(message),
+        Status {
+            execution_state: ExecutionState::Idle,
+        }
+        .as_child_of(message),
+    ]
+}
+
 impl PartialEq for RemoteKernelSpecification {
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name && self.url == other.url
@@ -114,15 +288,18 @@ impl Eq for RemoteKernelSpecification {}
 
 pub struct RemoteRunningKernel {
     remote_server: RemoteServer,
-    _receiving_task: Task<Result<()>>,
-    _routing_task: Task<Result<()>>,
+    _connection_task: Task<Result<()>>,
     http_client: Arc<dyn HttpClient>,
+    reconnecting: Arc<AtomicBool>,
     pub working_directory: std::path::PathBuf,
     pub request_tx: mpsc::Sender<JupyterMessage>,
     pub stdin_tx: mpsc::Sender<JupyterMessage>,
     pub execution_state: ExecutionState,
     pub kernel_info: Option<KernelInfoReply>,
     pub kernel_id: String,
+    /// Whether Zed created the kernel on the server, rather than attaching
+    /// to one that was already running.
+    owned: bool,
 }
 
 impl RemoteRunningKernel {
@@ -130,17 +307,24 @@ impl RemoteRunningKernel {
         kernelspec: RemoteKernelSpecification,
         working_directory: std::path::PathBuf,
         session: Entity<S>,
+        startup_timer: KernelStartupTimer,
         window: &mut Window,
         cx: &mut App,
     ) -> Task<Result<Box<dyn RunningKernel>>> {
         let remote_server = RemoteServer {
             base_url: kernelspec.url,
-            token: kernelspec.token,
+            token: kernelspec.token.expose().clone(),
         };
+        // Errors from the server or the websocket can quote the URL the
+        // token is sent in, and launch errors are shown in the session.
+        let token = kernelspec.token;
 
         let http_client = cx.http_client();
+        let reconnect_timeout =
+            Duration::from_secs(ReplSettings::get_global(cx).remote_reconnect_timeout_seconds);
 
-        window.spawn(cx, async move |cx| {
+        let launch = window.spawn(cx, async move |cx| {
+            let creation_started_at = Instant::now();
             let kernel_id = launch_remote_kernel(
                 &remote_server,
                 http_client.clone(),
@@ -148,77 +332,127 @@ impl RemoteRunningKernel {
                 working_directory.to_str().unwrap_or_default(),
             )
             .await?;
+            startup_timer.record(|timing| &mut timing.kernel_creation, creation_started_at);
 
-            let ws_url = format!(
-                "{}/api/kernels/{}/channels?token={}",
-                remote_server.base_url.replace("http", "ws"),
-                kernel_id,
-                remote_server.token
-            );
-
-            let mut req: Request<()> = ws_url.into_client_request()?;
-            let headers = req.headers_mut();
-
-            headers.insert(
-                "User-Agent",
-                HeaderValue::from_str(&format!(
-                    "Zed/{} ({}; {})",
-                    "repl",
-                    std::env::consts::OS,
-                    std::env::consts::ARCH
-                ))?,
-            );
-
-            let response = connect_async(req).await;
-
-            let (ws_stream, _response) = response?;
-
-            let kernel_socket = JupyterWebSocket {
-                inner: ws_stream,
-                protocol_mode: ProtocolMode::Json,
-            };
-            let (mut w, mut r): (JupyterWebSocketWriter, JupyterWebSocketReader) =
-                kernel_socket.split();
+            let connect_started_at = Instant::now();
+            let connection = connect_kernel_channels(&remote_server, &kernel_id).await?;
+            startup_timer.record(|timing| &mut timing.channel_connect, connect_started_at);
 
             let (request_tx, mut request_rx) =
                 futures::channel::mpsc::channel::<JupyterMessage>(100);
+            let reconnecting = Arc::new(AtomicBool::new(false));
 
-            let routing_task = cx.background_spawn({
-                async move {
-                    while let Some(message) = request_rx.next().await {
-                        w.send(message).await.ok();
-                    }
-                    Ok(())
-                }
-            });
-
-            let receiving_task = cx.spawn({
+            let connection_task = cx.spawn({
                 let session = session.clone();
+                let remote_server = RemoteServer {
+                    base_url: remote_server.base_url.clone(),
+                    token: remote_server.token.clone(),
+                };
+                let http_client = http_client.clone();
+                let kernel_id = kernel_id.clone();
+                let reconnecting = reconnecting.clone();
+
+                async move |cx| -> Result<()> {
+                    let (mut w, mut r) = connection;
+                    let mut queued = VecDeque::new();
+
+                    loop {
+                        for message in queued.drain(..) {
+                            w.send(message).await.ok();
+                        }
+
+                        loop {
+                            futures::select! {
+                                message = request_rx.next() => match message {
+                                    Some(message) => {
+                                        if w.send(message.clone()).await.is_err() {
+                                            queued.push_back(message);
+                                            break;
+                                        }
+                                    }
+                                    // The kernel was dropped, nobody is listening anymore
+                                    None => return Ok(()),
+                                },
+                                message = r.next().fuse() => match message {
+                                    Some(Ok(message)) => route_message(&session, &message, cx),
+                                    Some(Err(e)) => {
+                                        log::error!("Error receiving message: {:?}", e);
+                                    }
+                                    None => break,
+                                },
+                            }
+                        }
 
-                async move |cx| {
-                    while let Some(message) = r.next().await {
-                        match message {
-                            Ok(message) => {
-                                session
-                                    .update_in(cx, |session, window, cx| {
-                                        session.route(&message, window, cx);
-                                    })
-                                    .ok();
+                        log::warn!("connection to remote kernel {kernel_id} lost, reconnecting");
+                        reconnecting.store(true, Ordering::SeqCst);
+                        session.update(cx, |_, cx| cx.notify());
+
+                        let executor = cx.background_executor().clone();
+                        let reconnected = reconnect_with_backoff(
+                            &executor,
+                            reconnect_timeout,
+                            &mut request_rx,
+                            &mut queued,
+                            |message| {
+                                for reply in &rejection_replies(message, QUEUE_FULL_MESSAGE) {
+                                    route_message(&session, reply, cx);
+                                }
+                            },
+                            || async {
+                                match connect_kernel_channels(&remote_server, &kernel_id).await {
+                                    Ok(connection) => ReconnectAttempt::Connected(connection),
+                                    Err(err) => {
+                                        log::warn!(
+                                            "failed to reconnect to remote kernel {kernel_id}: {err:?}"
+                                        );
+                                        match remote_kernel_exists(
+                                            &remote_server,
+                                            http_client.clone(),
+                                            &kernel_id,
+                                        )
+                                        .await
+                                        {
+                                            Ok(false) => ReconnectAttempt::Gone,
+                                            _ => ReconnectAttempt::Failed(err),
+                                        }
+                                    }
+                                }
+                            },
+                        )
+                        .await;
+                        // The kernel was dropped while we were reconnecting.
+                        let Some(reconnected) = reconnected else {
+                            return Ok(());
+                        };
+
+                        reconnecting.store(false, Ordering::SeqCst);
+                        match reconnected {
+                            Ok((new_w, new_r)) => {
+                                log::info!("reconnected to remote kernel {kernel_id}");
+                                w = new_w;
+                                r = new_r;
+                                // Messages may have been missed while we were away, so ask the
+                                // kernel where it's at before flushing anything queued.
+                                queued.push_front(KernelInfoRequest {}.into());
+                                session.update(cx, |_, cx| cx.notify());
                             }
-                            Err(e) => {
-                                log::error!("Error receiving message: {:?}", e);
+                            Err(err) => {
+                                let err = redact_error(err, &remote_server.token);
+                                session.update(cx, |session, cx| {
+                                    session.kernel_errored(format!("{err:#}"), cx);
+                                    cx.notify();
+                                });
+                                return Err(err);
                             }
                         }
                     }
-                    Ok(())
                 }
             });
 
             let stdin_tx = request_tx.clone();
 
             anyhow::Ok(Box::new(Self {
-                _routing_task: routing_task,
-                _receiving_task: receiving_task,
+                _connection_task: connection_task,
                 remote_server,
                 working_directory,
                 request_tx,
@@ -228,7 +462,15 @@ impl RemoteRunningKernel {
                 kernel_info: None,
                 kernel_id,
                 http_client: http_client.clone(),
+                reconnecting,
+                // Zed created this kernel through the server's API above.
+                owned: true,
             }) as Box<dyn RunningKernel>)
+        });
+        cx.spawn(async move |_| {
+            launch
+                .await
+                .map_err(|error| redact_error(error, token.expose()))
         })
     }
 }
@@ -259,6 +501,20 @@ impl RunningKernel for RemoteRunningKernel {
         &self.working_directory
     }
 
+    fn set_working_directory(&mut self, working_directory: std::path::PathBuf) {
+        self.working_directory = working_directory;
+    }
+
+    fn runtime_info(&self) -> KernelRuntimeInfo {
+        KernelRuntimeInfo {
+            working_directory: self.working_directory.clone(),
+            kernel_id: Some(self.kernel_id.clone()),
+            server_url: Some(self.remote_server.base_url.clone()),
+            owned: Some(self.owned),
+            ..KernelRuntimeInfo::default()
+        }
+    }
+
     fn execution_state(&self) -> &runtimelib::ExecutionState {
         &self.execution_state
     }
@@ -276,32 +532,162 @@ impl RunningKernel for RemoteRunningKernel {
     }
 
     fn force_shutdown(&mut self, window: &mut Window, cx: &mut App) -> Task<anyhow::Result<()>> {
-        let url = self
-            .remote_server
-            .api_url(&format!("/kernels/{}", self.kernel_id));
-        let token = self.remote_server.token.clone();
+        let remote_server = RemoteServer {
+            base_url: self.remote_server.base_url.clone(),
+            token: self.remote_server.token.clone(),
+        };
         let http_client = self.http_client.clone();
+        let kernel_id = self.kernel_id.clone();
 
         window.spawn(cx, async move |_| {
-            let request = Request::builder()
-                .method("DELETE")
-                .uri(&url)
-                .header("Authorization", format!("token {}", token))
-                .body(AsyncBody::default())?;
-
-            let response = http_client.send(request).await?;
-
-            anyhow::ensure!(
-                response.status().is_success(),
-                "Failed to shutdown kernel: {}",
-                response.status()
-            );
-            Ok(())
+            delete_remote_kernel(&remote_server, http_client, &kernel_id).await
         })
     }
 
+    fn is_owned(&self) -> Option<bool> {
+        Some(self.owned)
+    }
+
+    fn set_owned(&mut self, owned: bool) {
+        self.owned = owned;
+    }
+
+    fn delete_if_owned(&self, cx: &App) -> Option<Task<Result<()>>> {
+        if !self.owned {
+            return None;
+        }
+        let remote_server = RemoteServer {
+            base_url: self.remote_server.base_url.clone(),
+            token: self.remote_server.token.clone(),
+        };
+        let http_client = self.http_client.clone();
+        let kernel_id = self.kernel_id.clone();
+        Some(cx.background_spawn(async move {
+            delete_remote_kernel(&remote_server, http_client, &kernel_id).await
+        }))
+    }
+
     fn kill(&mut self) {
         self.request_tx.close_channel();
         self.stdin_tx.close_channel();
     }
+
+    fn is_reconnecting(&self) -> bool {
+        self.reconnecting.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use std::{cell::RefCell, rc::Rc};
+
+    const TIMEOUT: Duration = Duration::from_secs(20);
+
+    /// Starts reconnecting, with attempts that fail until `attempts_to_fail`
+    /// have been made. Returns when each attempt was made, counted from the
+    /// start, and the outcome.
+    fn start_reconnecting(
+        attempts_to_fail: usize,
+        request_rx: mpsc::Receiver<JupyterMessage>,
+        cx: &mut TestAppContext,
+    ) -> (
+        Rc<RefCell<Vec<Duration>>>,
+        Task<(Option<Result<()>>, VecDeque<JupyterMessage>, usize)>,
+    ) {
+        let executor = cx.executor();
+        let attempt_times = Rc::new(RefCell::new(Vec::new()));
+        let task = cx.spawn({
+            let attempt_times = attempt_times.clone();
+            |_| async move {
+                let mut request_rx = request_rx;
+                let mut queued = VecDeque::new();
+                let mut rejected = 0;
+                let started_at = executor.now();
+                let result = reconnect_with_backoff(
+                    &executor,
+                    TIMEOUT,
+                    &mut request_rx,
+                    &mut queued,
+                    |_| rejected += 1,
+                    || {
+                        let mut attempt_times = attempt_times.borrow_mut();
+                        attempt_times.push(executor.now() - started_at);
+                        let attempt = if attempt_times.len() > attempts_to_fail {
+                            ReconnectAttempt::Connected(())
+                        } else {
+                            ReconnectAttempt::Failed(anyhow::anyhow!("connection refused"))
+                        };
+                        async move { attempt }
+                    },
+                )
+                .await;
+                (result, queued, rejected)
+            }
+        });
+        (attempt_times, task)
+    }
+
+    #[gpui::test]
+    async fn test_reconnect_backs_off_until_timeout(cx: &mut TestAppContext) {
+        let (_request_tx, request_rx) = mpsc::channel(100);
+        let (attempt_times, task) = start_reconnecting(usize::MAX, request_rx, cx);
+
+        for _ in 0..60 {
+            cx.executor().advance_clock(Duration::from_millis(500));
+            cx.run_until_parked();
+        }
+
+        let (result, _, _) = task.await;
+        let error = result.expect("still reconnecting").unwrap_err();
+        assert!(
+            format!("{error:#}").contains("connection refused"),
+            "{error:#}"
+        );
+        assert_eq!(
+            *attempt_times.borrow(),
+            [500, 1_500, 3_500, 7_500, 15_500, 25_500].map(Duration::from_millis)
+        );
+    }
+
+    #[gpui::test]
+    async fn test_reconnect_queues_requests_until_connected(cx: &mut TestAppContext) {
+        let (mut request_tx, request_rx) = mpsc::channel(100);
+        let (attempt_times, task) = start_reconnecting(2, request_rx, cx);
+
+        for _ in 0..MAX_QUEUED_MESSAGES + 3 {
+            request_tx
+                .try_send(KernelInfoRequest {}.into())
+                .expect("channel has room");
+        }
+        for _ in 0..8 {
+            cx.executor().advance_clock(Duration::from_millis(500));
+            cx.run_until_parked();
+        }
+
+        let (result, queued, rejected) = task.await;
+        assert!(matches!(result, Some(Ok(()))));
+        assert_eq!(queued.len(), MAX_QUEUED_MESSAGES);
+        assert_eq!(rejected, 3);
+        assert_eq!(
+            *attempt_times.borrow(),
+            [500, 1_500, 3_500].map(Duration::from_millis)
+        );
+    }
+
+    #[gpui::test]
+    async fn test_reconnect_stops_when_the_kernel_is_dropped(cx: &mut TestAppContext) {
+        let (request_tx, request_rx) = mpsc::channel(100);
+        let (attempt_times, task) = start_reconnecting(usize::MAX, request_rx, cx);
+
+        cx.executor().advance_clock(Duration::from_millis(500));
+        cx.run_until_parked();
+        drop(request_tx);
+        cx.run_until_parked();
+
+        let (result, _, _) = task.await;
+        assert!(result.is_none());
+        assert_eq!(attempt_times.borrow().len(), 1);
+    }
 }
@@ -0,0 +1,340 @@
+use std::fmt;
+
+use settings::KernelResourceLimitsContent;
+
+/// The highest niceness, as with `nice`.
+const MAX_NICENESS: u32 = 19;
+
+/// How close to its memory limit a kernel's peak usage has to come for its
+/// death to be put down to the limit, as a fraction of the limit.
+const MEMORY_LIMIT_HIT_RATIO: f64 = 0.95;
+
+/// What kernels print when an allocation fails: Python's `MemoryError`, libc,
+/// C++'s `std::bad_alloc`, and Rust's allocation failure handler.
+const OUT_OF_MEMORY_MESSAGES: [&str; 5] = [
+    "MemoryError",
+    "Cannot allocate memory",
+    "out of memory",
+    "std::bad_alloc",
+    "memory allocation of",
+];
+
+/// Limits on the resources of a kernel spawned on this machine, from the
+/// `repl.kernel_resource_limits` setting. `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KernelResourceLimits {
+    /// The most memory the kernel may allocate, in bytes.
+    pub memory: Option<u64>,
+    /// How much to lower the kernel's CPU priority, from 1 to 19.
+    pub niceness: Option<u32>,
+    /// The most processes the kernel may start.
+    pub max_processes: Option<u32>,
+}
+
+impl KernelResourceLimits {
+    pub fn from_settings(content: Option<&KernelResourceLimitsContent>) -> Self {
+        let Some(content) = content else {
+            return Self::default();
+        };
+        Self {
+            memory: content
+                .memory_mb
+                .filter(|memory_mb| *memory_mb > 0)
+                .map(|memory_mb| memory_mb.saturating_mul(1024 * 1024)),
+            niceness: content
+                .niceness
+                .filter(|niceness| *niceness > 0)
+                .map(|niceness| niceness.min(MAX_NICENESS)),
+            max_processes: content
+                .max_processes
+                .filter(|max_processes| *max_processes > 0),
+        }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether a kernel that exited abnormally most likely died of running
+    /// into its memory limit, going by the last of its output and the most
+    /// memory it was seen using, where that's tracked.
+    pub fn hit_memory_limit(&self, output_tail: &str, peak_memory: Option<u64>) -> bool {
+        let Some(limit) = self.memory else {
+            return false;
+        };
+        if peak_memory.is_some_and(|peak| peak as f64 >= limit as f64 * MEMORY_LIMIT_HIT_RATIO) {
+            return true;
+        }
+        OUT_OF_MEMORY_MESSAGES
+            .iter()
+            .any(|message| output_tail.contains(message))
+    }
+
+    /// Applies the limits to the kernel process `command` spawns, as it
+    /// starts. The process tree it starts inherits them.
+    #[cfg(unix)]
+    pub fn apply_to_command(&self, command: &mut std::process::Command) {
+        use std::os::unix::process::CommandExt as _;
+
+        if self.is_unlimited() {
+            return;
+        }
+        let limits = *self;
+        // Only async-signal-safe calls are made between fork and exec.
+        unsafe {
+            command.pre_exec(move || {
+                if let Some(memory) = limits.memory {
+                    lower_rlimit(MEMORY_RLIMIT, memory)?;
+                }
+                if let Some(max_processes) = limits.max_processes {
+                    lower_rlimit(libc::RLIMIT_NPROC, max_processes.into())?;
+                }
+                if let Some(niceness) = limits.niceness {
+                    // Zed may already run niced, and setting a niceness below
+                    // the current one needs privileges, so the kernel's is
+                    // raised from Zed's. Lowering a process's own priority
+                    // needs none.
+                    let current = libc::getpriority(libc::PRIO_PROCESS, 0);
+                    let lowered = current
+                        .saturating_add(niceness as libc::c_int)
+                        .min(MAX_NICENESS as libc::c_int);
+                    if lowered > current && libc::setpriority(libc::PRIO_PROCESS, 0, lowered) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+
+    /// Puts the kernel process `pid` in a job object that enforces the
+    /// limits on it and the processes it starts from then on. The limits
+    /// last as long as the returned job is kept.
+    #[cfg(windows)]
+    pub fn apply_to_process(&self, pid: u32) -> anyhow::Result<Option<KernelJob>> {
+        if self.is_unlimited() {
+            return Ok(None);
+        }
+        KernelJob::new(self, pid).map(Some)
+    }
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type RlimitResource = libc::c_int;
+
+/// macOS doesn't enforce `RLIMIT_AS`, but does fail allocations past
+/// `RLIMIT_DATA`.
+#[cfg(target_os = "macos")]
+const MEMORY_RLIMIT: RlimitResource = libc::RLIMIT_DATA;
+#[cfg(all(unix, not(target_os = "macos")))]
+const MEMORY_RLIMIT: RlimitResource = libc::RLIMIT_AS;
+
+/// Lowers the soft limit on `resource` to `limit`, or to the hard limit if
+/// that's lower still.
+#[cfg(unix)]
+fn lower_rlimit(resource: RlimitResource, limit: u64) -> std::io::Result<()> {
+    let mut current = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    if unsafe { libc::getrlimit(resource, &mut current) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let limit = limit as libc::rlim_t;
+    let lowered = libc::rlimit {
+        rlim_cur: if current.rlim_max == libc::RLIM_INFINITY {
+            limit
+        } else {
+            limit.min(current.rlim_max)
+        },
+        rlim_max: current.rlim_max,
+    };
+    if unsafe { libc::setrlimit(resource, &lowered) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// A job object holding a kernel's process tree to its resource limits.
+#[cfg(windows)]
+#[derive(Debug)]
+pub struct KernelJob {
+    handle: windows::Win32::Foundation::HANDLE,
+}
+
+#[cfg(windows)]
+unsafe impl Send for KernelJob {}
+
+#[cfg(windows)]
+impl KernelJob {
+    fn new(limits: &KernelResourceLimits, pid: u32) -> anyhow::Result<Self> {
+        use anyhow::Context as _;
+        use windows::Win32::Foundation::CloseHandle;
+        use windows::Win32::System::JobObjects::{
+            AssignProcessToJobObject, CreateJobObjectW, JOB_OBJECT_LIMIT_ACTIVE_PROCESS,
+            JOB_OBJECT_LIMIT_JOB_MEMORY, JOB_OBJECT_LIMIT_PRIORITY_CLASS,
+            JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JobObjectExtendedLimitInformation,
+            SetInformationJobObject,
+        };
+        use windows::Win32::System::Threading::{
+            BELOW_NORMAL_PRIORITY_CLASS, IDLE_PRIORITY_CLASS, OpenProcess, PROCESS_SET_QUOTA,
+            PROCESS_TERMINATE,
+        };
+        use windows::core::PCWSTR;
+
+        let handle = unsafe { CreateJobObjectW(None, PCWSTR::null()) }
+            .context("failed to create a job object for the kernel")?;
+        let job = Self { handle };
+
+        let mut information = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+        if let Some(memory) = limits.memory {
+            information.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_JOB_MEMORY;
+            information.JobMemoryLimit = usize::try_from(memory).unwrap_or(usize::MAX);
+        }
+        if let Some(max_processes) = limits.max_processes {
+            information.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_ACTIVE_PROCESS;
+            information.BasicLimitInformation.ActiveProcessLimit = max_processes;
+        }
+        if let Some(niceness) = limits.niceness {
+            information.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PRIORITY_CLASS;
+            information.BasicLimitInformation.PriorityClass = if niceness >= 10 {
+                IDLE_PRIORITY_CLASS.0
+            } else {
+                BELOW_NORMAL_PRIORITY_CLASS.0
+            };
+        }
+        unsafe {
+            SetInformationJobObject(
+                job.handle,
+                JobObjectExtendedLimitInformation,
+                &information as *const _ as *const std::ffi::c_void,
+                size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            )
+        }
+        .context("failed to set the kernel's resource limits")?;
+
+        let process = unsafe { OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, false, pid) }
+            .with_context(|| format!("failed to open kernel process {pid}"))?;
+        let assigned = unsafe { AssignProcessToJobObject(job.handle, process) };
+        unsafe { CloseHandle(process) }.ok();
+        assigned
+            .with_context(|| format!("failed to limit the resources of kernel process {pid}"))?;
+
+        Ok(job)
+    }
+
+    /// The most memory the processes in the job have used at once, in bytes.
+    pub fn peak_memory(&self) -> Option<u64> {
+        use windows::Win32::System::JobObjects::{
+            JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JobObjectExtendedLimitInformation,
+            QueryInformationJobObject,
+        };
+
+        let mut information = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+        unsafe {
+            QueryInformationJobObject(
+                Some(self.handle),
+                JobObjectExtendedLimitInformation,
+                &mut information as *mut _ as *mut std::ffi::c_void,
+                size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                None,
+            )
+        }
+        .ok()?;
+        Some(information.PeakJobMemoryUsed as u64)
+    }
+}
+
+#[cfg(windows)]
+impl Drop for KernelJob {
+    fn drop(&mut self) {
+        unsafe { windows::Win32::Foundation::CloseHandle(self.handle) }.ok();
+    }
+}
+
+/// A memory limit in bytes as whole gigabytes where it is one, such as
+/// "4 GB", and as megabytes otherwise.
+pub fn format_memory_limit(bytes: u64) -> String {
+    const MEGABYTE: u64 = 1024 * 1024;
+    const GIGABYTE: u64 = 1024 * MEGABYTE;
+    if bytes >= GIGABYTE && bytes % GIGABYTE == 0 {
+        format!("{} GB", bytes / GIGABYTE)
+    } else {
+        format!("{} MB", bytes / MEGABYTE)
+    }
+}
+
+impl fmt::Display for KernelResourceLimits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_unlimited() {
+            return f.write_str("unlimited");
+        }
+        let mut parts = Vec::new();
+        if let Some(memory) = self.memory {
+            parts.push(format!("{} memory", format_memory_limit(memory)));
+        }
+        if let Some(niceness) = self.niceness {
+            parts.push(format!("nice {niceness}"));
+        }
+        if let Some(max_processes) = self.max_processes {
+            parts.push(format!("{max_processes} processes"));
+        }
+        f.write_str(&parts.join(" · "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_from_settings() {
+        assert!(KernelResourceLimits::from_settings(None).is_unlimited());
+
+        let zeros = KernelResourceLimitsContent {
+            memory_mb: Some(0),
+            niceness: Some(0),
+            max_processes: Some(0),
+        };
+        let limits = KernelResourceLimits::from_settings(Some(&zeros));
+        assert!(limits.is_unlimited());
+        assert_eq!(limits.to_string(), "unlimited");
+
+        let content = KernelResourceLimitsContent {
+            memory_mb: Some(4096),
+            niceness: Some(40),
+            max_processes: Some(64),
+        };
+        let limits = KernelResourceLimits::from_settings(Some(&content));
+        assert_eq!(
+            limits,
+            KernelResourceLimits {
+                memory: Some(4 * 1024 * 1024 * 1024),
+                niceness: Some(19),
+                max_processes: Some(64),
+            }
+        );
+        assert_eq!(limits.to_string(), "4 GB memory · nice 19 · 64 processes");
+        assert_eq!(format_memory_limit(1536 * 1024 * 1024), "1536 MB");
+    }
+
+    #[test]
+    fn test_hit_memory_limit() {
+        let limits = KernelResourceLimits {
+            memory: Some(1024 * 1024 * 1024),
+            ..KernelResourceLimits::default()
+        };
+        assert!(limits.hit_memory_limit("Traceback...\nMemoryError", None));
+        assert!(limits.hit_memory_limit(
+            "terminate called after throwing an instance of 'std::bad_alloc'",
+            None
+        ));
+        assert!(limits.hit_memory_limit("", Some(1020 * 1024 * 1024)));
+        assert!(!limits.hit_memory_limit("Segmentation fault", Some(512 * 1024 * 1024)));
+
+        // Without a memory limit, running out of memory isn't the limit's doing.
+        assert!(!KernelResourceLimits::default().hit_memory_limit("MemoryError", None));
+    }
+}
@@ -0,0 +1,181 @@
+use std::time::Instant;
+
+use collections::{HashMap, HashSet};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+/// How many samples of a kernel's resource usage a session keeps.
+pub const RESOURCE_HISTORY_LEN: usize = 60;
+
+/// The memory and CPU used by a kernel's process and every process it
+/// started, such as multiprocessing workers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResourceSample {
+    /// Resident memory, in bytes.
+    pub memory_bytes: u64,
+    /// CPU usage since the previous sample, where 100 is one core.
+    pub cpu_percent: f32,
+    pub sampled_at: Instant,
+}
+
+/// One process's usage, as read from the system.
+#[derive(Debug, Clone, Copy)]
+struct ProcessUsage {
+    pid: Pid,
+    parent: Option<Pid>,
+    memory_bytes: u64,
+    cpu_percent: f32,
+}
+
+/// Samples the resource usage of a kernel process and its descendants.
+pub struct ResourceSampler {
+    system: System,
+    root: Pid,
+}
+
+impl ResourceSampler {
+    pub fn new(pid: u32) -> Self {
+        Self {
+            system: System::new(),
+            root: Pid::from_u32(pid),
+        }
+    }
+
+    /// Reads the current usage, or `None` once the kernel's process has
+    /// exited. CPU usage is measured since the previous call, so it's 0 the
+    /// first time. This reads every process on the system, so it shouldn't
+    /// run on the main thread.
+    pub fn sample(&mut self) -> Option<ResourceSample> {
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing()
+                .without_tasks()
+                .with_memory()
+                .with_cpu(),
+        );
+        let processes = self
+            .system
+            .processes()
+            .iter()
+            .map(|(&pid, process)| ProcessUsage {
+                pid,
+                parent: process.parent(),
+                memory_bytes: process.memory(),
+                cpu_percent: process.cpu_usage(),
+            })
+            .collect::<Vec<_>>();
+        let (memory_bytes, cpu_percent) = total_tree_usage(self.root, &processes)?;
+        Some(ResourceSample {
+            memory_bytes,
+            cpu_percent,
+            sampled_at: Instant::now(),
+        })
+    }
+}
+
+/// Adds up the memory and CPU of `root` and its descendants, or `None` if
+/// `root` isn't running. Processes can exit between being listed and being
+/// read, so parents that are missing are simply skipped.
+fn total_tree_usage(root: Pid, processes: &[ProcessUsage]) -> Option<(u64, f32)> {
+    let root_usage = processes.iter().find(|process| process.pid == root)?;
+
+    let mut children = HashMap::<Pid, Vec<&ProcessUsage>>::default();
+    for process in processes {
+        if let Some(parent) = process.parent
+            && parent != process.pid
+        {
+            children.entry(parent).or_default().push(process);
+        }
+    }
+
+    let mut memory_bytes = root_usage.memory_bytes;
+    let mut cpu_percent = root_usage.cpu_percent;
+    let mut visited = HashSet::from_iter([root]);
+    let mut pending = vec![root];
+    while let Some(pid) = pending.pop() {
+        for child in children.get(&pid).into_iter().flatten() {
+            if visited.insert(child.pid) {
+                memory_bytes += child.memory_bytes;
+                cpu_percent += child.cpu_percent;
+                pending.push(child.pid);
+            }
+        }
+    }
+    Some((memory_bytes, cpu_percent))
+}
+
+/// A size in bytes as megabytes or gigabytes, such as "512.0 MB".
+pub fn format_memory(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 * 1024 {
+        format!("{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, parent: Option<u32>, memory_bytes: u64) -> ProcessUsage {
+        ProcessUsage {
+            pid: Pid::from_u32(pid),
+            parent: parent.map(Pid::from_u32),
+            memory_bytes,
+            cpu_percent: 10.0,
+        }
+    }
+
+    #[test]
+    fn test_total_tree_usage() {
+        let processes = [
+            process(1, None, 1000),
+            process(10, Some(1), 100),
+            process(11, Some(10), 20),
+            process(12, Some(10), 30),
+            process(13, Some(11), 5),
+            // Not descended from the kernel.
+            process(20, Some(1), 7000),
+            // A worker whose parent exited, reparented elsewhere.
+            process(30, Some(99), 9000),
+        ];
+
+        assert_eq!(
+            total_tree_usage(Pid::from_u32(10), &processes),
+            Some((155, 40.0))
+        );
+        assert_eq!(
+            total_tree_usage(Pid::from_u32(13), &processes),
+            Some((5, 10.0))
+        );
+        // The kernel itself exited between samples.
+        assert_eq!(total_tree_usage(Pid::from_u32(40), &processes), None);
+    }
+
+    #[test]
+    fn test_total_tree_usage_with_parent_loop() {
+        let processes = [
+            process(10, Some(12), 100),
+            process(11, Some(10), 20),
+            process(12, Some(11), 30),
+        ];
+        assert_eq!(
+            total_tree_usage(Pid::from_u32(10), &processes),
+            Some((150, 30.0))
+        );
+    }
+
+    #[test]
+    fn test_sample_current_process() {
+        let mut sampler = ResourceSampler::new(std::process::id());
+        let sample = sampler.sample().unwrap();
+        assert!(sample.memory_bytes > 0);
+        assert!(sampler.sample().is_some());
+    }
+
+    #[test]
+    fn test_format_memory() {
+        assert_eq!(format_memory(512 * 1024 * 1024), "512.0 MB");
+        assert_eq!(format_memory(3 * 1024 * 1024 * 1024 / 2), "1.5 GB");
+    }
+}
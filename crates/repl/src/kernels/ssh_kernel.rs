@@ -1,4 +1,7 @@
-use super::{KernelSession, RunningKernel, SshRemoteKernelSpecification, start_kernel_tasks};
+use super::{
+    KernelRuntimeInfo, KernelSession, KernelStartupTimer, RunningKernel,
+    SshRemoteKernelSpecification, start_kernel_tasks,
+};
 use anyhow::{Context as _, Result};
 use client::proto;
 
@@ -10,7 +13,7 @@ use futures::{
 use gpui::{App, Entity, Task, Window};
 use project::Project;
 use runtimelib::{ExecutionState, JupyterMessage, KernelInfoReply};
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Instant};
 use util::ResultExt;
 
 #[derive(Debug)]
@@ -20,6 +23,7 @@ pub struct SshRunningKernel {
     execution_state: ExecutionState,
     kernel_info: Option<KernelInfoReply>,
     working_directory: PathBuf,
+    interpreter: Option<String>,
     _ssh_tunnel_process: util::command::Child,
     _local_connection_file: PathBuf,
     kernel_id: String,
@@ -33,6 +37,7 @@ impl SshRunningKernel {
         working_directory: PathBuf,
         project: Entity<Project>,
         session: Entity<S>,
+        startup_timer: KernelStartupTimer,
         window: &mut Window,
         cx: &mut App,
     ) -> Task<Result<Box<dyn RunningKernel>>> {
@@ -44,6 +49,7 @@ impl SshRunningKernel {
             .unwrap_or(proto::REMOTE_SERVER_PROJECT_ID);
 
         window.spawn(cx, async move |cx| {
+            let spawn_started_at = Instant::now();
             let command = kernel_spec
                 .kernelspec
                 .argv
@@ -77,6 +83,8 @@ impl SshRunningKernel {
             let kernel_id = response.kernel_id.clone();
             let connection_info: serde_json::Value =
                 serde_json::from_str(&response.connection_file)?;
+            startup_timer.record(|timing| &mut timing.process_spawn, spawn_started_at);
+            let connect_started_at = Instant::now();
 
             // Setup SSH Tunneling - allocate local ports
             let mut local_ports = Vec::new();
@@ -236,6 +244,7 @@ impl SshRunningKernel {
             )
             .await
             .context("failed to create stdin connection")?;
+            startup_timer.record(|timing| &mut timing.channel_connect, connect_started_at);
 
             let (request_tx, stdin_tx) = start_kernel_tasks(
                 session.clone(),
@@ -252,6 +261,7 @@ impl SshRunningKernel {
                 execution_state: ExecutionState::Idle,
                 kernel_info: None,
                 working_directory,
+                interpreter: kernel_spec.kernelspec.argv.first().cloned(),
                 _ssh_tunnel_process: ssh_tunnel_process,
                 _local_connection_file: local_connection_file,
                 kernel_id,
@@ -275,6 +285,15 @@ impl RunningKernel for SshRunningKernel {
         &self.working_directory
     }
 
+    fn runtime_info(&self) -> KernelRuntimeInfo {
+        KernelRuntimeInfo {
+            working_directory: self.working_directory.clone(),
+            interpreter: self.interpreter.clone(),
+            kernel_id: Some(self.kernel_id.clone()),
+            ..KernelRuntimeInfo::default()
+        }
+    }
+
     fn execution_state(&self) -> &ExecutionState {
         &self.execution_state
     }
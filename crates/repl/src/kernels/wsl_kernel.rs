@@ -1,6 +1,6 @@
 use super::{
-    KernelSession, KernelSpecification, RunningKernel, WslKernelSpecification,
-    build_python_exec_shell_script, start_kernel_tasks,
+    KernelRuntimeInfo, KernelSession, KernelSpecification, KernelStartupTimer, RunningKernel,
+    WslKernelSpecification, build_python_exec_shell_script, start_kernel_tasks,
 };
 use anyhow::{Context as _, Result};
 use futures::{
@@ -21,6 +21,7 @@ use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
     path::PathBuf,
     sync::Arc,
+    time::Instant,
 };
 
 use uuid::Uuid;
@@ -41,6 +42,7 @@ async fn peek_ports(ip: IpAddr) -> Result<[u16; 5]> {
 
 pub struct WslRunningKernel {
     pub process: util::command::Child,
+    interpreter: Option<String>,
     connection_path: PathBuf,
     _process_status_task: Option<Task<()>>,
     pub working_directory: PathBuf,
@@ -74,10 +76,12 @@ impl WslRunningKernel {
         working_directory: PathBuf,
         fs: Arc<dyn Fs>,
         session: Entity<S>,
+        startup_timer: KernelStartupTimer,
         window: &mut Window,
         cx: &mut App,
     ) -> Task<Result<Box<dyn RunningKernel>>> {
         window.spawn(cx, async move |cx| {
+            let spawn_started_at = Instant::now();
             // For WSL2, we need to get the WSL VM's IP address to connect to it
             // because WSL2 runs in a lightweight VM with its own network namespace.
             // The kernel will bind to 127.0.0.1 inside WSL, and we connect to localhost.
@@ -321,6 +325,8 @@ impl WslRunningKernel {
                 Ok(None) => {}
                 Err(_) => {}
             }
+            startup_timer.record(|timing| &mut timing.process_spawn, spawn_started_at);
+            let connect_started_at = Instant::now();
 
             let output_socket = runtimelib::create_client_iopub_connection(
                 &client_connection_info,
@@ -348,6 +354,7 @@ impl WslRunningKernel {
                 peer_identity,
             )
             .await?;
+            startup_timer.record(|timing| &mut timing.channel_connect, connect_started_at);
 
             let (request_tx, stdin_tx) = start_kernel_tasks(
                 session.clone(),
@@ -405,6 +412,7 @@ impl WslRunningKernel {
 
             anyhow::Ok(Box::new(Self {
                 process,
+                interpreter: kernel_specification.kernelspec.argv.first().cloned(),
                 request_tx,
                 stdin_tx,
                 working_directory,
@@ -430,6 +438,14 @@ impl RunningKernel for WslRunningKernel {
         &self.working_directory
     }
 
+    fn runtime_info(&self) -> KernelRuntimeInfo {
+        KernelRuntimeInfo {
+            working_directory: self.working_directory.clone(),
+            interpreter: self.interpreter.clone(),
+            ..KernelRuntimeInfo::default()
+        }
+    }
+
     fn execution_state(&self) -> &ExecutionState {
         &self.execution_state
     }
@@ -556,6 +572,7 @@ pub async fn wsl_kernel_specifications(
                             .map(|(name, spec)| {
                                 KernelSpecification::WslRemote(WslKernelSpecification {
                                     name,
+                                    display_name: spec.spec.display_name.clone(),
                                     kernelspec: jupyter_protocol::JupyterKernelspec {
                                         argv: spec.spec.argv,
                                         display_name: spec.spec.display_name,
@@ -10,7 +10,7 @@ use gpui::{
 use language::{Buffer, Language, LanguageRegistry};
 use markdown::{Markdown, MarkdownElement, MarkdownStyle};
 use nbformat::v4::{CellId, CellMetadata, CellType};
-use runtimelib::{JupyterMessage, JupyterMessageContent};
+use runtimelib::{JupyterMessage, JupyterMessageContent, MimeBundle};
 use settings::Settings as _;
 use theme_settings::ThemeSettings;
 use ui::{CommonAnimationExt, IconButtonShape, prelude::*};
@@ -18,7 +18,10 @@ use util::ResultExt;
 
 use crate::{
     notebook::{CODE_BLOCK_INSET, GUTTER_WIDTH},
-    outputs::{Output, plain, plain::TerminalOutput, user_error::ErrorView},
+    outputs::{
+        self, HtmlTrust, Output, plain, plain::TerminalOutput, stream::StreamLimiter,
+        user_error::ErrorView,
+    },
     repl_settings::ReplSettings,
 };
 
@@ -103,6 +106,8 @@ pub(crate) enum MovementDirection {
     End,
 }
 
+/// Outputs saved in a notebook could have come from any kernel, so their
+/// HTML is always sanitized.
 fn convert_outputs(
     outputs: &Vec<nbformat::v4::Output>,
     window: &mut Window,
@@ -115,16 +120,17 @@ fn convert_outputs(
                 content: cx.new(|cx| TerminalOutput::from(&text.0, window, cx)),
             },
             nbformat::v4::Output::DisplayData(display_data) => {
-                Output::new(&display_data.data, None, window, cx)
+                Output::new(&display_data.data, None, HtmlTrust::Untrusted, window, cx)
             }
             nbformat::v4::Output::ExecuteResult(execute_result) => {
-                Output::new(&execute_result.data, None, window, cx)
+                Output::new(&execute_result.data, None, HtmlTrust::Untrusted, window, cx)
             }
             nbformat::v4::Output::Error(error) => Output::ErrorOutput(ErrorView {
                 ename: error.ename.clone(),
                 evalue: error.evalue.clone(),
                 traceback: cx
                     .new(|cx| TerminalOutput::from(&error.traceback.join("\n"), window, cx)),
+                interrupted: false,
             }),
         })
         .collect()
@@ -652,6 +658,7 @@ pub struct CodeCell {
     source: String,
     editor: Entity<editor::Editor>,
     outputs: Vec<Output>,
+    stream_limiter: StreamLimiter,
     selected: bool,
     cell_position: Option<CellPosition>,
     _language_task: Task<()>,
@@ -744,6 +751,7 @@ impl CodeCell {
             source,
             editor,
             outputs,
+            stream_limiter: StreamLimiter::default(),
             selected: false,
             cell_position: None,
             execution_start_time: None,
@@ -810,12 +818,14 @@ impl CodeCell {
 
     pub fn clear_outputs(&mut self) {
         self.outputs.clear();
+        self.stream_limiter = StreamLimiter::default();
         self.execution_duration = None;
     }
 
     pub fn start_execution(&mut self) {
         self.execution_start_time = Some(Instant::now());
         self.execution_duration = None;
+        self.stream_limiter = StreamLimiter::default();
         self.is_executing = true;
     }
 
@@ -824,6 +834,11 @@ impl CodeCell {
             self.execution_duration = Some(start_time.elapsed());
         }
         self.is_executing = false;
+        // No more outputs are coming to replace what a waiting clear would
+        // have cleared, so they stay.
+        if let Some(Output::ClearOutputWaitMarker) = self.outputs.last() {
+            self.outputs.pop();
+        }
     }
 
     pub fn is_executing(&self) -> bool {
@@ -850,22 +865,53 @@ impl CodeCell {
     pub fn handle_message(
         &mut self,
         message: &JupyterMessage,
+        html_trust: HtmlTrust,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
         match &message.content {
             JupyterMessageContent::StreamContent(stream) => {
-                self.outputs.push(Output::Stream {
-                    content: cx.new(|cx| TerminalOutput::from(&stream.text, window, cx)),
-                });
+                let text = self.stream_limiter.limit(&stream.text);
+                if text.is_empty() {
+                    return;
+                }
+                if let Some(Output::Stream { content }) = self.outputs.last() {
+                    content.update(cx, |content, cx| {
+                        content.append_text(&text, cx);
+                        cx.notify();
+                    });
+                } else {
+                    outputs::push_output(
+                        &mut self.outputs,
+                        Output::Stream {
+                            content: cx.new(|cx| TerminalOutput::from(&text, window, cx)),
+                        },
+                    );
+                }
             }
             JupyterMessageContent::DisplayData(display_data) => {
-                self.outputs
-                    .push(Output::new(&display_data.data, None, window, cx));
+                let display_id = display_data
+                    .transient
+                    .as_ref()
+                    .and_then(|transient| transient.display_id.clone());
+                outputs::push_output(
+                    &mut self.outputs,
+                    Output::new(&display_data.data, display_id, html_trust, window, cx),
+                );
             }
             JupyterMessageContent::ExecuteResult(execute_result) => {
-                self.outputs
-                    .push(Output::new(&execute_result.data, None, window, cx));
+                let display_id = execute_result
+                    .transient
+                    .as_ref()
+                    .and_then(|transient| transient.display_id.clone());
+                outputs::push_output(
+                    &mut self.outputs,
+                    Output::new(&execute_result.data, display_id, html_trust, window, cx),
+                );
+            }
+            JupyterMessageContent::ClearOutput(options) => {
+                outputs::clear_outputs(&mut self.outputs, options.wait);
+                self.stream_limiter = StreamLimiter::default();
             }
             JupyterMessageContent::ExecuteInput(input) => {
                 self.execution_count = serde_json::to_value(&input.execution_count)
@@ -877,18 +923,39 @@ impl CodeCell {
                 self.finish_execution();
             }
             JupyterMessageContent::ErrorOutput(error) => {
-                self.outputs.push(Output::ErrorOutput(ErrorView {
-                    ename: error.ename.clone(),
-                    evalue: error.evalue.clone(),
-                    traceback: cx
-                        .new(|cx| TerminalOutput::from(&error.traceback.join("\n"), window, cx)),
-                }));
+                outputs::push_output(
+                    &mut self.outputs,
+                    Output::ErrorOutput(ErrorView {
+                        ename: error.ename.clone(),
+                        evalue: error.evalue.clone(),
+                        traceback: cx.new(|cx| {
+                            TerminalOutput::from(&error.traceback.join("\n"), window, cx)
+                        }),
+                        interrupted: false,
+                    }),
+                );
             }
             _ => {}
         }
         cx.notify();
     }
 
+    /// Replaces this cell's outputs shown under `display_id`, which may have
+    /// been created by a different cell's execution.
+    pub fn update_display_data(
+        &mut self,
+        data: &MimeBundle,
+        display_id: &str,
+        html_trust: HtmlTrust,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if outputs::update_display_data(&mut self.outputs, data, display_id, html_trust, window, cx)
+        {
+            cx.notify();
+        }
+    }
+
     pub fn gutter_output(&self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let is_selected = self.selected();
 
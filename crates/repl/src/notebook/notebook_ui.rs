@@ -17,7 +17,7 @@ use jupyter_protocol::JupyterKernelspec;
 use language::{Language, LanguageRegistry};
 use log;
 use project::{Project, ProjectEntryId, ProjectPath};
-use settings::Settings as _;
+use settings::{Settings as _, SettingsLocation};
 use ui::{CommonAnimationExt, Tooltip, prelude::*};
 use workspace::item::{ItemEvent, SaveOptions, TabContentParams};
 use workspace::searchable::SearchableItemHandle;
@@ -32,10 +32,13 @@ use uuid::Uuid;
 
 use crate::components::{KernelPickerDelegate, KernelSelector};
 use crate::kernels::{
-    Kernel, KernelSession, KernelSpecification, KernelStatus, LocalKernelSpecification,
-    NativeRunningKernel, RemoteRunningKernel, SshRunningKernel, WslRunningKernel,
+    Kernel, KernelSession, KernelSpecification, KernelStartupTimer, KernelStatus,
+    LocalKernelSpecification, NativeRunningKernel, RemoteRunningKernel, SshRunningKernel,
+    WslRunningKernel,
 };
 use crate::notebook::MovementDirection;
+use crate::outputs::HtmlTrust;
+use crate::repl_settings::ReplSettings;
 use crate::repl_store::ReplStore;
 
 use picker::Picker;
@@ -104,6 +107,8 @@ pub struct NotebookEditor {
     kernel: Kernel,
     kernel_specification: Option<KernelSpecification>,
     execution_requests: HashMap<String, CellId>,
+    /// Messages for cells that were handled but not shown yet.
+    unpresented_messages: Vec<JupyterMessage>,
     kernel_picker_handle: PopoverMenuHandle<Picker<KernelPickerDelegate>>,
 }
 
@@ -213,6 +218,7 @@ impl NotebookEditor {
             kernel: Kernel::Shutdown,
             kernel_specification: None,
             execution_requests: HashMap::default(),
+            unpresented_messages: Vec::new(),
             kernel_picker_handle: PopoverMenuHandle::default(),
         };
         editor.launch_kernel(window, cx);
@@ -233,7 +239,7 @@ impl NotebookEditor {
         let worktree_id = self.worktree_id;
 
         let refresh_task = store.update(cx, |store, cx| {
-            store.refresh_python_kernelspecs(worktree_id, &project, cx)
+            store.refresh_python_kernelspecs(Some(worktree_id), &project, cx)
         });
 
         cx.background_spawn(refresh_task).detach_and_log_err(cx);
@@ -333,7 +339,7 @@ impl NotebookEditor {
         let spec = self.kernel_specification.clone().or_else(|| {
             ReplStore::global(cx)
                 .read(cx)
-                .active_kernelspec(self.worktree_id, None, cx)
+                .active_kernelspec(Some(self.worktree_id), None, cx)
         });
 
         let spec = spec.unwrap_or_else(|| {
@@ -375,6 +381,15 @@ impl NotebookEditor {
             .unwrap_or_else(std::env::temp_dir);
         let fs = self.project.read(cx).fs().clone();
         let view = cx.entity();
+        let project_path = self.notebook_item.read(cx).project_path.clone();
+        let resource_limits = ReplSettings::get(
+            Some(SettingsLocation {
+                worktree_id: project_path.worktree_id,
+                path: &project_path.path,
+            }),
+            cx,
+        )
+        .kernel_resource_limits;
 
         self.kernel_specification = Some(spec.clone());
 
@@ -382,13 +397,7 @@ impl NotebookEditor {
             let kernel_name = spec.name().to_string();
             let language = spec.language().to_string();
 
-            let display_name = match &spec {
-                KernelSpecification::Jupyter(s) => s.kernelspec.display_name.clone(),
-                KernelSpecification::PythonEnv(s) => s.kernelspec.display_name.clone(),
-                KernelSpecification::JupyterServer(s) => s.kernelspec.display_name.clone(),
-                KernelSpecification::SshRemote(s) => s.kernelspec.display_name.clone(),
-                KernelSpecification::WslRemote(s) => s.kernelspec.display_name.clone(),
-            };
+            let display_name = spec.kernelspec().display_name.clone();
 
             let kernelspec_json = serde_json::json!({
                 "display_name": display_name,
@@ -402,6 +411,7 @@ impl NotebookEditor {
             }
         });
 
+        let startup_timer = KernelStartupTimer::default();
         let kernel_task = match spec {
             KernelSpecification::Jupyter(local_spec) => NativeRunningKernel::new(
                 local_spec,
@@ -409,6 +419,8 @@ impl NotebookEditor {
                 working_directory,
                 fs,
                 view,
+                startup_timer.clone(),
+                resource_limits,
                 window,
                 cx,
             ),
@@ -418,20 +430,42 @@ impl NotebookEditor {
                 working_directory,
                 fs,
                 view,
+                startup_timer.clone(),
+                resource_limits,
+                window,
+                cx,
+            ),
+            KernelSpecification::JupyterServer(remote_spec) => RemoteRunningKernel::new(
+                remote_spec,
+                working_directory,
+                view,
+                startup_timer.clone(),
                 window,
                 cx,
             ),
-            KernelSpecification::JupyterServer(remote_spec) => {
-                RemoteRunningKernel::new(remote_spec, working_directory, view, window, cx)
-            }
 
             KernelSpecification::SshRemote(spec) => {
                 let project = self.project.clone();
-                SshRunningKernel::new(spec, working_directory, project, view, window, cx)
-            }
-            KernelSpecification::WslRemote(spec) => {
-                WslRunningKernel::new(spec, entity_id, working_directory, fs, view, window, cx)
+                SshRunningKernel::new(
+                    spec,
+                    working_directory,
+                    project,
+                    view,
+                    startup_timer.clone(),
+                    window,
+                    cx,
+                )
             }
+            KernelSpecification::WslRemote(spec) => WslRunningKernel::new(
+                spec,
+                entity_id,
+                working_directory,
+                fs,
+                view,
+                startup_timer.clone(),
+                window,
+                cx,
+            ),
         };
 
         let pending_kernel = cx
@@ -449,7 +483,10 @@ impl NotebookEditor {
                     Err(err) => {
                         log::error!("Kernel failed to start: {:?}", err);
                         this.update(cx, |editor, cx| {
-                            editor.kernel = Kernel::ErroredLaunch(err.to_string());
+                            editor.kernel = Kernel::ErroredLaunch(
+                                err.to_string(),
+                                Some(startup_timer.timing()),
+                            );
                             cx.notify();
                         })
                         .ok();
@@ -478,6 +515,7 @@ impl NotebookEditor {
         }
 
         self.execution_requests.clear();
+        self.unpresented_messages.clear();
 
         self.launch_kernel_with_spec(spec, window, cx);
     }
@@ -1108,8 +1146,12 @@ impl NotebookEditor {
                             KernelStatus::Starting => (IconName::ReplNeutral, Color::Muted),
                             KernelStatus::Error => (IconName::ReplNeutral, Color::Error),
                             KernelStatus::ShuttingDown => (IconName::ReplNeutral, Color::Muted),
-                            KernelStatus::Shutdown => (IconName::ReplNeutral, Color::Disabled),
-                            KernelStatus::Restarting => (IconName::ReplNeutral, Color::Warning),
+                            KernelStatus::Shutdown | KernelStatus::Culled => {
+                                (IconName::ReplNeutral, Color::Disabled)
+                            }
+                            KernelStatus::Restarting | KernelStatus::Reconnecting => {
+                                (IconName::ReplNeutral, Color::Warning)
+                            }
                         };
                         let kernel_name = self
                             .kernel_specification
@@ -1150,8 +1192,10 @@ impl NotebookEditor {
             KernelStatus::Starting => (IconName::ArrowCircle, Color::Muted),
             KernelStatus::Error => (IconName::XCircle, Color::Error),
             KernelStatus::ShuttingDown => (IconName::ArrowCircle, Color::Muted),
-            KernelStatus::Shutdown => (IconName::Circle, Color::Muted),
-            KernelStatus::Restarting => (IconName::ArrowCircle, Color::Warning),
+            KernelStatus::Shutdown | KernelStatus::Culled => (IconName::Circle, Color::Muted),
+            KernelStatus::Restarting | KernelStatus::Reconnecting => {
+                (IconName::ArrowCircle, Color::Warning)
+            }
         };
 
         let is_spinning = matches!(
@@ -1160,6 +1204,7 @@ impl NotebookEditor {
                 | KernelStatus::Starting
                 | KernelStatus::ShuttingDown
                 | KernelStatus::Restarting
+                | KernelStatus::Reconnecting
         );
 
         let status_icon_element = if is_spinning {
@@ -1196,7 +1241,7 @@ impl NotebookEditor {
                             });
                         }
                     }),
-                    worktree_id,
+                    Some(worktree_id),
                     Button::new("kernel-selector", kernel_name.clone())
                         .label_size(LabelSize::Small)
                         .start_icon(
@@ -1866,7 +1911,7 @@ impl ProjectItem for NotebookEditor {
 }
 
 impl KernelSession for NotebookEditor {
-    fn route(&mut self, message: &JupyterMessage, window: &mut Window, cx: &mut Context<Self>) {
+    fn handle_message(&mut self, message: &JupyterMessage, cx: &mut Context<Self>) {
         // Handle kernel status updates (these are broadcast to all)
         if let JupyterMessageContent::Status(status) = &message.content {
             self.kernel.set_execution_state(&status.execution_state);
@@ -1887,20 +1932,61 @@ impl KernelSession for NotebookEditor {
             cx.notify();
         }
 
-        // Handle cell-specific messages
-        if let Some(parent_header) = &message.parent_header {
-            if let Some(cell_id) = self.execution_requests.get(&parent_header.msg_id) {
-                if let Some(Cell::Code(cell)) = self.cell_map.get(cell_id) {
-                    cell.update(cx, |cell, cx| {
-                        cell.handle_message(message, window, cx);
-                    });
+        // Display updates can target outputs in any cell
+        let is_display_update = matches!(
+            &message.content,
+            JupyterMessageContent::UpdateDisplayData(update) if update.transient.display_id.is_some()
+        );
+        let is_for_cell = message
+            .parent_header
+            .as_ref()
+            .is_some_and(|header| self.execution_requests.contains_key(&header.msg_id));
+        if is_display_update || is_for_cell {
+            self.unpresented_messages.push(message.clone());
+        }
+    }
+
+    fn present_messages(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let html_trust = self
+            .kernel_specification
+            .as_ref()
+            .map_or(HtmlTrust::Untrusted, |spec| spec.html_trust(cx));
+
+        for message in std::mem::take(&mut self.unpresented_messages) {
+            if let JupyterMessageContent::UpdateDisplayData(update) = &message.content {
+                let Some(display_id) = &update.transient.display_id else {
+                    continue;
+                };
+                for cell in self.cell_map.values() {
+                    if let Cell::Code(cell) = cell {
+                        cell.update(cx, |cell, cx| {
+                            cell.update_display_data(
+                                &update.data,
+                                display_id,
+                                html_trust,
+                                window,
+                                cx,
+                            );
+                        });
+                    }
                 }
+                continue;
+            }
+
+            let cell_id = message
+                .parent_header
+                .as_ref()
+                .and_then(|header| self.execution_requests.get(&header.msg_id));
+            if let Some(Cell::Code(cell)) = cell_id.and_then(|cell_id| self.cell_map.get(cell_id)) {
+                cell.update(cx, |cell, cx| {
+                    cell.handle_message(&message, html_trust, window, cx);
+                });
             }
         }
     }
 
     fn kernel_errored(&mut self, error_message: String, cx: &mut Context<Self>) {
-        self.kernel = Kernel::ErroredLaunch(error_message);
+        self.kernel = Kernel::ErroredLaunch(error_message, None);
         cx.notify();
     }
 }
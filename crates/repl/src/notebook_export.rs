@@ -0,0 +1,536 @@
+//! Exports a session's executions as a Jupyter notebook.
+//!
+//! Each execution that ran becomes a code cell, in the order the kernel ran
+//! them, with the outputs its [`ExecutionRecord`] resolved. Markdown and raw
+//! cells of the script, which never reach the kernel, are placed before the
+//! code cell that follows them in the script. The document is written as
+//! nbformat 4.5 JSON, the format Jupyter itself writes. Outputs that were
+//! spilled to disk are read back into the document.
+
+use anyhow::Result;
+use project::Fs;
+use runtimelib::KernelInfoReply;
+use serde_json::{Value, json};
+use settings::ReplCellType;
+
+use crate::execution_record::{ExecutionRecord, ExecutionRecordStatus, RecordedOutput};
+use crate::kernels::KernelSpecification;
+
+const NBFORMAT: u32 = 4;
+const NBFORMAT_MINOR: u32 = 5;
+
+/// The notebook-level `kernelspec` metadata for a kernel.
+pub(crate) fn kernelspec_metadata(kernel_specification: &KernelSpecification) -> Value {
+    json!({
+        "name": kernel_specification.name().to_string(),
+        "display_name": kernel_specification.kernelspec().display_name,
+        "language": kernel_specification.language().to_string(),
+    })
+}
+
+/// The notebook-level `language_info` metadata, as the kernel reported it.
+pub(crate) fn language_info_metadata(kernel_info: &KernelInfoReply) -> Option<Value> {
+    serde_json::to_value(&kernel_info.language_info).ok()
+}
+
+/// A cell of the script the session's executions came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ScriptCell {
+    pub cell_type: ReplCellType,
+    /// The code as it's run for code cells, and the uncommented text for
+    /// markdown and raw cells.
+    pub source: String,
+}
+
+/// Builds an nbformat 4 notebook with a code cell for each execution in
+/// `records` that the kernel ran. Executions aborted before they started are
+/// left out. Outputs spilled to disk are read back through `fs`, failing if
+/// any can't be.
+///
+/// Each markdown and raw cell of `script_cells` goes before the first
+/// execution of the next code cell in the script that ran, or at the end when
+/// none of the code cells after it ran.
+pub(crate) async fn notebook_from_records<'a>(
+    records: impl IntoIterator<Item = &'a ExecutionRecord>,
+    script_cells: &[ScriptCell],
+    kernelspec: Value,
+    language_info: Option<Value>,
+    fs: &dyn Fs,
+) -> Result<Value> {
+    let mut records = records
+        .into_iter()
+        .filter(|record| {
+            record.started_at.is_some()
+                || record.execution_count.is_some()
+                || !record.outputs.is_empty()
+        })
+        .collect::<Vec<_>>();
+    // Queued executions can be reordered, so submission order isn't always
+    // the order they ran in.
+    records.sort_by_key(|record| record.started_at.unwrap_or(record.submitted_at));
+
+    let mut metadata = serde_json::Map::new();
+    metadata.insert("kernelspec".into(), kernelspec);
+    if let Some(language_info) = language_info {
+        metadata.insert("language_info".into(), language_info);
+    }
+
+    let mut text_cells_before = vec![Vec::new(); records.len()];
+    let mut trailing_text_cells = Vec::new();
+    for (index, script_cell) in script_cells.iter().enumerate() {
+        let cell_type = match script_cell.cell_type {
+            ReplCellType::Code => continue,
+            ReplCellType::Markdown => "markdown",
+            ReplCellType::Raw => "raw",
+        };
+        let next_run = script_cells[index + 1..]
+            .iter()
+            .filter(|cell| cell.cell_type == ReplCellType::Code)
+            .find_map(|cell| records.iter().position(|record| record.code == cell.source));
+        let cell = text_cell(cell_type, index, &script_cell.source);
+        match next_run {
+            Some(position) => text_cells_before[position].push(cell),
+            None => trailing_text_cells.push(cell),
+        }
+    }
+
+    let mut cells = Vec::new();
+    for (record, text_cells) in records.into_iter().zip(text_cells_before) {
+        cells.extend(text_cells);
+        cells.push(code_cell(record, fs).await?);
+    }
+    cells.extend(trailing_text_cells);
+
+    Ok(json!({
+        "cells": cells,
+        "metadata": metadata,
+        "nbformat": NBFORMAT,
+        "nbformat_minor": NBFORMAT_MINOR,
+    }))
+}
+
+async fn code_cell(record: &ExecutionRecord, fs: &dyn Fs) -> Result<Value> {
+    let mut zed_metadata = serde_json::Map::new();
+    if record.truncated {
+        zed_metadata.insert("output_truncated".into(), true.into());
+    }
+    if record.status == ExecutionRecordStatus::Aborted {
+        zed_metadata.insert("aborted".into(), true.into());
+    }
+    let metadata = if zed_metadata.is_empty() {
+        json!({})
+    } else {
+        json!({ "zed": zed_metadata })
+    };
+
+    let outputs = record
+        .load_outputs(fs)
+        .await?
+        .iter()
+        .map(|loaded| output(loaded))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(json!({
+        "cell_type": "code",
+        // Message ids are UUIDs, which are valid and unique cell ids.
+        "id": record.msg_id,
+        "metadata": metadata,
+        "execution_count": record.execution_count,
+        "source": multiline_string(&record.code),
+        "outputs": outputs,
+    }))
+}
+
+/// A markdown or raw cell for the script cell at `index`.
+fn text_cell(cell_type: &str, index: usize, source: &str) -> Value {
+    json!({
+        "cell_type": cell_type,
+        // Code cells have UUIDs, so these can't collide with them.
+        "id": format!("{cell_type}-{index}"),
+        "metadata": {},
+        "source": multiline_string(source),
+    })
+}
+
+fn output(output: &RecordedOutput) -> Result<Value> {
+    Ok(match output {
+        RecordedOutput::Stream { name, text } => json!({
+            "output_type": "stream",
+            "name": name,
+            "text": multiline_string(text),
+        }),
+        // Binary data, such as images, is already base64 in the kernel's
+        // messages, which is how nbformat stores it too.
+        RecordedOutput::Data {
+            data,
+            execution_count: Some(execution_count),
+            ..
+        } => json!({
+            "output_type": "execute_result",
+            "execution_count": execution_count,
+            "data": data,
+            "metadata": {},
+        }),
+        RecordedOutput::Data {
+            data,
+            execution_count: None,
+            ..
+        } => json!({
+            "output_type": "display_data",
+            "data": data,
+            "metadata": {},
+        }),
+        RecordedOutput::Error {
+            ename,
+            evalue,
+            traceback,
+        } => json!({
+            "output_type": "error",
+            "ename": ename,
+            "evalue": evalue,
+            "traceback": traceback,
+        }),
+        RecordedOutput::Spilled(spilled) => {
+            anyhow::bail!("output spilled to {:?} wasn't read back", spilled.path)
+        }
+    })
+}
+
+/// Splits `text` into lines that keep their line endings, the way Jupyter
+/// writes multiline strings.
+fn multiline_string(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution_record::ExecutionRecords;
+    use chrono::Utc;
+    use gpui::TestAppContext;
+    use project::FakeFs;
+    use runtimelib::{
+        ErrorOutput, ExecuteReply, ExecuteRequest, ExecuteResult, ExecutionState, JupyterMessage,
+        JupyterMessageContent, Status, Stdio, StreamContent,
+    };
+
+    fn run(
+        records: &mut ExecutionRecords,
+        code: &str,
+        messages: Vec<JupyterMessageContent>,
+    ) -> JupyterMessage {
+        let request: JupyterMessage = ExecuteRequest {
+            code: code.to_string(),
+            ..ExecuteRequest::default()
+        }
+        .into();
+        records.start(&request, code.to_string());
+        let messages = std::iter::once(JupyterMessageContent::Status(Status {
+            execution_state: ExecutionState::Busy,
+        }))
+        .chain(messages)
+        .chain([JupyterMessageContent::Status(Status {
+            execution_state: ExecutionState::Idle,
+        })]);
+        for content in messages {
+            records.handle_message(&JupyterMessage::new(content, Some(&request)), Utc::now());
+        }
+        request
+    }
+
+    fn reply(status: &str, execution_count: usize) -> JupyterMessageContent {
+        JupyterMessageContent::ExecuteReply(
+            serde_json::from_value::<ExecuteReply>(json!({
+                "status": status,
+                "execution_count": execution_count,
+            }))
+            .unwrap(),
+        )
+    }
+
+    fn stream(text: &str) -> JupyterMessageContent {
+        JupyterMessageContent::StreamContent(StreamContent {
+            name: Stdio::Stdout,
+            text: text.to_string(),
+        })
+    }
+
+    /// Checks `notebook` against the structure the nbformat 4.5 schema
+    /// requires of notebooks, cells, and outputs.
+    fn assert_valid_nbformat(notebook: &Value) {
+        let object = notebook.as_object().expect("a notebook is an object");
+        assert_eq!(object["nbformat"], 4);
+        assert_eq!(object["nbformat_minor"], 5);
+        assert!(object["metadata"].is_object());
+
+        let is_multiline_string = |value: &Value| {
+            value.is_string()
+                || value
+                    .as_array()
+                    .is_some_and(|lines| lines.iter().all(Value::is_string))
+        };
+        let is_execution_count = |value: &Value| value.is_null() || value.is_u64();
+
+        let mut ids = std::collections::HashSet::new();
+        for cell in object["cells"].as_array().expect("cells are an array") {
+            let id = cell["id"].as_str().expect("cells have a string id");
+            assert!(
+                (1..=64).contains(&id.len())
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+                "invalid cell id {id:?}"
+            );
+            assert!(ids.insert(id.to_string()), "duplicate cell id {id:?}");
+            assert!(cell["metadata"].is_object());
+            assert!(is_multiline_string(&cell["source"]));
+            match cell["cell_type"].as_str() {
+                Some("code") => {}
+                Some("markdown" | "raw") => {
+                    assert!(cell.get("outputs").is_none());
+                    assert!(cell.get("execution_count").is_none());
+                    continue;
+                }
+                other => panic!("unexpected cell type {other:?}"),
+            }
+            assert!(is_execution_count(&cell["execution_count"]));
+
+            for output in cell["outputs"].as_array().expect("outputs are an array") {
+                match output["output_type"].as_str() {
+                    Some("stream") => {
+                        assert!(matches!(output["name"].as_str(), Some("stdout" | "stderr")));
+                        assert!(is_multiline_string(&output["text"]));
+                    }
+                    Some("display_data") => {
+                        assert!(output["data"].is_object());
+                        assert!(output["metadata"].is_object());
+                    }
+                    Some("execute_result") => {
+                        assert!(output["data"].is_object());
+                        assert!(output["metadata"].is_object());
+                        assert!(is_execution_count(&output["execution_count"]));
+                    }
+                    Some("error") => {
+                        assert!(output["ename"].is_string());
+                        assert!(output["evalue"].is_string());
+                        assert!(
+                            output["traceback"]
+                                .as_array()
+                                .is_some_and(|lines| { lines.iter().all(Value::is_string) })
+                        );
+                    }
+                    other => panic!("unexpected output type {other:?}"),
+                }
+            }
+        }
+
+        // nbformat's own parser accepts it too.
+        let serialized = serde_json::to_string_pretty(notebook).unwrap();
+        assert!(matches!(
+            nbformat::parse_notebook(&serialized),
+            Ok(nbformat::Notebook::V4(_))
+        ));
+    }
+
+    #[gpui::test]
+    async fn test_notebook_from_records(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let mut records = ExecutionRecords::default();
+        let png = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+
+        let printed = run(
+            &mut records,
+            "import sys\nprint('hi')\n",
+            vec![stream("hi\n"), reply("ok", 1)],
+        );
+        let plotted = run(
+            &mut records,
+            "plot()",
+            vec![
+                JupyterMessageContent::DisplayData(
+                    serde_json::from_value(json!({
+                        "data": { "image/png": png, "text/plain": "<Figure>" },
+                        "metadata": {},
+                    }))
+                    .unwrap(),
+                ),
+                JupyterMessageContent::ExecuteResult(
+                    serde_json::from_value::<ExecuteResult>(json!({
+                        "data": { "text/plain": "42" },
+                        "metadata": {},
+                        "execution_count": 2,
+                    }))
+                    .unwrap(),
+                ),
+                reply("ok", 2),
+            ],
+        );
+        let failed = run(
+            &mut records,
+            "1 / 0",
+            vec![
+                JupyterMessageContent::ErrorOutput(ErrorOutput {
+                    ename: "ZeroDivisionError".to_string(),
+                    evalue: "division by zero".to_string(),
+                    traceback: vec!["Traceback".to_string(), "ZeroDivisionError".to_string()],
+                }),
+                reply("error", 3),
+            ],
+        );
+        // Executions cancelled before they ran aren't exported.
+        let cancelled: JupyterMessage = ExecuteRequest::default().into();
+        records.start(&cancelled, "never_ran()".to_string());
+        records.abort(&cancelled.header.msg_id);
+
+        let notebook = notebook_from_records(
+            records.iter(),
+            &[],
+            json!({ "name": "python3", "display_name": "Python 3", "language": "python" }),
+            Some(json!({ "name": "python", "version": "3.12.1" })),
+            fs.as_ref(),
+        )
+        .await
+        .unwrap();
+        assert_valid_nbformat(&notebook);
+
+        assert_eq!(notebook["metadata"]["kernelspec"]["name"], "python3");
+        assert_eq!(notebook["metadata"]["language_info"]["version"], "3.12.1");
+
+        let cells = notebook["cells"].as_array().unwrap();
+        assert_eq!(
+            cells.iter().map(|cell| &cell["id"]).collect::<Vec<_>>(),
+            [
+                &json!(printed.header.msg_id),
+                &json!(plotted.header.msg_id),
+                &json!(failed.header.msg_id),
+            ]
+        );
+
+        assert_eq!(cells[0]["source"], json!(["import sys\n", "print('hi')\n"]));
+        assert_eq!(cells[0]["execution_count"], 1);
+        assert_eq!(
+            cells[0]["outputs"],
+            json!([{ "output_type": "stream", "name": "stdout", "text": ["hi\n"] }])
+        );
+        assert_eq!(cells[0]["metadata"], json!({}));
+
+        let outputs = cells[1]["outputs"].as_array().unwrap();
+        assert_eq!(outputs[0]["output_type"], "display_data");
+        assert_eq!(outputs[0]["data"]["image/png"], png);
+        assert_eq!(outputs[1]["output_type"], "execute_result");
+        assert_eq!(outputs[1]["execution_count"], 2);
+        assert_eq!(outputs[1]["data"]["text/plain"], "42");
+
+        assert_eq!(cells[2]["outputs"][0]["output_type"], "error");
+        assert_eq!(cells[2]["outputs"][0]["ename"], "ZeroDivisionError");
+        assert_eq!(
+            cells[2]["outputs"][0]["traceback"],
+            json!(["Traceback", "ZeroDivisionError"])
+        );
+    }
+
+    #[gpui::test]
+    async fn test_notebook_flags_truncated_and_aborted_cells(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let mut records = ExecutionRecords::default();
+        let huge = "x".repeat(crate::outputs::stream::MAX_STREAM_OUTPUT_BYTES + 1);
+        run(
+            &mut records,
+            "print_forever()",
+            vec![stream(&huge), reply("ok", 1)],
+        );
+
+        let interrupted: JupyterMessage = ExecuteRequest::default().into();
+        records.start(&interrupted, "sleep(100)".to_string());
+        records.handle_message(
+            &JupyterMessage::new(
+                Status {
+                    execution_state: ExecutionState::Busy,
+                },
+                Some(&interrupted),
+            ),
+            Utc::now(),
+        );
+        records.handle_message(
+            &JupyterMessage::new(stream("zzz"), Some(&interrupted)),
+            Utc::now(),
+        );
+        records.abort(&interrupted.header.msg_id);
+
+        let notebook = notebook_from_records(records.iter(), &[], json!({}), None, fs.as_ref())
+            .await
+            .unwrap();
+        assert_valid_nbformat(&notebook);
+        assert!(notebook["metadata"].get("language_info").is_none());
+
+        let cells = notebook["cells"].as_array().unwrap();
+        assert_eq!(cells.len(), 2);
+        assert_eq!(
+            cells[0]["metadata"],
+            json!({ "zed": { "output_truncated": true } })
+        );
+        assert_eq!(cells[1]["metadata"], json!({ "zed": { "aborted": true } }));
+        assert_eq!(cells[1]["execution_count"], Value::Null);
+        assert_eq!(cells[1]["outputs"][0]["text"], json!(["zzz"]));
+    }
+
+    #[gpui::test]
+    async fn test_notebook_with_script_cells(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let mut records = ExecutionRecords::default();
+        let second = run(&mut records, "# %%\nprint(2)", vec![reply("ok", 1)]);
+        let first = run(&mut records, "# %%\nprint(1)", vec![reply("ok", 2)]);
+
+        let script_cell = |cell_type, source: &str| ScriptCell {
+            cell_type,
+            source: source.to_string(),
+        };
+        let script_cells = [
+            script_cell(ReplCellType::Markdown, "# Title\n\nIntro"),
+            script_cell(ReplCellType::Code, "# %%\nprint(1)"),
+            script_cell(ReplCellType::Raw, "raw text"),
+            script_cell(ReplCellType::Code, "# %%\nnever_ran()"),
+            script_cell(ReplCellType::Code, "# %%\nprint(2)"),
+            script_cell(ReplCellType::Markdown, "The end"),
+        ];
+        let notebook =
+            notebook_from_records(records.iter(), &script_cells, json!({}), None, fs.as_ref())
+                .await
+                .unwrap();
+        assert_valid_nbformat(&notebook);
+
+        let cells = notebook["cells"].as_array().unwrap();
+        assert_eq!(
+            cells
+                .iter()
+                .map(|cell| (cell["cell_type"].as_str().unwrap(), &cell["id"]))
+                .collect::<Vec<_>>(),
+            [
+                // The raw cell goes before the next code cell that ran, which
+                // the kernel ran first.
+                ("raw", &json!("raw-2")),
+                ("code", &json!(second.header.msg_id)),
+                ("markdown", &json!("markdown-0")),
+                ("code", &json!(first.header.msg_id)),
+                ("markdown", &json!("markdown-5")),
+            ]
+        );
+        assert_eq!(cells[2]["source"], json!(["# Title\n", "\n", "Intro"]));
+        assert_eq!(cells[0]["source"], json!(["raw text"]));
+    }
+
+    #[gpui::test]
+    async fn test_empty_notebook(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let notebook = notebook_from_records(
+            std::iter::empty(),
+            &[],
+            json!({ "name": "ir", "display_name": "R", "language": "R" }),
+            None,
+            fs.as_ref(),
+        )
+        .await
+        .unwrap();
+        assert_valid_nbformat(&notebook);
+        assert_eq!(notebook["cells"], json!([]));
+    }
+}
@@ -0,0 +1,272 @@
+//! Tees the stream output of a session's executions to a file on disk.
+//!
+//! Long-running executions, like training runs, can print for hours. The
+//! in-memory outputs are capped, and are lost if Zed crashes or the window
+//! closes, so a session can also append every stream message to a log file
+//! as it arrives. Each execution is framed by a header line with its
+//! execution count and a footer with how it ended and how long it took,
+//! written once the kernel goes idle, after the last of its output.
+//!
+//! Writes happen on a background task. The file is created on the first
+//! write, and when it would grow past `repl.output_log_max_size_mb` it's
+//! renamed with a `.1` extension, replacing the previous one, and a new file
+//! is started. An execution whose output can't be written stops being logged
+//! with a single warning, rather than failing on every message.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Result;
+use futures::{StreamExt as _, channel::mpsc};
+use gpui::App;
+use project::{Fs, RenameOptions};
+
+use crate::execution_record::{ExecutionRecord, ExecutionRecordStatus};
+
+/// A file a session is logging its executions' stream output to.
+pub struct OutputLog {
+    path: PathBuf,
+    writes: mpsc::UnboundedSender<OutputLogWrite>,
+    /// Whether the last text sent ended a line, so header and footer lines
+    /// start on a line of their own.
+    at_line_start: bool,
+}
+
+struct OutputLogWrite {
+    msg_id: String,
+    text: String,
+}
+
+impl OutputLog {
+    /// Starts logging to `path`, keeping it under `max_size` bytes when set.
+    pub fn new(path: PathBuf, max_size: Option<u64>, fs: Arc<dyn Fs>, cx: &App) -> Self {
+        let (writes, mut pending_writes) = mpsc::unbounded::<OutputLogWrite>();
+        let mut writer = OutputLogWriter::new(fs, path.clone(), max_size);
+        // Detached, so that output sent before logging stops is still
+        // written once the sender is dropped.
+        cx.background_spawn(async move {
+            while let Some(write) = pending_writes.next().await {
+                writer.write(&write.msg_id, &write.text).await;
+            }
+        })
+        .detach();
+        Self {
+            path,
+            writes,
+            at_line_start: true,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Logs stream text printed by the execution sent as `msg_id`.
+    pub fn write_stream(&mut self, msg_id: &str, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        self.at_line_start = text.ends_with('\n');
+        self.send(msg_id, text.to_string());
+    }
+
+    /// Logs the line that starts an execution's output.
+    pub fn write_header(&mut self, record: &ExecutionRecord) {
+        let count = record
+            .execution_count
+            .map_or_else(|| " ".to_string(), |count| count.to_string());
+        self.write_line(
+            &record.msg_id,
+            format!(
+                "=== In [{count}] at {} ===",
+                record.submitted_at.to_rfc3339()
+            ),
+        );
+    }
+
+    /// Logs the line that ends an execution's output, with its status and
+    /// duration.
+    pub fn write_footer(&mut self, record: &ExecutionRecord) {
+        let status = match record.status {
+            ExecutionRecordStatus::Pending => "pending",
+            ExecutionRecordStatus::Ok => "ok",
+            ExecutionRecordStatus::Error => "error",
+            ExecutionRecordStatus::Interrupted => "interrupted",
+            ExecutionRecordStatus::Aborted => "aborted",
+        };
+        let line = match record.timing() {
+            Some(timing) => format!("=== Finished: {status}, {} ===", timing.summary()),
+            None => format!("=== Finished: {status} ==="),
+        };
+        self.write_line(&record.msg_id, line);
+    }
+
+    fn write_line(&mut self, msg_id: &str, line: String) {
+        let separator = if self.at_line_start { "" } else { "\n" };
+        self.at_line_start = true;
+        self.send(msg_id, format!("{separator}{line}\n"));
+    }
+
+    fn send(&self, msg_id: &str, text: String) {
+        self.writes
+            .unbounded_send(OutputLogWrite {
+                msg_id: msg_id.to_string(),
+                text,
+            })
+            .ok();
+    }
+}
+
+/// The path a full log file is renamed to when a new one is started.
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+/// Appends to the log file, off the main thread.
+struct OutputLogWriter {
+    fs: Arc<dyn Fs>,
+    path: PathBuf,
+    max_size: Option<u64>,
+    /// The size of the file, once it's known.
+    size: Option<u64>,
+    /// Executions whose output couldn't be written, which aren't logged
+    /// anymore.
+    failed_executions: HashSet<String>,
+}
+
+impl OutputLogWriter {
+    fn new(fs: Arc<dyn Fs>, path: PathBuf, max_size: Option<u64>) -> Self {
+        Self {
+            fs,
+            path,
+            max_size,
+            size: None,
+            failed_executions: HashSet::new(),
+        }
+    }
+
+    async fn write(&mut self, msg_id: &str, text: &str) {
+        if self.failed_executions.contains(msg_id) {
+            return;
+        }
+        if let Err(error) = self.try_write(text).await {
+            log::warn!(
+                "Stopped logging the output of execution {msg_id} to {:?}: {error:#}",
+                self.path
+            );
+            self.failed_executions.insert(msg_id.to_string());
+            // Measured again for the next execution, in case the problem was
+            // temporary.
+            self.size = None;
+        }
+    }
+
+    async fn try_write(&mut self, text: &str) -> Result<()> {
+        let length = text.len() as u64;
+        let mut size = match self.size {
+            Some(size) => size,
+            None => self.measure().await?,
+        };
+        if let Some(max_size) = self.max_size
+            && size > 0
+            && size + length > max_size
+        {
+            self.size = None;
+            self.fs
+                .rename(
+                    &self.path,
+                    &rotated_path(&self.path),
+                    RenameOptions {
+                        overwrite: true,
+                        ..RenameOptions::default()
+                    },
+                )
+                .await?;
+            size = 0;
+        }
+        self.fs.append(&self.path, text.as_bytes()).await?;
+        self.size = Some(size + length);
+        Ok(())
+    }
+
+    /// Creates the file's directory, and returns how large the file already
+    /// is.
+    async fn measure(&self) -> Result<u64> {
+        if let Some(parent) = self.path.parent() {
+            self.fs.create_dir(parent).await?;
+        }
+        Ok(self
+            .fs
+            .metadata(&self.path)
+            .await?
+            .map_or(0, |metadata| metadata.len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use project::FakeFs;
+    use util::path;
+
+    async fn read(fs: &FakeFs, path: &Path) -> String {
+        fs.load(path).await.unwrap()
+    }
+
+    #[gpui::test]
+    async fn test_writer_creates_file_lazily_and_appends(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let path = PathBuf::from(path!("/logs/run.log"));
+        let mut writer = OutputLogWriter::new(fs.clone(), path.clone(), None);
+        assert!(!fs.is_file(&path).await);
+
+        writer.write("a", "epoch 1\n").await;
+        writer.write("a", "epoch 2\n").await;
+        assert_eq!(read(&fs, &path).await, "epoch 1\nepoch 2\n");
+
+        // A new writer appends to what's already there.
+        let mut writer = OutputLogWriter::new(fs.clone(), path.clone(), None);
+        writer.write("b", "epoch 3\n").await;
+        assert_eq!(read(&fs, &path).await, "epoch 1\nepoch 2\nepoch 3\n");
+    }
+
+    #[gpui::test]
+    async fn test_writer_rotates_at_max_size(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let path = PathBuf::from(path!("/logs/run.log"));
+        let mut writer = OutputLogWriter::new(fs.clone(), path.clone(), Some(10));
+
+        writer.write("a", "12345\n").await;
+        writer.write("a", "67890\n").await;
+        writer.write("a", "abcde\n").await;
+        assert_eq!(read(&fs, &path).await, "abcde\n");
+        assert_eq!(read(&fs, &rotated_path(&path)).await, "67890\n");
+
+        // Text larger than the cap still goes into a file of its own.
+        writer.write("a", "a much longer line\n").await;
+        assert_eq!(read(&fs, &path).await, "a much longer line\n");
+    }
+
+    #[gpui::test]
+    async fn test_writer_stops_logging_failed_execution(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let directory = PathBuf::from(path!("/logs"));
+        fs.create_dir(&directory).await.unwrap();
+        // A directory can't be appended to.
+        let mut writer = OutputLogWriter::new(fs.clone(), directory.clone(), None);
+        writer.write("a", "lost\n").await;
+        assert!(writer.failed_executions.contains("a"));
+
+        // Later output of the same execution is dropped without trying again.
+        writer.path = directory.join("run.log");
+        writer.write("a", "also lost\n").await;
+        writer.write("b", "kept\n").await;
+        assert_eq!(read(&fs, &directory.join("run.log")).await, "kept\n");
+    }
+}
@@ -0,0 +1,350 @@
+//! Keeps the large outputs of older executions on disk instead of in memory.
+//!
+//! A session that runs for days can record thousands of executions, and
+//! plots alone can add up to hundreds of megabytes. Only the most recent
+//! executions, `repl.outputs_kept_in_memory` of them, keep all of their
+//! outputs in memory. Outputs of older executions that are larger than
+//! [`SPILL_THRESHOLD_BYTES`] are written to a directory of the session's own
+//! under the system's temporary directory, and the record keeps a
+//! [`SpilledOutput`] in their place, which is read back when the output is
+//! needed again, such as when the session is exported.
+//!
+//! Outputs are written and read back through the project's [`Fs`] on the
+//! background executor, since a single plot can take megabytes.
+//!
+//! The directory is removed when the session's records are dropped. Its name
+//! starts with the process id of the Zed that created it, so directories
+//! left behind by a crash are swept on a later launch.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context as _, Result};
+use futures::StreamExt as _;
+use gpui::{App, BackgroundExecutor};
+use project::{Fs, RemoveOptions};
+use sha2::{Digest as _, Sha256};
+
+use crate::kernels::is_process_alive;
+
+/// Outputs smaller than this stay in memory however old they are, since
+/// reading them back would cost more than they save.
+pub(crate) const SPILL_THRESHOLD_BYTES: usize = 16 * 1024;
+
+/// The directory the sessions of every Zed process spill outputs to.
+pub(crate) fn output_spill_root() -> PathBuf {
+    std::env::temp_dir().join("zed-repl-outputs")
+}
+
+/// An output that was written to disk, and what's needed to read it back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpilledOutput {
+    pub path: PathBuf,
+    /// The size of the file, in bytes.
+    pub size: u64,
+    /// The SHA-256 hash of the file, checked when it's read back.
+    pub hash: [u8; 32],
+    /// The display id of the output, so that display updates can replace it
+    /// without reading it back.
+    pub display_id: Option<String>,
+    /// Set for an execution's result, as it is for in-memory data.
+    pub execution_count: Option<usize>,
+}
+
+impl SpilledOutput {
+    /// Writes `bytes` to `path`, which the caller reserved with
+    /// [`OutputSpillDirectory::next_path`]. The directory is created if it
+    /// doesn't exist yet.
+    pub(crate) async fn write(fs: &dyn Fs, path: PathBuf, bytes: &[u8]) -> Result<Self> {
+        fs.write(&path, bytes)
+            .await
+            .with_context(|| format!("failed to write {path:?}"))?;
+        Ok(Self {
+            path,
+            size: bytes.len() as u64,
+            hash: hash(bytes),
+            display_id: None,
+            execution_count: None,
+        })
+    }
+
+    /// Reads the output back, failing if the file changed since it was
+    /// written.
+    pub async fn load(&self, fs: &dyn Fs) -> Result<Vec<u8>> {
+        let bytes = fs
+            .load_bytes(&self.path)
+            .await
+            .with_context(|| format!("failed to read spilled output {:?}", self.path))?;
+        if bytes.len() as u64 != self.size || hash(&bytes) != self.hash {
+            anyhow::bail!(
+                "spilled output {:?} changed since it was written",
+                self.path
+            );
+        }
+        Ok(bytes)
+    }
+}
+
+/// The directory a session spills its outputs to, created on the first
+/// spill and removed when it's dropped.
+pub(crate) struct OutputSpillDirectory {
+    path: PathBuf,
+    fs: Arc<dyn Fs>,
+    executor: BackgroundExecutor,
+    next_file: usize,
+}
+
+impl std::fmt::Debug for OutputSpillDirectory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutputSpillDirectory")
+            .field("path", &self.path)
+            .field("next_file", &self.next_file)
+            .finish_non_exhaustive()
+    }
+}
+
+impl OutputSpillDirectory {
+    /// A directory of this process's own under `root`.
+    pub fn new(root: &Path, fs: Arc<dyn Fs>, executor: BackgroundExecutor) -> Self {
+        Self {
+            path: root.join(format!("{}-{}", std::process::id(), uuid::Uuid::new_v4())),
+            fs,
+            executor,
+            next_file: 0,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn fs(&self) -> &Arc<dyn Fs> {
+        &self.fs
+    }
+
+    /// A path for a new file in the directory, to write with
+    /// [`SpilledOutput::write`].
+    pub fn next_path(&mut self) -> PathBuf {
+        let path = self.path.join(format!("{}.json", self.next_file));
+        self.next_file += 1;
+        path
+    }
+
+    /// Deletes the file of an output that's no longer needed, in the
+    /// background.
+    pub fn remove(&self, spilled: &SpilledOutput) {
+        let fs = self.fs.clone();
+        let path = spilled.path.clone();
+        self.executor
+            .spawn(async move {
+                if let Err(error) = fs
+                    .remove_file(
+                        &path,
+                        RemoveOptions {
+                            recursive: false,
+                            ignore_if_not_exists: true,
+                        },
+                    )
+                    .await
+                {
+                    log::warn!("Failed to remove spilled output {path:?}: {error:?}");
+                }
+            })
+            .detach();
+    }
+}
+
+impl Drop for OutputSpillDirectory {
+    fn drop(&mut self) {
+        if self.next_file == 0 {
+            return;
+        }
+        let fs = self.fs.clone();
+        let path = self.path.clone();
+        self.executor
+            .spawn(async move {
+                if let Err(error) = fs
+                    .remove_dir(
+                        &path,
+                        RemoveOptions {
+                            recursive: true,
+                            ignore_if_not_exists: true,
+                        },
+                    )
+                    .await
+                {
+                    log::warn!("Failed to remove spilled outputs in {path:?}: {error:?}");
+                }
+            })
+            .detach();
+    }
+}
+
+fn hash(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(bytes).into()
+}
+
+/// Sweeps the spill directory for the outputs of sessions whose Zed is no
+/// longer running.
+pub(crate) fn sweep_output_spill_directories_in_background(fs: Arc<dyn Fs>, cx: &App) {
+    cx.background_spawn(async move {
+        let removed =
+            sweep_output_spill_directories(fs.as_ref(), &output_spill_root(), is_process_alive)
+                .await?;
+        if !removed.is_empty() {
+            log::info!(
+                "Removed the spilled outputs of {} REPL sessions",
+                removed.len()
+            );
+        }
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Removes the session directories in `root` whose Zed process is no longer
+/// running. Anything not named like a session directory is left alone.
+/// Failures to remove a directory are logged and don't stop the sweep.
+pub(crate) async fn sweep_output_spill_directories(
+    fs: &dyn Fs,
+    root: &Path,
+    is_alive: impl Fn(u32) -> bool,
+) -> Result<Vec<PathBuf>> {
+    if !fs.is_dir(root).await {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = fs.read_dir(root).await?;
+    let mut removed = Vec::new();
+    while let Some(entry) = entries.next().await {
+        let path = match entry {
+            Ok(path) => path,
+            Err(error) => {
+                log::warn!("Failed to read spilled outputs in {root:?}: {error:?}");
+                continue;
+            }
+        };
+        let Some(pid) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.split_once('-'))
+            .and_then(|(pid, _)| pid.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if pid == std::process::id() || is_alive(pid) || !fs.is_dir(&path).await {
+            continue;
+        }
+        match fs
+            .remove_dir(
+                &path,
+                RemoveOptions {
+                    recursive: true,
+                    ignore_if_not_exists: true,
+                },
+            )
+            .await
+        {
+            Ok(()) => removed.push(path),
+            Err(error) => log::warn!("Failed to remove spilled outputs in {path:?}: {error:?}"),
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use project::FakeFs;
+    use serde_json::json;
+    use util::path;
+
+    #[gpui::test]
+    async fn test_spilled_output_round_trip(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let root = PathBuf::from(path!("/tmp/zed-repl-outputs"));
+        let mut directory = OutputSpillDirectory::new(&root, fs.clone(), cx.executor());
+        let path = directory.path().to_path_buf();
+        assert!(!fs.is_dir(&path).await);
+
+        let spilled =
+            SpilledOutput::write(fs.as_ref(), directory.next_path(), b"{\"text\": \"plot\"}")
+                .await
+                .unwrap();
+        assert_eq!(spilled.size, 16);
+        assert_eq!(
+            spilled.load(fs.as_ref()).await.unwrap(),
+            b"{\"text\": \"plot\"}"
+        );
+
+        // A file that changed on disk isn't trusted.
+        fs.write(&spilled.path, b"{\"text\": \"PLOT\"}")
+            .await
+            .unwrap();
+        assert!(spilled.load(fs.as_ref()).await.is_err());
+
+        directory.remove(&spilled);
+        cx.run_until_parked();
+        assert!(!fs.is_file(&spilled.path).await);
+
+        assert!(fs.is_dir(&path).await);
+        drop(directory);
+        cx.run_until_parked();
+        assert!(!fs.is_dir(&path).await);
+    }
+
+    #[gpui::test]
+    async fn test_sweep_output_spill_directories(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let root = PathBuf::from(path!("/tmp/zed-repl-outputs"));
+        let own_directory = format!("{}-c", std::process::id());
+        fs.insert_tree(
+            &root,
+            json!({
+                "10-a": { "0.json": "{}" },
+                "20-b": { "0.json": "{}" },
+                own_directory.as_str(): { "0.json": "{}" },
+                "notes": {},
+                "30-file.json": "{}",
+            }),
+        )
+        .await;
+
+        let removed = sweep_output_spill_directories(fs.as_ref(), &root, |pid| pid == 20)
+            .await
+            .unwrap();
+        assert_eq!(removed, [root.join("10-a")]);
+
+        let mut remaining = fs
+            .read_dir(&root)
+            .await
+            .unwrap()
+            .map(|path| path.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        remaining.sort();
+        let mut expected = vec![
+            root.join("20-b"),
+            root.join("30-file.json"),
+            root.join(&own_directory),
+            root.join("notes"),
+        ];
+        expected.sort();
+        assert_eq!(remaining, expected);
+    }
+
+    #[gpui::test]
+    async fn test_sweep_missing_spill_directory(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        let removed =
+            sweep_output_spill_directories(fs.as_ref(), Path::new(path!("/tmp/missing")), |_| {
+                false
+            })
+            .await
+            .unwrap();
+        assert!(removed.is_empty());
+    }
+}
@@ -17,6 +17,7 @@
 //! - Images (PNG and JPEG)
 //! - Tables
 //! - Error messages
+//! - MIME types with a renderer registered in the `MimeRendererRegistry`
 //!
 //! ## Clipboard Support
 //!
@@ -33,6 +34,8 @@
 //! This module is designed to work with Jupyter message protocols,
 //! interpreting and displaying various types of Jupyter output.
 
+use std::path::PathBuf;
+
 use editor::{Editor, MultiBuffer};
 use gpui::{AnyElement, ClipboardItem, Entity, EventEmitter, Render, WeakEntity};
 use language::Buffer;
@@ -40,13 +43,13 @@ use menu;
 use runtimelib::{ExecutionState, JupyterMessage, JupyterMessageContent, MimeBundle, MimeType};
 use ui::{CommonAnimationExt, CopyButton, IconButton, Tooltip, prelude::*};
 
-mod image;
-use image::ImageView;
+pub(crate) mod image;
+use image::{ImageLimits, ImageView};
 
 mod markdown;
 use markdown::MarkdownView;
 
-mod table;
+pub(crate) mod table;
 use table::TableView;
 
 mod json;
@@ -54,31 +57,79 @@ use json::JsonView;
 
 mod html;
 
+pub mod renderers;
+use renderers::{MimeRendererRegistry, RenderedOutput, Representation};
+
 pub mod plain;
 use plain::TerminalOutput;
 
+pub mod stream;
+use stream::StreamLimiter;
+
 pub(crate) mod user_error;
 use user_error::ErrorView;
 use workspace::Workspace;
 
+use crate::execution_record::RecordedOutput;
 use crate::repl_settings::ReplSettings;
 use settings::Settings;
 
-/// When deciding what to render from a collection of mediatypes, we need to rank them in order of importance
-fn rank_mime_type(mimetype: &MimeType) -> usize {
+const SVG_MIME_TYPE: &str = "image/svg+xml";
+
+/// Shown after an HTML output that had content removed by sanitizing it.
+const REMOVED_HTML_NOTICE: &str = "*Parts of this output were removed because its kernel isn't \
+     trusted to show HTML. See the `repl.trusted_html_sources` setting.*";
+
+/// Whether HTML outputs are shown as the kernel sent them, or sanitized
+/// first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HtmlTrust {
+    Trusted,
+    Untrusted,
+}
+
+/// The MIME type of a media type, or `None` for the ones Zed can't show.
+fn mime_type_name(mimetype: &MimeType) -> Option<&'static str> {
     match mimetype {
-        MimeType::DataTable(_) => 7,
-        MimeType::Html(_) => 6,
-        MimeType::Json(_) => 5,
-        MimeType::Png(_) => 4,
-        MimeType::Jpeg(_) => 3,
-        MimeType::Markdown(_) => 2,
-        MimeType::Plain(_) => 1,
+        MimeType::DataTable(_) => Some("application/vnd.dataresource+json"),
+        MimeType::Html(_) => Some("text/html"),
+        MimeType::Json(_) => Some("application/json"),
+        MimeType::Svg(_) => Some(SVG_MIME_TYPE),
+        MimeType::Png(_) => Some("image/png"),
+        MimeType::Jpeg(_) => Some("image/jpeg"),
+        MimeType::Markdown(_) => Some("text/markdown"),
+        MimeType::Latex(_) => Some("text/latex"),
+        MimeType::Plain(_) => Some("text/plain"),
         // All other media types are not supported in Zed at this time
-        _ => 0,
+        _ => None,
     }
 }
 
+/// Returns the MIME type and JSON payload of one of a bundle's
+/// representations, as they appear in the bundle's serialized form.
+fn serialize_representation(mimetype: &MimeType) -> Option<(String, serde_json::Value)> {
+    let mut bundle = MimeBundle::default();
+    bundle.content.push(mimetype.clone());
+    let serde_json::Value::Object(payloads) = serde_json::to_value(bundle).ok()? else {
+        return None;
+    };
+    payloads.into_iter().next()
+}
+
+/// Picks which of a bundle's MIME types to show: the first one in `priority`
+/// that the bundle has. Only one representation of a bundle is ever shown.
+fn select_mime_type<'a>(
+    available: &[&str],
+    priority: &'a [String],
+    prefer_svg_images: bool,
+) -> Option<&'a str> {
+    priority
+        .iter()
+        .map(String::as_str)
+        .filter(|mime_type| prefer_svg_images || *mime_type != SVG_MIME_TYPE)
+        .find(|mime_type| available.contains(mime_type))
+}
+
 pub(crate) trait OutputContent {
     fn clipboard_content(&self, window: &Window, cx: &App) -> Option<ClipboardItem>;
     fn has_clipboard_content(&self, _window: &Window, _cx: &App) -> bool {
@@ -136,6 +187,11 @@ pub enum Output {
         content: Entity<JsonView>,
         display_id: Option<String>,
     },
+    /// Shown by a renderer registered for one of the bundle's MIME types.
+    Rendered {
+        output: RenderedOutput,
+        display_id: Option<String>,
+    },
     ClearOutputWaitMarker,
 }
 
@@ -173,7 +229,8 @@ impl Output {
             Output::Image { .. }
             | Output::Markdown { .. }
             | Output::Table { .. }
-            | Output::Json { .. } => None,
+            | Output::Json { .. }
+            | Output::Rendered { .. } => None,
             Output::Message(_) => None,
             Output::ClearOutputWaitMarker => None,
         }
@@ -262,6 +319,7 @@ impl Output {
             Self::Message(message) => Some(div().child(message.clone()).into_any_element()),
             Self::Table { content, .. } => Some(content.clone().into_any_element()),
             Self::Json { content, .. } => Some(content.clone().into_any_element()),
+            Self::Rendered { output, .. } => Some(output.view.clone().into_any_element()),
             Self::ErrorOutput(error_view) => error_view.render(window, cx),
             Self::ClearOutputWaitMarker => None,
         }
@@ -306,12 +364,43 @@ impl Output {
                 Self::Stream { content, .. } => {
                     Self::render_output_controls(content.clone(), workspace, window, cx)
                 }
-                Self::Image { content, .. } => {
-                    Self::render_output_controls(content.clone(), workspace, window, cx)
-                }
+                Self::Image { content, .. } => Some(
+                    h_flex()
+                        .children(Self::render_output_controls(
+                            content.clone(),
+                            workspace.clone(),
+                            window,
+                            cx,
+                        ))
+                        .child(
+                            IconButton::new(
+                                ElementId::Name("save-image-as".into()),
+                                IconName::Download,
+                            )
+                            .style(ButtonStyle::Transparent)
+                            .tooltip(Tooltip::text("Save Image As…"))
+                            .on_click({
+                                let content = content.clone();
+                                move |_, _, cx| {
+                                    content
+                                        .update(cx, |content, cx| content.save_as(&workspace, cx))
+                                }
+                            }),
+                        )
+                        .into_any_element(),
+                ),
                 Self::Json { content, .. } => {
                     Self::render_output_controls(content.clone(), workspace, window, cx)
                 }
+                Self::Rendered { output, .. } => output.copy_text.clone().map(|copy_text| {
+                    h_flex()
+                        .pl_1()
+                        .child(
+                            CopyButton::new("copy-rendered-output", copy_text)
+                                .tooltip_label(output.copy_label.clone()),
+                        )
+                        .into_any_element()
+                }),
                 Self::ErrorOutput(err) => Some(
                     h_flex()
                         .pl_1()
@@ -387,6 +476,7 @@ impl Output {
             Output::Table { display_id, .. } => display_id.clone(),
             Output::Markdown { display_id, .. } => display_id.clone(),
             Output::Json { display_id, .. } => display_id.clone(),
+            Output::Rendered { display_id, .. } => display_id.clone(),
             Output::ClearOutputWaitMarker => None,
         }
     }
@@ -394,10 +484,37 @@ impl Output {
     pub fn new(
         data: &MimeBundle,
         display_id: Option<String>,
+        html_trust: HtmlTrust,
         window: &mut Window,
         cx: &mut App,
     ) -> Self {
-        match data.richest(rank_mime_type) {
+        let settings = ReplSettings::get_global(cx);
+        let image_limits = ImageLimits::from_settings(settings);
+        let available = data
+            .content
+            .iter()
+            .filter_map(mime_type_name)
+            .collect::<Vec<_>>();
+        let builtin = select_mime_type(
+            &available,
+            &settings.output_mime_priority,
+            settings.prefer_svg_images,
+        )
+        .map(str::to_string);
+
+        if let Some(output) =
+            Self::render_with_registered_renderer(data, builtin.as_deref(), window, cx)
+        {
+            return Output::Rendered { output, display_id };
+        }
+
+        let selected = builtin.as_deref().and_then(|selected| {
+            data.content
+                .iter()
+                .find(|mimetype| mime_type_name(mimetype) == Some(selected))
+        });
+
+        match selected {
             Some(MimeType::Json(json_value)) => match JsonView::from_value(json_value.clone()) {
                 Ok(json_view) => Output::Json {
                     content: cx.new(|_| json_view),
@@ -416,34 +533,201 @@ impl Output {
                     display_id,
                 }
             }
-            Some(MimeType::Png(data)) | Some(MimeType::Jpeg(data)) => match ImageView::from(data) {
-                Ok(view) => Output::Image {
-                    content: cx.new(|_| view),
+            Some(MimeType::Latex(latex)) => {
+                let content = cx.new(|cx| MarkdownView::from_latex(latex, cx));
+                Output::Markdown {
+                    content,
                     display_id,
-                },
-                Err(error) => Output::Message(format!("Failed to load image: {}", error)),
-            },
+                }
+            }
+            Some(MimeType::Png(encoded)) | Some(MimeType::Jpeg(encoded)) => {
+                Self::image_or_fallback(
+                    ImageView::from(encoded, image_limits),
+                    data,
+                    display_id,
+                    window,
+                    cx,
+                )
+            }
+            Some(MimeType::Svg(svg)) => Self::image_or_fallback(
+                ImageView::from_svg(svg, image_limits, cx),
+                data,
+                display_id,
+                window,
+                cx,
+            ),
             Some(MimeType::DataTable(data)) => Output::Table {
                 content: cx.new(|cx| TableView::new(data, window, cx)),
                 display_id,
             },
-            Some(MimeType::Html(html_content)) => match html::html_to_markdown(html_content) {
-                Ok(markdown_text) => {
-                    let content = cx.new(|cx| MarkdownView::from(markdown_text, cx));
-                    Output::Markdown {
-                        content,
-                        display_id,
+            Some(MimeType::Html(html_content)) => {
+                let sanitized = match html_trust {
+                    HtmlTrust::Trusted => None,
+                    HtmlTrust::Untrusted => Some(html::sanitize_html(html_content)),
+                };
+                let html_content = sanitized
+                    .as_ref()
+                    .map_or(html_content.as_str(), |sanitized| sanitized.html.as_str());
+                let removed_content = sanitized
+                    .as_ref()
+                    .is_some_and(|sanitized| sanitized.removed_content);
+                match html::html_to_markdown(html_content) {
+                    Ok(mut markdown_text) => {
+                        if removed_content {
+                            markdown_text.push_str("\n\n");
+                            markdown_text.push_str(REMOVED_HTML_NOTICE);
+                        }
+                        let content = cx.new(|cx| MarkdownView::from(markdown_text, cx));
+                        Output::Markdown {
+                            content,
+                            display_id,
+                        }
                     }
+                    Err(_) => Output::Plain {
+                        content: cx.new(|cx| TerminalOutput::from(html_content, window, cx)),
+                        display_id,
+                    },
                 }
-                Err(_) => Output::Plain {
-                    content: cx.new(|cx| TerminalOutput::from(html_content, window, cx)),
-                    display_id,
-                },
-            },
+            }
             // Any other media types are not supported
             _ => Output::Message("Unsupported media type".to_string()),
         }
     }
+
+    /// Shows a bundle with the first registered renderer for one of its MIME
+    /// types that succeeds, or `None` to show its built-in representation.
+    fn render_with_registered_renderer(
+        data: &MimeBundle,
+        builtin: Option<&str>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Option<RenderedOutput> {
+        let registry = cx.try_global::<MimeRendererRegistry>()?;
+        let claims = registry.claims();
+        if claims.is_empty() {
+            return None;
+        }
+        // Only types Zed can't name itself are serialized up front; the rest
+        // are serialized once a renderer is about to be given them.
+        let mut representations = data
+            .content
+            .iter()
+            .filter_map(|mimetype| match mime_type_name(mimetype) {
+                Some(name) => Some((name.to_string(), mimetype, None)),
+                None => {
+                    let (name, payload) = serialize_representation(mimetype)?;
+                    Some((name, mimetype, Some(payload)))
+                }
+            })
+            .collect::<Vec<_>>();
+        let bundle = representations
+            .iter()
+            .map(|(name, _, _)| name.as_str())
+            .collect::<Vec<_>>();
+        let renderers = renderers::resolve_representations(&bundle, &claims, builtin)
+            .into_iter()
+            .map_while(|representation| match representation {
+                Representation::Renderer { id, mime_type } => Some((id, mime_type)),
+                Representation::Builtin(_) => None,
+            })
+            .filter_map(|(id, mime_type)| Some((registry.renderer(id)?, mime_type.to_string())))
+            .collect::<Vec<_>>();
+
+        for (renderer, mime_type) in renderers {
+            let Some((_, mimetype, payload)) = representations
+                .iter_mut()
+                .find(|(name, _, _)| *name == mime_type)
+            else {
+                continue;
+            };
+            if payload.is_none() {
+                *payload = serialize_representation(*mimetype).map(|(_, payload)| payload);
+            }
+            let Some(payload) = payload.as_ref() else {
+                continue;
+            };
+            match renderer.render(&mime_type, payload, window, cx) {
+                Ok(output) => return Some(output),
+                Err(error) => log::warn!("failed to render {mime_type} output: {error:#}"),
+            }
+        }
+        None
+    }
+
+    /// Shows the image, or when it couldn't be loaded, the bundle's plain text
+    /// fallback so that a bad image doesn't lose the execution's output.
+    fn image_or_fallback(
+        image: anyhow::Result<ImageView>,
+        data: &MimeBundle,
+        display_id: Option<String>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Self {
+        let error = match image {
+            Ok(view) => {
+                return Output::Image {
+                    content: cx.new(|_| view),
+                    display_id,
+                };
+            }
+            Err(error) => error,
+        };
+
+        log::warn!("failed to load image output: {error:#}");
+        let fallback = data.content.iter().find_map(|mimetype| match mimetype {
+            MimeType::Plain(text) => Some(text),
+            _ => None,
+        });
+        match fallback {
+            Some(text) => Output::Plain {
+                content: cx.new(|cx| TerminalOutput::from(text, window, cx)),
+                display_id,
+            },
+            None => Output::Message(format!("Failed to load image: {error}")),
+        }
+    }
+}
+
+/// Adds a new output, first carrying out a `clear_output(wait=True)` that was
+/// waiting for it.
+pub(crate) fn push_output(outputs: &mut Vec<Output>, output: Output) {
+    if let Some(Output::ClearOutputWaitMarker) = outputs.last() {
+        outputs.clear();
+    }
+    outputs.push(output);
+}
+
+/// Handles a `clear_output` message. With `wait`, the outputs stay until the
+/// next output arrives and replaces them, which keeps animations from
+/// flickering.
+pub(crate) fn clear_outputs(outputs: &mut Vec<Output>, wait: bool) {
+    if !wait {
+        outputs.clear();
+    } else if !matches!(outputs.last(), Some(Output::ClearOutputWaitMarker)) {
+        outputs.push(Output::ClearOutputWaitMarker);
+    }
+}
+
+/// Replaces the outputs shown under `display_id` in place. Returns whether any
+/// output was updated.
+///
+/// Updates aren't new outputs, so they don't carry out a pending clear.
+pub(crate) fn update_display_data(
+    outputs: &mut [Output],
+    data: &MimeBundle,
+    display_id: &str,
+    html_trust: HtmlTrust,
+    window: &mut Window,
+    cx: &mut App,
+) -> bool {
+    let mut any = false;
+    for output in outputs {
+        if output.display_id().as_deref() == Some(display_id) {
+            *output = Output::new(data, Some(display_id.to_owned()), html_trust, window, cx);
+            any = true;
+        }
+    }
+    any
 }
 
 #[derive(Default, Clone, Debug)]
@@ -458,10 +742,46 @@ pub enum ExecutionStatus {
     Shutdown,
     KernelErrored(String),
     Restarting,
+    /// The kernel went away (restart or shutdown) before this execution
+    /// finished, so no reply will ever arrive for it.
+    Aborted,
+    /// The execution finished because the user interrupted it.
+    Interrupted,
+}
+
+impl ExecutionStatus {
+    /// Whether the execution is still waiting on the kernel to finish it.
+    pub fn is_in_flight(&self) -> bool {
+        match self {
+            ExecutionStatus::Unknown
+            | ExecutionStatus::ConnectingToKernel
+            | ExecutionStatus::Queued
+            | ExecutionStatus::Executing
+            | ExecutionStatus::Restarting => true,
+            ExecutionStatus::Finished
+            | ExecutionStatus::ShuttingDown
+            | ExecutionStatus::Shutdown
+            | ExecutionStatus::KernelErrored(_)
+            | ExecutionStatus::Aborted
+            | ExecutionStatus::Interrupted => false,
+        }
+    }
+}
+
+/// Text a kernel asked to show in a pager rather than inline, such as the
+/// help IPython prints for `df?`. Sent as a `page` payload on `execute_reply`.
+#[derive(Clone, Debug)]
+pub struct PagedOutput {
+    pub data: MimeBundle,
+    /// The line the pager should open at.
+    pub start: usize,
 }
 
 pub struct ExecutionViewFinishedEmpty;
 pub struct ExecutionViewFinishedSmall(pub String);
+/// Asks the session to read the execution's outputs back from disk, after
+/// they were dropped from the view to save memory.
+pub struct ShowSpilledOutputs;
 
 pub struct InputReplyEvent {
     pub value: String,
@@ -482,26 +802,138 @@ pub struct ExecutionView {
     #[allow(unused)]
     workspace: WeakEntity<Workspace>,
     pub outputs: Vec<Output>,
+    /// Pager text from the kernel's reply, which is also shown with the
+    /// outputs until there is somewhere else to show it.
+    pub paged_outputs: Vec<PagedOutput>,
     pub status: ExecutionStatus,
     pending_input: Option<PendingInput>,
+    /// Whether the kernel sent its `execute_reply` on the shell channel.
+    reply_received: bool,
+    /// Whether the kernel went idle for this execution on the iopub channel.
+    /// The two channels race, so outputs can still arrive after either one.
+    idle_received: bool,
+    stream_limiter: StreamLimiter,
+    /// The file the execution's stream output is also logged to.
+    pub output_log_path: Option<PathBuf>,
+    html_trust: HtmlTrust,
+    /// Whether the error the execution raised was caused by the user
+    /// interrupting it.
+    interrupted: bool,
+    /// Whether the outputs were dropped after the execution's record spilled
+    /// them to disk.
+    outputs_spilled: bool,
 }
 
 impl EventEmitter<ExecutionViewFinishedEmpty> for ExecutionView {}
 impl EventEmitter<ExecutionViewFinishedSmall> for ExecutionView {}
 impl EventEmitter<InputReplyEvent> for ExecutionView {}
+impl EventEmitter<ShowSpilledOutputs> for ExecutionView {}
 
 impl ExecutionView {
     pub fn new(
         status: ExecutionStatus,
         workspace: WeakEntity<Workspace>,
+        html_trust: HtmlTrust,
         _cx: &mut Context<Self>,
     ) -> Self {
         Self {
             workspace,
             outputs: Default::default(),
+            paged_outputs: Vec::new(),
             status,
             pending_input: None,
+            reply_received: false,
+            idle_received: false,
+            stream_limiter: StreamLimiter::default(),
+            output_log_path: None,
+            html_trust,
+            interrupted: false,
+            outputs_spilled: false,
+        }
+    }
+
+    pub fn outputs_spilled(&self) -> bool {
+        self.outputs_spilled
+    }
+
+    /// Drops the outputs shown, once the execution's record has spilled its
+    /// large ones to disk, leaving a button to read them back. Executions
+    /// that are still running keep theirs.
+    pub fn unload_outputs(&mut self, cx: &mut Context<Self>) {
+        if self.status.is_in_flight() || self.outputs.is_empty() {
+            return;
+        }
+        self.outputs.clear();
+        self.outputs_spilled = true;
+        cx.notify();
+    }
+
+    /// Shows the execution's outputs again, as its record has them once
+    /// they're read back from disk.
+    pub fn show_recorded_outputs(
+        &mut self,
+        outputs: &[RecordedOutput],
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let html_trust = self.html_trust;
+        let interrupted = self.interrupted;
+        let mut shown = Vec::with_capacity(outputs.len() + self.paged_outputs.len());
+        for output in outputs {
+            match output {
+                RecordedOutput::Stream { text, .. } => shown.push(Output::Stream {
+                    content: cx.new(|cx| TerminalOutput::from(text, window, cx)),
+                }),
+                RecordedOutput::Data {
+                    data, display_id, ..
+                } => shown.push(Output::new(
+                    data,
+                    display_id.clone(),
+                    html_trust,
+                    window,
+                    cx,
+                )),
+                RecordedOutput::Error {
+                    ename,
+                    evalue,
+                    traceback,
+                } => shown.push(Output::ErrorOutput(ErrorView {
+                    ename: ename.clone(),
+                    evalue: evalue.clone(),
+                    traceback: cx.new(|cx| TerminalOutput::from(&traceback.join("\n"), window, cx)),
+                    interrupted,
+                })),
+                RecordedOutput::Spilled(_) => {}
+            }
+        }
+        for paged_output in &self.paged_outputs {
+            shown.push(Output::new(
+                &paged_output.data,
+                None,
+                html_trust,
+                window,
+                cx,
+            ));
         }
+        self.outputs = shown;
+        self.outputs_spilled = false;
+        cx.notify();
+    }
+
+    /// Marks the error the execution raised, or is about to raise, as caused
+    /// by the user interrupting it. Its traceback is folded away, and the
+    /// execution ends as interrupted rather than finished.
+    pub fn mark_interrupted(&mut self, cx: &mut Context<Self>) {
+        self.interrupted = true;
+        for output in &mut self.outputs {
+            if let Output::ErrorOutput(error_view) = output {
+                error_view.interrupted = true;
+            }
+        }
+        if matches!(self.status, ExecutionStatus::Finished) {
+            self.status = ExecutionStatus::Interrupted;
+        }
+        cx.notify();
     }
 
     fn submit_input(&mut self, _window: &mut Window, cx: &mut Context<Self>) {
@@ -564,18 +996,24 @@ impl ExecutionView {
             JupyterMessageContent::ExecuteResult(result) => Output::new(
                 &result.data,
                 result.transient.as_ref().and_then(|t| t.display_id.clone()),
+                self.html_trust,
                 window,
                 cx,
             ),
             JupyterMessageContent::DisplayData(result) => Output::new(
                 &result.data,
                 result.transient.as_ref().and_then(|t| t.display_id.clone()),
+                self.html_trust,
                 window,
                 cx,
             ),
             JupyterMessageContent::StreamContent(result) => {
+                let text = self.stream_limiter.limit(&result.text);
+                if text.is_empty() {
+                    return;
+                }
                 // Previous stream data will combine together, handling colors, carriage returns, etc
-                if let Some(new_terminal) = self.apply_terminal_text(&result.text, window, cx) {
+                if let Some(new_terminal) = self.apply_terminal_text(&text, window, cx) {
                     new_terminal
                 } else {
                     return;
@@ -589,27 +1027,30 @@ impl ExecutionView {
                     ename: result.ename.clone(),
                     evalue: result.evalue.clone(),
                     traceback: terminal,
+                    interrupted: self.interrupted,
                 })
             }
             JupyterMessageContent::ExecuteReply(reply) => {
                 for payload in reply.payload.iter() {
-                    if let runtimelib::Payload::Page { data, .. } = payload {
-                        let output = Output::new(data, None, window, cx);
-                        self.outputs.push(output);
+                    if let runtimelib::Payload::Page { data, start } = payload {
+                        self.outputs
+                            .push(Output::new(data, None, self.html_trust, window, cx));
+                        self.paged_outputs.push(PagedOutput {
+                            data: data.clone(),
+                            start: *start,
+                        });
                     }
                 }
+                self.reply_received = true;
+                self.finish_if_complete(cx);
                 cx.notify();
                 return;
             }
             JupyterMessageContent::ClearOutput(options) => {
-                if !options.wait {
-                    self.outputs.clear();
-                    cx.notify();
-                    return;
-                }
-
-                // Create a marker to clear the output after we get in a new output
-                Output::ClearOutputWaitMarker
+                clear_outputs(&mut self.outputs, options.wait);
+                self.stream_limiter = StreamLimiter::default();
+                cx.notify();
+                return;
             }
             JupyterMessageContent::InputRequest(_) => {
                 // InputRequest is handled by handle_input_request which needs the full message
@@ -621,15 +1062,9 @@ impl ExecutionView {
                         self.status = ExecutionStatus::Executing;
                     }
                     ExecutionState::Idle => {
-                        self.status = ExecutionStatus::Finished;
+                        self.idle_received = true;
                         self.pending_input = None;
-                        if self.outputs.is_empty() {
-                            cx.emit(ExecutionViewFinishedEmpty);
-                        } else if ReplSettings::get_global(cx).inline_output {
-                            if let Some(small_text) = self.get_small_inline_output(cx) {
-                                cx.emit(ExecutionViewFinishedSmall(small_text));
-                            }
-                        }
+                        self.finish_if_complete(cx);
                     }
                     ExecutionState::Unknown => self.status = ExecutionStatus::Unknown,
                     ExecutionState::Starting => self.status = ExecutionStatus::ConnectingToKernel,
@@ -647,16 +1082,34 @@ impl ExecutionView {
             }
         };
 
-        // Check for a clear output marker as the previous output, so we can clear it out
-        if let Some(output) = self.outputs.last()
-            && let Output::ClearOutputWaitMarker = output
-        {
-            self.outputs.clear();
-        }
+        push_output(&mut self.outputs, output);
+        cx.notify();
+    }
 
-        self.outputs.push(output);
+    /// Marks the execution finished once both its reply and its idle status
+    /// have arrived, whichever came last.
+    fn finish_if_complete(&mut self, cx: &mut Context<Self>) {
+        if !self.reply_received || !self.idle_received {
+            return;
+        }
 
-        cx.notify();
+        self.status = if self.interrupted {
+            ExecutionStatus::Interrupted
+        } else {
+            ExecutionStatus::Finished
+        };
+        // No more outputs are coming to replace what a waiting clear would
+        // have cleared, so they stay.
+        if let Some(Output::ClearOutputWaitMarker) = self.outputs.last() {
+            self.outputs.pop();
+        }
+        if self.outputs.is_empty() {
+            cx.emit(ExecutionViewFinishedEmpty);
+        } else if ReplSettings::get_global(cx).inline_output {
+            if let Some(small_text) = self.get_small_inline_output(cx) {
+                cx.emit(ExecutionViewFinishedSmall(small_text));
+            }
+        }
     }
 
     pub fn update_display_data(
@@ -666,18 +1119,14 @@ impl ExecutionView {
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        let mut any = false;
-
-        self.outputs.iter_mut().for_each(|output| {
-            if let Some(other_display_id) = output.display_id().as_ref()
-                && other_display_id == display_id
-            {
-                *output = Output::new(data, Some(display_id.to_owned()), window, cx);
-                any = true;
-            }
-        });
-
-        if any {
+        if update_display_data(
+            &mut self.outputs,
+            data,
+            display_id,
+            self.html_trust,
+            window,
+            cx,
+        ) {
             cx.notify();
         }
     }
@@ -739,7 +1188,7 @@ impl ExecutionView {
 
 impl ExecutionView {
     #[cfg(test)]
-    fn output_as_stream_text(&self, cx: &App) -> Option<String> {
+    pub(crate) fn output_as_stream_text(&self, cx: &App) -> Option<String> {
         self.outputs.iter().find_map(|output| {
             if let Output::Stream { content } = output {
                 Some(content.read(cx).full_text())
@@ -784,10 +1233,28 @@ impl Render for ExecutionView {
             ExecutionStatus::Queued => Label::new("Queued...")
                 .color(Color::Muted)
                 .into_any_element(),
+            ExecutionStatus::Aborted => {
+                Label::new("Aborted").color(Color::Muted).into_any_element()
+            }
+            ExecutionStatus::Interrupted => Label::new("Interrupted")
+                .color(Color::Muted)
+                .into_any_element(),
             ExecutionStatus::KernelErrored(error) => Label::new(format!("Kernel error: {}", error))
                 .color(Color::Error)
                 .into_any_element(),
         };
+        let status = match &self.output_log_path {
+            Some(path) if self.status.is_in_flight() => h_flex()
+                .gap_2()
+                .child(status)
+                .child(
+                    Label::new(format!("Logging output to {}", path.display()))
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+                .into_any_element(),
+            _ => status,
+        };
 
         let pending_input_element = self.pending_input.as_ref().map(|pending_input| {
             let prompt_label = if pending_input.prompt.is_empty() {
@@ -817,6 +1284,23 @@ impl Render for ExecutionView {
                 )
         });
 
+        if self.outputs_spilled {
+            return h_flex()
+                .min_h(window.line_height())
+                .gap_2()
+                .child(
+                    Label::new("Outputs moved to disk to save memory")
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+                .child(
+                    Button::new("show-spilled-outputs", "Show Outputs")
+                        .label_size(LabelSize::Small)
+                        .on_click(cx.listener(|_, _, _, cx| cx.emit(ShowSpilledOutputs))),
+                )
+                .into_any_element();
+        }
+
         if self.outputs.is_empty() && pending_input_element.is_none() {
             return v_flex()
                 .min_h(window.line_height())
@@ -854,39 +1338,121 @@ mod tests {
     use std::path::Path;
     use std::sync::Arc;
 
+    fn default_mime_priority() -> Vec<String> {
+        [
+            "application/vnd.dataresource+json",
+            "text/html",
+            "application/json",
+            "image/svg+xml",
+            "image/png",
+            "image/jpeg",
+            "text/markdown",
+            "text/latex",
+            "text/plain",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect()
+    }
+
     #[test]
-    fn test_rank_mime_type_ordering() {
-        let data_table = MimeType::DataTable(Box::default());
-        let html = MimeType::Html(String::new());
-        let json = MimeType::Json(serde_json::json!({}));
-        let png = MimeType::Png(String::new());
-        let jpeg = MimeType::Jpeg(String::new());
-        let markdown = MimeType::Markdown(String::new());
-        let plain = MimeType::Plain(String::new());
-
-        assert_eq!(rank_mime_type(&data_table), 7);
-        assert_eq!(rank_mime_type(&html), 6);
-        assert_eq!(rank_mime_type(&json), 5);
-        assert_eq!(rank_mime_type(&png), 4);
-        assert_eq!(rank_mime_type(&jpeg), 3);
-        assert_eq!(rank_mime_type(&markdown), 2);
-        assert_eq!(rank_mime_type(&plain), 1);
-
-        assert!(rank_mime_type(&data_table) > rank_mime_type(&html));
-        assert!(rank_mime_type(&html) > rank_mime_type(&json));
-        assert!(rank_mime_type(&json) > rank_mime_type(&png));
-        assert!(rank_mime_type(&png) > rank_mime_type(&jpeg));
-        assert!(rank_mime_type(&jpeg) > rank_mime_type(&markdown));
-        assert!(rank_mime_type(&markdown) > rank_mime_type(&plain));
+    fn test_select_mime_type_ordering() {
+        let priority = default_mime_priority();
+        let select = |available: &[&str]| select_mime_type(available, &priority, true);
+
+        assert_eq!(
+            select(&[
+                "text/plain",
+                "text/html",
+                "application/vnd.dataresource+json"
+            ]),
+            Some("application/vnd.dataresource+json")
+        );
+        assert_eq!(
+            select(&["text/plain", "application/json", "text/html"]),
+            Some("text/html")
+        );
+        assert_eq!(
+            select(&["image/png", "application/json"]),
+            Some("application/json")
+        );
+        assert_eq!(select(&["image/jpeg", "image/png"]), Some("image/png"));
+        assert_eq!(select(&["text/markdown", "image/jpeg"]), Some("image/jpeg"));
+        assert_eq!(
+            select(&["text/plain", "text/markdown"]),
+            Some("text/markdown")
+        );
+        assert_eq!(select(&["text/plain", "text/latex"]), Some("text/latex"));
+        assert_eq!(
+            select(&["text/latex", "text/markdown", "text/plain"]),
+            Some("text/markdown")
+        );
+        assert_eq!(select(&["text/plain"]), Some("text/plain"));
+        assert_eq!(select(&[]), None);
     }
 
     #[test]
-    fn test_rank_mime_type_unsupported_returns_zero() {
+    fn test_select_mime_type_unsupported() {
         let svg = MimeType::Svg(String::new());
         let latex = MimeType::Latex(String::new());
+        let javascript = MimeType::Javascript(String::new());
+
+        assert_eq!(mime_type_name(&svg), Some("image/svg+xml"));
+        assert_eq!(mime_type_name(&latex), Some("text/latex"));
+        assert_eq!(mime_type_name(&javascript), None);
+
+        let priority = vec!["text/html".to_string(), "text/plain".to_string()];
+        assert_eq!(select_mime_type(&["text/latex"], &priority, true), None);
+    }
+
+    #[test]
+    fn test_serialize_representation() {
+        let data: MimeBundle = serde_json::from_value(serde_json::json!({
+            "application/vnd.vegalite.v5+json": {"mark": "bar"},
+            "text/plain": "alt.Chart(...)",
+        }))
+        .unwrap();
+        let mut representations = data
+            .content
+            .iter()
+            .filter_map(serialize_representation)
+            .collect::<Vec<_>>();
+        representations.sort_by(|(a, _), (b, _)| a.cmp(b));
+        assert_eq!(
+            representations,
+            [
+                (
+                    "application/vnd.vegalite.v5+json".to_string(),
+                    serde_json::json!({"mark": "bar"})
+                ),
+                (
+                    "text/plain".to_string(),
+                    serde_json::json!("alt.Chart(...)")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_select_mime_type_preferring_svg() {
+        let priority = default_mime_priority();
 
-        assert_eq!(rank_mime_type(&svg), 0);
-        assert_eq!(rank_mime_type(&latex), 0);
+        assert_eq!(
+            select_mime_type(&["image/png", "image/svg+xml"], &priority, true),
+            Some("image/svg+xml")
+        );
+        assert_eq!(
+            select_mime_type(&["image/png", "image/svg+xml"], &priority, false),
+            Some("image/png")
+        );
+        assert_eq!(
+            select_mime_type(&["application/json", "image/svg+xml"], &priority, true),
+            Some("application/json")
+        );
+        assert_eq!(
+            select_mime_type(&["image/svg+xml", "text/plain"], &priority, false),
+            Some("text/plain")
+        );
     }
 
     async fn init_test(
@@ -914,7 +1480,14 @@ mod tests {
         weak_workspace: WeakEntity<workspace::Workspace>,
     ) -> Entity<ExecutionView> {
         cx.update(|_window, cx| {
-            cx.new(|cx| ExecutionView::new(ExecutionStatus::Queued, weak_workspace, cx))
+            cx.new(|cx| {
+                ExecutionView::new(
+                    ExecutionStatus::Queued,
+                    weak_workspace,
+                    HtmlTrust::Trusted,
+                    cx,
+                )
+            })
         })
     }
 
@@ -1069,6 +1642,236 @@ mod tests {
         });
     }
 
+    fn display_data(text: &str, display_id: &str) -> JupyterMessageContent {
+        JupyterMessageContent::DisplayData(
+            serde_json::from_value(serde_json::json!({
+                "data": { "text/plain": text },
+                "metadata": {},
+                "transient": { "display_id": display_id },
+            }))
+            .unwrap(),
+        )
+    }
+
+    fn stdout(text: &str) -> JupyterMessageContent {
+        JupyterMessageContent::StreamContent(StreamContent {
+            name: Stdio::Stdout,
+            text: text.to_string(),
+        })
+    }
+
+    fn execute_reply() -> JupyterMessageContent {
+        JupyterMessageContent::ExecuteReply(
+            serde_json::from_value(serde_json::json!({ "status": "ok", "execution_count": 1 }))
+                .unwrap(),
+        )
+    }
+
+    fn output_texts(view: &ExecutionView, cx: &App) -> Vec<String> {
+        view.outputs
+            .iter()
+            .map(|output| match output {
+                Output::Stream { content } | Output::Plain { content, .. } => {
+                    content.read(cx).full_text().trim().to_string()
+                }
+                Output::ClearOutputWaitMarker => "<clear wait>".to_string(),
+                _ => "<other>".to_string(),
+            })
+            .collect()
+    }
+
+    #[gpui::test]
+    async fn test_clear_output_wait_sequence(cx: &mut TestAppContext) {
+        let (mut cx, workspace) = init_test(cx).await;
+        let execution_view = create_execution_view(&mut cx, workspace);
+
+        cx.update(|window, cx| {
+            execution_view.update(cx, |view, cx| {
+                view.push_message(&stdout("starting\n"), window, cx);
+                view.push_message(
+                    &JupyterMessageContent::ClearOutput(ClearOutput { wait: true }),
+                    window,
+                    cx,
+                );
+                view.push_message(
+                    &JupyterMessageContent::ClearOutput(ClearOutput { wait: true }),
+                    window,
+                    cx,
+                );
+                assert_eq!(
+                    output_texts(view, cx),
+                    ["starting", "<clear wait>"],
+                    "outputs stay until something replaces them"
+                );
+
+                view.push_message(&display_data("50%", "progress"), window, cx);
+                view.push_message(&stdout("halfway\n"), window, cx);
+                assert_eq!(output_texts(view, cx), ["50%", "halfway"]);
+            });
+        });
+    }
+
+    #[gpui::test]
+    async fn test_clear_output_wait_with_display_updates(cx: &mut TestAppContext) {
+        let (mut cx, workspace) = init_test(cx).await;
+        let execution_view = create_execution_view(&mut cx, workspace);
+
+        cx.update(|window, cx| {
+            execution_view.update(cx, |view, cx| {
+                view.push_message(&display_data("0%", "progress"), window, cx);
+                view.push_message(
+                    &JupyterMessageContent::ClearOutput(ClearOutput { wait: true }),
+                    window,
+                    cx,
+                );
+
+                let mut data = MimeBundle::default();
+                data.content.push(MimeType::Plain("10%".to_string()));
+                view.update_display_data(&data, "progress", window, cx);
+                assert_eq!(
+                    output_texts(view, cx),
+                    ["10%", "<clear wait>"],
+                    "display updates don't carry out a waiting clear"
+                );
+
+                view.push_message(&display_data("20%", "progress"), window, cx);
+                assert_eq!(output_texts(view, cx), ["20%"]);
+                assert_eq!(view.outputs[0].display_id().as_deref(), Some("progress"));
+
+                view.push_message(
+                    &JupyterMessageContent::ClearOutput(ClearOutput { wait: false }),
+                    window,
+                    cx,
+                );
+                view.update_display_data(&data, "progress", window, cx);
+                assert!(
+                    view.outputs.is_empty(),
+                    "cleared outputs no longer receive display updates"
+                );
+            });
+        });
+    }
+
+    #[gpui::test]
+    async fn test_clear_output_wait_kept_when_execution_finishes(cx: &mut TestAppContext) {
+        let (mut cx, workspace) = init_test(cx).await;
+        let execution_view = create_execution_view(&mut cx, workspace);
+
+        cx.update(|window, cx| {
+            execution_view.update(cx, |view, cx| {
+                view.push_message(&stdout("done\n"), window, cx);
+                view.push_message(
+                    &JupyterMessageContent::ClearOutput(ClearOutput { wait: true }),
+                    window,
+                    cx,
+                );
+                view.push_message(&execute_reply(), window, cx);
+                view.push_message(
+                    &JupyterMessageContent::Status(Status {
+                        execution_state: ExecutionState::Idle,
+                    }),
+                    window,
+                    cx,
+                );
+                assert_eq!(output_texts(view, cx), ["done"]);
+            });
+        });
+    }
+
+    #[gpui::test]
+    async fn test_corrupt_image_falls_back_to_plain_text(cx: &mut TestAppContext) {
+        let (mut cx, _workspace) = init_test(cx).await;
+
+        let data: MimeBundle = serde_json::from_value(serde_json::json!({
+            "image/png": "iVBORw0KGgoAAAANSUhEUgAA",
+            "text/plain": "<Figure size 640x480 with 1 Axes>",
+        }))
+        .unwrap();
+        let output =
+            cx.update(|window, cx| Output::new(&data, None, HtmlTrust::Trusted, window, cx));
+
+        match output {
+            Output::Plain { content, .. } => cx.update(|_, cx| {
+                assert_eq!(
+                    content.read(cx).full_text().trim(),
+                    "<Figure size 640x480 with 1 Axes>"
+                );
+            }),
+            other => panic!("expected Plain, got {:?}", std::mem::discriminant(&other)),
+        }
+    }
+
+    #[gpui::test]
+    async fn test_untrusted_html_is_sanitized(cx: &mut TestAppContext) {
+        let (mut cx, _workspace) = init_test(cx).await;
+
+        let data: MimeBundle = serde_json::from_value(serde_json::json!({
+            "text/html": "<p>Hello</p><script>alert(1)</script>",
+        }))
+        .unwrap();
+        let markdown_text = |html_trust: HtmlTrust, cx: &mut gpui::VisualTestContext| {
+            cx.update(
+                |window, cx| match Output::new(&data, None, html_trust, window, cx) {
+                    Output::Markdown { content, .. } => content
+                        .read(cx)
+                        .clipboard_content(window, cx)
+                        .and_then(|item| item.text())
+                        .unwrap_or_default(),
+                    other => panic!(
+                        "expected Markdown, got {:?}",
+                        std::mem::discriminant(&other)
+                    ),
+                },
+            )
+        };
+
+        let untrusted = markdown_text(HtmlTrust::Untrusted, &mut cx);
+        assert!(untrusted.starts_with("Hello"));
+        assert!(untrusted.ends_with(REMOVED_HTML_NOTICE));
+        assert!(!untrusted.contains("alert"));
+
+        let trusted = markdown_text(HtmlTrust::Trusted, &mut cx);
+        assert!(trusted.starts_with("Hello"));
+        assert!(!trusted.contains(REMOVED_HTML_NOTICE));
+    }
+
+    #[gpui::test]
+    async fn test_registered_renderer_takes_priority(cx: &mut TestAppContext) {
+        let (mut cx, _workspace) = init_test(cx).await;
+        cx.update(|_, cx| MimeRendererRegistry::init(cx));
+
+        let data: MimeBundle = serde_json::from_value(serde_json::json!({
+            "application/vnd.vegalite.v5+json": {"mark": "bar"},
+            "text/plain": "alt.Chart(...)",
+        }))
+        .unwrap();
+        let output =
+            cx.update(|window, cx| Output::new(&data, None, HtmlTrust::Trusted, window, cx));
+        match output {
+            Output::Rendered { output, .. } => {
+                assert_eq!(
+                    output.copy_text.as_deref(),
+                    Some("{\n  \"mark\": \"bar\"\n}")
+                );
+                assert_eq!(output.copy_label, "Copy Spec");
+            }
+            other => panic!(
+                "expected Rendered, got {:?}",
+                std::mem::discriminant(&other)
+            ),
+        }
+
+        // Vendor types without a renderer fall back as before.
+        let data: MimeBundle = serde_json::from_value(serde_json::json!({
+            "application/vnd.plotly.v1+json": {"data": []},
+            "text/plain": "Figure()",
+        }))
+        .unwrap();
+        let output =
+            cx.update(|window, cx| Output::new(&data, None, HtmlTrust::Trusted, window, cx));
+        assert!(matches!(output, Output::Plain { .. }));
+    }
+
     #[gpui::test]
     async fn test_push_message_status_transitions(cx: &mut TestAppContext) {
         let (mut cx, workspace) = init_test(cx).await;
@@ -1086,6 +1889,9 @@ mod tests {
                     execution_state: ExecutionState::Idle,
                 });
                 view.push_message(&idle, window, cx);
+                // Outputs may still be on their way until the reply arrives.
+                assert!(matches!(view.status, ExecutionStatus::Executing));
+                view.push_message(&execute_reply(), window, cx);
                 assert!(matches!(view.status, ExecutionStatus::Finished));
 
                 let starting = JupyterMessageContent::Status(Status {
@@ -1136,13 +1942,22 @@ mod tests {
         cx.update(|window, cx| {
             execution_view.update(cx, |view, cx| {
                 assert!(view.outputs.is_empty());
+                view.push_message(&execute_reply(), window, cx);
+            });
+        });
+        assert!(
+            !emitted.load(std::sync::atomic::Ordering::SeqCst),
+            "should not emit ExecutionViewFinishedEmpty before the kernel is idle"
+        );
+
+        cx.update(|window, cx| {
+            execution_view.update(cx, |view, cx| {
                 let idle = JupyterMessageContent::Status(Status {
                     execution_state: ExecutionState::Idle,
                 });
                 view.push_message(&idle, window, cx);
             });
         });
-
         assert!(
             emitted.load(std::sync::atomic::Ordering::SeqCst),
             "should emit ExecutionViewFinishedEmpty when idle with no outputs"
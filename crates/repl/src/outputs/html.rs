@@ -4,9 +4,262 @@ use html_to_markdown::markdown::{
     WebpageChromeRemover,
 };
 use html_to_markdown::{TagHandler, convert_html_to_markdown};
+use html5ever::tendril::TendrilSink;
+use html5ever::{Attribute, ParseOpts, ns, parse_document};
+use markup5ever_rcdom::{Handle, NodeData, RcDom};
 use std::cell::RefCell;
 use std::rc::Rc;
 
+/// Elements kept by [`sanitize_html`], which is enough for the HTML that
+/// libraries such as pandas and rich produce.
+const ALLOWED_ELEMENTS: &[&str] = &[
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "caption",
+    "code",
+    "col",
+    "colgroup",
+    "dd",
+    "del",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "q",
+    "s",
+    "samp",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "u",
+    "ul",
+    "var",
+];
+
+/// Elements removed by [`sanitize_html`] along with everything in them, as
+/// their contents would be code, styles, or embedded documents rather than
+/// text.
+const REMOVED_ELEMENTS: &[&str] = &[
+    "applet", "audio", "base", "button", "canvas", "embed", "form", "frame", "frameset", "iframe",
+    "input", "link", "math", "meta", "noembed", "noframes", "noscript", "object", "script",
+    "select", "style", "svg", "template", "textarea", "title", "video",
+];
+
+/// Elements that have no contents and so no end tag.
+const VOID_ELEMENTS: &[&str] = &["br", "col", "hr", "img"];
+
+/// Attributes kept on any allowed element, besides the ones checked by
+/// [`sanitize_attribute`].
+const ALLOWED_ATTRIBUTES: &[&str] = &[
+    "align", "alt", "border", "class", "colspan", "height", "open", "rowspan", "scope", "span",
+    "start", "title", "type", "valign", "width",
+];
+
+/// HTML with anything that could run code, load content, or restyle the
+/// editor removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanitizedHtml {
+    pub html: String,
+    /// Whether anything other than harmless attributes was removed, so the
+    /// output can say it's incomplete.
+    pub removed_content: bool,
+}
+
+/// Restricts `html` to a conservative set of formatting elements and
+/// attributes. Scripts, styles, frames, embedded objects, and forms are
+/// removed with their contents, event handler attributes and links to
+/// anything but web pages and email addresses are dropped, and elements that
+/// aren't allowed are replaced by their contents.
+///
+/// The markup is parsed the way a browser would, so malformed or nested
+/// markup and encoded attribute values are checked as a browser would read
+/// them.
+pub fn sanitize_html(html: &str) -> SanitizedHtml {
+    let dom = parse_document(RcDom::default(), ParseOpts::default()).one(html);
+    let mut sanitized = SanitizedHtml {
+        html: String::new(),
+        removed_content: false,
+    };
+    sanitize_node(&dom.document, &mut sanitized);
+    sanitized
+}
+
+fn sanitize_node(node: &Handle, sanitized: &mut SanitizedHtml) {
+    match &node.data {
+        NodeData::Document => sanitize_children(node, sanitized),
+        NodeData::Text { contents } => push_escaped(&mut sanitized.html, &contents.borrow(), false),
+        NodeData::Element { name, attrs, .. } => {
+            let tag = name.local.as_ref();
+            if name.ns != ns!(html) || REMOVED_ELEMENTS.contains(&tag) {
+                sanitized.removed_content = true;
+            } else if ALLOWED_ELEMENTS.contains(&tag) {
+                sanitized.html.push('<');
+                sanitized.html.push_str(tag);
+                for attribute in attrs.borrow().iter() {
+                    match sanitize_attribute(tag, attribute) {
+                        SanitizedAttribute::Kept(value) => {
+                            sanitized.html.push(' ');
+                            sanitized.html.push_str(attribute.name.local.as_ref());
+                            sanitized.html.push_str("=\"");
+                            push_escaped(&mut sanitized.html, value, true);
+                            sanitized.html.push('"');
+                        }
+                        SanitizedAttribute::Dropped => {}
+                        SanitizedAttribute::Removed => sanitized.removed_content = true,
+                    }
+                }
+                sanitized.html.push('>');
+                if !VOID_ELEMENTS.contains(&tag) {
+                    sanitize_children(node, sanitized);
+                    sanitized.html.push_str("</");
+                    sanitized.html.push_str(tag);
+                    sanitized.html.push('>');
+                }
+            } else {
+                // The parser adds these to every document, so they don't
+                // mean anything was removed.
+                if !matches!(tag, "html" | "head" | "body") {
+                    sanitized.removed_content = true;
+                }
+                sanitize_children(node, sanitized);
+            }
+        }
+        NodeData::Doctype { .. } | NodeData::Comment { .. } => {}
+        NodeData::ProcessingInstruction { .. } => sanitized.removed_content = true,
+    }
+}
+
+fn sanitize_children(node: &Handle, sanitized: &mut SanitizedHtml) {
+    for child in node.children.borrow().iter() {
+        sanitize_node(child, sanitized);
+    }
+}
+
+enum SanitizedAttribute<'a> {
+    Kept(&'a str),
+    /// Dropped without losing anything that was shown, such as an `id`.
+    Dropped,
+    /// Dropped because it could run code or load content.
+    Removed,
+}
+
+fn sanitize_attribute<'a>(tag: &str, attribute: &'a Attribute) -> SanitizedAttribute<'a> {
+    let name = attribute.name.local.as_ref();
+    let value = attribute.value.as_ref();
+    if attribute.name.ns != ns!() || name.starts_with("on") {
+        return SanitizedAttribute::Removed;
+    }
+    let is_safe = match (tag, name) {
+        ("a", "href") => is_safe_link(value),
+        ("img", "src") => is_safe_image_source(value),
+        (_, "style") => is_safe_style(value),
+        (_, "href" | "src" | "srcset" | "action" | "formaction" | "background" | "poster") => false,
+        (_, name) if ALLOWED_ATTRIBUTES.contains(&name) => true,
+        _ => return SanitizedAttribute::Dropped,
+    };
+    if is_safe {
+        SanitizedAttribute::Kept(value)
+    } else {
+        SanitizedAttribute::Removed
+    }
+}
+
+/// Returns `value` as a browser reads it when looking for a URL scheme, which
+/// ignores whitespace and control characters and any letter case.
+fn normalize_url(value: &str) -> String {
+    value
+        .chars()
+        .filter(|character| !character.is_whitespace() && !character.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+fn is_safe_link(value: &str) -> bool {
+    let url = normalize_url(value);
+    let scheme_end = url.find(|character| matches!(character, ':' | '/' | '?' | '#'));
+    match scheme_end {
+        Some(index) if url[index..].starts_with(':') => {
+            matches!(&url[..index], "http" | "https" | "mailto")
+        }
+        // Relative links and fragments stay within the page.
+        _ => true,
+    }
+}
+
+/// Only images embedded in the output are shown, so rendering an output
+/// never makes a request.
+fn is_safe_image_source(value: &str) -> bool {
+    let url = normalize_url(value);
+    [
+        "data:image/png",
+        "data:image/jpeg",
+        "data:image/gif",
+        "data:image/webp",
+    ]
+    .iter()
+    .any(|prefix| url.starts_with(prefix))
+}
+
+/// Allows inline styles such as colors and alignment, but nothing that can
+/// load content or hide what a value means behind escapes or comments.
+fn is_safe_style(value: &str) -> bool {
+    let style = normalize_url(value);
+    ![
+        "url(",
+        "expression(",
+        "@import",
+        "javascript:",
+        "\\",
+        "/*",
+        "<",
+        "behavior:",
+    ]
+    .iter()
+    .any(|pattern| style.contains(pattern))
+}
+
+fn push_escaped(html: &mut String, text: &str, in_attribute: bool) {
+    for character in text.chars() {
+        match character {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' if in_attribute => html.push_str("&quot;"),
+            character => html.push(character),
+        }
+    }
+}
+
 /// Convert HTML to Markdown for rendering in the REPL.
 pub fn html_to_markdown(html: &str) -> Result<String> {
     let mut handlers: Vec<TagHandler> = vec![
@@ -94,6 +347,131 @@ fn normalize_table_row(row: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sanitize_html_keeps_formatting() {
+        let html = r#"<table border="1" class="dataframe"><thead><tr style="text-align: right;"><th></th><th>A</th></tr></thead><tbody><tr><th>0</th><td>1 &lt; 2 &amp;&amp; x</td></tr></tbody></table>"#;
+        assert_eq!(
+            sanitize_html(html),
+            SanitizedHtml {
+                html: html.to_string(),
+                removed_content: false,
+            }
+        );
+
+        let html = r#"<pre style="white-space:pre;font-family:Menlo,monospace"><span style="color: #008000; font-weight: bold">True</span></pre>"#;
+        assert_eq!(sanitize_html(html).html, html);
+
+        // Comments, doctypes, and attributes that don't change what's shown
+        // are dropped without calling the output incomplete.
+        let sanitized =
+            sanitize_html(r#"<!DOCTYPE html><!-- note --><div id="T_1" data-x="1">text</div>"#);
+        assert_eq!(sanitized.html, "<div>text</div>");
+        assert!(!sanitized.removed_content);
+    }
+
+    #[test]
+    fn test_sanitize_html_removes_active_content() {
+        for (html, expected) in [
+            ("<script>alert(1)</script><p>after</p>", "<p>after</p>"),
+            ("<style>body { display: none }</style>text", "text"),
+            (r#"<iframe src="https://example.com"></iframe>text"#, "text"),
+            (
+                r#"<object data="x.swf"><embed src="x.swf"></object>text"#,
+                "text",
+            ),
+            ("<svg><script>alert(1)</script></svg>text", "text"),
+            ("<template><script>alert(1)</script></template>text", "text"),
+            (
+                r#"<div onclick="alert(1)" ONMOUSEOVER="alert(2)">text</div>"#,
+                "<div>text</div>",
+            ),
+            (r#"<img src="x" onerror="alert(1)">"#, "<img>"),
+            (r#"<img src="https://example.com/pixel.png">"#, "<img>"),
+            (r#"<font color="red">text</font>"#, "text"),
+            (r#"<form action="/"><input value="x"></form>text"#, "text"),
+        ] {
+            let sanitized = sanitize_html(html);
+            assert_eq!(sanitized.html, expected, "sanitizing {html}");
+            assert!(sanitized.removed_content, "sanitizing {html}");
+        }
+    }
+
+    #[test]
+    fn test_sanitize_html_malformed_markup() {
+        let sanitized = sanitize_html("<p><b>bold<i>both</p>after");
+        assert_eq!(
+            sanitized.html,
+            "<p><b>bold<i>both</i></b></p><b><i>after</i></b>"
+        );
+        assert!(!sanitized.removed_content);
+
+        // An unclosed script swallows the rest of the markup.
+        let sanitized = sanitize_html("<div><script>alert(1)</div><p>more</p>");
+        assert_eq!(sanitized.html, "<div></div>");
+        assert!(sanitized.removed_content);
+
+        for html in [
+            "<scr<script>ipt>alert(1)</script>",
+            "<<script>script>alert(1)<</script>/script>",
+            "<script/src=x></script>",
+            "<div <script>alert(1)</script>>",
+            "<p title='</p><script>alert(1)</script>'>x</p>",
+        ] {
+            let sanitized = sanitize_html(html);
+            assert!(!sanitized.html.contains("<script"), "sanitizing {html}");
+        }
+    }
+
+    #[test]
+    fn test_sanitize_html_attribute_encodings() {
+        for href in [
+            "javascript:alert(1)",
+            "JaVaScRiPt:alert(1)",
+            "jav&#x61;script:alert(1)",
+            "jav&#97;script&colon;alert(1)",
+            " java\tscript:alert(1)",
+            "&#14;javascript:alert(1)",
+            "vbscript:msgbox(1)",
+            "data:text/html,<script>alert(1)</script>",
+        ] {
+            let sanitized = sanitize_html(&format!(r#"<a href="{href}">link</a>"#));
+            assert_eq!(sanitized.html, "<a>link</a>", "sanitizing {href}");
+            assert!(sanitized.removed_content, "sanitizing {href}");
+        }
+
+        for href in [
+            "https://example.com/?q=1&amp;r=2",
+            "mailto:me@example.com",
+            "#section",
+            "docs/page.html",
+        ] {
+            let html = format!(r#"<a href="{href}">link</a>"#);
+            assert_eq!(sanitize_html(&html).html, html);
+        }
+
+        let image = r#"<img src="data:image/png;base64,iVBORw0KGgo=" alt="plot">"#;
+        assert_eq!(sanitize_html(image).html, image);
+        let sanitized = sanitize_html(r#"<img src="data:image/svg+xml;base64,PHN2Zz4=">"#);
+        assert_eq!(sanitized.html, "<img>");
+
+        for style in [
+            "background: url(https://example.com/x.png)",
+            "background: u\\72l(https://example.com/x.png)",
+            "width: expression(alert(1))",
+            "color: red; /* */ background: ur/**/l(x)",
+        ] {
+            let sanitized = sanitize_html(&format!(r#"<span style="{style}">x</span>"#));
+            assert_eq!(sanitized.html, "<span>x</span>", "sanitizing {style}");
+        }
+
+        // Quotes in attribute values can't end the attribute early.
+        let sanitized = sanitize_html(r#"<span title='a" onclick="alert(1)'>x</span>"#);
+        assert_eq!(
+            sanitized.html,
+            r#"<span title="a&quot; onclick=&quot;alert(1)">x</span>"#
+        );
+    }
+
     #[test]
     fn test_html_table_to_markdown() {
         let html = r#"<table>
@@ -1,21 +1,74 @@
-use anyhow::Result;
+use anyhow::{Context as _, Result};
 use base64::{
     Engine as _, alphabet,
     engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
 };
-use gpui::{App, ClipboardItem, Image, ImageFormat, Pixels, RenderImage, Window, img};
+use gpui::{
+    App, ClipboardItem, Image, ImageFormat, Pixels, RenderImage, SMOOTH_SVG_SCALE_FACTOR,
+    WeakEntity, Window, img,
+};
 use settings::Settings as _;
-use std::sync::Arc;
+use std::{io::Cursor, sync::Arc};
 use ui::{IntoElement, Styled, prelude::*};
+use workspace::Workspace;
 
 use crate::outputs::{OutputContent, plain};
 use crate::repl_settings::ReplSettings;
 
+/// Images larger than this on either side are downsampled for display. The
+/// original bytes are kept for copying and saving.
+const MAX_DISPLAY_DIMENSION: u32 = 2048;
+
+/// Caps on the images a kernel can send, so a runaway plot can't exhaust
+/// memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImageLimits {
+    pub max_bytes: usize,
+    pub max_dimension: u32,
+}
+
+impl ImageLimits {
+    pub fn from_settings(settings: &ReplSettings) -> Self {
+        Self {
+            max_bytes: settings
+                .max_image_size_mb
+                .saturating_mul(1024 * 1024)
+                .try_into()
+                .unwrap_or(usize::MAX),
+            max_dimension: settings.max_image_dimension,
+        }
+    }
+
+    fn check_bytes(&self, len: usize) -> Result<()> {
+        anyhow::ensure!(
+            len <= self.max_bytes,
+            "image is {len} bytes, over the limit of {} bytes",
+            self.max_bytes
+        );
+        Ok(())
+    }
+
+    fn check_dimensions(&self, width: u32, height: u32) -> Result<()> {
+        anyhow::ensure!(
+            width > 0 && height > 0,
+            "image has no pixels ({width}x{height})"
+        );
+        anyhow::ensure!(
+            width <= self.max_dimension && height <= self.max_dimension,
+            "image is {width}x{height}, over the limit of {} pixels per side",
+            self.max_dimension
+        );
+        Ok(())
+    }
+}
+
 /// ImageView renders an image inline in an editor, adapting to the line height to fit the image.
 pub struct ImageView {
-    clipboard_image: Arc<Image>,
+    /// The image exactly as the kernel sent it.
+    original: Arc<Image>,
     height: u32,
     width: u32,
+    /// What gets drawn, which may be downsampled from the original.
     image: Arc<RenderImage>,
 }
 
@@ -27,23 +80,35 @@ pub const STANDARD_INDIFFERENT: GeneralPurpose = GeneralPurpose::new(
 );
 
 impl ImageView {
-    pub fn from(base64_encoded_data: &str) -> Result<Self> {
-        let filtered =
-            base64_encoded_data.replace(&[' ', '\n', '\t', '\r', '\x0b', '\x0c'][..], "");
-        let bytes = STANDARD_INDIFFERENT.decode(filtered)?;
-
-        let format = image::guess_format(&bytes)?;
-
-        let mut data = image::load_from_memory_with_format(&bytes, format)?.into_rgba8();
+    /// Decodes a base64 encoded raster image, such as the `image/png` output
+    /// of a plot.
+    pub fn from(base64_encoded_data: &str, limits: ImageLimits) -> Result<Self> {
+        // Base64 encodes 3 bytes in 4 characters.
+        limits.check_bytes(base64_encoded_data.len() / 4 * 3)?;
+        let bytes = decode_base64(base64_encoded_data)?;
+        limits.check_bytes(bytes.len())?;
+
+        // Check the dimensions from the header before decoding any pixels.
+        let reader = image::ImageReader::new(Cursor::new(&bytes)).with_guessed_format()?;
+        let format = reader.format().context("unrecognized image format")?;
+        let (width, height) = reader.into_dimensions()?;
+        limits.check_dimensions(width, height)?;
+
+        let mut decoded = image::load_from_memory_with_format(&bytes, format)?;
+        if width.max(height) > MAX_DISPLAY_DIMENSION {
+            decoded = decoded.resize(
+                MAX_DISPLAY_DIMENSION,
+                MAX_DISPLAY_DIMENSION,
+                image::imageops::FilterType::Triangle,
+            );
+        }
+        let mut data = decoded.into_rgba8();
 
         // Convert from RGBA to BGRA.
         for pixel in data.chunks_exact_mut(4) {
             pixel.swap(0, 2);
         }
 
-        let height = data.height();
-        let width = data.width();
-
         let gpui_image_data = RenderImage::new(vec![image::Frame::new(data)]);
 
         let format = match format {
@@ -59,17 +124,60 @@ impl ImageView {
             }
         };
 
-        // Convert back to a GPUI image for use with the clipboard
-        let clipboard_image = Arc::new(Image::from_bytes(format, bytes));
-
         Ok(ImageView {
-            clipboard_image,
+            original: Arc::new(Image::from_bytes(format, bytes)),
             height,
             width,
             image: Arc::new(gpui_image_data),
         })
     }
 
+    /// Renders an `image/svg+xml` output, checking the size it declares
+    /// before rasterizing it.
+    pub fn from_svg(svg: &str, limits: ImageLimits, cx: &App) -> Result<Self> {
+        limits.check_bytes(svg.len())?;
+        let image = cx
+            .svg_renderer()
+            .render_single_frame_checked(svg.as_bytes(), 1.0, |size| {
+                limits.check_dimensions(size.width.ceil() as u32, size.height.ceil() as u32)
+            })
+            .context("failed to render SVG")?;
+
+        let size = image.size(0);
+        let width = (size.width.0 as f32 / SMOOTH_SVG_SCALE_FACTOR).round() as u32;
+        let height = (size.height.0 as f32 / SMOOTH_SVG_SCALE_FACTOR).round() as u32;
+        limits.check_dimensions(width, height)?;
+
+        Ok(ImageView {
+            original: Arc::new(Image::from_bytes(ImageFormat::Svg, svg.as_bytes().to_vec())),
+            height,
+            width,
+            image,
+        })
+    }
+
+    /// Prompts for a path and writes the image there exactly as the kernel
+    /// sent it, without re-encoding.
+    pub fn save_as(&self, workspace: &WeakEntity<Workspace>, cx: &mut App) {
+        let Some(workspace) = workspace.upgrade() else {
+            return;
+        };
+        let fs = workspace.read(cx).app_state().fs.clone();
+        let original = self.original.clone();
+        let suggested_name = format!("output.{}", file_extension(original.format));
+        let path = cx.prompt_for_new_path(util::paths::home_dir(), Some(&suggested_name));
+
+        cx.spawn(async move |_| {
+            let Some(path) = path.await?? else {
+                return anyhow::Ok(());
+            };
+            fs.write(&path, &original.bytes)
+                .await
+                .with_context(|| format!("failed to save image to {}", path.display()))
+        })
+        .detach_and_log_err(cx);
+    }
+
     fn scaled_size(
         &self,
         line_height: Pixels,
@@ -131,7 +239,7 @@ impl Render for ImageView {
 
 impl OutputContent for ImageView {
     fn clipboard_content(&self, _window: &Window, _cx: &App) -> Option<ClipboardItem> {
-        Some(ClipboardItem::new_image(self.clipboard_image.as_ref()))
+        Some(ClipboardItem::new_image(self.original.as_ref()))
     }
 
     fn has_clipboard_content(&self, _window: &Window, _cx: &App) -> bool {
@@ -139,11 +247,39 @@ impl OutputContent for ImageView {
     }
 }
 
+/// Decodes the base64 data of an image output, ignoring the whitespace kernels
+/// wrap it with.
+pub(crate) fn decode_base64(base64_encoded_data: &str) -> Result<Vec<u8>> {
+    let filtered = base64_encoded_data.replace(&[' ', '\n', '\t', '\r', '\x0b', '\x0c'][..], "");
+    STANDARD_INDIFFERENT
+        .decode(filtered)
+        .context("invalid base64 image data")
+}
+
+pub(crate) fn file_extension(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Png => "png",
+        ImageFormat::Jpeg => "jpg",
+        ImageFormat::Webp => "webp",
+        ImageFormat::Gif => "gif",
+        ImageFormat::Svg => "svg",
+        ImageFormat::Bmp => "bmp",
+        ImageFormat::Tiff => "tiff",
+        ImageFormat::Ico => "ico",
+        ImageFormat::Pnm => "pnm",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn encode_test_image(width: u32, height: u32) -> String {
+    const TEST_LIMITS: ImageLimits = ImageLimits {
+        max_bytes: 1024 * 1024,
+        max_dimension: 4096,
+    };
+
+    fn encode_test_png(width: u32, height: u32) -> Vec<u8> {
         let image_buffer =
             image::ImageBuffer::from_pixel(width, height, image::Rgba([0, 0, 0, 255]));
         let image = image::DynamicImage::ImageRgba8(image_buffer);
@@ -153,14 +289,59 @@ mod tests {
         if let Err(error) = image.write_to(&mut cursor, image::ImageFormat::Png) {
             panic!("failed to encode test image: {error}");
         }
+        bytes
+    }
 
-        base64::engine::general_purpose::STANDARD.encode(bytes)
+    fn encode_test_image(width: u32, height: u32) -> String {
+        base64::engine::general_purpose::STANDARD.encode(encode_test_png(width, height))
+    }
+
+    #[test]
+    fn test_image_view_rejects_images_over_limits() {
+        let encoded = encode_test_image(200, 120);
+        let small_dimension = ImageLimits {
+            max_dimension: 100,
+            ..TEST_LIMITS
+        };
+        assert!(ImageView::from(&encoded, small_dimension).is_err());
+
+        let small_size = ImageLimits {
+            max_bytes: 16,
+            ..TEST_LIMITS
+        };
+        assert!(ImageView::from(&encoded, small_size).is_err());
+    }
+
+    #[test]
+    fn test_image_view_rejects_corrupt_data() {
+        assert!(ImageView::from("not base64!", TEST_LIMITS).is_err());
+
+        let png = encode_test_png(200, 120);
+        let truncated = base64::engine::general_purpose::STANDARD.encode(&png[..png.len() / 2]);
+        assert!(ImageView::from(&truncated, TEST_LIMITS).is_err());
+    }
+
+    #[test]
+    fn test_image_view_downsamples_only_for_display() {
+        let png = encode_test_png(3000, 1500);
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&png);
+        let image_view = match ImageView::from(&encoded, TEST_LIMITS) {
+            Ok(view) => view,
+            Err(error) => panic!("failed to decode image view: {error}"),
+        };
+
+        assert_eq!((image_view.width, image_view.height), (3000, 1500));
+        let display_size = image_view.image.size(0);
+        assert_eq!(display_size.width.0, MAX_DISPLAY_DIMENSION as i32);
+        assert_eq!(display_size.height.0, MAX_DISPLAY_DIMENSION as i32 / 2);
+        assert_eq!(image_view.original.format, ImageFormat::Png);
+        assert_eq!(image_view.original.bytes, png);
     }
 
     #[test]
     fn test_image_view_scaled_size_respects_limits() {
         let encoded = encode_test_image(200, 120);
-        let image_view = match ImageView::from(&encoded) {
+        let image_view = match ImageView::from(&encoded, TEST_LIMITS) {
             Ok(view) => view,
             Err(error) => panic!("failed to decode image view: {error}"),
         };
@@ -175,10 +356,31 @@ mod tests {
         assert_eq!(f32::from(height), 30.0);
     }
 
+    #[gpui::test]
+    fn test_svg_checked_against_limits(cx: &mut gpui::TestAppContext) {
+        let svg = |width: u32, height: u32| {
+            format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}"><rect width="10" height="10"/></svg>"#
+            )
+        };
+        cx.update(|cx| {
+            let view = ImageView::from_svg(&svg(40, 20), TEST_LIMITS, cx).unwrap();
+            assert_eq!((view.width, view.height), (40, 20));
+
+            let error = ImageView::from_svg(&svg(100_000, 20), TEST_LIMITS, cx)
+                .err()
+                .unwrap();
+            assert!(
+                format!("{error:#}").contains("100000x20"),
+                "unexpected error: {error:#}"
+            );
+        });
+    }
+
     #[test]
     fn test_image_view_scaled_size_unbounded() {
         let encoded = encode_test_image(200, 120);
-        let image_view = match ImageView::from(&encoded) {
+        let image_view = match ImageView::from(&encoded, TEST_LIMITS) {
             Ok(view) => view,
             Err(error) => panic!("failed to decode image view: {error}"),
         };
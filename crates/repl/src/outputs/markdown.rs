@@ -6,20 +6,57 @@ use crate::outputs::OutputContent;
 
 pub struct MarkdownView {
     markdown: Entity<Markdown>,
+    /// The text to copy when it differs from the markdown source.
+    copy_text: Option<String>,
 }
 
 impl MarkdownView {
     pub fn from(text: String, cx: &mut Context<Self>) -> Self {
         let markdown = cx.new(|cx| Markdown::new(text.clone().into(), None, None, cx));
 
-        Self { markdown }
+        Self {
+            markdown,
+            copy_text: None,
+        }
+    }
+
+    /// Shows a `text/latex` output as a code block. Copying it gives the
+    /// expression without its math delimiters.
+    pub fn from_latex(latex: &str, cx: &mut Context<Self>) -> Self {
+        let expression = strip_math_delimiters(latex).to_string();
+        let source = format!("```latex\n{expression}\n```");
+        let markdown = cx.new(|cx| Markdown::new(source.into(), None, None, cx));
+
+        Self {
+            markdown,
+            copy_text: Some(expression),
+        }
+    }
+
+    fn text(&self, cx: &App) -> String {
+        match &self.copy_text {
+            Some(text) => text.clone(),
+            None => self.markdown.read(cx).source().to_string(),
+        }
     }
 }
 
+/// Removes the `$$`, `$`, `\[` or `\(` delimiters around a LaTeX expression.
+fn strip_math_delimiters(latex: &str) -> &str {
+    let latex = latex.trim();
+    [("$$", "$$"), ("\\[", "\\]"), ("\\(", "\\)"), ("$", "$")]
+        .into_iter()
+        .find_map(|(open, close)| {
+            latex
+                .strip_prefix(open)
+                .and_then(|rest| rest.strip_suffix(close))
+        })
+        .map_or(latex, str::trim)
+}
+
 impl OutputContent for MarkdownView {
     fn clipboard_content(&self, _window: &Window, cx: &App) -> Option<ClipboardItem> {
-        let source = self.markdown.read(cx).source().to_string();
-        Some(ClipboardItem::new_string(source))
+        Some(ClipboardItem::new_string(self.text(cx)))
     }
 
     fn has_clipboard_content(&self, _window: &Window, _cx: &App) -> bool {
@@ -31,10 +68,10 @@ impl OutputContent for MarkdownView {
     }
 
     fn buffer_content(&mut self, _: &mut Window, cx: &mut App) -> Option<Entity<Buffer>> {
-        let source = self.markdown.read(cx).source().to_string();
+        let source = self.text(cx);
         let buffer = cx.new(|cx| {
             let mut buffer =
-                Buffer::local(source.clone(), cx).with_language(language::PLAIN_TEXT.clone(), cx);
+                Buffer::local(source, cx).with_language(language::PLAIN_TEXT.clone(), cx);
             buffer.set_capability(language::Capability::ReadOnly, cx);
             buffer
         });
@@ -54,3 +91,27 @@ impl Render for MarkdownView {
 fn markdown_style(window: &Window, cx: &App) -> MarkdownStyle {
     MarkdownStyle::themed(MarkdownFont::Editor, window, cx)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_math_delimiters() {
+        assert_eq!(strip_math_delimiters("$$x^2 + y^2$$"), "x^2 + y^2");
+        assert_eq!(strip_math_delimiters("$\\alpha$"), "\\alpha");
+        assert_eq!(
+            strip_math_delimiters("\\[ \\frac{1}{2} \\]"),
+            "\\frac{1}{2}"
+        );
+        assert_eq!(strip_math_delimiters("\\(a = b\\)"), "a = b");
+        assert_eq!(
+            strip_math_delimiters("  $$\n\\sum_i x_i\n$$\n"),
+            "\\sum_i x_i"
+        );
+        assert_eq!(
+            strip_math_delimiters("\\begin{equation}x\\end{equation}"),
+            "\\begin{equation}x\\end{equation}"
+        );
+    }
+}
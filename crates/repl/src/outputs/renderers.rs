@@ -0,0 +1,249 @@
+//! Renderers for MIME types that the built-in outputs don't cover, such as
+//! the vendor types plotting libraries emit (`application/vnd.plotly.v1+json`,
+//! `application/vnd.vegalite.v5+json`).
+//!
+//! A renderer is registered for one MIME type and is handed that type's raw
+//! payload from a bundle. Bundles with a type that has a renderer are shown
+//! by it, ahead of the built-in representations chosen by
+//! `repl.output_mime_priority`; bundles without one are shown as before.
+
+use std::sync::Arc;
+
+use gpui::{AnyView, App, AppContext as _, Global, SharedString, Window};
+use serde_json::Value;
+
+use super::markdown::MarkdownView;
+
+/// The MIME types of the Vega-Lite specs Altair and other libraries emit.
+const VEGA_LITE_MIME_TYPES: [&str; 2] = [
+    "application/vnd.vegalite.v5+json",
+    "application/vnd.vegalite.v4+json",
+];
+
+/// Shows outputs of a MIME type the built-in outputs don't cover.
+pub trait MimeRenderer: 'static {
+    /// Renders `payload`, the bundle's data for `mime_type`: a string for
+    /// text types and JSON otherwise. An error falls back to the next
+    /// representation of the bundle.
+    fn render(
+        &self,
+        mime_type: &str,
+        payload: &Value,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> anyhow::Result<RenderedOutput>;
+}
+
+/// An output shown by a [`MimeRenderer`].
+#[derive(Clone)]
+pub struct RenderedOutput {
+    pub view: AnyView,
+    /// The text the output's copy button copies. Without it, there's no copy
+    /// button.
+    pub copy_text: Option<String>,
+    /// The copy button's tooltip, such as "Copy Spec".
+    pub copy_label: SharedString,
+}
+
+/// Identifies a registration, for [`MimeRendererRegistry::unregister`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RendererId(usize);
+
+struct RegisteredRenderer {
+    id: RendererId,
+    mime_type: String,
+    renderer: Arc<dyn MimeRenderer>,
+}
+
+/// The renderers registered for each MIME type. When several claim the same
+/// type, the one registered first is tried first.
+#[derive(Default)]
+pub struct MimeRendererRegistry {
+    renderers: Vec<RegisteredRenderer>,
+    next_id: usize,
+}
+
+impl Global for MimeRendererRegistry {}
+
+impl MimeRendererRegistry {
+    /// Registers the renderers that ship with Zed.
+    pub(crate) fn init(cx: &mut App) {
+        let vega_lite = Arc::new(VegaLiteRenderer);
+        for mime_type in VEGA_LITE_MIME_TYPES {
+            Self::register(mime_type, vega_lite.clone(), cx);
+        }
+    }
+
+    pub fn register(
+        mime_type: impl Into<String>,
+        renderer: Arc<dyn MimeRenderer>,
+        cx: &mut App,
+    ) -> RendererId {
+        let registry = cx.default_global::<Self>();
+        let id = RendererId(registry.next_id);
+        registry.next_id += 1;
+        registry.renderers.push(RegisteredRenderer {
+            id,
+            mime_type: mime_type.into(),
+            renderer,
+        });
+        id
+    }
+
+    /// Removes a registration. The other renderers keep their order.
+    pub fn unregister(id: RendererId, cx: &mut App) {
+        if cx.has_global::<Self>() {
+            cx.global_mut::<Self>()
+                .renderers
+                .retain(|registered| registered.id != id);
+        }
+    }
+
+    /// The registered renderers and the MIME type each claims, in the order
+    /// they're tried.
+    pub(crate) fn claims(&self) -> Vec<(RendererId, &str)> {
+        self.renderers
+            .iter()
+            .map(|registered| (registered.id, registered.mime_type.as_str()))
+            .collect()
+    }
+
+    pub(crate) fn renderer(&self, id: RendererId) -> Option<Arc<dyn MimeRenderer>> {
+        self.renderers
+            .iter()
+            .find(|registered| registered.id == id)
+            .map(|registered| registered.renderer.clone())
+    }
+}
+
+/// One way of showing a bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Representation<'a> {
+    /// A registered renderer, given the bundle's data for `mime_type`.
+    Renderer { id: RendererId, mime_type: &'a str },
+    /// The built-in output for a MIME type.
+    Builtin(&'a str),
+}
+
+/// The ways to show a bundle, in the order to try them: each registered
+/// renderer for one of the bundle's MIME types in `bundle`, by registration
+/// order, and then the built-in representation `builtin` picks, if any.
+pub(crate) fn resolve_representations<'a>(
+    bundle: &[&str],
+    claims: &[(RendererId, &'a str)],
+    builtin: Option<&'a str>,
+) -> Vec<Representation<'a>> {
+    claims
+        .iter()
+        .filter(|(_, mime_type)| bundle.contains(mime_type))
+        .map(|&(id, mime_type)| Representation::Renderer { id, mime_type })
+        .chain(builtin.map(Representation::Builtin))
+        .collect()
+}
+
+/// Shows a Vega-Lite spec as pretty-printed JSON, with a button to copy the
+/// spec for pasting into a Vega editor.
+struct VegaLiteRenderer;
+
+impl MimeRenderer for VegaLiteRenderer {
+    fn render(
+        &self,
+        _mime_type: &str,
+        payload: &Value,
+        _window: &mut Window,
+        cx: &mut App,
+    ) -> anyhow::Result<RenderedOutput> {
+        anyhow::ensure!(payload.is_object(), "Vega-Lite spec is not a JSON object");
+        let spec = serde_json::to_string_pretty(payload)?;
+        let source = format!("**Vega-Lite chart**\n\n```json\n{spec}\n```");
+        let view = cx.new(|cx| MarkdownView::from(source, cx));
+        Ok(RenderedOutput {
+            view: view.into(),
+            copy_text: Some(spec),
+            copy_label: "Copy Spec".into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VEGA_LITE: &str = "application/vnd.vegalite.v5+json";
+    const PLOTLY: &str = "application/vnd.plotly.v1+json";
+
+    #[test]
+    fn test_unclaimed_bundle_uses_builtin() {
+        let claims = [(RendererId(0), VEGA_LITE)];
+        assert_eq!(
+            resolve_representations(&[PLOTLY, "text/html"], &claims, Some("text/html")),
+            [Representation::Builtin("text/html")]
+        );
+        assert_eq!(resolve_representations(&[PLOTLY], &claims, None), []);
+    }
+
+    #[test]
+    fn test_renderers_come_before_builtin_in_registration_order() {
+        let claims = [
+            (RendererId(0), PLOTLY),
+            (RendererId(1), VEGA_LITE),
+            (RendererId(2), PLOTLY),
+        ];
+        assert_eq!(
+            resolve_representations(
+                &["text/plain", VEGA_LITE, PLOTLY],
+                &claims,
+                Some("text/plain")
+            ),
+            [
+                Representation::Renderer {
+                    id: RendererId(0),
+                    mime_type: PLOTLY
+                },
+                Representation::Renderer {
+                    id: RendererId(1),
+                    mime_type: VEGA_LITE
+                },
+                Representation::Renderer {
+                    id: RendererId(2),
+                    mime_type: PLOTLY
+                },
+                Representation::Builtin("text/plain"),
+            ]
+        );
+    }
+
+    #[gpui::test]
+    fn test_register_and_unregister_keep_order(cx: &mut App) {
+        struct Unused;
+        impl MimeRenderer for Unused {
+            fn render(
+                &self,
+                _: &str,
+                _: &Value,
+                _: &mut Window,
+                _: &mut App,
+            ) -> anyhow::Result<RenderedOutput> {
+                anyhow::bail!("unused")
+            }
+        }
+
+        let first = MimeRendererRegistry::register(PLOTLY, Arc::new(Unused), cx);
+        let second = MimeRendererRegistry::register(VEGA_LITE, Arc::new(Unused), cx);
+        let third = MimeRendererRegistry::register(PLOTLY, Arc::new(Unused), cx);
+        MimeRendererRegistry::unregister(second, cx);
+        assert_eq!(
+            cx.global::<MimeRendererRegistry>().claims(),
+            [(first, PLOTLY), (third, PLOTLY)]
+        );
+
+        // Ids aren't reused, so a stale id can't remove a later registration.
+        let fourth = MimeRendererRegistry::register(VEGA_LITE, Arc::new(Unused), cx);
+        assert_ne!(fourth, second);
+        MimeRendererRegistry::unregister(second, cx);
+        assert_eq!(
+            cx.global::<MimeRendererRegistry>().claims(),
+            [(first, PLOTLY), (third, PLOTLY), (fourth, VEGA_LITE)]
+        );
+    }
+}
@@ -0,0 +1,209 @@
+//! # Stream Limits
+//!
+//! Keeps a kernel's `stream` output from overwhelming Zed. A cell can print
+//! hundreds of megabytes, all on a single line, or write bytes that aren't
+//! UTF-8 straight to its process's stdout. [`StreamLimiter`] bounds how much
+//! of an execution's stream text is shown and splits pathologically long lines
+//! into segments, and [`LossyUtf8Decoder`] decodes raw output in chunks without
+//! failing on invalid bytes.
+
+use std::borrow::Cow;
+
+/// How much stream text a single execution shows, in bytes. Anything past this
+/// is dropped and replaced by a notice.
+pub const MAX_STREAM_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
+/// The longest line of stream text shown, in bytes. Longer lines are split into
+/// segments of this length.
+pub const MAX_STREAM_LINE_BYTES: usize = 16 * 1024;
+
+/// The notice shown in place of stream text past [`MAX_STREAM_OUTPUT_BYTES`].
+pub fn truncation_notice() -> String {
+    format!(
+        "\n[Output truncated after {} MB]\n",
+        MAX_STREAM_OUTPUT_BYTES / (1024 * 1024)
+    )
+}
+
+/// Bounds the stream text of one execution, across all of its messages.
+#[derive(Debug, Default)]
+pub struct StreamLimiter {
+    /// Bytes of stream text shown so far.
+    shown: usize,
+    /// Length of the line the last message ended in, in bytes.
+    line_len: usize,
+    truncated: bool,
+}
+
+impl StreamLimiter {
+    /// Returns the part of `text` to show, with lines longer than
+    /// [`MAX_STREAM_LINE_BYTES`] split into segments. Text past
+    /// [`MAX_STREAM_OUTPUT_BYTES`] is cut before anything is copied, and is
+    /// replaced by a notice the first time it happens.
+    pub fn limit<'a>(&mut self, text: &'a str) -> Cow<'a, str> {
+        if self.truncated {
+            return Cow::Borrowed("");
+        }
+
+        let remaining = MAX_STREAM_OUTPUT_BYTES.saturating_sub(self.shown);
+        let shown = &text[..floor_char_boundary(text, remaining)];
+        self.shown += shown.len();
+
+        let mut limited = self.split_long_lines(shown);
+        if shown.len() < text.len() {
+            self.truncated = true;
+            limited.to_mut().push_str(&truncation_notice());
+        }
+        limited
+    }
+
+    /// Whether text has been dropped for going over the limit.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    fn split_long_lines<'a>(&mut self, text: &'a str) -> Cow<'a, str> {
+        let mut split: Option<String> = None;
+        let mut segment_start = 0;
+        for (ix, character) in text.char_indices() {
+            // Carriage returns start the line over, as progress bars use them.
+            if character == '\n' || character == '\r' {
+                self.line_len = 0;
+                continue;
+            }
+            if self.line_len + character.len_utf8() > MAX_STREAM_LINE_BYTES {
+                let split = split.get_or_insert_with(|| {
+                    String::with_capacity(text.len() + text.len() / MAX_STREAM_LINE_BYTES + 1)
+                });
+                split.push_str(&text[segment_start..ix]);
+                split.push('\n');
+                segment_start = ix;
+                self.line_len = 0;
+            }
+            self.line_len += character.len_utf8();
+        }
+
+        match split {
+            Some(mut split) => {
+                split.push_str(&text[segment_start..]);
+                Cow::Owned(split)
+            }
+            None => Cow::Borrowed(text),
+        }
+    }
+}
+
+/// The largest index of at most `index` that falls on a character boundary.
+pub(crate) fn floor_char_boundary(text: &str, index: usize) -> usize {
+    if index >= text.len() {
+        return text.len();
+    }
+    let mut index = index;
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Decodes UTF-8 that arrives in chunks, replacing invalid bytes with U+FFFD.
+/// A character split across two chunks is held back until the rest of it
+/// arrives rather than being replaced.
+#[derive(Debug, Default)]
+pub struct LossyUtf8Decoder {
+    incomplete: Vec<u8>,
+}
+
+impl LossyUtf8Decoder {
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        let mut input = std::mem::take(&mut self.incomplete);
+        input.extend_from_slice(bytes);
+
+        let mut decoded = String::with_capacity(input.len());
+        let mut rest = input.as_slice();
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    decoded.push_str(valid);
+                    break;
+                }
+                Err(error) => {
+                    let (valid, after_valid) = rest.split_at(error.valid_up_to());
+                    decoded.push_str(&String::from_utf8_lossy(valid));
+                    match error.error_len() {
+                        Some(invalid_len) => {
+                            decoded.push(char::REPLACEMENT_CHARACTER);
+                            rest = &after_valid[invalid_len..];
+                        }
+                        None => {
+                            self.incomplete = after_valid.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        decoded
+    }
+
+    /// Decodes whatever is held back once no more chunks are coming.
+    pub fn finish(&mut self) -> String {
+        if self.incomplete.is_empty() {
+            String::new()
+        } else {
+            self.incomplete.clear();
+            char::REPLACEMENT_CHARACTER.to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_limiter_splits_long_lines_across_messages() {
+        let mut limiter = StreamLimiter::default();
+        assert!(matches!(limiter.limit("short\n"), Cow::Borrowed("short\n")));
+
+        let first = "a".repeat(MAX_STREAM_LINE_BYTES - 1);
+        assert_eq!(limiter.limit(&first), first);
+        let limited = limiter.limit("bcd");
+        assert_eq!(limited, "b\ncd");
+
+        let long_line = "é".repeat(MAX_STREAM_LINE_BYTES * 2);
+        let limited = limiter.limit(&format!("\n{long_line}"));
+        assert!(
+            limited
+                .lines()
+                .all(|line| line.len() <= MAX_STREAM_LINE_BYTES)
+        );
+        assert_eq!(limited.replace('\n', ""), long_line);
+    }
+
+    #[test]
+    fn test_stream_limiter_truncates_before_copying() {
+        let mut limiter = StreamLimiter::default();
+        let text = "x\n".repeat(MAX_STREAM_OUTPUT_BYTES);
+        let limited = limiter.limit(&text);
+        assert!(limiter.is_truncated());
+        assert!(limited.ends_with("[Output truncated after 4 MB]\n"));
+        assert!(limited.len() < MAX_STREAM_OUTPUT_BYTES + 64);
+        assert_eq!(limiter.limit("more\n"), "");
+    }
+
+    #[test]
+    fn test_lossy_utf8_decoder() {
+        let mut decoder = LossyUtf8Decoder::default();
+        let text = "héllo ✓".as_bytes();
+        // Split inside the two-byte "é" and the three-byte "✓".
+        assert_eq!(decoder.decode(&text[..2]), "h");
+        assert_eq!(decoder.decode(&text[2..8]), "éllo ");
+        assert_eq!(decoder.decode(&text[8..]), "✓");
+
+        assert_eq!(decoder.decode(b"a\xffb\xc3"), "a\u{FFFD}b");
+        assert_eq!(decoder.decode(b"\x80c"), "Àc");
+        assert_eq!(decoder.decode(b"\xc3x"), "\u{FFFD}x");
+        assert_eq!(decoder.decode(b"\xe2\x9c"), "");
+        assert_eq!(decoder.finish(), "\u{FFFD}");
+        assert_eq!(decoder.finish(), "");
+    }
+}
@@ -1,5 +1,7 @@
-use gpui::{AnyElement, App, Entity, FontWeight, Window};
-use ui::{Label, h_flex, prelude::*, v_flex};
+use std::sync::Arc;
+
+use gpui::{AnyElement, App, ClickEvent, Entity, FontWeight, Window};
+use ui::{Disclosure, Label, h_flex, prelude::*, v_flex};
 
 use crate::outputs::plain::TerminalOutput;
 
@@ -9,13 +11,16 @@ pub struct ErrorView {
     pub ename: String,
     pub evalue: String,
     pub traceback: Entity<TerminalOutput>,
+    /// Whether the error was caused by the user interrupting the execution,
+    /// in which case its traceback is hidden until it's expanded.
+    pub interrupted: bool,
 }
 
 impl ErrorView {
     pub fn render(&self, window: &mut Window, cx: &mut App) -> Option<AnyElement> {
-        let theme = cx.theme();
-
-        let padding = window.line_height() / 2.;
+        if self.interrupted {
+            return Some(self.render_interrupted(window, cx));
+        }
 
         Some(
             v_flex()
@@ -30,16 +35,54 @@ impl ErrorView {
                         )
                         .child(Label::new(self.evalue.clone()).weight(FontWeight::BOLD)),
                 )
-                .child(
-                    div()
-                        .w_full()
-                        .px(padding)
-                        .py(padding)
-                        .border_l_1()
-                        .border_color(theme.status().error_border)
-                        .child(self.traceback.clone()),
-                )
+                .child(self.render_traceback(window, cx))
                 .into_any_element(),
         )
     }
+
+    /// A single line saying the execution was interrupted, which expands to
+    /// show the traceback.
+    fn render_interrupted(&self, window: &mut Window, cx: &mut App) -> AnyElement {
+        let traceback_id = self.traceback.entity_id();
+        let expanded =
+            window.use_keyed_state(("interrupted-traceback", traceback_id), cx, |_, _| false);
+        let is_expanded = *expanded.read(cx);
+        let on_toggle: Arc<dyn Fn(&ClickEvent, &mut Window, &mut App)> =
+            Arc::new(move |_, _, cx| {
+                expanded.update(cx, |expanded, cx| {
+                    *expanded = !*expanded;
+                    cx.notify();
+                });
+            });
+
+        v_flex()
+            .gap_2()
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        Disclosure::new(
+                            ("interrupted-traceback-toggle", traceback_id),
+                            is_expanded,
+                        )
+                        .on_toggle_expanded(on_toggle),
+                    )
+                    .child(Label::new("Interrupted by user").color(Color::Muted)),
+            )
+            .when(is_expanded, |this| {
+                this.child(self.render_traceback(window, cx))
+            })
+            .into_any_element()
+    }
+
+    fn render_traceback(&self, window: &mut Window, cx: &App) -> impl IntoElement {
+        let padding = window.line_height() / 2.;
+        div()
+            .w_full()
+            .px(padding)
+            .py(padding)
+            .border_l_1()
+            .border_color(cx.theme().status().error_border)
+            .child(self.traceback.clone())
+    }
 }
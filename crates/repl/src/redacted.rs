@@ -0,0 +1,85 @@
+use std::fmt;
+
+const REDACTED: &str = "[redacted]";
+
+/// A secret, such as a Jupyter server token, whose `Debug` and `Display`
+/// print `[redacted]`, so that it stays out of logs and error messages when
+/// the struct holding it is formatted. The value itself is only reachable
+/// through [`Redacted::expose`].
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Redacted<T>(T);
+
+impl<T> Redacted<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// The secret itself, for sending to the server it belongs to.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<T> for Redacted<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T> fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// `text` with every occurrence of `secret` replaced by `[redacted]`.
+pub(crate) fn redact_secret(text: &str, secret: &str) -> String {
+    if secret.is_empty() {
+        return text.to_string();
+    }
+    text.replace(secret, REDACTED)
+}
+
+/// `error`, flattened into a single message with `secret` redacted if any
+/// error in its chain quotes it, such as one naming a URL that carries it.
+pub(crate) fn redact_error(error: anyhow::Error, secret: &str) -> anyhow::Error {
+    let message = format!("{error:#}");
+    if secret.is_empty() || !message.contains(secret) {
+        return error;
+    }
+    anyhow::anyhow!(redact_secret(&message, secret))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacted_formatting() {
+        let token = Redacted::new("s3cret-token".to_string());
+        assert_eq!(format!("{token:?}"), "[redacted]");
+        assert_eq!(token.to_string(), "[redacted]");
+        assert_eq!(token.expose(), "s3cret-token");
+    }
+
+    #[test]
+    fn test_redact_error() {
+        let error = anyhow::anyhow!("invalid URI ws://host/api?token=s3cret-token")
+            .context("failed to connect to kernel");
+        let redacted = redact_error(error, "s3cret-token");
+        assert_eq!(
+            redacted.to_string(),
+            "failed to connect to kernel: invalid URI ws://host/api?token=[redacted]"
+        );
+
+        let error = anyhow::anyhow!("connection refused");
+        assert_eq!(redact_error(error, "").to_string(), "connection refused");
+        assert_eq!(redact_secret("token=", ""), "token=");
+    }
+}
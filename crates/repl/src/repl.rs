@@ -1,13 +1,21 @@
+mod code_cells;
 pub mod components;
+mod execution_record;
 mod jupyter_settings;
+mod kernel_info_cache;
 pub mod kernels;
 pub mod notebook;
+mod notebook_export;
+mod output_log;
+mod output_spill;
 mod outputs;
+mod redacted;
 mod repl_editor;
 mod repl_sessions_ui;
 mod repl_settings;
 mod repl_store;
 mod session;
+mod variables;
 
 use std::{sync::Arc, time::Duration};
 
@@ -16,22 +24,41 @@ use gpui::{App, PlatformDispatcher, Priority, RunnableMeta};
 use project::Fs;
 pub use runtimelib::ExecutionState;
 
+pub use crate::code_cells::{CellMarkers, CodeCell, cell_markers, code_cells};
+pub use crate::execution_record::{
+    ExecutionRecord, ExecutionRecordStatus, MAX_EXECUTION_RECORD_BYTES, MAX_EXECUTION_RECORDS,
+    RecordedOutput,
+};
 pub use crate::jupyter_settings::JupyterSettings;
-pub use crate::kernels::{Kernel, KernelSpecification, KernelStatus, PythonEnvKernelSpecification};
+pub use crate::kernel_info_cache::CachedKernelInfo;
+pub use crate::kernels::{
+    Kernel, KernelSpecification, KernelStartupTiming, KernelStatus, PythonEnvKernelSpecification,
+};
+pub use crate::output_spill::SpilledOutput;
+pub use crate::outputs::renderers::{
+    MimeRenderer, MimeRendererRegistry, RenderedOutput, RendererId,
+};
+pub use crate::redacted::Redacted;
 pub use crate::repl_editor::*;
 pub use crate::repl_sessions_ui::{
-    ClearCurrentOutput, ClearOutputs, Interrupt, ReplSessionsPage, Restart, Run, Sessions, Shutdown,
+    AttachToSession, CancelCellRun, CancelCellRunAndInterrupt, ClearCurrentOutput, ClearOutputs,
+    DetachFromSession, Interrupt, OpenKernelLog, ReplSessionsPage, Restart, Run, RunAllCells,
+    RunCell, RunCellAndAdvance, RunCellsAbove, RunCellsToHere, Sessions, Shutdown, ToggleOutputLog,
 };
 pub use crate::repl_settings::ReplSettings;
 pub use crate::repl_store::ReplStore;
-pub use crate::session::Session;
+pub use crate::session::{CellRunProgress, ExecutionOptions, RemoteWorkingDirectory, Session};
+pub use crate::variables::VariableSummary;
 
 pub const KERNEL_DOCS_URL: &str = "https://zed.dev/docs/repl#changing-kernels";
 
 pub fn init(fs: Arc<dyn Fs>, cx: &mut App) {
     set_dispatcher(zed_dispatcher(cx));
     repl_sessions_ui::init(cx);
+    kernels::sweep_stale_connection_files_in_background(fs.clone(), cx);
+    output_spill::sweep_output_spill_directories_in_background(fs.clone(), cx);
     ReplStore::init(fs, cx);
+    MimeRendererRegistry::init(cx);
 }
 
 fn zed_dispatcher(cx: &mut App) -> impl Dispatcher {
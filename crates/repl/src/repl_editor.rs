@@ -4,18 +4,22 @@ use std::ops::Range;
 use std::sync::Arc;
 
 use anyhow::{Context as _, Result};
-use editor::{Editor, MultiBufferOffset};
-use gpui::{App, Entity, WeakEntity, Window, prelude::*};
-use language::{BufferSnapshot, Language, LanguageName, Point};
+use editor::{Anchor, Editor, MultiBufferOffset};
+use gpui::{App, Entity, SharedString, WeakEntity, Window, prelude::*};
+use language::{Buffer, BufferSnapshot, Language, LanguageName, Point, ToPoint as _};
 use project::{ProjectItem as _, WorktreeId};
+use runtimelib::IsCompleteReplyStatus;
+use settings::{ReplCellType, Settings as _};
 use workspace::{Workspace, notifications::NotificationId};
 
-use crate::kernels::PythonEnvKernelSpecification;
+use crate::code_cells::{CodeCell, cell_markers, code_cells, whole_buffer_range};
+use crate::kernels::{Kernel, PythonEnvKernelSpecification};
 use crate::repl_store::ReplStore;
-use crate::session::SessionEvent;
+use crate::session::{ExecutionOptions, SessionEvent};
 use crate::{
-    ClearCurrentOutput, ClearOutputs, Interrupt, JupyterSettings, KernelSpecification, Restart,
-    Session, Shutdown,
+    CancelCellRun, CancelCellRunAndInterrupt, ClearCurrentOutput, ClearOutputs, Interrupt,
+    JupyterSettings, KernelSpecification, OpenKernelLog, ReplSettings, Restart, Session, Shutdown,
+    ToggleOutputLog,
 };
 
 pub fn assign_kernelspec(
@@ -29,8 +33,7 @@ pub fn assign_kernelspec(
         return Ok(());
     }
 
-    let worktree_id = crate::repl_editor::worktree_id_for_editor(weak_editor.clone(), cx)
-        .context("editor is not in a worktree")?;
+    let worktree_id = crate::repl_editor::worktree_id_for_editor(weak_editor.clone(), cx);
 
     store.update(cx, |store, cx| {
         store.set_active_kernelspec(worktree_id, kernel_specification.clone(), cx);
@@ -39,29 +42,192 @@ pub fn assign_kernelspec(
     let fs = store.read(cx).fs().clone();
 
     if let Some(session) = store.read(cx).get_session(weak_editor.entity_id()).cloned() {
-        // Drop previous session, start new one
+        // Drop previous session, start new one. A session other editors
+        // are attached to keeps running for them.
+        store.update(cx, |store, _cx| {
+            store.detach_editor(weak_editor.entity_id())
+        });
         session.update(cx, |session, cx| {
-            session.clear_outputs(cx);
-            session.shutdown(window, cx);
+            session.detach_editor(weak_editor.entity_id(), cx);
+            if session.is_headless() {
+                session.shutdown(window, cx);
+            }
             cx.notify();
         });
     }
 
     let session =
         cx.new(|cx| Session::new(weak_editor.clone(), fs, kernel_specification, window, cx));
+    subscribe_to_session(&store, &weak_editor, &session, cx);
+
+    Ok(())
+}
+
+/// Runs the code of `editor` in `session`'s kernel, sharing it with the other
+/// editors attached to it. An editor that was attached to another session is
+/// detached from it first.
+pub fn attach_to_session(
+    editor: WeakEntity<Editor>,
+    session: Entity<Session>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let store = ReplStore::global(cx);
+    if let Some(previous) = store.read(cx).get_session(editor.entity_id()).cloned() {
+        if previous == session {
+            return;
+        }
+        detach_from_session(editor.clone(), window, cx);
+    }
+
+    session.update(cx, |session, cx| session.attach_editor(editor.clone(), cx));
+    subscribe_to_session(&store, &editor, &session, cx);
+}
+
+/// Attaches `editor` to the running session named `name`, or to the only
+/// other running session for its language when no name is given.
+pub fn attach_to_named_session(
+    editor: WeakEntity<Editor>,
+    name: Option<&str>,
+    window: &mut Window,
+    cx: &mut App,
+) -> Result<()> {
+    let (language, current) = match session(editor.clone(), cx) {
+        SessionSupport::ActiveSession(session) => (
+            session.read(cx).kernel_specification.language(),
+            Some(session),
+        ),
+        SessionSupport::Inactive(kernel_specification) => (kernel_specification.language(), None),
+        SessionSupport::RequiresSetup(_) | SessionSupport::Unsupported => {
+            anyhow::bail!("This buffer can't run code in a REPL session")
+        }
+    };
+
+    let session = if let Some(name) = name {
+        attachable_sessions(&language, None, cx)
+            .into_iter()
+            .find_map(|(session_name, session)| (session_name == name).then_some(session))
+            .with_context(|| format!("No running {language} session is named \"{name}\""))?
+    } else {
+        let mut sessions = attachable_sessions(&language, current.as_ref(), cx);
+        match sessions.len() {
+            0 => anyhow::bail!("No other {language} session is running"),
+            1 => sessions.remove(0).1,
+            _ => {
+                let names = sessions
+                    .iter()
+                    .map(|(name, _)| format!("\"{name}\""))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                anyhow::bail!(
+                    "Several {language} sessions are running ({names}), name the one to attach to"
+                )
+            }
+        }
+    };
+    attach_to_session(editor, session, window, cx);
+    Ok(())
+}
 
-    weak_editor
+/// The running sessions for `language`, other than `except`, with their
+/// names.
+pub fn attachable_sessions(
+    language: &str,
+    except: Option<&Entity<Session>>,
+    cx: &App,
+) -> Vec<(SharedString, Entity<Session>)> {
+    ReplStore::global(cx)
+        .read(cx)
+        .sessions()
+        .filter(|session| Some(*session) != except)
+        .filter(|session| {
+            let session = session.read(cx);
+            !matches!(session.kernel, Kernel::ShuttingDown | Kernel::Shutdown)
+                && session
+                    .kernel_specification
+                    .language()
+                    .eq_ignore_ascii_case(language)
+        })
+        .map(|session| (session.read(cx).name().clone(), session.clone()))
+        .collect()
+}
+
+/// Stops running the code of `editor` in its session. When no other editor
+/// is attached, asks whether to shut the kernel down or keep it running so
+/// editors can attach to it later.
+pub fn detach_from_session(editor: WeakEntity<Editor>, window: &mut Window, cx: &mut App) {
+    let store = ReplStore::global(cx);
+    let editor_id = editor.entity_id();
+    let Some(session) = store.read(cx).get_session(editor_id).cloned() else {
+        return;
+    };
+
+    store.update(cx, |store, _cx| store.detach_editor(editor_id));
+    session.update(cx, |session, cx| session.detach_editor(editor_id, cx));
+    if !session.read(cx).is_headless() {
+        return;
+    }
+
+    let message = format!("Shut down {}?", session.read(cx).name());
+    let answer = window.prompt(
+        gpui::PromptLevel::Info,
+        &message,
+        Some("No buffers are attached to it anymore. Its kernel can keep running so buffers can be attached to it again with \"repl: attach to session\"."),
+        &["Shut Down", "Keep Running"],
+        cx,
+    );
+    let window_handle = window.window_handle();
+    cx.spawn(async move |cx| {
+        let shut_down = answer.await? == 0;
+        window_handle.update(cx, |_, window, cx| {
+            session.update(cx, |session, cx| {
+                // Another editor may have attached while the prompt was open.
+                if !session.is_headless() {
+                    return;
+                }
+                if shut_down {
+                    session.shutdown(window, cx);
+                } else {
+                    session.set_keep_alive(true, cx);
+                }
+            });
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Records that `editor` is attached to `session` and follows the session's
+/// events.
+fn subscribe_to_session(
+    store: &Entity<ReplStore>,
+    editor: &WeakEntity<Editor>,
+    session: &Entity<Session>,
+    cx: &mut App,
+) {
+    editor
         .update(cx, |_editor, cx| {
             cx.notify();
 
-            cx.subscribe(&session, {
+            cx.subscribe(session, {
                 let store = store.clone();
-                move |_this, _session, event, cx| match event {
-                    SessionEvent::Shutdown(shutdown_event) => {
-                        store.update(cx, |store, _cx| {
-                            store.remove_session(shutdown_event.entity_id());
-                        });
+                move |editor, session, event, cx| match event {
+                    SessionEvent::Shutdown => {
+                        store.update(cx, |store, _cx| store.remove_session(&session));
                     }
+                    SessionEvent::SetNextInput {
+                        editor_id,
+                        code_range,
+                        text,
+                        replace,
+                    } => {
+                        if *editor_id == cx.entity_id() {
+                            set_next_input(editor, code_range, text, *replace, cx);
+                        }
+                    }
+                    SessionEvent::ExecutionAborted(_)
+                    | SessionEvent::VariablesChanged
+                    | SessionEvent::DebugEvent(_)
+                    | SessionEvent::RestartCompleted => {}
                 }
             })
             .detach();
@@ -69,10 +235,8 @@ pub fn assign_kernelspec(
         .ok();
 
     store.update(cx, |store, _cx| {
-        store.insert_session(weak_editor.entity_id(), session.clone());
+        store.insert_session(editor.entity_id(), session.clone());
     });
-
-    Ok(())
 }
 
 pub fn install_ipykernel_and_assign(
@@ -225,83 +389,351 @@ pub fn run(
     let Some(buffer) = multibuffer.read(cx).as_singleton() else {
         return Ok(());
     };
+    let worktree_id = buffer
+        .read(cx)
+        .project_path(cx)
+        .map(|project_path| project_path.worktree_id);
 
-    let Some(project_path) = buffer.read(cx).project_path(cx) else {
-        return Ok(());
-    };
-
+    let buffer_snapshot = buffer.read(cx).snapshot();
     let (runnable_ranges, next_cell_point) =
-        runnable_ranges(&buffer.read(cx).snapshot(), selected_range, cx);
+        runnable_ranges(&buffer_snapshot, selected_range.clone(), cx);
+
+    // Plain selections and "run line" are the only ranges that can split a
+    // statement; Jupytext cells and Markdown code blocks are run as written.
+    let expand_incomplete = ReplSettings::get_global(cx).expand_incomplete_selections
+        && is_plain_selection(&buffer_snapshot, selected_range.clone(), cx);
 
     for runnable_range in runnable_ranges {
         let Some(language) = multibuffer.read(cx).language_at(runnable_range.start, cx) else {
             continue;
         };
+        let session = get_or_start_session(&store, &editor, worktree_id, language, window, cx)?;
+
+        if expand_incomplete {
+            // "Run line" must never reach past the paragraph around the cursor.
+            let row_limit = selected_range
+                .is_empty()
+                .then(|| paragraph_rows(&buffer_snapshot, runnable_range.start.row));
+            execute_complete_statements(
+                editor.clone(),
+                buffer.clone(),
+                session,
+                runnable_range,
+                row_limit,
+                next_cell_point,
+                move_down,
+                window,
+                cx,
+            );
+        } else {
+            execute_range(
+                &editor,
+                &session,
+                runnable_range,
+                next_cell_point,
+                move_down,
+                window,
+                cx,
+            );
+        }
+    }
 
-        let kernel_specification = store
-            .read(cx)
-            .active_kernelspec(project_path.worktree_id, Some(language.clone()), cx)
-            .with_context(|| format!("No kernel found for language: {}", language.name()))?;
+    anyhow::Ok(())
+}
 
-        let fs = store.read(cx).fs().clone();
+/// Runs the code cell at the cursor, delimited by the language's
+/// `repl.cell_markers`. A script without markers is run as a whole. With
+/// `advance`, the cursor moves to the next cell.
+pub fn run_cell(
+    editor: WeakEntity<Editor>,
+    advance: bool,
+    window: &mut Window,
+    cx: &mut App,
+) -> Result<()> {
+    run_cells(editor, window, cx, |cells, current| {
+        let next_cell = cells.get(current + 1).map(|cell| cell.range.start);
+        (
+            cells.get(current).cloned().into_iter().collect(),
+            next_cell,
+            advance,
+        )
+    })
+}
 
-        let session = if let Some(session) = store.read(cx).get_session(editor.entity_id()).cloned()
-        {
-            session
-        } else {
-            let weak_editor = editor.downgrade();
-            let session =
-                cx.new(|cx| Session::new(weak_editor, fs, kernel_specification, window, cx));
-
-            editor.update(cx, |_editor, cx| {
-                cx.notify();
-
-                cx.subscribe(&session, {
-                    let store = store.clone();
-                    move |_this, _session, event, cx| match event {
-                        SessionEvent::Shutdown(shutdown_event) => {
-                            store.update(cx, |store, _cx| {
-                                store.remove_session(shutdown_event.entity_id());
-                            });
-                        }
-                    }
-                })
-                .detach();
-            });
+/// Runs every code cell before the one at the cursor, each as its own
+/// execution, in order.
+pub fn run_cells_above(
+    editor: WeakEntity<Editor>,
+    window: &mut Window,
+    cx: &mut App,
+) -> Result<()> {
+    run_cells(editor, window, cx, |cells, current| {
+        (cells.iter().take(current).cloned().collect(), None, false)
+    })
+}
 
-            store.update(cx, |store, _cx| {
-                store.insert_session(editor.entity_id(), session.clone());
-            });
+/// Runs every code cell up to and including the one at the cursor, each as
+/// its own execution, in order.
+pub fn run_cells_to_here(
+    editor: WeakEntity<Editor>,
+    window: &mut Window,
+    cx: &mut App,
+) -> Result<()> {
+    run_cells(editor, window, cx, |cells, current| {
+        (
+            cells.iter().take(current + 1).cloned().collect(),
+            None,
+            false,
+        )
+    })
+}
 
-            session
-        };
+/// Runs every code cell in the buffer, each as its own execution, in order.
+pub fn run_all_cells(editor: WeakEntity<Editor>, window: &mut Window, cx: &mut App) -> Result<()> {
+    run_cells(editor, window, cx, |cells, _| (cells.to_vec(), None, false))
+}
 
-        let selected_text;
-        let anchor_range;
-        let next_cursor;
-        {
-            let snapshot = multibuffer.read(cx).read(cx);
-            selected_text = snapshot
-                .text_for_range(runnable_range.clone())
-                .collect::<String>();
-            anchor_range = snapshot.anchor_before(runnable_range.start)
-                ..snapshot.anchor_after(runnable_range.end);
-            next_cursor = next_cell_point.map(|point| snapshot.anchor_after(point));
-        }
+/// Runs the cells `select` picks out of the buffer's cells, given the index of
+/// the cell at the cursor. It returns the cells to run, where the cursor moves
+/// to, and whether it moves. Markdown and raw cells are never run. The cells'
+/// code is read before any of it runs, and a cell that fails stops the cells
+/// after it.
+fn run_cells(
+    editor: WeakEntity<Editor>,
+    window: &mut Window,
+    cx: &mut App,
+    select: impl FnOnce(&[CodeCell], usize) -> (Vec<CodeCell>, Option<Point>, bool),
+) -> Result<()> {
+    let store = ReplStore::global(cx);
+    if !store.read(cx).is_enabled() {
+        return Ok(());
+    }
+    store.update(cx, |store, cx| store.ensure_kernelspecs(cx));
 
-        session.update(cx, |session, cx| {
-            session.execute(
-                selected_text,
-                anchor_range,
-                next_cursor,
-                move_down,
-                window,
-                cx,
-            );
-        });
+    let editor = editor.upgrade().context("editor was dropped")?;
+    let cursor = editor
+        .update(cx, |editor, cx| {
+            editor
+                .selections
+                .newest_adjusted(&editor.display_snapshot(cx))
+        })
+        .head();
+    let multibuffer = editor.read(cx).buffer().clone();
+    let Some(buffer) = multibuffer.read(cx).as_singleton() else {
+        return Ok(());
+    };
+    let worktree_id = buffer
+        .read(cx)
+        .project_path(cx)
+        .map(|project_path| project_path.worktree_id);
+
+    let buffer_snapshot = buffer.read(cx).snapshot();
+    let Some(language) = buffer_snapshot.language() else {
+        return Ok(());
+    };
+    let markers = cell_markers(language, cx);
+    let mut cells = code_cells(&buffer_snapshot, &markers);
+    if cells.is_empty() {
+        cells.extend(whole_buffer_range(&buffer_snapshot).map(|range| CodeCell {
+            range,
+            has_marker: false,
+            cell_type: ReplCellType::Code,
+        }));
     }
+    // A cursor above the first cell runs that cell.
+    let current = cells
+        .iter()
+        .rposition(|cell| cell.range.start.row <= cursor.row)
+        .unwrap_or(0);
+
+    let (selected_cells, next_cell_point, move_down) = select(&cells, current);
+    let runnable_cells = selected_cells
+        .into_iter()
+        .filter(|cell| cell.cell_type == ReplCellType::Code)
+        .map(|cell| {
+            (
+                buffer_snapshot
+                    .text_for_range(cell.range.clone())
+                    .collect::<String>(),
+                cell.range,
+            )
+        })
+        .filter(|(code, _)| !code.is_empty())
+        .collect::<Vec<_>>();
+    let Some((_, first_range)) = runnable_cells.first() else {
+        return Ok(());
+    };
+    let Some(language) = multibuffer.read(cx).language_at(first_range.start, cx) else {
+        return Ok(());
+    };
+    let session = get_or_start_session(&store, &editor, worktree_id, language, window, cx)?;
 
-    anyhow::Ok(())
+    let (runnable_cells, next_cell) = {
+        let snapshot = multibuffer.read(cx).read(cx);
+        let runnable_cells = runnable_cells
+            .into_iter()
+            .map(|(code, range)| {
+                let anchor_range =
+                    snapshot.anchor_before(range.start)..snapshot.anchor_after(range.end);
+                (code, anchor_range)
+            })
+            .collect::<Vec<_>>();
+        (
+            runnable_cells,
+            next_cell_point.map(|point| snapshot.anchor_after(point)),
+        )
+    };
+    session.update(cx, |session, cx| {
+        session.run_cells(
+            editor.downgrade(),
+            runnable_cells,
+            next_cell,
+            move_down,
+            window,
+            cx,
+        );
+    });
+    Ok(())
+}
+
+/// The editor's session, started with the active kernel for `language` when
+/// the editor doesn't have one yet. Buffers outside of any worktree, such as
+/// untitled ones, have a `worktree_id` of `None`.
+fn get_or_start_session(
+    store: &Entity<ReplStore>,
+    editor: &Entity<Editor>,
+    worktree_id: Option<WorktreeId>,
+    language: Arc<Language>,
+    window: &mut Window,
+    cx: &mut App,
+) -> Result<Entity<Session>> {
+    if let Some(session) = store.read(cx).get_session(editor.entity_id()).cloned() {
+        return Ok(session);
+    }
+
+    let kernel_specification = store
+        .read(cx)
+        .active_kernelspec(worktree_id, Some(language.clone()), cx)
+        .with_context(|| format!("No kernel found for language: {}", language.name()))?;
+    let fs = store.read(cx).fs().clone();
+    let weak_editor = editor.downgrade();
+    let session =
+        cx.new(|cx| Session::new(weak_editor.clone(), fs, kernel_specification, window, cx));
+    subscribe_to_session(store, &weak_editor, &session, cx);
+
+    Ok(session)
+}
+
+/// Puts code the kernel sent with `set_next_input` into the editor, either in
+/// place of the code that was run or on a new line after it.
+fn set_next_input(
+    editor: &mut Editor,
+    code_range: &Range<Anchor>,
+    text: &str,
+    replace: bool,
+    cx: &mut Context<Editor>,
+) {
+    if replace {
+        editor.edit([(code_range.clone(), text)], cx);
+    } else {
+        let end = code_range.end;
+        editor.edit([(end..end, format!("\n{text}"))], cx);
+    }
+}
+
+fn execute_range(
+    editor: &Entity<Editor>,
+    session: &Entity<Session>,
+    range: Range<Point>,
+    next_cell_point: Option<Point>,
+    move_down: bool,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let multibuffer = editor.read(cx).buffer().clone();
+    let selected_text;
+    let anchor_range;
+    let next_cursor;
+    {
+        let snapshot = multibuffer.read(cx).read(cx);
+        selected_text = snapshot.text_for_range(range.clone()).collect::<String>();
+        anchor_range = snapshot.anchor_before(range.start)..snapshot.anchor_after(range.end);
+        next_cursor = next_cell_point.map(|point| snapshot.anchor_after(point));
+    }
+
+    session.update(cx, |session, cx| {
+        session.execute_in(
+            editor.downgrade(),
+            selected_text,
+            anchor_range,
+            next_cursor,
+            move_down,
+            ExecutionOptions::default(),
+            window,
+            cx,
+        );
+    });
+}
+
+/// Asks the kernel whether `range` is complete before running it. When it isn't
+/// (e.g. a selection that splits an indented block), the range grows to the
+/// smallest enclosing complete statements, as long as those stay within
+/// `row_limit`. Kernels that can't tell are sent the range as-is.
+fn execute_complete_statements(
+    editor: Entity<Editor>,
+    buffer: Entity<Buffer>,
+    session: Entity<Session>,
+    range: Range<Point>,
+    row_limit: Option<Range<u32>>,
+    next_cell_point: Option<Point>,
+    move_down: bool,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let (code, anchor_range) = {
+        let snapshot = buffer.read(cx).snapshot();
+        (
+            snapshot.text_for_range(range.clone()).collect::<String>(),
+            snapshot.anchor_before(range.start)..snapshot.anchor_after(range.end),
+        )
+    };
+    let is_complete = session.update(cx, |session, cx| session.is_complete(code, cx));
+
+    window
+        .spawn(cx, async move |cx| {
+            let status = is_complete.await;
+            cx.update(|window, cx| {
+                let snapshot = buffer.read(cx).snapshot();
+                let range =
+                    anchor_range.start.to_point(&snapshot)..anchor_range.end.to_point(&snapshot);
+                let range = match status {
+                    IsCompleteReplyStatus::Incomplete => {
+                        complete_statements_range(&snapshot, range.clone())
+                            .filter(|expanded| {
+                                row_limit.as_ref().is_none_or(|rows| {
+                                    rows.contains(&expanded.start.row)
+                                        && rows.contains(&expanded.end.row)
+                                })
+                            })
+                            .unwrap_or(range)
+                    }
+                    IsCompleteReplyStatus::Complete
+                    | IsCompleteReplyStatus::Invalid
+                    | IsCompleteReplyStatus::Unknown => range,
+                };
+                execute_range(
+                    &editor,
+                    &session,
+                    range,
+                    next_cell_point,
+                    move_down,
+                    window,
+                    cx,
+                );
+            })
+            .ok();
+        })
+        .detach();
 }
 
 pub enum SessionSupport {
@@ -338,10 +770,6 @@ pub fn session(editor: WeakEntity<Editor>, cx: &mut App) -> SessionSupport {
 
     let worktree_id = worktree_id_for_editor(editor, cx);
 
-    let Some(worktree_id) = worktree_id else {
-        return SessionSupport::Unsupported;
-    };
-
     let kernelspec = store
         .read(cx)
         .active_kernelspec(worktree_id, Some(language.clone()), cx);
@@ -366,7 +794,7 @@ pub fn clear_outputs(editor: WeakEntity<Editor>, cx: &mut App) {
         return;
     };
     session.update(cx, |session, cx| {
-        session.clear_outputs(cx);
+        session.clear_outputs_in(entity_id, cx);
         cx.notify();
     });
 }
@@ -385,7 +813,7 @@ pub fn clear_current_output(editor: WeakEntity<Editor>, cx: &mut App) {
     let position = editor_entity.read(cx).selections.newest_anchor().head();
 
     session.update(cx, |session, cx| {
-        session.clear_output_at_position(position, cx);
+        session.clear_output_at_position(&editor_entity, position, cx);
     });
 }
 
@@ -402,6 +830,19 @@ pub fn interrupt(editor: WeakEntity<Editor>, cx: &mut App) {
     });
 }
 
+/// Stops the cells started together by "Run All Cells" and similar actions
+/// that haven't run yet. With `interrupt`, the cell that's running is
+/// interrupted too.
+pub fn cancel_cell_run(editor: WeakEntity<Editor>, interrupt: bool, cx: &mut App) {
+    let store = ReplStore::global(cx);
+    let entity_id = editor.entity_id();
+    let Some(session) = store.read(cx).get_session(entity_id).cloned() else {
+        return;
+    };
+
+    session.update(cx, |session, cx| session.cancel_cell_run(interrupt, cx));
+}
+
 pub fn shutdown(editor: WeakEntity<Editor>, window: &mut Window, cx: &mut App) {
     let store = ReplStore::global(cx);
     let entity_id = editor.entity_id();
@@ -415,6 +856,42 @@ pub fn shutdown(editor: WeakEntity<Editor>, window: &mut Window, cx: &mut App) {
     });
 }
 
+pub fn open_kernel_log(editor: WeakEntity<Editor>, window: &mut Window, cx: &mut App) {
+    let store = ReplStore::global(cx);
+    let entity_id = editor.entity_id();
+    let Some(session) = store.read(cx).get_session(entity_id).cloned() else {
+        return;
+    };
+
+    session.update(cx, |session, cx| {
+        session.open_diagnostics_log(window, cx);
+    });
+}
+
+/// Prompts for a file to log the session's stream output to, or stops logging
+/// if it already is.
+pub fn toggle_output_log(editor: WeakEntity<Editor>, cx: &mut App) {
+    let store = ReplStore::global(cx);
+    let entity_id = editor.entity_id();
+    let Some(session) = store.read(cx).get_session(entity_id).cloned() else {
+        return;
+    };
+    if session.read(cx).output_log_path().is_some() {
+        session.update(cx, |session, cx| session.set_output_log(None, cx));
+        return;
+    }
+
+    let path = cx.prompt_for_new_path(util::paths::home_dir(), Some("output.log"));
+    cx.spawn(async move |cx| {
+        let Some(path) = path.await?? else {
+            return anyhow::Ok(());
+        };
+        session.update(cx, |session, cx| session.set_output_log(Some(path), cx));
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
+
 pub fn restart(editor: WeakEntity<Editor>, window: &mut Window, cx: &mut App) {
     let Some(editor) = editor.upgrade() else {
         return;
@@ -476,6 +953,32 @@ pub fn setup_editor_session_actions(editor: &mut Editor, editor_handle: WeakEnti
         })
         .detach();
 
+    editor
+        .register_action({
+            let editor_handle = editor_handle.clone();
+            move |_: &CancelCellRun, _, cx| {
+                if !JupyterSettings::enabled(cx) {
+                    return;
+                }
+
+                crate::cancel_cell_run(editor_handle.clone(), false, cx);
+            }
+        })
+        .detach();
+
+    editor
+        .register_action({
+            let editor_handle = editor_handle.clone();
+            move |_: &CancelCellRunAndInterrupt, _, cx| {
+                if !JupyterSettings::enabled(cx) {
+                    return;
+                }
+
+                crate::cancel_cell_run(editor_handle.clone(), true, cx);
+            }
+        })
+        .detach();
+
     editor
         .register_action({
             let editor_handle = editor_handle.clone();
@@ -489,6 +992,32 @@ pub fn setup_editor_session_actions(editor: &mut Editor, editor_handle: WeakEnti
         })
         .detach();
 
+    editor
+        .register_action({
+            let editor_handle = editor_handle.clone();
+            move |_: &OpenKernelLog, window, cx| {
+                if !JupyterSettings::enabled(cx) {
+                    return;
+                }
+
+                crate::open_kernel_log(editor_handle.clone(), window, cx);
+            }
+        })
+        .detach();
+
+    editor
+        .register_action({
+            let editor_handle = editor_handle.clone();
+            move |_: &ToggleOutputLog, _, cx| {
+                if !JupyterSettings::enabled(cx) {
+                    return;
+                }
+
+                crate::toggle_output_log(editor_handle.clone(), cx);
+            }
+        })
+        .detach();
+
     editor
         .register_action({
             let editor_handle = editor_handle;
@@ -515,63 +1044,31 @@ fn cell_range(buffer: &BufferSnapshot, start_row: u32, end_row: u32) -> Range<Po
 fn jupytext_cells(
     buffer: &BufferSnapshot,
     range: Range<Point>,
+    cx: &App,
 ) -> (Vec<Range<Point>>, Option<Point>) {
-    let mut current_row = range.start.row;
-
     let Some(language) = buffer.language() else {
         return (Vec::new(), None);
     };
-
-    let default_scope = language.default_scope();
-    let comment_prefixes = default_scope.line_comment_prefixes();
-    if comment_prefixes.is_empty() {
-        return (Vec::new(), None);
-    }
-
-    let jupytext_prefixes = comment_prefixes
-        .iter()
-        .map(|comment_prefix| format!("{comment_prefix}%%"))
+    let markers = cell_markers(language, cx);
+    // Code before the first marker isn't a cell, so it runs line by line.
+    let cells = code_cells(buffer, &markers)
+        .into_iter()
+        .filter(|cell| cell.has_marker)
+        .map(|cell| cell.range)
         .collect::<Vec<_>>();
 
-    let mut snippet_start_row = None;
-    loop {
-        if jupytext_prefixes
-            .iter()
-            .any(|prefix| buffer.contains_str_at(Point::new(current_row, 0), prefix))
-        {
-            snippet_start_row = Some(current_row);
-            break;
-        } else if current_row > 0 {
-            current_row -= 1;
-        } else {
-            break;
-        }
-    }
-
     let mut snippets = Vec::new();
-    if let Some(mut snippet_start_row) = snippet_start_row {
-        for current_row in range.start.row + 1..=buffer.max_point().row {
-            if jupytext_prefixes
-                .iter()
-                .any(|prefix| buffer.contains_str_at(Point::new(current_row, 0), prefix))
-            {
-                snippets.push(cell_range(buffer, snippet_start_row, current_row - 1));
-
-                if current_row <= range.end.row {
-                    snippet_start_row = current_row;
-                } else {
-                    // Return our snippets as well as the next point for moving the cursor to
-                    return (snippets, Some(Point::new(current_row, 0)));
-                }
-            }
+    for (index, cell) in cells.iter().enumerate() {
+        // A cell owns the blank lines after it, up to the next marker.
+        let last_row = cells
+            .get(index + 1)
+            .map_or(buffer.max_point().row, |next| next.start.row - 1);
+        if cell.start.row > range.end.row {
+            return (snippets, Some(cell.start));
+        }
+        if last_row >= range.start.row {
+            snippets.push(cell.clone());
         }
-
-        // Go to the end of the buffer (no more jupytext cells found)
-        snippets.push(cell_range(
-            buffer,
-            snippet_start_row,
-            buffer.max_point().row,
-        ));
     }
 
     (snippets, None)
@@ -588,7 +1085,7 @@ fn runnable_ranges(
         return (markdown_code_blocks(buffer, range, cx), None);
     }
 
-    let (jupytext_snippets, next_cursor) = jupytext_cells(buffer, range.clone());
+    let (jupytext_snippets, next_cursor) = jupytext_cells(buffer, range.clone(), cx);
     if !jupytext_snippets.is_empty() {
         return (jupytext_snippets, next_cursor);
     }
@@ -637,6 +1134,67 @@ fn runnable_ranges(
     }
 }
 
+/// Whether `range` is an ordinary selection, rather than one in a Markdown
+/// file or a Jupytext cell, which run as whole blocks.
+fn is_plain_selection(buffer: &BufferSnapshot, range: Range<Point>, cx: &App) -> bool {
+    let is_markdown = buffer
+        .language()
+        .is_some_and(|language| language.name() == "Markdown");
+    !is_markdown && jupytext_cells(buffer, range, cx).0.is_empty()
+}
+
+/// The rows of the block of non-blank lines around `row`.
+fn paragraph_rows(buffer: &BufferSnapshot, row: u32) -> Range<u32> {
+    let mut start_row = row;
+    while start_row > 0 && !buffer.is_line_blank(start_row - 1) {
+        start_row -= 1;
+    }
+    let mut end_row = row;
+    while end_row < buffer.max_point().row && !buffer.is_line_blank(end_row + 1) {
+        end_row += 1;
+    }
+    start_row..end_row + 1
+}
+
+/// Grows `range` to the smallest run of whole statements that contains it,
+/// judged by the syntax tree: a statement is a node that starts its line.
+fn complete_statements_range(buffer: &BufferSnapshot, range: Range<Point>) -> Option<Range<Point>> {
+    let byte_range = buffer.point_to_offset(range.start)..buffer.point_to_offset(range.end);
+    let starts_line = |offset: usize| {
+        let point = buffer.offset_to_point(offset);
+        point.column <= buffer.indent_size_for_line(point.row).len
+    };
+    let whole_lines = |start: usize, end: usize| {
+        let start = buffer.offset_to_point(start);
+        let end = buffer.offset_to_point(end);
+        Point::new(start.row, 0)..Point::new(end.row, buffer.line_len(end.row))
+    };
+
+    let mut node = buffer.syntax_ancestor(byte_range.clone())?;
+    loop {
+        let mut cursor = node.walk();
+        let statements = node
+            .named_children(&mut cursor)
+            .filter(|child| {
+                child.start_byte() < byte_range.end && child.end_byte() > byte_range.start
+            })
+            .collect::<Vec<_>>();
+        if let (Some(first), Some(last)) = (statements.first(), statements.last())
+            && statements
+                .iter()
+                .all(|statement| starts_line(statement.start_byte()))
+        {
+            return Some(whole_lines(first.start_byte(), last.end_byte()));
+        }
+
+        let parent = node.parent()?;
+        if starts_line(node.start_byte()) {
+            return Some(whole_lines(node.start_byte(), node.end_byte()));
+        }
+        node = parent;
+    }
+}
+
 // We allow markdown code blocks to end in a trailing newline in order to render the output
 // below the final code fence. This is different than our behavior for selections and Jupytext cells.
 fn markdown_code_blocks(
@@ -687,6 +1245,8 @@ mod tests {
 
     #[gpui::test]
     fn test_snippet_ranges(cx: &mut App) {
+        settings::init(cx);
+
         // Create a test language
         let test_language = Arc::new(Language::new(
             LanguageConfig {
@@ -753,6 +1313,8 @@ mod tests {
 
     #[gpui::test]
     fn test_jupytext_snippet_ranges(cx: &mut App) {
+        settings::init(cx);
+
         // Create a test language
         let test_language = Arc::new(Language::new(
             LanguageConfig {
@@ -1010,8 +1572,54 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    fn test_session_support_for_untitled_buffer(cx: &mut gpui::TestAppContext) {
+        use crate::kernels::LocalKernelSpecification;
+        use jupyter_protocol::JupyterKernelspec;
+
+        let python_spec = KernelSpecification::Jupyter(LocalKernelSpecification {
+            name: "python3".into(),
+            kernelspec: JupyterKernelspec {
+                argv: vec![],
+                display_name: "Python 3".into(),
+                language: "python".into(),
+                interrupt_mode: None,
+                metadata: None,
+                env: None,
+            },
+            path: std::path::PathBuf::new(),
+        });
+        cx.update(|cx| {
+            let settings_store = settings::SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            theme_settings::init(theme::LoadThemes::JustBase, cx);
+            editor::init(cx);
+            ReplStore::init(project::FakeFs::new(cx.background_executor().clone()), cx);
+            ReplStore::global(cx).update(cx, |store, cx| {
+                store.set_kernel_specs_for_testing(vec![python_spec.clone()], cx);
+            });
+        });
+
+        let python = languages::language("python", tree_sitter_python::LANGUAGE.into());
+        let buffer = cx.new(|cx| {
+            let mut buffer = Buffer::local("print(1)\n", cx);
+            buffer.set_language(Some(python), cx);
+            buffer
+        });
+        let window = cx.add_window(|window, cx| Editor::for_buffer(buffer, None, window, cx));
+        let editor = window.root(cx).unwrap();
+
+        let support = cx.update(|cx| session(editor.downgrade(), cx));
+        let SessionSupport::Inactive(kernel_specification) = support else {
+            panic!("expected a kernel for an untitled Python buffer");
+        };
+        assert_eq!(kernel_specification, python_spec);
+    }
+
     #[gpui::test]
     fn test_skip_blank_lines_to_next_cell(cx: &mut App) {
+        settings::init(cx);
+
         let test_language = Arc::new(Language::new(
             LanguageConfig {
                 name: "TestLang".into(),
@@ -1081,4 +1689,69 @@ mod tests {
         let (snippets, _) = runnable_ranges(&snapshot, Point::new(1, 0)..Point::new(1, 0), cx);
         assert!(snippets.is_empty());
     }
+
+    #[gpui::test]
+    fn test_complete_statements_range(cx: &mut App) {
+        let python = languages::language("python", tree_sitter_python::LANGUAGE.into());
+        let buffer = cx.new(|cx| {
+            Buffer::local(
+                indoc! { r#"
+                    total = 0
+                    for i in range(3):
+                        total += i
+                        print(total)
+
+                    result = max(
+                        1,
+                        2,
+                    )
+                "# },
+                cx,
+            )
+            .with_language(python, cx)
+        });
+        let snapshot = buffer.read(cx).snapshot();
+        let expand = |range: Range<Point>| {
+            complete_statements_range(&snapshot, range)
+                .map(|range| snapshot.text_for_range(range).collect::<String>())
+        };
+
+        // A loop header grows to cover its whole body
+        assert_eq!(
+            expand(Point::new(1, 0)..Point::new(1, 18)).as_deref(),
+            Some(indoc! { r#"
+                for i in range(3):
+                    total += i
+                    print(total)"# })
+        );
+
+        // A selection spanning two top-level statements keeps both, whole
+        assert_eq!(
+            expand(Point::new(0, 4)..Point::new(1, 5)).as_deref(),
+            Some(indoc! { r#"
+                total = 0
+                for i in range(3):
+                    total += i
+                    print(total)"# })
+        );
+
+        // The first line of a multi-line expression grows to the whole assignment
+        assert_eq!(
+            expand(Point::new(5, 0)..Point::new(5, 13)).as_deref(),
+            Some(indoc! { r#"
+                result = max(
+                    1,
+                    2,
+                )"# })
+        );
+
+        // Statements inside a block stay inside that block
+        assert_eq!(
+            expand(Point::new(2, 4)..Point::new(2, 14)).as_deref(),
+            Some("    total += i")
+        );
+
+        assert_eq!(paragraph_rows(&snapshot, 2), 0..4);
+        assert_eq!(paragraph_rows(&snapshot, 6), 5..9);
+    }
 }
@@ -1,9 +1,12 @@
-use editor::Editor;
+use collections::HashMap;
+use editor::{Editor, EditorEvent};
 use gpui::{
-    AnyElement, App, Entity, EventEmitter, FocusHandle, Focusable, Subscription, TaskExt, actions,
-    prelude::*,
+    Action, AnyElement, App, Entity, EntityId, EventEmitter, FocusHandle, Focusable, Subscription,
+    TaskExt, WeakEntity, actions, prelude::*,
 };
 use project::ProjectItem as _;
+use schemars::JsonSchema;
+use serde::Deserialize;
 use ui::{ButtonLike, ElevationIndex, KeyBinding, prelude::*};
 use util::ResultExt as _;
 use workspace::item::ItemEvent;
@@ -11,6 +14,7 @@ use workspace::{Workspace, item::Item};
 
 use crate::jupyter_settings::JupyterSettings;
 use crate::repl_store::ReplStore;
+use crate::session::Session;
 
 actions!(
     repl,
@@ -19,6 +23,24 @@ actions!(
         Run,
         /// Runs the current cell without advancing.
         RunInPlace,
+        /// Runs the code cell at the cursor, delimited by cell markers such as
+        /// `# %%`.
+        RunCell,
+        /// Runs the code cell at the cursor and moves to the next one.
+        RunCellAndAdvance,
+        /// Runs every code cell above the one at the cursor.
+        RunCellsAbove,
+        /// Runs every code cell up to and including the one at the cursor.
+        RunCellsToHere,
+        /// Runs every code cell in the script, skipping markdown and raw
+        /// cells.
+        RunAllCells,
+        /// Stops running the cells started together by a command like "Run
+        /// All Cells", letting the cell that's running finish.
+        CancelCellRun,
+        /// Stops running the cells started together by a command like "Run
+        /// All Cells", interrupting the cell that's running.
+        CancelCellRunAndInterrupt,
         /// Clears all outputs in the REPL.
         ClearOutputs,
         /// Clears the output of the cell at the current cursor position.
@@ -31,11 +53,35 @@ actions!(
         Shutdown,
         /// Restarts the current kernel.
         Restart,
+        /// Opens the output of the current kernel's process, such as warnings
+        /// and crash messages printed outside of the notebook protocol.
+        OpenKernelLog,
+        /// Starts logging the stream output of executions to a file, or stops
+        /// logging it.
+        ToggleOutputLog,
         /// Refreshes the list of available kernelspecs.
-        RefreshKernelspecs
+        RefreshKernelspecs,
+        /// Forgets the kernels picked for this project, so default kernels are
+        /// chosen automatically again.
+        ClearKernelSelection,
+        /// Stops running the current buffer's code in its session, leaving the
+        /// kernel running for the other buffers attached to it.
+        DetachFromSession
     ]
 );
 
+/// Runs the current buffer's code in a running session that other buffers
+/// share, instead of in a kernel of its own.
+#[derive(Clone, Default, PartialEq, Deserialize, JsonSchema, Action)]
+#[action(namespace = repl)]
+#[serde(deny_unknown_fields)]
+pub struct AttachToSession {
+    /// The name of the session to attach to. When omitted, the only running
+    /// session for the buffer's language is used.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
 pub fn init(cx: &mut App) {
     cx.observe_new(
         |workspace: &mut Workspace, _window, _cx: &mut Context<Workspace>| {
@@ -66,6 +112,23 @@ pub fn init(cx: &mut App) {
                     store.refresh_kernelspecs(cx).detach();
                 });
             });
+
+            workspace.register_action(|workspace, _: &ClearKernelSelection, _, cx| {
+                // Includes the kernel picked for buffers outside of any worktree.
+                let worktree_ids = workspace
+                    .project()
+                    .read(cx)
+                    .worktrees(cx)
+                    .map(|worktree| Some(worktree.read(cx).id()))
+                    .chain([None])
+                    .collect::<Vec<_>>();
+                let store = ReplStore::global(cx);
+                store.update(cx, |store, cx| {
+                    for worktree_id in worktree_ids {
+                        store.clear_selected_kernel(worktree_id, cx);
+                    }
+                });
+            });
         },
     )
     .detach();
@@ -107,12 +170,13 @@ pub fn init(cx: &mut App) {
 
                 if let Some(language) = language
                     && language.name() == "Python"
-                    && let (Some(project_path), Some(project)) = (project_path, project)
+                    && let Some(project) = project
                 {
+                    let worktree_id = project_path.map(|project_path| project_path.worktree_id);
                     let store = ReplStore::global(cx);
                     store.update(cx, |store, cx| {
                         store
-                            .refresh_python_kernelspecs(project_path.worktree_id, &project, cx)
+                            .refresh_python_kernelspecs(worktree_id, &project, cx)
                             .detach_and_log_err(cx);
                     });
                 }
@@ -132,6 +196,7 @@ pub fn init(cx: &mut App) {
 
                 editor
                     .register_action({
+                        let editor_handle = editor_handle.clone();
                         move |_: &RunInPlace, window, cx| {
                             if !JupyterSettings::enabled(cx) {
                                 return;
@@ -141,6 +206,107 @@ pub fn init(cx: &mut App) {
                         }
                     })
                     .detach();
+
+                editor
+                    .register_action({
+                        let editor_handle = editor_handle.clone();
+                        move |_: &RunCell, window, cx| {
+                            if !JupyterSettings::enabled(cx) {
+                                return;
+                            }
+
+                            crate::run_cell(editor_handle.clone(), false, window, cx).log_err();
+                        }
+                    })
+                    .detach();
+
+                editor
+                    .register_action({
+                        let editor_handle = editor_handle.clone();
+                        move |_: &RunCellAndAdvance, window, cx| {
+                            if !JupyterSettings::enabled(cx) {
+                                return;
+                            }
+
+                            crate::run_cell(editor_handle.clone(), true, window, cx).log_err();
+                        }
+                    })
+                    .detach();
+
+                editor
+                    .register_action({
+                        let editor_handle = editor_handle.clone();
+                        move |_: &RunCellsAbove, window, cx| {
+                            if !JupyterSettings::enabled(cx) {
+                                return;
+                            }
+
+                            crate::run_cells_above(editor_handle.clone(), window, cx).log_err();
+                        }
+                    })
+                    .detach();
+
+                editor
+                    .register_action({
+                        let editor_handle = editor_handle.clone();
+                        move |_: &RunCellsToHere, window, cx| {
+                            if !JupyterSettings::enabled(cx) {
+                                return;
+                            }
+
+                            crate::run_cells_to_here(editor_handle.clone(), window, cx).log_err();
+                        }
+                    })
+                    .detach();
+
+                editor
+                    .register_action({
+                        let editor_handle = editor_handle.clone();
+                        move |_: &RunAllCells, window, cx| {
+                            if !JupyterSettings::enabled(cx) {
+                                return;
+                            }
+
+                            crate::run_all_cells(editor_handle.clone(), window, cx).log_err();
+                        }
+                    })
+                    .detach();
+
+                editor
+                    .register_action({
+                        let editor_handle = editor_handle.clone();
+                        move |action: &AttachToSession, window, cx| {
+                            if !JupyterSettings::enabled(cx) {
+                                return;
+                            }
+
+                            let attached = crate::attach_to_named_session(
+                                editor_handle.clone(),
+                                action.name.as_deref(),
+                                window,
+                                cx,
+                            );
+                            if let Err(error) = attached
+                                && let Some(workspace) = Workspace::for_window(window, cx)
+                            {
+                                workspace
+                                    .update(cx, |workspace, cx| workspace.show_error(&error, cx));
+                            }
+                        }
+                    })
+                    .detach();
+
+                editor
+                    .register_action({
+                        move |_: &DetachFromSession, window, cx| {
+                            if !JupyterSettings::enabled(cx) {
+                                return;
+                            }
+
+                            crate::detach_from_session(editor_handle.clone(), window, cx);
+                        }
+                    })
+                    .detach();
             });
         },
     )
@@ -149,11 +315,16 @@ pub fn init(cx: &mut App) {
 
 pub struct ReplSessionsPage {
     focus_handle: FocusHandle,
+    workspace: WeakEntity<Workspace>,
+    /// The editor each session is renamed with, by the session's entity id,
+    /// along with the subscription that renames it on blur.
+    name_editors: HashMap<EntityId, (Entity<Editor>, Subscription)>,
     _subscriptions: Vec<Subscription>,
 }
 
 impl ReplSessionsPage {
     pub fn new(window: &mut Window, cx: &mut Context<Workspace>) -> Entity<Self> {
+        let workspace = cx.entity().downgrade();
         cx.new(|cx| {
             let focus_handle = cx.focus_handle();
 
@@ -166,10 +337,60 @@ impl ReplSessionsPage {
 
             Self {
                 focus_handle,
+                workspace,
+                name_editors: HashMap::default(),
                 _subscriptions: subscriptions,
             }
         })
     }
+
+    fn name_editor(
+        &mut self,
+        session: &Entity<Session>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Entity<Editor> {
+        if let Some((editor, _)) = self.name_editors.get(&session.entity_id()) {
+            return editor.clone();
+        }
+
+        let name = session.read(cx).name().clone();
+        let editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_text(name, window, cx);
+            editor
+        });
+        let subscription = cx.subscribe_in(&editor, window, {
+            let session = session.downgrade();
+            move |this, editor, event, window, cx| {
+                if let (EditorEvent::Blurred, Some(session)) = (event, session.upgrade()) {
+                    this.rename_session(&session, editor, window, cx);
+                }
+            }
+        });
+        self.name_editors
+            .insert(session.entity_id(), (editor.clone(), subscription));
+        editor
+    }
+
+    fn rename_session(
+        &mut self,
+        session: &Entity<Session>,
+        editor: &Entity<Editor>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let name = editor.read(cx).text(cx);
+        let renamed =
+            ReplStore::global(cx).update(cx, |store, cx| store.rename_session(session, &name, cx));
+        if let Err(error) = renamed {
+            let name = session.read(cx).name().clone();
+            editor.update(cx, |editor, cx| editor.set_text(name, window, cx));
+            self.workspace
+                .update(cx, |workspace, cx| workspace.show_error(&error, cx))
+                .log_err();
+        }
+    }
 }
 
 impl EventEmitter<ItemEvent> for ReplSessionsPage {}
@@ -201,7 +422,7 @@ impl Item for ReplSessionsPage {
 }
 
 impl Render for ReplSessionsPage {
-    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let store = ReplStore::global(cx);
 
         let (kernel_specifications, sessions) = store.update(cx, |store, cx| {
@@ -250,7 +471,28 @@ impl Render for ReplSessionsPage {
             );
         }
 
-        ReplSessionsContainer::new("Jupyter Kernel Sessions").children(sessions)
+        self.name_editors.retain(|session_id, _| {
+            sessions
+                .iter()
+                .any(|session| session.entity_id() == *session_id)
+        });
+        let rows = sessions
+            .into_iter()
+            .map(|session| {
+                let name_editor = self.name_editor(&session, window, cx);
+                v_flex()
+                    .gap_1()
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(Label::new("Name").color(Color::Muted))
+                            .child(div().w(rems(16.)).child(name_editor)),
+                    )
+                    .child(session)
+            })
+            .collect::<Vec<_>>();
+
+        ReplSessionsContainer::new("Jupyter Kernel Sessions").children(rows)
     }
 }
 
@@ -1,4 +1,10 @@
-use settings::{RegisterSetting, Settings};
+use collections::HashMap;
+use settings::{
+    RegisterSetting, RemoteKernelExitBehavior, ReplCellType, Settings, merge_from::MergeFrom as _,
+};
+use std::{path::PathBuf, time::Duration};
+
+use crate::kernels::KernelResourceLimits;
 
 /// Settings for configuring REPL display and behavior.
 #[derive(Clone, Debug, RegisterSetting)]
@@ -27,11 +33,121 @@ pub struct ReplSettings {
     ///
     /// Default: 0
     pub output_max_height_lines: usize,
+    /// Whether to grow a selection the kernel reports as incomplete to the
+    /// smallest enclosing complete statements before running it.
+    ///
+    /// Default: true
+    pub expand_incomplete_selections: bool,
+    /// How long to keep trying to reconnect to a remote kernel after its
+    /// connection drops before giving up.
+    ///
+    /// Default: 60
+    pub remote_reconnect_timeout_seconds: u64,
+    /// How long to wait, in milliseconds, for a kernel to report that it's
+    /// idle after replying to an execution before finishing it anyway. 0
+    /// waits forever.
+    ///
+    /// Default: 2000
+    pub idle_status_timeout_ms: u64,
+    /// What to do with kernels on remote Jupyter servers when their workspace
+    /// closes or Zed quits, keyed by server URL.
+    ///
+    /// Default: {}
+    pub remote_kernels_on_exit: HashMap<String, RemoteKernelExitBehavior>,
+    /// Whether to show HTML outputs as the kernel sent them, keyed by where
+    /// the kernel runs.
+    ///
+    /// Default: {"local": true}
+    pub trusted_html_sources: HashMap<String, bool>,
+    /// Where local directories live on remote Jupyter servers, keyed by
+    /// server URL.
+    ///
+    /// Default: {}
+    pub remote_project_roots: HashMap<String, HashMap<PathBuf, String>>,
+    /// Environment variables to read from a running kernel when showing its
+    /// runtime info. Variables that aren't listed are never read.
+    ///
+    /// Default: ["PATH", "VIRTUAL_ENV", "CONDA_PREFIX", "PYTHONPATH"]
+    pub runtime_info_environment_variables: Vec<String>,
+    /// Largest image output to show, in megabytes.
+    ///
+    /// Default: 20
+    pub max_image_size_mb: u64,
+    /// Largest width or height of an image output to show, in pixels.
+    ///
+    /// Default: 16384
+    pub max_image_dimension: u32,
+    /// Whether to show the SVG version of an image output when the kernel
+    /// also sends a PNG or JPEG.
+    ///
+    /// Default: true
+    pub prefer_svg_images: bool,
+    /// Shut down a kernel after it has been idle for this many minutes.
+    /// Set to 0 to keep idle kernels running.
+    ///
+    /// Default: 0
+    pub idle_kernel_shutdown_minutes: u64,
+    /// How often, in seconds, to sample the memory and CPU used by local
+    /// kernels. 0 doesn't sample.
+    ///
+    /// Default: 0
+    pub resource_sample_interval_seconds: u64,
+    /// Warn about kernels using more than this many megabytes of memory. 0
+    /// never warns.
+    ///
+    /// Default: 4096
+    pub memory_warning_mb: u64,
+    /// MIME types to show outputs as, from most to least preferred.
+    ///
+    /// Default: ["application/vnd.dataresource+json", "text/html", "application/json", "image/svg+xml", "image/png", "image/jpeg", "text/markdown", "text/latex", "text/plain"]
+    pub output_mime_priority: Vec<String>,
+    /// Prefixes of the lines that start a code cell in a script, keyed by
+    /// language name.
+    ///
+    /// Default: {"Python": ["# %%", "#%%"], "R": ["# %%", "#'"], "Julia": ["# %%", "##"]}
+    pub cell_markers: HashMap<String, Vec<String>>,
+    /// The cell types that tags on marker lines give their cells, keyed by
+    /// language name and then by tag.
+    ///
+    /// Default: {}
+    pub cell_tags: HashMap<String, HashMap<String, ReplCellType>>,
+    /// Code run silently each time a kernel starts, keyed by the kernel's
+    /// language in lowercase.
+    ///
+    /// Default: {"python": "from IPython import get_ipython\nget_ipython().run_line_magic(\"matplotlib\", \"inline\")", "r": "options(jupyter.plot_mimetypes = c(\"text/plain\", \"image/png\"))", "julia": "ENV[\"GKSwstype\"] = \"nul\""}
+    pub display_setup_code: HashMap<String, String>,
+    /// The directory kernels run in for buffers that aren't saved to a file.
+    ///
+    /// Default: None, which uses the home directory
+    pub scratch_working_directory: Option<PathBuf>,
+    /// Largest size of a file that execution output is logged to, in
+    /// megabytes. Set to 0 to let log files grow without limit.
+    ///
+    /// Default: 100
+    pub output_log_max_size_mb: u64,
+    /// Whether interrupting an execution that stops on errors also cancels
+    /// the executions queued after it.
+    ///
+    /// Default: false
+    pub stop_on_interrupt: bool,
+    /// How many of the most recent executions keep all of their outputs in
+    /// memory. Large outputs of older executions are spilled to disk.
+    ///
+    /// Default: 20
+    pub outputs_kept_in_memory: usize,
+    /// Limits on the memory, CPU priority, and processes of kernels started
+    /// on this machine. Project settings can set their own.
+    ///
+    /// Default: unlimited
+    pub kernel_resource_limits: KernelResourceLimits,
 }
 
 impl Settings for ReplSettings {
     fn from_settings(content: &settings::SettingsContent) -> Self {
         let repl = content.repl.as_ref().unwrap();
+        // Project settings files can set a subset of the REPL settings.
+        let mut project = repl.project.clone();
+        project.merge_from_option(content.project.repl.as_ref());
 
         Self {
             max_lines: repl.max_lines.unwrap(),
@@ -39,6 +155,74 @@ impl Settings for ReplSettings {
             inline_output: repl.inline_output.unwrap_or(true),
             inline_output_max_length: repl.inline_output_max_length.unwrap_or(50),
             output_max_height_lines: repl.output_max_height_lines.unwrap_or(0),
+            expand_incomplete_selections: repl.expand_incomplete_selections.unwrap_or(true),
+            remote_reconnect_timeout_seconds: repl.remote_reconnect_timeout_seconds.unwrap_or(60),
+            idle_status_timeout_ms: repl.idle_status_timeout_ms.unwrap_or(2000),
+            remote_kernels_on_exit: repl.remote_kernels_on_exit.clone().unwrap_or_default(),
+            trusted_html_sources: repl.trusted_html_sources.clone().unwrap_or_default(),
+            remote_project_roots: repl
+                .remote_project_roots
+                .iter()
+                .flatten()
+                .map(|(url, roots)| {
+                    let roots = roots
+                        .iter()
+                        .map(|(local, remote)| (PathBuf::from(local), remote.clone()))
+                        .collect();
+                    (url.clone(), roots)
+                })
+                .collect(),
+            runtime_info_environment_variables: repl
+                .runtime_info_environment_variables
+                .clone()
+                .unwrap_or_default(),
+            max_image_size_mb: repl.max_image_size_mb.unwrap_or(20),
+            max_image_dimension: repl.max_image_dimension.unwrap_or(16384),
+            prefer_svg_images: repl.prefer_svg_images.unwrap_or(true),
+            idle_kernel_shutdown_minutes: repl.idle_kernel_shutdown_minutes.unwrap_or(0),
+            resource_sample_interval_seconds: repl.resource_sample_interval_seconds.unwrap_or(0),
+            memory_warning_mb: repl.memory_warning_mb.unwrap_or(4096),
+            output_mime_priority: repl.output_mime_priority.clone().unwrap_or_default(),
+            cell_markers: repl.cell_markers.clone().unwrap_or_default(),
+            cell_tags: repl.cell_tags.clone().unwrap_or_default(),
+            display_setup_code: repl.display_setup_code.clone().unwrap_or_default(),
+            scratch_working_directory: repl.scratch_working_directory.as_deref().map(|path| {
+                match path.strip_prefix("~/") {
+                    Some(relative_path) => util::paths::home_dir().join(relative_path),
+                    None => PathBuf::from(path),
+                }
+            }),
+            output_log_max_size_mb: repl.output_log_max_size_mb.unwrap_or(100),
+            stop_on_interrupt: repl.stop_on_interrupt.unwrap_or(false),
+            outputs_kept_in_memory: repl.outputs_kept_in_memory.unwrap_or(20),
+            kernel_resource_limits: KernelResourceLimits::from_settings(
+                project.kernel_resource_limits.as_ref(),
+            ),
         }
     }
 }
+
+impl ReplSettings {
+    /// The largest size of an output log file in bytes, if it's limited.
+    pub fn output_log_max_size(&self) -> Option<u64> {
+        (self.output_log_max_size_mb > 0).then(|| self.output_log_max_size_mb * 1024 * 1024)
+    }
+
+    /// How often to sample the resource usage of local kernels, if at all.
+    pub fn resource_sample_interval(&self) -> Option<Duration> {
+        (self.resource_sample_interval_seconds > 0)
+            .then(|| Duration::from_secs(self.resource_sample_interval_seconds))
+    }
+
+    /// The memory, in bytes, above which a kernel is warned about, if any.
+    pub fn memory_warning_threshold(&self) -> Option<u64> {
+        (self.memory_warning_mb > 0).then(|| self.memory_warning_mb * 1024 * 1024)
+    }
+
+    /// How long to wait for a kernel's idle status after its execute reply,
+    /// if it's limited.
+    pub fn idle_status_timeout(&self) -> Option<Duration> {
+        (self.idle_status_timeout_ms > 0)
+            .then(|| Duration::from_millis(self.idle_status_timeout_ms))
+    }
+}
@@ -1,25 +1,57 @@
 use std::future::Future;
+use std::path::Path;
+use std::pin::pin;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{Context as _, Result};
 use collections::{HashMap, HashSet};
 use command_palette_hooks::CommandPaletteFilter;
+use db::kvp::KeyValueStore;
+use futures::{FutureExt as _, StreamExt as _, channel::oneshot, stream::FuturesUnordered};
 use gpui::{
     App, Context, Entity, EntityId, Global, SharedString, Subscription, Task, TaskExt, prelude::*,
 };
 use jupyter_websocket_client::RemoteServer;
-use language::{Language, LanguageName};
+use language::{Buffer, Language, LanguageName};
 use project::{Fs, Project, ProjectPath, WorktreeId};
 use remote::RemoteConnectionOptions;
+use runtimelib::KernelInfoReply;
 use settings::{Settings, SettingsStore};
+use util::ResultExt as _;
 use util::rel_path::RelPath;
 
+use crate::kernel_info_cache::{CachedKernelInfo, KERNEL_INFO_CACHE_KEY};
+
 use crate::kernels::{
-    Kernel, PythonEnvKernelSpecification, list_remote_kernelspecs, local_kernel_specifications,
-    python_env_kernel_specifications, wsl_kernel_specifications,
+    PythonEnvKernelSpecification, RunningKernel, list_remote_kernelspecs,
+    local_kernel_specifications, python_env_kernel_specifications, select_kernel_by_rules,
+    wsl_kernel_specifications,
 };
 use crate::{JupyterSettings, KernelSpecification, Session};
 
+/// How long kernels get to acknowledge a shutdown request when their workspace
+/// closes before they're killed.
+pub(crate) const EXIT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long kernels get to acknowledge a shutdown request when Zed quits
+/// before they're killed. GPUI stops polling quit futures after
+/// `gpui::SHUTDOWN_TIMEOUT`, so this leaves the rest of it for the kills.
+pub(crate) const QUIT_SHUTDOWN_TIMEOUT: Duration =
+    Duration::from_millis(gpui::SHUTDOWN_TIMEOUT.as_millis() as u64 / 2);
+
+/// A kernel that has been asked to shut down because its workspace closed or
+/// Zed is quitting.
+pub(crate) struct ExitingKernel {
+    pub name: SharedString,
+    pub kernel: Box<dyn RunningKernel>,
+    /// Resolves once the kernel replies to the shutdown request.
+    pub acknowledged: oneshot::Receiver<()>,
+    /// Deletes the kernel from the Jupyter server it runs on, for kernels
+    /// Zed owns there.
+    pub deletion: Option<Task<Result<()>>>,
+}
+
 struct GlobalReplStore(Entity<ReplStore>);
 
 impl Global for GlobalReplStore {}
@@ -27,14 +59,24 @@ impl Global for GlobalReplStore {}
 pub struct ReplStore {
     fs: Arc<dyn Fs>,
     enabled: bool,
-    sessions: HashMap<EntityId, Entity<Session>>,
+    /// Every session, in the order they started, including headless ones
+    /// with no editor attached.
+    sessions: Vec<Entity<Session>>,
+    /// The session each editor is attached to, by the editor's entity id.
+    attached_sessions: HashMap<EntityId, Entity<Session>>,
     kernel_specifications: Vec<KernelSpecification>,
     kernelspecs_initialized: bool,
-    selected_kernel_for_worktree: HashMap<WorktreeId, KernelSpecification>,
-    kernel_specifications_for_worktree: HashMap<WorktreeId, Vec<KernelSpecification>>,
-    active_python_toolchain_for_worktree: HashMap<WorktreeId, SharedString>,
-    remote_worktrees: HashSet<WorktreeId>,
-    fetching_python_kernelspecs: HashSet<WorktreeId>,
+    // The maps below are keyed by worktree, with `None` standing for buffers
+    // outside of any worktree, such as untitled ones.
+    selected_kernel_for_worktree: HashMap<Option<WorktreeId>, KernelSpecification>,
+    kernel_specifications_for_worktree: HashMap<Option<WorktreeId>, Vec<KernelSpecification>>,
+    active_python_toolchain_for_worktree: HashMap<Option<WorktreeId>, SharedString>,
+    worktree_roots: HashMap<WorktreeId, Arc<Path>>,
+    remote_worktrees: HashSet<Option<WorktreeId>>,
+    fetching_python_kernelspecs: HashSet<Option<WorktreeId>>,
+    /// What kernels last reported about themselves, by
+    /// [`KernelSpecification::cache_key`].
+    kernel_info_cache: HashMap<String, CachedKernelInfo>,
     _subscriptions: Vec<Subscription>,
 }
 
@@ -50,6 +92,11 @@ impl ReplStore {
         cx.global::<GlobalReplStore>().0.clone()
     }
 
+    pub fn try_global(cx: &App) -> Option<Entity<Self>> {
+        cx.try_global::<GlobalReplStore>()
+            .map(|store| store.0.clone())
+    }
+
     pub fn new(fs: Arc<dyn Fs>, cx: &mut Context<Self>) -> Self {
         let subscriptions = vec![
             cx.observe_global::<SettingsStore>(move |this, cx| {
@@ -61,17 +108,21 @@ impl ReplStore {
         let this = Self {
             fs,
             enabled: JupyterSettings::enabled(cx),
-            sessions: HashMap::default(),
+            sessions: Vec::new(),
+            attached_sessions: HashMap::default(),
             kernel_specifications: Vec::new(),
             kernelspecs_initialized: false,
             _subscriptions: subscriptions,
             kernel_specifications_for_worktree: HashMap::default(),
             selected_kernel_for_worktree: HashMap::default(),
             active_python_toolchain_for_worktree: HashMap::default(),
+            worktree_roots: HashMap::default(),
             remote_worktrees: HashSet::default(),
             fetching_python_kernelspecs: HashSet::default(),
+            kernel_info_cache: HashMap::default(),
         };
         this.on_enabled_changed(cx);
+        this.load_kernel_info_cache(cx);
         this
     }
 
@@ -83,14 +134,16 @@ impl ReplStore {
         self.enabled
     }
 
-    pub fn has_python_kernelspecs(&self, worktree_id: WorktreeId) -> bool {
+    pub fn has_python_kernelspecs(&self, worktree_id: Option<WorktreeId>) -> bool {
         self.kernel_specifications_for_worktree
             .contains_key(&worktree_id)
     }
 
+    /// The kernels available to buffers in `worktree_id`, or to buffers
+    /// outside of any worktree when it's `None`.
     pub fn kernel_specifications_for_worktree(
         &self,
-        worktree_id: WorktreeId,
+        worktree_id: Option<WorktreeId>,
     ) -> impl Iterator<Item = &KernelSpecification> {
         let global_specs = if self.remote_worktrees.contains(&worktree_id) {
             None
@@ -110,7 +163,67 @@ impl ReplStore {
     }
 
     pub fn sessions(&self) -> impl Iterator<Item = &Entity<Session>> {
-        self.sessions.values()
+        self.sessions.iter()
+    }
+
+    /// Every session with the buffers of the editors attached to it.
+    /// Headless sessions have none.
+    pub fn sessions_with_buffers(&self, cx: &App) -> Vec<(Entity<Session>, Vec<Entity<Buffer>>)> {
+        self.sessions
+            .iter()
+            .map(|session| {
+                let buffers = session
+                    .read(cx)
+                    .attached_editors()
+                    .filter_map(|editor| editor.read(cx).buffer().read(cx).as_singleton())
+                    .collect();
+                (session.clone(), buffers)
+            })
+            .collect()
+    }
+
+    pub fn session_named(&self, name: &str, cx: &App) -> Option<&Entity<Session>> {
+        self.sessions
+            .iter()
+            .find(|session| session.read(cx).name().as_ref() == name)
+    }
+
+    /// The kernel's name with the lowest ordinal no other session uses, such
+    /// as "Python 3 (2)".
+    pub fn default_session_name(&self, spec: &KernelSpecification, cx: &App) -> SharedString {
+        let kernel_name = spec.name();
+        let mut ordinal = 1;
+        loop {
+            let name = SharedString::from(format!("{kernel_name} ({ordinal})"));
+            if self.session_named(&name, cx).is_none() {
+                return name;
+            }
+            ordinal += 1;
+        }
+    }
+
+    /// Renames `session`, keeping names unique so sessions can be told apart
+    /// and attached to by name.
+    pub fn rename_session(
+        &mut self,
+        session: &Entity<Session>,
+        name: &str,
+        cx: &mut Context<Self>,
+    ) -> Result<()> {
+        let name = name.trim();
+        anyhow::ensure!(!name.is_empty(), "Session names can't be empty");
+        if let Some(existing) = self.session_named(name, cx) {
+            anyhow::ensure!(
+                existing == session,
+                "There's already a session named {name}"
+            );
+            return Ok(());
+        }
+        session.update(cx, |session, cx| {
+            session.set_name(SharedString::from(name.to_string()), cx)
+        });
+        cx.notify();
+        Ok(())
     }
 
     fn set_enabled(&mut self, enabled: bool, cx: &mut Context<Self>) {
@@ -157,7 +270,7 @@ impl ReplStore {
 
     pub fn refresh_python_kernelspecs(
         &mut self,
-        worktree_id: WorktreeId,
+        worktree_id: Option<WorktreeId>,
         project: &Entity<Project>,
         cx: &mut Context<Self>,
     ) -> Task<Result<()>> {
@@ -174,15 +287,24 @@ impl ReplStore {
             .map_or(false, |opts| {
                 matches!(opts, RemoteConnectionOptions::Wsl(_))
             });
+        if let Some(worktree_id) = worktree_id
+            && let Some(worktree) = project.read(cx).worktree_for_id(worktree_id, cx)
+        {
+            self.worktree_roots
+                .insert(worktree_id, worktree.read(cx).abs_path());
+        }
         let kernel_specifications_task = python_env_kernel_specifications(project, worktree_id, cx);
-        let active_toolchain = project.read(cx).active_toolchain(
-            ProjectPath {
-                worktree_id,
-                path: RelPath::empty().into(),
-            },
-            LanguageName::new_static("Python"),
-            cx,
-        );
+        let active_toolchain = match worktree_id {
+            Some(worktree_id) => project.read(cx).active_toolchain(
+                ProjectPath {
+                    worktree_id,
+                    path: RelPath::empty().into(),
+                },
+                LanguageName::new_static("Python"),
+                cx,
+            ),
+            None => Task::ready(None),
+        };
 
         cx.spawn(async move |this, cx| {
             let kernel_specifications_res = kernel_specifications_task.await;
@@ -293,7 +415,7 @@ impl ReplStore {
 
     pub fn set_active_kernelspec(
         &mut self,
-        worktree_id: WorktreeId,
+        worktree_id: Option<WorktreeId>,
         kernelspec: KernelSpecification,
         _cx: &mut Context<Self>,
     ) {
@@ -301,17 +423,36 @@ impl ReplStore {
             .insert(worktree_id, kernelspec);
     }
 
-    pub fn active_python_toolchain_path(&self, worktree_id: WorktreeId) -> Option<&SharedString> {
+    /// Forgets the kernel picked for `worktree_id`, so the default kernel is
+    /// chosen automatically again.
+    pub fn clear_selected_kernel(
+        &mut self,
+        worktree_id: Option<WorktreeId>,
+        cx: &mut Context<Self>,
+    ) {
+        if self
+            .selected_kernel_for_worktree
+            .remove(&worktree_id)
+            .is_some()
+        {
+            cx.notify();
+        }
+    }
+
+    pub fn active_python_toolchain_path(
+        &self,
+        worktree_id: Option<WorktreeId>,
+    ) -> Option<&SharedString> {
         self.active_python_toolchain_for_worktree.get(&worktree_id)
     }
 
-    pub fn selected_kernel(&self, worktree_id: WorktreeId) -> Option<&KernelSpecification> {
+    pub fn selected_kernel(&self, worktree_id: Option<WorktreeId>) -> Option<&KernelSpecification> {
         self.selected_kernel_for_worktree.get(&worktree_id)
     }
 
     pub fn is_recommended_kernel(
         &self,
-        worktree_id: WorktreeId,
+        worktree_id: Option<WorktreeId>,
         spec: &KernelSpecification,
     ) -> bool {
         if let Some(active_path) = self.active_python_toolchain_path(worktree_id) {
@@ -321,9 +462,54 @@ impl ReplStore {
         }
     }
 
+    /// Returns the kernel that `kernel_selection_rules` pick for `language`
+    /// among the kernels whose language satisfies `matches_language`.
+    fn kernel_by_rules(
+        &self,
+        worktree_id: Option<WorktreeId>,
+        language: &str,
+        matches_language: impl Fn(&str) -> bool,
+        cx: &App,
+    ) -> Option<&KernelSpecification> {
+        let rules = JupyterSettings::get_global(cx)
+            .kernel_selection_rules
+            .iter()
+            .find(|(rule_language, _)| rule_language.eq_ignore_ascii_case(language))
+            .map(|(_, rules)| rules)?;
+        let specs = self
+            .kernel_specifications_for_worktree(worktree_id)
+            .filter(|spec| matches_language(spec.language().as_ref()))
+            .collect::<Vec<_>>();
+        select_kernel_by_rules(
+            &specs,
+            rules,
+            worktree_id
+                .and_then(|worktree_id| self.worktree_roots.get(&worktree_id))
+                .map(AsRef::as_ref),
+        )
+    }
+
+    /// Whether `spec` is the kernel that `kernel_selection_rules` pick for its
+    /// language, so the kernel picker can mark it.
+    pub fn is_default_kernel_by_rules(
+        &self,
+        worktree_id: Option<WorktreeId>,
+        spec: &KernelSpecification,
+        cx: &App,
+    ) -> bool {
+        let language = spec.language();
+        self.kernel_by_rules(
+            worktree_id,
+            &language,
+            |other| other.eq_ignore_ascii_case(&language),
+            cx,
+        )
+        .is_some_and(|default| default == spec)
+    }
+
     pub fn active_kernelspec(
         &self,
-        worktree_id: WorktreeId,
+        worktree_id: Option<WorktreeId>,
         language_at_cursor: Option<Arc<Language>>,
         cx: &App,
     ) -> Option<KernelSpecification> {
@@ -333,6 +519,15 @@ impl ReplStore {
 
         let language_at_cursor = language_at_cursor?;
 
+        if let Some(by_rules) = self.kernel_by_rules(
+            worktree_id,
+            language_at_cursor.code_fence_block_name().as_ref(),
+            |language| language_at_cursor.matches_kernel_language(language),
+            cx,
+        ) {
+            return Some(by_rules.clone());
+        }
+
         // Prefer the recommended (active toolchain) kernel if it has ipykernel
         if let Some(active_path) = self.active_python_toolchain_path(worktree_id) {
             let recommended = self
@@ -367,7 +562,7 @@ impl ReplStore {
 
     fn kernelspec_legacy_by_lang_only(
         &self,
-        worktree_id: WorktreeId,
+        worktree_id: Option<WorktreeId>,
         language_at_cursor: Arc<Language>,
         cx: &App,
     ) -> Option<KernelSpecification> {
@@ -400,33 +595,186 @@ impl ReplStore {
             .cloned()
     }
 
+    /// The session the editor `entity_id` is attached to.
     pub fn get_session(&self, entity_id: EntityId) -> Option<&Entity<Session>> {
-        self.sessions.get(&entity_id)
+        self.attached_sessions.get(&entity_id)
     }
 
+    /// Records that the editor `entity_id` is attached to `session`.
     pub fn insert_session(&mut self, entity_id: EntityId, session: Entity<Session>) {
-        self.sessions.insert(entity_id, session);
+        if !self.sessions.contains(&session) {
+            self.sessions.push(session.clone());
+        }
+        self.attached_sessions.insert(entity_id, session);
+    }
+
+    /// Records that the editor `entity_id` was detached from its session,
+    /// which keeps running.
+    pub fn detach_editor(&mut self, entity_id: EntityId) {
+        self.attached_sessions.remove(&entity_id);
     }
 
-    pub fn remove_session(&mut self, entity_id: EntityId) {
-        self.sessions.remove(&entity_id);
+    /// Forgets a session that shut down, along with its attached editors.
+    pub fn remove_session(&mut self, session: &Entity<Session>) {
+        self.sessions.retain(|existing| existing != session);
+        self.attached_sessions
+            .retain(|_, existing| existing != session);
     }
 
     fn shutdown_all_sessions(
         &mut self,
         cx: &mut Context<Self>,
     ) -> impl Future<Output = ()> + use<> {
-        for session in self.sessions.values() {
-            session.update(cx, |session, _cx| {
-                if let Kernel::RunningKernel(mut kernel) =
-                    std::mem::replace(&mut session.kernel, Kernel::Shutdown)
-                {
-                    kernel.kill();
+        self.attached_sessions.clear();
+        let kernels = self
+            .sessions
+            .drain(..)
+            .filter_map(|session| {
+                session.update(cx, |session, cx| session.take_kernel_for_exit(cx))
+            })
+            .collect();
+        let deadline = cx.background_executor().timer(QUIT_SHUTDOWN_TIMEOUT);
+        Self::shutdown_kernels(kernels, deadline)
+    }
+
+    /// Waits for `kernels` to acknowledge their shutdown requests until
+    /// `deadline` resolves, then kills every one of them, logging which
+    /// ones exited gracefully. Finally waits for owned kernels to be deleted
+    /// from their Jupyter servers.
+    pub(crate) async fn shutdown_kernels(
+        mut kernels: Vec<ExitingKernel>,
+        deadline: impl Future<Output = ()>,
+    ) {
+        let mut acknowledged = vec![false; kernels.len()];
+        {
+            let mut replies = kernels
+                .iter_mut()
+                .enumerate()
+                .map(|(ix, exiting)| {
+                    (&mut exiting.acknowledged).map(move |reply| (ix, reply.is_ok()))
+                })
+                .collect::<FuturesUnordered<_>>();
+            let mut deadline = pin!(deadline.fuse());
+            loop {
+                futures::select_biased! {
+                    reply = replies.next() => match reply {
+                        Some((ix, ok)) => acknowledged[ix] = ok,
+                        None => break,
+                    },
+                    _ = deadline => break,
                 }
-            });
+            }
         }
-        self.sessions.clear();
-        futures::future::ready(())
+
+        let mut deletions = Vec::new();
+        for (exiting, acknowledged) in kernels.into_iter().zip(acknowledged) {
+            let ExitingKernel {
+                name,
+                mut kernel,
+                deletion,
+                ..
+            } = exiting;
+            kernel.kill();
+            if acknowledged {
+                log::info!("kernel {name} shut down");
+            } else {
+                log::warn!("kernel {name} didn't shut down in time and was killed");
+            }
+            if let Some(deletion) = deletion {
+                deletions.push(async move {
+                    deletion
+                        .await
+                        .with_context(|| format!("deleting kernel {name} from its server"))
+                        .log_err();
+                });
+            }
+        }
+        futures::future::join_all(deletions).await;
+    }
+
+    pub fn cached_kernel_info(&self, spec: &KernelSpecification) -> Option<&CachedKernelInfo> {
+        self.kernel_info_cache.get(&spec.cache_key())
+    }
+
+    /// Loads the persisted kernel info, marking entries whose interpreter
+    /// changed since they were cached as stale.
+    fn load_kernel_info_cache(&self, cx: &mut Context<Self>) {
+        let kvp = KeyValueStore::global(cx);
+        let fs = self.fs.clone();
+        cx.spawn(async move |this, cx| {
+            let Some(serialized) = cx
+                .background_spawn(async move { kvp.read_kvp(KERNEL_INFO_CACHE_KEY) })
+                .await
+                .log_err()
+                .flatten()
+            else {
+                return;
+            };
+            let Some(mut cache) =
+                serde_json::from_str::<HashMap<String, CachedKernelInfo>>(&serialized).log_err()
+            else {
+                return;
+            };
+            for info in cache.values_mut() {
+                if let Some(path) = &info.source_path {
+                    let mtime = fs
+                        .metadata(path)
+                        .await
+                        .ok()
+                        .flatten()
+                        .map(|metadata| metadata.mtime);
+                    info.is_stale = mtime.is_none() || mtime != info.source_mtime;
+                }
+            }
+            this.update(cx, |this, cx| {
+                // Info recorded while loading is newer than the persisted info.
+                cache.extend(this.kernel_info_cache.drain());
+                this.kernel_info_cache = cache;
+                cx.notify();
+            })
+            .log_err();
+        })
+        .detach();
+    }
+
+    /// Caches what a kernel reported about itself and persists the cache.
+    pub fn record_kernel_info(
+        &mut self,
+        spec: &KernelSpecification,
+        reply: &KernelInfoReply,
+        cx: &mut Context<Self>,
+    ) {
+        let key = spec.cache_key();
+        let source_path = spec.local_source_path();
+        let reply = reply.clone();
+        let fs = self.fs.clone();
+        cx.spawn(async move |this, cx| {
+            let source_mtime = match &source_path {
+                Some(path) => fs
+                    .metadata(path)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|metadata| metadata.mtime),
+                None => None,
+            };
+            let info = CachedKernelInfo::new(&reply, source_path, source_mtime);
+            let serialized = this.update(cx, |this, cx| {
+                if this.kernel_info_cache.get(&key) == Some(&info) {
+                    return None;
+                }
+                this.kernel_info_cache.insert(key, info);
+                cx.notify();
+                serde_json::to_string(&this.kernel_info_cache).log_err()
+            })?;
+            if let Some(serialized) = serialized {
+                let kvp = cx.update(|cx| KeyValueStore::global(cx));
+                kvp.write_kvp(KERNEL_INFO_CACHE_KEY.to_string(), serialized)
+                    .await?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
     }
 
     #[cfg(test)]
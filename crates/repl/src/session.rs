@@ -2,17 +2,29 @@ use crate::components::KernelListItem;
 use crate::setup_editor_session_actions;
 use crate::{
     KernelStatus,
+    code_cells::{cell_markers, cell_source, code_cells},
+    execution_record::{ExecutionRecord, ExecutionRecords},
     kernels::{
-        Kernel, KernelSession, KernelSpecification, NativeRunningKernel, RemoteRunningKernel,
-        SshRunningKernel, WslRunningKernel,
+        InterruptMode, Kernel, KernelDebugSupport, KernelResourceLimits, KernelRuntimeInfo,
+        KernelSession, KernelSpecification, KernelStartupTimer, KernelStartupTiming,
+        NativeRunningKernel, ProtocolFeature, ProtocolVersion, RESOURCE_HISTORY_LEN,
+        RemoteRunningKernel, ResourceSample, ResourceSampler, RunningKernel, SshRunningKernel,
+        WslRunningKernel, format_memory, format_memory_limit,
     },
+    notebook_export::{
+        ScriptCell, kernelspec_metadata, language_info_metadata, notebook_from_records,
+    },
+    output_log::OutputLog,
     outputs::{
         ExecutionStatus, ExecutionView, ExecutionViewFinishedEmpty, ExecutionViewFinishedSmall,
-        InputReplyEvent,
+        HtmlTrust, InputReplyEvent, ShowSpilledOutputs, stream::MAX_STREAM_OUTPUT_BYTES,
     },
     repl_settings::ReplSettings,
+    repl_store::{EXIT_SHUTDOWN_TIMEOUT, ExitingKernel, ReplStore},
+    variables::{VariableSummary, introspection_snippet, parse_variables},
 };
 use anyhow::Context as _;
+use chrono::Utc;
 use collections::{HashMap, HashSet};
 use editor::SelectionEffects;
 use editor::{
@@ -28,36 +40,558 @@ use project::InlayId;
 /// Marker types
 enum ReplExecutedRange {}
 
-use futures::FutureExt as _;
+use futures::{FutureExt as _, channel::oneshot};
 use gpui::{
-    Context, Entity, EventEmitter, Render, Subscription, Task, WeakEntity, Window, div, prelude::*,
+    AnyWindowHandle, Context, Entity, EntityId, EventEmitter, PromptLevel, Render, Subscription,
+    Task, WeakEntity, Window, div, prelude::*,
 };
 use language::Point;
-use project::Fs;
+use project::{Fs, Project};
 use runtimelib::{
-    ExecuteRequest, ExecutionState, InputReply, InterruptRequest, JupyterMessage,
-    JupyterMessageContent, KernelInfoRequest, ReplyStatus, ShutdownRequest,
+    DebugRequest, ExecuteRequest, ExecutionState, InputReply, InterruptRequest,
+    IsCompleteReplyStatus, IsCompleteRequest, JupyterMessage, JupyterMessageContent,
+    KernelInfoRequest, Payload, ReplyStatus, ShutdownRequest, Status, Stdio,
+};
+use settings::{RemoteKernelExitBehavior, Settings as _, SettingsLocation};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, VecDeque},
+    env::temp_dir,
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
 };
-use settings::Settings as _;
-use std::{env::temp_dir, ops::Range, sync::Arc, time::Duration};
 use theme::ActiveTheme;
 use ui::{IconButtonShape, Tooltip, prelude::*};
 use util::ResultExt as _;
 
+/// How long to wait on an `is_complete_reply` before treating the code as
+/// `unknown` and running it as-is.
+const IS_COMPLETE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Prefixes the line the runtime probe prints, so that output from anything
+/// else running in the kernel is ignored.
+const RUNTIME_PROBE_MARKER: &str = "__zed_runtime_info__";
+
+/// Prefixes the line printed after a remote kernel changes into its mapped
+/// project root.
+const WORKING_DIRECTORY_MARKER: &str = "__zed_working_directory__";
+
+/// How long to wait after an execution completes before listing the kernel's
+/// variables, so that running several cells in a row only lists them once.
+const REFRESH_VARIABLES_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// How long an idle kernel is given to acknowledge its shutdown request before
+/// it's killed.
+const IDLE_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+/// How long a restarting kernel is given to acknowledge its shutdown request
+/// before it's killed and launched again.
+const RESTART_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+/// How long to hold on to an execution's messages that arrive before its block
+/// is registered.
+const UNROUTED_MESSAGE_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// How many handled messages are kept for showing in their blocks while the
+/// session has no window. Past this the oldest are dropped; their outputs are
+/// missing from the blocks but still in the execution records.
+const MAX_UNPRESENTED_MESSAGES: usize = 1000;
+
+/// How long a kernel is given to answer an `interrupt_request` before the
+/// interrupt is reported as failed.
+const INTERRUPT_REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How soon after the user interrupts an execution an error it raises is
+/// taken to be caused by the interrupt. Errors that name an interrupt, like
+/// Python's `KeyboardInterrupt`, count no matter how long the kernel took.
+const INTERRUPT_ERROR_WINDOW: Duration = Duration::from_secs(5);
+
+/// The names kernels give the error an interrupted execution raises.
+const INTERRUPT_ERROR_NAMES: &[&str] = &[
+    // Python
+    "KeyboardInterrupt",
+    // Julia
+    "InterruptException",
+];
+
+/// How long a kernel is given to answer a `debug_request`. Some debugger
+/// requests, like `dumpCell` for a large cell, take a while.
+const DEBUG_REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The protocol flags of an `execute_request`, chosen per execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionOptions {
+    /// Runs the code without broadcasting outputs or incrementing the
+    /// kernel's execution count.
+    pub silent: bool,
+    /// Records the code in the kernel's history. Ignored for silent executions.
+    pub store_history: bool,
+    /// Aborts the executions queued after this one when it raises an error.
+    pub stop_on_error: bool,
+}
+
+impl ExecutionOptions {
+    /// The options for executions Zed sends for its own purposes, which should
+    /// leave no trace in the kernel's history or execution count.
+    pub fn silent() -> Self {
+        Self {
+            silent: true,
+            store_history: false,
+            stop_on_error: false,
+        }
+    }
+
+    fn request(self, code: String) -> ExecuteRequest {
+        ExecuteRequest {
+            code,
+            silent: self.silent,
+            store_history: self.store_history,
+            stop_on_error: self.stop_on_error,
+            // Silent executions can't prompt, as nothing would show the prompt.
+            allow_stdin: !self.silent,
+            ..ExecuteRequest::default()
+        }
+    }
+}
+
+impl Default for ExecutionOptions {
+    fn default() -> Self {
+        let request = ExecuteRequest::default();
+        Self {
+            silent: request.silent,
+            store_history: request.store_history,
+            stop_on_error: request.stop_on_error,
+        }
+    }
+}
+
+/// An execution Zed sends for its own purposes, such as probing the kernel's
+/// runtime. It runs with `silent` and without storing history so that it
+/// doesn't show up in the user's `In[]`/`Out[]`, and its stdout is collected
+/// instead of displayed.
+struct SilentExecution {
+    msg_id: String,
+    stdout: String,
+}
+
+impl SilentExecution {
+    fn send(code: String, session: &mut Session, cx: &mut Context<Session>) -> Self {
+        let message: JupyterMessage = ExecutionOptions::silent().request(code).into();
+        let msg_id = message.header.msg_id.clone();
+        session.send(message, cx).log_err();
+        Self {
+            msg_id,
+            stdout: String::new(),
+        }
+    }
+}
+
+enum SilentExecutionUpdate {
+    /// The message isn't part of the silent execution.
+    Unrelated,
+    /// The message was consumed and the execution hasn't finished yet.
+    Pending,
+    /// The kernel replied. Carries everything the execution printed.
+    Finished(String),
+}
+
+/// Collects `message` into `execution` if it belongs to it, clearing
+/// `execution` once the kernel replies.
+///
+/// Errors and rich outputs are swallowed so that a failing snippet never
+/// shows up in the user's outputs.
+fn update_silent_execution(
+    execution: &mut Option<SilentExecution>,
+    message: &JupyterMessage,
+) -> SilentExecutionUpdate {
+    let Some(pending) = execution.as_mut() else {
+        return SilentExecutionUpdate::Unrelated;
+    };
+    if message.parent_header.as_ref().map(|header| &header.msg_id) != Some(&pending.msg_id) {
+        return SilentExecutionUpdate::Unrelated;
+    }
+
+    match &message.content {
+        JupyterMessageContent::StreamContent(stream) => {
+            // The output is only parsed for markers, so a runaway snippet
+            // doesn't get to grow it without bound.
+            if stream.name == Stdio::Stdout
+                && pending.stdout.len() + stream.text.len() <= MAX_STREAM_OUTPUT_BYTES
+            {
+                pending.stdout.push_str(&stream.text);
+            }
+            SilentExecutionUpdate::Pending
+        }
+        JupyterMessageContent::ExecuteReply(_) => {
+            let stdout = std::mem::take(&mut pending.stdout);
+            *execution = None;
+            SilentExecutionUpdate::Finished(stdout)
+        }
+        JupyterMessageContent::ErrorOutput(_)
+        | JupyterMessageContent::ExecuteResult(_)
+        | JupyterMessageContent::DisplayData(_) => SilentExecutionUpdate::Pending,
+        _ => SilentExecutionUpdate::Unrelated,
+    }
+}
+
+/// An execution held by the session until the kernel is done with the one
+/// before it.
+struct PendingExecution {
+    message: JupyterMessage,
+    code_preview: String,
+    submitted_at: Instant,
+    stop_on_error: bool,
+    /// When the user last interrupted the execution after it was sent.
+    interrupted_at: Option<Instant>,
+    /// Whether the error the execution raised was caused by that interrupt,
+    /// once it raised one.
+    interrupt_caused_error: Option<bool>,
+}
+
+impl PendingExecution {
+    fn msg_id(&self) -> &str {
+        &self.message.header.msg_id
+    }
+}
+
+/// An entry of a session's execution queue, as listed by
+/// [`Session::queued_executions`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedExecution {
+    pub msg_id: String,
+    /// The first line of the code.
+    pub code_preview: String,
+    pub submitted_at: Instant,
+    /// Whether the execution was sent to the kernel, after which it can no
+    /// longer be cancelled or reordered, only interrupted.
+    pub sent: bool,
+}
+
+/// Cells submitted together to run one after the other, such as by
+/// [`crate::RunAllCells`].
+struct CellRun {
+    /// Each cell's execution message id and code range, in document order.
+    cells: Vec<(String, Range<Anchor>)>,
+}
+
+/// How far a run of cells has got, as reported by
+/// [`Session::cell_run_progress`].
+#[derive(Debug, Clone)]
+pub struct CellRunProgress {
+    /// How many of the run's cells are done, including any that were
+    /// cancelled.
+    pub finished: usize,
+    pub total: usize,
+    /// The code range of the cell the kernel is running, if it has started on
+    /// one.
+    pub running: Option<Range<Anchor>>,
+}
+
+/// Where a kernel on a Jupyter server is running relative to the project,
+/// when `repl.remote_project_roots` maps the project to a path on the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteWorkingDirectory {
+    /// The kernel was asked to change into the path and hasn't answered yet.
+    Pending(String),
+    /// The kernel is running in the path.
+    Changed(String),
+    /// The kernel couldn't change into the path, so relative paths resolve
+    /// against wherever the server started it.
+    Failed(String),
+}
+
+/// What the runtime probe reported, kept until the kernel restarts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ProbedRuntime {
+    interpreter: Option<String>,
+    environment: BTreeMap<String, Option<String>>,
+}
+
 pub struct Session {
     fs: Arc<dyn Fs>,
-    editor: WeakEntity<Editor>,
+    /// What the session is called in the UI. It's kept when the kernel
+    /// restarts.
+    name: SharedString,
+    /// The editors whose code runs in this kernel, in the order they were
+    /// attached. Without any, the kernel keeps running headless.
+    editors: Vec<AttachedEditor>,
     pub kernel: Kernel,
     pub kernel_specification: KernelSpecification,
 
     blocks: HashMap<String, EditorBlock>,
-    result_inlays: HashMap<String, (InlayId, Range<Anchor>, usize)>,
+    /// Messages for executions that have no block yet, with when they arrived.
+    /// They're replayed into the block once it's registered.
+    unrouted_messages: Vec<(Instant, JupyterMessage)>,
+    /// Messages that were handled but not shown in their blocks yet, because
+    /// they were handled without a window. Bounded by
+    /// [`MAX_UNPRESENTED_MESSAGES`].
+    unpresented_messages: VecDeque<JupyterMessage>,
+    /// The window messages were last shown in, for showing those handled
+    /// later in the background.
+    presentation_window: Option<AnyWindowHandle>,
+    result_inlays: HashMap<String, ResultInlay>,
     next_inlay_id: usize,
+    /// Message ids of executions that were in flight when the kernel restarted
+    /// or shut down. Replies that still reference them are dropped, until the
+    /// `execute_reply` and idle status that end the execution have both
+    /// arrived; each id maps to whether one of them has.
+    aborted_executions: HashMap<String, bool>,
+    /// Executions that haven't been sent yet, in the order they'll be sent.
+    /// Only one is sent at a time, so that those after it can still be
+    /// cancelled or reordered.
+    execution_queue: VecDeque<PendingExecution>,
+    /// The execution sent to the kernel that it hasn't replied to or gone
+    /// idle for yet.
+    sent_execution: Option<PendingExecution>,
+    /// The last cells submitted together by [`Session::run_cells`].
+    cell_run: Option<CellRun>,
+    /// Finishes the last execution the kernel replied to if it never reports
+    /// going idle for it, along with its request. Restarted by every message
+    /// about the execution.
+    missing_idle_task: Option<(JupyterMessage, Task<()>)>,
+    /// What the most recent executions ran and produced, kept after their
+    /// blocks are closed.
+    execution_records: ExecutionRecords,
+    /// The file executions' stream output is logged to, when enabled.
+    output_log: Option<OutputLog>,
+    pending_is_complete: HashMap<String, oneshot::Sender<IsCompleteReplyStatus>>,
+    /// Executions started by [`Session::execute_code`], waiting to be handed
+    /// their record once they complete.
+    execution_waiters: HashMap<String, oneshot::Sender<ExecutionRecord>>,
+    runtime_probe: Option<SilentExecution>,
+    probed_runtime: Option<ProbedRuntime>,
+    variables: Vec<VariableSummary>,
+    variables_query: Option<SilentExecution>,
+    remote_working_directory: Option<RemoteWorkingDirectory>,
+    working_directory_change: Option<SilentExecution>,
+    /// The language's `repl.display_setup_code`, sent once the kernel starts.
+    display_setup: Option<SilentExecution>,
+    /// Whether an execution completed while the variables were being listed,
+    /// so they need listing again once that finishes.
+    variables_stale: bool,
+    refresh_variables_task: Option<Task<()>>,
+    /// Fulfilled when the kernel acknowledges the shutdown request sent by
+    /// [`Session::take_kernel_for_exit`] or a restart.
+    shutdown_acknowledged_tx: Option<oneshot::Sender<()>>,
+    /// When code last ran or finished running, used to shut down idle kernels.
+    last_activity: Instant,
+    /// Comms the kernel has open, such as widgets. A kernel with open comms is
+    /// never shut down for being idle.
+    open_comms: HashSet<String>,
+    /// Whether the user asked to keep this kernel running even when idle.
+    keep_alive: bool,
+    idle_shutdown_task: Option<Task<()>>,
+    /// How long the kernel took to start, once its launch has finished.
+    startup_timing: Option<KernelStartupTiming>,
+    /// When the kernel was first asked for its info, until it answers.
+    kernel_info_requested_at: Option<Instant>,
+    /// Recent samples of the memory and CPU used by the kernel's processes,
+    /// oldest first, while `repl.resource_sample_interval_seconds` is set.
+    resource_samples: VecDeque<ResourceSample>,
+    resource_sampling_task: Option<Task<()>>,
+    /// The `interrupt_request` waiting on its reply, and the task that reports
+    /// it as failed if the reply doesn't come in time.
+    pending_interrupt: Option<(String, Task<()>)>,
+    /// Why the last interrupt failed.
+    interrupt_error: Option<SharedString>,
+    /// `debug_request`s waiting on their `debug_reply`, by message id.
+    pending_debug_requests: HashMap<String, oneshot::Sender<serde_json::Value>>,
+    /// The body of the kernel's reply to `debugInfo`, for kernels that support
+    /// the debug protocol.
+    debug_info: Option<serde_json::Value>,
+    /// The protocol version from the kernel's kernel info, which decides what
+    /// requests it's sent.
+    protocol_version: Option<ProtocolVersion>,
+    /// The directory the kernel was first launched in. Restarts launch it
+    /// there again, even once no editor is attached to tell where that is.
+    launch_directory: Option<PathBuf>,
+    /// The memory limit, in bytes, the user raised the configured one to
+    /// after the kernel ran into it. Kept for later restarts.
+    memory_limit_override: Option<u64>,
+    /// The memory limit, in bytes, that the kernel died of running into.
+    exceeded_memory_limit: Option<u64>,
+    /// Set from the start of a restart until the new kernel has answered
+    /// `kernel_info` and run its setup code.
+    pending_restart: Option<PendingRestart>,
+    kernel_launcher: Rc<dyn KernelLauncher>,
+
+    _subscriptions: Vec<Subscription>,
+}
+
+/// A restart that's underway. Callers of [`Session::restart_kernel`] that
+/// come before it completes wait on it rather than restarting again.
+#[derive(Default)]
+struct PendingRestart {
+    waiters: Vec<oneshot::Sender<Result<(), SharedString>>>,
+}
+
+/// What a session's kernel is launched with.
+pub(crate) struct KernelLaunch {
+    pub kernel_specification: KernelSpecification,
+    pub working_directory: PathBuf,
+    pub fs: Arc<dyn Fs>,
+    /// The project of the session's editor, which kernels on SSH hosts are
+    /// started through.
+    pub project: Option<Entity<Project>>,
+    pub startup_timer: KernelStartupTimer,
+    pub resource_limits: KernelResourceLimits,
+}
+
+/// Starts the kernels of a session, when it's created and on every restart.
+pub(crate) trait KernelLauncher {
+    fn launch(
+        &self,
+        launch: KernelLaunch,
+        window: &mut Window,
+        cx: &mut Context<Session>,
+    ) -> Task<anyhow::Result<Box<dyn RunningKernel>>>;
+}
+
+/// Launches the kernel the session's specification describes.
+struct SpecifiedKernelLauncher;
+
+impl KernelLauncher for SpecifiedKernelLauncher {
+    fn launch(
+        &self,
+        launch: KernelLaunch,
+        window: &mut Window,
+        cx: &mut Context<Session>,
+    ) -> Task<anyhow::Result<Box<dyn RunningKernel>>> {
+        let KernelLaunch {
+            kernel_specification,
+            working_directory,
+            fs,
+            project,
+            startup_timer,
+            resource_limits,
+        } = launch;
+        let entity_id = cx.entity_id();
+        let session_view = cx.entity();
+        match kernel_specification {
+            KernelSpecification::Jupyter(kernel_specification) => NativeRunningKernel::new(
+                kernel_specification,
+                entity_id,
+                working_directory,
+                fs,
+                session_view,
+                startup_timer,
+                resource_limits,
+                window,
+                cx,
+            ),
+            KernelSpecification::PythonEnv(env_specification) => NativeRunningKernel::new(
+                env_specification.as_local_spec(),
+                entity_id,
+                working_directory,
+                fs,
+                session_view,
+                startup_timer,
+                resource_limits,
+                window,
+                cx,
+            ),
+            KernelSpecification::JupyterServer(remote_kernel_specification) => {
+                RemoteRunningKernel::new(
+                    remote_kernel_specification,
+                    working_directory,
+                    session_view,
+                    startup_timer,
+                    window,
+                    cx,
+                )
+            }
+            KernelSpecification::SshRemote(spec) => {
+                if let Some(project) = project {
+                    SshRunningKernel::new(
+                        spec,
+                        working_directory,
+                        project,
+                        session_view,
+                        startup_timer,
+                        window,
+                        cx,
+                    )
+                } else {
+                    Task::ready(Err(anyhow::anyhow!("No project associated with editor")))
+                }
+            }
+            KernelSpecification::WslRemote(spec) => WslRunningKernel::new(
+                spec,
+                entity_id,
+                working_directory,
+                fs,
+                session_view,
+                startup_timer,
+                window,
+                cx,
+            ),
+        }
+    }
+}
+
+/// Why [`Session::debug_request`] has no reply to return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugRequestError {
+    /// The kernel isn't running.
+    NotRunning,
+    /// The kernel hasn't sent its kernel info yet, so it isn't known whether
+    /// it supports the debug protocol.
+    NotReady,
+    /// The kernel doesn't implement the Jupyter debug protocol.
+    Unsupported,
+    /// The kernel says it supports debugging, but speaks a protocol version
+    /// older than debug requests need.
+    UnsupportedProtocol(ProtocolVersion),
+    /// The request couldn't be handed to the kernel's connection.
+    NotSent,
+    /// The kernel didn't reply in time, or restarted before replying.
+    NoReply,
+}
+
+impl std::fmt::Display for DebugRequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            Self::NotRunning => "the kernel isn't running",
+            Self::NotReady => "the kernel hasn't reported whether it supports debugging yet",
+            Self::Unsupported => "the kernel doesn't support the Jupyter debug protocol",
+            Self::UnsupportedProtocol(version) => {
+                return write!(
+                    f,
+                    "the kernel speaks protocol version {version}, but debug requests need {}",
+                    ProtocolFeature::DebugRequests.minimum_version()
+                );
+            }
+            Self::NotSent => "the debug request couldn't be sent to the kernel",
+            Self::NoReply => "the kernel didn't answer the debug request",
+        };
+        f.write_str(message)
+    }
+}
 
+impl std::error::Error for DebugRequestError {}
+
+/// An editor attached to a session, with the subscriptions that keep its
+/// outputs in sync with its buffer.
+struct AttachedEditor {
+    editor: WeakEntity<Editor>,
     _subscriptions: Vec<Subscription>,
 }
 
+/// A short result shown after the code that produced it, in place of its
+/// output block.
+struct ResultInlay {
+    editor: WeakEntity<Editor>,
+    inlay_id: InlayId,
+    code_range: Range<Anchor>,
+    /// The length of the code when it ran. Editing it removes the result.
+    original_len: usize,
+}
+
 struct EditorBlock {
+    editor: WeakEntity<Editor>,
     code_range: Range<Anchor>,
     invalidation_anchor: Anchor,
     block_id: CustomBlockId,
@@ -72,13 +606,15 @@ impl EditorBlock {
         editor: WeakEntity<Editor>,
         code_range: Range<Anchor>,
         status: ExecutionStatus,
+        html_trust: HtmlTrust,
         on_close: CloseBlockFn,
         cx: &mut Context<Session>,
     ) -> anyhow::Result<Self> {
         let editor = editor.upgrade().context("editor is not open")?;
         let workspace = editor.read(cx).workspace().context("workspace dropped")?;
 
-        let execution_view = cx.new(|cx| ExecutionView::new(status, workspace.downgrade(), cx));
+        let execution_view =
+            cx.new(|cx| ExecutionView::new(status, workspace.downgrade(), html_trust, cx));
 
         let (block_id, invalidation_anchor) = editor.update(cx, |editor, cx| {
             let buffer = editor.buffer().clone();
@@ -118,6 +654,7 @@ impl EditorBlock {
         });
 
         anyhow::Ok(Self {
+            editor: editor.downgrade(),
             code_range,
             invalidation_anchor,
             block_id,
@@ -126,7 +663,7 @@ impl EditorBlock {
     }
 
     fn handle_message(
-        &mut self,
+        &self,
         message: &JupyterMessage,
         window: &mut Window,
         cx: &mut Context<Session>,
@@ -234,40 +771,156 @@ impl Session {
         window: &mut Window,
         cx: &mut Context<Self>,
     ) -> Self {
-        let subscription = match editor.upgrade() {
-            Some(editor) => {
-                let buffer = editor.read(cx).buffer().clone();
-                cx.subscribe(&buffer, Self::on_buffer_event)
-            }
-            None => Subscription::new(|| {}),
+        let name = match ReplStore::try_global(cx) {
+            Some(store) => store
+                .read(cx)
+                .default_session_name(&kernel_specification, cx),
+            None => kernel_specification.name(),
         };
 
-        let editor_handle = editor.clone();
-
-        editor
-            .update(cx, |editor, _cx| {
-                setup_editor_session_actions(editor, editor_handle);
-            })
-            .ok();
-
         let mut session = Self {
             fs,
-            editor,
+            name,
+            editors: Vec::new(),
             kernel: Kernel::StartingKernel(Task::ready(()).shared()),
             blocks: HashMap::default(),
+            unrouted_messages: Vec::new(),
+            unpresented_messages: VecDeque::new(),
+            presentation_window: None,
             result_inlays: HashMap::default(),
             next_inlay_id: 0,
+            aborted_executions: HashMap::default(),
+            execution_queue: VecDeque::new(),
+            sent_execution: None,
+            cell_run: None,
+            missing_idle_task: None,
+            execution_records: ExecutionRecords::default(),
+            output_log: None,
+            pending_is_complete: HashMap::default(),
+            execution_waiters: HashMap::default(),
+            runtime_probe: None,
+            probed_runtime: None,
+            variables: Vec::new(),
+            variables_query: None,
+            remote_working_directory: None,
+            working_directory_change: None,
+            display_setup: None,
+            variables_stale: false,
+            refresh_variables_task: None,
+            shutdown_acknowledged_tx: None,
+            last_activity: cx.background_executor().now(),
+            open_comms: HashSet::default(),
+            keep_alive: false,
+            idle_shutdown_task: None,
+            startup_timing: None,
+            kernel_info_requested_at: None,
+            resource_samples: VecDeque::new(),
+            resource_sampling_task: None,
+            pending_interrupt: None,
+            interrupt_error: None,
+            pending_debug_requests: HashMap::default(),
+            debug_info: None,
+            protocol_version: None,
+            launch_directory: None,
+            memory_limit_override: None,
+            exceeded_memory_limit: None,
+            pending_restart: None,
+            kernel_launcher: Rc::new(SpecifiedKernelLauncher),
             kernel_specification,
-            _subscriptions: vec![subscription],
+            _subscriptions: Vec::new(),
         };
 
+        session.attach_editor(editor, cx);
         session.start_kernel(window, cx);
         session
     }
 
+    pub fn name(&self) -> &SharedString {
+        &self.name
+    }
+
+    /// Renames the session. Names are kept unique by
+    /// [`ReplStore::rename_session`].
+    pub(crate) fn set_name(&mut self, name: SharedString, cx: &mut Context<Self>) {
+        self.name = name;
+        cx.notify();
+    }
+
+    /// The editor code runs in when it isn't run from a particular editor,
+    /// which is the one attached first.
+    fn editor(&self) -> WeakEntity<Editor> {
+        self.editors
+            .first()
+            .map_or_else(WeakEntity::new_invalid, |attached| attached.editor.clone())
+    }
+
+    /// The open editors whose code runs in this session's kernel.
+    pub fn attached_editors(&self) -> impl Iterator<Item = Entity<Editor>> + '_ {
+        self.editors
+            .iter()
+            .filter_map(|attached| attached.editor.upgrade())
+    }
+
+    pub fn is_attached(&self, editor_id: EntityId) -> bool {
+        self.editors
+            .iter()
+            .any(|attached| attached.editor.entity_id() == editor_id)
+    }
+
+    /// Whether the kernel is running without any editor attached.
+    pub fn is_headless(&self) -> bool {
+        self.editors.is_empty()
+    }
+
+    /// Runs `editor`'s code in this session's kernel too. Outputs are shown in
+    /// the editor that ran the code.
+    pub fn attach_editor(&mut self, editor: WeakEntity<Editor>, cx: &mut Context<Self>) {
+        let Some(editor_entity) = editor.upgrade() else {
+            return;
+        };
+        let editor_id = editor.entity_id();
+        if self.is_attached(editor_id) {
+            return;
+        }
+
+        let buffer = editor_entity.read(cx).buffer().clone();
+        let subscriptions = vec![
+            cx.subscribe(&buffer, Self::on_buffer_event),
+            cx.observe_release(&editor_entity, move |session, _, cx| {
+                session.on_editor_released(editor_id, cx)
+            }),
+        ];
+        editor_entity.update(cx, |editor_entity, _| {
+            setup_editor_session_actions(editor_entity, editor.clone());
+        });
+        self.editors.push(AttachedEditor {
+            editor,
+            _subscriptions: subscriptions,
+        });
+        cx.notify();
+    }
+
+    /// Stops running `editor_id`'s code in this session and removes the
+    /// outputs shown in it. The kernel keeps running, headless if this was
+    /// the last editor.
+    pub fn detach_editor(&mut self, editor_id: EntityId, cx: &mut Context<Self>) {
+        let Some(ix) = self
+            .editors
+            .iter()
+            .position(|attached| attached.editor.entity_id() == editor_id)
+        else {
+            return;
+        };
+        self.clear_outputs_in(editor_id, cx);
+        self.editors.remove(ix);
+        cx.notify();
+    }
+
     fn start_kernel(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let startup_timer = KernelStartupTimer::default();
+        self.startup_timing = None;
+        self.kernel_info_requested_at = None;
         let kernel_language = self.kernel_specification.language();
-        let entity_id = self.editor.entity_id();
 
         // For WSL Remote kernels, use project root instead of potentially temporary working directory
         // which causes .venv/bin/python checks to fail
@@ -276,10 +929,12 @@ impl Session {
             crate::KernelSpecification::WslRemote(_) | crate::KernelSpecification::SshRemote(_)
         );
 
-        let working_directory = if is_remote_execution {
+        let working_directory = if let Some(directory) = self.launch_directory.clone() {
+            directory
+        } else if is_remote_execution {
             // For WSL Remote kernels, use project root instead of potentially temporary working directory
             // which causes .venv/bin/python checks to fail
-            self.editor
+            self.editor()
                 .upgrade()
                 .and_then(|editor| editor.read(cx).project().cloned())
                 .and_then(|project| {
@@ -291,11 +946,28 @@ impl Session {
                 })
                 .unwrap_or_else(temp_dir)
         } else {
-            self.editor
+            // Buffers that aren't saved to a file have no directory of their
+            // own to run in.
+            self.editor()
                 .upgrade()
                 .and_then(|editor| editor.read(cx).working_directory(cx))
-                .unwrap_or_else(temp_dir)
+                .unwrap_or_else(|| {
+                    ReplSettings::get_global(cx)
+                        .scratch_working_directory
+                        .clone()
+                        .unwrap_or_else(|| util::paths::home_dir().clone())
+                })
+        };
+        self.launch_directory = Some(working_directory.clone());
+
+        let remote_working_directory = match &self.kernel_specification {
+            KernelSpecification::JupyterServer(spec) => ReplSettings::get_global(cx)
+                .remote_project_roots
+                .get(&spec.url)
+                .and_then(|roots| map_to_remote_root(roots, &working_directory)),
+            _ => None,
         };
+        let resource_limits = self.kernel_resource_limits(&working_directory, cx);
 
         telemetry::event!(
             "Kernel Status Changed",
@@ -304,82 +976,59 @@ impl Session {
             repl_session_id = cx.entity_id().to_string(),
         );
 
-        let session_view = cx.entity();
-
-        let kernel = match self.kernel_specification.clone() {
-            KernelSpecification::Jupyter(kernel_specification) => NativeRunningKernel::new(
-                kernel_specification,
-                entity_id,
-                working_directory,
-                self.fs.clone(),
-                session_view,
-                window,
-                cx,
-            ),
-            KernelSpecification::PythonEnv(env_specification) => NativeRunningKernel::new(
-                env_specification.as_local_spec(),
-                entity_id,
-                working_directory,
-                self.fs.clone(),
-                session_view,
-                window,
-                cx,
-            ),
-            KernelSpecification::JupyterServer(remote_kernel_specification) => {
-                RemoteRunningKernel::new(
-                    remote_kernel_specification,
-                    working_directory,
-                    session_view,
-                    window,
-                    cx,
-                )
-            }
-            KernelSpecification::SshRemote(spec) => {
-                let project = self
-                    .editor
-                    .upgrade()
-                    .and_then(|editor| editor.read(cx).project().cloned());
-                if let Some(project) = project {
-                    SshRunningKernel::new(
-                        spec,
-                        working_directory,
-                        project,
-                        session_view,
-                        window,
-                        cx,
-                    )
-                } else {
-                    Task::ready(Err(anyhow::anyhow!("No project associated with editor")))
-                }
-            }
-            KernelSpecification::WslRemote(spec) => WslRunningKernel::new(
-                spec,
-                entity_id,
-                working_directory,
-                self.fs.clone(),
-                session_view,
-                window,
-                cx,
-            ),
+        let launch = KernelLaunch {
+            kernel_specification: self.kernel_specification.clone(),
+            working_directory,
+            fs: self.fs.clone(),
+            project: self
+                .editor()
+                .upgrade()
+                .and_then(|editor| editor.read(cx).project().cloned()),
+            startup_timer: startup_timer.clone(),
+            resource_limits,
         };
+        let kernel = self.kernel_launcher.launch(launch, window, cx);
 
         let pending_kernel = cx
             .spawn(async move |this, cx| {
-                let kernel: anyhow::Result<Box<dyn crate::kernels::RunningKernel>> = kernel.await;
+                let kernel: anyhow::Result<Box<dyn RunningKernel>> = kernel.await;
 
                 match kernel {
-                    Ok(kernel) => {
+                    Ok(mut kernel) => {
                         this.update(cx, |session, cx| {
+                            if !matches!(session.kernel, Kernel::StartingKernel(_)) {
+                                log::info!(
+                                    "killing kernel {} that finished starting after its session shut down",
+                                    session.kernel_specification.name()
+                                );
+                                kernel.kill();
+                                return;
+                            }
+                            let pid = kernel.runtime_info().pid;
                             session.kernel(Kernel::RunningKernel(kernel), cx);
+                            if let Some(pid) = pid {
+                                session.start_resource_sampling(pid, cx);
+                            }
+                            session.startup_timing = Some(startup_timer.timing());
+                            session.kernel_info_requested_at = Some(cx.background_executor().now());
                             let request =
                                 JupyterMessageContent::KernelInfoRequest(KernelInfoRequest {});
                             session.send(request.into(), cx).log_err();
+                            if let Some(path) = remote_working_directory {
+                                session.change_remote_working_directory(path, cx);
+                            }
+                            session.send_display_setup(cx);
+                            session.send_next_queued_execution(cx);
                         })
                         .ok();
                     }
                     Err(err) => {
                         this.update(cx, |session, cx| {
-                            session.kernel_errored(err.to_string(), cx);
+                            session.kernel_failed(
+                                err.to_string(),
+                                Some(startup_timer.timing()),
+                                cx,
+                            );
                         })
                         .ok();
                     }
@@ -392,636 +1041,5977 @@ impl Session {
     }
 
     pub fn kernel_errored(&mut self, error_message: String, cx: &mut Context<Self>) {
-        self.kernel(Kernel::ErroredLaunch(error_message.clone()), cx);
+        self.kernel_failed(error_message, None, cx);
+    }
 
-        self.blocks.values().for_each(|block| {
-            block.execution_view.update(cx, |execution_view, cx| {
-                match execution_view.status {
-                    ExecutionStatus::Finished => {
-                        // Do nothing when the output was good
-                    }
-                    _ => {
-                        // All other cases, set the status to errored
-                        execution_view.status =
-                            ExecutionStatus::KernelErrored(error_message.clone())
-                    }
-                }
-                cx.notify();
-            });
-        });
+    /// The resource limits for a kernel running in `working_directory`,
+    /// from the settings of the worktree it's in, if any.
+    fn kernel_resource_limits(&self, working_directory: &Path, cx: &App) -> KernelResourceLimits {
+        let worktree = self
+            .editor()
+            .upgrade()
+            .and_then(|editor| editor.read(cx).project().cloned())
+            .and_then(|project| project.read(cx).find_worktree(working_directory, cx));
+        let mut limits = match worktree {
+            Some((worktree, path)) => {
+                let location = SettingsLocation {
+                    worktree_id: worktree.read(cx).id(),
+                    path: &path,
+                };
+                ReplSettings::get(Some(location), cx).kernel_resource_limits
+            }
+            None => ReplSettings::get_global(cx).kernel_resource_limits,
+        };
+        if let Some(memory) = self.memory_limit_override {
+            limits.memory = Some(memory);
+        }
+        limits
     }
 
-    fn on_buffer_event(
+    /// The resource limits the running kernel was started with, if it has
+    /// any.
+    pub fn resource_limits(&self) -> Option<KernelResourceLimits> {
+        let Kernel::RunningKernel(kernel) = &self.kernel else {
+            return None;
+        };
+        kernel
+            .runtime_info()
+            .resource_limits
+            .filter(|limits| !limits.is_unlimited())
+    }
+
+    /// The memory limit, in bytes, that the kernel died of running into, if
+    /// that's why it stopped.
+    pub fn exceeded_memory_limit(&self) -> Option<u64> {
+        self.exceeded_memory_limit
+    }
+
+    /// Restarts the kernel that died of running into its memory limit with
+    /// twice the limit.
+    pub fn restart_with_higher_memory_limit(
         &mut self,
-        buffer: Entity<MultiBuffer>,
-        event: &multi_buffer::Event,
+        window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        if let multi_buffer::Event::Edited { .. } = event {
-            let snapshot = buffer.read(cx).snapshot(cx);
+        let Some(limit) = self.exceeded_memory_limit else {
+            return;
+        };
+        self.memory_limit_override = Some(limit.saturating_mul(2));
+        self.restart_kernel(window, cx).detach_and_log_err(cx);
+    }
 
-            let mut blocks_to_remove: HashSet<CustomBlockId> = HashSet::default();
-            let mut gutter_ranges_to_remove: Vec<Range<Anchor>> = Vec::new();
-            let mut keys_to_remove: Vec<String> = Vec::new();
+    /// How long each phase of the kernel's launch took, once it has started.
+    /// The first `kernel_info` round trip is filled in when the kernel answers.
+    pub fn startup_timing(&self) -> Option<&KernelStartupTiming> {
+        self.startup_timing.as_ref()
+    }
 
-            self.blocks.retain(|id, block| {
-                if block.invalidation_anchor.is_valid(&snapshot) {
-                    true
-                } else {
-                    blocks_to_remove.insert(block.block_id);
-                    gutter_ranges_to_remove.push(block.code_range.clone());
-                    keys_to_remove.push(id.clone());
-                    false
-                }
-            });
+    /// The latest sample of the memory and CPU used by the kernel and the
+    /// processes it started. Only kernels running on this machine are
+    /// sampled, and only when `repl.resource_sample_interval_seconds` is set.
+    pub fn resource_usage(&self) -> Option<&ResourceSample> {
+        self.resource_samples.back()
+    }
 
-            let mut inlays_to_remove: Vec<InlayId> = Vec::new();
+    /// Up to [`RESOURCE_HISTORY_LEN`] recent samples of the kernel's resource
+    /// usage, oldest first.
+    pub fn resource_history(&self) -> &VecDeque<ResourceSample> {
+        &self.resource_samples
+    }
 
-            self.result_inlays
-                .retain(|id, (inlay_id, code_range, original_len)| {
-                    let start_offset = code_range.start.to_offset(&snapshot);
-                    let end_offset = code_range.end.to_offset(&snapshot);
-                    let current_len = end_offset.saturating_sub(start_offset);
-
-                    if current_len != *original_len {
-                        inlays_to_remove.push(*inlay_id);
-                        gutter_ranges_to_remove.push(code_range.clone());
-                        keys_to_remove.push(id.clone());
-                        false
-                    } else {
-                        true
-                    }
-                });
+    /// Samples the resource usage of the kernel's process tree until the
+    /// kernel stops or its process exits. Replacing the kernel drops the task.
+    fn start_resource_sampling(&mut self, pid: u32, cx: &mut Context<Self>) {
+        let Some(interval) = ReplSettings::get_global(cx).resource_sample_interval() else {
+            return;
+        };
 
-            if !blocks_to_remove.is_empty()
-                || !inlays_to_remove.is_empty()
-                || !gutter_ranges_to_remove.is_empty()
-            {
-                self.editor
-                    .update(cx, |editor, cx| {
-                        if !blocks_to_remove.is_empty() {
-                            editor.remove_blocks(blocks_to_remove, None, cx);
-                        }
-                        if !inlays_to_remove.is_empty() {
-                            editor.splice_inlays(&inlays_to_remove, vec![], cx);
-                        }
-                        if !gutter_ranges_to_remove.is_empty() {
-                            editor.remove_gutter_highlights::<ReplExecutedRange>(
-                                gutter_ranges_to_remove,
-                                cx,
-                            );
-                        }
+        let mut sampler = ResourceSampler::new(pid);
+        self.resource_sampling_task = Some(cx.spawn(async move |this, cx| {
+            // CPU usage is measured between samples, so the first one only
+            // sets a baseline.
+            let mut is_baseline = true;
+            loop {
+                let (returned_sampler, sample) = cx
+                    .background_spawn(async move {
+                        let sample = sampler.sample();
+                        (sampler, sample)
                     })
-                    .ok();
-                cx.notify();
+                    .await;
+                sampler = returned_sampler;
+                // The kernel's process has exited, which its status reports.
+                let Some(sample) = sample else {
+                    return;
+                };
+                if !is_baseline {
+                    let pushed =
+                        this.update(cx, |session, cx| session.push_resource_sample(sample, cx));
+                    if pushed.is_err() {
+                        return;
+                    }
+                }
+                is_baseline = false;
+                cx.background_executor().timer(interval).await;
             }
-        }
+        }));
     }
 
-    fn send(&mut self, message: JupyterMessage, _cx: &mut Context<Self>) -> anyhow::Result<()> {
-        if let Kernel::RunningKernel(kernel) = &mut self.kernel {
-            kernel.request_tx().try_send(message).ok();
+    fn push_resource_sample(&mut self, sample: ResourceSample, cx: &mut Context<Self>) {
+        if self.resource_samples.len() == RESOURCE_HISTORY_LEN {
+            self.resource_samples.pop_front();
         }
+        self.resource_samples.push_back(sample);
+        cx.notify();
+    }
+
+    fn kernel_failed(
+        &mut self,
+        error_message: String,
+        startup_timing: Option<KernelStartupTiming>,
+        cx: &mut Context<Self>,
+    ) {
+        self.kernel(
+            Kernel::ErroredLaunch(error_message.clone(), startup_timing),
+            cx,
+        );
+        self.execution_queue.clear();
+        self.sent_execution = None;
+        self.missing_idle_task = None;
+        self.execution_records.abort_pending();
+        self.settle_execution_waiters();
 
-        anyhow::Ok(())
+        self.blocks.values().for_each(|block| {
+            block.execution_view.update(cx, |execution_view, cx| {
+                match execution_view.status {
+                    ExecutionStatus::Finished | ExecutionStatus::Interrupted => {
+                        // Do nothing when the output was good
+                    }
+                    _ => {
+                        // All other cases, set the status to errored
+                        execution_view.status =
+                            ExecutionStatus::KernelErrored(error_message.clone())
+                    }
+                }
+                cx.notify();
+            });
+        });
     }
 
-    fn send_stdin_reply(
+    /// Starts showing the outputs of the execution with `msg_id` in `block`,
+    /// including any that arrived before it was registered.
+    fn register_block(
         &mut self,
-        value: String,
-        parent_message: &JupyterMessage,
-        _cx: &mut Context<Self>,
+        msg_id: String,
+        block: EditorBlock,
+        window: &mut Window,
+        cx: &mut Context<Self>,
     ) {
-        if let Kernel::RunningKernel(kernel) = &mut self.kernel {
-            let reply = InputReply {
-                value,
-                status: ReplyStatus::Ok,
-                error: None,
-            };
-            let message = reply.as_child_of(parent_message);
-            kernel.stdin_tx().try_send(message).log_err();
+        self.blocks.insert(msg_id.clone(), block);
+        let interrupted = self.sent_execution.as_ref().is_some_and(|sent_execution| {
+            sent_execution.msg_id() == msg_id && sent_execution.interrupt_caused_error == Some(true)
+        });
+        if interrupted {
+            self.mark_interrupted(&msg_id, cx);
+        }
+
+        let now = cx.background_executor().now();
+        let mut early_messages = Vec::new();
+        self.unrouted_messages.retain(|(received_at, message)| {
+            if now.duration_since(*received_at) >= UNROUTED_MESSAGE_GRACE_PERIOD {
+                return false;
+            }
+            let is_for_block = message
+                .parent_header
+                .as_ref()
+                .is_some_and(|header| header.msg_id == msg_id);
+            if is_for_block {
+                early_messages.push(message.clone());
+            }
+            !is_for_block
+        });
+        for message in early_messages {
+            self.handle_block_message(&message, cx);
+            self.queue_for_presentation(message);
         }
+        self.present_messages(window, cx);
     }
 
-    fn replace_block_with_inlay(&mut self, message_id: &str, text: &str, cx: &mut Context<Self>) {
-        let Some(block) = self.blocks.remove(message_id) else {
-            return;
-        };
+    /// The block of the execution `message` is a child of. Outputs are only
+    /// ever attributed by their parent, never by which execution is running.
+    fn block_for_message(&self, message: &JupyterMessage) -> Option<&EditorBlock> {
+        let parent_message_id = &message.parent_header.as_ref()?.msg_id;
+        self.blocks.get(parent_message_id)
+    }
 
-        let Some(editor) = self.editor.upgrade() else {
+    /// Acts on a reply to an execution shown in a block. Showing the message
+    /// itself is left to [`KernelSession::present_messages`].
+    fn handle_block_message(&mut self, message: &JupyterMessage, cx: &mut Context<Self>) {
+        let Some(block) = self.block_for_message(message) else {
             return;
         };
 
-        let code_range = block.code_range.clone();
-
-        editor.update(cx, |editor, cx| {
-            let mut block_ids = HashSet::default();
-            block_ids.insert(block.block_id);
-            editor.remove_blocks(block_ids, None, cx);
-
-            let buffer = editor.buffer().read(cx).snapshot(cx);
-            let start_offset = code_range.start.to_offset(&buffer);
-            let end_offset = code_range.end.to_offset(&buffer);
-            let original_len = end_offset.saturating_sub(start_offset);
-
-            let end_point = code_range.end.to_point(&buffer);
-            let inlay_position = buffer.anchor_after(end_point);
+        if let JupyterMessageContent::ExecuteReply(reply) = &message.content {
+            // Page payloads are kept by the execution view. Payloads Zed
+            // doesn't act on, like `ask_exit`, are ignored.
+            let editor_id = block.editor.entity_id();
+            let code_range = block.code_range.clone();
+            for payload in &reply.payload {
+                if let Payload::SetNextInput { text, replace } = payload {
+                    cx.emit(SessionEvent::SetNextInput {
+                        editor_id,
+                        code_range: code_range.clone(),
+                        text: text.clone(),
+                        replace: *replace,
+                    });
+                }
+            }
+            if matches!(reply.status, ReplyStatus::Ok) {
+                self.schedule_variables_refresh(cx);
+            }
+            self.record_activity(cx);
+        }
+    }
 
-            let inlay_id = self.next_inlay_id;
-            self.next_inlay_id += 1;
+    /// The record of the execution sent as `msg_id`, if it's recent enough to
+    /// still be kept.
+    pub fn execution_record(&self, msg_id: &str) -> Option<&ExecutionRecord> {
+        self.execution_records.get(msg_id)
+    }
 
-            let inlay = Inlay::repl_result(inlay_id, inlay_position, format!("    {}", text));
+    /// The record of the most recent execution the kernel counted as
+    /// `execution_count`.
+    pub fn execution_record_for_count(&self, execution_count: usize) -> Option<&ExecutionRecord> {
+        self.execution_records.for_execution_count(execution_count)
+    }
 
-            editor.splice_inlays(&[], vec![inlay], cx);
-            self.result_inlays.insert(
-                message_id.to_string(),
-                (
-                    InlayId::ReplResult(inlay_id),
-                    code_range.clone(),
-                    original_len,
-                ),
-            );
+    /// Records of the most recent executions, oldest first.
+    pub fn execution_records(&self) -> impl DoubleEndedIterator<Item = &ExecutionRecord> {
+        self.execution_records.iter()
+    }
 
-            editor.insert_gutter_highlight::<ReplExecutedRange>(
-                code_range,
-                |cx| cx.theme().status().success,
-                cx,
-            );
-        });
+    /// The file executions' stream output is being logged to.
+    pub fn output_log_path(&self) -> Option<&Path> {
+        self.output_log.as_ref().map(|output_log| output_log.path())
+    }
 
+    /// Starts logging the stream output of executions run from now on to
+    /// `path`, or stops logging when it's `None`. Executions submitted before
+    /// logging to `path` started aren't logged.
+    pub fn set_output_log(&mut self, path: Option<PathBuf>, cx: &mut Context<Self>) {
+        let max_size = ReplSettings::get_global(cx).output_log_max_size();
+        self.output_log = path.map(|path| OutputLog::new(path, max_size, self.fs.clone(), cx));
         cx.notify();
     }
 
-    pub fn clear_outputs(&mut self, cx: &mut Context<Self>) {
-        let blocks_to_remove: HashSet<CustomBlockId> =
-            self.blocks.values().map(|block| block.block_id).collect();
+    /// Logs a message's stream output, and the lines around it, when its
+    /// execution started while the current log file was in use. The footer
+    /// waits for the kernel to go idle, since stream output can still arrive
+    /// after the `execute_reply`.
+    fn log_output(&mut self, message: &JupyterMessage) {
+        let Some(output_log) = &mut self.output_log else {
+            return;
+        };
+        let Some(parent_header) = &message.parent_header else {
+            return;
+        };
+        let Some(record) = self.execution_records.get(&parent_header.msg_id) else {
+            return;
+        };
+        if record.output_log_path.as_deref() != Some(output_log.path()) {
+            return;
+        }
+        match &message.content {
+            JupyterMessageContent::ExecuteInput(_) => output_log.write_header(record),
+            JupyterMessageContent::StreamContent(stream) => {
+                output_log.write_stream(&record.msg_id, &stream.text)
+            }
+            JupyterMessageContent::Status(status)
+                if matches!(status.execution_state, ExecutionState::Idle) =>
+            {
+                output_log.write_footer(record)
+            }
+            _ => {}
+        }
+    }
 
-        let inlays_to_remove: Vec<InlayId> =
-            self.result_inlays.values().map(|(id, _, _)| *id).collect();
+    /// The session's executions as an nbformat 4 notebook, one code cell per
+    /// execution in the order they ran, with their outputs. The markdown and
+    /// raw cells of the editor's script are exported among them. Outputs
+    /// spilled to disk are read back in the background.
+    pub fn export_notebook(&self, cx: &App) -> Task<anyhow::Result<String>> {
+        let language_info = match self.kernel.kernel_info() {
+            Some(kernel_info) => language_info_metadata(kernel_info),
+            None => ReplStore::try_global(cx)
+                .and_then(|store| {
+                    store
+                        .read(cx)
+                        .cached_kernel_info(&self.kernel_specification)
+                        .cloned()
+                })
+                .map(|info| {
+                    serde_json::json!({
+                        "name": info.language,
+                        "version": info.language_version,
+                    })
+                }),
+        };
+        let records = self.execution_records().cloned().collect::<Vec<_>>();
+        let script_cells = self.script_cells(cx);
+        let kernelspec = kernelspec_metadata(&self.kernel_specification);
+        let fs = self.fs.clone();
+        cx.background_spawn(async move {
+            let notebook = notebook_from_records(
+                &records,
+                &script_cells,
+                kernelspec,
+                language_info,
+                fs.as_ref(),
+            )
+            .await?;
+            let mut serialized =
+                serde_json::to_string_pretty(&notebook).context("Failed to serialize notebook")?;
+            serialized.push('\n');
+            Ok(serialized)
+        })
+    }
 
-        self.editor
-            .update(cx, |editor, cx| {
-                editor.remove_blocks(blocks_to_remove, None, cx);
-                editor.splice_inlays(&inlays_to_remove, vec![], cx);
-                editor.clear_gutter_highlights::<ReplExecutedRange>(cx);
+    /// The cells of the script in the session's first editor, or none when it
+    /// has no cell markers.
+    fn script_cells(&self, cx: &App) -> Vec<ScriptCell> {
+        let Some(editor) = self.editor().upgrade() else {
+            return Vec::new();
+        };
+        let Some(buffer) = editor.read(cx).buffer().read(cx).as_singleton() else {
+            return Vec::new();
+        };
+        let snapshot = buffer.read(cx).snapshot();
+        let Some(language) = snapshot.language() else {
+            return Vec::new();
+        };
+        let markers = cell_markers(language, cx);
+        let comment_prefixes = language
+            .default_scope()
+            .line_comment_prefixes()
+            .iter()
+            .map(|prefix| prefix.to_string())
+            .collect::<Vec<_>>();
+        code_cells(&snapshot, &markers)
+            .iter()
+            .map(|cell| ScriptCell {
+                cell_type: cell.cell_type,
+                source: cell_source(&snapshot, cell, &markers, &comment_prefixes),
             })
-            .ok();
+            .collect()
+    }
 
-        self.blocks.clear();
-        self.result_inlays.clear();
+    /// Number of executions still waiting on a reply from the kernel.
+    pub fn pending_execution_count(&self, cx: &App) -> usize {
+        self.blocks
+            .values()
+            .filter(|block| block.execution_view.read(cx).status.is_in_flight())
+            .count()
     }
 
-    pub fn clear_output_at_position(&mut self, position: Anchor, cx: &mut Context<Self>) {
-        let Some(editor) = self.editor.upgrade() else {
-            return;
-        };
+    /// Finalize every in-flight execution as aborted. Their message ids will
+    /// never get a reply from the next kernel, so anything still waiting on
+    /// them (spinners, pending counts) has to be settled here.
+    fn abort_in_flight_executions(&mut self, cx: &mut Context<Self>) {
+        // Executions started without a window have no block to find them by.
+        let mut aborted = self
+            .sent_execution
+            .iter()
+            .chain(&self.execution_queue)
+            .map(|execution| execution.msg_id().to_string())
+            .filter(|msg_id| !self.blocks.contains_key(msg_id))
+            .collect::<Vec<_>>();
+        self.execution_queue.clear();
+        self.sent_execution = None;
+        self.missing_idle_task = None;
 
-        let (block_id, code_range, msg_id) = {
-            let snapshot = editor.read(cx).buffer().read(cx).read(cx);
-            let pos_range = position..position;
+        for (msg_id, block) in &self.blocks {
+            block.execution_view.update(cx, |execution_view, cx| {
+                if execution_view.status.is_in_flight() {
+                    execution_view.status = ExecutionStatus::Aborted;
+                    aborted.push(msg_id.clone());
+                    cx.notify();
+                }
+            });
+        }
 
-            let block_to_remove = self
-                .blocks
-                .iter()
-                .find(|(_, block)| block.code_range.includes(&pos_range, &snapshot));
+        for msg_id in aborted {
+            self.execution_records.abort(&msg_id);
+            self.aborted_executions.insert(msg_id.clone(), false);
+            cx.emit(SessionEvent::ExecutionAborted(msg_id));
+        }
+        self.settle_execution_waiters();
+    }
 
-            let Some((msg_id, block)) = block_to_remove else {
-                return;
-            };
+    /// The executions that were submitted and haven't finished, starting with
+    /// the one the kernel is running.
+    pub fn queued_executions(&self) -> Vec<QueuedExecution> {
+        let sent = self
+            .sent_execution
+            .iter()
+            .map(|execution| (execution, true));
+        let queued = self
+            .execution_queue
+            .iter()
+            .map(|execution| (execution, false));
+        sent.chain(queued)
+            .map(|(execution, sent)| QueuedExecution {
+                msg_id: execution.msg_id().to_string(),
+                code_preview: execution.code_preview.clone(),
+                submitted_at: execution.submitted_at,
+                sent,
+            })
+            .collect()
+    }
 
-            (block.block_id, block.code_range.clone(), msg_id.clone())
+    /// Removes an execution that hasn't been sent to the kernel yet from the
+    /// queue, marking it as aborted. Returns whether it was found.
+    pub fn cancel_queued_execution(&mut self, msg_id: &str, cx: &mut Context<Self>) -> bool {
+        let Some(index) = self
+            .execution_queue
+            .iter()
+            .position(|execution| execution.msg_id() == msg_id)
+        else {
+            return false;
         };
-
-        let inlay_to_remove = self.result_inlays.get(&msg_id).map(|(id, _, _)| *id);
-
-        self.blocks.remove(&msg_id);
-        if inlay_to_remove.is_some() {
-            self.result_inlays.remove(&msg_id);
+        if let Some(execution) = self.execution_queue.remove(index) {
+            self.abort_queued_execution(execution, cx);
         }
+        cx.notify();
+        true
+    }
 
-        self.editor
-            .update(cx, |editor, cx| {
-                let mut block_ids = HashSet::default();
-                block_ids.insert(block_id);
-                editor.remove_blocks(block_ids, None, cx);
-
-                if let Some(inlay_id) = inlay_to_remove {
-                    editor.splice_inlays(&[inlay_id], vec![], cx);
-                }
-
-                editor.remove_gutter_highlights::<ReplExecutedRange>(vec![code_range], cx);
-            })
-            .ok();
-
+    /// Moves an execution that hasn't been sent to the kernel yet to `index`
+    /// among the unsent ones. Returns whether it was found.
+    pub fn move_queued_execution(
+        &mut self,
+        msg_id: &str,
+        index: usize,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let Some(current_index) = self
+            .execution_queue
+            .iter()
+            .position(|execution| execution.msg_id() == msg_id)
+        else {
+            return false;
+        };
+        if let Some(execution) = self.execution_queue.remove(current_index) {
+            let index = index.min(self.execution_queue.len());
+            self.execution_queue.insert(index, execution);
+        }
         cx.notify();
+        true
     }
 
-    pub fn execute(
+    /// Runs `cells` from `editor`, each a cell's code and range, one after the
+    /// other in order. A cell that raises an error aborts the cells after it.
+    /// The code is taken as given, so editing the buffer during the run
+    /// doesn't change what runs. With `move_down`, the cursor moves to
+    /// `next_cell` once the cells are submitted.
+    pub fn run_cells(
         &mut self,
-        code: String,
-        anchor_range: Range<Anchor>,
+        editor: WeakEntity<Editor>,
+        cells: Vec<(String, Range<Anchor>)>,
         next_cell: Option<Anchor>,
         move_down: bool,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        let Some(editor) = self.editor.upgrade() else {
-            return;
+        let options = ExecutionOptions {
+            stop_on_error: true,
+            ..ExecutionOptions::default()
         };
-
-        if code.is_empty() {
-            return;
+        let last_index = cells.len().saturating_sub(1);
+        let mut run = Vec::with_capacity(cells.len());
+        for (index, (code, anchor_range)) in cells.into_iter().enumerate() {
+            let is_last = index == last_index;
+            if let Some(msg_id) = self.execute_in(
+                editor.clone(),
+                code,
+                anchor_range.clone(),
+                next_cell.filter(|_| is_last),
+                move_down && is_last,
+                options,
+                window,
+                cx,
+            ) {
+                run.push((msg_id, anchor_range));
+            }
         }
+        self.cell_run = Some(CellRun { cells: run });
+        cx.notify();
+    }
 
-        let execute_request = ExecuteRequest {
-            code,
-            allow_stdin: true,
-            ..ExecuteRequest::default()
+    /// How far the last [`Session::run_cells`] has got, while any of its
+    /// cells are still waiting to run or running.
+    pub fn cell_run_progress(&self) -> Option<CellRunProgress> {
+        let run = self.cell_run.as_ref()?;
+        let is_unfinished = |msg_id: &str| {
+            self.sent_execution
+                .iter()
+                .chain(&self.execution_queue)
+                .any(|execution| execution.msg_id() == msg_id)
         };
+        let unfinished = run
+            .cells
+            .iter()
+            .filter(|(msg_id, _)| is_unfinished(msg_id))
+            .count();
+        if unfinished == 0 {
+            return None;
+        }
+        let running = self.sent_execution.as_ref().and_then(|execution| {
+            run.cells
+                .iter()
+                .find(|(msg_id, _)| msg_id == execution.msg_id())
+                .map(|(_, range)| range.clone())
+        });
+        Some(CellRunProgress {
+            finished: run.cells.len() - unfinished,
+            total: run.cells.len(),
+            running,
+        })
+    }
 
-        let message: JupyterMessage = execute_request.into();
+    /// Stops the last [`Session::run_cells`], aborting its cells that haven't
+    /// been sent to the kernel. With `interrupt`, the cell the kernel is
+    /// running is interrupted too; otherwise it's left to finish.
+    pub fn cancel_cell_run(&mut self, interrupt: bool, cx: &mut Context<Self>) {
+        let Some(run) = self.cell_run.take() else {
+            return;
+        };
+        for (msg_id, _) in &run.cells {
+            self.cancel_queued_execution(msg_id, cx);
+        }
+        let is_running_cell = self.sent_execution.as_ref().is_some_and(|execution| {
+            run.cells
+                .iter()
+                .any(|(msg_id, _)| msg_id == execution.msg_id())
+        });
+        if interrupt && is_running_cell {
+            self.interrupt(cx);
+        }
+        cx.notify();
+    }
 
-        let mut blocks_to_remove: HashSet<CustomBlockId> = HashSet::default();
-        let mut inlays_to_remove: Vec<InlayId> = Vec::new();
-        let mut gutter_ranges_to_remove: Vec<Range<Anchor>> = Vec::new();
+    /// Aborts every execution that hasn't been sent to the kernel yet.
+    fn cancel_queued_executions(&mut self, cx: &mut Context<Self>) {
+        for execution in std::mem::take(&mut self.execution_queue) {
+            self.abort_queued_execution(execution, cx);
+        }
+        cx.notify();
+    }
 
-        let buffer = editor.read(cx).buffer().read(cx).snapshot(cx);
+    fn abort_queued_execution(&mut self, execution: PendingExecution, cx: &mut Context<Self>) {
+        let msg_id = execution.msg_id().to_string();
+        if let Some(block) = self.blocks.get(&msg_id) {
+            block.execution_view.update(cx, |execution_view, cx| {
+                execution_view.status = ExecutionStatus::Aborted;
+                cx.notify();
+            });
+        }
+        self.execution_records.abort(&msg_id);
+        self.aborted_executions.insert(msg_id.clone(), false);
+        cx.emit(SessionEvent::ExecutionAborted(msg_id));
+        self.settle_execution_waiters();
+    }
 
-        self.blocks.retain(|_key, block| {
-            if anchor_range.overlaps(&block.code_range, &buffer) {
-                blocks_to_remove.insert(block.block_id);
-                false
+    /// Sends the execution at the front of the queue, unless the kernel isn't
+    /// running or is still busy with the last one sent. Executions whose
+    /// caller stopped waiting on [`Session::execute_code`] are aborted
+    /// instead.
+    fn send_next_queued_execution(&mut self, cx: &mut Context<Self>) {
+        if self.sent_execution.is_some() || !matches!(self.kernel, Kernel::RunningKernel(_)) {
+            return;
+        }
+        while let Some(execution) = self.execution_queue.pop_front() {
+            let is_abandoned = self
+                .execution_waiters
+                .get(execution.msg_id())
+                .is_some_and(|waiter| waiter.is_canceled());
+            if is_abandoned {
+                self.abort_queued_execution(execution, cx);
+                continue;
+            }
+            let mut message = execution.message.clone();
+            if let JupyterMessageContent::ExecuteRequest(request) = &mut message.content
+                && request.stop_on_error
+                && !self.supports(ProtocolFeature::StopOnError)
+            {
+                // The kernel's own queue is left alone; Zed still cancels
+                // the executions it queued after this one if it fails.
+                request.stop_on_error = false;
+            }
+            self.send(message, cx).log_err();
+            self.sent_execution = Some(execution);
+            cx.notify();
+            return;
+        }
+    }
+
+    /// Runs `code` on the session's kernel without an editor or a window,
+    /// resolving with the execution's record once the kernel has replied and
+    /// gone idle, or once the execution is aborted. A record with an `Error`
+    /// or `Interrupted` status holds the error among its outputs. The
+    /// execution waits its turn in the session's queue like any other, and
+    /// its record keeps the same capped outputs. A kernel that has been shut
+    /// down for being idle isn't started again.
+    ///
+    /// Dropping the returned task before the execution was sent to the kernel
+    /// cancels it.
+    pub fn execute_code(
+        &mut self,
+        code: String,
+        options: ExecutionOptions,
+        cx: &mut Context<Self>,
+    ) -> Task<anyhow::Result<ExecutionRecord>> {
+        if code.is_empty() {
+            return Task::ready(Err(anyhow::anyhow!("No code to execute")));
+        }
+        if !matches!(
+            self.kernel,
+            Kernel::RunningKernel(_) | Kernel::StartingKernel(_)
+        ) {
+            return Task::ready(Err(anyhow::anyhow!("The kernel isn't running")));
+        }
+        self.record_activity(cx);
+
+        let code_preview = code.lines().next().unwrap_or_default().to_string();
+        let message: JupyterMessage = options.request(code.clone()).into();
+        let msg_id = message.header.msg_id.clone();
+        let output_log_path = self
+            .output_log
+            .as_ref()
+            .map(|output_log| output_log.path().to_path_buf());
+        self.execution_records.start(&message, code).output_log_path = output_log_path;
+        self.spill_older_outputs(cx);
+
+        let (record_tx, record_rx) = oneshot::channel();
+        self.execution_waiters.insert(msg_id.clone(), record_tx);
+        self.execution_queue.push_back(PendingExecution {
+            message,
+            code_preview,
+            submitted_at: cx.background_executor().now(),
+            stop_on_error: options.stop_on_error,
+            interrupted_at: None,
+            interrupt_caused_error: None,
+        });
+        self.send_next_queued_execution(cx);
+        cx.notify();
+
+        cx.background_spawn(async move {
+            record_rx.await.map_err(|_| {
+                anyhow::anyhow!("The session ended before execution {msg_id} completed")
+            })
+        })
+    }
+
+    /// Hands each waiting [`Session::execute_code`] caller its record once
+    /// the execution completes. Callers whose record was pushed out by newer
+    /// executions are dropped, which fails their task.
+    fn settle_execution_waiters(&mut self) {
+        let settled = self
+            .execution_waiters
+            .keys()
+            .filter(|msg_id| {
+                self.execution_records
+                    .get(msg_id)
+                    .is_none_or(ExecutionRecord::is_complete)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        for msg_id in settled {
+            if let Some(waiter) = self.execution_waiters.remove(&msg_id)
+                && let Some(record) = self.execution_records.get(&msg_id)
+            {
+                // Fails only when the caller stopped waiting.
+                waiter.send(record.clone()).ok();
+            }
+        }
+    }
+
+    /// Settles the sent execution when `message` is its reply or says it went
+    /// idle, whichever comes first, so kernels that never report going idle
+    /// don't hold up the queue. An error reply also stops the executions
+    /// queued after it.
+    /// Errors caused by the user interrupting the execution only stop them
+    /// when `repl.stop_on_interrupt` is set.
+    fn update_sent_execution(&mut self, message: &JupyterMessage, cx: &mut Context<Self>) {
+        let Some(sent_execution) = &mut self.sent_execution else {
+            return;
+        };
+        let is_child = message
+            .parent_header
+            .as_ref()
+            .is_some_and(|header| header.msg_id == sent_execution.msg_id());
+        if !is_child {
+            return;
+        }
+
+        let now = cx.background_executor().now();
+        let recently_interrupted = sent_execution.interrupted_at.is_some_and(|interrupted_at| {
+            now.duration_since(interrupted_at) <= INTERRUPT_ERROR_WINDOW
+        });
+        let interrupt_caused_error = match &message.content {
+            // The first error decides, since the kernel may report more
+            // while unwinding.
+            JupyterMessageContent::ErrorOutput(error)
+                if sent_execution.interrupt_caused_error.is_none() =>
+            {
+                Some(
+                    sent_execution.interrupted_at.is_some()
+                        && (recently_interrupted
+                            || INTERRUPT_ERROR_NAMES.contains(&error.ename.as_str())),
+                )
+            }
+            // Some kernels only report the error in their reply.
+            JupyterMessageContent::ExecuteReply(reply)
+                if sent_execution.interrupt_caused_error.is_none()
+                    && matches!(reply.status, ReplyStatus::Error) =>
+            {
+                Some(recently_interrupted)
+            }
+            _ => None,
+        };
+        if let Some(interrupt_caused_error) = interrupt_caused_error {
+            sent_execution.interrupt_caused_error = Some(interrupt_caused_error);
+            if interrupt_caused_error {
+                let msg_id = sent_execution.msg_id().to_string();
+                self.mark_interrupted(&msg_id, cx);
+            }
+        }
+
+        let Some(sent_execution) = &self.sent_execution else {
+            return;
+        };
+        match &message.content {
+            JupyterMessageContent::ExecuteReply(reply) => {
+                if sent_execution.stop_on_error
+                    && matches!(reply.status, ReplyStatus::Error)
+                    && (sent_execution.interrupt_caused_error != Some(true)
+                        || ReplSettings::get_global(cx).stop_on_interrupt)
+                {
+                    self.cancel_queued_executions(cx);
+                }
+                self.sent_execution = None;
+                self.send_next_queued_execution(cx);
+            }
+            JupyterMessageContent::Status(status)
+                if matches!(status.execution_state, ExecutionState::Idle) =>
+            {
+                self.sent_execution = None;
+                self.send_next_queued_execution(cx);
+            }
+            _ => {}
+        }
+    }
+
+    /// Shows the error of the execution sent as `msg_id` as caused by the
+    /// user interrupting it, rather than as a failure.
+    fn mark_interrupted(&mut self, msg_id: &str, cx: &mut Context<Self>) {
+        self.execution_records.mark_interrupted(msg_id);
+        if let Some(block) = self.blocks.get(msg_id) {
+            block
+                .execution_view
+                .update(cx, |execution_view, cx| execution_view.mark_interrupted(cx));
+        }
+    }
+
+    /// Starts waiting for the sent execution's idle status once the kernel
+    /// replied to it, and starts waiting over whenever the kernel sends
+    /// anything else about it, since it can keep sending output after the
+    /// reply. Only the last execution replied to is waited for, since the
+    /// kernel must have finished the one before it.
+    fn watch_for_missing_idle(&mut self, message: &JupyterMessage, cx: &mut Context<Self>) {
+        let Some(parent_header) = &message.parent_header else {
+            return;
+        };
+        let msg_id = parent_header.msg_id.clone();
+        let watched_request = self
+            .missing_idle_task
+            .as_ref()
+            .map(|(request, _)| request)
+            .filter(|request| request.header.msg_id == msg_id)
+            .cloned();
+        let request = match &message.content {
+            JupyterMessageContent::Status(status)
+                if matches!(status.execution_state, ExecutionState::Idle) =>
+            {
+                if watched_request.is_some() {
+                    self.missing_idle_task = None;
+                }
+                return;
+            }
+            JupyterMessageContent::ExecuteReply(_) => {
+                let sent_request = self
+                    .sent_execution
+                    .as_ref()
+                    .filter(|execution| execution.msg_id() == msg_id)
+                    .map(|execution| execution.message.clone());
+                match sent_request {
+                    Some(request) => request,
+                    None => return,
+                }
+            }
+            _ => match watched_request {
+                Some(request) => request,
+                None => return,
+            },
+        };
+        if let Some((previous_request, _)) = self.missing_idle_task.take()
+            && previous_request.header.msg_id != msg_id
+        {
+            self.finish_without_idle(&previous_request, cx);
+        }
+        let Some(timeout) = ReplSettings::get_global(cx).idle_status_timeout() else {
+            return;
+        };
+
+        let timer = cx.background_executor().timer(timeout);
+        let task = cx.spawn({
+            let request = request.clone();
+            async move |this, cx| {
+                timer.await;
+                let Ok(presentation_window) = this.update(cx, |session, cx| {
+                    session.missing_idle_task = None;
+                    session.finish_without_idle(&request, cx);
+                    session.presentation_window
+                }) else {
+                    return;
+                };
+                if let Some(presentation_window) = presentation_window {
+                    presentation_window
+                        .update(cx, |_, window, cx| {
+                            this.update(cx, |session, cx| session.present_messages(window, cx))
+                                .ok();
+                        })
+                        .ok();
+                }
+            }
+        });
+        self.missing_idle_task = Some((request, task));
+    }
+
+    /// Finishes the execution sent as `request` as if the kernel had reported
+    /// going idle, for kernels that reply to an execution but never do.
+    fn finish_without_idle(&mut self, request: &JupyterMessage, cx: &mut Context<Self>) {
+        let msg_id = &request.header.msg_id;
+
+        let kernel_version = self
+            .kernel
+            .kernel_info()
+            .map(|info| format!("{} {}", info.implementation, info.implementation_version))
+            .unwrap_or_else(|| "unknown version".to_string());
+        log::warn!(
+            "kernel {} ({kernel_version}) replied to execution {msg_id} but never reported going idle, finishing it anyway",
+            self.kernel_specification.name(),
+        );
+
+        let idle = JupyterMessage::new(
+            Status {
+                execution_state: ExecutionState::Idle,
+            },
+            Some(request),
+        );
+        self.execution_records.handle_message(&idle, Utc::now());
+        self.log_output(&idle);
+        if self.block_for_message(&idle).is_some() {
+            self.queue_for_presentation(idle);
+        }
+        self.settle_execution_waiters();
+
+        // Only the kernel's own status can say it's idle while it still runs
+        // something else.
+        let is_kernel_busy_elsewhere = self.pending_execution_count(cx) > 0
+            || self.sent_execution.is_some()
+            || self.runtime_probe.is_some()
+            || self.variables_query.is_some()
+            || self.working_directory_change.is_some()
+            || self.display_setup.is_some();
+        if !is_kernel_busy_elsewhere {
+            self.kernel.set_execution_state(&ExecutionState::Idle);
+        }
+        cx.notify();
+    }
+
+    fn on_buffer_event(
+        &mut self,
+        buffer: Entity<MultiBuffer>,
+        event: &multi_buffer::Event,
+        cx: &mut Context<Self>,
+    ) {
+        if let multi_buffer::Event::Edited { .. } = event {
+            let Some(editor) = self.editors.iter().find_map(|attached| {
+                attached
+                    .editor
+                    .upgrade()
+                    .filter(|editor| editor.read(cx).buffer() == &buffer)
+            }) else {
+                return;
+            };
+            let editor_id = editor.entity_id();
+            let snapshot = buffer.read(cx).snapshot(cx);
+
+            let mut blocks_to_remove: HashSet<CustomBlockId> = HashSet::default();
+            let mut gutter_ranges_to_remove: Vec<Range<Anchor>> = Vec::new();
+            let mut keys_to_remove: Vec<String> = Vec::new();
+
+            self.blocks.retain(|id, block| {
+                if block.editor.entity_id() != editor_id
+                    || block.invalidation_anchor.is_valid(&snapshot)
+                {
+                    true
+                } else {
+                    blocks_to_remove.insert(block.block_id);
+                    gutter_ranges_to_remove.push(block.code_range.clone());
+                    keys_to_remove.push(id.clone());
+                    false
+                }
+            });
+
+            let mut inlays_to_remove: Vec<InlayId> = Vec::new();
+
+            self.result_inlays.retain(|id, inlay| {
+                if inlay.editor.entity_id() != editor_id {
+                    return true;
+                }
+                let start_offset = inlay.code_range.start.to_offset(&snapshot);
+                let end_offset = inlay.code_range.end.to_offset(&snapshot);
+                let current_len = end_offset.saturating_sub(start_offset);
+
+                if current_len != inlay.original_len {
+                    inlays_to_remove.push(inlay.inlay_id);
+                    gutter_ranges_to_remove.push(inlay.code_range.clone());
+                    keys_to_remove.push(id.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+
+            if !blocks_to_remove.is_empty()
+                || !inlays_to_remove.is_empty()
+                || !gutter_ranges_to_remove.is_empty()
+            {
+                editor.update(cx, |editor, cx| {
+                    if !blocks_to_remove.is_empty() {
+                        editor.remove_blocks(blocks_to_remove, None, cx);
+                    }
+                    if !inlays_to_remove.is_empty() {
+                        editor.splice_inlays(&inlays_to_remove, vec![], cx);
+                    }
+                    if !gutter_ranges_to_remove.is_empty() {
+                        editor.remove_gutter_highlights::<ReplExecutedRange>(
+                            gutter_ranges_to_remove,
+                            cx,
+                        );
+                    }
+                });
+                cx.notify();
+            }
+        }
+    }
+
+    fn send(&mut self, message: JupyterMessage, _cx: &mut Context<Self>) -> anyhow::Result<()> {
+        let Kernel::RunningKernel(kernel) = &mut self.kernel else {
+            anyhow::bail!("the kernel isn't running");
+        };
+        kernel
+            .request_tx()
+            .try_send(message)
+            .map_err(|error| anyhow::anyhow!("failed to send a message to the kernel: {error}"))
+    }
+
+    /// Ask the kernel whether `code` is made of complete statements.
+    ///
+    /// Resolves to [`IsCompleteReplyStatus::Unknown`] when the kernel isn't
+    /// running or doesn't answer in time, so callers can fall back to running
+    /// the code as-is.
+    pub fn is_complete(
+        &mut self,
+        code: String,
+        cx: &mut Context<Self>,
+    ) -> Task<IsCompleteReplyStatus> {
+        if !matches!(self.kernel, Kernel::RunningKernel(_)) {
+            return Task::ready(IsCompleteReplyStatus::Unknown);
+        }
+
+        let message: JupyterMessage = IsCompleteRequest { code }.into();
+        let msg_id = message.header.msg_id.clone();
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.pending_is_complete.insert(msg_id.clone(), reply_tx);
+        self.send(message, cx).log_err();
+
+        let timeout = cx.background_executor().timer(IS_COMPLETE_TIMEOUT);
+        cx.spawn(async move |this, cx| {
+            let status = futures::select_biased! {
+                status = reply_rx.fuse() => status.unwrap_or(IsCompleteReplyStatus::Unknown),
+                _ = timeout.fuse() => IsCompleteReplyStatus::Unknown,
+            };
+            this.update(cx, |session, _| {
+                session.pending_is_complete.remove(&msg_id);
+            })
+            .ok();
+            status
+        })
+    }
+
+    /// What's known about the running kernel's process.
+    ///
+    /// The interpreter and environment are fetched from the kernel the first
+    /// time this is called and cached until the kernel restarts, so the
+    /// environment is `None` until the kernel has answered. Only the variables
+    /// listed in `repl.runtime_info_environment_variables` are ever read.
+    pub fn runtime_info(&mut self, cx: &mut Context<Self>) -> Option<KernelRuntimeInfo> {
+        let Kernel::RunningKernel(kernel) = &self.kernel else {
+            return None;
+        };
+        let mut info = kernel.runtime_info();
+
+        if let Some(probed) = &self.probed_runtime {
+            if probed.interpreter.is_some() {
+                info.interpreter = probed.interpreter.clone();
+            }
+            info.environment = Some(probed.environment.clone());
+        } else if self.runtime_probe.is_none() {
+            self.probe_runtime(cx);
+        }
+        info.protocol_version = self.protocol_version;
+        info.debug_support = self.kernel.kernel_info().map(|kernel_info| {
+            if kernel_info.debugger && self.supports(ProtocolFeature::DebugRequests) {
+                KernelDebugSupport::Supported {
+                    info: self.debug_info.clone(),
+                }
             } else {
-                true
+                KernelDebugSupport::Unsupported
             }
         });
 
-        self.result_inlays
-            .retain(|_key, (inlay_id, inlay_range, _)| {
-                if anchor_range.overlaps(inlay_range, &buffer) {
-                    inlays_to_remove.push(*inlay_id);
-                    gutter_ranges_to_remove.push(inlay_range.clone());
-                    false
-                } else {
-                    true
+        Some(info)
+    }
+
+    /// Whether the kernel supports `feature`. Kernels that haven't sent their
+    /// kernel info yet are assumed to, as they were before versions were
+    /// checked.
+    fn supports(&self, feature: ProtocolFeature) -> bool {
+        self.protocol_version
+            .is_none_or(|version| version.supports(feature))
+    }
+
+    /// Sends a Debug Adapter Protocol request, such as
+    /// `{"type": "request", "seq": 1, "command": "debugInfo"}`, to the kernel
+    /// over its control channel, and returns the content of the kernel's
+    /// `debug_reply`. Kernels whose kernel info says they don't support the
+    /// debug protocol, or whose protocol version predates it, fail right away
+    /// rather than being waited on.
+    pub fn debug_request(
+        &mut self,
+        request: serde_json::Value,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<serde_json::Value, DebugRequestError>> {
+        if !matches!(self.kernel, Kernel::RunningKernel(_)) {
+            return Task::ready(Err(DebugRequestError::NotRunning));
+        }
+        match self.kernel.kernel_info() {
+            Some(kernel_info) if kernel_info.debugger => {}
+            Some(_) => return Task::ready(Err(DebugRequestError::Unsupported)),
+            None => return Task::ready(Err(DebugRequestError::NotReady)),
+        }
+        if let Some(version) = self.protocol_version
+            && !version.supports(ProtocolFeature::DebugRequests)
+        {
+            return Task::ready(Err(DebugRequestError::UnsupportedProtocol(version)));
+        }
+
+        let message: JupyterMessage = DebugRequest { content: request }.into();
+        let msg_id = message.header.msg_id.clone();
+        if let Err(error) = self.send(message, cx) {
+            log::error!("couldn't send a debug_request: {error:#}");
+            return Task::ready(Err(DebugRequestError::NotSent));
+        }
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.pending_debug_requests.insert(msg_id.clone(), reply_tx);
+
+        let timeout = cx.background_executor().timer(DEBUG_REPLY_TIMEOUT);
+        cx.spawn(async move |this, cx| {
+            let reply = futures::select_biased! {
+                reply = reply_rx.fuse() => reply.ok(),
+                _ = timeout.fuse() => None,
+            };
+            this.update(cx, |session, _| {
+                session.pending_debug_requests.remove(&msg_id);
+            })
+            .ok();
+            reply.ok_or(DebugRequestError::NoReply)
+        })
+    }
+
+    /// Asks a kernel that supports the debug protocol for its `debugInfo`,
+    /// which is kept for [`Session::runtime_info`].
+    fn request_debug_info(&mut self, cx: &mut Context<Self>) {
+        let request = serde_json::json!({
+            "type": "request",
+            "seq": 1,
+            "command": "debugInfo",
+        });
+        let reply = self.debug_request(request, cx);
+        cx.spawn(async move |this, cx| {
+            let reply = match reply.await {
+                Ok(reply) => reply,
+                Err(error) => {
+                    log::warn!("kernel didn't answer debugInfo: {error}");
+                    return;
+                }
+            };
+            this.update(cx, |session, cx| {
+                session.debug_info = Some(reply.get("body").cloned().unwrap_or(reply));
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn probe_runtime(&mut self, cx: &mut Context<Self>) {
+        let variables = allowed_environment_variables(cx);
+        let Some(code) = runtime_probe_code(&self.kernel_specification.language(), &variables)
+        else {
+            return;
+        };
+
+        self.runtime_probe = Some(SilentExecution::send(code, self, cx));
+    }
+
+    fn clear_runtime_info(&mut self) {
+        self.runtime_probe = None;
+        self.probed_runtime = None;
+        self.remote_working_directory = None;
+        self.working_directory_change = None;
+        self.display_setup = None;
+    }
+
+    /// Where the kernel is running on its Jupyter server, if the project is
+    /// mapped to a path there.
+    pub fn remote_working_directory(&self) -> Option<&RemoteWorkingDirectory> {
+        self.remote_working_directory.as_ref()
+    }
+
+    /// Asks a kernel on a Jupyter server to change into `path`. This is sent
+    /// before any queued executions, so user code always runs from there.
+    fn change_remote_working_directory(&mut self, path: String, cx: &mut Context<Self>) {
+        let language = self.kernel_specification.language();
+        let Some(code) = working_directory_code(&language, &path) else {
+            log::warn!("can't change the working directory of {language} kernels");
+            self.remote_working_directory = Some(RemoteWorkingDirectory::Failed(path));
+            return;
+        };
+
+        self.working_directory_change = Some(SilentExecution::send(code, self, cx));
+        self.remote_working_directory = Some(RemoteWorkingDirectory::Pending(path));
+    }
+
+    /// Runs the kernel language's `repl.display_setup_code`, such as turning
+    /// on inline plots. Like the working directory change, this is sent
+    /// before any queued executions, so it's in place before user code runs.
+    fn send_display_setup(&mut self, cx: &mut Context<Self>) {
+        let language = self.kernel_specification.language().to_lowercase();
+        let code = ReplSettings::get_global(cx)
+            .display_setup_code
+            .get(&language)
+            .cloned();
+        let Some(code) = code.filter(|code| !code.trim().is_empty()) else {
+            return;
+        };
+
+        self.display_setup = Some(SilentExecution::send(code, self, cx));
+    }
+
+    fn finish_working_directory_change(&mut self, stdout: &str, cx: &mut Context<Self>) {
+        let Some(RemoteWorkingDirectory::Pending(path)) = self.remote_working_directory.take()
+        else {
+            return;
+        };
+
+        let changed_to = stdout
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(WORKING_DIRECTORY_MARKER));
+        self.remote_working_directory = match changed_to {
+            Some(directory) => {
+                if let Kernel::RunningKernel(kernel) = &mut self.kernel {
+                    kernel.set_working_directory(PathBuf::from(directory));
+                }
+                Some(RemoteWorkingDirectory::Changed(directory.to_string()))
+            }
+            None => {
+                log::warn!("remote kernel failed to change its working directory to {path}");
+                Some(RemoteWorkingDirectory::Failed(path))
+            }
+        };
+        cx.notify();
+    }
+
+    /// The variables defined in the kernel's global namespace, as of the last
+    /// execution that completed. Empty for languages without an
+    /// introspection snippet.
+    pub fn variables(&self) -> &[VariableSummary] {
+        &self.variables
+    }
+
+    fn schedule_variables_refresh(&mut self, cx: &mut Context<Self>) {
+        if introspection_snippet(&self.kernel_specification.language()).is_none() {
+            return;
+        }
+
+        let timer = cx.background_executor().timer(REFRESH_VARIABLES_DEBOUNCE);
+        self.refresh_variables_task = Some(cx.spawn(async move |this, cx| {
+            timer.await;
+            this.update(cx, |session, cx| session.refresh_variables(cx))
+                .ok();
+        }));
+    }
+
+    fn refresh_variables(&mut self, cx: &mut Context<Self>) {
+        self.refresh_variables_task = None;
+        if !matches!(self.kernel, Kernel::RunningKernel(_)) {
+            return;
+        }
+        if self.variables_query.is_some() {
+            self.variables_stale = true;
+            return;
+        }
+        let Some(code) = introspection_snippet(&self.kernel_specification.language()) else {
+            return;
+        };
+        self.variables_stale = false;
+        self.variables_query = Some(SilentExecution::send(code, self, cx));
+    }
+
+    fn finish_variables_query(&mut self, stdout: &str, cx: &mut Context<Self>) {
+        match parse_variables(stdout) {
+            Some(variables) => {
+                if variables != self.variables {
+                    self.variables = variables;
+                    cx.emit(SessionEvent::VariablesChanged);
+                    cx.notify();
+                }
+            }
+            None => log::debug!("failed to list kernel variables"),
+        }
+
+        if self.variables_stale {
+            self.refresh_variables(cx);
+        }
+    }
+
+    fn clear_variables(&mut self, cx: &mut Context<Self>) {
+        self.variables_query = None;
+        self.variables_stale = false;
+        self.refresh_variables_task = None;
+        if !self.variables.is_empty() {
+            self.variables.clear();
+            cx.emit(SessionEvent::VariablesChanged);
+        }
+    }
+
+    /// Whether the kernel is kept running even when it's idle for longer than
+    /// `repl.idle_kernel_shutdown_minutes`.
+    pub fn keep_alive(&self) -> bool {
+        self.keep_alive
+    }
+
+    pub fn set_keep_alive(&mut self, keep_alive: bool, cx: &mut Context<Self>) {
+        self.keep_alive = keep_alive;
+        self.schedule_idle_shutdown(cx);
+        cx.notify();
+    }
+
+    fn record_activity(&mut self, cx: &mut Context<Self>) {
+        self.last_activity = cx.background_executor().now();
+        self.schedule_idle_shutdown(cx);
+    }
+
+    /// Spills the large outputs of older executions to disk in the
+    /// background, and drops them from their blocks once they're written.
+    fn spill_older_outputs(&mut self, cx: &mut Context<Self>) {
+        let kept_in_memory = ReplSettings::get_global(cx).outputs_kept_in_memory;
+        let Some(spill) = self
+            .execution_records
+            .spill_older_outputs(kept_in_memory, &self.fs, cx)
+        else {
+            return;
+        };
+        cx.spawn(async move |this, cx| {
+            let pending = spill.await;
+            this.update(cx, |session, cx| {
+                for msg_id in session.execution_records.finish_spill(pending) {
+                    if let Some(block) = session.blocks.get(&msg_id) {
+                        block.execution_view.update(cx, |execution_view, cx| {
+                            execution_view.unload_outputs(cx);
+                        });
+                    }
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Reads the outputs of an execution whose block dropped them back from
+    /// disk, and shows them in the block again.
+    fn show_spilled_outputs(
+        &mut self,
+        msg_id: &str,
+        execution_view: Entity<ExecutionView>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(record) = self.execution_records.get(msg_id).cloned() else {
+            return;
+        };
+        let fs = self.fs.clone();
+        let load = cx.background_spawn(async move {
+            let outputs = record.load_outputs(fs.as_ref()).await?;
+            anyhow::Ok(outputs.into_iter().map(Cow::into_owned).collect::<Vec<_>>())
+        });
+        cx.spawn_in(window, async move |_, cx| {
+            let outputs = load.await?;
+            execution_view.update_in(cx, |execution_view, window, cx| {
+                execution_view.show_recorded_outputs(&outputs, window, cx);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn idle_shutdown_timeout(&self, cx: &App) -> Option<Duration> {
+        let minutes = ReplSettings::get_global(cx).idle_kernel_shutdown_minutes;
+        if minutes == 0 || self.keep_alive {
+            return None;
+        }
+        Some(Duration::from_secs(minutes.saturating_mul(60)))
+    }
+
+    fn schedule_idle_shutdown(&mut self, cx: &mut Context<Self>) {
+        let Some(timeout) = self.idle_shutdown_timeout(cx) else {
+            self.idle_shutdown_task = None;
+            return;
+        };
+        if !matches!(self.kernel, Kernel::RunningKernel(_)) {
+            self.idle_shutdown_task = None;
+            return;
+        }
+
+        let idle_for = cx.background_executor().now() - self.last_activity;
+        let timer = cx
+            .background_executor()
+            .timer(timeout.saturating_sub(idle_for));
+        self.idle_shutdown_task = Some(cx.spawn(async move |this, cx| {
+            timer.await;
+            this.update(cx, |session, cx| session.shutdown_if_idle(cx))
+                .ok();
+        }));
+    }
+
+    fn shutdown_if_idle(&mut self, cx: &mut Context<Self>) {
+        self.idle_shutdown_task = None;
+        let Some(timeout) = self.idle_shutdown_timeout(cx) else {
+            return;
+        };
+        // Executions started without a window have no block to count.
+        let is_busy = self.pending_execution_count(cx) > 0
+            || self.sent_execution.is_some()
+            || !self.execution_queue.is_empty()
+            || !self.open_comms.is_empty();
+        if is_busy {
+            // The kernel only counts as idle from when its work finishes.
+            self.record_activity(cx);
+            return;
+        }
+        if cx.background_executor().now() - self.last_activity < timeout {
+            self.schedule_idle_shutdown(cx);
+            return;
+        }
+
+        let kernel = std::mem::replace(&mut self.kernel, Kernel::ShuttingDown);
+        let Kernel::RunningKernel(mut kernel) = kernel else {
+            self.kernel = kernel;
+            return;
+        };
+
+        let name = self.kernel_specification.name();
+        let minutes = timeout.as_secs() / 60;
+        // Like on exit, kernels Zed attached to are only disconnected from.
+        if self.detaches_on_exit(cx) || kernel.is_owned() == Some(false) {
+            kernel.kill();
+            log::info!(
+                "detached from kernel {name} after being idle for {minutes} minutes, \
+                 leaving it running on the server"
+            );
+        } else {
+            log::info!("shutting down kernel {name} after being idle for {minutes} minutes");
+            let message: JupyterMessage = ShutdownRequest { restart: false }.into();
+            kernel.request_tx().try_send(message).log_err();
+            let deletion = kernel.delete_if_owned(cx);
+            let grace_period = cx.background_executor().timer(IDLE_SHUTDOWN_GRACE_PERIOD);
+            cx.spawn(async move |_, _| {
+                grace_period.await;
+                kernel.kill();
+                if let Some(deletion) = deletion {
+                    deletion
+                        .await
+                        .with_context(|| format!("deleting kernel {name} from its server"))
+                        .log_err();
+                }
+            })
+            .detach();
+        }
+
+        self.kernel(Kernel::Culled, cx);
+        cx.notify();
+    }
+
+    fn send_stdin_reply(
+        &mut self,
+        value: String,
+        parent_message: &JupyterMessage,
+        _cx: &mut Context<Self>,
+    ) {
+        if let Kernel::RunningKernel(kernel) = &mut self.kernel {
+            let reply = InputReply {
+                value,
+                status: ReplyStatus::Ok,
+                error: None,
+            };
+            let message = reply.as_child_of(parent_message);
+            kernel.stdin_tx().try_send(message).log_err();
+        }
+    }
+
+    fn replace_block_with_inlay(&mut self, message_id: &str, text: &str, cx: &mut Context<Self>) {
+        let Some(block) = self.blocks.remove(message_id) else {
+            return;
+        };
+
+        let Some(editor) = block.editor.upgrade() else {
+            return;
+        };
+
+        let code_range = block.code_range.clone();
+
+        editor.update(cx, |editor, cx| {
+            let mut block_ids = HashSet::default();
+            block_ids.insert(block.block_id);
+            editor.remove_blocks(block_ids, None, cx);
+
+            let buffer = editor.buffer().read(cx).snapshot(cx);
+            let start_offset = code_range.start.to_offset(&buffer);
+            let end_offset = code_range.end.to_offset(&buffer);
+            let original_len = end_offset.saturating_sub(start_offset);
+
+            let end_point = code_range.end.to_point(&buffer);
+            let inlay_position = buffer.anchor_after(end_point);
+
+            let inlay_id = self.next_inlay_id;
+            self.next_inlay_id += 1;
+
+            let inlay = Inlay::repl_result(inlay_id, inlay_position, format!("    {}", text));
+
+            editor.splice_inlays(&[], vec![inlay], cx);
+            self.result_inlays.insert(
+                message_id.to_string(),
+                ResultInlay {
+                    editor: block.editor.clone(),
+                    inlay_id: InlayId::ReplResult(inlay_id),
+                    code_range: code_range.clone(),
+                    original_len,
+                },
+            );
+
+            editor.insert_gutter_highlight::<ReplExecutedRange>(
+                code_range,
+                |cx| cx.theme().status().success,
+                cx,
+            );
+        });
+
+        cx.notify();
+    }
+
+    /// Removes the outputs shown in every attached editor.
+    pub fn clear_outputs(&mut self, cx: &mut Context<Self>) {
+        let editor_ids = self
+            .editors
+            .iter()
+            .map(|attached| attached.editor.entity_id())
+            .collect::<Vec<_>>();
+        for editor_id in editor_ids {
+            self.clear_outputs_in(editor_id, cx);
+        }
+
+        self.blocks.clear();
+        self.result_inlays.clear();
+    }
+
+    /// Removes the outputs shown in the editor `editor_id`.
+    pub fn clear_outputs_in(&mut self, editor_id: EntityId, cx: &mut Context<Self>) {
+        let mut blocks_to_remove: HashSet<CustomBlockId> = HashSet::default();
+        self.blocks.retain(|_, block| {
+            if block.editor.entity_id() == editor_id {
+                blocks_to_remove.insert(block.block_id);
+                false
+            } else {
+                true
+            }
+        });
+
+        let mut inlays_to_remove: Vec<InlayId> = Vec::new();
+        self.result_inlays.retain(|_, inlay| {
+            if inlay.editor.entity_id() == editor_id {
+                inlays_to_remove.push(inlay.inlay_id);
+                false
+            } else {
+                true
+            }
+        });
+
+        let Some(editor) = self
+            .editors
+            .iter()
+            .find(|attached| attached.editor.entity_id() == editor_id)
+            .and_then(|attached| attached.editor.upgrade())
+        else {
+            return;
+        };
+        editor.update(cx, |editor, cx| {
+            editor.remove_blocks(blocks_to_remove, None, cx);
+            editor.splice_inlays(&inlays_to_remove, vec![], cx);
+            editor.clear_gutter_highlights::<ReplExecutedRange>(cx);
+        });
+    }
+
+    pub fn clear_output_at_position(
+        &mut self,
+        editor: &Entity<Editor>,
+        position: Anchor,
+        cx: &mut Context<Self>,
+    ) {
+        let (block_id, code_range, msg_id) = {
+            let snapshot = editor.read(cx).buffer().read(cx).read(cx);
+            let pos_range = position..position;
+
+            let block_to_remove = self.blocks.iter().find(|(_, block)| {
+                block.editor.entity_id() == editor.entity_id()
+                    && block.code_range.includes(&pos_range, &snapshot)
+            });
+
+            let Some((msg_id, block)) = block_to_remove else {
+                return;
+            };
+
+            (block.block_id, block.code_range.clone(), msg_id.clone())
+        };
+
+        let inlay_to_remove = self.result_inlays.get(&msg_id).map(|inlay| inlay.inlay_id);
+
+        self.blocks.remove(&msg_id);
+        if inlay_to_remove.is_some() {
+            self.result_inlays.remove(&msg_id);
+        }
+
+        editor.update(cx, |editor, cx| {
+            let mut block_ids = HashSet::default();
+            block_ids.insert(block_id);
+            editor.remove_blocks(block_ids, None, cx);
+
+            if let Some(inlay_id) = inlay_to_remove {
+                editor.splice_inlays(&[inlay_id], vec![], cx);
+            }
+
+            editor.remove_gutter_highlights::<ReplExecutedRange>(vec![code_range], cx);
+        });
+
+        cx.notify();
+    }
+
+    /// Runs `code` from the session's first editor. See
+    /// [`Session::execute_in`].
+    pub fn execute(
+        &mut self,
+        code: String,
+        anchor_range: Range<Anchor>,
+        next_cell: Option<Anchor>,
+        move_down: bool,
+        options: ExecutionOptions,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.execute_in(
+            self.editor(),
+            code,
+            anchor_range,
+            next_cell,
+            move_down,
+            options,
+            window,
+            cx,
+        );
+    }
+
+    /// Runs `code` from `editor`, which must be attached to the session,
+    /// showing its output below `anchor_range`. Returns the execution's
+    /// message id, unless nothing was run.
+    pub fn execute_in(
+        &mut self,
+        editor: WeakEntity<Editor>,
+        code: String,
+        anchor_range: Range<Anchor>,
+        next_cell: Option<Anchor>,
+        move_down: bool,
+        options: ExecutionOptions,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<String> {
+        if !self.is_attached(editor.entity_id()) {
+            return None;
+        }
+        let weak_editor = editor;
+        let editor = weak_editor.upgrade()?;
+
+        if code.is_empty() {
+            return None;
+        }
+
+        if let Kernel::Culled = self.kernel {
+            self.start_kernel(window, cx);
+        }
+        self.record_activity(cx);
+
+        let code_preview = code.lines().next().unwrap_or_default().to_string();
+        let message: JupyterMessage = options.request(code.clone()).into();
+        let output_log_path = self
+            .output_log
+            .as_ref()
+            .map(|output_log| output_log.path().to_path_buf());
+        self.execution_records.start(&message, code).output_log_path = output_log_path.clone();
+        self.spill_older_outputs(cx);
+
+        let mut blocks_to_remove: HashSet<CustomBlockId> = HashSet::default();
+        let mut inlays_to_remove: Vec<InlayId> = Vec::new();
+        let mut gutter_ranges_to_remove: Vec<Range<Anchor>> = Vec::new();
+
+        let buffer = editor.read(cx).buffer().read(cx).snapshot(cx);
+        let editor_id = editor.entity_id();
+
+        self.blocks.retain(|_key, block| {
+            if block.editor.entity_id() == editor_id
+                && anchor_range.overlaps(&block.code_range, &buffer)
+            {
+                blocks_to_remove.insert(block.block_id);
+                false
+            } else {
+                true
+            }
+        });
+
+        self.result_inlays.retain(|_key, inlay| {
+            if inlay.editor.entity_id() == editor_id
+                && anchor_range.overlaps(&inlay.code_range, &buffer)
+            {
+                inlays_to_remove.push(inlay.inlay_id);
+                gutter_ranges_to_remove.push(inlay.code_range.clone());
+                false
+            } else {
+                true
+            }
+        });
+
+        editor.update(cx, |editor, cx| {
+            editor.remove_blocks(blocks_to_remove, None, cx);
+            if !inlays_to_remove.is_empty() {
+                editor.splice_inlays(&inlays_to_remove, vec![], cx);
+            }
+            if !gutter_ranges_to_remove.is_empty() {
+                editor.remove_gutter_highlights::<ReplExecutedRange>(gutter_ranges_to_remove, cx);
+            }
+        });
+
+        let status = match &self.kernel {
+            // Nothing is sent while restarting, so this execution will never run
+            Kernel::Restarting => ExecutionStatus::Aborted,
+            Kernel::RunningKernel(_) => ExecutionStatus::Queued,
+            Kernel::StartingKernel(_) => ExecutionStatus::ConnectingToKernel,
+            Kernel::ErroredLaunch(error, _) => ExecutionStatus::KernelErrored(error.clone()),
+            Kernel::ShuttingDown => ExecutionStatus::ShuttingDown,
+            Kernel::Shutdown => ExecutionStatus::Shutdown,
+            // Relaunched above
+            Kernel::Culled => ExecutionStatus::ConnectingToKernel,
+        };
+
+        let execution_id = message.header.msg_id.clone();
+        let parent_message_id = message.header.msg_id.clone();
+        let session_view = cx.entity().downgrade();
+        let code_range_for_close = anchor_range.clone();
+        let close_editor = weak_editor.clone();
+
+        let on_close: CloseBlockFn = Arc::new(
+            move |block_id: CustomBlockId, _: &mut Window, cx: &mut App| {
+                if let Some(session) = session_view.upgrade() {
+                    session.update(cx, |session, cx| {
+                        session.cancel_queued_execution(&parent_message_id, cx);
+                        session.blocks.remove(&parent_message_id);
+                        cx.notify();
+                    });
+                }
+
+                if let Some(editor) = close_editor.upgrade() {
+                    editor.update(cx, |editor, cx| {
+                        let mut block_ids = HashSet::default();
+                        block_ids.insert(block_id);
+                        editor.remove_blocks(block_ids, None, cx);
+                        editor.remove_gutter_highlights::<ReplExecutedRange>(
+                            vec![code_range_for_close.clone()],
+                            cx,
+                        );
+                    });
+                }
+            },
+        );
+
+        let Ok(editor_block) = EditorBlock::new(
+            weak_editor,
+            anchor_range.clone(),
+            status,
+            self.kernel_specification.html_trust(cx),
+            on_close,
+            cx,
+        ) else {
+            return None;
+        };
+        if output_log_path.is_some() {
+            editor_block.execution_view.update(cx, |execution_view, _| {
+                execution_view.output_log_path = output_log_path;
+            });
+        }
+
+        editor.update(cx, |editor, cx| {
+            editor.insert_gutter_highlight::<ReplExecutedRange>(
+                anchor_range.clone(),
+                |cx| cx.theme().status().success,
+                cx,
+            );
+        });
+
+        let new_cursor_pos = if let Some(next_cursor) = next_cell {
+            next_cursor
+        } else {
+            editor_block.invalidation_anchor
+        };
+
+        let msg_id = message.header.msg_id.clone();
+        let subscription = cx.subscribe(
+            &editor_block.execution_view,
+            move |session, _execution_view, _event: &ExecutionViewFinishedEmpty, cx| {
+                session.replace_block_with_inlay(&msg_id, "✓", cx);
+            },
+        );
+        self._subscriptions.push(subscription);
+
+        let msg_id = message.header.msg_id.clone();
+        let subscription = cx.subscribe(
+            &editor_block.execution_view,
+            move |session, _execution_view, event: &ExecutionViewFinishedSmall, cx| {
+                session.replace_block_with_inlay(&msg_id, &event.0, cx);
+            },
+        );
+        self._subscriptions.push(subscription);
+
+        let subscription = cx.subscribe(
+            &editor_block.execution_view,
+            |session, _execution_view, event: &InputReplyEvent, cx| {
+                session.send_stdin_reply(event.value.clone(), &event.parent_message, cx);
+            },
+        );
+        self._subscriptions.push(subscription);
+
+        let msg_id = message.header.msg_id.clone();
+        let subscription = cx.subscribe_in(
+            &editor_block.execution_view,
+            window,
+            move |session, execution_view, _event: &ShowSpilledOutputs, window, cx| {
+                session.show_spilled_outputs(&msg_id, execution_view.clone(), window, cx);
+            },
+        );
+        self._subscriptions.push(subscription);
+
+        self.register_block(message.header.msg_id.clone(), editor_block, window, cx);
+
+        if let Kernel::Restarting = &self.kernel {
+            let msg_id = message.header.msg_id.clone();
+            self.aborted_executions.insert(msg_id.clone(), false);
+            cx.emit(SessionEvent::ExecutionAborted(msg_id));
+        }
+
+        // Executions submitted while the kernel starts are sent once it's running.
+        if let Kernel::RunningKernel(_) | Kernel::StartingKernel(_) = &self.kernel {
+            self.execution_queue.push_back(PendingExecution {
+                message,
+                code_preview,
+                submitted_at: cx.background_executor().now(),
+                stop_on_error: options.stop_on_error,
+                interrupted_at: None,
+                interrupt_caused_error: None,
+            });
+            self.send_next_queued_execution(cx);
+        } else {
+            // Nothing will ever answer it.
+            self.execution_records.abort(&message.header.msg_id);
+        }
+
+        if move_down {
+            editor.update(cx, move |editor, cx| {
+                editor.change_selections(
+                    SelectionEffects::scroll(Autoscroll::top_relative(8.0)),
+                    window,
+                    cx,
+                    |selections| {
+                        selections.select_ranges([new_cursor_pos..new_cursor_pos]);
+                    },
+                );
+            });
+        }
+
+        Some(execution_id)
+    }
+
+    /// Interrupts the kernel the way its kernelspec asks for, falling back to
+    /// an `interrupt_request` when it can't be signaled.
+    pub fn interrupt(&mut self, cx: &mut Context<Self>) {
+        match &mut self.kernel {
+            Kernel::RunningKernel(kernel) => {
+                self.interrupt_error = None;
+                if let Some(sent_execution) = &mut self.sent_execution {
+                    sent_execution.interrupted_at = Some(cx.background_executor().now());
+                }
+                let signaled = match kernel.interrupt_mode() {
+                    InterruptMode::Signal => kernel
+                        .signal_interrupt()
+                        .context("sending an interrupt_request instead")
+                        .log_err()
+                        .is_some(),
+                    InterruptMode::Message => false,
+                };
+                if !signaled {
+                    self.send_interrupt_request(cx);
+                }
+                self.record_activity(cx);
+                cx.notify();
+            }
+            Kernel::StartingKernel(_task) => {
+                self.cancel_queued_executions(cx);
+            }
+            _ => {}
+        }
+    }
+
+    fn send_interrupt_request(&mut self, cx: &mut Context<Self>) {
+        let message: JupyterMessage = InterruptRequest {}.into();
+        let msg_id = message.header.msg_id.clone();
+        if let Err(error) = self.send(message, cx) {
+            log::error!("couldn't send an interrupt_request: {error:#}");
+            self.interrupt_error = Some(format!("Couldn't interrupt the kernel: {error}").into());
+            cx.notify();
+            return;
+        }
+
+        let timeout = cx.spawn(async move |this, cx| {
+            cx.background_executor()
+                .timer(INTERRUPT_REPLY_TIMEOUT)
+                .await;
+            this.update(cx, |session, cx| {
+                log::warn!(
+                    "kernel {} didn't answer an interrupt_request within {INTERRUPT_REPLY_TIMEOUT:?}",
+                    session.kernel_specification.name()
+                );
+                session.pending_interrupt = None;
+                session.interrupt_error = Some("The kernel didn't answer the interrupt".into());
+                cx.notify();
+            })
+            .ok();
+        });
+        self.pending_interrupt = Some((msg_id, timeout));
+    }
+
+    /// Why the last interrupt failed, if it did.
+    pub fn interrupt_error(&self) -> Option<&SharedString> {
+        self.interrupt_error.as_ref()
+    }
+
+    pub fn kernel(&mut self, kernel: Kernel, cx: &mut Context<Self>) {
+        if let Kernel::Restarting | Kernel::Shutdown = kernel {
+            self.abort_in_flight_executions(cx);
+        }
+        // Only the kernel being restarted or shut down can still send
+        // messages for the executions it was running.
+        if !matches!(kernel, Kernel::Restarting | Kernel::ShuttingDown) {
+            self.aborted_executions.clear();
+        }
+        self.clear_runtime_info();
+        self.clear_variables(cx);
+        self.open_comms.clear();
+        self.pending_interrupt = None;
+        self.interrupt_error = None;
+        self.pending_debug_requests.clear();
+        self.debug_info = None;
+        self.protocol_version = None;
+        self.exceeded_memory_limit = None;
+        self.resource_samples.clear();
+        self.resource_sampling_task = None;
+
+        match &kernel {
+            Kernel::ErroredLaunch(error, _) => self.fail_restart(error.clone().into()),
+            Kernel::ShuttingDown | Kernel::Shutdown => {
+                self.fail_restart("The kernel shut down before it restarted".into())
+            }
+            _ => {}
+        }
+        if let Kernel::Shutdown = kernel {
+            cx.emit(SessionEvent::Shutdown);
+        }
+
+        let kernel_status = KernelStatus::from(&kernel).to_string();
+        let kernel_language = self.kernel_specification.language();
+
+        telemetry::event!(
+            "Kernel Status Changed",
+            kernel_language,
+            kernel_status,
+            repl_session_id = cx.entity_id().to_string(),
+        );
+
+        self.kernel = kernel;
+        self.record_activity(cx);
+    }
+
+    /// Whether Zed owns the session's kernel on the Jupyter server it runs
+    /// on. `None` for kernels that don't run on a Jupyter server.
+    pub fn kernel_owned(&self) -> Option<bool> {
+        match &self.kernel {
+            Kernel::RunningKernel(kernel) => kernel.is_owned(),
+            _ => None,
+        }
+    }
+
+    /// Takes or releases ownership of the session's kernel, which decides
+    /// whether it's shut down when the session closes.
+    pub fn set_kernel_owned(&mut self, owned: bool, cx: &mut Context<Self>) {
+        if let Kernel::RunningKernel(kernel) = &mut self.kernel {
+            kernel.set_owned(owned);
+            cx.notify();
+        }
+    }
+
+    /// Runs `stop` right away for kernels Zed owns, or once the user confirms
+    /// stopping a kernel that other clients may be sharing.
+    fn confirm_stopping_shared_kernel(
+        &mut self,
+        title: &str,
+        confirm_label: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+        stop: fn(&mut Self, &mut Window, &mut Context<Self>),
+    ) {
+        if self.kernel_owned() != Some(false) {
+            stop(self, window, cx);
+            return;
+        }
+
+        let answer = window.prompt(
+            PromptLevel::Warning,
+            title,
+            Some(
+                "Zed attached to this kernel rather than starting it, so other clients may be \
+                using it. Stopping it stops it for all of them.",
+            ),
+            &[confirm_label, "Cancel"],
+            cx,
+        );
+        cx.spawn_in(window, async move |this, cx| {
+            if answer.await == Ok(0) {
+                this.update_in(cx, |session, window, cx| stop(session, window, cx))
+                    .ok();
+            }
+        })
+        .detach();
+    }
+
+    pub fn shutdown(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.confirm_stopping_shared_kernel(
+            "Shut down a shared kernel?",
+            "Shut Down",
+            window,
+            cx,
+            Self::shutdown_kernel,
+        );
+    }
+
+    fn shutdown_kernel(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let kernel = std::mem::replace(&mut self.kernel, Kernel::ShuttingDown);
+
+        match kernel {
+            Kernel::RunningKernel(mut kernel) => {
+                let mut request_tx = kernel.request_tx();
+
+                let forced = kernel.force_shutdown(window, cx);
+
+                cx.spawn(async move |this, cx| {
+                    let message: JupyterMessage = ShutdownRequest { restart: false }.into();
+                    request_tx.try_send(message).ok();
+
+                    forced.await.log_err();
+
+                    // Give the kernel a bit of time to clean up
+                    cx.background_executor().timer(Duration::from_secs(3)).await;
+
+                    this.update(cx, |session, cx| {
+                        session.clear_outputs(cx);
+                        session.kernel(Kernel::Shutdown, cx);
+                        cx.notify();
+                    })
+                    .ok();
+                })
+                .detach();
+            }
+            _ => {
+                self.kernel(Kernel::Shutdown, cx);
+            }
+        }
+        cx.notify();
+    }
+
+    /// Opens what the kernel process printed outside of the Jupyter protocol in
+    /// a read-only buffer, for kernels that keep such a log.
+    pub fn open_diagnostics_log(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Kernel::RunningKernel(kernel) = &self.kernel else {
+            return;
+        };
+        let Some(log) = kernel.diagnostics_log() else {
+            return;
+        };
+        let Some(editor) = self.editor().upgrade() else {
+            return;
+        };
+        let Some(workspace) = editor.read(cx).workspace() else {
+            return;
+        };
+
+        let title = format!("{} Kernel Log", self.kernel_specification.name());
+        let buffer = cx.new(|cx| {
+            let mut buffer =
+                language::Buffer::local(log, cx).with_language(language::PLAIN_TEXT.clone(), cx);
+            buffer.set_capability(language::Capability::ReadOnly, cx);
+            buffer
+        });
+        let buffer = cx.new(|cx| MultiBuffer::singleton(buffer, cx).with_title(title));
+        workspace.update(cx, |workspace, cx| {
+            let editor = cx.new(|cx| {
+                let mut editor = Editor::for_multibuffer(buffer, None, window, cx);
+                editor.set_read_only(true);
+                editor
+            });
+            workspace.add_item_to_active_pane(Box::new(editor), None, true, window, cx);
+        });
+    }
+
+    /// Takes the kernel out of the session because its workspace is closing or
+    /// Zed is quitting, and asks it to shut down. Returns the kernel so the
+    /// caller can kill it if it doesn't acknowledge in time.
+    ///
+    /// Kernels on remote servers configured to detach, and kernels Zed
+    /// attached to rather than started, are disconnected from and left
+    /// running instead. Owned kernels on remote servers are also deleted
+    /// from the server. Kernels that are still starting are killed as
+    /// soon as their launch completes.
+    pub(crate) fn take_kernel_for_exit(&mut self, cx: &mut Context<Self>) -> Option<ExitingKernel> {
+        let name = self.kernel_specification.name();
+        let exiting = match std::mem::replace(&mut self.kernel, Kernel::Shutdown) {
+            Kernel::RunningKernel(mut kernel) => {
+                if self.detaches_on_exit(cx) || kernel.is_owned() == Some(false) {
+                    kernel.kill();
+                    log::info!("detached from kernel {name}, leaving it running on the server");
+                    None
+                } else {
+                    let (acknowledged_tx, acknowledged) = oneshot::channel();
+                    self.shutdown_acknowledged_tx = Some(acknowledged_tx);
+                    let message: JupyterMessage = ShutdownRequest { restart: false }.into();
+                    kernel.request_tx().try_send(message).log_err();
+                    let deletion = kernel.delete_if_owned(cx);
+                    Some(ExitingKernel {
+                        name,
+                        kernel,
+                        acknowledged,
+                        deletion,
+                    })
+                }
+            }
+            Kernel::StartingKernel(_) => {
+                log::info!("cancelled start of kernel {name}");
+                None
+            }
+            _ => None,
+        };
+        self.kernel(Kernel::Shutdown, cx);
+        exiting
+    }
+
+    fn detaches_on_exit(&self, cx: &App) -> bool {
+        let KernelSpecification::JupyterServer(spec) = &self.kernel_specification else {
+            return false;
+        };
+        ReplSettings::get_global(cx)
+            .remote_kernels_on_exit
+            .get(&spec.url)
+            .is_some_and(|behavior| *behavior == RemoteKernelExitBehavior::Detach)
+    }
+
+    /// Forgets an attached editor that was closed. Closing the last one shuts
+    /// the kernel down.
+    fn on_editor_released(&mut self, editor_id: EntityId, cx: &mut Context<Self>) {
+        self.editors
+            .retain(|attached| attached.editor.entity_id() != editor_id);
+        self.blocks
+            .retain(|_, block| block.editor.entity_id() != editor_id);
+        self.result_inlays
+            .retain(|_, inlay| inlay.editor.entity_id() != editor_id);
+        let store = ReplStore::global(cx);
+        if !self.editors.is_empty() {
+            store.update(cx, |store, _cx| store.detach_editor(editor_id));
+            cx.notify();
+            return;
+        }
+
+        let session = cx.entity();
+        let kernels = self.take_kernel_for_exit(cx).into_iter().collect();
+        store.update(cx, |store, _cx| store.remove_session(&session));
+
+        let deadline = cx.background_executor().timer(EXIT_SHUTDOWN_TIMEOUT);
+        cx.background_spawn(ReplStore::shutdown_kernels(kernels, deadline))
+            .detach();
+    }
+
+    pub fn restart(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.confirm_stopping_shared_kernel(
+            "Restart a shared kernel?",
+            "Restart",
+            window,
+            cx,
+            |session, window, cx| session.restart_kernel(window, cx).detach_and_log_err(cx),
+        );
+    }
+
+    /// Restarts the kernel from the same specification and working directory,
+    /// keeping the session and the editors attached to it. In-flight
+    /// executions are aborted.
+    ///
+    /// Completes once the new kernel has answered `kernel_info` and run its
+    /// setup code, when [`SessionEvent::RestartCompleted`] is also emitted.
+    /// Fails if the kernel can't be launched again, or shuts down first.
+    /// Restarting while a restart is underway waits on that one instead.
+    pub fn restart_kernel(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<anyhow::Result<()>> {
+        let (restarted_tx, restarted_rx) = oneshot::channel();
+        let is_underway = self.pending_restart.is_some();
+        self.pending_restart
+            .get_or_insert_default()
+            .waiters
+            .push(restarted_tx);
+        if !is_underway {
+            self.begin_restart(window, cx);
+        }
+
+        cx.background_spawn(async move {
+            match restarted_rx.await {
+                Ok(result) => result.map_err(|error| anyhow::anyhow!("{error}")),
+                Err(_) => Err(anyhow::anyhow!(
+                    "The session ended before the kernel restarted"
+                )),
+            }
+        })
+    }
+
+    fn begin_restart(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let kernel = std::mem::replace(&mut self.kernel, Kernel::Restarting);
+
+        self.abort_in_flight_executions(cx);
+        self.clear_runtime_info();
+        self.clear_variables(cx);
+        self.execution_records.record_restart();
+
+        match kernel {
+            Kernel::RunningKernel(mut kernel) => {
+                log::debug!("restarting kernel");
+                let (acknowledged_tx, acknowledged) = oneshot::channel();
+                self.shutdown_acknowledged_tx = Some(acknowledged_tx);
+                let message: JupyterMessage = ShutdownRequest { restart: true }.into();
+                kernel.request_tx().try_send(message).log_err();
+
+                let deadline = cx
+                    .background_executor()
+                    .timer(RESTART_SHUTDOWN_GRACE_PERIOD);
+                cx.spawn_in(window, async move |this, cx| {
+                    futures::select_biased! {
+                        _ = acknowledged.fuse() => {}
+                        _ = deadline.fuse() => {
+                            log::warn!("kernel didn't acknowledge its restart, killing it");
+                        }
+                    }
+
+                    let forced =
+                        this.update_in(cx, |_, window, cx| kernel.force_shutdown(window, cx))?;
+                    forced.await.log_err();
+                    drop(kernel);
+
+                    this.update_in(cx, |session, window, cx| {
+                        session.clear_outputs(cx);
+                        session.start_kernel(window, cx);
+                    })
+                })
+                .detach_and_log_err(cx);
+            }
+            _ => {
+                self.clear_outputs(cx);
+                self.start_kernel(window, cx);
+            }
+        }
+        cx.notify();
+    }
+
+    /// Completes the restart that's underway once the new kernel has sent its
+    /// info and finished the setup code sent before any queued executions.
+    fn finish_restart_if_ready(&mut self, cx: &mut Context<Self>) {
+        let is_ready = self.kernel.kernel_info().is_some()
+            && self.working_directory_change.is_none()
+            && self.display_setup.is_none();
+        if !is_ready {
+            return;
+        }
+        let Some(restart) = self.pending_restart.take() else {
+            return;
+        };
+        for waiter in restart.waiters {
+            // Fails only when the caller stopped waiting.
+            waiter.send(Ok(())).ok();
+        }
+        cx.emit(SessionEvent::RestartCompleted);
+    }
+
+    fn fail_restart(&mut self, error: SharedString) {
+        let Some(restart) = self.pending_restart.take() else {
+            return;
+        };
+        for waiter in restart.waiters {
+            waiter.send(Err(error.clone())).ok();
+        }
+    }
+}
+
+pub enum SessionEvent {
+    Shutdown,
+    /// An in-flight execution was finalized because the kernel restarted or
+    /// shut down before replying. Carries the execution's message id.
+    ExecutionAborted(String),
+    /// The list of variables defined in the kernel changed.
+    VariablesChanged,
+    /// The kernel sent a `debug_event`, such as a breakpoint being hit.
+    /// Carries the event's Debug Adapter Protocol content.
+    DebugEvent(serde_json::Value),
+    /// A restart completed: the new kernel answered `kernel_info` and ran its
+    /// setup code.
+    RestartCompleted,
+    /// The kernel asked for `text` to be the next code run, as IPython's
+    /// `%load` does. With `replace`, it takes the place of the code in
+    /// `code_range`, in the editor `editor_id`. Otherwise it goes after it.
+    SetNextInput {
+        editor_id: EntityId,
+        code_range: Range<Anchor>,
+        text: String,
+        replace: bool,
+    },
+}
+
+impl EventEmitter<SessionEvent> for Session {}
+
+impl Render for Session {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let (status_text, interrupt_button) = match &self.kernel {
+            Kernel::RunningKernel(kernel) if kernel.is_reconnecting() => {
+                (Some("Reconnecting".into()), None)
+            }
+            Kernel::RunningKernel(kernel) => (
+                kernel
+                    .kernel_info()
+                    .as_ref()
+                    .map(|info| info.language_info.name.clone()),
+                Some(
+                    Button::new("interrupt", "Interrupt")
+                        .style(ButtonStyle::Subtle)
+                        .tooltip(Tooltip::text(kernel.interrupt_mode().description()))
+                        .on_click(cx.listener(move |session, _, _, cx| {
+                            session.interrupt(cx);
+                        })),
+                ),
+            ),
+            Kernel::StartingKernel(_) => (Some("Starting".into()), None),
+            Kernel::ErroredLaunch(err, _) => (Some(format!("Error: {err}")), None),
+            Kernel::ShuttingDown => (Some("Shutting Down".into()), None),
+            Kernel::Shutdown => (Some("Shutdown".into()), None),
+            Kernel::Culled => (Some("Culled (Idle)".into()), None),
+            Kernel::Restarting => (Some("Restarting".into()), None),
+        };
+
+        let keep_alive_button = (ReplSettings::get_global(cx).idle_kernel_shutdown_minutes > 0)
+            .then(|| {
+                Button::new("keep-alive", "Keep Alive")
+                    .style(ButtonStyle::Subtle)
+                    .toggle_state(self.keep_alive)
+                    .tooltip(Tooltip::text(
+                        "Keep this kernel running even when it's idle",
+                    ))
+                    .on_click(cx.listener(move |session, _, _, cx| {
+                        session.set_keep_alive(!session.keep_alive, cx);
+                    }))
+            });
+
+        let ownership_button = self.kernel_owned().map(|owned| {
+            Button::new("kernel-ownership", "Owned")
+                .style(ButtonStyle::Subtle)
+                .toggle_state(owned)
+                .tooltip(Tooltip::text(if owned {
+                    "Zed started this kernel and shuts it down when the session closes. Click to release it and leave it running."
+                } else {
+                    "Zed attached to this kernel and leaves it running when the session closes. Click to take ownership and shut it down with the session."
+                }))
+                .on_click(cx.listener(move |session, _, _, cx| {
+                    session.set_kernel_owned(!owned, cx);
+                }))
+        });
+
+        let startup_timing = match &self.kernel {
+            Kernel::RunningKernel(_) => self
+                .startup_timing
+                .as_ref()
+                .map(|timing| ("Started in", timing)),
+            Kernel::ErroredLaunch(_, timing) => {
+                timing.as_ref().map(|timing| ("Failed after", timing))
+            }
+            _ => None,
+        }
+        .map(|(prefix, timing)| {
+            let breakdown = timing
+                .phases()
+                .into_iter()
+                .map(|(phase, duration)| format!("{phase}: {}", format_seconds(duration)))
+                .collect::<Vec<_>>()
+                .join("\n");
+            div()
+                .id("startup-timing")
+                .child(
+                    Label::new(format!("{prefix} {}", format_seconds(timing.total())))
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+                .tooltip(Tooltip::text(breakdown))
+        });
+
+        let memory_warning_threshold = ReplSettings::get_global(cx).memory_warning_threshold();
+        let resource_usage = self.resource_usage().map(|sample| {
+            let is_over_threshold = memory_warning_threshold
+                .is_some_and(|threshold| sample.memory_bytes > threshold);
+            let tooltip = if is_over_threshold {
+                "Memory and CPU used by the kernel and the processes it started. The memory is over `repl.memory_warning_mb`."
+            } else {
+                "Memory and CPU used by the kernel and the processes it started"
+            };
+            h_flex()
+                .id("resource-usage")
+                .gap_1()
+                .when(is_over_threshold, |this| {
+                    this.child(
+                        Icon::new(IconName::Warning)
+                            .size(IconSize::Small)
+                            .color(Color::Warning),
+                    )
+                })
+                .child(
+                    Label::new(format!(
+                        "{} · {:.0}% CPU",
+                        format_memory(sample.memory_bytes),
+                        sample.cpu_percent
+                    ))
+                    .size(LabelSize::Small)
+                    .color(if is_over_threshold {
+                        Color::Warning
+                    } else {
+                        Color::Muted
+                    }),
+                )
+                .tooltip(Tooltip::text(tooltip))
+        });
+
+        let resource_limits = self.resource_limits().map(|limits| {
+            div()
+                .id("resource-limits")
+                .child(
+                    Label::new(format!("Limits: {limits}"))
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+                .tooltip(Tooltip::text(
+                    "The resource limits from `repl.kernel_resource_limits` the kernel was started with",
+                ))
+        });
+
+        let raise_memory_limit_button = self.exceeded_memory_limit.map(|limit| {
+            Button::new(
+                "raise-memory-limit",
+                format!(
+                    "Restart with {} Limit",
+                    format_memory_limit(limit.saturating_mul(2))
+                ),
+            )
+            .style(ButtonStyle::Subtle)
+            .tooltip(Tooltip::text(
+                "Restart the kernel with twice the memory it ran out of. The limit stays raised for this session.",
+            ))
+            .on_click(cx.listener(move |session, _, window, cx| {
+                session.restart_with_higher_memory_limit(window, cx);
+            }))
+        });
+
+        let mut unsent_index = 0;
+        let queued_executions = self
+            .queued_executions()
+            .into_iter()
+            .map(|execution| {
+                let row = h_flex()
+                    .id(SharedString::from(format!("queued-{}", execution.msg_id)))
+                    .pl_6()
+                    .gap_2()
+                    .child(
+                        Label::new(execution.code_preview)
+                            .size(LabelSize::Small)
+                            .buffer_font(cx)
+                            .truncate(),
+                    );
+                if execution.sent {
+                    return row.child(
+                        Label::new("Running")
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    );
+                }
+                let index = unsent_index;
+                unsent_index += 1;
+                let move_up_id = execution.msg_id.clone();
+                let cancel_id = execution.msg_id;
+                row.child(
+                    Label::new("Queued")
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+                .child(
+                    IconButton::new("move-up", IconName::ArrowUp)
+                        .icon_size(IconSize::Small)
+                        .disabled(index == 0)
+                        .tooltip(Tooltip::text("Run Earlier"))
+                        .on_click(cx.listener(move |session, _, _, cx| {
+                            session.move_queued_execution(&move_up_id, index.saturating_sub(1), cx);
+                        })),
+                )
+                .child(
+                    IconButton::new("cancel", IconName::Close)
+                        .icon_size(IconSize::Small)
+                        .tooltip(Tooltip::text("Cancel"))
+                        .on_click(cx.listener(move |session, _, _, cx| {
+                            session.cancel_queued_execution(&cancel_id, cx);
+                        })),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let attached_titles = self
+            .attached_editors()
+            .map(|editor| editor.read(cx).buffer().read(cx).title(cx).to_string())
+            .collect::<Vec<_>>();
+        let attached_buffers = if attached_titles.is_empty() {
+            "No buffers attached".to_string()
+        } else {
+            attached_titles.join(", ")
+        };
+
+        let kernel_list_item = KernelListItem::new(self.kernel_specification.clone())
+            .status_color(match &self.kernel {
+                Kernel::RunningKernel(kernel) if kernel.is_reconnecting() => Color::Modified,
+                Kernel::RunningKernel(kernel) => match kernel.execution_state() {
+                    ExecutionState::Idle => Color::Success,
+                    ExecutionState::Busy => Color::Modified,
+                    ExecutionState::Unknown => Color::Modified,
+                    ExecutionState::Starting => Color::Modified,
+                    ExecutionState::Restarting => Color::Modified,
+                    ExecutionState::Terminating => Color::Disabled,
+                    ExecutionState::AutoRestarting => Color::Modified,
+                    ExecutionState::Dead => Color::Disabled,
+                    ExecutionState::Other(_) => Color::Modified,
+                },
+                Kernel::StartingKernel(_) => Color::Modified,
+                Kernel::ErroredLaunch(..) => Color::Error,
+                Kernel::ShuttingDown => Color::Modified,
+                Kernel::Shutdown => Color::Disabled,
+                Kernel::Culled => Color::Disabled,
+                Kernel::Restarting => Color::Modified,
+            })
+            .child(Label::new(self.name.clone()))
+            .when(self.name != self.kernel_specification.name(), |this| {
+                this.child(Label::new(self.kernel_specification.name()).color(Color::Muted))
+            })
+            .child(
+                Label::new(attached_buffers)
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            )
+            .children(status_text.map(|status_text| Label::new(format!("({status_text})"))))
+            .children(startup_timing)
+            .children(resource_usage)
+            .children(resource_limits)
+            .children(
+                self.interrupt_error
+                    .clone()
+                    .map(|error| Label::new(error).size(LabelSize::Small).color(Color::Error)),
+            )
+            .button(
+                Button::new("shutdown", "Shutdown")
+                    .style(ButtonStyle::Subtle)
+                    .disabled(self.kernel.is_shutting_down())
+                    .on_click(cx.listener(move |session, _, window, cx| {
+                        session.shutdown(window, cx);
+                    })),
+            )
+            .buttons(interrupt_button)
+            .buttons(raise_memory_limit_button)
+            .buttons(keep_alive_button)
+            .buttons(ownership_button);
+
+        v_flex().child(kernel_list_item).children(queued_executions)
+    }
+}
+
+impl KernelSession for Session {
+    fn handle_message(&mut self, message: &JupyterMessage, cx: &mut Context<Self>) {
+        match &message.content {
+            JupyterMessageContent::CommOpen(open) => {
+                self.open_comms.insert(open.comm_id.0.clone());
+            }
+            JupyterMessageContent::CommClose(close) => {
+                if self.open_comms.remove(&close.comm_id.0) {
+                    self.record_activity(cx);
+                }
+            }
+            // Debug events aren't always children of the request that caused
+            // them.
+            JupyterMessageContent::DebugEvent(event) => {
+                cx.emit(SessionEvent::DebugEvent(event.content.clone()));
+                return;
+            }
+            _ => {}
+        }
+
+        let parent_message_id = match message.parent_header.as_ref() {
+            Some(header) => &header.msg_id,
+            None => return,
+        };
+
+        if let Some(has_ended_once) = self.aborted_executions.get_mut(parent_message_id) {
+            log::debug!(
+                "dropping {} for aborted execution {parent_message_id}",
+                message.header.msg_type
+            );
+            let is_end = match &message.content {
+                JupyterMessageContent::ExecuteReply(_) => true,
+                JupyterMessageContent::Status(status) => {
+                    matches!(status.execution_state, ExecutionState::Idle)
+                }
+                _ => false,
+            };
+            if is_end {
+                if *has_ended_once {
+                    self.aborted_executions.remove(parent_message_id);
+                } else {
+                    *has_ended_once = true;
+                }
+            }
+            return;
+        }
+
+        match update_silent_execution(&mut self.runtime_probe, message) {
+            SilentExecutionUpdate::Unrelated => {}
+            SilentExecutionUpdate::Pending => return,
+            SilentExecutionUpdate::Finished(stdout) => {
+                let variables = allowed_environment_variables(cx);
+                self.probed_runtime =
+                    Some(parse_runtime_probe_output(&stdout, &variables).unwrap_or_default());
+                cx.notify();
+                return;
+            }
+        }
+
+        match update_silent_execution(&mut self.working_directory_change, message) {
+            SilentExecutionUpdate::Unrelated => {}
+            SilentExecutionUpdate::Pending => return,
+            SilentExecutionUpdate::Finished(stdout) => {
+                self.finish_working_directory_change(&stdout, cx);
+                self.finish_restart_if_ready(cx);
+                return;
+            }
+        }
+
+        match update_silent_execution(&mut self.display_setup, message) {
+            SilentExecutionUpdate::Unrelated => {}
+            SilentExecutionUpdate::Pending => return,
+            SilentExecutionUpdate::Finished(_) => {
+                // Kernels without a plotting library fail this, which is
+                // expected, so it's only worth a quiet note.
+                if let JupyterMessageContent::ExecuteReply(reply) = &message.content
+                    && !matches!(reply.status, ReplyStatus::Ok)
+                {
+                    log::info!(
+                        "display setup code failed in kernel {}",
+                        self.kernel_specification.name()
+                    );
+                }
+                self.finish_restart_if_ready(cx);
+                return;
+            }
+        }
+
+        match update_silent_execution(&mut self.variables_query, message) {
+            SilentExecutionUpdate::Unrelated => {}
+            SilentExecutionUpdate::Pending => return,
+            SilentExecutionUpdate::Finished(stdout) => {
+                self.finish_variables_query(&stdout, cx);
+                return;
+            }
+        }
+
+        self.watch_for_missing_idle(message, cx);
+        self.update_sent_execution(message, cx);
+        self.execution_records.handle_message(message, Utc::now());
+        self.log_output(message);
+        if self.execution_waiters.contains_key(parent_message_id) {
+            // Executions shown in the editor get this from `handle_block_message`.
+            if let JupyterMessageContent::ExecuteReply(reply) = &message.content {
+                if matches!(reply.status, ReplyStatus::Ok) {
+                    self.schedule_variables_refresh(cx);
+                }
+                self.record_activity(cx);
+            }
+            self.settle_execution_waiters();
+        }
+
+        match &message.content {
+            JupyterMessageContent::Status(status) => {
+                self.kernel.set_execution_state(&status.execution_state);
+
+                telemetry::event!(
+                    "Kernel Status Changed",
+                    kernel_language = self.kernel_specification.language(),
+                    kernel_status = KernelStatus::from(&self.kernel).to_string(),
+                    repl_session_id = cx.entity_id().to_string(),
+                );
+
+                cx.notify();
+            }
+            JupyterMessageContent::KernelInfoReply(reply) => {
+                let is_first_kernel_info = self.kernel.kernel_info().is_none();
+                self.kernel.set_kernel_info(reply);
+                self.protocol_version =
+                    Some(ProtocolVersion::from_kernel_info(&reply.protocol_version));
+                if is_first_kernel_info
+                    && reply.debugger
+                    && self.supports(ProtocolFeature::DebugRequests)
+                {
+                    self.request_debug_info(cx);
+                }
+                if let Some(requested_at) = self.kernel_info_requested_at.take()
+                    && let Some(timing) = &mut self.startup_timing
+                {
+                    timing.first_kernel_info = Some(cx.background_executor().now() - requested_at);
+                    log::info!(
+                        "kernel {} started in {:?}: {:?}",
+                        self.kernel_specification.name(),
+                        timing.total(),
+                        timing.phases()
+                    );
+                }
+                if let Some(store) = ReplStore::try_global(cx) {
+                    store.update(cx, |store, cx| {
+                        store.record_kernel_info(&self.kernel_specification, reply, cx);
+                    });
+                }
+                self.finish_restart_if_ready(cx);
+                cx.notify();
+            }
+            JupyterMessageContent::InterruptReply(reply) => {
+                if self
+                    .pending_interrupt
+                    .as_ref()
+                    .is_some_and(|(msg_id, _)| msg_id == parent_message_id)
+                {
+                    self.pending_interrupt = None;
+                    if !matches!(reply.status, ReplyStatus::Ok) {
+                        self.interrupt_error = Some("The kernel couldn't be interrupted".into());
+                    }
+                    cx.notify();
+                }
+                return;
+            }
+            JupyterMessageContent::ShutdownReply(_) => {
+                if let Some(acknowledged_tx) = self.shutdown_acknowledged_tx.take() {
+                    acknowledged_tx.send(()).ok();
+                }
+                return;
+            }
+            JupyterMessageContent::IsCompleteReply(reply) => {
+                if let Some(reply_tx) = self.pending_is_complete.remove(parent_message_id) {
+                    reply_tx.send(reply.status.clone()).ok();
+                }
+                return;
+            }
+            JupyterMessageContent::DebugReply(reply) => {
+                if let Some(reply_tx) = self.pending_debug_requests.remove(parent_message_id) {
+                    reply_tx.send(reply.content.clone()).ok();
+                }
+                return;
+            }
+            JupyterMessageContent::UpdateDisplayData(update) => {
+                // Display updates can target outputs in any block.
+                if update.transient.display_id.is_some() && !self.blocks.is_empty() {
+                    self.queue_for_presentation(message.clone());
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        if self.blocks.contains_key(parent_message_id) {
+            self.handle_block_message(message, cx);
+            self.queue_for_presentation(message.clone());
+        } else if is_execution_message(message) {
+            // The kernel can answer before the execution's block is registered.
+            let now = cx.background_executor().now();
+            self.unrouted_messages.retain(|(received_at, _)| {
+                now.duration_since(*received_at) < UNROUTED_MESSAGE_GRACE_PERIOD
+            });
+            self.unrouted_messages.push((now, message.clone()));
+        }
+    }
+
+    fn queue_for_presentation(&mut self, message: JupyterMessage) {
+        if self.unpresented_messages.len() >= MAX_UNPRESENTED_MESSAGES {
+            self.unpresented_messages.pop_front();
+        }
+        self.unpresented_messages.push_back(message);
+    }
+
+    fn present_messages(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.presentation_window = Some(window.window_handle());
+        for message in std::mem::take(&mut self.unpresented_messages) {
+            if let JupyterMessageContent::UpdateDisplayData(update) = &message.content {
+                let Some(display_id) = &update.transient.display_id else {
+                    continue;
+                };
+                for block in self.blocks.values() {
+                    block.execution_view.update(cx, |execution_view, cx| {
+                        execution_view.update_display_data(&update.data, display_id, window, cx);
+                    });
+                }
+            } else if let Some(block) = self.block_for_message(&message) {
+                block.handle_message(&message, window, cx);
+            }
+        }
+    }
+
+    fn kernel_errored(&mut self, error_message: String, cx: &mut Context<Self>) {
+        self.kernel_errored(error_message, cx);
+    }
+
+    fn kernel_exceeded_memory_limit(
+        &mut self,
+        limit: u64,
+        error_message: String,
+        cx: &mut Context<Self>,
+    ) {
+        self.kernel_errored(error_message, cx);
+        self.exceeded_memory_limit = Some(limit);
+    }
+}
+
+/// A duration such as "2.4s", for startup timings.
+fn format_seconds(duration: Duration) -> String {
+    format!("{:.1}s", duration.as_secs_f64())
+}
+
+/// Whether `message` is part of an execution's output, as opposed to a reply to
+/// some other request.
+fn is_execution_message(message: &JupyterMessage) -> bool {
+    let is_execution_child = message
+        .parent_header
+        .as_ref()
+        .is_some_and(|header| header.msg_type == "execute_request");
+    is_execution_child
+        && matches!(
+            message.content,
+            JupyterMessageContent::Status(_)
+                | JupyterMessageContent::StreamContent(_)
+                | JupyterMessageContent::ExecuteResult(_)
+                | JupyterMessageContent::DisplayData(_)
+                | JupyterMessageContent::ErrorOutput(_)
+                | JupyterMessageContent::ClearOutput(_)
+                | JupyterMessageContent::InputRequest(_)
+                | JupyterMessageContent::ExecuteReply(_)
+        )
+}
+
+/// The allowlisted environment variable names that are safe to splice into
+/// a probe snippet.
+fn allowed_environment_variables(cx: &App) -> Vec<String> {
+    ReplSettings::get_global(cx)
+        .runtime_info_environment_variables
+        .iter()
+        .filter(|name| is_environment_variable_name(name))
+        .cloned()
+        .collect()
+}
+
+fn is_environment_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first == '_' || first.is_ascii_alphabetic())
+        && chars.all(|character| character == '_' || character.is_ascii_alphanumeric())
+}
+
+/// A snippet that prints the interpreter path and the given environment
+/// variables as a single marked line of JSON, or `None` if the kernel's
+/// language isn't supported. This covers the same languages as
+/// `working_directory_code`; other kernels report no interpreter or
+/// environment.
+fn runtime_probe_code(language: &str, variables: &[String]) -> Option<String> {
+    // The names are checked by `is_environment_variable_name`, so a JSON array
+    // of them is also a valid Python, TypeScript and Julia array literal.
+    let names = serde_json::to_string(variables).ok()?;
+    match language.to_lowercase().as_str() {
+        "python" => Some(format!(
+            "import json as _zed_json, os as _zed_os, sys as _zed_sys\n\
+             print({RUNTIME_PROBE_MARKER:?} + _zed_json.dumps({{\
+             \"interpreter\": _zed_sys.executable, \
+             \"environment\": {{_zed_name: _zed_os.environ.get(_zed_name) for _zed_name in {names}}}}}))\n\
+             del _zed_json, _zed_os, _zed_sys"
+        )),
+        "typescript" | "javascript" => Some(format!(
+            "console.log({RUNTIME_PROBE_MARKER:?} + JSON.stringify({{\
+             interpreter: Deno.execPath(), \
+             environment: Object.fromEntries({names}.map((name) => [name, Deno.env.get(name) ?? null])),\
+             }}));"
+        )),
+        // `encodeString` and `escape_string` escape quotes and backslashes the
+        // way JSON does.
+        "r" => Some(format!(
+            "invisible(local({{\n\
+             json <- function(values) ifelse(is.na(values), \"null\", encodeString(values, quote = \"\\\"\"))\n\
+             names <- as.character(c({}))\n\
+             values <- Sys.getenv(names, unset = NA, names = FALSE)\n\
+             entries <- if (length(names)) paste0(json(names), \":\", json(values), collapse = \",\") else \"\"\n\
+             cat({RUNTIME_PROBE_MARKER:?}, \"{{\\\"interpreter\\\":\", json(file.path(R.home(\"bin\"), \"R\")), \
+             \",\\\"environment\\\":{{\", entries, \"}}}}\\n\", sep = \"\")\n\
+             }}))",
+            names.trim_start_matches('[').trim_end_matches(']')
+        )),
+        "julia" => Some(format!(
+            "let\n\
+             json(value) = value === nothing ? \"null\" : \"\\\"\" * escape_string(value) * \"\\\"\"\n\
+             entries = [json(name) * \":\" * json(get(ENV, name, nothing)) for name in {names}]\n\
+             println({RUNTIME_PROBE_MARKER:?}, \"{{\\\"interpreter\\\":\", json(joinpath(Sys.BINDIR, Base.julia_exename())), \
+             \",\\\"environment\\\":{{\", join(entries, \",\"), \"}}}}\")\n\
+             end"
+        )),
+        _ => None,
+    }
+}
+
+/// Maps a local directory to its path on a Jupyter server, using the most
+/// specific local root that contains it.
+fn map_to_remote_root(roots: &HashMap<PathBuf, String>, local: &Path) -> Option<String> {
+    let (_, remote_root, relative) = roots
+        .iter()
+        .filter_map(|(local_root, remote_root)| {
+            let relative = local.strip_prefix(local_root).ok()?;
+            Some((local_root.components().count(), remote_root, relative))
+        })
+        .max_by_key(|(depth, _, _)| *depth)?;
+
+    // Jupyter servers are nearly always Unix-like, so the relative part is
+    // joined with forward slashes.
+    let mut remote = remote_root.trim_end_matches('/').to_string();
+    for component in relative.components() {
+        remote.push('/');
+        remote.push_str(&component.as_os_str().to_string_lossy());
+    }
+    if remote.is_empty() {
+        remote.push('/');
+    }
+    Some(remote)
+}
+
+/// A snippet that changes the kernel's working directory to `path` and prints
+/// the new directory as a marked line, or `None` if the kernel's language
+/// isn't supported.
+fn working_directory_code(language: &str, path: &str) -> Option<String> {
+    // A JSON string is also a valid string literal in each of these languages,
+    // apart from `$` starting an interpolation in Julia.
+    let path = serde_json::to_string(path).ok()?;
+    let marker = serde_json::to_string(WORKING_DIRECTORY_MARKER).ok()?;
+    match language.to_lowercase().as_str() {
+        "python" => Some(format!(
+            "import os as _zed_os\n\
+             _zed_os.chdir({path})\n\
+             print({marker} + _zed_os.getcwd())\n\
+             del _zed_os"
+        )),
+        "r" => Some(format!(
+            "setwd({path})\ncat({marker}, getwd(), \"\\n\", sep = \"\")"
+        )),
+        "julia" => Some(format!(
+            "cd({})\nprintln({marker}, pwd())",
+            path.replace('$', "\\$")
+        )),
+        "typescript" | "javascript" => Some(format!(
+            "Deno.chdir({path});\nconsole.log({marker} + Deno.cwd());"
+        )),
+        _ => None,
+    }
+}
+
+/// Reads the probe's marked line, keeping only allowlisted variables even if
+/// the kernel reported more.
+fn parse_runtime_probe_output(output: &str, variables: &[String]) -> Option<ProbedRuntime> {
+    #[derive(serde::Deserialize)]
+    struct ProbeOutput {
+        interpreter: Option<String>,
+        #[serde(default)]
+        environment: BTreeMap<String, Option<String>>,
+    }
+
+    let json = output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix(RUNTIME_PROBE_MARKER))?;
+    let probe_output: ProbeOutput = serde_json::from_str(json).log_err()?;
+    Some(ProbedRuntime {
+        interpreter: probe_output.interpreter.filter(|path| !path.is_empty()),
+        environment: probe_output
+            .environment
+            .into_iter()
+            .filter(|(name, _)| variables.contains(name))
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution_record::{ExecutionRecordStatus, RecordedOutput};
+    use crate::kernels::{
+        FakeKernel, FakeKernelBehavior, FakeKernelMessages, FakeKernelStep,
+        LocalKernelSpecification, RunningKernel,
+    };
+    use crate::outputs::Output;
+    use crate::repl_store::QUIT_SHUTDOWN_TIMEOUT;
+    use crate::variables::VARIABLES_MARKER;
+    use futures::channel::mpsc;
+    use gpui::{TestAppContext, VisualTestContext};
+    use jupyter_protocol::JupyterKernelspec;
+    use project::{FakeFs, Project};
+    use runtimelib::{
+        DebugEvent, DebugReply, ErrorOutput, ExecuteReply, KernelInfoReply, MimeType, Status,
+        StreamContent,
+    };
+    use serde_json::json;
+    use settings::{KernelResourceLimitsContent, SettingsStore};
+    use std::{
+        cell::{Cell, RefCell},
+        path::PathBuf,
+        rc::Rc,
+        sync::atomic::{AtomicBool, Ordering},
+    };
+    use util::{path, rel_path::rel_path};
+    use workspace::{AppState, MultiWorkspace};
+
+    fn test_kernel_specification() -> KernelSpecification {
+        KernelSpecification::Jupyter(LocalKernelSpecification {
+            name: "python3".to_string(),
+            path: PathBuf::from("python3"),
+            kernelspec: JupyterKernelspec {
+                argv: vec!["python3".to_string()],
+                display_name: "Python 3".to_string(),
+                language: "python".to_string(),
+                interrupt_mode: None,
+                metadata: None,
+                env: None,
+            },
+        })
+    }
+
+    async fn init_session(
+        cx: &mut TestAppContext,
+    ) -> (
+        Entity<Editor>,
+        Entity<Session>,
+        mpsc::Receiver<JupyterMessage>,
+        VisualTestContext,
+    ) {
+        cx.update(|cx| {
+            AppState::test(cx);
+            editor::init(cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/project"),
+            json!({ "main.py": "print(1)\nprint(2)\nprint(3)\nprint(4)\n" }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), [path!("/project").as_ref()], cx).await;
+        let window =
+            cx.add_window(|window, cx| MultiWorkspace::test_new(project.clone(), window, cx));
+        let workspace = window
+            .read_with(cx, |mw, _| mw.workspace().clone())
+            .unwrap();
+        let mut cx = VisualTestContext::from_window(window.into(), cx);
+
+        let worktree_id = project.read_with(&cx, |project, cx| {
+            project.worktrees(cx).next().unwrap().read(cx).id()
+        });
+        let editor = workspace
+            .update_in(&mut cx, |workspace, window, cx| {
+                workspace.open_path((worktree_id, rel_path("main.py")), None, true, window, cx)
+            })
+            .await
+            .unwrap()
+            .downcast::<Editor>()
+            .unwrap();
+
+        let (kernel, request_rx) = FakeKernel::unscripted(PathBuf::from(path!("/project")));
+
+        let weak_editor = editor.downgrade();
+        let session = cx.update(|_, cx| {
+            cx.new(|_| Session {
+                fs,
+                name: "Python 3 (1)".into(),
+                editors: vec![AttachedEditor {
+                    editor: weak_editor,
+                    _subscriptions: Vec::new(),
+                }],
+                kernel: Kernel::RunningKernel(Box::new(kernel)),
+                kernel_specification: test_kernel_specification(),
+                blocks: HashMap::default(),
+                unrouted_messages: Vec::new(),
+                unpresented_messages: VecDeque::new(),
+                presentation_window: None,
+                result_inlays: HashMap::default(),
+                next_inlay_id: 0,
+                aborted_executions: HashMap::default(),
+                execution_queue: VecDeque::new(),
+                sent_execution: None,
+                cell_run: None,
+                missing_idle_task: None,
+                execution_records: ExecutionRecords::default(),
+                output_log: None,
+                pending_is_complete: HashMap::default(),
+                execution_waiters: HashMap::default(),
+                runtime_probe: None,
+                probed_runtime: None,
+                variables: Vec::new(),
+                variables_query: None,
+                remote_working_directory: None,
+                working_directory_change: None,
+                display_setup: None,
+                variables_stale: false,
+                refresh_variables_task: None,
+                shutdown_acknowledged_tx: None,
+                last_activity: cx.background_executor().now(),
+                open_comms: HashSet::default(),
+                keep_alive: false,
+                idle_shutdown_task: None,
+                startup_timing: None,
+                kernel_info_requested_at: None,
+                resource_samples: VecDeque::new(),
+                resource_sampling_task: None,
+                pending_interrupt: None,
+                interrupt_error: None,
+                pending_debug_requests: HashMap::default(),
+                debug_info: None,
+                protocol_version: None,
+                launch_directory: Some(PathBuf::from(path!("/project"))),
+                memory_limit_override: None,
+                exceeded_memory_limit: None,
+                pending_restart: None,
+                kernel_launcher: Rc::new(SpecifiedKernelLauncher),
+                _subscriptions: Vec::new(),
+            })
+        });
+
+        (editor, session, request_rx, cx)
+    }
+
+    /// Replaces the session's kernel with a [`FakeKernel`] playing `behavior`.
+    fn start_fake_kernel(
+        session: &Entity<Session>,
+        behavior: FakeKernelBehavior,
+        cx: &mut VisualTestContext,
+    ) -> FakeKernelMessages {
+        session.update_in(cx, |session, window, cx| {
+            let entity = cx.entity();
+            let kernel = FakeKernel::new(
+                &entity,
+                behavior,
+                PathBuf::from(path!("/project")),
+                window,
+                cx,
+            );
+            let received = kernel.received();
+            session.kernel = Kernel::RunningKernel(Box::new(kernel));
+            received
+        })
+    }
+
+    fn execute_line(
+        editor: &Entity<Editor>,
+        session: &Entity<Session>,
+        row: u32,
+        cx: &mut VisualTestContext,
+    ) {
+        let anchor_range = editor.update(cx, |editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            snapshot.anchor_before(Point::new(row, 0))..snapshot.anchor_after(Point::new(row, 8))
+        });
+        session.update_in(cx, |session, window, cx| {
+            session.execute_in(
+                editor.downgrade(),
+                format!("print({})", row + 1),
+                anchor_range,
+                None,
+                false,
+                ExecutionOptions::default(),
+                window,
+                cx,
+            );
+        });
+    }
+
+    fn open_second_editor(editor: &Entity<Editor>, cx: &mut VisualTestContext) -> Entity<Editor> {
+        let workspace = editor.read_with(cx, |editor, _| editor.workspace().unwrap());
+        workspace.update_in(cx, |workspace, window, cx| {
+            let project = workspace.project().clone();
+            let buffer = cx.new(|cx| language::Buffer::local("print(5)\nprint(6)\n", cx));
+            let second = cx.new(|cx| Editor::for_buffer(buffer, Some(project), window, cx));
+            workspace.add_item_to_active_pane(Box::new(second.clone()), None, true, window, cx);
+            second
+        })
+    }
+
+    fn stream(text: &str) -> FakeKernelStep {
+        FakeKernelStep::Reply(JupyterMessageContent::StreamContent(StreamContent {
+            name: Stdio::Stdout,
+            text: text.to_string(),
+        }))
+    }
+
+    fn kernel_status(session: &Entity<Session>, cx: &VisualTestContext) -> String {
+        session.read_with(cx, |session, _| session.kernel.status().to_string())
+    }
+
+    /// Makes restarts launch a [`FakeKernel`] playing `behavior`, and returns
+    /// the messages each launched kernel received. Launches fail while
+    /// `binary_deleted` is set, like a kernel whose interpreter was removed.
+    fn launch_fake_kernels(
+        session: &Entity<Session>,
+        behavior: FakeKernelBehavior,
+        binary_deleted: Rc<Cell<bool>>,
+        cx: &mut VisualTestContext,
+    ) -> Rc<RefCell<Vec<FakeKernelMessages>>> {
+        let launched = Rc::new(RefCell::new(Vec::new()));
+        let launcher = FakeKernelLauncher {
+            behavior,
+            binary_deleted,
+            launched: launched.clone(),
+        };
+        session.update(cx, |session, _| {
+            session.kernel_launcher = Rc::new(launcher);
+        });
+        launched
+    }
+
+    /// Launches a [`FakeKernel`] in place of the one the session's
+    /// specification describes.
+    struct FakeKernelLauncher {
+        behavior: FakeKernelBehavior,
+        binary_deleted: Rc<Cell<bool>>,
+        launched: Rc<RefCell<Vec<FakeKernelMessages>>>,
+    }
+
+    impl KernelLauncher for FakeKernelLauncher {
+        fn launch(
+            &self,
+            launch: KernelLaunch,
+            window: &mut Window,
+            cx: &mut Context<Session>,
+        ) -> Task<anyhow::Result<Box<dyn RunningKernel>>> {
+            if self.binary_deleted.get() {
+                return Task::ready(Err(anyhow::anyhow!(
+                    "failed to start kernel: python3: No such file or directory"
+                )));
+            }
+            let kernel = FakeKernel::new(
+                &cx.entity(),
+                self.behavior.clone(),
+                launch.working_directory,
+                window,
+                cx,
+            )
+            .with_resource_limits(launch.resource_limits);
+            self.launched.borrow_mut().push(kernel.received());
+            Task::ready(Ok(Box::new(kernel) as Box<dyn RunningKernel>))
+        }
+    }
+
+    fn restart_events(session: &Entity<Session>, cx: &mut VisualTestContext) -> Rc<Cell<usize>> {
+        let completed = Rc::new(Cell::new(0));
+        cx.update(|_, cx| {
+            let completed = completed.clone();
+            cx.subscribe(session, move |_, event: &SessionEvent, _| {
+                if let SessionEvent::RestartCompleted = event {
+                    completed.set(completed.get() + 1);
+                }
+            })
+            .detach();
+        });
+        completed
+    }
+
+    #[gpui::test]
+    async fn test_restart_with_higher_memory_limit(cx: &mut TestAppContext) {
+        const LIMIT: u64 = 512 * 1024 * 1024;
+
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        cx.update(|_, cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings
+                        .repl
+                        .get_or_insert_default()
+                        .project
+                        .kernel_resource_limits = Some(KernelResourceLimitsContent {
+                        memory_mb: Some(512),
+                        ..KernelResourceLimitsContent::default()
+                    });
+                });
+            });
+        });
+        let launched = launch_fake_kernels(
+            &session,
+            FakeKernelBehavior::replying_with([FakeKernelStep::ExceedMemoryLimit(LIMIT)]),
+            Rc::new(Cell::new(false)),
+            &mut cx,
+        );
+        session.update_in(&mut cx, |session, window, cx| {
+            session.start_kernel(window, cx);
+        });
+        cx.run_until_parked();
+        session.read_with(&cx, |session, _| {
+            assert_eq!(session.resource_limits().unwrap().memory, Some(LIMIT));
+            assert_eq!(session.exceeded_memory_limit(), None);
+        });
+
+        // The kernel dying of its memory limit is told apart from other deaths.
+        execute_line(&editor, &session, 0, &mut cx);
+        cx.run_until_parked();
+        session.read_with(&cx, |session, _| {
+            assert!(matches!(session.kernel, Kernel::ErroredLaunch(..)));
+            assert_eq!(session.exceeded_memory_limit(), Some(LIMIT));
+        });
+
+        // Restarting from there doubles the limit the new kernel gets.
+        session.update_in(&mut cx, |session, window, cx| {
+            session.restart_with_higher_memory_limit(window, cx);
+        });
+        cx.run_until_parked();
+        assert_eq!(launched.borrow().len(), 2);
+        session.read_with(&cx, |session, _| {
+            assert!(matches!(session.kernel, Kernel::RunningKernel(_)));
+            assert_eq!(session.resource_limits().unwrap().memory, Some(2 * LIMIT));
+            assert_eq!(session.exceeded_memory_limit(), None);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_restart_kernel(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let first_kernel = start_fake_kernel(
+            &session,
+            FakeKernelBehavior::replying_with([FakeKernelStep::Delay(Duration::from_secs(10))]),
+            &mut cx,
+        );
+        let launched = launch_fake_kernels(
+            &session,
+            FakeKernelBehavior::default(),
+            Rc::new(Cell::new(false)),
+            &mut cx,
+        );
+        let completed = restart_events(&session, &mut cx);
+
+        execute_line(&editor, &session, 0, &mut cx);
+        cx.run_until_parked();
+        let in_flight = execution_views(&session, &first_kernel, &cx);
+        assert_eq!(in_flight.len(), 1);
+
+        // A second restart while the first is underway waits on it.
+        let (first, second) = session.update_in(&mut cx, |session, window, cx| {
+            let first = session.restart_kernel(window, cx);
+            let second = session.restart_kernel(window, cx);
+            assert!(matches!(session.kernel, Kernel::Restarting));
+            (first, second)
+        });
+        cx.update(|_, cx| {
+            assert!(matches!(
+                in_flight[0].read(cx).status,
+                ExecutionStatus::Aborted
+            ));
+        });
+
+        cx.run_until_parked();
+        first.await.unwrap();
+        second.await.unwrap();
+        assert_eq!(completed.get(), 1);
+        // The old kernel was asked to shut down for a restart.
+        assert!(first_kernel.all().iter().any(|message| matches!(
+            &message.content,
+            JupyterMessageContent::ShutdownRequest(request) if request.restart
+        )));
+
+        // The new kernel was asked for its info, then ran the display setup.
+        assert_eq!(launched.borrow().len(), 1);
+        let received = launched.borrow()[0].all();
+        assert!(matches!(
+            received.first().map(|message| &message.content),
+            Some(JupyterMessageContent::KernelInfoRequest(_))
+        ));
+        assert!(matches!(
+            received.get(1).map(|message| &message.content),
+            Some(JupyterMessageContent::ExecuteRequest(request))
+                if request.silent && request.code.contains("matplotlib")
+        ));
+
+        session.read_with(&cx, |session, _| {
+            assert!(matches!(session.kernel, Kernel::RunningKernel(_)));
+            assert!(session.kernel.kernel_info().is_some());
+            // The round trip is timed on the executor's clock, which hasn't
+            // moved since the request was sent.
+            assert_eq!(
+                session
+                    .startup_timing()
+                    .and_then(|timing| timing.first_kernel_info),
+                Some(Duration::ZERO)
+            );
+            assert!(session.pending_restart.is_none());
+            assert_eq!(
+                session
+                    .execution_records
+                    .iter()
+                    .last()
+                    .map(|record| record.restarts),
+                Some(0)
+            );
+            assert_eq!(session.name(), "Python 3 (1)");
+            assert!(session.is_attached(editor.entity_id()));
+        });
+
+        // Executions after the restart are counted from the new kernel.
+        execute_line(&editor, &session, 1, &mut cx);
+        cx.run_until_parked();
+        session.read_with(&cx, |session, _| {
+            assert_eq!(
+                session
+                    .execution_records
+                    .iter()
+                    .last()
+                    .map(|record| record.restarts),
+                Some(1)
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_restart_waits_for_shutdown_acknowledgement(cx: &mut TestAppContext) {
+        let (_editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let behavior = FakeKernelBehavior {
+            ignore_shutdowns: true,
+            ..FakeKernelBehavior::default()
+        };
+        start_fake_kernel(&session, behavior, &mut cx);
+        let launched = launch_fake_kernels(
+            &session,
+            FakeKernelBehavior::default(),
+            Rc::new(Cell::new(false)),
+            &mut cx,
+        );
+
+        let restart = session.update_in(&mut cx, |session, window, cx| {
+            session.restart_kernel(window, cx)
+        });
+        cx.run_until_parked();
+        // The old kernel hasn't acknowledged its shutdown, so it's given
+        // the grace period before the new one is launched.
+        assert!(launched.borrow().is_empty());
+        session.read_with(&cx, |session, _| {
+            assert!(matches!(session.kernel, Kernel::Restarting));
+        });
+
+        cx.executor()
+            .advance_clock(RESTART_SHUTDOWN_GRACE_PERIOD - Duration::from_millis(1));
+        cx.run_until_parked();
+        assert!(launched.borrow().is_empty());
+
+        cx.executor().advance_clock(Duration::from_millis(1));
+        cx.run_until_parked();
+        restart.await.unwrap();
+        assert_eq!(launched.borrow().len(), 1);
+    }
+
+    #[gpui::test]
+    async fn test_restart_kernel_relaunch_fails(cx: &mut TestAppContext) {
+        let (_editor, session, _request_rx, mut cx) = init_session(cx).await;
+        start_fake_kernel(&session, FakeKernelBehavior::default(), &mut cx);
+        let binary_deleted = Rc::new(Cell::new(true));
+        let launched = launch_fake_kernels(
+            &session,
+            FakeKernelBehavior::default(),
+            binary_deleted.clone(),
+            &mut cx,
+        );
+        let completed = restart_events(&session, &mut cx);
+
+        let restart = session.update_in(&mut cx, |session, window, cx| {
+            session.restart_kernel(window, cx)
+        });
+        cx.run_until_parked();
+        let error = restart.await.unwrap_err();
+        assert!(error.to_string().contains("No such file or directory"));
+        assert_eq!(completed.get(), 0);
+        session.read_with(&cx, |session, _| {
+            assert!(matches!(session.kernel, Kernel::ErroredLaunch(..)));
+            assert!(session.pending_restart.is_none());
+        });
+
+        // Once the kernel can be launched again, restarting recovers it.
+        binary_deleted.set(false);
+        let restart = session.update_in(&mut cx, |session, window, cx| {
+            session.restart_kernel(window, cx)
+        });
+        cx.run_until_parked();
+        restart.await.unwrap();
+        assert_eq!(completed.get(), 1);
+        assert_eq!(launched.borrow().len(), 1);
+        session.read_with(&cx, |session, _| {
+            assert!(matches!(session.kernel, Kernel::RunningKernel(_)));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_shared_session_outputs_follow_their_editor(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior::replying_with([stream("printed\n")]),
+            &mut cx,
+        );
+        let second = open_second_editor(&editor, &mut cx);
+        session.update(&mut cx, |session, cx| {
+            session.attach_editor(second.downgrade(), cx);
+        });
+
+        execute_line(&editor, &session, 0, &mut cx);
+        execute_line(&second, &session, 0, &mut cx);
+        cx.run_until_parked();
+
+        let editor_ids = session.read_with(&cx, |session, _| {
+            assert_eq!(session.attached_editors().count(), 2);
+            received
+                .take()
+                .into_iter()
+                .filter(|message| {
+                    matches!(
+                        &message.content,
+                        JupyterMessageContent::ExecuteRequest(request) if !request.silent
+                    )
+                })
+                .map(|request| session.blocks[&request.header.msg_id].editor.entity_id())
+                .collect::<Vec<_>>()
+        });
+        // Both buffers ran in the one kernel, each output in its own editor.
+        assert_eq!(editor_ids, vec![editor.entity_id(), second.entity_id()]);
+
+        session.update(&mut cx, |session, cx| {
+            session.detach_editor(second.entity_id(), cx);
+        });
+        session.read_with(&cx, |session, _| {
+            assert!(!session.is_attached(second.entity_id()));
+            assert!(!session.is_headless());
+            assert!(
+                session
+                    .blocks
+                    .values()
+                    .all(|block| block.editor.entity_id() == editor.entity_id())
+            );
+        });
+
+        // Code from a detached editor no longer runs in the session.
+        execute_line(&second, &session, 1, &mut cx);
+        cx.run_until_parked();
+        assert!(execution_views(&session, &received, &cx).is_empty());
+
+        session.update(&mut cx, |session, cx| {
+            session.detach_editor(editor.entity_id(), cx);
+        });
+        session.read_with(&cx, |session, _| {
+            assert!(session.is_headless());
+            assert!(session.blocks.is_empty());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_session_names(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let store = cx.update(|_, cx| {
+            ReplStore::init(FakeFs::new(cx.background_executor().clone()), cx);
+            ReplStore::global(cx)
+        });
+        let spec = test_kernel_specification();
+
+        store.update(&mut cx, |store, cx| {
+            session.update(cx, |session, cx| session.set_name("python3 (1)".into(), cx));
+            store.insert_session(editor.entity_id(), session.clone());
+            // The first name is taken by the running session.
+            assert_eq!(store.default_session_name(&spec, cx), "python3 (2)");
+
+            assert!(store.rename_session(&session, "  ", cx).is_err());
+            store.rename_session(&session, " analysis ", cx).unwrap();
+            assert_eq!(store.default_session_name(&spec, cx), "python3 (1)");
+            // Renaming a session to its own name is fine.
+            store.rename_session(&session, "analysis", cx).unwrap();
+        });
+        session.read_with(&cx, |session, _| assert_eq!(session.name(), "analysis"));
+    }
+
+    #[gpui::test]
+    async fn test_restart_aborts_in_flight_executions(cx: &mut TestAppContext) {
+        let (editor, session, mut request_rx, mut cx) = init_session(cx).await;
+
+        let aborted = Arc::new(std::sync::Mutex::new(Vec::<String>::new()));
+        cx.update(|_, cx| {
+            let aborted = aborted.clone();
+            cx.subscribe(&session, move |_, event: &SessionEvent, _| {
+                if let SessionEvent::ExecutionAborted(msg_id) = event {
+                    aborted.lock().unwrap().push(msg_id.clone());
+                }
+            })
+            .detach();
+        });
+
+        for row in 0..3 {
+            let anchor_range = editor.update(&mut cx, |editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                snapshot.anchor_before(Point::new(row, 0))
+                    ..snapshot.anchor_after(Point::new(row, 8))
+            });
+            session.update_in(&mut cx, |session, window, cx| {
+                session.execute(
+                    format!("print({})", row + 1),
+                    anchor_range,
+                    None,
+                    false,
+                    ExecutionOptions::default(),
+                    window,
+                    cx,
+                );
+            });
+        }
+
+        // Only the first execution is sent, the others wait in the queue.
+        let mut sent = Vec::new();
+        while let Ok(Some(message)) = request_rx.try_next() {
+            sent.push(message);
+        }
+        assert_eq!(sent.len(), 1);
+        let mut expected = session.read_with(&cx, |session, cx| {
+            assert_eq!(session.pending_execution_count(cx), 3);
+            session
+                .queued_executions()
+                .into_iter()
+                .map(|execution| execution.msg_id)
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(expected.len(), 3);
+
+        session.update_in(&mut cx, |session, window, cx| {
+            session.restart(window, cx);
+        });
+
+        session.read_with(&cx, |session, cx| {
+            assert_eq!(session.pending_execution_count(cx), 0);
+            assert!(session.queued_executions().is_empty());
+            for msg_id in &expected {
+                let block = session.blocks.get(msg_id).unwrap();
+                assert!(matches!(
+                    block.execution_view.read(cx).status,
+                    ExecutionStatus::Aborted
+                ));
+            }
+        });
+
+        let mut aborted = aborted.lock().unwrap().clone();
+        aborted.sort();
+        expected.sort();
+        assert_eq!(aborted, expected);
+
+        // A late reply from the old kernel must not bring the execution back to life.
+        let late_message = Status {
+            execution_state: ExecutionState::Busy,
+        }
+        .as_child_of(&sent[0]);
+        session.update_in(&mut cx, |session, window, cx| {
+            session.route(&late_message, window, cx);
+        });
+        session.read_with(&cx, |session, cx| {
+            assert_eq!(session.pending_execution_count(cx), 0);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_idle_kernel_shutdown(cx: &mut TestAppContext) {
+        let (editor, session, mut request_rx, mut cx) = init_session(cx).await;
+        cx.update(|_, cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings
+                        .repl
+                        .get_or_insert_default()
+                        .idle_kernel_shutdown_minutes = Some(1);
+                });
+            });
+        });
+
+        let anchor_range = editor.update(&mut cx, |editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            snapshot.anchor_before(Point::new(0, 0))..snapshot.anchor_after(Point::new(0, 8))
+        });
+        session.update_in(&mut cx, |session, window, cx| {
+            session.execute(
+                "x = 1".to_string(),
+                anchor_range,
+                None,
+                false,
+                ExecutionOptions::default(),
+                window,
+                cx,
+            );
+        });
+        let execution = request_rx.try_next().unwrap().unwrap();
+
+        // A kernel with a pending execution is never shut down.
+        cx.executor().advance_clock(Duration::from_secs(90));
+        cx.run_until_parked();
+        session.read_with(&cx, |session, _| {
+            assert!(matches!(session.kernel, Kernel::RunningKernel(_)));
+        });
+
+        let reply: ExecuteReply =
+            serde_json::from_value(json!({ "status": "ok", "execution_count": 1 })).unwrap();
+        let idle = Status {
+            execution_state: ExecutionState::Idle,
+        };
+        session.update_in(&mut cx, |session, window, cx| {
+            session.route(&reply.as_child_of(&execution), window, cx);
+            session.route(&idle.as_child_of(&execution), window, cx);
+        });
+        while let Ok(Some(_)) = request_rx.try_next() {}
+
+        // Keeping the kernel alive overrides the timeout.
+        session.update(&mut cx, |session, cx| session.set_keep_alive(true, cx));
+        cx.executor().advance_clock(Duration::from_secs(90));
+        cx.run_until_parked();
+        session.read_with(&cx, |session, _| {
+            assert!(matches!(session.kernel, Kernel::RunningKernel(_)));
+        });
+
+        session.update(&mut cx, |session, cx| session.set_keep_alive(false, cx));
+        cx.executor().advance_clock(Duration::from_secs(60));
+        cx.run_until_parked();
+        session.read_with(&cx, |session, _| {
+            assert!(matches!(session.kernel, Kernel::Culled));
+        });
+        let mut sent = Vec::new();
+        while let Ok(Some(message)) = request_rx.try_next() {
+            sent.push(message);
+        }
+        assert!(sent.iter().any(|message| matches!(
+            &message.content,
+            JupyterMessageContent::ShutdownRequest(request) if !request.restart
+        )));
+    }
+
+    #[gpui::test]
+    async fn test_idle_shutdown_waits_for_execute_code(cx: &mut TestAppContext) {
+        let (_editor, session, _request_rx, mut cx) = init_session(cx).await;
+        cx.update(|_, cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings
+                        .repl
+                        .get_or_insert_default()
+                        .idle_kernel_shutdown_minutes = Some(1);
+                });
+            });
+        });
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior::replying_with([
+                FakeKernelStep::Delay(Duration::from_secs(90)),
+                stream("done\n"),
+            ]),
+            &mut cx,
+        );
+
+        let task = session.update(&mut cx, |session, cx| {
+            session.execute_code("long()".into(), ExecutionOptions::default(), cx)
+        });
+        cx.run_until_parked();
+        assert_eq!(sent_code(&received), ["long()"]);
+
+        // The execution has no block, but still keeps the kernel running.
+        cx.executor().advance_clock(Duration::from_secs(75));
+        cx.run_until_parked();
+        session.read_with(&cx, |session, _| {
+            assert!(matches!(session.kernel, Kernel::RunningKernel(_)));
+        });
+
+        cx.executor().advance_clock(Duration::from_secs(15));
+        cx.run_until_parked();
+        let record = task.await.unwrap();
+        assert_eq!(record.status, ExecutionRecordStatus::Ok);
+        session.read_with(&cx, |session, _| {
+            assert!(matches!(session.kernel, Kernel::RunningKernel(_)));
+        });
+
+        // The idle timeout counts from when the execution finished.
+        cx.executor().advance_clock(Duration::from_secs(60));
+        cx.run_until_parked();
+        session.read_with(&cx, |session, _| {
+            assert!(matches!(session.kernel, Kernel::Culled));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_idle_shutdown_detaches_from_kernels_zed_does_not_own(cx: &mut TestAppContext) {
+        let (_editor, session, mut request_rx, mut cx) = init_session(cx).await;
+        cx.update(|_, cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings
+                        .repl
+                        .get_or_insert_default()
+                        .idle_kernel_shutdown_minutes = Some(1);
+                });
+            });
+        });
+        session.update(&mut cx, |session, cx| {
+            if let Kernel::RunningKernel(kernel) = &mut session.kernel {
+                kernel.set_owned(false);
+            }
+            session.record_activity(cx);
+        });
+
+        cx.executor().advance_clock(Duration::from_secs(60));
+        cx.run_until_parked();
+        session.read_with(&cx, |session, _| {
+            assert!(matches!(session.kernel, Kernel::Culled));
+        });
+        while let Ok(Some(message)) = request_rx.try_next() {
+            assert!(!matches!(
+                message.content,
+                JupyterMessageContent::ShutdownRequest(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_runtime_probe_output_filters_to_allowlist() {
+        let variables = vec!["PATH".to_string(), "VIRTUAL_ENV".to_string()];
+        let output = format!(
+            "some earlier output\n{RUNTIME_PROBE_MARKER}{}\n",
+            json!({
+                "interpreter": "/project/.venv/bin/python",
+                "environment": {
+                    "PATH": "/project/.venv/bin:/usr/bin",
+                    "VIRTUAL_ENV": null,
+                    "API_TOKEN": "secret",
+                },
+            })
+        );
+
+        let probed = parse_runtime_probe_output(&output, &variables).unwrap();
+        assert_eq!(
+            probed.interpreter.as_deref(),
+            Some("/project/.venv/bin/python")
+        );
+        assert_eq!(
+            probed.environment,
+            BTreeMap::from([
+                (
+                    "PATH".to_string(),
+                    Some("/project/.venv/bin:/usr/bin".to_string())
+                ),
+                ("VIRTUAL_ENV".to_string(), None),
+            ])
+        );
+
+        assert_eq!(
+            parse_runtime_probe_output("no marker here\n", &variables),
+            None
+        );
+    }
+
+    #[test]
+    fn test_runtime_probe_code_languages() {
+        let variables = vec!["PATH".to_string()];
+        for language in ["Python", "R", "julia", "TypeScript", "javascript"] {
+            let code = runtime_probe_code(language, &variables)
+                .unwrap_or_else(|| panic!("no probe for {language}"));
+            assert!(code.contains(RUNTIME_PROBE_MARKER), "{language}: {code}");
+            assert!(code.contains("\"PATH\""), "{language}: {code}");
+        }
+        assert_eq!(runtime_probe_code("scala", &variables), None);
+    }
+
+    #[test]
+    fn test_map_to_remote_root() {
+        let roots = HashMap::from_iter([
+            (
+                PathBuf::from(path!("/Users/me/project")),
+                "/home/me/project/".to_string(),
+            ),
+            (
+                PathBuf::from(path!("/Users/me/project/data")),
+                "/mnt/data".to_string(),
+            ),
+        ]);
+
+        assert_eq!(
+            map_to_remote_root(&roots, Path::new(path!("/Users/me/project"))).as_deref(),
+            Some("/home/me/project")
+        );
+        assert_eq!(
+            map_to_remote_root(&roots, Path::new(path!("/Users/me/project/src/app"))).as_deref(),
+            Some("/home/me/project/src/app")
+        );
+        assert_eq!(
+            map_to_remote_root(&roots, Path::new(path!("/Users/me/project/data/raw"))).as_deref(),
+            Some("/mnt/data/raw")
+        );
+        assert_eq!(
+            map_to_remote_root(&roots, Path::new(path!("/Users/me/other"))),
+            None
+        );
+    }
+
+    #[gpui::test]
+    async fn test_remote_working_directory_change(cx: &mut TestAppContext) {
+        let (_editor, session, mut request_rx, mut cx) = init_session(cx).await;
+
+        session.update(&mut cx, |session, cx| {
+            session.change_remote_working_directory("/home/me/project".to_string(), cx);
+            assert_eq!(
+                session.remote_working_directory(),
+                Some(&RemoteWorkingDirectory::Pending(
+                    "/home/me/project".to_string()
+                ))
+            );
+        });
+        let request = request_rx.try_next().unwrap().unwrap();
+        match &request.content {
+            JupyterMessageContent::ExecuteRequest(request) => {
+                assert!(request.silent);
+                assert!(request.code.contains("chdir(\"/home/me/project\")"));
+            }
+            other => panic!("expected an execute request, got {other:?}"),
+        }
+
+        let output = StreamContent {
+            name: Stdio::Stdout,
+            text: format!("{WORKING_DIRECTORY_MARKER}/home/me/project\n"),
+        };
+        let reply: ExecuteReply =
+            serde_json::from_value(json!({ "status": "ok", "execution_count": 0 })).unwrap();
+        session.update_in(&mut cx, |session, window, cx| {
+            session.route(&output.as_child_of(&request), window, cx);
+            session.route(&reply.as_child_of(&request), window, cx);
+        });
+        session.read_with(&cx, |session, _| {
+            assert_eq!(
+                session.remote_working_directory(),
+                Some(&RemoteWorkingDirectory::Changed(
+                    "/home/me/project".to_string()
+                ))
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_display_setup_code(cx: &mut TestAppContext) {
+        let (_editor, session, mut request_rx, mut cx) = init_session(cx).await;
+
+        session.update(&mut cx, |session, cx| session.send_display_setup(cx));
+        let request = request_rx.try_next().unwrap().unwrap();
+        match &request.content {
+            JupyterMessageContent::ExecuteRequest(request) => {
+                assert!(request.silent);
+                assert!(
+                    request
+                        .code
+                        .contains("run_line_magic(\"matplotlib\", \"inline\")")
+                );
+            }
+            other => panic!("expected an execute request, got {other:?}"),
+        }
+
+        // A kernel without matplotlib fails the setup without any output.
+        let error = ErrorOutput {
+            ename: "ModuleNotFoundError".to_string(),
+            evalue: "No module named 'matplotlib'".to_string(),
+            traceback: Vec::new(),
+        };
+        let reply: ExecuteReply =
+            serde_json::from_value(json!({ "status": "error", "execution_count": 0 })).unwrap();
+        session.update_in(&mut cx, |session, window, cx| {
+            session.route(&error.as_child_of(&request), window, cx);
+            session.route(&reply.as_child_of(&request), window, cx);
+            assert!(session.display_setup.is_none());
+        });
+
+        cx.update(|_, cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.repl.get_or_insert_default().display_setup_code =
+                        Some(HashMap::from_iter([("python".to_string(), String::new())]));
+                });
+            });
+        });
+        session.update(&mut cx, |session, cx| session.send_display_setup(cx));
+        assert!(request_rx.try_next().is_err());
+    }
+
+    #[gpui::test]
+    async fn test_variables_refresh_after_execution(cx: &mut TestAppContext) {
+        let (editor, session, mut request_rx, mut cx) = init_session(cx).await;
+
+        let changes = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        cx.update(|_, cx| {
+            let changes = changes.clone();
+            cx.subscribe(&session, move |_, event: &SessionEvent, _| {
+                if let SessionEvent::VariablesChanged = event {
+                    changes.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                }
+            })
+            .detach();
+        });
+
+        let anchor_range = editor.update(&mut cx, |editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            snapshot.anchor_before(Point::new(0, 0))..snapshot.anchor_after(Point::new(0, 8))
+        });
+        session.update_in(&mut cx, |session, window, cx| {
+            session.execute(
+                "x = 1".to_string(),
+                anchor_range,
+                None,
+                false,
+                ExecutionOptions::default(),
+                window,
+                cx,
+            );
+        });
+        let execution = request_rx.try_next().unwrap().unwrap();
+
+        let reply: ExecuteReply =
+            serde_json::from_value(json!({ "status": "ok", "execution_count": 1 })).unwrap();
+        session.update_in(&mut cx, |session, window, cx| {
+            session.route(&reply.clone().as_child_of(&execution), window, cx);
+        });
+        assert!(
+            request_rx.try_next().is_err(),
+            "variables are listed only after the debounce"
+        );
+
+        cx.executor().advance_clock(REFRESH_VARIABLES_DEBOUNCE);
+        cx.run_until_parked();
+        let query = request_rx.try_next().unwrap().unwrap();
+        match &query.content {
+            JupyterMessageContent::ExecuteRequest(request) => {
+                assert!(request.silent);
+                assert!(!request.store_history);
+            }
+            other => panic!("expected an execute request, got {other:?}"),
+        }
+
+        let output = StreamContent {
+            name: Stdio::Stdout,
+            text: format!(
+                "{VARIABLES_MARKER}{}\n",
+                json!([{ "name": "x", "type": "int", "size": null }])
+            ),
+        };
+        session.update_in(&mut cx, |session, window, cx| {
+            session.route(&output.as_child_of(&query), window, cx);
+            session.route(&reply.as_child_of(&query), window, cx);
+        });
+
+        session.read_with(&cx, |session, _| {
+            assert_eq!(
+                session.variables(),
+                [VariableSummary {
+                    name: "x".to_string(),
+                    type_name: "int".to_string(),
+                    size: None,
+                }]
+            );
+        });
+        assert_eq!(changes.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(
+            request_rx.try_next().is_err(),
+            "the silent query doesn't trigger another refresh"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_kernel_status_follows_execution_state(cx: &mut TestAppContext) {
+        let (_editor, session, _request_rx, mut cx) = init_session(cx).await;
+
+        let expected = [
+            (ExecutionState::Idle, "Idle"),
+            (ExecutionState::Busy, "Busy"),
+            (ExecutionState::Starting, "Starting"),
+            (ExecutionState::Restarting, "Restarting"),
+            (ExecutionState::AutoRestarting, "Restarting"),
+            (ExecutionState::Terminating, "Shutting Down"),
+            (ExecutionState::Dead, "Error"),
+            (ExecutionState::Unknown, "Error"),
+        ];
+        let parent: JupyterMessage = KernelInfoRequest {}.into();
+        for (execution_state, status) in expected {
+            let message = Status { execution_state }.as_child_of(&parent);
+            session.update_in(&mut cx, |session, window, cx| {
+                session.route(&message, window, cx);
+            });
+            assert_eq!(kernel_status(&session, &cx), status);
+        }
+
+        session.update(&mut cx, |session, cx| session.kernel(Kernel::Culled, cx));
+        assert_eq!(kernel_status(&session, &cx), "Culled (Idle)");
+    }
+
+    #[gpui::test]
+    async fn test_fake_kernel_execution(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior::replying_with([
+                stream("first\n"),
+                FakeKernelStep::Delay(Duration::from_secs(1)),
+                FakeKernelStep::Reply(JupyterMessageContent::ErrorOutput(ErrorOutput {
+                    ename: "ValueError".to_string(),
+                    evalue: "bad value".to_string(),
+                    traceback: Vec::new(),
+                })),
+                stream("last\n"),
+            ]),
+            &mut cx,
+        );
+
+        execute_line(&editor, &session, 0, &mut cx);
+        execute_line(&editor, &session, 1, &mut cx);
+        cx.run_until_parked();
+        assert_eq!(kernel_status(&session, &cx), "Busy");
+        session.read_with(&cx, |session, cx| {
+            assert_eq!(session.pending_execution_count(cx), 2);
+        });
+
+        // The second execution only starts once the first one is done.
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.run_until_parked();
+        assert_eq!(kernel_status(&session, &cx), "Busy");
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.run_until_parked();
+        assert_eq!(kernel_status(&session, &cx), "Idle");
+
+        // Leave out the silent execution that listed the variables.
+        let requests = received
+            .take()
+            .into_iter()
+            .filter(|message| {
+                matches!(
+                    &message.content,
+                    JupyterMessageContent::ExecuteRequest(request) if !request.silent
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(requests.len(), 2);
+        session.read_with(&cx, |session, cx| {
+            assert_eq!(session.pending_execution_count(cx), 0);
+            for request in &requests {
+                let execution_view = session.blocks[&request.header.msg_id]
+                    .execution_view
+                    .read(cx);
+                assert!(matches!(execution_view.status, ExecutionStatus::Finished));
+                // Outputs keep the order the kernel sent them in, and every
+                // execution only gets its own.
+                assert!(matches!(
+                    execution_view.outputs.as_slice(),
+                    [
+                        Output::Stream { .. },
+                        Output::ErrorOutput(_),
+                        Output::Stream { .. }
+                    ]
+                ));
+            }
+        });
+    }
+
+    #[gpui::test]
+    async fn test_execution_options_in_requests(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let received = start_fake_kernel(&session, FakeKernelBehavior::default(), &mut cx);
+
+        let anchor_range = editor.update(&mut cx, |editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            snapshot.anchor_before(Point::new(0, 0))..snapshot.anchor_after(Point::new(0, 8))
+        });
+        let options = ExecutionOptions {
+            store_history: false,
+            stop_on_error: false,
+            ..ExecutionOptions::default()
+        };
+        session.update_in(&mut cx, |session, window, cx| {
+            session.execute(
+                "x = 1".to_string(),
+                anchor_range,
+                None,
+                false,
+                options,
+                window,
+                cx,
+            );
+        });
+        execute_line(&editor, &session, 1, &mut cx);
+        cx.run_until_parked();
+        cx.executor().advance_clock(REFRESH_VARIABLES_DEBOUNCE);
+        cx.run_until_parked();
+
+        let requests = received
+            .take()
+            .into_iter()
+            .filter_map(|message| match message.content {
+                JupyterMessageContent::ExecuteRequest(request) => {
+                    Some(serde_json::to_value(&request).unwrap())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let flags = |request: &serde_json::Value| {
+            (
+                request["silent"].clone(),
+                request["store_history"].clone(),
+                request["stop_on_error"].clone(),
+                request["allow_stdin"].clone(),
+            )
+        };
+        let [custom, default, variables_query] = requests.as_slice() else {
+            panic!("expected three execute requests, got {requests:?}");
+        };
+        assert_eq!(
+            flags(custom),
+            (json!(false), json!(false), json!(false), json!(true))
+        );
+        assert_eq!(
+            flags(default),
+            (json!(false), json!(true), json!(true), json!(true))
+        );
+        // Zed's own executions leave no trace in the kernel's history.
+        assert_eq!(
+            flags(variables_query),
+            (json!(true), json!(false), json!(false), json!(false))
+        );
+    }
+
+    #[gpui::test]
+    async fn test_reordered_messages_follow_their_parent(cx: &mut TestAppContext) {
+        let (editor, session, mut request_rx, mut cx) = init_session(cx).await;
+        for row in 0..3 {
+            execute_line(&editor, &session, row, &mut cx);
+        }
+        let mut take_requests = || {
+            let mut executions = Vec::new();
+            while let Ok(Some(message)) = request_rx.try_next() {
+                executions.push(message);
+            }
+            executions
+        };
+
+        let busy = || Status {
+            execution_state: ExecutionState::Busy,
+        };
+        let idle = || Status {
+            execution_state: ExecutionState::Idle,
+        };
+        let reply = || -> ExecuteReply {
+            serde_json::from_value(json!({ "status": "ok", "execution_count": 1 })).unwrap()
+        };
+        let output = |text: &str| StreamContent {
+            name: Stdio::Stdout,
+            text: text.to_string(),
+        };
+        let route = |messages: Vec<JupyterMessage>, cx: &mut VisualTestContext| {
+            session.update_in(cx, |session, window, cx| {
+                for message in &messages {
+                    session.route(message, window, cx);
+                }
+            });
+        };
+
+        // Each execution is sent once the kernel has replied to the one
+        // before it, or gone idle for it.
+        let executions = take_requests();
+        let [first] = executions.as_slice() else {
+            panic!("expected one execute request, got {executions:?}");
+        };
+        // The shell reply overtakes the first execution's outputs.
+        route(
+            vec![busy().as_child_of(first), reply().as_child_of(first)],
+            &mut cx,
+        );
+
+        let executions = take_requests();
+        let [second] = executions.as_slice() else {
+            panic!("expected one execute request, got {executions:?}");
+        };
+        route(
+            vec![
+                busy().as_child_of(second),
+                output("second 1\n").as_child_of(second),
+                output("first 1\n").as_child_of(first),
+                // The second execution goes idle before its reply arrives.
+                idle().as_child_of(second),
+            ],
+            &mut cx,
+        );
+
+        let executions = take_requests();
+        let [third] = executions.as_slice() else {
+            panic!("expected one execute request, got {executions:?}");
+        };
+        // The kernel answers the third execution before its block is registered.
+        let third_block = session.update(&mut cx, |session, _| {
+            session.blocks.remove(&third.header.msg_id).unwrap()
+        });
+        route(
+            vec![
+                busy().as_child_of(third),
+                output("third 1\n").as_child_of(third),
+                output("first 2\n").as_child_of(first),
+                output("third 2\n").as_child_of(third),
+            ],
+            &mut cx,
+        );
+        session.read_with(&cx, |session, cx| {
+            for execution in [first, second] {
+                let execution_view = session.blocks[&execution.header.msg_id]
+                    .execution_view
+                    .read(cx);
+                assert!(matches!(execution_view.status, ExecutionStatus::Executing));
+            }
+        });
+
+        session.update_in(&mut cx, |session, window, cx| {
+            session.register_block(third.header.msg_id.clone(), third_block, window, cx);
+        });
+        route(
+            vec![
+                idle().as_child_of(first),
+                reply().as_child_of(third),
+                reply().as_child_of(second),
+                idle().as_child_of(third),
+            ],
+            &mut cx,
+        );
+
+        session.read_with(&cx, |session, cx| {
+            assert_eq!(session.pending_execution_count(cx), 0);
+            let expected = [
+                (first, "first 1\nfirst 2"),
+                (second, "second 1"),
+                (third, "third 1\nthird 2"),
+            ];
+            for (execution, text) in expected {
+                let execution_view = session.blocks[&execution.header.msg_id]
+                    .execution_view
+                    .read(cx);
+                assert!(matches!(execution_view.status, ExecutionStatus::Finished));
+                let texts = execution_view
+                    .outputs
+                    .iter()
+                    .map(|output| match output {
+                        Output::Stream { content } => {
+                            content.read(cx).full_text().trim().to_string()
+                        }
+                        _ => "<other>".to_string(),
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(texts, [text]);
+            }
+        });
+    }
+
+    /// Answers every execute request with an `execute_reply` carrying
+    /// `payload`.
+    fn replying_with_payload(payload: serde_json::Value) -> FakeKernelBehavior {
+        let reply: ExecuteReply = serde_json::from_value(json!({
+            "status": "ok",
+            "execution_count": 1,
+            "payload": payload,
+        }))
+        .unwrap();
+        FakeKernelBehavior {
+            on_execute: vec![
+                FakeKernelStep::status(ExecutionState::Busy),
+                FakeKernelStep::Reply(JupyterMessageContent::ExecuteReply(reply)),
+                FakeKernelStep::status(ExecutionState::Idle),
+            ],
+            ignore_interrupts: false,
+            ignore_shutdowns: false,
+        }
+    }
+
+    fn execution_views(
+        session: &Entity<Session>,
+        received: &FakeKernelMessages,
+        cx: &VisualTestContext,
+    ) -> Vec<Entity<ExecutionView>> {
+        session.read_with(cx, |session, _| {
+            received
+                .take()
+                .into_iter()
+                .filter(|message| {
+                    matches!(
+                        &message.content,
+                        JupyterMessageContent::ExecuteRequest(request) if !request.silent
+                    )
+                })
+                .map(|request| {
+                    session.blocks[&request.header.msg_id]
+                        .execution_view
+                        .clone()
+                })
+                .collect()
+        })
+    }
+
+    #[gpui::test]
+    async fn test_page_payload(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let received = start_fake_kernel(
+            &session,
+            replying_with_payload(json!([
+                {
+                    "source": "page",
+                    "data": { "text/plain": "Signature: df(x)\nDocstring: A frame." },
+                    "start": 2,
+                },
+                { "source": "ask_exit", "keepkernel": false },
+            ])),
+            &mut cx,
+        );
+        execute_line(&editor, &session, 0, &mut cx);
+        cx.run_until_parked();
+
+        let views = execution_views(&session, &received, &cx);
+        let [execution_view] = views.as_slice() else {
+            panic!("expected one execution");
+        };
+        execution_view.read_with(&cx, |execution_view, _| {
+            assert!(matches!(execution_view.status, ExecutionStatus::Finished));
+            let [paged] = execution_view.paged_outputs.as_slice() else {
+                panic!("expected one paged output");
+            };
+            assert_eq!(paged.start, 2);
+            assert!(paged.data.content.iter().any(|media| matches!(
+                media,
+                MimeType::Plain(text) if text.starts_with("Signature: df(x)")
+            )));
+        });
+
+        // Kernels that don't send payloads at all work like ones that send
+        // none.
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior::replying_with([stream("done\n")]),
+            &mut cx,
+        );
+        execute_line(&editor, &session, 1, &mut cx);
+        cx.run_until_parked();
+        let views = execution_views(&session, &received, &cx);
+        let [execution_view] = views.as_slice() else {
+            panic!("expected one execution");
+        };
+        execution_view.read_with(&cx, |execution_view, _| {
+            assert!(matches!(execution_view.status, ExecutionStatus::Finished));
+            assert!(execution_view.paged_outputs.is_empty());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_set_next_input_payload(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let next_inputs = Rc::new(RefCell::new(Vec::new()));
+        cx.update(|_, cx| {
+            let next_inputs = next_inputs.clone();
+            let editor = editor.clone();
+            cx.subscribe(&session, move |_, event, cx| {
+                if let SessionEvent::SetNextInput {
+                    code_range,
+                    text,
+                    replace,
+                    ..
+                } = event
+                {
+                    let snapshot = editor.read(cx).buffer().read(cx).snapshot(cx);
+                    next_inputs.borrow_mut().push((
+                        code_range.start.to_point(&snapshot).row,
+                        text.clone(),
+                        *replace,
+                    ));
+                }
+            })
+            .detach();
+        });
+        start_fake_kernel(
+            &session,
+            replying_with_payload(json!([
+                { "source": "set_next_input", "text": "import os\n", "replace": true },
+            ])),
+            &mut cx,
+        );
+        execute_line(&editor, &session, 1, &mut cx);
+        cx.run_until_parked();
+
+        assert_eq!(
+            next_inputs.borrow().as_slice(),
+            [(1, "import os\n".to_string(), true)]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_huge_stream_output_stays_bounded(cx: &mut TestAppContext) {
+        let (editor, session, mut request_rx, mut cx) = init_session(cx).await;
+        for row in 0..2 {
+            execute_line(&editor, &session, row, &mut cx);
+        }
+        let mut take_requests = || {
+            let mut executions = Vec::new();
+            while let Ok(Some(message)) = request_rx.try_next() {
+                executions.push(message);
+            }
+            executions
+        };
+        let output = |text: String| StreamContent {
+            name: Stdio::Stdout,
+            text,
+        };
+        let reply = || -> ExecuteReply {
+            serde_json::from_value(json!({ "status": "ok", "execution_count": 1 })).unwrap()
+        };
+        let route = |messages: Vec<JupyterMessage>, cx: &mut VisualTestContext| {
+            session.update_in(cx, |session, window, cx| {
+                for message in &messages {
+                    session.route(message, window, cx);
                 }
             });
+        };
+        // A kernel decodes bytes that aren't UTF-8 with replacement
+        // characters before sending them.
+        let binary = String::from_utf8_lossy(b"small \xff\xfe\n").into_owned();
+        let huge_line = "x".repeat(MAX_STREAM_OUTPUT_BYTES * 5);
 
-        self.editor
-            .update(cx, |editor, cx| {
-                editor.remove_blocks(blocks_to_remove, None, cx);
-                if !inlays_to_remove.is_empty() {
-                    editor.splice_inlays(&inlays_to_remove, vec![], cx);
-                }
-                if !gutter_ranges_to_remove.is_empty() {
-                    editor
-                        .remove_gutter_highlights::<ReplExecutedRange>(gutter_ranges_to_remove, cx);
-                }
-            })
-            .ok();
+        let executions = take_requests();
+        let [noisy] = executions.as_slice() else {
+            panic!("expected one execute request, got {executions:?}");
+        };
+        let started_at = Instant::now();
+        // The next execution is sent once the kernel replied, while the noisy
+        // one still has output on its way.
+        route(
+            vec![
+                output(huge_line.clone()).as_child_of(noisy),
+                reply().as_child_of(noisy),
+            ],
+            &mut cx,
+        );
+        let executions = take_requests();
+        let [small] = executions.as_slice() else {
+            panic!("expected one execute request, got {executions:?}");
+        };
+        route(
+            vec![
+                output(binary).as_child_of(small),
+                output(huge_line).as_child_of(noisy),
+            ],
+            &mut cx,
+        );
+        // Only the capped part of the output is ever processed, so routing
+        // takes a fraction of what the full 40 MB would.
+        assert!(
+            started_at.elapsed() < Duration::from_secs(10),
+            "routing took {:?}",
+            started_at.elapsed()
+        );
 
-        let status = match &self.kernel {
-            Kernel::Restarting => ExecutionStatus::Restarting,
-            Kernel::RunningKernel(_) => ExecutionStatus::Queued,
-            Kernel::StartingKernel(_) => ExecutionStatus::ConnectingToKernel,
-            Kernel::ErroredLaunch(error) => ExecutionStatus::KernelErrored(error.clone()),
-            Kernel::ShuttingDown => ExecutionStatus::ShuttingDown,
-            Kernel::Shutdown => ExecutionStatus::Shutdown,
+        session.read_with(&cx, |session, cx| {
+            let stream_text = |execution: &JupyterMessage| {
+                session.blocks[&execution.header.msg_id]
+                    .execution_view
+                    .read(cx)
+                    .output_as_stream_text(cx)
+                    .unwrap_or_default()
+            };
+            assert_eq!(stream_text(small).trim(), "small \u{FFFD}\u{FFFD}");
+            assert!(
+                stream_text(noisy)
+                    .trim_end()
+                    .ends_with("[Output truncated after 4 MB]")
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_output_log_footer_follows_late_output(cx: &mut TestAppContext) {
+        let (editor, session, mut request_rx, mut cx) = init_session(cx).await;
+        let log_path = PathBuf::from(path!("/logs/run.log"));
+        session.update(&mut cx, |session, cx| {
+            session.set_output_log(Some(log_path.clone()), cx)
+        });
+        execute_line(&editor, &session, 0, &mut cx);
+        let Ok(Some(request)) = request_rx.try_next() else {
+            panic!("expected an execute request");
         };
 
-        let parent_message_id = message.header.msg_id.clone();
-        let session_view = cx.entity().downgrade();
-        let weak_editor = self.editor.clone();
-        let code_range_for_close = anchor_range.clone();
+        let input: runtimelib::ExecuteInput =
+            serde_json::from_value(json!({ "code": "print(1)", "execution_count": 1 })).unwrap();
+        let reply: ExecuteReply =
+            serde_json::from_value(json!({ "status": "ok", "execution_count": 1 })).unwrap();
+        let output = |text: &str| StreamContent {
+            name: Stdio::Stdout,
+            text: text.to_string(),
+        };
+        // Stream output can still arrive after the reply, since the kernel
+        // sends them on different channels.
+        let messages = [
+            input.as_child_of(&request),
+            output("1\n").as_child_of(&request),
+            reply.as_child_of(&request),
+            output("flushed late\n").as_child_of(&request),
+            Status {
+                execution_state: ExecutionState::Idle,
+            }
+            .as_child_of(&request),
+        ];
+        session.update_in(&mut cx, |session, window, cx| {
+            for message in &messages {
+                session.route(message, window, cx);
+            }
+        });
+        cx.run_until_parked();
 
-        let on_close: CloseBlockFn = Arc::new(
-            move |block_id: CustomBlockId, _: &mut Window, cx: &mut App| {
-                if let Some(session) = session_view.upgrade() {
-                    session.update(cx, |session, cx| {
-                        session.blocks.remove(&parent_message_id);
-                        cx.notify();
-                    });
+        let fs = session.read_with(&cx, |session, _| session.fs.clone());
+        let log = fs.load(&log_path).await.unwrap();
+        let lines = log.lines().collect::<Vec<_>>();
+        let [header, "1", "flushed late", footer] = lines.as_slice() else {
+            panic!("unexpected log:\n{log}");
+        };
+        assert!(header.starts_with("=== In [1] at "), "{header}");
+        assert!(footer.starts_with("=== Finished: ok"), "{footer}");
+    }
+
+    #[gpui::test]
+    async fn test_attached_kernel_left_running_on_exit(cx: &mut TestAppContext) {
+        let (_editor, session, mut request_rx, mut cx) = init_session(cx).await;
+        session.update(&mut cx, |session, cx| {
+            assert_eq!(session.kernel_owned(), None);
+            session.set_kernel_owned(false, cx);
+            assert_eq!(session.kernel_owned(), Some(false));
+            assert!(session.take_kernel_for_exit(cx).is_none());
+        });
+        assert!(request_rx.try_next().is_err());
+    }
+
+    #[gpui::test]
+    async fn test_owned_kernel_shut_down_on_exit(cx: &mut TestAppContext) {
+        let (_editor, session, mut request_rx, mut cx) = init_session(cx).await;
+        session.update(&mut cx, |session, cx| {
+            session.set_kernel_owned(true, cx);
+            assert!(session.take_kernel_for_exit(cx).is_some());
+        });
+        let request = request_rx.try_next().unwrap().unwrap();
+        assert!(matches!(
+            request.content,
+            JupyterMessageContent::ShutdownRequest(_)
+        ));
+    }
+
+    #[gpui::test]
+    async fn test_quit_kills_kernels_after_grace_period(cx: &mut TestAppContext) {
+        let (_editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let behavior = FakeKernelBehavior {
+            ignore_shutdowns: true,
+            ..FakeKernelBehavior::default()
+        };
+        start_fake_kernel(&session, behavior, &mut cx);
+        let exiting = session.update(&mut cx, |session, cx| {
+            session.set_kernel_owned(true, cx);
+            session.take_kernel_for_exit(cx)
+        });
+        let exiting = exiting.expect("owned kernel is shut down");
+
+        // The fake kernel never acknowledges the shutdown request.
+        let finished = shut_down_kernels_on_quit(vec![exiting], &cx);
+        cx.executor()
+            .advance_clock(QUIT_SHUTDOWN_TIMEOUT - Duration::from_millis(1));
+        cx.run_until_parked();
+        assert!(!finished.load(Ordering::SeqCst));
+
+        cx.executor().advance_clock(Duration::from_millis(1));
+        cx.run_until_parked();
+        assert!(finished.load(Ordering::SeqCst));
+    }
+
+    #[gpui::test]
+    async fn test_quit_kills_acknowledged_kernels_right_away(cx: &mut TestAppContext) {
+        let (_editor, session, _request_rx, mut cx) = init_session(cx).await;
+        start_fake_kernel(&session, FakeKernelBehavior::default(), &mut cx);
+        let exiting = session.update(&mut cx, |session, cx| {
+            session.set_kernel_owned(true, cx);
+            session.take_kernel_for_exit(cx)
+        });
+        let mut exiting = exiting.expect("owned kernel is shut down");
+        let (acknowledged_tx, acknowledged) = futures::channel::oneshot::channel();
+        exiting.acknowledged = acknowledged;
+        acknowledged_tx.send(()).ok();
+
+        let finished = shut_down_kernels_on_quit(vec![exiting], &cx);
+        cx.run_until_parked();
+        assert!(finished.load(Ordering::SeqCst));
+    }
+
+    /// Shuts down `kernels` with the grace period Zed gives them when quitting,
+    /// returning whether that has finished.
+    fn shut_down_kernels_on_quit(
+        kernels: Vec<ExitingKernel>,
+        cx: &VisualTestContext,
+    ) -> Arc<AtomicBool> {
+        let finished = Arc::new(AtomicBool::new(false));
+        let deadline = cx.executor().timer(QUIT_SHUTDOWN_TIMEOUT);
+        cx.executor()
+            .spawn({
+                let finished = finished.clone();
+                async move {
+                    ReplStore::shutdown_kernels(kernels, deadline).await;
+                    finished.store(true, Ordering::SeqCst);
                 }
+            })
+            .detach();
+        finished
+    }
 
-                if let Some(editor) = weak_editor.upgrade() {
-                    editor.update(cx, |editor, cx| {
-                        let mut block_ids = HashSet::default();
-                        block_ids.insert(block_id);
-                        editor.remove_blocks(block_ids, None, cx);
-                        editor.remove_gutter_highlights::<ReplExecutedRange>(
-                            vec![code_range_for_close.clone()],
-                            cx,
-                        );
-                    });
+    /// Runs the code on `row`, stopping the executions queued after it if it
+    /// fails.
+    fn execute_stopping_on_error(
+        editor: &Entity<Editor>,
+        session: &Entity<Session>,
+        row: u32,
+        cx: &mut VisualTestContext,
+    ) {
+        let anchor_range = editor.update(cx, |editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            snapshot.anchor_before(Point::new(row, 0))..snapshot.anchor_after(Point::new(row, 8))
+        });
+        session.update_in(cx, |session, window, cx| {
+            session.execute(
+                format!("print({})", row + 1),
+                anchor_range,
+                None,
+                false,
+                ExecutionOptions {
+                    stop_on_error: true,
+                    ..ExecutionOptions::default()
+                },
+                window,
+                cx,
+            );
+        });
+    }
+
+    fn sent_code(received: &FakeKernelMessages) -> Vec<String> {
+        received
+            .take()
+            .into_iter()
+            .filter_map(|message| match message.content {
+                JupyterMessageContent::ExecuteRequest(request) if !request.silent => {
+                    Some(request.code)
                 }
-            },
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[gpui::test]
+    async fn test_execution_queue(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior::replying_with([FakeKernelStep::Delay(Duration::from_secs(1))]),
+            &mut cx,
         );
 
-        let Ok(editor_block) = EditorBlock::new(
-            self.editor.clone(),
-            anchor_range.clone(),
-            status,
-            on_close,
-            cx,
-        ) else {
-            return;
+        for row in 0..4 {
+            execute_line(&editor, &session, row, &mut cx);
+        }
+        cx.run_until_parked();
+        assert_eq!(sent_code(&received), ["print(1)"]);
+        let queued = session.read_with(&cx, |session, _| session.queued_executions());
+        assert_eq!(
+            queued
+                .iter()
+                .map(|execution| (execution.code_preview.as_str(), execution.sent))
+                .collect::<Vec<_>>(),
+            [
+                ("print(1)", true),
+                ("print(2)", false),
+                ("print(3)", false),
+                ("print(4)", false),
+            ]
+        );
+
+        session.update(&mut cx, |session, cx| {
+            // The running execution is out of the session's hands.
+            assert!(!session.cancel_queued_execution(&queued[0].msg_id, cx));
+            assert!(session.cancel_queued_execution(&queued[2].msg_id, cx));
+            assert!(session.move_queued_execution(&queued[3].msg_id, 0, cx));
+        });
+        session.read_with(&cx, |session, cx| {
+            let status = &session.blocks[&queued[2].msg_id]
+                .execution_view
+                .read(cx)
+                .status;
+            assert!(matches!(status, ExecutionStatus::Aborted));
+            assert_eq!(session.pending_execution_count(cx), 3);
+        });
+
+        for _ in 0..3 {
+            cx.executor().advance_clock(Duration::from_secs(1));
+            cx.run_until_parked();
+        }
+        assert_eq!(sent_code(&received), ["print(4)", "print(2)"]);
+        session.read_with(&cx, |session, cx| {
+            assert!(session.queued_executions().is_empty());
+            assert_eq!(session.pending_execution_count(cx), 0);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_cell_run_progress_and_cancel(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior::replying_with([FakeKernelStep::Delay(Duration::from_secs(1))]),
+            &mut cx,
+        );
+
+        let cells = editor.update(&mut cx, |editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            (0..3)
+                .map(|row| {
+                    (
+                        format!("print({})", row + 1),
+                        snapshot.anchor_before(Point::new(row, 0))
+                            ..snapshot.anchor_after(Point::new(row, 8)),
+                    )
+                })
+                .collect::<Vec<_>>()
+        });
+        session.update_in(&mut cx, |session, window, cx| {
+            session.run_cells(editor.downgrade(), cells, None, false, window, cx);
+        });
+        cx.run_until_parked();
+
+        let progress = |cx: &mut VisualTestContext| {
+            let progress = session.read_with(cx, |session, _| session.cell_run_progress());
+            editor.update(cx, |editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                progress.map(|progress| {
+                    let running_row = progress
+                        .running
+                        .map(|range| range.start.to_point(&snapshot).row);
+                    (progress.finished, progress.total, running_row)
+                })
+            })
         };
+        assert_eq!(progress(&mut cx), Some((0, 3, Some(0))));
+
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.run_until_parked();
+        assert_eq!(progress(&mut cx), Some((1, 3, Some(1))));
+        assert_eq!(sent_code(&received), ["print(1)", "print(2)"]);
+
+        // The running cell finishes, but the one after it never starts.
+        session.update(&mut cx, |session, cx| session.cancel_cell_run(false, cx));
+        assert_eq!(progress(&mut cx), None);
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.run_until_parked();
+        assert!(sent_code(&received).is_empty());
+        session.read_with(&cx, |session, cx| {
+            assert!(session.queued_executions().is_empty());
+            assert_eq!(session.pending_execution_count(cx), 0);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_execution_queue_stops_on_error(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior {
+                on_execute: [
+                    FakeKernelStep::status(ExecutionState::Busy),
+                    FakeKernelStep::Delay(Duration::from_secs(1)),
+                ]
+                .into_iter()
+                .chain(FakeKernelStep::execute_reply("error"))
+                .chain([FakeKernelStep::status(ExecutionState::Idle)])
+                .collect(),
+                ignore_interrupts: false,
+                ignore_shutdowns: false,
+            },
+            &mut cx,
+        );
 
-        self.editor
-            .update(cx, |editor, cx| {
-                editor.insert_gutter_highlight::<ReplExecutedRange>(
-                    anchor_range.clone(),
-                    |cx| cx.theme().status().success,
+        let execute = |row: u32, stop_on_error: bool, cx: &mut VisualTestContext| {
+            let anchor_range = editor.update(cx, |editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                snapshot.anchor_before(Point::new(row, 0))
+                    ..snapshot.anchor_after(Point::new(row, 8))
+            });
+            session.update_in(cx, |session, window, cx| {
+                session.execute(
+                    format!("print({})", row + 1),
+                    anchor_range,
+                    None,
+                    false,
+                    ExecutionOptions {
+                        stop_on_error,
+                        ..ExecutionOptions::default()
+                    },
+                    window,
                     cx,
                 );
-            })
-            .ok();
-
-        let new_cursor_pos = if let Some(next_cursor) = next_cell {
-            next_cursor
-        } else {
-            editor_block.invalidation_anchor
+            });
         };
 
-        let msg_id = message.header.msg_id.clone();
-        let subscription = cx.subscribe(
-            &editor_block.execution_view,
-            move |session, _execution_view, _event: &ExecutionViewFinishedEmpty, cx| {
-                session.replace_block_with_inlay(&msg_id, "✓", cx);
-            },
+        // An error in an execution that doesn't stop on errors leaves the
+        // queue alone.
+        execute(0, false, &mut cx);
+        execute(1, true, &mut cx);
+        execute(2, true, &mut cx);
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.run_until_parked();
+        assert_eq!(sent_code(&received), ["print(1)", "print(2)"]);
+
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.run_until_parked();
+        assert!(sent_code(&received).is_empty());
+        session.read_with(&cx, |session, cx| {
+            assert!(session.queued_executions().is_empty());
+            assert_eq!(session.pending_execution_count(cx), 0);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_interrupted_execution_is_not_an_error(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior::replying_with([FakeKernelStep::Delay(Duration::from_secs(10))]),
+            &mut cx,
         );
-        self._subscriptions.push(subscription);
 
-        let msg_id = message.header.msg_id.clone();
-        let subscription = cx.subscribe(
-            &editor_block.execution_view,
-            move |session, _execution_view, event: &ExecutionViewFinishedSmall, cx| {
-                session.replace_block_with_inlay(&msg_id, &event.0, cx);
+        execute_stopping_on_error(&editor, &session, 0, &mut cx);
+        execute_stopping_on_error(&editor, &session, 1, &mut cx);
+        cx.run_until_parked();
+        assert_eq!(sent_code(&received), ["print(1)"]);
+
+        // The kernel raises KeyboardInterrupt, which doesn't stop the queue.
+        session.update(&mut cx, |session, cx| session.interrupt(cx));
+        cx.run_until_parked();
+        assert_eq!(sent_code(&received), ["print(2)"]);
+        session.read_with(&cx, |session, cx| {
+            let interrupted = session.execution_records().next().unwrap();
+            assert_eq!(interrupted.status, ExecutionRecordStatus::Interrupted);
+            let execution_view = session.blocks[&interrupted.msg_id].execution_view.read(cx);
+            assert!(matches!(
+                execution_view.status,
+                ExecutionStatus::Interrupted
+            ));
+            assert!(matches!(
+                execution_view.outputs.as_slice(),
+                [Output::ErrorOutput(error_view)] if error_view.interrupted
+            ));
+        });
+        cx.executor().advance_clock(Duration::from_secs(10));
+        cx.run_until_parked();
+
+        // Unless interrupts are set to stop it.
+        cx.update(|_, cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.repl.get_or_insert_default().stop_on_interrupt = Some(true);
+                });
+            });
+        });
+        execute_stopping_on_error(&editor, &session, 2, &mut cx);
+        execute_stopping_on_error(&editor, &session, 3, &mut cx);
+        cx.run_until_parked();
+        assert_eq!(sent_code(&received), ["print(3)"]);
+        session.update(&mut cx, |session, cx| session.interrupt(cx));
+        cx.run_until_parked();
+        assert!(sent_code(&received).is_empty());
+        session.read_with(&cx, |session, cx| {
+            assert!(session.queued_executions().is_empty());
+            assert_eq!(session.pending_execution_count(cx), 0);
+            let statuses = session
+                .execution_records()
+                .map(|record| record.status)
+                .collect::<Vec<_>>();
+            assert_eq!(
+                statuses,
+                [
+                    ExecutionRecordStatus::Interrupted,
+                    ExecutionRecordStatus::Ok,
+                    ExecutionRecordStatus::Interrupted,
+                    ExecutionRecordStatus::Aborted,
+                ]
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_keyboard_interrupt_raised_by_code_is_an_error(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior {
+                on_execute: [
+                    FakeKernelStep::status(ExecutionState::Busy),
+                    FakeKernelStep::error("KeyboardInterrupt", ""),
+                ]
+                .into_iter()
+                .chain(FakeKernelStep::execute_reply("error"))
+                .chain([FakeKernelStep::status(ExecutionState::Idle)])
+                .collect(),
+                ignore_interrupts: false,
+                ignore_shutdowns: false,
             },
+            &mut cx,
         );
-        self._subscriptions.push(subscription);
 
-        let subscription = cx.subscribe(
-            &editor_block.execution_view,
-            |session, _execution_view, event: &InputReplyEvent, cx| {
-                session.send_stdin_reply(event.value.clone(), &event.parent_message, cx);
-            },
+        // Without an interrupt from Zed, it's a failure like any other.
+        execute_stopping_on_error(&editor, &session, 0, &mut cx);
+        execute_stopping_on_error(&editor, &session, 1, &mut cx);
+        cx.run_until_parked();
+        assert_eq!(sent_code(&received), ["print(1)"]);
+        session.read_with(&cx, |session, cx| {
+            let statuses = session
+                .execution_records()
+                .map(|record| record.status)
+                .collect::<Vec<_>>();
+            assert_eq!(
+                statuses,
+                [ExecutionRecordStatus::Error, ExecutionRecordStatus::Aborted]
+            );
+            let failed = session.execution_records().next().unwrap();
+            let execution_view = session.blocks[&failed.msg_id].execution_view.read(cx);
+            assert!(matches!(execution_view.status, ExecutionStatus::Finished));
+            assert!(matches!(
+                execution_view.outputs.as_slice(),
+                [Output::ErrorOutput(error_view)] if !error_view.interrupted
+            ));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_execute_code(cx: &mut TestAppContext) {
+        let (_editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior::replying_with([
+                FakeKernelStep::Delay(Duration::from_secs(1)),
+                stream("done\n"),
+            ]),
+            &mut cx,
         );
-        self._subscriptions.push(subscription);
 
-        self.blocks
-            .insert(message.header.msg_id.clone(), editor_block);
+        let (first, second, abandoned) = session.update(&mut cx, |session, cx| {
+            (
+                session.execute_code("print(1)".into(), ExecutionOptions::default(), cx),
+                session.execute_code("print(2)".into(), ExecutionOptions::default(), cx),
+                session.execute_code("print(3)".into(), ExecutionOptions::default(), cx),
+            )
+        });
+        // Dropped while waiting behind the others, so it's never sent.
+        drop(abandoned);
+        for _ in 0..4 {
+            cx.executor().advance_clock(Duration::from_secs(1));
+            cx.run_until_parked();
+        }
+        assert_eq!(sent_code(&received), ["print(1)", "print(2)"]);
 
-        match &self.kernel {
-            Kernel::RunningKernel(_) => {
-                self.send(message, cx).ok();
+        for (task, code) in [(first, "print(1)"), (second, "print(2)")] {
+            let record = task.await.unwrap();
+            assert_eq!(record.code, code);
+            assert_eq!(record.status, ExecutionRecordStatus::Ok);
+            assert!(record.is_complete());
+            let [RecordedOutput::Stream { text, .. }] = record.outputs.as_slice() else {
+                panic!("expected the execution's own output");
+            };
+            assert_eq!(text, "done\n");
+        }
+        session.read_with(&cx, |session, _| {
+            assert!(session.queued_executions().is_empty());
+            assert!(session.execution_waiters.is_empty());
+            let abandoned = session.execution_records().last().unwrap();
+            assert_eq!(abandoned.code, "print(3)");
+            assert_eq!(abandoned.status, ExecutionRecordStatus::Aborted);
+        });
+
+        session.update(&mut cx, |session, _| session.kernel = Kernel::Shutdown);
+        let result = session
+            .update(&mut cx, |session, cx| {
+                session.execute_code("print(4)".into(), ExecutionOptions::default(), cx)
+            })
+            .await;
+        assert!(result.is_err());
+    }
+
+    /// What a kernel sends for an execution that prints `text` and succeeds.
+    fn successful_execution(request: &JupyterMessage, text: &str) -> Vec<JupyterMessage> {
+        let reply: ExecuteReply =
+            serde_json::from_value(json!({ "status": "ok", "execution_count": 1 })).unwrap();
+        vec![
+            Status {
+                execution_state: ExecutionState::Busy,
+            }
+            .as_child_of(request),
+            StreamContent {
+                name: Stdio::Stdout,
+                text: text.to_string(),
+            }
+            .as_child_of(request),
+            reply.as_child_of(request),
+            Status {
+                execution_state: ExecutionState::Idle,
+            }
+            .as_child_of(request),
+        ]
+    }
+
+    #[gpui::test]
+    async fn test_execute_code_without_window(cx: &mut TestAppContext) {
+        let (_editor, session, mut request_rx, mut cx) = init_session(cx).await;
+
+        let task = session.update(&mut cx, |session, cx| {
+            session.execute_code("print(1)".into(), ExecutionOptions::default(), cx)
+        });
+        let request = request_rx.try_next().unwrap().unwrap();
+        // Only the session's own context is used, as when nothing shows it.
+        session.update(&mut cx, |session, cx| {
+            for message in &successful_execution(&request, "1\n") {
+                session.handle_message(message, cx);
+            }
+        });
+
+        let record = task.await.unwrap();
+        assert_eq!(record.status, ExecutionRecordStatus::Ok);
+        let [RecordedOutput::Stream { text, .. }] = record.outputs.as_slice() else {
+            panic!("expected the execution's output");
+        };
+        assert_eq!(text, "1\n");
+        assert_eq!(kernel_status(&session, &cx), "Idle");
+        session.read_with(&cx, |session, _| {
+            assert!(session.sent_execution.is_none());
+            assert!(session.unpresented_messages.is_empty());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_block_outputs_wait_for_presentation(cx: &mut TestAppContext) {
+        let (editor, session, mut request_rx, mut cx) = init_session(cx).await;
+
+        execute_line(&editor, &session, 0, &mut cx);
+        let request = request_rx.try_next().unwrap().unwrap();
+        let msg_id = request.header.msg_id.clone();
+        session.update(&mut cx, |session, cx| {
+            for message in &successful_execution(&request, "1\n") {
+                session.handle_message(message, cx);
+            }
+        });
+
+        // The execution is finished, but its block isn't updated until
+        // there's a window to show it in.
+        session.read_with(&cx, |session, cx| {
+            assert!(session.sent_execution.is_none());
+            assert!(session.execution_record(&msg_id).unwrap().is_complete());
+            let execution_view = session.blocks[&msg_id].execution_view.read(cx);
+            assert!(execution_view.outputs.is_empty());
+        });
+
+        session.update_in(&mut cx, |session, window, cx| {
+            session.present_messages(window, cx)
+        });
+        session.read_with(&cx, |session, cx| {
+            assert_eq!(session.pending_execution_count(cx), 0);
+            let execution_view = session.blocks[&msg_id].execution_view.read(cx);
+            assert!(matches!(execution_view.status, ExecutionStatus::Finished));
+            assert!(matches!(
+                execution_view.outputs.as_slice(),
+                [Output::Stream { .. }]
+            ));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_unpresented_messages_are_bounded(cx: &mut TestAppContext) {
+        let (editor, session, mut request_rx, mut cx) = init_session(cx).await;
+
+        execute_line(&editor, &session, 0, &mut cx);
+        let request = request_rx.try_next().unwrap().unwrap();
+        let msg_id = request.header.msg_id.clone();
+        let message_count = MAX_UNPRESENTED_MESSAGES + 10;
+        session.update(&mut cx, |session, cx| {
+            for index in 0..message_count {
+                let stream = StreamContent {
+                    name: Stdio::Stdout,
+                    text: format!("{index}\n"),
+                }
+                .as_child_of(&request);
+                session.handle_message(&stream, cx);
             }
-            Kernel::StartingKernel(task) => {
-                // Queue up the execution as a task to run after the kernel starts
-                let task = task.clone();
+        });
+
+        // Only the newest messages wait for a window; the record has them all.
+        session.read_with(&cx, |session, _| {
+            assert_eq!(session.unpresented_messages.len(), MAX_UNPRESENTED_MESSAGES);
+            let JupyterMessageContent::StreamContent(newest) =
+                &session.unpresented_messages.back().unwrap().content
+            else {
+                panic!("expected a stream message");
+            };
+            assert_eq!(newest.text, format!("{}\n", message_count - 1));
+            let JupyterMessageContent::StreamContent(oldest) =
+                &session.unpresented_messages.front().unwrap().content
+            else {
+                panic!("expected a stream message");
+            };
+            assert_eq!(oldest.text, "10\n");
+            let record = session.execution_record(&msg_id).unwrap();
+            let [RecordedOutput::Stream { text, .. }] = record.outputs.as_slice() else {
+                panic!("expected the execution's output");
+            };
+            assert!(text.starts_with("0\n1\n"));
+        });
+    }
+
+    fn kernel_info_reply(protocol_version: &str, debugger: bool) -> JupyterMessage {
+        let reply: KernelInfoReply = serde_json::from_value(json!({
+            "status": "ok",
+            "protocol_version": protocol_version,
+            "implementation": "ipython",
+            "implementation_version": "8.29.0",
+            "language_info": { "name": "python", "version": "3.11.6" },
+            "banner": "",
+            "help_links": [],
+            "debugger": debugger,
+        }))
+        .unwrap();
+        let request: JupyterMessage = KernelInfoRequest {}.into();
+        reply.as_child_of(&request)
+    }
+
+    #[gpui::test]
+    async fn test_debug_requests(cx: &mut TestAppContext) {
+        let (_editor, session, mut request_rx, mut cx) = init_session(cx).await;
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        cx.update(|_, cx| {
+            let events = events.clone();
+            cx.subscribe(&session, move |_, event: &SessionEvent, _| {
+                if let SessionEvent::DebugEvent(content) = event {
+                    events.lock().unwrap().push(content.clone());
+                }
+            })
+            .detach();
+        });
+
+        let threads = json!({ "type": "request", "seq": 2, "command": "threads" });
+        let result = session
+            .update(&mut cx, |session, cx| {
+                session.debug_request(threads.clone(), cx)
+            })
+            .await;
+        assert_eq!(result, Err(DebugRequestError::NotReady));
+
+        // A kernel that supports debugging is asked for its debug info as
+        // soon as it sends its kernel info.
+        session.update(&mut cx, |session, cx| {
+            session.handle_message(&kernel_info_reply("5.3", true), cx)
+        });
+        let debug_info_request = request_rx.try_next().unwrap().unwrap();
+        let JupyterMessageContent::DebugRequest(request) = &debug_info_request.content else {
+            panic!("expected a debug request, got {debug_info_request:?}");
+        };
+        assert_eq!(request.content["command"], "debugInfo");
+        let debug_info_reply = DebugReply {
+            content: json!({
+                "type": "response",
+                "success": true,
+                "command": "debugInfo",
+                "body": { "isStarted": false },
+            }),
+        };
+        session.update(&mut cx, |session, cx| {
+            session.handle_message(&debug_info_reply.as_child_of(&debug_info_request), cx)
+        });
+        cx.run_until_parked();
+        session.update(&mut cx, |session, cx| {
+            assert_eq!(
+                session.runtime_info(cx).unwrap().debug_support,
+                Some(KernelDebugSupport::Supported {
+                    info: Some(json!({ "isStarted": false })),
+                })
+            );
+        });
+
+        // Replies are matched to their request by its message id.
+        let reply_task = session.update(&mut cx, |session, cx| session.debug_request(threads, cx));
+        let threads_request = std::iter::from_fn(|| request_rx.try_next().ok().flatten())
+            .find(|message| matches!(message.content, JupyterMessageContent::DebugRequest(_)))
+            .unwrap();
+        let threads_reply = json!({ "type": "response", "success": true, "command": "threads" });
+        session.update(&mut cx, |session, cx| {
+            let reply = DebugReply {
+                content: threads_reply.clone(),
+            };
+            session.handle_message(&reply.as_child_of(&threads_request), cx)
+        });
+        assert_eq!(reply_task.await, Ok(threads_reply));
+
+        // Events are passed on whether or not they have a parent.
+        let stopped = json!({ "type": "event", "event": "stopped" });
+        let event = DebugEvent {
+            content: stopped.clone(),
+        };
+        session.update(&mut cx, |session, cx| {
+            session.handle_message(&JupyterMessage::new(event, None), cx)
+        });
+        assert_eq!(*events.lock().unwrap(), [stopped]);
+    }
+
+    #[gpui::test]
+    async fn test_debug_request_to_kernel_without_debugger(cx: &mut TestAppContext) {
+        let (_editor, session, mut request_rx, mut cx) = init_session(cx).await;
+
+        session.update(&mut cx, |session, cx| {
+            session.handle_message(&kernel_info_reply("5.3", false), cx)
+        });
+        let request = json!({ "type": "request", "seq": 1, "command": "threads" });
+        let result = session
+            .update(&mut cx, |session, cx| session.debug_request(request, cx))
+            .await;
+        assert_eq!(result, Err(DebugRequestError::Unsupported));
+        assert!(request_rx.try_next().is_err());
+        session.update(&mut cx, |session, cx| {
+            assert_eq!(
+                session.runtime_info(cx).unwrap().debug_support,
+                Some(KernelDebugSupport::Unsupported)
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_debug_request_that_cannot_be_sent(cx: &mut TestAppContext) {
+        let (_editor, session, request_rx, mut cx) = init_session(cx).await;
+
+        session.update(&mut cx, |session, cx| {
+            session.handle_message(&kernel_info_reply("5.3", true), cx)
+        });
+        drop(request_rx);
+        let request = json!({ "type": "request", "seq": 1, "command": "threads" });
+        let result = session
+            .update(&mut cx, |session, cx| session.debug_request(request, cx))
+            .await;
+        assert_eq!(result, Err(DebugRequestError::NotSent));
+        session.read_with(&cx, |session, _| {
+            assert!(session.pending_debug_requests.is_empty());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_requests_to_protocol_5_0_kernel(cx: &mut TestAppContext) {
+        let (editor, session, mut request_rx, mut cx) = init_session(cx).await;
+
+        // The kernel claims a debugger, but its protocol predates debug
+        // requests, so it isn't sent any.
+        session.update(&mut cx, |session, cx| {
+            session.handle_message(&kernel_info_reply("5.0", true), cx)
+        });
+        assert!(request_rx.try_next().is_err());
+        let request = json!({ "type": "request", "seq": 1, "command": "threads" });
+        let result = session
+            .update(&mut cx, |session, cx| session.debug_request(request, cx))
+            .await;
+        let version = ProtocolVersion { major: 5, minor: 0 };
+        assert_eq!(result, Err(DebugRequestError::UnsupportedProtocol(version)));
+        assert!(request_rx.try_next().is_err());
+        session.update(&mut cx, |session, cx| {
+            let info = session.runtime_info(cx).unwrap();
+            assert_eq!(info.protocol_version, Some(version));
+            assert_eq!(info.debug_support, Some(KernelDebugSupport::Unsupported));
+        });
+
+        // Executions that stop on error are sent without the flag.
+        execute_line(&editor, &session, 0, &mut cx);
+        let request = std::iter::from_fn(|| request_rx.try_next().ok().flatten())
+            .find_map(|message| match message.content {
+                JupyterMessageContent::ExecuteRequest(request) if !request.silent => Some(request),
+                _ => None,
+            })
+            .unwrap();
+        assert!(!request.stop_on_error);
+        session.read_with(&cx, |session, _| {
+            assert!(session.sent_execution.as_ref().unwrap().stop_on_error);
+        });
+    }
 
-                cx.spawn(async move |this, cx| {
-                    task.await;
-                    this.update(cx, |session, cx| {
-                        session.send(message, cx).ok();
-                    })
-                    .ok();
-                })
-                .detach();
-            }
-            _ => {}
-        }
+    #[gpui::test]
+    async fn test_execution_records(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        start_fake_kernel(
+            &session,
+            FakeKernelBehavior {
+                on_execute: [
+                    FakeKernelStep::status(ExecutionState::Busy),
+                    stream("partial\n"),
+                    FakeKernelStep::Reply(JupyterMessageContent::ClearOutput(
+                        runtimelib::ClearOutput { wait: false },
+                    )),
+                    stream("final\n"),
+                    FakeKernelStep::Reply(JupyterMessageContent::ErrorOutput(ErrorOutput {
+                        ename: "ValueError".to_string(),
+                        evalue: "bad value".to_string(),
+                        traceback: vec!["ValueError: bad value".to_string()],
+                    })),
+                ]
+                .into_iter()
+                .chain(FakeKernelStep::execute_reply("error"))
+                .chain([FakeKernelStep::status(ExecutionState::Idle)])
+                .collect(),
+                ignore_interrupts: false,
+                ignore_shutdowns: false,
+            },
+            &mut cx,
+        );
 
-        if move_down {
-            editor.update(cx, move |editor, cx| {
-                editor.change_selections(
-                    SelectionEffects::scroll(Autoscroll::top_relative(8.0)),
+        // The second execution is cancelled by the first one's error.
+        for row in 0..2 {
+            let anchor_range = editor.update(&mut cx, |editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                snapshot.anchor_before(Point::new(row, 0))
+                    ..snapshot.anchor_after(Point::new(row, 8))
+            });
+            session.update_in(&mut cx, |session, window, cx| {
+                session.execute(
+                    format!("print({})", row + 1),
+                    anchor_range,
+                    None,
+                    false,
+                    ExecutionOptions {
+                        stop_on_error: true,
+                        ..ExecutionOptions::default()
+                    },
                     window,
                     cx,
-                    |selections| {
-                        selections.select_ranges([new_cursor_pos..new_cursor_pos]);
-                    },
                 );
             });
         }
+        cx.run_until_parked();
+
+        // Records outlive the blocks showing the outputs.
+        session.update(&mut cx, |session, cx| session.clear_outputs(cx));
+
+        session.read_with(&cx, |session, _| {
+            let records: Vec<_> = session.execution_records().collect();
+            let [failed, cancelled] = records.as_slice() else {
+                panic!("expected two execution records");
+            };
+
+            assert_eq!(failed.code, "print(1)");
+            assert_eq!(failed.status, ExecutionRecordStatus::Error);
+            assert_eq!(failed.execution_count, Some(1));
+            assert!(failed.is_complete());
+            assert!(failed.started_at.is_some_and(|started_at| {
+                failed
+                    .finished_at
+                    .is_some_and(|finished_at| started_at <= finished_at)
+            }));
+            assert!(!failed.truncated);
+            let [
+                RecordedOutput::Stream { text, .. },
+                RecordedOutput::Error { ename, evalue, .. },
+            ] = failed.outputs.as_slice()
+            else {
+                panic!("expected the cleared stream to be replaced");
+            };
+            assert_eq!(text, "final\n");
+            assert_eq!(ename, "ValueError");
+            assert_eq!(evalue, "bad value");
+
+            assert_eq!(cancelled.code, "print(2)");
+            assert_eq!(cancelled.status, ExecutionRecordStatus::Aborted);
+            assert!(cancelled.started_at.is_none());
+            assert!(cancelled.outputs.is_empty());
+
+            assert_eq!(
+                session
+                    .execution_record(&failed.msg_id)
+                    .map(|record| record.code.as_str()),
+                Some("print(1)")
+            );
+            assert_eq!(
+                session
+                    .execution_record_for_count(1)
+                    .map(|record| record.msg_id.as_str()),
+                Some(failed.msg_id.as_str())
+            );
+        });
     }
 
-    pub fn interrupt(&mut self, cx: &mut Context<Self>) {
-        match &mut self.kernel {
-            Kernel::RunningKernel(_kernel) => {
-                self.send(InterruptRequest {}.into(), cx).ok();
-            }
-            Kernel::StartingKernel(_task) => {
-                // NOTE: If we switch to a literal queue instead of chaining on to the task, clear all queued executions
-            }
-            _ => {}
-        }
+    #[gpui::test]
+    async fn test_execution_records_of_aborted_executions(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        start_fake_kernel(
+            &session,
+            FakeKernelBehavior::replying_with([
+                stream("working\n"),
+                FakeKernelStep::Delay(Duration::from_secs(10)),
+            ]),
+            &mut cx,
+        );
+
+        execute_line(&editor, &session, 0, &mut cx);
+        execute_line(&editor, &session, 1, &mut cx);
+        cx.run_until_parked();
+        session.update(&mut cx, |session, cx| {
+            session.abort_in_flight_executions(cx);
+        });
+        // Messages the old kernel sends afterwards don't change the records.
+        cx.executor().advance_clock(Duration::from_secs(10));
+        cx.run_until_parked();
+
+        session.read_with(&cx, |session, _| {
+            let records: Vec<_> = session.execution_records().collect();
+            let [running, queued] = records.as_slice() else {
+                panic!("expected two execution records");
+            };
+            assert_eq!(running.status, ExecutionRecordStatus::Aborted);
+            assert!(running.started_at.is_some());
+            assert!(running.finished_at.is_none());
+            assert!(matches!(
+                running.outputs.as_slice(),
+                [RecordedOutput::Stream { text, .. }] if text == "working\n"
+            ));
+            assert_eq!(queued.status, ExecutionRecordStatus::Aborted);
+            assert!(queued.started_at.is_none());
+            // The running execution's reply and idle status have arrived, so
+            // only the queued one, which was never sent, is still dropped.
+            assert_eq!(session.aborted_executions.len(), 1);
+        });
+
+        // Executions submitted while the kernel can't run them are recorded
+        // as aborted right away.
+        session.update(&mut cx, |session, _| session.kernel = Kernel::Shutdown);
+        execute_line(&editor, &session, 2, &mut cx);
+        session.read_with(&cx, |session, _| {
+            let record = session.execution_records().next_back().unwrap();
+            assert_eq!(record.code, "print(3)");
+            assert_eq!(record.status, ExecutionRecordStatus::Aborted);
+        });
+
+        // A replaced kernel can't send anything for its executions.
+        session.update(&mut cx, |session, cx| session.kernel(Kernel::Shutdown, cx));
+        session.read_with(&cx, |session, _| {
+            assert!(session.aborted_executions.is_empty());
+        });
     }
 
-    pub fn kernel(&mut self, kernel: Kernel, cx: &mut Context<Self>) {
-        if let Kernel::Shutdown = kernel {
-            cx.emit(SessionEvent::Shutdown(self.editor.clone()));
-        }
+    #[gpui::test]
+    async fn test_spilled_outputs_are_dropped_from_blocks(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        cx.update(|_, cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.repl.get_or_insert_default().outputs_kept_in_memory = Some(1);
+                });
+            });
+        });
+        let large_output = "x".repeat(32 * 1024);
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior::replying_with([stream(&large_output)]),
+            &mut cx,
+        );
 
-        let kernel_status = KernelStatus::from(&kernel).to_string();
-        let kernel_language = self.kernel_specification.language();
+        execute_line(&editor, &session, 0, &mut cx);
+        cx.run_until_parked();
+        let views = execution_views(&session, &received, &cx);
+        execute_line(&editor, &session, 1, &mut cx);
+        cx.run_until_parked();
 
-        telemetry::event!(
-            "Kernel Status Changed",
-            kernel_language,
-            kernel_status,
-            repl_session_id = cx.entity_id().to_string(),
+        // The first execution's outputs were written to disk, and its block
+        // no longer holds them.
+        let spilled_view = views[0].clone();
+        spilled_view.read_with(&cx, |view, _| {
+            assert!(view.outputs_spilled());
+            assert!(view.outputs.is_empty());
+        });
+
+        // Asking for them reads them back.
+        spilled_view.update(&mut cx, |_, cx| cx.emit(ShowSpilledOutputs));
+        cx.run_until_parked();
+        spilled_view.read_with(&cx, |view, cx| {
+            assert!(!view.outputs_spilled());
+            let text = view.output_as_stream_text(cx).unwrap();
+            assert_eq!(
+                text.chars().filter(|ch| *ch == 'x').count(),
+                large_output.len()
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_export_notebook(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        start_fake_kernel(
+            &session,
+            FakeKernelBehavior::replying_with([stream("printed\n")]),
+            &mut cx,
         );
+        execute_line(&editor, &session, 0, &mut cx);
+        execute_line(&editor, &session, 1, &mut cx);
+        cx.run_until_parked();
 
-        self.kernel = kernel;
+        let exported = session
+            .read_with(&cx, |session, cx| session.export_notebook(cx))
+            .await
+            .unwrap();
+        let Ok(nbformat::Notebook::V4(notebook)) = nbformat::parse_notebook(&exported) else {
+            panic!("expected an nbformat 4 notebook");
+        };
+        let kernelspec = notebook.metadata.kernelspec.unwrap();
+        assert_eq!(kernelspec.name, "python3");
+        assert_eq!(kernelspec.display_name, "Python 3");
+
+        let sources = notebook
+            .cells
+            .iter()
+            .map(|cell| match cell {
+                nbformat::v4::Cell::Code {
+                    source, outputs, ..
+                } => {
+                    assert_eq!(outputs.len(), 1);
+                    source.join("")
+                }
+                _ => panic!("expected only code cells"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sources, ["print(1)", "print(2)"]);
     }
 
-    pub fn shutdown(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let kernel = std::mem::replace(&mut self.kernel, Kernel::ShuttingDown);
+    #[gpui::test]
+    async fn test_export_notebook_with_markdown_cells(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior::replying_with([stream("printed\n")]),
+            &mut cx,
+        );
+        cx.update(|_, cx| {
+            ReplStore::init(FakeFs::new(cx.background_executor().clone()), cx);
+            ReplStore::global(cx).update(cx, |store, _| {
+                store.insert_session(editor.entity_id(), session.clone());
+            });
+        });
+        let python = languages::language("python", tree_sitter_python::LANGUAGE.into());
+        editor.update(&mut cx, |editor, cx| {
+            let buffer = editor.buffer().read(cx).as_singleton().unwrap();
+            buffer.update(cx, |buffer, cx| {
+                buffer.set_text(
+                    indoc::indoc! {r#"
+                        # %% [markdown]
+                        # # Title
+                        #
+                        # Some *text*.
 
-        match kernel {
-            Kernel::RunningKernel(mut kernel) => {
-                let mut request_tx = kernel.request_tx();
+                        # %%
+                        print(1)
 
-                let forced = kernel.force_shutdown(window, cx);
+                        # %% [raw]
+                        # raw text
 
-                cx.spawn(async move |this, cx| {
-                    let message: JupyterMessage = ShutdownRequest { restart: false }.into();
-                    request_tx.try_send(message).ok();
+                        # %%
+                        print(2)
+                    "#},
+                    cx,
+                );
+                buffer.set_language(Some(python), cx);
+            });
+        });
 
-                    forced.await.log_err();
+        cx.update(|window, cx| crate::run_all_cells(editor.downgrade(), window, cx))
+            .unwrap();
+        cx.run_until_parked();
 
-                    // Give the kernel a bit of time to clean up
-                    cx.background_executor().timer(Duration::from_secs(3)).await;
+        // Markdown and raw cells never reach the kernel.
+        let codes = received
+            .take()
+            .into_iter()
+            .filter_map(|message| match message.content {
+                JupyterMessageContent::ExecuteRequest(request) if !request.silent => {
+                    Some(request.code)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(codes, ["# %%\nprint(1)", "# %%\nprint(2)"]);
 
-                    this.update(cx, |session, cx| {
-                        session.clear_outputs(cx);
-                        session.kernel(Kernel::Shutdown, cx);
-                        cx.notify();
-                    })
-                    .ok();
-                })
-                .detach();
-            }
-            _ => {
-                self.kernel(Kernel::Shutdown, cx);
-            }
-        }
-        cx.notify();
+        let exported = session
+            .read_with(&cx, |session, cx| session.export_notebook(cx))
+            .await
+            .unwrap();
+        let Ok(nbformat::Notebook::V4(notebook)) = nbformat::parse_notebook(&exported) else {
+            panic!("expected an nbformat 4 notebook");
+        };
+        let cells = notebook
+            .cells
+            .iter()
+            .map(|cell| match cell {
+                nbformat::v4::Cell::Markdown { source, .. } => ("markdown", source.join("")),
+                nbformat::v4::Cell::Code { source, .. } => ("code", source.join("")),
+                nbformat::v4::Cell::Raw { source, .. } => ("raw", source.join("")),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            cells,
+            [
+                ("markdown", "# Title\n\nSome *text*.".to_string()),
+                ("code", "# %%\nprint(1)".to_string()),
+                ("raw", "raw text".to_string()),
+                ("code", "# %%\nprint(2)".to_string()),
+            ]
+        );
     }
 
-    pub fn restart(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let kernel = std::mem::replace(&mut self.kernel, Kernel::Restarting);
+    #[gpui::test]
+    async fn test_missing_idle_status_finishes_execution(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        // The kernel replies but never reports going idle, and keeps
+        // sending output for a while after the reply. An error reply means
+        // no variables query follows it.
+        let on_execute = [
+            FakeKernelStep::status(ExecutionState::Busy),
+            stream("printed\n"),
+        ]
+        .into_iter()
+        .chain(FakeKernelStep::execute_reply("error"))
+        .chain([
+            FakeKernelStep::Delay(Duration::from_millis(1500)),
+            stream("late\n"),
+            FakeKernelStep::Delay(Duration::from_millis(1500)),
+            stream("later\n"),
+        ])
+        .collect();
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior {
+                on_execute,
+                ignore_interrupts: false,
+                ignore_shutdowns: false,
+            },
+            &mut cx,
+        );
 
-        match kernel {
-            Kernel::Restarting => {
-                // Do nothing if already restarting
-            }
-            Kernel::RunningKernel(mut kernel) => {
-                let mut request_tx = kernel.request_tx();
+        let execute_row = |row: u32, cx: &mut VisualTestContext| {
+            let anchor_range = editor.update(cx, |editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                snapshot.anchor_before(Point::new(row, 0))
+                    ..snapshot.anchor_after(Point::new(row, 8))
+            });
+            session.update_in(cx, |session, window, cx| {
+                session.execute(
+                    format!("print({})", row + 1),
+                    anchor_range,
+                    None,
+                    false,
+                    ExecutionOptions {
+                        stop_on_error: false,
+                        ..ExecutionOptions::default()
+                    },
+                    window,
+                    cx,
+                );
+            });
+        };
+        let sent_codes = || {
+            received
+                .all()
+                .into_iter()
+                .filter_map(|message| match message.content {
+                    JupyterMessageContent::ExecuteRequest(request) if !request.silent => {
+                        Some(request.code)
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let is_complete = |code: &str, cx: &VisualTestContext| {
+            session.read_with(cx, |session, _| {
+                session
+                    .execution_records()
+                    .find(|record| record.code == code)
+                    .is_some_and(|record| record.is_complete())
+            })
+        };
 
-                let forced = kernel.force_shutdown(window, cx);
+        execute_row(0, &mut cx);
+        cx.run_until_parked();
+        assert_eq!(sent_codes(), ["print(1)"]);
 
-                cx.spawn_in(window, async move |this, cx| {
-                    // Send shutdown request with restart flag
-                    log::debug!("restarting kernel");
-                    let message: JupyterMessage = ShutdownRequest { restart: true }.into();
-                    request_tx.try_send(message).ok();
+        // Output after the reply restarts the wait for the idle status.
+        for _ in 0..2 {
+            cx.executor().advance_clock(Duration::from_millis(1500));
+            cx.run_until_parked();
+        }
+        cx.executor().advance_clock(Duration::from_millis(1900));
+        cx.run_until_parked();
+        assert!(!is_complete("print(1)", &cx));
+        assert_eq!(kernel_status(&session, &cx), "Busy");
 
-                    // Wait for kernel to shutdown
-                    cx.background_executor().timer(Duration::from_secs(1)).await;
+        // Once the kernel has been quiet for the whole timeout, the
+        // execution is finished.
+        cx.executor().advance_clock(Duration::from_millis(200));
+        cx.run_until_parked();
+        assert!(is_complete("print(1)", &cx));
 
-                    // Force kill the kernel if it hasn't shut down
-                    forced.await.log_err();
+        // With nothing else pending, the kernel is shown as idle again.
+        cx.executor().advance_clock(Duration::from_secs(5));
+        cx.run_until_parked();
+        assert_eq!(kernel_status(&session, &cx), "Idle");
 
-                    // Start a new kernel
-                    this.update_in(cx, |session, window, cx| {
-                        // TODO: Differentiate between restart and restart+clear-outputs
-                        session.clear_outputs(cx);
-                        session.start_kernel(window, cx);
-                    })
-                    .ok();
-                })
-                .detach();
-            }
-            _ => {
-                self.clear_outputs(cx);
-                self.start_kernel(window, cx);
-            }
+        // The queue doesn't wait for the idle status: the next execution is
+        // sent once the kernel replied, and its reply finishes the one
+        // before it.
+        execute_row(1, &mut cx);
+        execute_row(2, &mut cx);
+        cx.run_until_parked();
+        assert_eq!(sent_codes(), ["print(1)", "print(2)", "print(3)"]);
+        for _ in 0..2 {
+            cx.executor().advance_clock(Duration::from_millis(1500));
+            cx.run_until_parked();
         }
-        cx.notify();
+        assert!(is_complete("print(2)", &cx));
+        assert!(!is_complete("print(3)", &cx));
+
+        cx.executor().advance_clock(Duration::from_secs(10));
+        cx.run_until_parked();
+        session.read_with(&cx, |session, cx| {
+            assert_eq!(session.pending_execution_count(cx), 0);
+            assert!(
+                session
+                    .execution_records()
+                    .all(|record| record.is_complete())
+            );
+        });
     }
-}
 
-pub enum SessionEvent {
-    Shutdown(WeakEntity<Editor>),
-}
+    #[gpui::test]
+    async fn test_interrupt_request_that_cannot_be_sent(cx: &mut TestAppContext) {
+        let (_editor, session, request_rx, mut cx) = init_session(cx).await;
+        drop(request_rx);
 
-impl EventEmitter<SessionEvent> for Session {}
+        session.update(&mut cx, |session, cx| session.interrupt(cx));
+        cx.run_until_parked();
+        session.read_with(&cx, |session, _| {
+            assert!(session.pending_interrupt.is_none());
+            let error = session.interrupt_error().unwrap();
+            assert!(error.starts_with("Couldn't interrupt the kernel"));
+        });
+    }
 
-impl Render for Session {
-    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let (status_text, interrupt_button) = match &self.kernel {
-            Kernel::RunningKernel(kernel) => (
-                kernel
-                    .kernel_info()
-                    .as_ref()
-                    .map(|info| info.language_info.name.clone()),
-                Some(
-                    Button::new("interrupt", "Interrupt")
-                        .style(ButtonStyle::Subtle)
-                        .on_click(cx.listener(move |session, _, _, cx| {
-                            session.interrupt(cx);
-                        })),
-                ),
-            ),
-            Kernel::StartingKernel(_) => (Some("Starting".into()), None),
-            Kernel::ErroredLaunch(err) => (Some(format!("Error: {err}")), None),
-            Kernel::ShuttingDown => (Some("Shutting Down".into()), None),
-            Kernel::Shutdown => (Some("Shutdown".into()), None),
-            Kernel::Restarting => (Some("Restarting".into()), None),
-        };
+    #[gpui::test]
+    async fn test_fake_kernel_interrupt(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior::replying_with([FakeKernelStep::Delay(Duration::from_secs(10))]),
+            &mut cx,
+        );
 
-        KernelListItem::new(self.kernel_specification.clone())
-            .status_color(match &self.kernel {
-                Kernel::RunningKernel(kernel) => match kernel.execution_state() {
-                    ExecutionState::Idle => Color::Success,
-                    ExecutionState::Busy => Color::Modified,
-                    ExecutionState::Unknown => Color::Modified,
-                    ExecutionState::Starting => Color::Modified,
-                    ExecutionState::Restarting => Color::Modified,
-                    ExecutionState::Terminating => Color::Disabled,
-                    ExecutionState::AutoRestarting => Color::Modified,
-                    ExecutionState::Dead => Color::Disabled,
-                    ExecutionState::Other(_) => Color::Modified,
-                },
-                Kernel::StartingKernel(_) => Color::Modified,
-                Kernel::ErroredLaunch(_) => Color::Error,
-                Kernel::ShuttingDown => Color::Modified,
-                Kernel::Shutdown => Color::Disabled,
-                Kernel::Restarting => Color::Modified,
-            })
-            .child(Label::new(self.kernel_specification.name()))
-            .children(status_text.map(|status_text| Label::new(format!("({status_text})"))))
-            .button(
-                Button::new("shutdown", "Shutdown")
-                    .style(ButtonStyle::Subtle)
-                    .disabled(self.kernel.is_shutting_down())
-                    .on_click(cx.listener(move |session, _, window, cx| {
-                        session.shutdown(window, cx);
-                    })),
-            )
-            .buttons(interrupt_button)
-    }
-}
+        execute_line(&editor, &session, 0, &mut cx);
+        cx.run_until_parked();
+        assert_eq!(kernel_status(&session, &cx), "Busy");
 
-impl KernelSession for Session {
-    fn route(&mut self, message: &JupyterMessage, window: &mut Window, cx: &mut Context<Self>) {
-        let parent_message_id = match message.parent_header.as_ref() {
-            Some(header) => &header.msg_id,
-            None => return,
-        };
+        session.update(&mut cx, |session, cx| session.interrupt(cx));
+        cx.run_until_parked();
+        assert_eq!(kernel_status(&session, &cx), "Idle");
+        session.read_with(&cx, |session, cx| {
+            assert_eq!(session.pending_execution_count(cx), 0);
+            assert!(session.pending_interrupt.is_none());
+            assert_eq!(session.interrupt_error(), None);
+        });
+        assert!(matches!(
+            received.all().last().map(|message| &message.content),
+            Some(JupyterMessageContent::InterruptRequest(_))
+        ));
 
-        match &message.content {
-            JupyterMessageContent::Status(status) => {
-                self.kernel.set_execution_state(&status.execution_state);
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior {
+                ignore_interrupts: true,
+                ignore_shutdowns: false,
+                ..FakeKernelBehavior::replying_with([FakeKernelStep::Delay(Duration::from_secs(
+                    10,
+                ))])
+            },
+            &mut cx,
+        );
+        execute_line(&editor, &session, 1, &mut cx);
+        cx.run_until_parked();
+        session.update(&mut cx, |session, cx| session.interrupt(cx));
+        cx.run_until_parked();
+        assert_eq!(kernel_status(&session, &cx), "Busy");
 
-                telemetry::event!(
-                    "Kernel Status Changed",
-                    kernel_language = self.kernel_specification.language(),
-                    kernel_status = KernelStatus::from(&self.kernel).to_string(),
-                    repl_session_id = cx.entity_id().to_string(),
-                );
+        // A kernel that never answers the interrupt request is reported.
+        cx.executor().advance_clock(INTERRUPT_REPLY_TIMEOUT);
+        cx.run_until_parked();
+        session.read_with(&cx, |session, _| {
+            assert!(session.pending_interrupt.is_none());
+            assert!(session.interrupt_error().is_some());
+        });
 
-                cx.notify();
-            }
-            JupyterMessageContent::KernelInfoReply(reply) => {
-                self.kernel.set_kernel_info(reply);
-                cx.notify();
-            }
-            JupyterMessageContent::UpdateDisplayData(update) => {
-                let display_id = if let Some(display_id) = update.transient.display_id.clone() {
-                    display_id
-                } else {
-                    return;
-                };
+        cx.executor().advance_clock(Duration::from_secs(10));
+        cx.run_until_parked();
+        assert_eq!(kernel_status(&session, &cx), "Idle");
+        assert_eq!(received.all().len(), 2);
+    }
 
-                self.blocks.iter_mut().for_each(|(_, block)| {
-                    block.execution_view.update(cx, |execution_view, cx| {
-                        execution_view.update_display_data(&update.data, &display_id, window, cx);
-                    });
-                });
-                return;
-            }
-            _ => {}
-        }
+    #[gpui::test]
+    async fn test_fake_kernel_death(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior::replying_with([
+                stream("working\n"),
+                FakeKernelStep::Delay(Duration::from_secs(1)),
+                FakeKernelStep::Die,
+            ]),
+            &mut cx,
+        );
 
-        if let Some(block) = self.blocks.get_mut(parent_message_id) {
-            block.handle_message(message, window, cx);
-        }
+        execute_line(&editor, &session, 0, &mut cx);
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.run_until_parked();
+        assert_eq!(kernel_status(&session, &cx), "Error");
+
+        let requests = received.all();
+        session.read_with(&cx, |session, cx| {
+            let execution_view = session.blocks[&requests[0].header.msg_id]
+                .execution_view
+                .read(cx);
+            assert!(matches!(
+                execution_view.status,
+                ExecutionStatus::KernelErrored(_)
+            ));
+            assert_eq!(execution_view.outputs.len(), 1);
+        });
     }
 
-    fn kernel_errored(&mut self, error_message: String, cx: &mut Context<Self>) {
-        self.kernel_errored(error_message, cx);
+    #[gpui::test]
+    async fn test_fake_kernel_shutdown(cx: &mut TestAppContext) {
+        let (editor, session, _request_rx, mut cx) = init_session(cx).await;
+        let received = start_fake_kernel(
+            &session,
+            FakeKernelBehavior::replying_with([FakeKernelStep::Delay(Duration::from_secs(60))]),
+            &mut cx,
+        );
+
+        execute_line(&editor, &session, 0, &mut cx);
+        cx.run_until_parked();
+        session.update_in(&mut cx, |session, window, cx| session.shutdown(window, cx));
+        assert_eq!(kernel_status(&session, &cx), "Shutting Down");
+
+        cx.executor().advance_clock(Duration::from_secs(3));
+        cx.run_until_parked();
+        assert_eq!(kernel_status(&session, &cx), "Shutdown");
+        assert!(received.all().iter().any(|message| matches!(
+            &message.content,
+            JupyterMessageContent::ShutdownRequest(request) if !request.restart
+        )));
+        session.read_with(&cx, |session, cx| {
+            assert_eq!(session.pending_execution_count(cx), 0);
+        });
     }
 }
@@ -0,0 +1,185 @@
+//! Introspection of the variables defined in a live kernel.
+//!
+//! Each supported language has a snippet that the session runs as a silent
+//! execution after the user's code completes. The snippet prints a single
+//! marked line of JSON describing the kernel's global variables, which is
+//! parsed into [`VariableSummary`] values.
+//!
+//! Snippets only ever report a variable's type and size or shape. Values are
+//! never serialized, so very large collections cost no more to list than small
+//! ones.
+
+use serde::Deserialize;
+
+/// Prefixes the line an introspection snippet prints, so that output from
+/// anything else running in the kernel is ignored.
+pub(crate) const VARIABLES_MARKER: &str = "__zed_variables__";
+
+/// The most variables a snippet reports, to keep huge namespaces cheap.
+const MAX_VARIABLES: usize = 500;
+
+/// A variable defined in the kernel's global namespace.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct VariableSummary {
+    pub name: String,
+    /// The name of the value's type, as the kernel's language spells it.
+    #[serde(rename = "type")]
+    pub type_name: String,
+    /// The number of items, or the shape of arrays and data frames, such as
+    /// `3 x 4`. `None` for values without a size.
+    pub size: Option<String>,
+}
+
+const PYTHON_SNIPPET: &str = r#"
+def _zed_variables():
+    import json as _json
+    import types as _types
+
+    summaries = []
+    for name, value in list(globals().items()):
+        if name.startswith("_") or name in ("In", "Out", "exit", "quit", "get_ipython"):
+            continue
+        if isinstance(
+            value,
+            (
+                _types.ModuleType,
+                _types.FunctionType,
+                _types.BuiltinFunctionType,
+                _types.MethodType,
+                type,
+            ),
+        ):
+            continue
+        size = None
+        try:
+            shape = getattr(value, "shape", None)
+            if isinstance(shape, tuple):
+                size = " x ".join(str(dimension) for dimension in shape)
+            elif hasattr(value, "__len__"):
+                size = str(len(value))
+        except Exception:
+            pass
+        summaries.append({"name": name, "type": type(value).__name__, "size": size})
+        if len(summaries) >= MAX_VARIABLES:
+            break
+    print(MARKER + _json.dumps(summaries))
+
+
+try:
+    _zed_variables()
+finally:
+    del _zed_variables
+"#;
+
+const R_SNIPPET: &str = r#"
+local({
+    json_string <- function(value) encodeString(value, quote = '"')
+    names <- head(ls(envir = globalenv()), MAX_VARIABLES)
+    entries <- vapply(names, function(name) {
+        value <- get(name, envir = globalenv())
+        size <- if (!is.null(dim(value))) {
+            json_string(paste(dim(value), collapse = " x "))
+        } else if (is.function(value)) {
+            "null"
+        } else {
+            json_string(as.character(length(value)))
+        }
+        sprintf('{"name":%s,"type":%s,"size":%s}', json_string(name), json_string(class(value)[1]), size)
+    }, character(1))
+    cat(MARKER, "[", paste(entries, collapse = ","), "]\n", sep = "")
+})
+"#;
+
+const JULIA_SNIPPET: &str = r##"
+let
+    json_string(value) = "\"" * replace(string(value), "\\" => "\\\\", "\"" => "\\\"", "\n" => "\\n") * "\""
+    entries = String[]
+    for name in names(Main)
+        name in (:Base, :Core, :Main, :ans) && continue
+        startswith(string(name), "#") && continue
+        isdefined(Main, name) || continue
+        value = getfield(Main, name)
+        (value isa Module || value isa Function || value isa Type) && continue
+        size = if value isa AbstractArray
+            json_string(join(Base.size(value), " x "))
+        elseif applicable(length, value)
+            json_string(length(value))
+        else
+            "null"
+        end
+        push!(entries, "{\"name\":$(json_string(name)),\"type\":$(json_string(typeof(value))),\"size\":$size}")
+        length(entries) >= MAX_VARIABLES && break
+    end
+    println(MARKER, "[", join(entries, ","), "]")
+end
+"##;
+
+/// The snippet that lists variables for a kernel language, or `None` if the
+/// language isn't supported.
+pub(crate) fn introspection_snippet(language: &str) -> Option<String> {
+    let snippet = match language.to_lowercase().as_str() {
+        "python" => PYTHON_SNIPPET,
+        "r" => R_SNIPPET,
+        "julia" => JULIA_SNIPPET,
+        _ => return None,
+    };
+    // Every supported language accepts a double quoted string literal.
+    Some(
+        snippet
+            .replace("MAX_VARIABLES", &MAX_VARIABLES.to_string())
+            .replace("MARKER", &format!("{VARIABLES_MARKER:?}")),
+    )
+}
+
+/// Reads the marked line printed by an introspection snippet.
+pub(crate) fn parse_variables(output: &str) -> Option<Vec<VariableSummary>> {
+    let json = output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix(VARIABLES_MARKER))?;
+    serde_json::from_str(json).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_introspection_snippets() {
+        for language in ["python", "Python", "r", "julia"] {
+            let snippet = introspection_snippet(language).unwrap();
+            assert!(snippet.contains(&format!("{VARIABLES_MARKER:?}")));
+            assert!(snippet.contains(&MAX_VARIABLES.to_string()));
+            assert!(!snippet.contains("MAX_VARIABLES"));
+        }
+        assert_eq!(introspection_snippet("typescript"), None);
+    }
+
+    #[test]
+    fn test_parse_variables() {
+        let output = format!(
+            "unrelated output\n{VARIABLES_MARKER}{}\n",
+            r#"[{"name":"df","type":"DataFrame","size":"100 x 3"},{"name":"x","type":"int","size":null}]"#
+        );
+        assert_eq!(
+            parse_variables(&output),
+            Some(vec![
+                VariableSummary {
+                    name: "df".to_string(),
+                    type_name: "DataFrame".to_string(),
+                    size: Some("100 x 3".to_string()),
+                },
+                VariableSummary {
+                    name: "x".to_string(),
+                    type_name: "int".to_string(),
+                    size: None,
+                },
+            ])
+        );
+
+        assert_eq!(parse_variables("no marker\n"), None);
+        assert_eq!(
+            parse_variables(&format!("{VARIABLES_MARKER}not json")),
+            None
+        );
+    }
+}
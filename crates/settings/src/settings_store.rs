@@ -41,7 +41,9 @@ use crate::{
     },
 };
 
-use settings_json::{infer_json_indent_size, update_value_in_json_text};
+use settings_json::{
+    infer_json_indent_size, replace_value_in_json_text, update_value_in_json_text_by_entry,
+};
 
 pub const LSP_SETTINGS_SCHEMA_URL_PREFIX: &str = "zed://schemas/settings/lsp/";
 
@@ -621,6 +623,20 @@ impl SettingsStore {
         })
     }
 
+    /// Rewrites the settings at `key_paths` in the user settings file in the
+    /// shape Zed writes them. See [`SettingsStore::new_text_for_normalization`].
+    pub fn normalize_settings_file(
+        &self,
+        fs: Arc<dyn Fs>,
+        key_paths: &'static [&'static [&'static str]],
+    ) -> oneshot::Receiver<Result<()>> {
+        self.update_settings_file_inner(fs, move |old_text: String, cx: AsyncApp| {
+            cx.read_global(|store: &SettingsStore, _cx| {
+                store.new_text_for_normalization(old_text, key_paths)
+            })
+        })
+    }
+
     pub fn import_vscode_settings(
         &self,
         fs: Arc<dyn Fs>,
@@ -862,7 +878,9 @@ impl SettingsStore {
         let mut edits = Vec::new();
         let tab_size = infer_json_indent_size(&text);
         let mut text = text.to_string();
-        update_value_in_json_text(
+        // Entries of lists such as `ssh_connections` are edited one by one, so
+        // that saving one doesn't rewrite the others.
+        update_value_in_json_text_by_entry(
             &mut text,
             &mut key_path,
             tab_size,
@@ -873,6 +891,46 @@ impl SettingsStore {
         Ok(edits)
     }
 
+    /// Rewrites the settings at `key_paths` in a JSON settings file in the
+    /// shape Zed writes them: each with every field it has a value for,
+    /// including ones older entries leave out, in the usual order. Only the
+    /// settings at `key_paths` are rewritten, and ones the file doesn't set
+    /// are left out.
+    ///
+    /// Updates, by contrast, only ever rewrite what they change.
+    pub fn new_text_for_normalization(
+        &self,
+        old_text: String,
+        key_paths: &[&[&str]],
+    ) -> Result<String> {
+        if old_text.trim().is_empty() {
+            return Ok(old_text);
+        }
+        let (content, parse_status) = UserSettingsContent::parse_json(&old_text);
+        if let ParseStatus::Failed { error } = &parse_status {
+            log::error!("Failed to parse settings for normalization: {error}");
+        }
+        let content = content
+            .context("Settings file could not be parsed. Fix syntax errors before normalizing.")?;
+        let value = serde_json::to_value(&content)?;
+
+        let tab_size = infer_json_indent_size(&old_text);
+        let mut text = old_text;
+        for key_path in key_paths {
+            let Some(normalized) = key_path
+                .iter()
+                .try_fold(&value, |value, key| value.get(key))
+                .filter(|value| !value.is_null())
+            else {
+                continue;
+            };
+            let (range, replacement) =
+                replace_value_in_json_text(&text, key_path, tab_size, Some(normalized), None);
+            text.replace_range(range, &replacement);
+        }
+        Ok(text)
+    }
+
     /// Mutates the default settings in place and recomputes all setting values.
     pub fn update_default_settings(
         &mut self,
@@ -2126,6 +2184,81 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    fn test_update_leaves_other_list_entries_alone(cx: &mut App) {
+        let store = SettingsStore::new(cx, &test_settings());
+        let first = r#"{
+                    // jump box
+                    "host": "bastion",   "args": ["-A"]
+                }"#;
+        let third = r#"{"nickname": "db", "host": "db.internal", "port": 2222}"#;
+        let old_text = format!(
+            r#"{{
+            "ssh_connections": [
+                {first},
+                {{
+                    "host": "web",
+                    "nickname": "old"
+                }},
+                {third}
+            ]
+        }}"#
+        );
+
+        let new_text = store
+            .new_text_for_update(old_text.clone(), |content| {
+                if let Some(connection) = content
+                    .remote
+                    .ssh_connections
+                    .as_mut()
+                    .and_then(|connections| connections.get_mut(1))
+                {
+                    connection.nickname = Some("staging".to_string());
+                }
+            })
+            .unwrap();
+
+        pretty_assertions::assert_eq!(new_text, old_text.replace(r#""old""#, r#""staging""#));
+        assert!(new_text.contains(first));
+        assert!(new_text.contains(third));
+    }
+
+    #[gpui::test]
+    fn test_normalize_settings(cx: &mut App) {
+        let store = SettingsStore::new(cx, &test_settings());
+        let old_text = r#"{
+            // Font
+            "buffer_font_size":   14,
+            "ssh_connections": [
+                {"nickname": "web", "host": "web"}
+            ]
+        }"#
+        .unindent();
+        let key_paths: &[&[&str]] = &[&["ssh_connections"], &["dev_container_connections"]];
+
+        let new_text = store
+            .new_text_for_normalization(old_text.clone(), key_paths)
+            .unwrap();
+        assert!(new_text.starts_with("{\n    // Font\n    \"buffer_font_size\":   14,\n"));
+        assert!(!new_text.contains("dev_container_connections"));
+        let value: serde_json::Value = crate::parse_json_with_comments(&new_text).unwrap();
+        assert_eq!(
+            value["ssh_connections"][0],
+            serde_json::json!({
+                "host": "web",
+                "nickname": "web",
+                "args": [],
+                "projects": [],
+            })
+        );
+        assert_eq!(
+            store
+                .new_text_for_normalization(new_text.clone(), key_paths)
+                .unwrap(),
+            new_text
+        );
+    }
+
     #[gpui::test]
     fn test_edits_for_update_preserves_unknown_keys(cx: &mut App) {
         let mut store = SettingsStore::new(cx, &test_settings());
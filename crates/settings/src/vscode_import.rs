@@ -515,6 +515,7 @@ impl VsCodeSettings {
             worktree: self.worktree_settings_content(),
             lsp: Default::default(),
             terminal: None,
+            repl: None,
             dap: Default::default(),
             context_servers: self.context_servers(),
             context_server_timeout: None,
@@ -790,6 +791,7 @@ impl VsCodeSettings {
             cursor_position_button: None,
             line_endings_button: None,
             active_encoding_button: None,
+            remote_connection_button: None,
         })
     }
 
@@ -952,6 +952,26 @@ pub struct JupyterContent {
     ///
     /// Default: `{}`
     pub kernel_selections: Option<HashMap<String, String>>,
+
+    /// Rules for picking the default kernel for each language. Rules are tried
+    /// in order and the first one that matches an available kernel picks it.
+    /// A kernel chosen in the kernel picker overrides these rules.
+    ///
+    /// Default: `{}`
+    pub kernel_selection_rules: Option<HashMap<String, Vec<KernelSelectionRule>>>,
+}
+
+/// A rule that matches the kernels that may be picked as a language's default.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema, MergeFrom)]
+#[serde(rename_all = "snake_case")]
+pub enum KernelSelectionRule {
+    /// Matches kernels whose name matches a glob, such as `python3*`.
+    Name(String),
+    /// Matches Python environments of a kind, such as `uv`, `venv` or `Conda`.
+    EnvironmentKind(String),
+    /// Matches kernels whose path starts with a prefix. Relative prefixes,
+    /// such as `.venv/`, are resolved against the worktree root.
+    PathPrefix(String),
 }
 
 /// Whether to allow drag and drop text selection in buffer.
@@ -1140,6 +1140,109 @@ pub struct RemoteSettingsContent {
     pub dev_container_connections: Option<Vec<DevContainerConnection>>,
     pub read_ssh_config: Option<bool>,
     pub use_podman: Option<bool>,
+    /// The oldest docker or podman version known to work with dev containers.
+    /// Older versions are reported in the log but still used.
+    ///
+    /// Default: "20.10.0" for docker, "4.0.0" for podman
+    pub dev_container_min_cli_version: Option<String>,
+    /// Which section of the remote projects modal is focused when it opens.
+    ///
+    /// Default: last_used
+    pub remote_servers_default_section: Option<RemoteServersSection>,
+    /// What the remote projects modal does as soon as it opens.
+    ///
+    /// Default: none
+    pub remote_servers_default_action: Option<RemoteServersDefaultAction>,
+    /// How much room each server takes up in the remote projects modal.
+    ///
+    /// Default: comfortable
+    pub remote_servers_density: Option<RemoteServersDensity>,
+    /// Whether projects opened from the remote projects modal open in a new
+    /// window rather than replacing the current one.
+    ///
+    /// Default: false
+    pub remote_servers_open_in_new_window: Option<bool>,
+    /// Whether the remote projects modal checks that the jump hosts saved SSH
+    /// servers connect through can be reached, and flags the servers behind
+    /// one that can't.
+    ///
+    /// Default: true
+    pub remote_servers_check_jump_hosts: Option<bool>,
+    /// Seconds between keepalive messages sent over idle SSH connections, for
+    /// connections that don't set their own `keepalive_interval`. 0 disables
+    /// keepalive messages, and null leaves the interval to the ssh config.
+    ///
+    /// Default: null
+    pub ssh_keepalive_interval: Option<u32>,
+    /// How many keepalive messages may go unanswered before an SSH connection
+    /// is considered lost, for connections that don't set their own
+    /// `keepalive_count_max`. 0 or null leaves the limit to ssh.
+    ///
+    /// Default: null
+    pub ssh_keepalive_count_max: Option<u32>,
+    /// How many more times to try transferring the remote server binary
+    /// after a transfer fails, resuming from the bytes already transferred.
+    ///
+    /// Default: 3
+    pub ssh_binary_transfer_retries: Option<u32>,
+    /// The program terminals and interactive commands on SSH servers are
+    /// started in, such as `/bin/bash`, for connections that don't set their
+    /// own `shell`. Unset starts terminals in each server's login shell and
+    /// runs commands without a shell of their own.
+    ///
+    /// Default: null
+    pub ssh_shell: Option<String>,
+    /// Whether terminals and interactive commands on SSH servers start as
+    /// login shells, for connections that don't set their own `login_shell`.
+    ///
+    /// Default: true
+    pub ssh_login_shell: Option<bool>,
+}
+
+/// A section of the remote projects modal.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, MergeFrom,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteServersSection {
+    /// The section that was used most recently.
+    #[default]
+    LastUsed,
+    /// SSH servers.
+    Ssh,
+    /// Dev containers.
+    DevContainer,
+    /// WSL distros.
+    Wsl,
+}
+
+/// What the remote projects modal does as soon as it opens.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, MergeFrom,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteServersDefaultAction {
+    /// Show the list of remote servers.
+    #[default]
+    None,
+    /// Start connecting a new SSH server.
+    ConnectSshServer,
+    /// Start connecting a dev container for the current project.
+    ConnectDevContainer,
+}
+
+/// How much room each server takes up in the remote projects modal.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, MergeFrom,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteServersDensity {
+    /// Show every server with its projects and actions.
+    #[default]
+    Comfortable,
+    /// Show each server on a single line, with its projects and actions only
+    /// once it's opened.
+    Compact,
 }
 
 #[with_fallible_options]
@@ -1151,8 +1254,40 @@ pub struct DevContainerConnection {
     pub remote_user: String,
     pub container_id: String,
     pub use_podman: bool,
+    /// The WSL distro that docker or podman runs the container in, on Windows
+    /// machines where docker is only installed in WSL.
+    pub wsl_distro: Option<String>,
     pub extension_ids: Vec<String>,
     pub remote_env: BTreeMap<String, String>,
+    /// Variables set in the container on top of `remote_env`, which comes
+    /// from devcontainer.json, overriding it where both set one.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra_env: BTreeMap<String, String>,
+    /// The shell to run for terminals and commands in this container, such as
+    /// `/bin/sh` for images without `bash`. Detected on connect when unset.
+    pub shell: Option<String>,
+    /// The shell detected in the container the last time Zed connected to it
+    /// without `shell` set. Later connections use it while the container
+    /// still has it, instead of probing again.
+    pub detected_shell: Option<String>,
+    /// A free-form label, such as `prod` or `staging`, that saved connections
+    /// are grouped by in the remote servers list.
+    pub tag: Option<String>,
+    /// The project folder on the host that the container was started from.
+    pub host_project_root: Option<String>,
+    /// The `devcontainer.json` the container was started with, as a path on
+    /// the host.
+    pub config_path: Option<String>,
+    /// Extra arguments for every `docker exec` into the container, such as
+    /// `--privileged` or `--env-file=/path/to/env`. Each is passed as it is,
+    /// without a shell, so they can't contain shell metacharacters.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_exec_args: Vec<String>,
+    /// Extra arguments for the devcontainer CLI when setting the container up,
+    /// such as `--build-no-cache`. Each is passed as it is, without a shell,
+    /// so they can't contain shell metacharacters.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_cli_args: Vec<String>,
 }
 
 #[with_fallible_options]
@@ -1177,6 +1312,43 @@ pub struct SshConnection {
     /// Timeout in seconds for SSH connection and downloading the remote server binary.
     /// Defaults to 10 seconds if not specified.
     pub connection_timeout: Option<u16>,
+    /// Seconds between keepalive messages sent while the connection is idle,
+    /// like ssh's `ServerAliveInterval`. 0 disables keepalive messages.
+    /// Defaults to `ssh_keepalive_interval` if not specified.
+    pub keepalive_interval: Option<u32>,
+    /// How many keepalive messages may go unanswered before the connection is
+    /// considered lost, like ssh's `ServerAliveCountMax`. 0 leaves the limit
+    /// to ssh. Defaults to `ssh_keepalive_count_max` if not specified.
+    pub keepalive_count_max: Option<u32>,
+    /// The server's operating system, such as `linux` or `macos`. Recorded by
+    /// Zed after each successful connection.
+    pub remote_os: Option<String>,
+    /// The server's architecture, such as `x86_64` or `aarch64`. Recorded by
+    /// Zed after each successful connection.
+    pub remote_arch: Option<String>,
+    /// The version of the remote server binary on the host, as
+    /// `zed-remote-server version` prints it. Recorded by Zed after each
+    /// successful connection.
+    pub remote_server_version: Option<String>,
+    /// A free-form label, such as `prod` or `staging`, that saved connections
+    /// are grouped by in the remote servers list.
+    pub tag: Option<String>,
+    /// The program terminals and interactive commands are started in, such as
+    /// `/bin/bash`. Only the program can be given, without arguments.
+    /// Defaults to `ssh_shell` if not specified.
+    pub shell: Option<String>,
+    /// Whether terminals and interactive commands start as login shells.
+    /// Defaults to `ssh_login_shell` if not specified.
+    pub login_shell: Option<bool>,
+    /// Environment variables the remote server process is started with, such
+    /// as `HTTP_PROXY` for downloading language servers. Values are stored in
+    /// plain text, so passwords belong in the system keychain instead.
+    pub env: Option<BTreeMap<String, String>>,
+    /// The command the server was added with in the remote servers modal,
+    /// such as `ssh -J bastion host -L 5432:db:5432`, so it can be edited and
+    /// run again. Cleared when the connection's arguments change in a way the
+    /// command doesn't reflect.
+    pub original_command: Option<String>,
 }
 
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema, MergeFrom, Debug)]
@@ -1185,6 +1357,15 @@ pub struct WslConnection {
     pub user: Option<String>,
     #[serde(default)]
     pub projects: BTreeSet<RemoteProject>,
+    /// A free-form label, such as `prod` or `staging`, that saved connections
+    /// are grouped by in the remote servers list.
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Environment variables the remote server process is started with.
+    /// Values are stored in plain text, so passwords belong in the system
+    /// keychain instead.
+    #[serde(default)]
+    pub env: Option<BTreeMap<String, String>>,
 }
 
 #[with_fallible_options]
@@ -1232,6 +1413,206 @@ pub struct ReplSettingsContent {
     ///
     /// Default: 0
     pub output_max_height_lines: Option<usize>,
+    /// Whether to grow a selection the kernel reports as incomplete to the
+    /// smallest enclosing complete statements before running it.
+    ///
+    /// Default: true
+    pub expand_incomplete_selections: Option<bool>,
+    /// How long to keep trying to reconnect to a remote kernel after its
+    /// connection drops before giving up.
+    ///
+    /// Default: 60
+    pub remote_reconnect_timeout_seconds: Option<u64>,
+    /// How long to wait, in milliseconds, for a kernel to report that it's
+    /// idle after replying to an execution. Executions the kernel says
+    /// nothing more about for this long are finished anyway, so a kernel
+    /// that never reports being idle doesn't hold up the executions queued
+    /// after them. Set to 0 to wait forever.
+    ///
+    /// Default: 2000
+    pub idle_status_timeout_ms: Option<u64>,
+    /// What to do with kernels on remote Jupyter servers when their workspace
+    /// closes or Zed quits, keyed by server URL. Servers that aren't listed
+    /// have their kernels shut down.
+    ///
+    /// Default: {}
+    pub remote_kernels_on_exit: Option<HashMap<String, RemoteKernelExitBehavior>>,
+    /// Whether to show HTML outputs as the kernel sent them, keyed by where
+    /// the kernel runs: `local` for kernels on this machine, `ssh` and `wsl`
+    /// for kernels started on remote machines, or a Jupyter server's URL.
+    /// HTML from kernels that aren't trusted has scripts, styles, frames,
+    /// event handlers, and other active content removed, keeping the
+    /// formatting that tables and rich text need.
+    ///
+    /// Default: {"local": true}
+    pub trusted_html_sources: Option<HashMap<String, bool>>,
+    /// Where local projects live on remote Jupyter servers, keyed by server
+    /// URL. Each entry maps a local directory to the matching directory on
+    /// the server. Kernels started on a listed server change into the mapped
+    /// directory before running any code.
+    ///
+    /// Default: {}
+    pub remote_project_roots: Option<HashMap<String, HashMap<String, String>>>,
+    /// Environment variables to read from a running kernel when showing its
+    /// runtime info. Variables that aren't listed are never read, so keep
+    /// secrets out of this list.
+    ///
+    /// Default: ["PATH", "VIRTUAL_ENV", "CONDA_PREFIX", "PYTHONPATH"]
+    pub runtime_info_environment_variables: Option<Vec<String>>,
+    /// Largest image output to show, in megabytes. Larger images show their
+    /// plain text fallback instead.
+    ///
+    /// Default: 20
+    pub max_image_size_mb: Option<u64>,
+    /// Largest width or height of an image output to show, in pixels. Larger
+    /// images show their plain text fallback instead.
+    ///
+    /// Default: 16384
+    pub max_image_dimension: Option<u32>,
+    /// Whether to show the SVG version of an image output when the kernel
+    /// also sends a PNG or JPEG.
+    ///
+    /// Default: true
+    pub prefer_svg_images: Option<bool>,
+    /// Shut down a kernel after it has been idle for this many minutes. It
+    /// starts again the next time code runs. Kernels with running executions
+    /// or open comms, such as widgets, are never shut down. Set to 0 to keep
+    /// idle kernels running.
+    ///
+    /// Default: 0
+    pub idle_kernel_shutdown_minutes: Option<u64>,
+    /// How often, in seconds, to sample the memory and CPU used by kernels
+    /// running on this machine, including the processes they start. The
+    /// latest sample is shown next to the kernel in the session details.
+    /// Set to 0 to not sample.
+    ///
+    /// Default: 0
+    pub resource_sample_interval_seconds: Option<u64>,
+    /// Show a warning next to a kernel whose sampled memory is over this many
+    /// megabytes. Set to 0 to never warn.
+    ///
+    /// Default: 4096
+    pub memory_warning_mb: Option<u64>,
+    /// Which representation of an output to show when the kernel sends
+    /// several, as MIME types from most to least preferred. Types that aren't
+    /// listed are never shown.
+    ///
+    /// Default: ["application/vnd.dataresource+json", "text/html", "application/json", "image/svg+xml", "image/png", "image/jpeg", "text/markdown", "text/latex", "text/plain"]
+    pub output_mime_priority: Option<Vec<String>>,
+    /// Prefixes of the lines that start a code cell in a script, keyed by
+    /// language name. Running a cell runs the code from its marker line to
+    /// the next one. Languages that aren't listed start cells with their line
+    /// comment followed by `%%`.
+    ///
+    /// Default: {"Python": ["# %%", "#%%"], "R": ["# %%", "#'"], "Julia": ["# %%", "##"]}
+    pub cell_markers: Option<HashMap<String, Vec<String>>>,
+    /// The types of the cells whose marker line has a tag in brackets, such
+    /// as `# %% [markdown]`, keyed by language name and then by tag. Markdown
+    /// and raw cells are never run, and are exported as markdown and raw
+    /// notebook cells. Cells without a listed tag are code cells. Languages
+    /// that aren't listed use `markdown` and `md` for markdown cells and `raw`
+    /// for raw cells.
+    ///
+    /// Default: {}
+    pub cell_tags: Option<HashMap<String, HashMap<String, ReplCellType>>>,
+    /// Code run silently each time a kernel starts or restarts, keyed by the
+    /// kernel's language in lowercase. It runs before any of your code, and
+    /// is used to show plots inline. Failures are only logged. Set a
+    /// language's code to an empty string to run nothing.
+    ///
+    /// Default: {"python": "from IPython import get_ipython\nget_ipython().run_line_magic(\"matplotlib\", \"inline\")", "r": "options(jupyter.plot_mimetypes = c(\"text/plain\", \"image/png\"))", "julia": "ENV[\"GKSwstype\"] = \"nul\""}
+    pub display_setup_code: Option<HashMap<String, String>>,
+    /// The directory kernels run in for buffers that aren't saved to a file,
+    /// such as untitled buffers. A leading `~/` is your home directory.
+    ///
+    /// Default: null, which uses your home directory
+    pub scratch_working_directory: Option<String>,
+    /// Largest size of a file that execution output is logged to, in
+    /// megabytes. A full log file is renamed with a `.1` extension, replacing
+    /// the previous one, and a new one is started. Set to 0 to let log files
+    /// grow without limit.
+    ///
+    /// Default: 100
+    pub output_log_max_size_mb: Option<u64>,
+    /// Whether interrupting an execution that stops on errors also cancels
+    /// the executions queued after it. Otherwise the error the interrupt
+    /// raises is shown as an interruption rather than a failure, and the
+    /// queued executions still run.
+    ///
+    /// Default: false
+    pub stop_on_interrupt: Option<bool>,
+    /// How many of the most recent executions keep all of their outputs in
+    /// memory. Large outputs of older executions, such as plots, are written
+    /// to a temporary directory that's removed when the session closes, and
+    /// read back when they're needed, such as when exporting the session as
+    /// a notebook.
+    ///
+    /// Default: 20
+    pub outputs_kept_in_memory: Option<usize>,
+    #[serde(flatten)]
+    pub project: ProjectReplSettingsContent,
+}
+
+/// The REPL settings that a project's settings file can also set, for the
+/// worktree it's in.
+#[with_fallible_options]
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize, JsonSchema, MergeFrom)]
+pub struct ProjectReplSettingsContent {
+    /// Limits on the resources of kernels started on this machine, applied
+    /// when their process is spawned. Kernels on Jupyter servers, over SSH,
+    /// and in WSL aren't limited.
+    pub kernel_resource_limits: Option<KernelResourceLimitsContent>,
+}
+
+#[with_fallible_options]
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize, JsonSchema, MergeFrom)]
+pub struct KernelResourceLimitsContent {
+    /// The most memory a kernel may allocate, in megabytes. Past it,
+    /// allocations fail, which kernels usually report as a `MemoryError` or
+    /// die of. Set to 0 for no limit.
+    ///
+    /// Default: 0
+    pub memory_mb: Option<u64>,
+    /// How much to lower the CPU priority of kernels, from 0 to 19 as with
+    /// `nice`. On Windows, 1 to 9 runs kernels below normal priority and 10
+    /// or more at idle priority.
+    ///
+    /// Default: 0
+    pub niceness: Option<u32>,
+    /// The most processes a kernel may start. On Unix this caps the
+    /// processes of the user running Zed, as `RLIMIT_NPROC` does, so leave
+    /// room for the rest of them. Set to 0 for no limit.
+    ///
+    /// Default: 0
+    pub max_processes: Option<u32>,
+}
+
+/// The notebook cell type of a cell in a script.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, MergeFrom,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplCellType {
+    /// Code that's run by the kernel.
+    #[default]
+    Code,
+    /// Markdown text, written as comments in the script.
+    Markdown,
+    /// Text that's neither run nor rendered, written as comments in the script.
+    Raw,
+}
+
+/// What happens to a kernel on a remote Jupyter server when Zed is done with it.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, MergeFrom,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteKernelExitBehavior {
+    /// Shut the kernel down on the server.
+    #[default]
+    Shutdown,
+    /// Disconnect from the kernel and leave it running on the server.
+    Detach,
 }
 
 /// Settings for configuring the which-key popup behaviour.
@@ -488,6 +488,10 @@ pub struct StatusBarSettingsContent {
     ///
     /// Default: non_utf8
     pub active_encoding_button: Option<EncodingDisplayOptions>,
+    /// Whether to show the remote connection button in the status bar.
+    ///
+    /// Default: true
+    pub remote_connection_button: Option<bool>,
 }
 
 #[derive(
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use serde::{Serialize, de::DeserializeOwned};
 use serde_json::Value;
-use std::{ops::Range, sync::LazyLock};
+use std::{borrow::Cow, ops::Range, sync::LazyLock};
 use tree_sitter::{Query, StreamingIterator as _};
 use util::RangeExt;
 
@@ -12,22 +12,76 @@ pub fn update_value_in_json_text<'a>(
     old_value: &'a Value,
     new_value: &'a Value,
     edits: &mut Vec<(Range<usize>, String)>,
+) {
+    let mut key_path = key_path.iter().copied().map(Cow::Borrowed).collect();
+    update_value(
+        text,
+        &mut key_path,
+        tab_size,
+        old_value,
+        new_value,
+        edits,
+        ArrayEdits::Whole,
+    );
+}
+
+/// Like [`update_value_in_json_text`], except that arrays of objects with
+/// entries changed, appended, or removed are edited entry by entry, so the
+/// text of the entries that didn't change is left exactly as it was.
+pub fn update_value_in_json_text_by_entry<'a>(
+    text: &mut String,
+    key_path: &mut Vec<&'a str>,
+    tab_size: usize,
+    old_value: &'a Value,
+    new_value: &'a Value,
+    edits: &mut Vec<(Range<usize>, String)>,
+) {
+    let mut key_path = key_path.iter().copied().map(Cow::Borrowed).collect();
+    update_value(
+        text,
+        &mut key_path,
+        tab_size,
+        old_value,
+        new_value,
+        edits,
+        ArrayEdits::ByEntry,
+    );
+}
+
+/// How a changed array is written back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArrayEdits {
+    /// The whole array is replaced.
+    Whole,
+    /// Arrays of objects are edited entry by entry when possible.
+    ByEntry,
+}
+
+fn update_value<'a>(
+    text: &mut String,
+    key_path: &mut Vec<Cow<'a, str>>,
+    tab_size: usize,
+    old_value: &'a Value,
+    new_value: &'a Value,
+    edits: &mut Vec<(Range<usize>, String)>,
+    array_edits: ArrayEdits,
 ) {
     // If the old and new values are both objects, then compare them key by key,
     // preserving the comments and formatting of the unchanged parts. Otherwise,
     // replace the old value with the new value.
     if let (Value::Object(old_object), Value::Object(new_object)) = (old_value, new_value) {
         for (key, old_sub_value) in old_object.iter() {
-            key_path.push(key);
+            key_path.push(Cow::Borrowed(key));
             if let Some(new_sub_value) = new_object.get(key) {
                 // Key exists in both old and new, recursively update
-                update_value_in_json_text(
+                update_value(
                     text,
                     key_path,
                     tab_size,
                     old_sub_value,
                     new_sub_value,
                     edits,
+                    array_edits,
                 );
             } else {
                 // Key was removed from new object, remove the entire key-value pair
@@ -39,19 +93,59 @@ pub fn update_value_in_json_text<'a>(
             key_path.pop();
         }
         for (key, new_sub_value) in new_object.iter() {
-            key_path.push(key);
+            key_path.push(Cow::Borrowed(key));
             if !old_object.contains_key(key) {
-                update_value_in_json_text(
+                update_value(
                     text,
                     key_path,
                     tab_size,
                     &Value::Null,
                     new_sub_value,
                     edits,
+                    array_edits,
                 );
             }
             key_path.pop();
         }
+    } else if array_edits == ArrayEdits::ByEntry
+        && old_value != new_value
+        && let (Value::Array(old_entries), Value::Array(new_entries)) = (old_value, new_value)
+        && let Some(removed) = entry_changes(old_entries, new_entries)
+    {
+        if removed.is_empty() {
+            // Entries at the same index are compared key by key, like objects.
+            for (index, (old_entry, new_entry)) in old_entries.iter().zip(new_entries).enumerate() {
+                if old_entry != new_entry {
+                    key_path.push(Cow::Owned(format!("#{index}")));
+                    update_value(
+                        text,
+                        key_path,
+                        tab_size,
+                        old_entry,
+                        new_entry,
+                        edits,
+                        array_edits,
+                    );
+                    key_path.pop();
+                }
+            }
+        }
+        // Removing from the back keeps the indices of the rest valid.
+        for index in removed.into_iter().rev() {
+            key_path.push(Cow::Owned(format!("#{index}")));
+            let (range, replacement) = replace_value_in_json_text(text, key_path, 0, None, None);
+            text.replace_range(range.clone(), &replacement);
+            edits.push((range, replacement));
+            key_path.pop();
+        }
+        for (index, new_entry) in new_entries.iter().enumerate().skip(old_entries.len()) {
+            key_path.push(Cow::Owned(format!("#{index}")));
+            let (range, replacement) =
+                replace_value_in_json_text(text, key_path, tab_size, Some(new_entry), None);
+            text.replace_range(range.clone(), &replacement);
+            edits.push((range, replacement));
+            key_path.pop();
+        }
     } else if old_value != new_value {
         let mut new_value = new_value.clone();
         if let Some(new_object) = new_value.as_object_mut() {
@@ -64,6 +158,30 @@ pub fn update_value_in_json_text<'a>(
     }
 }
 
+/// Whether a non-empty array of objects can be edited entry by entry into
+/// another: entries changed in place or appended, or some entries removed
+/// and the rest left as they were. Returns the indices of the removed
+/// entries, or `None` when the array needs replacing as a whole.
+fn entry_changes(old_entries: &[Value], new_entries: &[Value]) -> Option<Vec<usize>> {
+    if old_entries.is_empty() || !old_entries.iter().chain(new_entries).all(Value::is_object) {
+        return None;
+    }
+    if new_entries.len() >= old_entries.len() {
+        return Some(Vec::new());
+    }
+
+    let mut remaining = new_entries.iter().peekable();
+    let mut removed = Vec::new();
+    for (index, old_entry) in old_entries.iter().enumerate() {
+        if remaining.peek() == Some(&old_entry) {
+            remaining.next();
+        } else {
+            removed.push(index);
+        }
+    }
+    remaining.peek().is_none().then_some(removed)
+}
+
 /// * `replace_key` - When an exact key match according to `key_path` is found, replace the key with `replace_key` if `Some`.
 pub fn replace_value_in_json_text<T: AsRef<str>>(
     text: &str,
@@ -2568,6 +2686,105 @@ mod tests {
         )
     }
 
+    #[test]
+    fn update_array_by_entry() {
+        #[track_caller]
+        fn update(input: &str, new_value: Value) -> String {
+            let old_value: Value = parse_json_with_comments(input).unwrap();
+            let mut text = input.to_string();
+            update_value_in_json_text_by_entry(
+                &mut text,
+                &mut Vec::new(),
+                4,
+                &old_value,
+                &new_value,
+                &mut Vec::new(),
+            );
+            pretty_assertions::assert_eq!(
+                parse_json_with_comments::<Value>(&text).unwrap(),
+                new_value
+            );
+            text
+        }
+
+        let input = r#"{
+            "servers": [
+                {"host": "a", "nickname": "A"},
+                {"host": "b"},
+                {"nickname": "C",   "host": "c"}
+            ]
+        }"#
+        .unindent();
+
+        // Only the changed entry is edited.
+        pretty_assertions::assert_eq!(
+            update(
+                &input,
+                json!({"servers": [
+                    {"host": "a", "nickname": "A"},
+                    {"host": "b", "nickname": "B"},
+                    {"host": "c", "nickname": "C"},
+                ]}),
+            ),
+            r#"{
+                "servers": [
+                    {"host": "a", "nickname": "A"},
+                    {"nickname": "B", "host": "b"},
+                    {"nickname": "C",   "host": "c"}
+                ]
+            }"#
+            .unindent()
+        );
+
+        // Removing an entry leaves the others as they were.
+        pretty_assertions::assert_eq!(
+            update(
+                &input,
+                json!({"servers": [
+                    {"host": "a", "nickname": "A"},
+                    {"host": "c", "nickname": "C"},
+                ]}),
+            ),
+            r#"{
+                "servers": [
+                    {"host": "a", "nickname": "A"},
+                    {"nickname": "C",   "host": "c"}
+                ]
+            }"#
+            .unindent()
+        );
+
+        // So does appending one.
+        let appended = update(
+            &input,
+            json!({"servers": [
+                {"host": "a", "nickname": "A"},
+                {"host": "b"},
+                {"host": "c", "nickname": "C"},
+                {"host": "d"},
+            ]}),
+        );
+        for entry in [
+            r#"{"host": "a", "nickname": "A"},"#,
+            r#"{"host": "b"},"#,
+            r#"{"nickname": "C",   "host": "c"}"#,
+        ] {
+            assert!(
+                appended.contains(&format!("\n        {entry}")),
+                "{appended}"
+            );
+        }
+
+        // Reordering entries replaces the array.
+        update(
+            &input,
+            json!({"servers": [
+                {"host": "c", "nickname": "C"},
+                {"host": "a", "nickname": "A"},
+            ]}),
+        );
+    }
+
     #[test]
     fn test_infer_json_indent_size() {
         let json_2_spaces = r#"{
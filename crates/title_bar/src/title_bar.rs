@@ -547,29 +547,27 @@ impl TitleBar {
 
         let nickname = nickname.unwrap_or_else(|| host.clone());
 
-        let (indicator_color, meta) = match self.project.read(cx).remote_connection_state(cx)? {
-            remote::ConnectionState::Connecting => (Color::Info, format!("Connecting to: {host}")),
-            remote::ConnectionState::Connected => (Color::Success, format!("Connected to: {host}")),
-            remote::ConnectionState::HeartbeatMissed => (
-                Color::Warning,
-                format!("Connection attempt to {host} missed. Retrying..."),
-            ),
-            remote::ConnectionState::Reconnecting => (
-                Color::Warning,
-                format!("Lost connection to {host}. Reconnecting..."),
-            ),
-            remote::ConnectionState::Disconnected => {
-                (Color::Error, format!("Disconnected from {host}"))
+        let connection_state = self.project.read(cx).remote_connection_state(cx)?;
+        let meta = match connection_state {
+            remote::ConnectionState::Connecting => format!("Connecting to: {host}"),
+            remote::ConnectionState::Connected => format!("Connected to: {host}"),
+            remote::ConnectionState::HeartbeatMissed => {
+                format!("Connection attempt to {host} missed. Retrying...")
+            }
+            remote::ConnectionState::Reconnecting => {
+                format!("Lost connection to {host}. Reconnecting...")
             }
+            remote::ConnectionState::Disconnected => format!("Disconnected from {host}"),
         };
 
-        let icon_color = match self.project.read(cx).remote_connection_state(cx)? {
+        let icon_color = match connection_state {
             remote::ConnectionState::Connecting => Color::Info,
             remote::ConnectionState::Connected => Color::Default,
             remote::ConnectionState::HeartbeatMissed => Color::Warning,
             remote::ConnectionState::Reconnecting => Color::Warning,
             remote::ConnectionState::Disconnected => Color::Error,
         };
+        let health = recent_projects::remote_health::RemoteHealth::from(connection_state);
 
         let meta = SharedString::from(meta);
 
@@ -596,7 +594,7 @@ impl TitleBar {
                                 .child(
                                     IconWithIndicator::new(
                                         Icon::new(icon).size(IconSize::Small).color(icon_color),
-                                        Some(Indicator::dot().color(indicator_color)),
+                                        Some(health.indicator()),
                                     )
                                     .indicator_border_color(Some(
                                         cx.theme().colors().title_bar_background,
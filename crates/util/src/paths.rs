@@ -824,13 +824,21 @@ impl PathWithPosition {
                 // but in reality there could be `foo/bar.py:22:in` inputs which we want to match too.
                 // The regex mentioned is not very extendable with "digit or random string" checks, so do this here instead.
                 let delimiter = ':';
+                // Don't mistake the colon of a Windows drive letter for a position delimiter.
+                let (drive_prefix, s) = match s.as_bytes() {
+                    [letter, b':', b'\\' | b'/', ..] if letter.is_ascii_alphabetic() => {
+                        s.split_at(2)
+                    }
+                    _ => ("", s),
+                };
                 let mut path_parts = s
                     .rsplitn(3, delimiter)
                     .collect::<Vec<_>>()
                     .into_iter()
                     .rev()
                     .fuse();
-                let mut path_string = path_parts.next().expect("rsplitn should have the rest of the string as its last parameter that we reversed").to_owned();
+                let mut path_string = drive_prefix.to_owned();
+                path_string.push_str(path_parts.next().expect("rsplitn should have the rest of the string as its last parameter that we reversed"));
                 let mut row = None;
                 let mut column = None;
                 if let Some(maybe_row) = path_parts.next() {
@@ -2612,6 +2620,36 @@ mod tests {
         );
     }
 
+    #[perf]
+    fn path_with_position_parse_drive_letter_path_with_row() {
+        assert_eq!(
+            PathWithPosition::parse_str("C:\\Users\\someone\\test_file.rs:10"),
+            PathWithPosition {
+                path: PathBuf::from("C:\\Users\\someone\\test_file.rs"),
+                row: Some(10),
+                column: None
+            }
+        );
+
+        assert_eq!(
+            PathWithPosition::parse_str("C:/Users/someone/test_file.rs:10:7"),
+            PathWithPosition {
+                path: PathBuf::from("C:/Users/someone/test_file.rs"),
+                row: Some(10),
+                column: Some(7)
+            }
+        );
+
+        assert_eq!(
+            PathWithPosition::parse_str("C:\\Users\\someone\\test_file.rs"),
+            PathWithPosition {
+                path: PathBuf::from("C:\\Users\\someone\\test_file.rs"),
+                row: None,
+                column: None
+            }
+        );
+    }
+
     #[perf]
     #[cfg(target_os = "windows")]
     fn path_with_position_parse_windows_path_with_suffix() {
@@ -120,6 +120,19 @@ pub fn get_windows_bash() -> Option<String> {
     (*BASH).clone()
 }
 
+/// Returns the first character of `arg` that only means something to a shell,
+/// such as `;`, `|`, or `$`. Arguments passed straight to a program never need
+/// one, so finding one usually means the argument was written as if a shell
+/// would run it.
+pub fn find_shell_metacharacter(arg: &str) -> Option<char> {
+    arg.chars().find(|character| {
+        matches!(
+            character,
+            ';' | '&' | '|' | '`' | '$' | '<' | '>' | '(' | ')' | '\n' | '\r'
+        )
+    })
+}
+
 pub fn get_windows_system_shell() -> String {
     use std::path::PathBuf;
 
@@ -812,6 +825,15 @@ mod tests {
     // PowerShell from CMD
     // cmd /C \" \"C:\\\\Users\\\\kubko\\\\dev\\\\python\\\\39007\\\\tests\\\\.venv\\\\Scripts\\\\activate.bat\"& \"C:\\\\Program Files\\\\PowerShell\\\\7\\\\pwsh.exe\" -C \"C:\\\\Users\\\\kubko\\\\dev\\\\python\\\\39007\\\\tests\\\\.venv\\\\Scripts\\\\python.exe -m pytest \\\"test_foo.py::test_foo\\\"\"\"
 
+    #[test]
+    fn test_find_shell_metacharacter() {
+        assert_eq!(find_shell_metacharacter("--privileged"), None);
+        assert_eq!(find_shell_metacharacter("--env-file=/home/me/.env"), None);
+        assert_eq!(find_shell_metacharacter("--gpus all; rm -rf /"), Some(';'));
+        assert_eq!(find_shell_metacharacter("--user=$(whoami)"), Some('$'));
+        assert_eq!(find_shell_metacharacter("a\nb"), Some('\n'));
+    }
+
     #[test]
     fn test_try_quote_powershell() {
         let shell_kind = ShellKind::PowerShell;
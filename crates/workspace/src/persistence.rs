@@ -1689,6 +1689,9 @@ impl WorkspaceDb {
         }
 
         if let RemoteConnectionOptions::Docker(options) = options {
+            // Docker connections have no distro of their own, so the column
+            // records the WSL distro that runs the container, if any.
+            distro = options.wsl_distro;
             use_podman = Some(options.use_podman);
             remote_env = serde_json::to_string(&options.remote_env).ok();
         }
@@ -1952,6 +1955,7 @@ impl WorkspaceDb {
             RemoteConnectionKind::Wsl => Some(RemoteConnectionOptions::Wsl(WslConnectionOptions {
                 distro_name: distro?,
                 user: user,
+                env: Default::default(),
             })),
             RemoteConnectionKind::Ssh => Some(RemoteConnectionOptions::Ssh(SshConnectionOptions {
                 host: host?.into(),
@@ -1968,7 +1972,11 @@ impl WorkspaceDb {
                     remote_user: user?,
                     upload_binary_over_docker_exec: false,
                     use_podman: use_podman?,
+                    wsl_distro: distro,
                     remote_env,
+                    shell: None,
+                    detected_shell: None,
+                    extra_exec_args: Vec::new(),
                 }))
             }
         }
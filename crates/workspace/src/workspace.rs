@@ -9991,6 +9991,7 @@ pub fn open_paths(
                                                 distro: remote::WslConnectionOptions {
                                                         distro_name: distro.clone(),
                                                     user: None,
+                                                    env: Default::default(),
                                                 },
                                                 paths: vec![path.clone().into()],
                                             }), cx)
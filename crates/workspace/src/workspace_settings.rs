@@ -160,6 +160,7 @@ pub struct StatusBarSettings {
     pub cursor_position_button: bool,
     pub line_endings_button: bool,
     pub active_encoding_button: EncodingDisplayOptions,
+    pub remote_connection_button: bool,
 }
 
 impl Settings for StatusBarSettings {
@@ -172,6 +173,7 @@ impl Settings for StatusBarSettings {
             cursor_position_button: status_bar.cursor_position_button.unwrap(),
             line_endings_button: status_bar.line_endings_button.unwrap(),
             active_encoding_button: status_bar.active_encoding_button.unwrap(),
+            remote_connection_button: status_bar.remote_connection_button.unwrap(),
         }
     }
 }
@@ -42,7 +42,6 @@ use language::LanguageRegistry;
 use onboarding::{FIRST_OPEN, show_onboarding_view};
 use project_panel::ProjectPanel;
 use prompt_store::PromptBuilder;
-use remote::RemoteConnectionOptions;
 use reqwest_client::ReqwestClient;
 
 use assets::Assets;
@@ -1434,12 +1433,10 @@ pub(crate) async fn restore_or_create_workspace(
                 }
                 SerializedWorkspaceLocation::Remote(connection_options) => {
                     let mut connection_options = connection_options.clone();
-                    if let RemoteConnectionOptions::Ssh(options) = &mut connection_options {
-                        cx.update(|cx| {
-                            RemoteSettings::get_global(cx)
-                                .fill_connection_options_from_settings(options)
-                        });
-                    }
+                    cx.update(|cx| {
+                        RemoteSettings::get_global(cx)
+                            .fill_remote_connection_options_from_settings(&mut connection_options)
+                    });
 
                     let paths = multi_workspace
                         .active_workspace
@@ -133,10 +133,13 @@ impl OpenRequest {
             } else {
                 (None, wsl)
             };
-            this.remote_connection = Some(RemoteConnectionOptions::Wsl(WslConnectionOptions {
+            let mut options = WslConnectionOptions {
                 distro_name,
                 user,
-            }));
+                env: Default::default(),
+            };
+            RemoteSettings::get_global(cx).fill_wsl_connection_options_from_settings(&mut options);
+            this.remote_connection = Some(RemoteConnectionOptions::Wsl(options));
         }
 
         for url in request.urls {
@@ -770,12 +773,10 @@ async fn open_workspaces(
             }
             SerializedWorkspaceLocation::Remote(mut connection) => {
                 let app_state = app_state.clone();
-                if let RemoteConnectionOptions::Ssh(options) = &mut connection {
-                    cx.update(|cx| {
-                        RemoteSettings::get_global(cx)
-                            .fill_connection_options_from_settings(options)
-                    });
-                }
+                cx.update(|cx| {
+                    RemoteSettings::get_global(cx)
+                        .fill_remote_connection_options_from_settings(&mut connection)
+                });
                 cx.spawn(async move |cx| {
                     open_remote_project(
                         connection,
@@ -1023,6 +1024,11 @@ mod tests {
                 nickname: None,
                 upload_binary_over_ssh: false,
                 connection_timeout: None,
+                keepalive_interval: None,
+                keepalive_count_max: None,
+                shell: None,
+                login_shell: None,
+                env: Default::default(),
             })
         );
         assert_eq!(request.open_paths, vec!["/"]);
@@ -3,11 +3,12 @@ use gpui::TaskExt;
 use gpui::{AnyElement, Entity};
 use picker::Picker;
 use repl::{
-    ExecutionState, JupyterSettings, Kernel, KernelSpecification, KernelStatus, Session,
-    SessionSupport,
+    ExecutionState, JupyterSettings, Kernel, KernelSpecification, KernelStatus, ReplSettings,
+    Session, SessionSupport, attachable_sessions,
     components::{KernelPickerDelegate, KernelSelector},
     worktree_id_for_editor,
 };
+use settings::Settings as _;
 use ui::{
     ButtonLike, CommonAnimationExt, ContextMenu, IconWithIndicator, Indicator, IntoElement,
     PopoverMenu, PopoverMenuHandle, Tooltip, prelude::*,
@@ -28,6 +29,7 @@ struct ReplMenuState {
 
     status: KernelStatus,
     kernel_name: SharedString,
+    session_name: SharedString,
     kernel_language: SharedString,
 }
 
@@ -90,6 +92,36 @@ impl QuickActionBar {
                 let editor = editor.clone();
                 let session = session.clone();
                 ContextMenu::build(window, cx, move |menu, _, cx| {
+                    let is_logging_output = session.read(cx).output_log_path().is_some();
+                    let memory_warning_threshold =
+                        ReplSettings::get_global(cx).memory_warning_threshold();
+                    let resource_usage = session.read(cx).resource_usage().map(|sample| {
+                        let is_over_threshold = memory_warning_threshold
+                            .is_some_and(|threshold| sample.memory_bytes > threshold);
+                        let label = format!(
+                            "memory: {} · cpu: {:.0}%",
+                            repl::kernels::format_memory(sample.memory_bytes),
+                            sample.cpu_percent
+                        );
+                        (label, is_over_threshold)
+                    });
+                    let resource_limits = session
+                        .read(cx)
+                        .resource_limits()
+                        .map(|limits| format!("limits: {limits}"));
+                    let cell_run = session.read(cx).cell_run_progress().map(|progress| {
+                        format!(
+                            "running cell {} of {}",
+                            progress.finished + 1,
+                            progress.total
+                        )
+                    });
+                    let is_running_cells = cell_run.is_some();
+                    let attachable_sessions = attachable_sessions(
+                        &session.read(cx).kernel_specification.language(),
+                        Some(&session),
+                        cx,
+                    );
                     let menu_state = session_state(session, cx);
                     let status = menu_state.status;
                     let editor = editor.clone();
@@ -97,7 +129,17 @@ impl QuickActionBar {
                     menu.map(|menu| {
                         if status.is_connected() {
                             let status = status.clone();
+                            let session_name = menu_state.session_name.clone();
                             menu.custom_row(move |_window, _cx| {
+                                h_flex()
+                                    .child(
+                                        Label::new(format!("session: {session_name}"))
+                                            .size(LabelSize::Small)
+                                            .color(Color::Muted),
+                                    )
+                                    .into_any_element()
+                            })
+                            .custom_row(move |_window, _cx| {
                                 h_flex()
                                     .child(
                                         Label::new(format!(
@@ -118,6 +160,54 @@ impl QuickActionBar {
                                     )
                                     .into_any_element()
                             })
+                            .when_some(
+                                resource_usage,
+                                |menu, (label, is_over_threshold)| {
+                                    menu.custom_row(move |_window, _cx| {
+                                        h_flex()
+                                            .gap_1()
+                                            .when(is_over_threshold, |this| {
+                                                this.child(
+                                                    Icon::new(IconName::Warning)
+                                                        .size(IconSize::Small)
+                                                        .color(Color::Warning),
+                                                )
+                                            })
+                                            .child(
+                                                Label::new(label.clone())
+                                                    .size(LabelSize::Small)
+                                                    .color(if is_over_threshold {
+                                                        Color::Warning
+                                                    } else {
+                                                        Color::Muted
+                                                    }),
+                                            )
+                                            .into_any_element()
+                                    })
+                                },
+                            )
+                            .when_some(resource_limits, |menu, label| {
+                                menu.custom_row(move |_window, _cx| {
+                                    h_flex()
+                                        .child(
+                                            Label::new(label.clone())
+                                                .size(LabelSize::Small)
+                                                .color(Color::Muted),
+                                        )
+                                        .into_any_element()
+                                })
+                            })
+                            .when_some(cell_run, |menu, label| {
+                                menu.custom_row(move |_window, _cx| {
+                                    h_flex()
+                                        .child(
+                                            Label::new(label.clone())
+                                                .size(LabelSize::Small)
+                                                .color(Color::Muted),
+                                        )
+                                        .into_any_element()
+                                })
+                            })
                         } else {
                             let status = status.clone();
                             menu.custom_row(move |_window, _cx| {
@@ -162,6 +252,22 @@ impl QuickActionBar {
                             }
                         },
                     )
+                    .when(is_running_cells, |menu| {
+                        menu.custom_entry(
+                            move |_window, _cx| {
+                                Label::new("Cancel Remaining Cells")
+                                    .size(LabelSize::Small)
+                                    .color(Color::Error)
+                                    .into_any_element()
+                            },
+                            {
+                                let editor = editor.clone();
+                                move |_, cx| {
+                                    repl::cancel_cell_run(editor.clone(), false, cx);
+                                }
+                            },
+                        )
+                    })
                     .custom_entry(
                         move |_window, _cx| {
                             Label::new("Clear Outputs")
@@ -176,6 +282,24 @@ impl QuickActionBar {
                             }
                         },
                     )
+                    .custom_entry(
+                        move |_window, _cx| {
+                            Label::new(if is_logging_output {
+                                "Stop Logging Output"
+                            } else {
+                                "Log Output to File..."
+                            })
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                            .into_any_element()
+                        },
+                        {
+                            let editor = editor.clone();
+                            move |_, cx| {
+                                repl::toggle_output_log(editor.clone(), cx);
+                            }
+                        },
+                    )
                     .separator()
                     .custom_entry(
                         move |_window, _cx| {
@@ -199,12 +323,35 @@ impl QuickActionBar {
                                 .into_any_element()
                         },
                         {
+                            let editor = editor.clone();
                             move |window, cx| {
                                 repl::restart(editor.clone(), window, cx);
                             }
                         },
                     )
                     .separator()
+                    .entry("Detach from Session", None, {
+                        let editor = editor.clone();
+                        move |window, cx| {
+                            repl::detach_from_session(editor.clone(), window, cx);
+                        }
+                    })
+                    .map(|menu| {
+                        attachable_sessions
+                            .into_iter()
+                            .fold(menu, |menu, (name, other)| {
+                                let editor = editor.clone();
+                                menu.entry(format!("Attach to {name}"), None, move |window, cx| {
+                                    repl::attach_to_session(
+                                        editor.clone(),
+                                        other.clone(),
+                                        window,
+                                        cx,
+                                    );
+                                })
+                            })
+                    })
+                    .separator()
                     .action("View Sessions", Box::new(repl::Sessions))
                     // TODO: Add shut down all kernels action
                     // .action("Shut Down all Kernels", Box::new(gpui::NoAction))
@@ -257,7 +404,52 @@ impl QuickActionBar {
         cx: &mut Context<Self>,
     ) -> Option<AnyElement> {
         let tooltip: SharedString =
-            SharedString::from(format!("Start REPL for {}", kernel_specification.name()));
+            SharedString::from(format!("Start REPL for {}", kernel_specification.display_name()));
+
+        // Instead of starting a kernel of its own, the editor can share one
+        // that's already running.
+        let attachable_sessions = attachable_sessions(&kernel_specification.language(), None, cx);
+        let attach_menu = self
+            .active_editor()
+            .filter(|_| !attachable_sessions.is_empty())
+            .map(|editor| {
+                let editor = editor.downgrade();
+                PopoverMenu::new("repl-attach-menu")
+                    .menu(move |window, cx| {
+                        let editor = editor.clone();
+                        let attachable_sessions = attachable_sessions.clone();
+                        ContextMenu::build(window, cx, move |menu, _, _| {
+                            attachable_sessions
+                                .into_iter()
+                                .fold(menu, |menu, (name, session)| {
+                                    let editor = editor.clone();
+                                    menu.entry(
+                                        format!("Attach to {name}"),
+                                        None,
+                                        move |window, cx| {
+                                            repl::attach_to_session(
+                                                editor.clone(),
+                                                session.clone(),
+                                                window,
+                                                cx,
+                                            );
+                                        },
+                                    )
+                                })
+                        })
+                        .into()
+                    })
+                    .trigger_with_tooltip(
+                        ButtonLike::new_rounded_right("repl-attach-dropdown")
+                            .child(
+                                Icon::new(IconName::ChevronDown)
+                                    .size(IconSize::XSmall)
+                                    .color(Color::Muted),
+                            )
+                            .width(rems(1.)),
+                        Tooltip::text("Attach to a Running Session"),
+                    )
+            });
 
         Some(
             h_flex()
@@ -272,6 +464,7 @@ impl QuickActionBar {
                             window.dispatch_action(Box::new(repl::Run {}), cx)
                         }),
                 )
+                .children(attach_menu)
                 .into_any_element(),
         )
     }
@@ -283,9 +476,7 @@ impl QuickActionBar {
             return div().into_any_element();
         };
 
-        let Some(worktree_id) = worktree_id_for_editor(editor.downgrade(), cx) else {
-            return div().into_any_element();
-        };
+        let worktree_id = worktree_id_for_editor(editor.downgrade(), cx);
 
         let store = repl::ReplStore::global(cx);
         if !store.read(cx).has_python_kernelspecs(worktree_id) {
@@ -314,6 +505,14 @@ impl QuickActionBar {
         };
 
         let current_kernel_name = current_kernelspec.as_ref().map(|spec| spec.name());
+        let current_kernel_info = current_kernelspec.as_ref().and_then(|spec| {
+            let info = store.read(cx).cached_kernel_info(spec)?;
+            Some(SharedString::from(if info.is_stale {
+                format!("{} (cached)", info.label())
+            } else {
+                info.label()
+            }))
+        });
 
         let menu_handle: PopoverMenuHandle<Picker<KernelPickerDelegate>> =
             PopoverMenuHandle::default();
@@ -367,7 +566,12 @@ impl QuickActionBar {
                                 .size(IconSize::XSmall),
                         ),
                 ),
-            Tooltip::text("Select Kernel"),
+            move |_window, cx| match &current_kernel_info {
+                Some(kernel_info) => {
+                    Tooltip::with_meta("Select Kernel", None, kernel_info.clone(), cx)
+                }
+                None => Tooltip::simple("Select Kernel", cx),
+            },
         )
         .with_handle(menu_handle)
         .into_any_element()
@@ -395,10 +599,13 @@ impl QuickActionBar {
     }
 }
 
+/// The names of the running sessions for `language` that an editor could be
+/// attached to, leaving out the one it's already attached to.
 fn session_state(session: Entity<Session>, cx: &mut App) -> ReplMenuState {
     let session = session.read(cx);
 
     let kernel_name = session.kernel_specification.name();
+    let session_name = session.name().clone();
     let kernel_language: SharedString = session.kernel_specification.language();
 
     let fill_fields = || {
@@ -410,6 +617,7 @@ fn session_state(session: Entity<Session>, cx: &mut App) -> ReplMenuState {
             popover_disabled: false,
             indicator: None,
             kernel_name: kernel_name.clone(),
+            session_name: session_name.clone(),
             kernel_language: kernel_language.clone(),
             // TODO: Technically not shutdown, but indeterminate
             status: KernelStatus::Shutdown,
@@ -428,26 +636,32 @@ fn session_state(session: Entity<Session>, cx: &mut App) -> ReplMenuState {
             ..fill_fields()
         };
 
-    let starting = || transitional(format!("{} is starting", kernel_name).into(), true, true);
-    let restarting = || transitional(format!("Restarting {}", kernel_name).into(), true, true);
+    let starting = || transitional(format!("{} is starting", session_name).into(), true, true);
+    let restarting = || transitional(format!("Restarting {}", session_name).into(), true, true);
     let shutting_down = || {
         transitional(
-            format!("{} is shutting down", kernel_name).into(),
+            format!("{} is shutting down", session_name).into(),
             false,
             true,
         )
     };
     let auto_restarting = || {
         transitional(
-            format!("Auto-restarting {}", kernel_name).into(),
+            format!("Auto-restarting {}", session_name).into(),
+            true,
             true,
+        )
+    };
+    let unknown = || {
+        transitional(
+            format!("{} state unknown", session_name).into(),
+            false,
             true,
         )
     };
-    let unknown = || transitional(format!("{} state unknown", kernel_name).into(), false, true);
     let other = |state: &str| {
         transitional(
-            format!("{} state: {}", kernel_name, state).into(),
+            format!("{} state: {}", session_name, state).into(),
             false,
             true,
         )
@@ -468,13 +682,23 @@ fn session_state(session: Entity<Session>, cx: &mut App) -> ReplMenuState {
         Kernel::Restarting => restarting(),
         Kernel::RunningKernel(kernel) => match &kernel.execution_state() {
             ExecutionState::Idle => ReplMenuState {
-                tooltip: format!("Run code on {} ({})", kernel_name, kernel_language).into(),
+                tooltip: format!("Run code on {} ({})", session_name, kernel_language).into(),
                 indicator: Some(Indicator::dot().color(Color::Success)),
                 status: session.kernel.status(),
                 ..fill_fields()
             },
             ExecutionState::Busy => ReplMenuState {
-                tooltip: format!("Interrupt {} ({})", kernel_name, kernel_language).into(),
+                tooltip: match session.cell_run_progress() {
+                    Some(progress) => format!(
+                        "Running cell {} of {} on {} ({})",
+                        progress.finished + 1,
+                        progress.total,
+                        session_name,
+                        kernel_language
+                    )
+                    .into(),
+                    None => format!("Interrupt {} ({})", session_name, kernel_language).into(),
+                },
                 icon_is_animating: true,
                 popover_disabled: false,
                 indicator: None,
@@ -490,8 +714,8 @@ fn session_state(session: Entity<Session>, cx: &mut App) -> ReplMenuState {
             ExecutionState::Other(state) => other(state),
         },
         Kernel::StartingKernel(_) => starting(),
-        Kernel::ErroredLaunch(e) => ReplMenuState {
-            tooltip: format!("Error with kernel {}: {}", kernel_name, e).into(),
+        Kernel::ErroredLaunch(e, _) => ReplMenuState {
+            tooltip: format!("Error with kernel {}: {}", session_name, e).into(),
             popover_disabled: false,
             indicator: Some(Indicator::dot().color(Color::Error)),
             status: session.kernel.status(),
@@ -499,5 +723,15 @@ fn session_state(session: Entity<Session>, cx: &mut App) -> ReplMenuState {
         },
         Kernel::ShuttingDown => shutting_down(),
         Kernel::Shutdown => shutdown(),
+        Kernel::Culled => ReplMenuState {
+            tooltip: format!(
+                "{} was shut down while idle. Run code to restart it.",
+                session_name
+            )
+            .into(),
+            indicator: Some(Indicator::dot().color(Color::Muted)),
+            status: session.kernel.status(),
+            ..fill_fields()
+        },
     }
 }